@@ -0,0 +1,82 @@
+//! Prompt assembly: the system prompt and the recalled-memory block built
+//! before each turn.
+//!
+//! Run with `cargo bench --bench prompt`; see docs/benchmarks.md for
+//! comparing against a saved baseline.
+
+use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+use localgpt::agent::{
+    MemorySource, SystemPromptParams, build_recall_context, build_recall_query,
+    build_system_prompt, wrap_memory_content,
+};
+use localgpt::config::RecallConfig;
+use localgpt::memory::MemoryChunk;
+use std::path::Path;
+
+const TOOLS: &[&str] = &[
+    "bash",
+    "read_file",
+    "write_file",
+    "edit_file",
+    "memory_search",
+    "memory_get",
+    "web_fetch",
+];
+
+fn chunks(count: usize) -> Vec<MemoryChunk> {
+    (0..count)
+        .map(|i| {
+            let content = format!(
+                "## Notes {}\n\nDiscussed the deployment schedule and the database \
+                 migration. Follow up on the backup job and the monitoring alerts.\n",
+                i
+            )
+            .repeat(4);
+            MemoryChunk::new(
+                format!("memory/2026-10-{:02}.md", i % 28 + 1),
+                1,
+                20,
+                content,
+                1.0 - i as f64 / count as f64,
+            )
+        })
+        .collect()
+}
+
+fn system_prompt(c: &mut Criterion) {
+    let workspace = Path::new("/home/user/.localgpt/workspace");
+    c.bench_function("system_prompt", |b| {
+        b.iter(|| {
+            let mut params = SystemPromptParams::new(workspace, "claude-cli/opus");
+            params.tool_names = TOOLS.to_vec();
+            build_system_prompt(black_box(params))
+        })
+    });
+}
+
+fn memory_context(c: &mut Criterion) {
+    let memory = "- Prefers short answers\n- Works on the LocalGPT Discord bot\n".repeat(100);
+    c.bench_function("memory_context/wrap_memory_md", |b| {
+        b.iter(|| wrap_memory_content("MEMORY.md", black_box(&memory), MemorySource::Memory))
+    });
+
+    let recent: Vec<String> = (0..10)
+        .map(|i| format!("earlier question number {} about the deploy", i))
+        .collect();
+    c.bench_function("memory_context/recall_query", |b| {
+        b.iter(|| build_recall_query(black_box("when is the migration?"), &recent))
+    });
+
+    let config = RecallConfig::default();
+    let mut group = c.benchmark_group("memory_context/recall");
+    for count in [5, 20, 100] {
+        let found = chunks(count);
+        group.bench_with_input(BenchmarkId::from_parameter(count), &found, |b, f| {
+            b.iter(|| build_recall_context(black_box(f), &config, true))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, system_prompt, memory_context);
+criterion_main!(benches);
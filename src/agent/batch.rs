@@ -0,0 +1,197 @@
+//! Batched summaries for background work
+//!
+//! Background jobs that summarize several small texts at once (e.g. the
+//! Discord bot archiving idle sessions) would otherwise send one request
+//! per text, and on a small local model the fixed cost of each request
+//! (prompt processing, model warm-up) dominates. [`summarize_batch`] packs
+//! texts into groups of at most `agent.summary_batch_chars` characters and
+//! asks for all summaries of a group in one request, each under a numbered
+//! marker, then splits the answer apart again. Texts larger than the limit,
+//! groups of one, and any summary missing from a batched answer are
+//! summarized on their own, so results never depend on the model following
+//! the format.
+
+use anyhow::Result;
+use regex::Regex;
+use std::sync::LazyLock;
+use tracing::debug;
+
+use super::providers::{LLMProvider, LLMResponseContent, Message, Role};
+
+static SUMMARY_MARKER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^=== SUMMARY (\d+) ===[ \t]*$").unwrap());
+
+/// Summarize `texts`, batching small ones into shared requests. Results are
+/// in the order of `texts`; `max_chars` of 0 summarizes each text alone.
+pub async fn summarize_batch(
+    provider: &dyn LLMProvider,
+    texts: &[String],
+    max_chars: usize,
+) -> Vec<Result<String>> {
+    let mut results: Vec<Option<Result<String>>> = texts.iter().map(|_| None).collect();
+
+    for group in pack(texts, max_chars) {
+        if group.len() > 1 {
+            let batch: Vec<&str> = group.iter().map(|&i| texts[i].as_str()).collect();
+            match summarize_together(provider, &batch).await {
+                Ok(summaries) => {
+                    for (i, summary) in group.iter().zip(summaries) {
+                        if let Some(summary) = summary {
+                            results[*i] = Some(Ok(summary));
+                        }
+                    }
+                }
+                Err(e) => debug!("Batched summary failed, summarizing one by one: {}", e),
+            }
+        }
+        for i in group {
+            if results[i].is_none() {
+                results[i] = Some(provider.summarize(&texts[i]).await);
+            }
+        }
+    }
+
+    results.into_iter().flatten().collect()
+}
+
+/// Indexes of `texts` grouped greedily, in order, so each group's total
+/// length stays within `max_chars`
+fn pack(texts: &[String], max_chars: usize) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut current = Vec::new();
+    let mut current_chars = 0;
+    for (i, text) in texts.iter().enumerate() {
+        let chars = text.chars().count();
+        if !current.is_empty() && current_chars + chars > max_chars {
+            groups.push(std::mem::take(&mut current));
+            current_chars = 0;
+        }
+        current.push(i);
+        current_chars += chars;
+    }
+    if !current.is_empty() {
+        groups.push(current);
+    }
+    groups
+}
+
+/// One request for several summaries; None for each one the answer lacks
+async fn summarize_together(
+    provider: &dyn LLMProvider,
+    texts: &[&str],
+) -> Result<Vec<Option<String>>> {
+    let mut prompt = format!(
+        "Summarize each of the following {} conversations concisely, preserving key \
+         information and context. Summarize each one separately. Start each summary \
+         with a line containing only its marker, `=== SUMMARY 1 ===` for the first, \
+         `=== SUMMARY 2 ===` for the second and so on, and write nothing else.\n",
+        texts.len()
+    );
+    for (i, text) in texts.iter().enumerate() {
+        prompt.push_str(&format!("\n=== CONVERSATION {} ===\n{}\n", i + 1, text));
+    }
+    let messages = vec![Message {
+        role: Role::User,
+        content: prompt,
+        tool_calls: None,
+        tool_call_id: None,
+        images: Vec::new(),
+    }];
+
+    let response = provider.chat(&messages, None).await?;
+    if let Some(ref usage) = response.usage {
+        debug!(
+            "Summarized {} texts in one request ({} input, {} output tokens)",
+            texts.len(),
+            usage.input_tokens,
+            usage.output_tokens
+        );
+    }
+    match response.content {
+        LLMResponseContent::Text(answer) => Ok(split_summaries(&answer, texts.len())),
+        _ => anyhow::bail!("Unexpected response type"),
+    }
+}
+
+/// The text under each `=== SUMMARY n ===` marker, for n in 1..=count
+fn split_summaries(answer: &str, count: usize) -> Vec<Option<String>> {
+    let mut summaries = vec![None; count];
+    let markers: Vec<_> = SUMMARY_MARKER_RE.captures_iter(answer).collect();
+    for (i, cap) in markers.iter().enumerate() {
+        let start = cap.get(0).unwrap().end();
+        let end = markers
+            .get(i + 1)
+            .map_or(answer.len(), |next| next.get(0).unwrap().start());
+        let text = answer[start..end].trim();
+        if let Ok(n) = cap[1].parse::<usize>()
+            && (1..=count).contains(&n)
+            && !text.is_empty()
+            && summaries[n - 1].is_none()
+        {
+            summaries[n - 1] = Some(text.to_string());
+        }
+    }
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::providers::{LLMResponse, ToolSchema};
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// Answers batches with summaries 1 and 3 only; counts single requests
+    struct BatchProvider {
+        singles: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for BatchProvider {
+        async fn chat(
+            &self,
+            _messages: &[Message],
+            _tools: Option<&[ToolSchema]>,
+        ) -> Result<LLMResponse> {
+            Ok(LLMResponse {
+                content: LLMResponseContent::Text(
+                    "=== SUMMARY 1 ===\nfirst\n\n=== SUMMARY 3 ===\nthird\n".to_string(),
+                ),
+                usage: None,
+            })
+        }
+
+        async fn summarize(&self, text: &str) -> Result<String> {
+            self.singles.lock().unwrap().push(text.to_string());
+            Ok(format!("alone: {}", text))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_summarize_batch() {
+        let texts: Vec<String> = ["aaaa", "bbbb", "cccc", "dddddddddddd"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        assert_eq!(pack(&texts, 12), vec![vec![0, 1, 2], vec![3]]);
+        assert_eq!(pack(&texts, 0), vec![vec![0], vec![1], vec![2], vec![3]]);
+
+        let provider = BatchProvider {
+            singles: Mutex::new(Vec::new()),
+        };
+        let results: Vec<String> = summarize_batch(&provider, &texts, 12)
+            .await
+            .into_iter()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(
+            results,
+            vec!["first", "alone: bbbb", "third", "alone: dddddddddddd"]
+        );
+        // Missing from the batched answer, or too large to batch
+        assert_eq!(
+            *provider.singles.lock().unwrap(),
+            vec!["bbbb", "dddddddddddd"]
+        );
+    }
+}
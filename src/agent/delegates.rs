@@ -0,0 +1,387 @@
+//! Delegated agents defined in AGENTS.md
+//!
+//! Each `## name` section of AGENTS.md that has a `description:` line defines
+//! a lightweight specialist agent:
+//!
+//! ```markdown
+//! ## translator
+//! description: Translates text between Japanese and English
+//! model: claude-cli/sonnet
+//! tools: web_fetch
+//!
+//! You are a careful translator. Preserve tone and formatting.
+//! ```
+//!
+//! `model` and `tools` are optional (default: the main model, no tools). The
+//! text after the fields is the agent's SOUL. The main agent hands work to
+//! these agents through the `delegate` tool; each run is a fresh, single-task
+//! conversation in the same process, and every delegation is appended to
+//! `delegations.jsonl` in the state directory.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info, warn};
+
+use super::providers::{self, LLMResponseContent, Message, Role, ToolSchema};
+use super::sanitize;
+use super::tools::{Tool, create_default_tools};
+use crate::config::Config;
+use crate::memory::MemoryManager;
+
+/// Maximum tool-call rounds a delegated agent may take for one task
+const MAX_DELEGATE_ROUNDS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DelegateAgent {
+    pub name: String,
+    pub description: String,
+    /// Model override (None = the main agent's default model)
+    pub model: Option<String>,
+    /// Names of tools the agent may use
+    pub tools: Vec<String>,
+    /// Persona / instructions for the agent
+    pub soul: String,
+}
+
+/// Parse delegated agent definitions from AGENTS.md content.
+/// Sections without a `description:` line are ignored, so free-form notes
+/// in AGENTS.md keep working as plain context.
+pub fn parse_agents_md(content: &str) -> Vec<DelegateAgent> {
+    let mut agents = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+
+    for line in content.lines() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            if let Some((name, body)) = current.take() {
+                agents.extend(parse_section(&name, &body));
+            }
+            current = Some((heading.trim().to_string(), Vec::new()));
+        } else if line.starts_with("# ") {
+            if let Some((name, body)) = current.take() {
+                agents.extend(parse_section(&name, &body));
+            }
+        } else if let Some((_, body)) = current.as_mut() {
+            body.push(line);
+        }
+    }
+    if let Some((name, body)) = current {
+        agents.extend(parse_section(&name, &body));
+    }
+
+    agents
+}
+
+fn parse_section(name: &str, lines: &[&str]) -> Option<DelegateAgent> {
+    let name = name.trim().to_lowercase().replace(' ', "-");
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut description = None;
+    let mut model = None;
+    let mut tools = Vec::new();
+    let mut soul_start = lines.len();
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim().trim_start_matches("- ");
+        if trimmed.is_empty() && description.is_none() {
+            continue;
+        }
+        let Some((key, value)) = trimmed.split_once(':') else {
+            soul_start = i;
+            break;
+        };
+        let value = value.trim();
+        match key.trim().to_lowercase().as_str() {
+            "description" => description = Some(value.to_string()),
+            "model" if !value.is_empty() => model = Some(value.to_string()),
+            "model" => {}
+            "tools" => {
+                tools = value
+                    .split(',')
+                    .map(|t| t.trim().to_string())
+                    .filter(|t| !t.is_empty())
+                    .collect()
+            }
+            _ => {
+                soul_start = i;
+                break;
+            }
+        }
+    }
+
+    Some(DelegateAgent {
+        name,
+        description: description?,
+        model,
+        tools,
+        soul: lines[soul_start..].join("\n").trim().to_string(),
+    })
+}
+
+/// Load the registry from the workspace's AGENTS.md (empty if missing)
+pub fn load_registry(workspace: &std::path::Path) -> Vec<DelegateAgent> {
+    std::fs::read_to_string(workspace.join("AGENTS.md"))
+        .map(|content| parse_agents_md(&content))
+        .unwrap_or_default()
+}
+
+// Delegate Tool
+pub struct DelegateTool {
+    config: Config,
+    memory: Option<Arc<MemoryManager>>,
+    workspace: PathBuf,
+    state_dir: PathBuf,
+}
+
+impl DelegateTool {
+    pub fn new(config: Config, memory: Option<Arc<MemoryManager>>) -> Self {
+        let workspace = config.workspace_path();
+        let state_dir = workspace
+            .parent()
+            .unwrap_or_else(|| std::path::Path::new("~/.localgpt"))
+            .to_path_buf();
+        Self {
+            config,
+            memory,
+            workspace,
+            state_dir,
+        }
+    }
+
+    /// Run one task on a delegated agent and return its final answer
+    async fn run(&self, agent: &DelegateAgent, task: &str) -> Result<String> {
+        let model = agent
+            .model
+            .clone()
+            .unwrap_or_else(|| self.config.agent.default_model.clone());
+        let provider = providers::create_provider(&model, &self.config)?;
+
+        // Never hand the delegate tool itself to a delegated agent
+        let tools: Vec<Box<dyn Tool>> = create_default_tools(&self.config, self.memory.clone())?
+            .into_iter()
+            .filter(|t| t.name() != "delegate" && agent.tools.iter().any(|n| n == t.name()))
+            .collect();
+        let tool_schemas: Vec<ToolSchema> = tools.iter().map(|t| t.schema()).collect();
+        let schemas = (!tool_schemas.is_empty()).then_some(tool_schemas.as_slice());
+
+        let mut system = format!(
+            "You are \"{}\", a specialist agent working for another assistant. \
+             Complete the task you are given and reply with the result only.",
+            agent.name
+        );
+        if !agent.soul.is_empty() {
+            system = format!("{}\n\n{}", agent.soul, system);
+        }
+
+        let mut messages = vec![
+            text_message(Role::System, system),
+            text_message(Role::User, task.to_string()),
+        ];
+
+        for _ in 0..MAX_DELEGATE_ROUNDS {
+            let response = provider.chat(&messages, schemas).await?;
+            let calls = match response.content {
+                LLMResponseContent::Text(text) => return Ok(text),
+                LLMResponseContent::ToolCalls(calls) => calls,
+            };
+
+            let mut results = Vec::with_capacity(calls.len());
+            for call in &calls {
+                let output = match tools.iter().find(|t| t.name() == call.name) {
+                    Some(tool) => match tool.execute(&call.arguments).await {
+                        Ok(out) if self.config.tools.use_content_delimiters => {
+                            sanitize::wrap_tool_output(&call.name, &out, None).content
+                        }
+                        Ok(out) => out,
+                        Err(e) => format!("Error: {}", e),
+                    },
+                    None => format!("Error: tool {} is not available to this agent", call.name),
+                };
+                results.push((call.id.clone(), output));
+            }
+
+            messages.push(Message {
+                role: Role::Assistant,
+                content: String::new(),
+                tool_calls: Some(calls),
+                tool_call_id: None,
+                images: Vec::new(),
+            });
+            for (call_id, output) in results {
+                messages.push(Message {
+                    role: Role::Tool,
+                    content: output,
+                    tool_calls: None,
+                    tool_call_id: Some(call_id),
+                    images: Vec::new(),
+                });
+            }
+        }
+
+        anyhow::bail!(
+            "agent {} did not finish within {} tool rounds",
+            agent.name,
+            MAX_DELEGATE_ROUNDS
+        )
+    }
+
+    fn log_delegation(&self, agent: &str, task: &str, ok: bool, output: &str, duration_ms: u64) {
+        let entry = json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "agent": agent,
+            "task": task,
+            "ok": ok,
+            "output": output,
+            "duration_ms": duration_ms,
+        });
+        let result = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.state_dir.join("delegations.jsonl"))
+            .and_then(|mut f| writeln!(f, "{}", entry));
+        if let Err(e) = result {
+            warn!("Failed to write delegation log: {}", e);
+        }
+    }
+}
+
+fn text_message(role: Role, content: String) -> Message {
+    Message {
+        role,
+        content,
+        tool_calls: None,
+        tool_call_id: None,
+        images: Vec::new(),
+    }
+}
+
+#[async_trait]
+impl Tool for DelegateTool {
+    fn name(&self) -> &str {
+        "delegate"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        let agents = load_registry(&self.workspace);
+        let listing = agents
+            .iter()
+            .map(|a| format!("- {}: {}", a.name, a.description))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        ToolSchema {
+            name: "delegate".to_string(),
+            description: format!(
+                "Hand a self-contained task to a specialist agent from AGENTS.md and get its answer. \
+                 The agent does not see this conversation, so include all needed context.\n\
+                 Available agents:\n{}",
+                listing
+            ),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "agent": {
+                        "type": "string",
+                        "enum": agents.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(),
+                        "description": "Name of the agent to delegate to"
+                    },
+                    "task": {
+                        "type": "string",
+                        "description": "The task, with all context the agent needs"
+                    }
+                },
+                "required": ["agent", "task"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let name = args["agent"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing agent"))?;
+        let task = args["task"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing task"))?;
+
+        let agents = load_registry(&self.workspace);
+        let agent = agents
+            .iter()
+            .find(|a| a.name == name.to_lowercase())
+            .ok_or_else(|| anyhow::anyhow!("Unknown agent: {}", name))?;
+
+        info!("Delegating to agent {}: {}", agent.name, task);
+        let start = Instant::now();
+        let result = self.run(agent, task).await;
+        let duration_ms = start.elapsed().as_millis() as u64;
+
+        match result {
+            Ok(answer) => {
+                info!("Agent {} finished in {} ms", agent.name, duration_ms);
+                self.log_delegation(&agent.name, task, true, &answer, duration_ms);
+                Ok(format!("[{} agent] {}", agent.name, answer))
+            }
+            Err(e) => {
+                warn!("Agent {} failed: {}", agent.name, e);
+                self.log_delegation(&agent.name, task, false, &e.to_string(), duration_ms);
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_agents_md() {
+        let content = "\
+# Agents
+
+Some free-form notes.
+
+## Translator
+description: Translates between Japanese and English
+model: claude-cli/sonnet
+tools: web_fetch, memory_search
+
+You are a careful translator.
+Keep formatting intact.
+
+## Notes
+Just a heading without fields.
+
+## coder
+- description: Writes and reviews small scripts
+";
+        let agents = parse_agents_md(content);
+        assert_eq!(agents.len(), 2);
+
+        assert_eq!(agents[0].name, "translator");
+        assert_eq!(agents[0].model.as_deref(), Some("claude-cli/sonnet"));
+        assert_eq!(agents[0].tools, vec!["web_fetch", "memory_search"]);
+        assert_eq!(
+            agents[0].soul,
+            "You are a careful translator.\nKeep formatting intact."
+        );
+
+        assert_eq!(agents[1].name, "coder");
+        assert_eq!(agents[1].model, None);
+        assert!(agents[1].tools.is_empty());
+        assert!(agents[1].soul.is_empty());
+    }
+
+    #[test]
+    fn test_empty_agents_md() {
+        assert!(parse_agents_md("").is_empty());
+        assert!(parse_agents_md("# Agents\n\nNothing here yet.").is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+//! The host itself: local time and timezone, battery
+//!
+//! The battery is read from `/sys/class/power_supply` on Linux and from
+//! `pmset -g batt` on macOS. Machines without a battery add nothing.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, Local};
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use super::ContextProvider;
+use crate::jobs::format_elapsed;
+
+static PMSET_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\d+)%;\s*([^;]+);\s*(?:(\d+):(\d+) remaining)?").unwrap());
+
+/// Injects the local date, time and timezone
+pub(super) struct ClockProvider;
+
+#[async_trait]
+impl ContextProvider for ClockProvider {
+    fn name(&self) -> &str {
+        "clock"
+    }
+
+    async fn context(&self) -> Result<Option<String>> {
+        Ok(Some(format_clock(
+            Local::now().fixed_offset(),
+            zone_name().as_deref(),
+        )))
+    }
+}
+
+fn format_clock(now: DateTime<FixedOffset>, zone: Option<&str>) -> String {
+    let mut line = format!(
+        "Local time: {}, UTC{}",
+        now.format("%Y-%m-%d (%a) %H:%M"),
+        now.format("%:z")
+    );
+    if let Some(zone) = zone {
+        line.push_str(&format!(" ({})", zone));
+    }
+    line
+}
+
+/// IANA name of the local timezone, e.g. `Asia/Tokyo`
+fn zone_name() -> Option<String> {
+    if let Ok(tz) = std::env::var("TZ") {
+        let tz = tz.trim_start_matches(':').trim();
+        if !tz.is_empty() && !tz.starts_with('/') {
+            return Some(tz.to_string());
+        }
+    }
+    // /etc/localtime links to /usr/share/zoneinfo/<name> on Linux and macOS
+    if let Ok(target) = fs::read_link("/etc/localtime") {
+        let target = target.to_string_lossy().to_string();
+        if let Some((_, name)) = target.split_once("zoneinfo/") {
+            return Some(name.to_string());
+        }
+    }
+    fs::read_to_string("/etc/timezone")
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+#[derive(Debug, PartialEq)]
+struct BatteryStatus {
+    percent: u32,
+    /// "charging", "discharging", "full", ...
+    state: String,
+    /// Until empty when discharging, until full when charging
+    minutes_left: Option<i64>,
+}
+
+/// Injects the battery's charge and power state
+pub(super) struct BatteryProvider;
+
+#[async_trait]
+impl ContextProvider for BatteryProvider {
+    fn name(&self) -> &str {
+        "battery"
+    }
+
+    async fn context(&self) -> Result<Option<String>> {
+        let status = if cfg!(target_os = "macos") {
+            let output = tokio::process::Command::new("pmset")
+                .args(["-g", "batt"])
+                .output()
+                .await?;
+            parse_pmset(&String::from_utf8_lossy(&output.stdout))
+        } else {
+            read_power_supply(Path::new("/sys/class/power_supply"))
+        };
+        Ok(status.map(|status| format_battery(&status)))
+    }
+}
+
+fn format_battery(status: &BatteryStatus) -> String {
+    let mut line = format!("Battery: {}%, {}", status.percent, status.state);
+    match (status.minutes_left, status.state.as_str()) {
+        (Some(minutes), "charging") => {
+            line.push_str(&format!(", full in about {}", format_elapsed(minutes * 60)))
+        }
+        (Some(minutes), _) => {
+            line.push_str(&format!(", about {} left", format_elapsed(minutes * 60)))
+        }
+        (None, _) => {}
+    }
+    line
+}
+
+/// The first battery under a Linux `power_supply` class directory
+fn read_power_supply(dir: &Path) -> Option<BatteryStatus> {
+    let mut entries: Vec<_> = fs::read_dir(dir).ok()?.flatten().collect();
+    entries.sort_by_key(|e| e.file_name());
+    let read = |path: &Path, name: &str| {
+        fs::read_to_string(path.join(name))
+            .ok()
+            .map(|v| v.trim().to_string())
+    };
+    let number = |path: &Path, name: &str| read(path, name)?.parse::<f64>().ok();
+
+    let battery = entries
+        .iter()
+        .map(|e| e.path())
+        .find(|path| read(path, "type").as_deref() == Some("Battery"))?;
+    let percent = read(&battery, "capacity")?.parse().ok()?;
+    let state = read(&battery, "status")
+        .unwrap_or_else(|| "unknown".to_string())
+        .to_lowercase();
+
+    // Energy in µWh and power in µW, or charge in µAh and current in µA
+    let (now, full, rate) = match number(&battery, "energy_now") {
+        Some(now) => (
+            now,
+            number(&battery, "energy_full"),
+            number(&battery, "power_now"),
+        ),
+        None => (
+            number(&battery, "charge_now")?,
+            number(&battery, "charge_full"),
+            number(&battery, "current_now"),
+        ),
+    };
+    let hours = match (state.as_str(), rate.filter(|r| *r > 0.0)) {
+        ("discharging", Some(rate)) => Some(now / rate),
+        ("charging", Some(rate)) => full.map(|full| (full - now).max(0.0) / rate),
+        _ => None,
+    };
+
+    Some(BatteryStatus {
+        percent,
+        state,
+        minutes_left: hours.map(|h| (h * 60.0).round() as i64),
+    })
+}
+
+/// `pmset -g batt` output, e.g.
+/// ` -InternalBattery-0 (id=4653155) 85%; discharging; 4:12 remaining present: true`
+fn parse_pmset(output: &str) -> Option<BatteryStatus> {
+    let caps = PMSET_RE.captures(output)?;
+    let minutes_left = match (caps.get(3), caps.get(4)) {
+        (Some(h), Some(m)) => {
+            let minutes = h.as_str().parse::<i64>().ok()? * 60 + m.as_str().parse::<i64>().ok()?;
+            (minutes > 0).then_some(minutes)
+        }
+        _ => None,
+    };
+    Some(BatteryStatus {
+        percent: caps[1].parse().ok()?,
+        state: match caps[2].trim() {
+            "charged" => "full".to_string(),
+            state => state.to_string(),
+        },
+        minutes_left,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_host_context() {
+        let now = FixedOffset::east_opt(9 * 3600)
+            .unwrap()
+            .with_ymd_and_hms(2026, 3, 2, 8, 45, 0)
+            .unwrap();
+        assert_eq!(
+            format_clock(now, Some("Asia/Tokyo")),
+            "Local time: 2026-03-02 (Mon) 08:45, UTC+09:00 (Asia/Tokyo)"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let ac = dir.path().join("AC");
+        fs::create_dir(&ac).unwrap();
+        fs::write(ac.join("type"), "Mains\n").unwrap();
+        assert_eq!(read_power_supply(dir.path()), None);
+
+        let bat = dir.path().join("BAT0");
+        fs::create_dir(&bat).unwrap();
+        for (name, value) in [
+            ("type", "Battery"),
+            ("capacity", "64"),
+            ("status", "Discharging"),
+            ("energy_now", "30000000"),
+            ("energy_full", "50000000"),
+            ("power_now", "12000000"),
+        ] {
+            fs::write(bat.join(name), format!("{}\n", value)).unwrap();
+        }
+        let status = read_power_supply(dir.path()).unwrap();
+        assert_eq!(
+            format_battery(&status),
+            "Battery: 64%, discharging, about 2h 30m left"
+        );
+
+        let status = parse_pmset(
+            "Now drawing from 'AC Power'\n -InternalBattery-0 (id=4653155)\t92%; charging; 0:25 remaining present: true\n",
+        )
+        .unwrap();
+        assert_eq!(
+            format_battery(&status),
+            "Battery: 92%, charging, full in about 25m"
+        );
+        let status =
+            parse_pmset(" -InternalBattery-0 (id=1)\t100%; charged; 0:00 remaining").unwrap();
+        assert_eq!(format_battery(&status), "Battery: 100%, full");
+        assert_eq!(parse_pmset("Now drawing from 'AC Power'\n"), None);
+    }
+}
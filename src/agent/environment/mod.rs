@@ -0,0 +1,132 @@
+//! Context providers
+//!
+//! A provider contributes a few lines about the user's surroundings (weather,
+//! and so on) that are sent with every turn, so the model can answer "is it
+//! raining?" without a tool call and doesn't make things up. Blocks are
+//! synthetic like recalled memories: refreshed per turn, never persisted.
+//!
+//! | Section | Block |
+//! |---------|-------|
+//! | `[context.weather]` | current weather, today's forecast |
+//! | `[context.clock]` | local time and timezone |
+//! | `[context.battery]` | charge and power state |
+//! | `[context.calendar]` | current and next event |
+//! | `[context.timers]` | pending reminders, running jobs |
+
+mod host;
+mod schedule;
+mod weather;
+
+pub use weather::WeatherTool;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::config::Config;
+
+/// A slow provider is skipped for the turn rather than delaying the reply
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[async_trait]
+pub trait ContextProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// A few lines for the prompt, or `None` when there is nothing to add
+    async fn context(&self) -> Result<Option<String>>;
+}
+
+/// Providers enabled in the config
+pub fn create_context_providers(config: &Config) -> Vec<Arc<dyn ContextProvider>> {
+    let mut providers: Vec<Arc<dyn ContextProvider>> = Vec::new();
+
+    if let Some(ref weather) = config.context.weather
+        && weather.enabled
+    {
+        providers.push(Arc::new(weather::WeatherProvider::new(weather.clone())));
+    }
+    if config.context.clock.as_ref().is_some_and(|c| c.enabled) {
+        providers.push(Arc::new(host::ClockProvider));
+    }
+    if config.context.battery.as_ref().is_some_and(|c| c.enabled) {
+        providers.push(Arc::new(host::BatteryProvider));
+    }
+    if let Some(ref calendar) = config.context.calendar
+        && calendar.enabled
+    {
+        match config.calendar {
+            Some(ref source) if source.enabled => providers.push(Arc::new(
+                schedule::CalendarProvider::new(source, calendar.clone()),
+            )),
+            _ => warn!("[context.calendar] needs an enabled [calendar] section"),
+        }
+    }
+    if let Some(ref timers) = config.context.timers
+        && timers.enabled
+    {
+        providers.push(Arc::new(schedule::TimersProvider::new(timers.clone())));
+    }
+
+    providers
+}
+
+/// Collect every provider's block into one message. Failing or slow
+/// providers are left out.
+pub async fn build_environment_context(providers: &[Arc<dyn ContextProvider>]) -> Option<String> {
+    let mut sections = Vec::new();
+    for provider in providers {
+        match tokio::time::timeout(PROVIDER_TIMEOUT, provider.context()).await {
+            Ok(Ok(Some(block))) => sections.push(block),
+            Ok(Ok(None)) => {}
+            Ok(Err(e)) => debug!("Context provider {} failed: {}", provider.name(), e),
+            Err(_) => debug!("Context provider {} timed out", provider.name()),
+        }
+    }
+
+    if sections.is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        "Current environment (added automatically; mention it only when relevant):\n\n{}",
+        sections.join("\n\n")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(Option<&'static str>);
+
+    #[async_trait]
+    impl ContextProvider for Fixed {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        async fn context(&self) -> Result<Option<String>> {
+            match self.0 {
+                Some(text) => Ok(Some(text.to_string())),
+                None => anyhow::bail!("unavailable"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_build_environment_context() {
+        assert!(build_environment_context(&[]).await.is_none());
+
+        let providers: Vec<Arc<dyn ContextProvider>> = vec![
+            Arc::new(Fixed(Some("Weather: sunny"))),
+            Arc::new(Fixed(None)),
+        ];
+        let context = build_environment_context(&providers).await.unwrap();
+        assert!(context.ends_with("Weather: sunny"));
+
+        let failing: Vec<Arc<dyn ContextProvider>> = vec![Arc::new(Fixed(None))];
+        assert!(build_environment_context(&failing).await.is_none());
+    }
+}
@@ -0,0 +1,236 @@
+//! What's on: the current and next calendar event, pending reminders and
+//! running background jobs
+//!
+//! Times are given both as clock times and as countdowns, so "how long
+//! until my meeting?" needs no arithmetic and no tool call.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Local, Utc};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use super::ContextProvider;
+use crate::calendar::{Calendar, CalendarEvent};
+use crate::config::{CalendarConfig, CalendarContextConfig, TimersConfig};
+use crate::discord::{Reminder, ReminderStore};
+use crate::jobs::{Job, JobStatus, JobStore, format_elapsed};
+
+/// Injects the calendar event in progress and the next one
+pub(super) struct CalendarProvider {
+    calendar: Calendar,
+    config: CalendarContextConfig,
+    /// Events fetched for the lookahead window plus the cache period
+    cache: Mutex<Option<(Instant, Vec<CalendarEvent>)>>,
+}
+
+impl CalendarProvider {
+    pub fn new(calendar: &CalendarConfig, config: CalendarContextConfig) -> Self {
+        Self {
+            calendar: Calendar::new(calendar),
+            config,
+            cache: Mutex::new(None),
+        }
+    }
+
+    async fn events(&self, now: DateTime<Utc>) -> Result<Vec<CalendarEvent>> {
+        let max_age = std::time::Duration::from_secs(self.config.cache_minutes * 60);
+        if let Some((fetched, events)) = self.cache.lock().unwrap().as_ref()
+            && fetched.elapsed() < max_age
+        {
+            return Ok(events.clone());
+        }
+
+        let to = now
+            + Duration::hours(self.config.lookahead_hours as i64)
+            + Duration::minutes(self.config.cache_minutes as i64);
+        let events = self.calendar.list_events(now, to).await?;
+        *self.cache.lock().unwrap() = Some((Instant::now(), events.clone()));
+        Ok(events)
+    }
+}
+
+#[async_trait]
+impl ContextProvider for CalendarProvider {
+    fn name(&self) -> &str {
+        "calendar"
+    }
+
+    async fn context(&self) -> Result<Option<String>> {
+        let now = Utc::now();
+        let events = self.events(now).await?;
+        Ok(Some(format_calendar(
+            &events,
+            now,
+            Duration::hours(self.config.lookahead_hours as i64),
+        )))
+    }
+}
+
+/// Local clock time, with the day when it isn't today
+fn clock_time(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let local = time.with_timezone(&Local);
+    if local.date_naive() == now.with_timezone(&Local).date_naive() {
+        local.format("%H:%M").to_string()
+    } else {
+        local.format("%a %H:%M").to_string()
+    }
+}
+
+fn format_calendar(events: &[CalendarEvent], now: DateTime<Utc>, lookahead: Duration) -> String {
+    let mut lines = Vec::new();
+    let named = |event: &CalendarEvent| match event.location {
+        Some(ref location) => format!("{} @ {}", event.summary, location),
+        None => event.summary.clone(),
+    };
+
+    for event in events.iter().filter(|e| e.start <= now && now < e.end) {
+        if event.all_day {
+            lines.push(format!("Calendar today: {}", named(event)));
+        } else {
+            lines.push(format!(
+                "Calendar now: {} (until {}, {} left)",
+                named(event),
+                clock_time(event.end, now),
+                format_elapsed((event.end - now).num_seconds())
+            ));
+        }
+    }
+    match events
+        .iter()
+        .find(|e| !e.all_day && e.start > now && e.start <= now + lookahead)
+    {
+        Some(event) => lines.push(format!(
+            "Next event: {} at {} (in {})",
+            named(event),
+            clock_time(event.start, now),
+            format_elapsed((event.start - now).num_seconds())
+        )),
+        None => lines.push(format!(
+            "Next event: none in the next {} hours",
+            lookahead.num_hours()
+        )),
+    }
+    lines.join("\n")
+}
+
+/// Injects pending Discord reminders and queued or running jobs
+pub(super) struct TimersProvider {
+    config: TimersConfig,
+}
+
+impl TimersProvider {
+    pub fn new(config: TimersConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl ContextProvider for TimersProvider {
+    fn name(&self) -> &str {
+        "timers"
+    }
+
+    async fn context(&self) -> Result<Option<String>> {
+        let reminders = ReminderStore::open_default()?.upcoming(self.config.limit)?;
+        let mut jobs = JobStore::open_default()?.active()?;
+        jobs.truncate(self.config.limit);
+        Ok(format_timers(&reminders, &jobs, Utc::now()))
+    }
+}
+
+fn format_timers(reminders: &[Reminder], jobs: &[Job], now: DateTime<Utc>) -> Option<String> {
+    let mut lines = Vec::new();
+    if !reminders.is_empty() {
+        lines.push("Pending reminders:".to_string());
+    }
+    for reminder in reminders {
+        let due = DateTime::from_timestamp(reminder.due_at, 0).unwrap_or(now);
+        let when = if due <= now {
+            "due now".to_string()
+        } else {
+            format!(
+                "at {} (in {})",
+                clock_time(due, now),
+                format_elapsed((due - now).num_seconds())
+            )
+        };
+        lines.push(format!(
+            "- {} for <@{}>: {}",
+            when, reminder.user_id, reminder.message
+        ));
+    }
+
+    if !jobs.is_empty() {
+        lines.push("Background jobs:".to_string());
+    }
+    for job in jobs {
+        let state = match (job.status, job.started_at) {
+            (JobStatus::Running, Some(started)) => {
+                format!("running for {}", format_elapsed(now.timestamp() - started))
+            }
+            (status, _) => status.as_str().to_string(),
+        };
+        lines.push(format!("- #{} {}: {}", job.id, state, job.title));
+    }
+
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn event(summary: &str, start: DateTime<Utc>, minutes: i64, all_day: bool) -> CalendarEvent {
+        CalendarEvent {
+            uid: summary.to_string(),
+            summary: summary.to_string(),
+            start,
+            end: start + Duration::minutes(minutes),
+            all_day,
+            location: None,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_format_schedule() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap();
+        let mut review = event("Design review", now + Duration::minutes(95), 60, false);
+        review.location = Some("Room 3".to_string());
+        let events = vec![
+            event("Holiday", now - Duration::hours(12), 24 * 60, true),
+            event("Standup", now - Duration::minutes(5), 15, false),
+            review,
+        ];
+
+        let text = format_calendar(&events, now, Duration::hours(12));
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "Calendar today: Holiday");
+        assert!(lines[1].starts_with("Calendar now: Standup (until "));
+        assert!(lines[1].ends_with(", 10m left)"));
+        assert!(lines[2].starts_with("Next event: Design review @ Room 3 at "));
+        assert!(lines[2].ends_with(" (in 1h 35m)"));
+        assert_eq!(
+            format_calendar(&[], now, Duration::hours(12)),
+            "Next event: none in the next 12 hours"
+        );
+
+        assert_eq!(format_timers(&[], &[], now), None);
+        let reminders = vec![Reminder {
+            id: 1,
+            user_id: "42".to_string(),
+            channel_id: "7".to_string(),
+            requested_by: "42".to_string(),
+            message: "Take the bread out".to_string(),
+            due_at: (now + Duration::minutes(20)).timestamp(),
+        }];
+        let store = JobStore::open_in_memory().unwrap();
+        let job = store.create("Summarize notes", "…", None).unwrap();
+        let text = format_timers(&reminders, &[job], now).unwrap();
+        assert!(text.starts_with("Pending reminders:\n- at "));
+        assert!(text.contains(" (in 20m) for <@42>: Take the bread out\n"));
+        assert!(text.ends_with("Background jobs:\n- #1 queued: Summarize notes"));
+    }
+}
@@ -0,0 +1,437 @@
+//! Weather and sunrise/sunset from Open-Meteo (no API key)
+//!
+//! Forecasts and geocoding results are cached process-wide, so the many
+//! agents of a Discord bot share one request per location and cache period.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use super::ContextProvider;
+use crate::agent::providers::ToolSchema;
+use crate::agent::tools::Tool;
+use crate::config::WeatherConfig;
+
+const FORECAST_URL: &str = "https://api.open-meteo.com/v1/forecast";
+const GEOCODING_URL: &str = "https://geocoding-api.open-meteo.com/v1/search";
+
+/// Days fetched per forecast (the tool can show up to this many)
+const FORECAST_DAYS: usize = 7;
+
+static FORECASTS: LazyLock<Mutex<HashMap<String, (Instant, Forecast)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+static PLACES: LazyLock<Mutex<HashMap<String, Place>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone)]
+struct Place {
+    name: String,
+    latitude: f64,
+    longitude: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Forecast {
+    current: Current,
+    daily: Daily,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Current {
+    temperature_2m: f64,
+    apparent_temperature: f64,
+    relative_humidity_2m: f64,
+    weather_code: u32,
+    wind_speed_10m: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Daily {
+    time: Vec<String>,
+    weather_code: Vec<u32>,
+    temperature_2m_max: Vec<f64>,
+    temperature_2m_min: Vec<f64>,
+    precipitation_probability_max: Vec<Option<f64>>,
+    sunrise: Vec<String>,
+    sunset: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct GeocodingResponse {
+    #[serde(default)]
+    results: Vec<GeocodingResult>,
+}
+
+#[derive(Deserialize)]
+struct GeocodingResult {
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    country_code: Option<String>,
+}
+
+struct Units {
+    temperature: &'static str,
+    wind: &'static str,
+}
+
+struct OpenMeteo {
+    client: reqwest::Client,
+    config: WeatherConfig,
+}
+
+impl OpenMeteo {
+    fn new(config: WeatherConfig) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            config,
+        }
+    }
+
+    fn imperial(&self) -> bool {
+        self.config.units.eq_ignore_ascii_case("imperial")
+    }
+
+    fn units(&self) -> Units {
+        if self.imperial() {
+            Units {
+                temperature: "°F",
+                wind: "mph",
+            }
+        } else {
+            Units {
+                temperature: "°C",
+                wind: "km/h",
+            }
+        }
+    }
+
+    /// The configured place, or `query` looked up by name
+    async fn place(&self, query: Option<&str>) -> Result<Place> {
+        if query.is_none()
+            && let (Some(latitude), Some(longitude)) = (self.config.latitude, self.config.longitude)
+        {
+            return Ok(Place {
+                name: self
+                    .config
+                    .location
+                    .clone()
+                    .unwrap_or_else(|| format!("{:.2}, {:.2}", latitude, longitude)),
+                latitude,
+                longitude,
+            });
+        }
+
+        let name = query
+            .or(self.config.location.as_deref())
+            .context("No weather location configured ([context.weather] location)")?;
+        let key = name.trim().to_lowercase();
+        let cached = PLACES.lock().unwrap().get(&key).cloned();
+        if let Some(place) = cached {
+            return Ok(place);
+        }
+
+        let url = reqwest::Url::parse_with_params(
+            GEOCODING_URL,
+            &[("name", name), ("count", "1"), ("format", "json")],
+        )?;
+        let response: GeocodingResponse = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let found = response
+            .results
+            .into_iter()
+            .next()
+            .with_context(|| format!("Unknown location: {}", name))?;
+        let place = Place {
+            name: match found.country_code {
+                Some(country) => format!("{}, {}", found.name, country),
+                None => found.name,
+            },
+            latitude: found.latitude,
+            longitude: found.longitude,
+        };
+
+        PLACES.lock().unwrap().insert(key, place.clone());
+        Ok(place)
+    }
+
+    async fn forecast(&self, place: &Place) -> Result<Forecast> {
+        let key = format!(
+            "{:.3},{:.3},{}",
+            place.latitude,
+            place.longitude,
+            self.imperial()
+        );
+        let max_age = Duration::from_secs(self.config.cache_minutes * 60);
+        let cached = FORECASTS
+            .lock()
+            .unwrap()
+            .get(&key)
+            .filter(|(fetched, _)| fetched.elapsed() < max_age)
+            .map(|(_, forecast)| forecast.clone());
+        if let Some(forecast) = cached {
+            return Ok(forecast);
+        }
+
+        let mut query = vec![
+            ("latitude", place.latitude.to_string()),
+            ("longitude", place.longitude.to_string()),
+            (
+                "current",
+                "temperature_2m,apparent_temperature,relative_humidity_2m,weather_code,wind_speed_10m"
+                    .to_string(),
+            ),
+            (
+                "daily",
+                "weather_code,temperature_2m_max,temperature_2m_min,\
+                 precipitation_probability_max,sunrise,sunset"
+                    .to_string(),
+            ),
+            ("timezone", "auto".to_string()),
+            ("forecast_days", FORECAST_DAYS.to_string()),
+        ];
+        if self.imperial() {
+            query.push(("temperature_unit", "fahrenheit".to_string()));
+            query.push(("wind_speed_unit", "mph".to_string()));
+        }
+
+        let url = reqwest::Url::parse_with_params(FORECAST_URL, &query)?;
+        let forecast: Forecast = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Unexpected Open-Meteo response")?;
+
+        FORECASTS
+            .lock()
+            .unwrap()
+            .insert(key, (Instant::now(), forecast.clone()));
+        Ok(forecast)
+    }
+}
+
+/// Current conditions plus today's range and daylight
+fn format_current(place: &Place, forecast: &Forecast, units: &Units) -> String {
+    let now = &forecast.current;
+    let mut text = format!(
+        "Weather in {}: {}, {:.0}{} (feels like {:.0}{}), humidity {:.0}%, wind {:.0} {}.",
+        place.name,
+        describe_code(now.weather_code),
+        now.temperature_2m,
+        units.temperature,
+        now.apparent_temperature,
+        units.temperature,
+        now.relative_humidity_2m,
+        now.wind_speed_10m,
+        units.wind,
+    );
+    if let Some(today) = format_day(&forecast.daily, 0, units) {
+        text.push_str(&format!("\nToday: {}", today));
+    }
+    text
+}
+
+/// One day of the daily forecast
+fn format_day(daily: &Daily, index: usize, units: &Units) -> Option<String> {
+    let mut text = format!(
+        "{}, {:.0}-{:.0}{}",
+        describe_code(*daily.weather_code.get(index)?),
+        daily.temperature_2m_min.get(index)?,
+        daily.temperature_2m_max.get(index)?,
+        units.temperature,
+    );
+    if let Some(Some(chance)) = daily.precipitation_probability_max.get(index) {
+        text.push_str(&format!(", {:.0}% chance of precipitation", chance));
+    }
+    if let (Some(sunrise), Some(sunset)) = (daily.sunrise.get(index), daily.sunset.get(index)) {
+        text.push_str(&format!(
+            ", sunrise {}, sunset {}",
+            time_of_day(sunrise),
+            time_of_day(sunset)
+        ));
+    }
+    Some(text)
+}
+
+/// "2026-03-01T06:05" -> "06:05"
+fn time_of_day(timestamp: &str) -> &str {
+    timestamp
+        .split_once('T')
+        .map(|(_, time)| time)
+        .unwrap_or(timestamp)
+}
+
+/// WMO weather interpretation codes
+fn describe_code(code: u32) -> &'static str {
+    match code {
+        0 => "clear sky",
+        1 => "mainly clear",
+        2 => "partly cloudy",
+        3 => "overcast",
+        45 | 48 => "fog",
+        51 | 53 | 55 => "drizzle",
+        56 | 57 => "freezing drizzle",
+        61 => "light rain",
+        63 => "rain",
+        65 => "heavy rain",
+        66 | 67 => "freezing rain",
+        71 => "light snow",
+        73 => "snow",
+        75 => "heavy snow",
+        77 => "snow grains",
+        80..=82 => "rain showers",
+        85 | 86 => "snow showers",
+        95 => "thunderstorm",
+        96 | 99 => "thunderstorm with hail",
+        _ => "unknown conditions",
+    }
+}
+
+/// Injects current weather for the configured location
+pub(super) struct WeatherProvider {
+    api: OpenMeteo,
+}
+
+impl WeatherProvider {
+    pub fn new(config: WeatherConfig) -> Self {
+        Self {
+            api: OpenMeteo::new(config),
+        }
+    }
+}
+
+#[async_trait]
+impl ContextProvider for WeatherProvider {
+    fn name(&self) -> &str {
+        "weather"
+    }
+
+    async fn context(&self) -> Result<Option<String>> {
+        let place = self.api.place(None).await?;
+        let forecast = self.api.forecast(&place).await?;
+        Ok(Some(format_current(&place, &forecast, &self.api.units())))
+    }
+}
+
+/// Weather lookup for any place and the coming days
+pub struct WeatherTool {
+    api: OpenMeteo,
+}
+
+impl WeatherTool {
+    pub fn new(config: WeatherConfig) -> Self {
+        Self {
+            api: OpenMeteo::new(config),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for WeatherTool {
+    fn name(&self) -> &str {
+        "weather"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "weather".to_string(),
+            description: "Current weather and daily forecast (with sunrise/sunset) for a place"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "location": {
+                        "type": "string",
+                        "description": "City or place name (default: the user's location)"
+                    },
+                    "days": {
+                        "type": "integer",
+                        "description": "Days of forecast to include (1-7, default: 3)"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments).unwrap_or(json!({}));
+        let place = self.api.place(args["location"].as_str()).await?;
+        let forecast = self.api.forecast(&place).await?;
+        let units = self.api.units();
+        let days = (args["days"].as_u64().unwrap_or(3) as usize).clamp(1, FORECAST_DAYS);
+
+        let mut lines = vec![format_current(&place, &forecast, &units)];
+        for index in 1..days {
+            if let (Some(date), Some(day)) = (
+                forecast.daily.time.get(index),
+                format_day(&forecast.daily, index, &units),
+            ) {
+                lines.push(format!("{}: {}", date, day));
+            }
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_forecast() {
+        let forecast: Forecast = serde_json::from_value(json!({
+            "current": {
+                "temperature_2m": 12.4,
+                "apparent_temperature": 10.1,
+                "relative_humidity_2m": 81,
+                "weather_code": 61,
+                "wind_speed_10m": 14.2
+            },
+            "daily": {
+                "time": ["2026-03-01", "2026-03-02"],
+                "weather_code": [61, 0],
+                "temperature_2m_max": [14.0, 17.2],
+                "temperature_2m_min": [8.3, 7.9],
+                "precipitation_probability_max": [80, null],
+                "sunrise": ["2026-03-01T06:05", "2026-03-02T06:04"],
+                "sunset": ["2026-03-01T17:40", "2026-03-02T17:41"]
+            }
+        }))
+        .unwrap();
+        let place = Place {
+            name: "Tokyo, JP".to_string(),
+            latitude: 35.68,
+            longitude: 139.69,
+        };
+        let units = Units {
+            temperature: "°C",
+            wind: "km/h",
+        };
+
+        let text = format_current(&place, &forecast, &units);
+        assert!(text.starts_with("Weather in Tokyo, JP: light rain, 12°C (feels like 10°C)"));
+        assert!(text.contains(
+            "Today: light rain, 8-14°C, 80% chance of precipitation, sunrise 06:05, sunset 17:40"
+        ));
+
+        assert_eq!(
+            format_day(&forecast.daily, 1, &units).unwrap(),
+            "clear sky, 8-17°C, sunrise 06:04, sunset 17:41"
+        );
+        assert!(format_day(&forecast.daily, 2, &units).is_none());
+    }
+}
@@ -0,0 +1,301 @@
+//! A/B experiments
+//!
+//! `[agent.experiment]` answers a share of turns with an alternate
+//! configuration (another model and/or extra instructions) so it can be
+//! compared with the usual one on real traffic. Turns are assigned by a hash
+//! of the message being answered, so a retried message keeps its variant.
+//! Every experiment turn is recorded in `~/.localgpt/experiments.sqlite`
+//! with its latency, reply length and the prompt it came from;
+//! `localgpt experiment` compares the variants.
+
+use anyhow::Result;
+use rusqlite::params;
+use serde::Serialize;
+use std::path::Path;
+
+use super::get_state_dir;
+use crate::config::ExperimentConfig;
+use crate::db::SqlitePool;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    /// The usual configuration
+    Control,
+    /// The experiment's alternate configuration
+    Treatment,
+}
+
+impl Variant {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Variant::Control => "control",
+            Variant::Treatment => "treatment",
+        }
+    }
+}
+
+impl ExperimentConfig {
+    /// Whether the experiment is configured and gets any traffic
+    pub fn is_active(&self) -> bool {
+        !self.name.is_empty()
+            && self.percent > 0
+            && (self.model.is_some() || self.instructions.is_some())
+    }
+
+    /// The variant answering the message with this ID
+    pub fn assign(&self, message_id: &str) -> Variant {
+        // FNV-1a: stable across restarts, unlike the std hasher
+        let hash = message_id
+            .bytes()
+            .fold(0xcbf29ce484222325u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+            });
+        if hash % 100 < u64::from(self.percent.min(100)) {
+            Variant::Treatment
+        } else {
+            Variant::Control
+        }
+    }
+}
+
+/// One answered turn
+#[derive(Debug, Clone)]
+pub struct ExperimentTurn<'a> {
+    pub experiment: &'a str,
+    pub variant: Variant,
+    /// Where the reply was sent, e.g. `discord:<channel_id>`
+    pub scope: &'a str,
+    /// The reply's record in the prompt store
+    pub prompt_id: Option<i64>,
+    pub model: &'a str,
+    pub latency_ms: u64,
+    pub reply_chars: usize,
+}
+
+/// Totals for one variant
+#[derive(Debug, Clone, Serialize)]
+pub struct VariantReport {
+    pub variant: String,
+    pub turns: u64,
+    pub avg_latency_ms: f64,
+    pub max_latency_ms: u64,
+    pub avg_reply_chars: f64,
+    pub thumbs_up: u64,
+    pub thumbs_down: u64,
+}
+
+#[derive(Clone)]
+pub struct ExperimentStore {
+    pool: SqlitePool,
+}
+
+impl ExperimentStore {
+    /// Open the shared database at `~/.localgpt/experiments.sqlite`
+    pub fn open_default() -> Result<Self> {
+        Self::open(&get_state_dir()?.join("experiments.sqlite"))
+    }
+
+    pub fn open(db_path: &Path) -> Result<Self> {
+        Self::init(SqlitePool::shared(db_path, None)?)
+    }
+
+    /// In-memory store (tests)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(SqlitePool::open_in_memory(None)?)
+    }
+
+    fn init(pool: SqlitePool) -> Result<Self> {
+        pool.get()?.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS experiment_turns (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                experiment TEXT NOT NULL,
+                variant TEXT NOT NULL,
+                scope TEXT NOT NULL,
+                prompt_id INTEGER,
+                model TEXT NOT NULL,
+                latency_ms INTEGER NOT NULL,
+                reply_chars INTEGER NOT NULL,
+                thumbs_up INTEGER NOT NULL DEFAULT 0,
+                thumbs_down INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_experiment_turns_experiment
+                ON experiment_turns(experiment);
+            CREATE INDEX IF NOT EXISTS idx_experiment_turns_prompt
+                ON experiment_turns(prompt_id);
+            "#,
+        )?;
+
+        Ok(Self { pool })
+    }
+
+    pub fn record(&self, turn: &ExperimentTurn) -> Result<i64> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "INSERT INTO experiment_turns
+                 (experiment, variant, scope, prompt_id, model, latency_ms, reply_chars, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                turn.experiment,
+                turn.variant.as_str(),
+                turn.scope,
+                turn.prompt_id,
+                turn.model,
+                turn.latency_ms as i64,
+                turn.reply_chars as i64,
+                chrono::Utc::now().timestamp()
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Count a user's reaction to the reply behind a prompt. Returns false
+    /// when the reply wasn't part of an experiment.
+    pub fn record_reaction(&self, prompt_id: i64, positive: bool) -> Result<bool> {
+        self.adjust_reactions(prompt_id, positive, 1)
+    }
+
+    /// Take back a reaction counted with `record_reaction`
+    pub fn remove_reaction(&self, prompt_id: i64, positive: bool) -> Result<bool> {
+        self.adjust_reactions(prompt_id, positive, -1)
+    }
+
+    fn adjust_reactions(&self, prompt_id: i64, positive: bool, delta: i64) -> Result<bool> {
+        let column = if positive { "thumbs_up" } else { "thumbs_down" };
+        let updated = self.pool.get()?.execute(
+            &format!(
+                "UPDATE experiment_turns SET {0} = MAX({0} + ?2, 0) WHERE prompt_id = ?1",
+                column
+            ),
+            params![prompt_id, delta],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Names of recorded experiments, most recent first
+    pub fn experiments(&self) -> Result<Vec<String>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT experiment FROM experiment_turns
+             GROUP BY experiment ORDER BY MAX(id) DESC",
+        )?;
+        let names = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(names)
+    }
+
+    /// Per-variant totals of an experiment, control first
+    pub fn report(&self, experiment: &str) -> Result<Vec<VariantReport>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT variant, COUNT(*), AVG(latency_ms), MAX(latency_ms), AVG(reply_chars),
+                    SUM(thumbs_up), SUM(thumbs_down)
+             FROM experiment_turns WHERE experiment = ?1
+             GROUP BY variant ORDER BY variant",
+        )?;
+        let reports = stmt
+            .query_map(params![experiment], |row| {
+                Ok(VariantReport {
+                    variant: row.get(0)?,
+                    turns: row.get::<_, i64>(1)? as u64,
+                    avg_latency_ms: row.get(2)?,
+                    max_latency_ms: row.get::<_, i64>(3)? as u64,
+                    avg_reply_chars: row.get(4)?,
+                    thumbs_up: row.get::<_, i64>(5)? as u64,
+                    thumbs_down: row.get::<_, i64>(6)? as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(reports)
+    }
+}
+
+/// Render an experiment's report as a table
+pub fn format_experiment_report(experiment: &str, reports: &[VariantReport]) -> String {
+    let mut out = format!("Experiment: {}\n\n", experiment);
+    out.push_str(&format!(
+        "{:<10} {:>6} {:>12} {:>12} {:>10} {:>5} {:>5}\n",
+        "variant", "turns", "avg latency", "max latency", "avg chars", "👍", "👎"
+    ));
+    for report in reports {
+        out.push_str(&format!(
+            "{:<10} {:>6} {:>11.0}ms {:>11}ms {:>10.0} {:>5} {:>5}\n",
+            report.variant,
+            report.turns,
+            report.avg_latency_ms,
+            report.max_latency_ms,
+            report.avg_reply_chars,
+            report.thumbs_up,
+            report.thumbs_down
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assignment_and_report() {
+        let config = ExperimentConfig {
+            name: "sonnet".to_string(),
+            percent: 30,
+            model: Some("anthropic/claude-sonnet-4-5".to_string()),
+            instructions: None,
+        };
+        assert!(config.is_active());
+        let ids: Vec<String> = (0..1000).map(|i| format!("13{:08}", i * 7919)).collect();
+        let treated = ids
+            .iter()
+            .filter(|id| config.assign(id) == Variant::Treatment)
+            .count();
+        assert!((200..400).contains(&treated), "{} treated", treated);
+        assert_eq!(config.assign(&ids[0]), config.assign(&ids[0]));
+        assert!(
+            !ExperimentConfig {
+                percent: 0,
+                ..config.clone()
+            }
+            .is_active()
+        );
+
+        let store = ExperimentStore::open_in_memory().unwrap();
+        for (variant, prompt_id, latency_ms, reply_chars) in [
+            (Variant::Control, 1, 1000, 100),
+            (Variant::Control, 2, 3000, 300),
+            (Variant::Treatment, 3, 500, 50),
+        ] {
+            store
+                .record(&ExperimentTurn {
+                    experiment: "sonnet",
+                    variant,
+                    scope: "discord:1",
+                    prompt_id: Some(prompt_id),
+                    model: "m",
+                    latency_ms,
+                    reply_chars,
+                })
+                .unwrap();
+        }
+        assert!(store.record_reaction(3, true).unwrap());
+        assert!(store.record_reaction(1, false).unwrap());
+        assert!(store.record_reaction(1, true).unwrap());
+        assert!(store.remove_reaction(1, true).unwrap());
+        assert!(!store.record_reaction(99, true).unwrap());
+
+        assert_eq!(store.experiments().unwrap(), vec!["sonnet"]);
+        let reports = store.report("sonnet").unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].variant, "control");
+        assert_eq!(reports[0].turns, 2);
+        assert_eq!(reports[0].avg_latency_ms, 2000.0);
+        assert_eq!(reports[0].thumbs_down, 1);
+        assert_eq!(reports[1].max_latency_ms, 500);
+        assert_eq!(reports[1].thumbs_up, 1);
+        assert!(format_experiment_report("sonnet", &reports).contains("treatment"));
+    }
+}
@@ -0,0 +1,421 @@
+//! Session export to markdown and standalone HTML
+//!
+//! Renders a session with speaker names, timestamps and tool-call
+//! annotations. Used by the CLI `/export` command, the HTTP API, the desktop
+//! Sessions view and the Discord `/export` command.
+
+use chrono::{Local, TimeZone};
+use std::collections::HashMap;
+
+use super::providers::Role;
+use super::session::{Session, SessionMessage};
+
+/// Maximum characters of a tool result included in an export
+const TOOL_RESULT_MAX_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+}
+
+impl ExportFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            "html" | "htm" => Some(ExportFormat::Html),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "text/markdown; charset=utf-8",
+            ExportFormat::Html => "text/html; charset=utf-8",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub title: String,
+    pub model: Option<String>,
+    pub user_label: String,
+    pub assistant_label: String,
+    /// Include tool calls and (truncated) tool results
+    pub include_tools: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            title: "LocalGPT Session Export".to_string(),
+            model: None,
+            user_label: "User".to_string(),
+            assistant_label: "Assistant".to_string(),
+            include_tools: true,
+        }
+    }
+}
+
+/// One rendered entry of the transcript
+enum Entry<'a> {
+    Message {
+        speaker: &'a str,
+        time: Option<String>,
+        content: &'a str,
+        tool_calls: Vec<(&'a str, &'a str)>,
+    },
+    ToolResult {
+        tool: &'a str,
+        output: String,
+    },
+}
+
+/// Render a session in the given format
+pub fn export_session(session: &Session, format: ExportFormat, opts: &ExportOptions) -> String {
+    let entries = collect_entries(session.raw_messages(), opts);
+    let created = session
+        .created_at()
+        .with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string();
+
+    match format {
+        ExportFormat::Markdown => render_markdown(session.id(), &created, &entries, opts),
+        ExportFormat::Html => render_html(session.id(), &created, &entries, opts),
+    }
+}
+
+/// Suggested file name for an exported session
+pub fn export_file_name(session: &Session, format: ExportFormat) -> String {
+    format!(
+        "session-{}-{}.{}",
+        session.created_at().with_timezone(&Local).format("%Y%m%d"),
+        session.id().chars().take(8).collect::<String>(),
+        format.extension()
+    )
+}
+
+fn collect_entries<'a>(messages: &'a [SessionMessage], opts: &'a ExportOptions) -> Vec<Entry<'a>> {
+    // Tool results only carry the call id; map it back to the tool name
+    let mut tool_names: HashMap<&str, &str> = HashMap::new();
+    let mut entries = Vec::new();
+
+    for sm in messages {
+        let msg = &sm.message;
+        match msg.role {
+            Role::System => {}
+            Role::User | Role::Assistant => {
+                let tool_calls: Vec<(&str, &str)> = msg
+                    .tool_calls
+                    .iter()
+                    .flatten()
+                    .map(|tc| {
+                        tool_names.insert(tc.id.as_str(), tc.name.as_str());
+                        (tc.name.as_str(), tc.arguments.as_str())
+                    })
+                    .collect();
+
+                if msg.content.trim().is_empty() && (tool_calls.is_empty() || !opts.include_tools) {
+                    continue;
+                }
+
+                entries.push(Entry::Message {
+                    speaker: if msg.role == Role::User {
+                        &opts.user_label
+                    } else {
+                        &opts.assistant_label
+                    },
+                    time: format_timestamp(sm.timestamp),
+                    content: msg.content.trim(),
+                    tool_calls: if opts.include_tools {
+                        tool_calls
+                    } else {
+                        Vec::new()
+                    },
+                });
+            }
+            Role::Tool if opts.include_tools => {
+                let tool = msg
+                    .tool_call_id
+                    .as_deref()
+                    .and_then(|id| tool_names.get(id).copied())
+                    .unwrap_or("tool");
+                let (output, _) = super::sanitize::truncate_with_notice(
+                    msg.content.trim(),
+                    TOOL_RESULT_MAX_CHARS,
+                );
+                entries.push(Entry::ToolResult { tool, output });
+            }
+            Role::Tool => {}
+        }
+    }
+
+    entries
+}
+
+fn format_timestamp(ts_millis: u64) -> Option<String> {
+    if ts_millis == 0 {
+        return None;
+    }
+    Local
+        .timestamp_millis_opt(ts_millis as i64)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+}
+
+fn render_markdown(id: &str, created: &str, entries: &[Entry], opts: &ExportOptions) -> String {
+    let mut out = format!("# {}\n\n", opts.title);
+    out.push_str(&format!("- Session: `{}`\n", id));
+    out.push_str(&format!("- Created: {}\n", created));
+    if let Some(ref model) = opts.model {
+        out.push_str(&format!("- Model: {}\n", model));
+    }
+    out.push_str("\n---\n\n");
+
+    for entry in entries {
+        match entry {
+            Entry::Message {
+                speaker,
+                time,
+                content,
+                tool_calls,
+            } => {
+                match time {
+                    Some(t) => out.push_str(&format!("### {} · {}\n\n", speaker, t)),
+                    None => out.push_str(&format!("### {}\n\n", speaker)),
+                }
+                if !content.is_empty() {
+                    out.push_str(content);
+                    out.push_str("\n\n");
+                }
+                for (name, args) in tool_calls {
+                    out.push_str(&format!("> 🔧 **{}** `{}`\n\n", name, args));
+                }
+            }
+            Entry::ToolResult { tool, output } => {
+                let fence = code_fence(output);
+                out.push_str(&format!(
+                    "<details><summary>Tool result: {}</summary>\n\n{}\n{}\n{}\n\n</details>\n\n",
+                    tool, fence, output, fence
+                ));
+            }
+        }
+    }
+
+    out
+}
+
+/// A backtick fence longer than any backtick run in `content`, so output that
+/// contains its own code blocks can't close the fence early.
+fn code_fence(content: &str) -> String {
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn render_html(id: &str, created: &str, entries: &[Entry], opts: &ExportOptions) -> String {
+    let mut body = String::new();
+
+    for entry in entries {
+        match entry {
+            Entry::Message {
+                speaker,
+                time,
+                content,
+                tool_calls,
+            } => {
+                let class = if *speaker == opts.user_label {
+                    "user"
+                } else {
+                    "assistant"
+                };
+                body.push_str(&format!("<div class=\"msg {}\">\n", class));
+                body.push_str(&format!(
+                    "<div class=\"meta\"><strong>{}</strong>{}</div>\n",
+                    escape_html(speaker),
+                    time.as_ref()
+                        .map(|t| format!(" <span class=\"time\">{}</span>", escape_html(t)))
+                        .unwrap_or_default()
+                ));
+                if !content.is_empty() {
+                    body.push_str(&format!(
+                        "<div class=\"content\">{}</div>\n",
+                        escape_html(content)
+                    ));
+                }
+                for (name, args) in tool_calls {
+                    body.push_str(&format!(
+                        "<div class=\"tool-call\">🔧 <strong>{}</strong> <code>{}</code></div>\n",
+                        escape_html(name),
+                        escape_html(args)
+                    ));
+                }
+                body.push_str("</div>\n");
+            }
+            Entry::ToolResult { tool, output } => {
+                body.push_str(&format!(
+                    "<details class=\"tool-result\"><summary>Tool result: {}</summary><pre>{}</pre></details>\n",
+                    escape_html(tool),
+                    escape_html(output)
+                ));
+            }
+        }
+    }
+
+    let model = opts
+        .model
+        .as_ref()
+        .map(|m| format!(" · Model: {}", escape_html(m)))
+        .unwrap_or_default();
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; max-width: 820px; margin: 2em auto; padding: 0 1em; color: #222; }}
+header {{ border-bottom: 1px solid #ddd; margin-bottom: 1.5em; }}
+header p {{ color: #666; font-size: 0.9em; }}
+.msg {{ margin: 1em 0; padding: 0.75em 1em; border-radius: 8px; }}
+.msg.user {{ background: #eef4ff; }}
+.msg.assistant {{ background: #f6f6f6; }}
+.meta {{ margin-bottom: 0.4em; }}
+.time {{ color: #888; font-size: 0.85em; margin-left: 0.5em; }}
+.content {{ white-space: pre-wrap; line-height: 1.5; }}
+.tool-call {{ margin-top: 0.5em; font-size: 0.9em; color: #555; }}
+.tool-result {{ margin: 0.5em 0 0.5em 1em; font-size: 0.9em; }}
+pre {{ white-space: pre-wrap; background: #fafafa; border: 1px solid #eee; padding: 0.5em; }}
+</style>
+</head>
+<body>
+<header>
+<h1>{title}</h1>
+<p>Session <code>{id}</code> · Created {created}{model}</p>
+</header>
+{body}</body>
+</html>
+"#,
+        title = escape_html(&opts.title),
+        id = escape_html(id),
+        created = escape_html(created),
+        model = model,
+        body = body,
+    )
+}
+
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::providers::{Message, ToolCall};
+
+    fn sample_session() -> Session {
+        let mut session = Session::new_with_cwd("/tmp".to_string());
+        session.add_message(Message {
+            role: Role::User,
+            content: "List my files <please>".to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        });
+        session.add_message(Message {
+            role: Role::Assistant,
+            content: String::new(),
+            tool_calls: Some(vec![ToolCall {
+                id: "call_1".to_string(),
+                name: "bash".to_string(),
+                arguments: r#"{"command":"ls"}"#.to_string(),
+            }]),
+            tool_call_id: None,
+            images: Vec::new(),
+        });
+        session.add_message(Message {
+            role: Role::Tool,
+            content: "notes.md".to_string(),
+            tool_calls: None,
+            tool_call_id: Some("call_1".to_string()),
+            images: Vec::new(),
+        });
+        session.add_message(Message {
+            role: Role::Assistant,
+            content: "You have one file.".to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        });
+        session
+    }
+
+    #[test]
+    fn test_markdown_export() {
+        let md = export_session(
+            &sample_session(),
+            ExportFormat::Markdown,
+            &ExportOptions::default(),
+        );
+        assert!(md.starts_with("# LocalGPT Session Export"));
+        assert!(md.contains("### User · "));
+        assert!(md.contains("> 🔧 **bash** `{\"command\":\"ls\"}`"));
+        assert!(md.contains("Tool result: bash"));
+        assert!(md.contains("You have one file."));
+    }
+
+    #[test]
+    fn test_markdown_fence_outlasts_backticks_in_output() {
+        let mut session = sample_session();
+        session.add_message(Message {
+            role: Role::Tool,
+            content: "README:\n````rust\nfn main() {}\n````".to_string(),
+            tool_calls: None,
+            tool_call_id: Some("call_1".to_string()),
+            images: Vec::new(),
+        });
+        let md = export_session(&session, ExportFormat::Markdown, &ExportOptions::default());
+        assert!(md.contains("```\nnotes.md\n```\n"));
+        assert!(md.contains("`````\nREADME:\n````rust\nfn main() {}\n````\n`````\n"));
+        assert_eq!(code_fence("no ticks"), "```");
+    }
+
+    #[test]
+    fn test_html_export_escapes_content() {
+        let opts = ExportOptions {
+            include_tools: false,
+            ..Default::default()
+        };
+        let html = export_session(&sample_session(), ExportFormat::Html, &opts);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("List my files &lt;please&gt;"));
+        assert!(!html.contains("Tool result"));
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(ExportFormat::parse("HTML"), Some(ExportFormat::Html));
+        assert_eq!(ExportFormat::parse("md"), Some(ExportFormat::Markdown));
+        assert_eq!(ExportFormat::parse("pdf"), None);
+    }
+}
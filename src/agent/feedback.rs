@@ -0,0 +1,287 @@
+//! Reaction feedback
+//!
+//! 👍/👎 reactions on the bot's replies are kept in
+//! `~/.localgpt/feedback.sqlite` together with the prompt behind the reply:
+//! its ID in the prompt store, a hash of the messages sent to the model (so
+//! feedback can still be grouped after the prompt record has rotated out),
+//! the request and the response. `GET /api/feedback` aggregates them per
+//! scope. With `agent.feedback_examples`, the most recent 👎 replies are
+//! listed in the system prompt as examples to avoid.
+
+use anyhow::Result;
+use rusqlite::{OptionalExtension, params};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+use super::get_state_dir;
+use super::providers::{Message, Role};
+use super::sanitize::EXTERNAL_CONTENT_END;
+use crate::db::SqlitePool;
+
+/// Longest request and response shown in the system prompt (characters)
+const MAX_EXAMPLE_CHARS: usize = 300;
+
+/// One reaction on a reply
+#[derive(Debug, Clone)]
+pub struct Feedback<'a> {
+    /// The reply that was reacted to
+    pub message_id: &'a str,
+    pub user_id: &'a str,
+    pub positive: bool,
+    pub prompt_id: i64,
+    pub prompt_hash: &'a str,
+    /// Where the reply was sent, e.g. `discord:<channel_id>`
+    pub scope: &'a str,
+    pub request: &'a str,
+    pub response: &'a str,
+}
+
+/// Reactions in one scope
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedbackSummary {
+    pub scope: String,
+    pub positive: u64,
+    pub negative: u64,
+}
+
+/// A reply users disliked
+#[derive(Debug, Clone)]
+pub struct NegativeExample {
+    pub request: String,
+    pub response: String,
+}
+
+#[derive(Clone)]
+pub struct FeedbackStore {
+    pool: SqlitePool,
+}
+
+impl FeedbackStore {
+    /// Open the shared database at `~/.localgpt/feedback.sqlite`
+    pub fn open_default() -> Result<Self> {
+        Self::open(&get_state_dir()?.join("feedback.sqlite"))
+    }
+
+    pub fn open(db_path: &Path) -> Result<Self> {
+        Self::init(SqlitePool::shared(db_path, None)?)
+    }
+
+    /// In-memory store (tests)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(SqlitePool::open_in_memory(None)?)
+    }
+
+    fn init(pool: SqlitePool) -> Result<Self> {
+        pool.get()?.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS feedback (
+                message_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                positive INTEGER NOT NULL,
+                prompt_id INTEGER NOT NULL,
+                prompt_hash TEXT NOT NULL,
+                scope TEXT NOT NULL,
+                request TEXT NOT NULL,
+                response TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (message_id, user_id, positive)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_feedback_created ON feedback(created_at);
+            "#,
+        )?;
+
+        Ok(Self { pool })
+    }
+
+    /// Store a reaction. Returns false if the user had already given it.
+    pub fn record(&self, feedback: &Feedback) -> Result<bool> {
+        let inserted = self.pool.get()?.execute(
+            "INSERT OR IGNORE INTO feedback
+                 (message_id, user_id, positive, prompt_id, prompt_hash, scope, request, response, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                feedback.message_id,
+                feedback.user_id,
+                feedback.positive,
+                feedback.prompt_id,
+                feedback.prompt_hash,
+                feedback.scope,
+                feedback.request,
+                feedback.response,
+                chrono::Utc::now().timestamp()
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// Take back a reaction. Returns the prompt it was about, if it was
+    /// recorded.
+    pub fn remove(&self, message_id: &str, user_id: &str, positive: bool) -> Result<Option<i64>> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        let prompt_id = tx
+            .query_row(
+                "SELECT prompt_id FROM feedback
+                 WHERE message_id = ?1 AND user_id = ?2 AND positive = ?3",
+                params![message_id, user_id, positive],
+                |row| row.get(0),
+            )
+            .optional()?;
+        tx.execute(
+            "DELETE FROM feedback WHERE message_id = ?1 AND user_id = ?2 AND positive = ?3",
+            params![message_id, user_id, positive],
+        )?;
+        tx.commit()?;
+        Ok(prompt_id)
+    }
+
+    /// Reactions per scope since a Unix timestamp
+    pub fn summary(&self, since: i64) -> Result<Vec<FeedbackSummary>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT scope, SUM(positive), SUM(1 - positive) FROM feedback
+             WHERE created_at >= ?1 GROUP BY scope ORDER BY COUNT(*) DESC",
+        )?;
+        let summaries = stmt
+            .query_map(params![since], |row| {
+                Ok(FeedbackSummary {
+                    scope: row.get(0)?,
+                    positive: row.get::<_, i64>(1)? as u64,
+                    negative: row.get::<_, i64>(2)? as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(summaries)
+    }
+
+    /// The most recent disliked replies, one per prompt
+    pub fn recent_negative(&self, limit: usize) -> Result<Vec<NegativeExample>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT request, response FROM feedback
+             WHERE positive = 0 GROUP BY prompt_hash
+             ORDER BY MAX(created_at) DESC LIMIT ?1",
+        )?;
+        let examples = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok(NegativeExample {
+                    request: row.get(0)?,
+                    response: row.get(1)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(examples)
+    }
+}
+
+/// Hash of the messages sent to the model
+pub fn prompt_hash(messages: &[Message]) -> String {
+    let mut hasher = Sha256::new();
+    for message in messages {
+        hasher.update(serde_json::to_string(message).unwrap_or_default());
+        hasher.update(b"\n");
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// The last user message of a prompt, i.e. what the reply answered,
+/// without the external content (channel history) placed before it
+pub fn prompt_request(messages: &[Message]) -> &str {
+    let content = messages
+        .iter()
+        .rev()
+        .find(|m| m.role == Role::User)
+        .map(|m| m.content.as_str())
+        .unwrap_or_default();
+    match content.rsplit_once(EXTERNAL_CONTENT_END) {
+        Some((_, request)) => request.trim(),
+        None => content,
+    }
+}
+
+/// System prompt section listing disliked replies
+pub(super) fn avoid_section(examples: &[NegativeExample]) -> Option<String> {
+    if examples.is_empty() {
+        return None;
+    }
+    let mut lines = vec![
+        "## Replies Users Disliked".to_string(),
+        "Users reacted 👎 to these recent replies. Avoid responses like them.".to_string(),
+    ];
+    for example in examples {
+        lines.push(format!(
+            "- Request: {}\n  Reply: {}",
+            excerpt(&example.request),
+            excerpt(&example.response)
+        ));
+    }
+    Some(lines.join("\n"))
+}
+
+fn excerpt(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > MAX_EXAMPLE_CHARS {
+        let cut: String = text.chars().take(MAX_EXAMPLE_CHARS).collect();
+        format!("{}…", cut)
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feedback<'a>(message_id: &'a str, user_id: &'a str, positive: bool) -> Feedback<'a> {
+        Feedback {
+            message_id,
+            user_id,
+            positive,
+            prompt_id: 7,
+            prompt_hash: message_id,
+            scope: "discord:1",
+            request: "What's the weather?",
+            response: "I can't\nsay.",
+        }
+    }
+
+    #[test]
+    fn test_record_summarize_and_remove() {
+        let store = FeedbackStore::open_in_memory().unwrap();
+        assert!(store.record(&feedback("r1", "alice", false)).unwrap());
+        assert!(!store.record(&feedback("r1", "alice", false)).unwrap());
+        assert!(store.record(&feedback("r1", "bob", true)).unwrap());
+        assert!(store.record(&feedback("r2", "bob", true)).unwrap());
+
+        let summary = store.summary(0).unwrap();
+        assert_eq!(summary.len(), 1);
+        assert_eq!((summary[0].positive, summary[0].negative), (2, 1));
+        assert!(store.summary(i64::MAX).unwrap().is_empty());
+
+        let examples = store.recent_negative(5).unwrap();
+        assert_eq!(examples.len(), 1);
+        let section = avoid_section(&examples).unwrap();
+        assert!(section.contains("Reply: I can't say."));
+        assert!(avoid_section(&[]).is_none());
+
+        assert_eq!(store.remove("r1", "alice", false).unwrap(), Some(7));
+        assert_eq!(store.remove("r1", "alice", false).unwrap(), None);
+        assert!(store.recent_negative(5).unwrap().is_empty());
+
+        let messages = vec![Message {
+            role: Role::User,
+            content: format!(
+                "{}\n[bob 12:34] earlier\n{}\n\nIs it raining?",
+                crate::agent::EXTERNAL_CONTENT_START,
+                EXTERNAL_CONTENT_END
+            ),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        }];
+        assert_eq!(prompt_request(&messages), "Is it raining?");
+        assert_eq!(prompt_hash(&messages).len(), 64);
+    }
+}
@@ -0,0 +1,278 @@
+//! Prompt inspection
+//!
+//! The messages sent to the model for a reply (system prompt, injected
+//! memories and environment, tool outputs, security block) are kept in
+//! `~/.localgpt/prompts.sqlite`, keyed by the IDs of the messages that were
+//! answered. `/inspect` in Discord and `GET /api/inspect/{message_id}` show
+//! them, so a bad reply can be traced to what the model actually saw.
+//! Only the most recent [`MAX_RECORDS`] prompts are kept.
+
+use anyhow::Result;
+use rusqlite::{OptionalExtension, Row, params};
+use serde::Serialize;
+use std::path::Path;
+
+use super::get_state_dir;
+use super::providers::{Message, Role};
+use crate::db::SqlitePool;
+
+/// Older prompts are dropped when a new one is recorded
+const MAX_RECORDS: i64 = 200;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptRecord {
+    pub id: i64,
+    /// Where the reply was sent, e.g. `discord:<channel_id>` or `http:<session_id>`
+    pub scope: String,
+    pub model: String,
+    pub messages: Vec<Message>,
+    pub response: String,
+    pub created_at: i64,
+}
+
+#[derive(Clone)]
+pub struct PromptStore {
+    pool: SqlitePool,
+}
+
+impl PromptStore {
+    /// Open the shared prompt database at `~/.localgpt/prompts.sqlite`
+    pub fn open_default() -> Result<Self> {
+        Self::open(&get_state_dir()?.join("prompts.sqlite"))
+    }
+
+    pub fn open(db_path: &Path) -> Result<Self> {
+        Self::init(SqlitePool::shared(db_path, None)?)
+    }
+
+    /// In-memory store (tests)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(SqlitePool::open_in_memory(None)?)
+    }
+
+    fn init(pool: SqlitePool) -> Result<Self> {
+        pool.get()?.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS prompts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                scope TEXT NOT NULL,
+                model TEXT NOT NULL,
+                messages TEXT NOT NULL,
+                response TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS prompt_keys (
+                message_id TEXT PRIMARY KEY,
+                prompt_id INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_prompts_scope ON prompts(scope);
+            "#,
+        )?;
+
+        Ok(Self { pool })
+    }
+
+    /// Store the prompt behind a reply under the IDs of the answered messages
+    pub fn record(
+        &self,
+        scope: &str,
+        message_ids: &[String],
+        model: &str,
+        messages: &[Message],
+        response: &str,
+    ) -> Result<i64> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "INSERT INTO prompts (scope, model, messages, response, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                scope,
+                model,
+                serde_json::to_string(messages)?,
+                response,
+                chrono::Utc::now().timestamp()
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+
+        for message_id in message_ids {
+            tx.execute(
+                "INSERT OR REPLACE INTO prompt_keys (message_id, prompt_id) VALUES (?1, ?2)",
+                params![message_id, id],
+            )?;
+        }
+
+        tx.execute(
+            "DELETE FROM prompts WHERE id <= ?1",
+            params![id - MAX_RECORDS],
+        )?;
+        tx.execute(
+            "DELETE FROM prompt_keys WHERE prompt_id NOT IN (SELECT id FROM prompts)",
+            [],
+        )?;
+
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// Key an existing prompt under more message IDs, e.g. the bot's replies
+    pub fn add_keys(&self, prompt_id: i64, message_ids: &[String]) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        for message_id in message_ids {
+            tx.execute(
+                "INSERT OR REPLACE INTO prompt_keys (message_id, prompt_id) VALUES (?1, ?2)",
+                params![message_id, prompt_id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// The prompt behind the reply to a message (or behind the reply itself)
+    pub fn get(&self, message_id: &str) -> Result<Option<PromptRecord>> {
+        let conn = self.pool.get()?;
+        let record = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM prompts
+                     WHERE id = (SELECT prompt_id FROM prompt_keys WHERE message_id = ?1)",
+                    COLUMNS
+                ),
+                params![message_id],
+                row_to_record,
+            )
+            .optional()?;
+        Ok(record)
+    }
+
+    /// The most recent prompt in a scope
+    pub fn latest(&self, scope: &str) -> Result<Option<PromptRecord>> {
+        let conn = self.pool.get()?;
+        let record = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM prompts WHERE scope = ?1 ORDER BY id DESC LIMIT 1",
+                    COLUMNS
+                ),
+                params![scope],
+                row_to_record,
+            )
+            .optional()?;
+        Ok(record)
+    }
+}
+
+const COLUMNS: &str = "id, scope, model, messages, response, created_at";
+
+fn row_to_record(row: &Row) -> rusqlite::Result<PromptRecord> {
+    let messages: String = row.get(3)?;
+    Ok(PromptRecord {
+        id: row.get(0)?,
+        scope: row.get(1)?,
+        model: row.get(2)?,
+        messages: serde_json::from_str(&messages).unwrap_or_default(),
+        response: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+/// Render a prompt as Markdown, one section per message
+pub fn format_prompt_record(record: &PromptRecord) -> String {
+    let time = chrono::DateTime::from_timestamp(record.created_at, 0)
+        .map(|t| {
+            t.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_default();
+    let mut out = format!(
+        "# Prompt #{}\n\n- Scope: {}\n- Model: {}\n- Time: {}\n- Messages: {}\n",
+        record.id,
+        record.scope,
+        record.model,
+        time,
+        record.messages.len()
+    );
+
+    for (i, message) in record.messages.iter().enumerate() {
+        let role = match message.role {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::Tool => "tool",
+        };
+        out.push_str(&format!("\n## {}. {}\n\n", i + 1, role));
+        if !message.content.is_empty() {
+            out.push_str(&message.content);
+            out.push('\n');
+        }
+        for call in message.tool_calls.iter().flatten() {
+            out.push_str(&format!("\n→ {}({})\n", call.name, call.arguments));
+        }
+        if !message.images.is_empty() {
+            out.push_str(&format!("\n[{} image(s)]\n", message.images.len()));
+        }
+    }
+
+    out.push_str(&format!("\n## Response\n\n{}\n", record.response));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: Role, content: &str) -> Message {
+        Message {
+            role,
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_record_and_lookup() {
+        let store = PromptStore::open_in_memory().unwrap();
+        let messages = vec![
+            message(Role::System, "You are helpful."),
+            message(Role::User, "Is it raining?"),
+        ];
+        let ids = vec!["m1".to_string(), "m2".to_string()];
+        store
+            .record("discord:c1", &ids, "openai/gpt-4o", &messages, "No.")
+            .unwrap();
+
+        let record = store.get("m2").unwrap().unwrap();
+        assert_eq!(record.messages.len(), 2);
+        assert_eq!(record.response, "No.");
+        assert_eq!(store.latest("discord:c1").unwrap().unwrap().id, record.id);
+        assert!(store.get("m3").unwrap().is_none());
+        assert!(store.latest("discord:c2").unwrap().is_none());
+
+        store.add_keys(record.id, &["r1".to_string()]).unwrap();
+        assert_eq!(store.get("r1").unwrap().unwrap().id, record.id);
+
+        let text = format_prompt_record(&record);
+        assert!(text.contains("## 2. user\n\nIs it raining?"));
+        assert!(text.ends_with("## Response\n\nNo.\n"));
+    }
+
+    #[test]
+    fn test_old_records_are_dropped() {
+        let store = PromptStore::open_in_memory().unwrap();
+        for i in 0..=MAX_RECORDS {
+            store
+                .record("http:s", &[format!("m{}", i)], "m", &[], "")
+                .unwrap();
+        }
+        assert!(store.get("m0").unwrap().is_none());
+        assert!(store.get("m1").unwrap().is_some());
+    }
+}
@@ -0,0 +1,140 @@
+//! Prompt localization
+//!
+//! `agent.language` selects the language of the built-in prompt fragments
+//! (tool call guidance, the heartbeat prompt, error apologies) and of the
+//! persona: `SOUL.<code>.md` (e.g. `SOUL.ja.md`) is used instead of
+//! `SOUL.md` when it exists. With `agent.detect_language` a conversation
+//! switches to the language of its first message. Languages without
+//! translations fall back to English.
+
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+    Japanese,
+}
+
+/// Built-in text that has translations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fragment {
+    /// When to narrate tool calls (system prompt)
+    ToolCallStyle,
+    /// Heartbeat poll; `{git}` and `{ok}` are filled in
+    HeartbeatPrompt,
+    /// Appended to the heartbeat prompt in git workspaces
+    HeartbeatGitInstruction,
+    /// Reply when a request failed unexpectedly
+    ErrorApology,
+    /// Which language to answer in (empty for English)
+    ReplyLanguage,
+}
+
+impl Language {
+    /// Parse a language code or name ("ja", "ja-JP", "japanese")
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        let code = value.split(['-', '_']).next().unwrap_or_default();
+        match code {
+            "en" | "english" => Some(Language::English),
+            "ja" | "jp" | "japanese" | "日本語" => Some(Language::Japanese),
+            _ => None,
+        }
+    }
+
+    /// The configured language, English if it has no translations
+    pub fn from_config(value: &str) -> Self {
+        Self::parse(value).unwrap_or_else(|| {
+            warn!("No translations for language {:?}, using English", value);
+            Language::English
+        })
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Japanese => "ja",
+        }
+    }
+
+    /// Guess the language of a user message. Kana means Japanese; mostly
+    /// Latin letters mean English.
+    pub fn detect(text: &str) -> Option<Self> {
+        if text.chars().any(|c| matches!(c, '\u{3040}'..='\u{30ff}')) {
+            return Some(Language::Japanese);
+        }
+        let letters = text.chars().filter(|c| c.is_alphabetic()).count();
+        let latin = text.chars().filter(|c| c.is_ascii_alphabetic()).count();
+        (latin >= 3 && latin * 2 > letters).then_some(Language::English)
+    }
+
+    pub fn text(&self, fragment: Fragment) -> &'static str {
+        match (self, fragment) {
+            (Language::English, Fragment::ToolCallStyle) => {
+                "Default: do not narrate routine, low-risk tool calls (just call the tool).\n\
+                 Narrate only when it helps: multi-step work, complex problems, sensitive actions \
+                 (e.g., deletions), or when the user explicitly asks.\n\
+                 Keep narration brief and value-dense."
+            }
+            (Language::English, Fragment::HeartbeatPrompt) => {
+                "Read HEARTBEAT.md if it exists. Follow it strictly. \
+                 Mark completed tasks with [x] — do NOT delete or clear tasks. \
+                 Do not infer or repeat old tasks from prior chats.{git} \
+                 If nothing needs attention, reply {ok}."
+            }
+            (Language::English, Fragment::HeartbeatGitInstruction) => {
+                " After completing tasks that modify files, commit the changes with a descriptive message."
+            }
+            (Language::English, Fragment::ErrorApology) => "Sorry, I encountered an error.",
+            (Language::English, Fragment::ReplyLanguage) => "",
+            (Language::Japanese, Fragment::ToolCallStyle) => {
+                "既定: 日常的でリスクの低いツール呼び出しは説明せず、そのまま呼び出してください。\n\
+                 説明するのは役に立つときだけにしてください: 複数ステップの作業、複雑な問題、\
+                 削除などの慎重を要する操作、またはユーザーが明示的に求めたとき。\n\
+                 説明は短く、要点を絞ってください。"
+            }
+            (Language::Japanese, Fragment::HeartbeatPrompt) => {
+                "HEARTBEAT.md があれば読み、その内容に厳密に従ってください。\
+                 完了したタスクには [x] を付け、タスクを削除・消去しないでください。\
+                 過去のチャットから古いタスクを推測したり繰り返したりしないでください。{git}\
+                 対応が必要なものがなければ {ok} とだけ返信してください。"
+            }
+            (Language::Japanese, Fragment::HeartbeatGitInstruction) => {
+                "ファイルを変更するタスクを終えたら、内容がわかるメッセージで変更をコミットしてください。"
+            }
+            (Language::Japanese, Fragment::ErrorApology) => "すみません、エラーが発生しました。",
+            (Language::Japanese, Fragment::ReplyLanguage) => {
+                "日本語で返信してください。ユーザーが別の言語で書いた場合はその言語で返信してください。"
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_detect() {
+        assert_eq!(Language::parse("ja-JP"), Some(Language::Japanese));
+        assert_eq!(Language::parse("EN"), Some(Language::English));
+        assert_eq!(Language::parse("fr"), None);
+        assert_eq!(Language::from_config("fr"), Language::English);
+
+        assert_eq!(
+            Language::detect("明日の予定を教えて"),
+            Some(Language::Japanese)
+        );
+        assert_eq!(
+            Language::detect("What's on tomorrow?"),
+            Some(Language::English)
+        );
+        assert_eq!(Language::detect("👍"), None);
+        assert!(
+            Language::Japanese
+                .text(Fragment::ReplyLanguage)
+                .contains("日本語")
+        );
+    }
+}
@@ -1,15 +1,46 @@
+mod batch;
+mod delegates;
+mod environment;
+mod experiment;
+mod export;
+mod feedback;
+mod inspect;
+mod locale;
+mod postprocess;
 mod providers;
+mod recall;
+mod reserve;
+mod resilience;
 mod sanitize;
+#[cfg(feature = "screenshot")]
+mod screenshot;
 mod session;
 mod session_store;
 mod skills;
 mod system_prompt;
+mod tool_loop;
 mod tools;
 
+pub use batch::summarize_batch;
+pub use delegates::{DelegateAgent, load_registry as load_delegate_agents, parse_agents_md};
+pub use environment::ContextProvider;
+pub use experiment::{
+    ExperimentStore, ExperimentTurn, Variant, VariantReport, format_experiment_report,
+};
+pub use export::{ExportFormat, ExportOptions, export_file_name, export_session};
+pub use feedback::{
+    Feedback, FeedbackStore, FeedbackSummary, NegativeExample, prompt_hash, prompt_request,
+};
+pub use inspect::{PromptRecord, PromptStore, format_prompt_record};
+pub use locale::{Fragment, Language};
+pub use postprocess::{ChannelKind, ReplyPipeline, ReplyStage};
 pub use providers::{
     ImageAttachment, LLMProvider, LLMResponse, LLMResponseContent, Message, Role, StreamChunk,
-    StreamEvent, StreamResult, ToolCall, ToolSchema, Usage,
+    StreamEvent, StreamResult, ToolCall, ToolSchema, Usage, create_provider,
 };
+pub use recall::{build_recall_context, build_recall_query};
+pub use reserve::{CompletionStats, DEFAULT_RESERVE_SCOPE, ReserveStore};
+pub use resilience::{CircuitState, CircuitStatus, circuit_statuses};
 pub use sanitize::{
     EXTERNAL_CONTENT_END, EXTERNAL_CONTENT_START, MEMORY_CONTENT_END, MEMORY_CONTENT_START,
     MemorySource, SanitizeResult, TOOL_OUTPUT_END, TOOL_OUTPUT_START, detect_suspicious_patterns,
@@ -17,22 +48,26 @@ pub use sanitize::{
     wrap_tool_output,
 };
 pub use session::{
-    DEFAULT_AGENT_ID, Session, SessionInfo, SessionMessage, SessionSearchResult, SessionStatus,
-    get_last_session_id, get_last_session_id_for_agent, get_sessions_dir_for_agent, get_state_dir,
-    list_sessions, list_sessions_for_agent, search_sessions, search_sessions_for_agent,
+    DEFAULT_AGENT_ID, ModelChange, Session, SessionInfo, SessionMessage, SessionSearchResult,
+    SessionStatus, get_last_session_id, get_last_session_id_for_agent, get_sessions_dir_for_agent,
+    get_state_dir, list_sessions, list_sessions_for_agent, search_sessions,
+    search_sessions_for_agent,
 };
 pub use session_store::{SessionEntry, SessionStore};
 pub use skills::{Skill, SkillInvocation, get_skills_summary, load_skills, parse_skill_command};
 pub use system_prompt::{
-    HEARTBEAT_OK_TOKEN, SILENT_REPLY_TOKEN, build_heartbeat_prompt, is_heartbeat_ok,
-    is_silent_reply,
+    HEARTBEAT_OK_TOKEN, SILENT_REPLY_TOKEN, SystemPromptParams, build_heartbeat_prompt,
+    build_system_prompt, is_heartbeat_ok, is_silent_reply,
 };
-pub use tools::{Tool, ToolResult, extract_tool_detail};
+pub use tools::{JobProgressTool, Tool, ToolResult, extract_tool_detail};
 
 use anyhow::Result;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tracing::{debug, info};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+use tool_loop::{REPEATED_CALL_OUTPUT, ToolLoopGuard, wrap_up_prompt};
 
 /// Clean up Claude CLI session files for this workspace.
 /// Claude CLI stores sessions in ~/.claude/projects/<workspace-path-with-slashes-as-dashes>/
@@ -88,7 +123,8 @@ pub fn clean_claude_cli_sessions(workspace: &std::path::Path) {
 }
 
 use crate::config::Config;
-use crate::memory::{MemoryChunk, MemoryManager};
+use crate::jobs::{JobOrigin, JobStore};
+use crate::memory::{ContextReloadEvent, LogContext, LogOrigin, MemoryChunk, MemoryManager};
 
 /// Soft threshold buffer before compaction (tokens)
 /// Memory flush runs when within this buffer of the hard limit
@@ -117,6 +153,10 @@ fn generate_slug(text: &str) -> String {
         .collect()
 }
 
+/// Told about the tool calls of non-streaming turns, with the events a
+/// stream would yield for them
+pub type ToolObserver = Arc<dyn Fn(&StreamEvent) + Send + Sync>;
+
 #[derive(Debug, Clone)]
 pub struct AgentConfig {
     pub model: String,
@@ -135,8 +175,32 @@ pub struct Agent {
     cumulative_usage: Usage,
     /// Verified security policy content (None if missing, unsigned, or tampered)
     verified_security_policy: Option<String>,
-    /// Last known modification time of SOUL.md for dynamic reload
-    soul_last_modified: Option<std::time::SystemTime>,
+    /// Memory snippets recalled for the current turn (synthetic, not persisted)
+    recall_context: Option<String>,
+    /// Workspace context file change notifications
+    context_reloads: broadcast::Receiver<ContextReloadEvent>,
+    /// Tools the current requester may not use (calls are refused)
+    denied_tools: Vec<String>,
+    /// Channel-specific instructions sent with every turn (synthetic, not persisted)
+    turn_instructions: Option<String>,
+    /// Providers of the per-turn environment block (weather, ...)
+    context_providers: Vec<Arc<dyn ContextProvider>>,
+    /// Environment block for the current turn (synthetic, not persisted)
+    environment_context: Option<String>,
+    /// Where jobs started this turn report back (shared with `job_start`)
+    job_origin: JobOrigin,
+    /// Channel and participants of this turn (shared with `memory_log`)
+    log_origin: LogOrigin,
+    /// Messages of the most recent model call, for prompt inspection
+    last_prompt: Option<Vec<Message>>,
+    /// Language of the prompt fragments and persona for this session
+    language: Language,
+    /// `reserve_tokens` from the config, used until reply lengths are known
+    configured_reserve: usize,
+    /// Reply lengths for `agent.adaptive_reserve` (None when it is off)
+    reserve_store: Option<ReserveStore>,
+    /// Watches tool calls made by `chat` (e.g. the Discord mirror)
+    tool_observer: Option<ToolObserver>,
 }
 
 impl Agent {
@@ -149,7 +213,27 @@ impl Agent {
 
         // Wrap memory in Arc so tools can share it
         let memory = Arc::new(memory);
-        let tools = tools::create_default_tools(app_config, Some(Arc::clone(&memory)))?;
+        let mut tools = tools::create_default_tools(app_config, Some(Arc::clone(&memory)))?;
+
+        // Topic logs and saved memories record where the conversation happened
+        let log_origin = LogOrigin::default();
+        tools.push(Box::new(tools::MemoryLogTool::new(
+            Arc::clone(&memory),
+            log_origin.clone(),
+        )));
+        tools.push(Box::new(tools::SaveMemoryTool::new(
+            Arc::clone(&memory),
+            log_origin.clone(),
+        )));
+
+        // Background job tools share the turn's origin with this agent
+        let job_origin = JobOrigin::default();
+        if app_config.jobs.enabled {
+            match JobStore::open_default() {
+                Ok(store) => tools.extend(tools::create_job_tools(store, job_origin.clone())),
+                Err(e) => warn!("Job store unavailable, job tools disabled: {}", e),
+            }
+        }
 
         // Load and verify security policy
         let workspace = app_config.workspace_path();
@@ -247,7 +331,20 @@ impl Agent {
             }
         };
 
-        Ok(Self {
+        let reserve_store = if app_config.agent.adaptive_reserve.enabled {
+            match ReserveStore::open_default() {
+                Ok(store) => Some(store),
+                Err(e) => {
+                    warn!("Reply length store unavailable, static reserve: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let mut agent = Self {
+            configured_reserve: config.reserve_tokens,
             config,
             app_config: app_config.clone(),
             provider,
@@ -256,8 +353,21 @@ impl Agent {
             tools,
             cumulative_usage: Usage::default(),
             verified_security_policy,
-            soul_last_modified: None,
-        })
+            recall_context: None,
+            context_reloads: crate::memory::subscribe_context_reloads(),
+            denied_tools: Vec::new(),
+            turn_instructions: None,
+            context_providers: environment::create_context_providers(app_config),
+            environment_context: None,
+            job_origin,
+            log_origin,
+            last_prompt: None,
+            language: Language::from_config(&app_config.agent.language),
+            reserve_store,
+            tool_observer: None,
+        };
+        agent.tune_reserve();
+        Ok(agent)
     }
 
     pub fn model(&self) -> &str {
@@ -278,15 +388,78 @@ impl Agent {
         &self.app_config.tools.require_approval
     }
 
+    /// Refuse calls to these tools until changed (e.g. for a Discord user
+    /// without the matching permission)
+    pub fn set_denied_tools(&mut self, tools: Vec<String>) {
+        self.denied_tools = tools;
+    }
+
+    /// Instructions appended to every turn (e.g. a channel's reply style)
+    pub fn set_turn_instructions(&mut self, instructions: Option<String>) {
+        self.turn_instructions = instructions;
+    }
+
+    /// Discord channel that jobs started from now on report to (None =
+    /// results are only available through `job_status`)
+    pub fn set_job_channel(&mut self, channel_id: Option<String>) {
+        self.job_origin.set(channel_id);
+    }
+
+    /// Channel and participants recorded in the front matter of topic logs
+    /// written from now on
+    pub fn set_log_context(&mut self, context: LogContext) {
+        self.log_origin.set(context);
+        self.tune_reserve();
+    }
+
+    /// Report the tool calls of non-streaming turns to `observer` from now
+    /// on (None = stop)
+    pub fn set_tool_observer(&mut self, observer: Option<ToolObserver>) {
+        self.tool_observer = observer;
+    }
+
+    /// Offer an extra tool for the rest of the session
+    pub fn add_tool(&mut self, tool: Box<dyn Tool>) {
+        self.tools.push(tool);
+    }
+
     /// Switch to a different model
     pub fn set_model(&mut self, model: &str) -> Result<()> {
-        let provider = providers::create_provider(model, &self.app_config)?;
-        self.config.model = model.to_string();
-        self.provider = provider;
+        let previous = self.config.model.clone();
+        self.use_model(model)?;
+        if previous != model {
+            self.session.record_model_change(&previous, model);
+        }
         info!("Switched to model: {}", model);
         Ok(())
     }
 
+    /// Switch models on a user's request (Discord `/model`, the desktop
+    /// picker); only the default model and `agent.allowed_models` are accepted
+    pub fn switch_model(&mut self, model: &str) -> Result<()> {
+        let allowed = self.app_config.agent.switchable_models();
+        if !allowed.iter().any(|m| m == model) {
+            anyhow::bail!(
+                "Model {} is not allowed (choose from: {})",
+                model,
+                allowed.join(", ")
+            );
+        }
+        self.set_model(model)
+    }
+
+    /// Answer with `model` without recording a model change in the session,
+    /// for temporary switches such as experiment variants
+    pub fn set_model_for_turn(&mut self, model: &str) -> Result<()> {
+        self.use_model(model)
+    }
+
+    fn use_model(&mut self, model: &str) -> Result<()> {
+        self.provider = providers::create_provider(model, &self.app_config)?;
+        self.config.model = model.to_string();
+        Ok(())
+    }
+
     pub fn memory_chunk_count(&self) -> usize {
         self.memory.chunk_count().unwrap_or(0)
     }
@@ -300,11 +473,46 @@ impl Agent {
         self.config.context_window
     }
 
-    /// Get reserve tokens configuration
+    /// Tokens kept free for the reply (adjusted to measured reply lengths
+    /// with `agent.adaptive_reserve`)
     pub fn reserve_tokens(&self) -> usize {
         self.config.reserve_tokens
     }
 
+    /// Scope reply lengths are measured in: the channel, if any
+    fn reserve_scope(&self) -> String {
+        self.log_origin
+            .get()
+            .channel
+            .unwrap_or_else(|| DEFAULT_RESERVE_SCOPE.to_string())
+    }
+
+    /// Size the reserve from the scope's measured reply lengths
+    fn tune_reserve(&mut self) {
+        let Some(ref store) = self.reserve_store else {
+            return;
+        };
+        let adaptive = &self.app_config.agent.adaptive_reserve;
+        let reserve = match store.stats(&self.reserve_scope(), adaptive.percentile) {
+            Ok(stats) => stats
+                .and_then(|stats| adaptive.reserve_for(&stats, self.app_config.agent.max_tokens)),
+            Err(e) => {
+                warn!("Failed to read reply lengths: {}", e);
+                None
+            }
+        };
+        let reserve = reserve.unwrap_or(self.configured_reserve);
+        if reserve != self.config.reserve_tokens {
+            debug!(
+                "reserve_tokens for {}: {} -> {}",
+                self.reserve_scope(),
+                self.config.reserve_tokens,
+                reserve
+            );
+            self.config.reserve_tokens = reserve;
+        }
+    }
+
     /// Get current context usage info
     pub fn context_usage(&self) -> (usize, usize, usize) {
         let used = self.session.token_count();
@@ -316,23 +524,26 @@ impl Agent {
 
     /// Export session messages as markdown
     pub fn export_markdown(&self) -> String {
-        let mut output = String::new();
-        output.push_str("# LocalGPT Session Export\n\n");
-        output.push_str(&format!("Model: {}\n", self.config.model));
-        output.push_str(&format!("Session ID: {}\n\n", self.session.id()));
-        output.push_str("---\n\n");
+        self.export(ExportFormat::Markdown)
+    }
 
-        for msg in self.session.messages() {
-            let role = match msg.role {
-                Role::User => "**User**",
-                Role::Assistant => "**Assistant**",
-                Role::System => "**System**",
-                Role::Tool => "**Tool**",
-            };
-            output.push_str(&format!("{}\n\n{}\n\n---\n\n", role, msg.content));
-        }
+    /// Export the current session as markdown or standalone HTML
+    pub fn export(&self, format: ExportFormat) -> String {
+        let opts = ExportOptions {
+            model: Some(self.config.model.clone()),
+            ..Default::default()
+        };
+        self.export_with(format, &opts)
+    }
+
+    /// Export the current session with custom title/labels
+    pub fn export_with(&self, format: ExportFormat, opts: &ExportOptions) -> String {
+        export_session(&self.session, format, opts)
+    }
 
-        output
+    /// Suggested file name for an export of the current session
+    pub fn export_file_name(&self, format: ExportFormat) -> String {
+        export_file_name(&self.session, format)
     }
 
     /// Get cumulative token usage for this session
@@ -345,9 +556,67 @@ impl Agent {
         if let Some(u) = usage {
             self.cumulative_usage.input_tokens += u.input_tokens;
             self.cumulative_usage.output_tokens += u.output_tokens;
+
+            // Providers that don't report usage give 0, which says nothing
+            if let Some(ref store) = self.reserve_store
+                && u.output_tokens > 0
+            {
+                let window = self.app_config.agent.adaptive_reserve.window;
+                if let Err(e) = store.record(&self.reserve_scope(), u.output_tokens, window) {
+                    warn!("Failed to record reply length: {}", e);
+                }
+                self.tune_reserve();
+            }
         }
     }
 
+    /// Search memory with the incoming message (plus recent user turns) and
+    /// stash the relevant snippets for injection in this turn's API calls.
+    fn refresh_recall(&mut self, message: &str) {
+        self.recall_context = None;
+
+        let recall_config = &self.app_config.memory.recall;
+        if !recall_config.enabled || recall_config.top_k == 0 || message.trim().is_empty() {
+            return;
+        }
+
+        // Previous user turns (excluding the message just added)
+        let mut recent: Vec<String> = self
+            .session
+            .user_assistant_messages()
+            .into_iter()
+            .filter(|m| m.role == Role::User)
+            .map(|m| m.content)
+            .collect();
+        recent.pop();
+        let start = recent.len().saturating_sub(2);
+        let query = recall::build_recall_query(message, &recent[start..]);
+
+        // Over-fetch so filtering out always-loaded files still leaves top_k
+        match self.memory.recall(&query, recall_config.top_k * 2) {
+            Ok(chunks) => {
+                self.recall_context = recall::build_recall_context(
+                    &chunks,
+                    recall_config,
+                    self.app_config.tools.use_content_delimiters,
+                );
+                if self.recall_context.is_some() {
+                    debug!("Recalled memory snippets for message");
+                }
+            }
+            Err(e) => debug!("Memory recall failed: {}", e),
+        }
+    }
+
+    /// Ask the context providers for this turn's environment block
+    async fn refresh_environment(&mut self) {
+        self.environment_context = if self.context_providers.is_empty() {
+            None
+        } else {
+            environment::build_environment_context(&self.context_providers).await
+        };
+    }
+
     /// Build the message array for an LLM API call, with the security
     /// block injected as a trailing user message on every call.
     ///
@@ -365,6 +634,38 @@ impl Agent {
             self.verified_security_policy.as_deref()
         };
 
+        // Environment and recalled memories go before the security block so
+        // the block keeps the recency position
+        if let Some(ref environment) = self.environment_context {
+            messages.push(Message {
+                role: Role::User,
+                content: environment.clone(),
+                tool_calls: None,
+                tool_call_id: None,
+                images: Vec::new(),
+            });
+        }
+
+        if let Some(ref recalled) = self.recall_context {
+            messages.push(Message {
+                role: Role::User,
+                content: recalled.clone(),
+                tool_calls: None,
+                tool_call_id: None,
+                images: Vec::new(),
+            });
+        }
+
+        if let Some(ref instructions) = self.turn_instructions {
+            messages.push(Message {
+                role: Role::User,
+                content: instructions.clone(),
+                tool_calls: None,
+                tool_call_id: None,
+                images: Vec::new(),
+            });
+        }
+
         let security_block = crate::security::build_ending_security_block(policy, include_suffix);
 
         // Only append if the block has content
@@ -381,24 +682,44 @@ impl Agent {
         messages
     }
 
+    /// [`Self::messages_for_api_call`], kept as the last prompt
+    fn prompt_for_api_call(&mut self) -> Vec<Message> {
+        let messages = self.messages_for_api_call();
+        self.last_prompt = Some(messages.clone());
+        messages
+    }
+
+    /// Everything sent to the model in the most recent call of this session
+    /// (system prompt, recalled memories, tool outputs, security block)
+    pub fn last_prompt(&self) -> Option<&[Message]> {
+        self.last_prompt.as_deref()
+    }
+
     pub async fn new_session(&mut self) -> Result<()> {
         self.session = Session::new();
+        self.recall_context = None;
+        self.environment_context = None;
+        self.last_prompt = None;
 
         // Reset provider session state (e.g., clear Claude CLI session ID)
         self.provider.reset_session();
 
+        let full_context = self.build_system_context().await?;
+        self.session.set_system_context(full_context);
+
+        info!("Created new session: {}", self.session.id());
+        Ok(())
+    }
+
+    /// Build the full system context: SOUL.md, system prompt, skills and
+    /// workspace memory files.
+    async fn build_system_context(&mut self) -> Result<String> {
         // Load skills from workspace
         let workspace_skills = skills::load_skills(self.memory.workspace()).unwrap_or_default();
         let skills_prompt = skills::build_skills_prompt(&workspace_skills);
         debug!("Loaded {} skills from workspace", workspace_skills.len());
 
         // Load SOUL.md first - it defines who the agent is and should come before everything
-        let soul_path = self.memory.workspace().join("SOUL.md");
-        if let Ok(meta) = soul_path.metadata() {
-            if let Ok(modified) = meta.modified() {
-                self.soul_last_modified = Some(modified);
-            }
-        }
         let soul_content = self.read_soul_content();
         let has_soul = !soul_content.is_empty();
 
@@ -407,9 +728,29 @@ impl Agent {
         let system_prompt_params =
             system_prompt::SystemPromptParams::new(self.memory.workspace(), &self.config.model)
                 .with_tools(tool_names)
-                .with_skills_prompt(skills_prompt);
+                .with_skills_prompt(skills_prompt)
+                .with_language(self.language);
         let mut system_prompt = system_prompt::build_system_prompt(system_prompt_params);
 
+        // Replies users reacted 👎 to, as examples to avoid
+        let feedback_examples = self.app_config.agent.feedback_examples;
+        if feedback_examples > 0 {
+            match FeedbackStore::open_default().and_then(|s| s.recent_negative(feedback_examples)) {
+                Ok(examples) => {
+                    if let Some(section) = feedback::avoid_section(&examples) {
+                        system_prompt = format!("{}\n\n{}", system_prompt.trim_end(), section);
+                    }
+                }
+                Err(e) => warn!("Failed to load feedback examples: {}", e),
+            }
+        }
+
+        // Obsidian conventions when the workspace is a vault
+        if let Some(vault) = self.memory.vault() {
+            let section = vault.prompt_section(chrono::Local::now().date_naive());
+            system_prompt = format!("{}\n\n{}", system_prompt.trim_end(), section);
+        }
+
         // If SOUL.md exists, remove the default identity line and prepend soul content
         if has_soul {
             system_prompt = system_prompt
@@ -433,41 +774,87 @@ impl Agent {
             )
         };
 
-        self.session.set_system_context(full_context);
-
-        info!("Created new session: {}", self.session.id());
-        Ok(())
+        Ok(full_context)
     }
 
-    /// Check if SOUL.md has been modified and reload the session if so.
-    /// Returns `Ok(true)` if the session was reloaded.
-    pub async fn check_and_reload_soul(&mut self) -> Result<bool> {
-        let soul_path = self.memory.workspace().join("SOUL.md");
-        let current_modified = match soul_path.metadata() {
-            Ok(meta) => meta.modified().ok(),
-            Err(_) => None,
-        };
+    /// Apply workspace file changes broadcast by the context watcher
+    /// (see `memory::watch_context_files`).
+    ///
+    /// A SOUL.md change starts a fresh session. Other context files
+    /// (MEMORY.md, AGENTS.md, ...) are reloaded in place so the conversation
+    /// is kept.
+    /// Returns `Ok(true)` if anything was reloaded.
+    pub async fn apply_context_reloads(&mut self) -> Result<bool> {
+        let mut changed: Vec<String> = Vec::new();
+        loop {
+            match self.context_reloads.try_recv() {
+                Ok(event) => changed.extend(event.files),
+                Err(broadcast::error::TryRecvError::Lagged(_)) => {
+                    // Missed events; assume everything changed
+                    changed.push("SOUL.md".to_string());
+                }
+                Err(_) => break,
+            }
+        }
 
-        let changed = match (current_modified, self.soul_last_modified) {
-            (Some(current), Some(previous)) => current != previous,
-            (Some(_), None) => true,  // First load
-            (None, Some(_)) => true,  // File deleted
-            (None, None) => false,
-        };
+        if changed.is_empty() {
+            return Ok(false);
+        }
 
-        if changed {
+        if changed.iter().any(|f| f.starts_with("SOUL.")) {
             info!("SOUL.md changed, reloading session");
             clean_claude_cli_sessions(self.memory.workspace());
             self.new_session().await?;
-            Ok(true)
         } else {
-            Ok(false)
+            info!("Workspace context changed ({}), reloading", changed.join(", "));
+            let full_context = self.build_system_context().await?;
+            self.session.set_system_context(full_context);
         }
+
+        Ok(true)
+    }
+
+    /// With `agent.detect_language`, switch to the language of the
+    /// session's first message, rebuilding the system context for it
+    async fn follow_language(&mut self, message: &str) -> Result<()> {
+        if !self.app_config.agent.detect_language
+            || self.session.messages().iter().any(|m| m.role == Role::User)
+        {
+            return Ok(());
+        }
+        match Language::detect(message) {
+            Some(language) => self.set_language(language).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Use `language` for the prompt fragments and persona from now on,
+    /// rebuilding the system context if it changes
+    pub async fn set_language(&mut self, language: Language) -> Result<()> {
+        if language == self.language {
+            return Ok(());
+        }
+        info!("Conversation language: {}", language.code());
+        self.language = language;
+        let full_context = self.build_system_context().await?;
+        self.session.set_system_context(full_context);
+        Ok(())
     }
 
     pub async fn resume_session(&mut self, session_id: &str) -> Result<()> {
         self.session = Session::load(session_id)?;
         info!("Resumed session: {}", session_id);
+
+        // Continue with the model the session was last switched to
+        if let Some(model) = self.session.model_changes().last().map(|c| c.to.clone())
+            && model != self.config.model
+            && let Err(e) = self.use_model(&model)
+        {
+            warn!(
+                "Keeping model {} for resumed session: {}",
+                self.config.model, e
+            );
+        }
         Ok(())
     }
 
@@ -480,6 +867,10 @@ impl Agent {
         message: &str,
         images: Vec<ImageAttachment>,
     ) -> Result<String> {
+        // Pick up edited workspace files before this turn
+        self.apply_context_reloads().await?;
+        self.follow_language(message).await?;
+
         // Add user message with images
         self.session.add_message(Message {
             role: Role::User,
@@ -489,6 +880,10 @@ impl Agent {
             images,
         });
 
+        // Recall relevant memories for this message
+        self.refresh_recall(message);
+        self.refresh_environment().await;
+
         // Check if we should run pre-compaction memory flush (soft threshold)
         if self.should_memory_flush() {
             info!("Running pre-compaction memory flush (soft threshold)");
@@ -501,7 +896,7 @@ impl Agent {
         }
 
         // Build messages for LLM (with per-turn security block)
-        let messages = self.messages_for_api_call();
+        let messages = self.prompt_for_api_call();
 
         // Get available tools
         let tool_schemas: Vec<ToolSchema> = self.tools.iter().map(|t| t.schema()).collect();
@@ -527,68 +922,133 @@ impl Agent {
         Ok(final_response)
     }
 
-    async fn handle_response(&mut self, response: LLMResponse) -> Result<String> {
-        // Track usage
-        self.add_usage(response.usage);
+    /// Run the tool loop until the model answers with text, within the
+    /// limits of `agent.tool_loop`
+    async fn handle_response(&mut self, mut response: LLMResponse) -> Result<String> {
+        let mut guard = ToolLoopGuard::new(&self.app_config.agent.tool_loop);
 
-        match response.content {
-            LLMResponseContent::Text(text) => Ok(text),
-            LLMResponseContent::ToolCalls(calls) => {
-                // Execute tool calls
-                let mut results = Vec::new();
+        loop {
+            // Track usage
+            self.add_usage(response.usage);
 
-                for call in &calls {
+            let calls = match response.content {
+                LLMResponseContent::Text(text) => return Ok(text),
+                LLMResponseContent::ToolCalls(calls) => calls,
+            };
+
+            if !guard.next_iteration() {
+                warn!(
+                    "Tool loop limit of {} rounds reached, asking for a final answer",
+                    guard.max_iterations()
+                );
+                return self.wrap_up_tool_loop("round limit reached").await;
+            }
+
+            // Execute tool calls, refusing exact repeats
+            let mut results = Vec::new();
+            let mut all_repeated = true;
+            for call in &calls {
+                if let Some(ref observer) = self.tool_observer {
+                    observer(&StreamEvent::ToolCallStart {
+                        name: call.name.clone(),
+                        id: call.id.clone(),
+                        arguments: call.arguments.clone(),
+                    });
+                }
+                let output = if guard.is_repeat(call) {
+                    debug!("Refusing repeated tool call: {}", call.name);
+                    REPEATED_CALL_OUTPUT.to_string()
+                } else {
+                    all_repeated = false;
                     debug!(
                         "Executing tool: {} with args: {}",
                         call.name, call.arguments
                     );
-
-                    let result = self.execute_tool(call).await;
-                    let output = match result {
+                    match self.execute_tool(call).await {
                         Ok((content, _warnings)) => content,
                         Err(e) => format!("Error: {}", e),
-                    };
-                    results.push(ToolResult {
-                        call_id: call.id.clone(),
-                        output,
+                    }
+                };
+                let output = guard.fit_output(output, calls.len());
+                if let Some(ref observer) = self.tool_observer {
+                    observer(&StreamEvent::ToolCallEnd {
+                        name: call.name.clone(),
+                        id: call.id.clone(),
+                        output: output.clone(),
+                        warnings: Vec::new(),
                     });
                 }
+                results.push(ToolResult {
+                    call_id: call.id.clone(),
+                    output,
+                });
+            }
 
-                // Add tool call message
+            // Add tool call message
+            self.session.add_message(Message {
+                role: Role::Assistant,
+                content: String::new(),
+                tool_calls: Some(calls),
+                tool_call_id: None,
+                images: Vec::new(),
+            });
+
+            // Add tool results
+            for result in results {
                 self.session.add_message(Message {
-                    role: Role::Assistant,
-                    content: String::new(),
-                    tool_calls: Some(calls),
-                    tool_call_id: None,
+                    role: Role::Tool,
+                    content: result.output,
+                    tool_calls: None,
+                    tool_call_id: Some(result.call_id),
                     images: Vec::new(),
                 });
+            }
 
-                // Add tool results
-                for result in &results {
-                    self.session.add_message(Message {
-                        role: Role::Tool,
-                        content: result.output.clone(),
-                        tool_calls: None,
-                        tool_call_id: Some(result.call_id.clone()),
-                        images: Vec::new(),
-                    });
-                }
+            if all_repeated {
+                warn!("Tool loop detected (only repeated calls), asking for a final answer");
+                return self.wrap_up_tool_loop("the same calls were repeated").await;
+            }
 
-                // Continue conversation with tool results (with per-turn security block)
-                let messages = self.messages_for_api_call();
-                let tool_schemas: Vec<ToolSchema> = self.tools.iter().map(|t| t.schema()).collect();
-                let next_response = self
-                    .provider
-                    .chat(&messages, Some(tool_schemas.as_slice()))
-                    .await?;
+            // Continue conversation with tool results (with per-turn security block)
+            let messages = self.prompt_for_api_call();
+            let tool_schemas: Vec<ToolSchema> = self.tools.iter().map(|t| t.schema()).collect();
+            response = self
+                .provider
+                .chat(&messages, Some(tool_schemas.as_slice()))
+                .await?;
+        }
+    }
+
+    /// Ask for a final text answer without offering tools (the prompt is
+    /// not kept in the session)
+    async fn wrap_up_tool_loop(&mut self, reason: &str) -> Result<String> {
+        let mut messages = self.messages_for_api_call();
+        messages.push(Message {
+            role: Role::User,
+            content: wrap_up_prompt(reason),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        });
+        self.last_prompt = Some(messages.clone());
 
-                // Recursively handle (in case of more tool calls)
-                Box::pin(self.handle_response(next_response)).await
+        let response = self.provider.chat(&messages, None).await?;
+        self.add_usage(response.usage);
+        match response.content {
+            LLMResponseContent::Text(text) => Ok(text),
+            LLMResponseContent::ToolCalls(_) => {
+                anyhow::bail!(
+                    "Model kept calling tools after the tool loop was stopped ({})",
+                    reason
+                )
             }
         }
     }
 
     async fn execute_tool(&self, call: &ToolCall) -> Result<(String, Vec<String>)> {
+        if self.denied_tools.iter().any(|t| *t == call.name) {
+            anyhow::bail!("Tool {} is not permitted for this user", call.name);
+        }
         for tool in &self.tools {
             if tool.name() == call.name {
                 let raw_output = tool.execute(&call.arguments).await?;
@@ -623,16 +1083,20 @@ impl Agent {
     /// Read SOUL.md content (persona/tone definition).
     /// Extracted so it can be prepended before the system prompt in new_session.
     fn read_soul_content(&self) -> String {
-        match self.memory.read_soul_file() {
+        let soul_path = self.memory.soul_file_path(self.language.code());
+        match self.memory.read_soul_file_for(self.language.code()) {
             Ok(content) if !content.is_empty() => {
                 if self.app_config.tools.use_content_delimiters {
                     sanitize::wrap_memory_content(
-                        "SOUL.md",
+                        soul_path
+                            .file_name()
+                            .and_then(|n| n.to_str())
+                            .unwrap_or("SOUL.md"),
                         &content,
                         sanitize::MemorySource::Soul,
                     )
                 } else {
-                    content
+                    content.to_string()
                 }
             }
             _ => String::new(),
@@ -772,6 +1236,25 @@ impl Agent {
             context.push('\n');
         }
 
+        // Load tracked tasks (shared task store, see crate::tasks)
+        if let Ok(store) = crate::tasks::TaskStore::open_default()
+            && let Ok(tasks) = store.pending()
+            && !tasks.is_empty()
+        {
+            let task_list = crate::tasks::format_task_list(&tasks);
+            if use_delimiters {
+                context.push_str(&sanitize::wrap_memory_content(
+                    "tasks",
+                    &task_list,
+                    sanitize::MemorySource::Heartbeat,
+                ));
+            } else {
+                context.push_str("# Pending Tasks (tracked)\n\n");
+                context.push_str(&task_list);
+            }
+            context.push('\n');
+        }
+
         Ok(context)
     }
 
@@ -812,14 +1295,13 @@ impl Agent {
         // Mark as flushed for this compaction cycle (prevents running twice)
         self.session.mark_memory_flushed();
 
-        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
         let flush_prompt = format!(
             "Pre-compaction memory flush. Session nearing token limit.\n\
-             Store durable memories now (use memory/{}.md; create memory/ if needed).\n\
+             Store durable memories now.\n\
              - MEMORY.md for persistent facts (user info, preferences, key decisions)\n\
-             - memory/{}.md for session notes\n\n\
+             - memory_log for session notes, one entry per topic\n\n\
              If nothing to store, reply: {}",
-            today, today, SILENT_REPLY_TOKEN
+            SILENT_REPLY_TOKEN
         );
 
         // Add flush prompt as user message
@@ -837,8 +1319,12 @@ impl Agent {
 
         let response = self.provider.chat(&messages, Some(&tool_schemas)).await?;
 
-        // Handle response (may include tool calls)
-        let final_response = self.handle_response(response).await?;
+        // Handle response (may include tool calls). The flush is our own
+        // request, so the requester's tool restrictions don't apply.
+        let denied_tools = std::mem::take(&mut self.denied_tools);
+        let final_response = self.handle_response(response).await;
+        self.denied_tools = denied_tools;
+        let final_response = final_response?;
 
         // Add response to session
         self.session.add_message(Message {
@@ -925,8 +1411,8 @@ impl Agent {
         }
 
         // Write to memory/YYYY-MM-DD-slug.md
-        let memory_dir = self.memory.workspace().join("memory");
-        std::fs::create_dir_all(&memory_dir)?;
+        let workspace = self.memory.workspace();
+        let memory_dir = workspace.join("memory");
 
         let filename = format!("{}-{}.md", date_str, slug);
         let path = memory_dir.join(&filename);
@@ -936,14 +1422,18 @@ impl Agent {
             content.len(),
             path.display()
         );
-        std::fs::write(&path, content)?;
+        let content = crate::security::redact("memory", &path.display().to_string(), &content);
+        crate::memory::write_memory_file(workspace, &path, &content, "session")?;
         info!("Saved session to memory: {}", path.display());
+        self.memory.link_from_daily_note(&path, &slug);
 
         Ok(Some(path))
     }
 
     pub fn clear_session(&mut self) {
         self.session = Session::new();
+        self.recall_context = None;
+        self.environment_context = None;
         self.provider.reset_session();
     }
 
@@ -989,6 +1479,10 @@ impl Agent {
         message: &str,
         images: Vec<ImageAttachment>,
     ) -> Result<StreamResult> {
+        // Pick up edited workspace files before this turn
+        self.apply_context_reloads().await?;
+        self.follow_language(message).await?;
+
         // Add user message with images
         self.session.add_message(Message {
             role: Role::User,
@@ -998,6 +1492,10 @@ impl Agent {
             images,
         });
 
+        // Recall relevant memories for this message
+        self.refresh_recall(message);
+        self.refresh_environment().await;
+
         // Check if we should run pre-compaction memory flush (soft threshold)
         if self.should_memory_flush() {
             info!("Running pre-compaction memory flush (soft threshold)");
@@ -1010,7 +1508,7 @@ impl Agent {
         }
 
         // Build messages for LLM (with per-turn security block)
-        let messages = self.messages_for_api_call();
+        let messages = self.prompt_for_api_call();
 
         // Get tool schemas so the model knows the correct tool call format
         let tool_schemas: Vec<ToolSchema> = self.tools.iter().map(|t| t.schema()).collect();
@@ -1083,7 +1581,7 @@ impl Agent {
         }
 
         // Get follow-up response from LLM (with per-turn security block)
-        let messages = self.messages_for_api_call();
+        let messages = self.prompt_for_api_call();
         let tool_schemas: Vec<ToolSchema> = self.tools.iter().map(|t| t.schema()).collect();
         let response = self
             .provider
@@ -1149,6 +1647,10 @@ impl Agent {
         &mut self,
         message: &str,
     ) -> Result<impl futures::Stream<Item = Result<StreamEvent>> + '_> {
+        // Pick up edited workspace files before this turn
+        self.apply_context_reloads().await?;
+        self.follow_language(message).await?;
+
         // Add user message
         self.session.add_message(Message {
             role: Role::User,
@@ -1158,6 +1660,10 @@ impl Agent {
             images: Vec::new(),
         });
 
+        // Recall relevant memories for this message
+        self.refresh_recall(message);
+        self.refresh_environment().await;
+
         // Check if we should run pre-compaction memory flush (soft threshold)
         if self.should_memory_flush() {
             info!("Running pre-compaction memory flush (soft threshold)");
@@ -1174,21 +1680,14 @@ impl Agent {
 
     fn stream_with_tool_loop(&mut self) -> impl futures::Stream<Item = Result<StreamEvent>> + '_ {
         async_stream::stream! {
-            let max_tool_iterations = 10;
-            let mut iteration = 0;
+            let mut guard = ToolLoopGuard::new(&self.app_config.agent.tool_loop);
 
             loop {
-                iteration += 1;
-                if iteration > max_tool_iterations {
-                    yield Err(anyhow::anyhow!("Max tool iterations exceeded"));
-                    break;
-                }
-
                 // Get tool schemas
                 let tool_schemas: Vec<ToolSchema> = self.tools.iter().map(|t| t.schema()).collect();
 
                 // Build messages for LLM (with per-turn security block)
-                let messages = self.messages_for_api_call();
+                let messages = self.prompt_for_api_call();
 
                 // Try streaming first (without tools since most providers don't support tool streaming)
                 // Then check for tool calls in the response
@@ -1197,78 +1696,120 @@ impl Agent {
                     .chat(&messages, Some(tool_schemas.as_slice()))
                     .await;
 
-                match response {
-                    Ok(resp) => {
-                        // Track usage
-                        self.add_usage(resp.usage);
-
-                        match resp.content {
-                            LLMResponseContent::Text(text) => {
-                                // No tool calls - yield the text and we're done
-                                yield Ok(StreamEvent::Content(text.clone()));
-                                yield Ok(StreamEvent::Done);
-
-                                // Add to session
-                                self.session.add_message(Message {
-                                    role: Role::Assistant,
-                                    content: text,
-                                    tool_calls: None,
-                                    tool_call_id: None,
-                                    images: Vec::new(),
-                                });
-                                break;
-                            }
-                            LLMResponseContent::ToolCalls(calls) => {
-                        // Notify about tool calls
-                        for call in &calls {
-                            yield Ok(StreamEvent::ToolCallStart {
-                                name: call.name.clone(),
-                                id: call.id.clone(),
-                                arguments: call.arguments.clone(),
-                            });
-
-                            // Execute tool
-                            let result = self.execute_tool(call).await;
-                            let (output, warnings) = match result {
-                                Ok((content, warnings)) => (content, warnings),
-                                Err(e) => (format!("Error: {}", e), Vec::new()),
-                            };
+                let resp = match response {
+                    Ok(resp) => resp,
+                    Err(e) => {
+                        yield Err(e);
+                        break;
+                    }
+                };
 
-                            yield Ok(StreamEvent::ToolCallEnd {
-                                name: call.name.clone(),
-                                id: call.id.clone(),
-                                output: output.clone(),
-                                warnings,
-                            });
+                // Track usage
+                self.add_usage(resp.usage);
 
-                            // Add tool result to session
-                            self.session.add_message(Message {
-                                role: Role::Tool,
-                                content: output,
-                                tool_calls: None,
-                                tool_call_id: Some(call.id.clone()),
-                                images: Vec::new(),
-                            });
-                        }
+                let calls = match resp.content {
+                    LLMResponseContent::Text(text) => {
+                        // No tool calls - yield the text and we're done
+                        yield Ok(StreamEvent::Content(text.clone()));
+                        yield Ok(StreamEvent::Done);
 
-                        // Add tool call message to session
+                        // Add to session
                         self.session.add_message(Message {
                             role: Role::Assistant,
-                            content: String::new(),
-                            tool_calls: Some(calls),
+                            content: text,
+                            tool_calls: None,
                             tool_call_id: None,
                             images: Vec::new(),
                         });
+                        break;
+                    }
+                    LLMResponseContent::ToolCalls(calls) => calls,
+                };
+
+                let stop_reason = if guard.next_iteration() {
+                    None
+                } else {
+                    warn!(
+                        "Tool loop limit of {} rounds reached, asking for a final answer",
+                        guard.max_iterations()
+                    );
+                    Some("round limit reached")
+                };
+
+                let mut all_repeated = true;
+                if stop_reason.is_none() {
+                    // Add tool call message to session
+                    self.session.add_message(Message {
+                        role: Role::Assistant,
+                        content: String::new(),
+                        tool_calls: Some(calls.clone()),
+                        tool_call_id: None,
+                        images: Vec::new(),
+                    });
+
+                    // Notify about tool calls
+                    for call in &calls {
+                        yield Ok(StreamEvent::ToolCallStart {
+                            name: call.name.clone(),
+                            id: call.id.clone(),
+                            arguments: call.arguments.clone(),
+                        });
 
-                        // Continue loop to get next response
+                        // Execute tool, refusing exact repeats
+                        let (output, warnings) = if guard.is_repeat(call) {
+                            (REPEATED_CALL_OUTPUT.to_string(), Vec::new())
+                        } else {
+                            all_repeated = false;
+                            match self.execute_tool(call).await {
+                                Ok((content, warnings)) => (content, warnings),
+                                Err(e) => (format!("Error: {}", e), Vec::new()),
                             }
-                        }
+                        };
+                        let output = guard.fit_output(output, calls.len());
+
+                        yield Ok(StreamEvent::ToolCallEnd {
+                            name: call.name.clone(),
+                            id: call.id.clone(),
+                            output: output.clone(),
+                            warnings,
+                        });
+
+                        // Add tool result to session
+                        self.session.add_message(Message {
+                            role: Role::Tool,
+                            content: output,
+                            tool_calls: None,
+                            tool_call_id: Some(call.id.clone()),
+                            images: Vec::new(),
+                        });
                     }
-                    Err(e) => {
-                        yield Err(e);
-                        break;
+                }
+
+                let stop_reason = stop_reason.or_else(|| {
+                    all_repeated.then(|| {
+                        warn!("Tool loop detected (only repeated calls), asking for a final answer");
+                        "the same calls were repeated"
+                    })
+                });
+                if let Some(reason) = stop_reason {
+                    match self.wrap_up_tool_loop(reason).await {
+                        Ok(text) => {
+                            yield Ok(StreamEvent::Content(text.clone()));
+                            yield Ok(StreamEvent::Done);
+                            self.session.add_message(Message {
+                                role: Role::Assistant,
+                                content: text,
+                                tool_calls: None,
+                                tool_call_id: None,
+                                images: Vec::new(),
+                            });
+                        }
+                        Err(e) => yield Err(e),
                     }
+                    break;
                 }
+
+                // Continue loop to get next response
             }
         }
     }
@@ -0,0 +1,397 @@
+//! Reply post-processing
+//!
+//! Every channel runs the agent's final reply through the same ordered
+//! pipeline before sending it:
+//!
+//! | Stage | Does |
+//! |-------|------|
+//! | `strip_tags` | removes `<think>` blocks, `NO_REPLY` lines and, outside Discord, action tags such as `[REACT:👍]` |
+//! | `guardrails` | masks secrets like the log and memory writers do |
+//! | `localize` | Japanese replies get full-width `！`/`？` and lose stray spaces between Japanese characters |
+//! | `split` | breaks the reply into parts no longer than the channel's limit |
+//!
+//! `[replies.<channel>]` picks the stages per channel type and the split
+//! limit. Other stages can be added with [`ReplyPipeline::with_stage`].
+
+use regex::Regex;
+use std::sync::LazyLock;
+use tracing::warn;
+
+use super::locale::Language;
+use super::system_prompt::SILENT_REPLY_TOKEN;
+use crate::config::{Config, ReplyPipelineConfig};
+use crate::security::redact;
+
+static THINK_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<think>.*?(</think>|$)").unwrap());
+
+/// Tags the Discord bot acts on, besides the command tags in `[tags.*]`
+const ACTION_TAGS: [&str; 6] = ["POST", "PUBLISH", "REMIND", "REACT", "LIST", "READ"];
+
+/// Where a reply is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelKind {
+    Discord,
+    Telegram,
+    Http,
+    Desktop,
+}
+
+impl ChannelKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ChannelKind::Discord => "discord",
+            ChannelKind::Telegram => "telegram",
+            ChannelKind::Http => "http",
+            ChannelKind::Desktop => "desktop",
+        }
+    }
+
+    /// Longest message the channel accepts
+    pub fn message_limit(self) -> Option<usize> {
+        match self {
+            ChannelKind::Discord => Some(2000),
+            ChannelKind::Telegram => Some(4096),
+            ChannelKind::Http | ChannelKind::Desktop => None,
+        }
+    }
+
+    fn config(self, config: &Config) -> &ReplyPipelineConfig {
+        match self {
+            ChannelKind::Discord => &config.replies.discord,
+            ChannelKind::Telegram => &config.replies.telegram,
+            ChannelKind::Http => &config.replies.http,
+            ChannelKind::Desktop => &config.replies.desktop,
+        }
+    }
+}
+
+/// One step of the pipeline. Stages see the reply as a list of parts (a
+/// single part until `split` has run) and may drop, change or add parts.
+pub trait ReplyStage: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn apply(&self, parts: Vec<String>) -> Vec<String>;
+}
+
+/// Apply `f` to every part, dropping parts left empty
+fn map_parts(parts: Vec<String>, f: impl Fn(&str) -> String) -> Vec<String> {
+    parts
+        .iter()
+        .map(|part| f(part))
+        .filter(|part| !part.trim().is_empty())
+        .collect()
+}
+
+/// Removes model scaffolding the user shouldn't see
+pub struct StripTags {
+    /// Action tags to remove; empty where the channel handles them itself
+    action_tags: Option<Regex>,
+}
+
+impl StripTags {
+    pub fn new(action_tags: &[String]) -> Self {
+        let action_tags = (!action_tags.is_empty()).then(|| {
+            let names: Vec<String> = action_tags.iter().map(|n| regex::escape(n)).collect();
+            Regex::new(&format!(r"\[(?:{})(?::[^\]]*)?\]", names.join("|"))).unwrap()
+        });
+        Self { action_tags }
+    }
+
+    fn strip(&self, text: &str) -> String {
+        let text = THINK_RE.replace_all(text, "");
+        let text = match self.action_tags {
+            Some(ref re) => re.replace_all(&text, ""),
+            None => text,
+        };
+        let lines: Vec<&str> = text
+            .lines()
+            .filter(|line| line.trim() != SILENT_REPLY_TOKEN)
+            .collect();
+        lines.join("\n").trim().to_string()
+    }
+}
+
+impl ReplyStage for StripTags {
+    fn name(&self) -> &str {
+        "strip_tags"
+    }
+
+    fn apply(&self, parts: Vec<String>) -> Vec<String> {
+        map_parts(parts, |part| self.strip(part))
+    }
+}
+
+/// Masks secrets with the configured redaction rules
+pub struct Guardrails {
+    channel: ChannelKind,
+}
+
+impl ReplyStage for Guardrails {
+    fn name(&self) -> &str {
+        "guardrails"
+    }
+
+    fn apply(&self, parts: Vec<String>) -> Vec<String> {
+        map_parts(parts, |part| {
+            redact("reply", self.channel.name(), part).into_owned()
+        })
+    }
+}
+
+/// Fixes typography that models get wrong in some languages
+pub struct Localize;
+
+fn is_japanese(c: char) -> bool {
+    matches!(c,
+        '\u{3000}'..='\u{303f}'     // punctuation
+        | '\u{3040}'..='\u{30ff}'   // kana
+        | '\u{4e00}'..='\u{9fff}'   // kanji
+        | '\u{ff01}'..='\u{ff5e}') // full-width forms
+}
+
+impl Localize {
+    fn japanese(text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut in_code = false;
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            if line.trim_start().starts_with("```") {
+                in_code = !in_code;
+            }
+            if in_code || line.trim_start().starts_with("```") {
+                out.push_str(line);
+                continue;
+            }
+            let chars: Vec<char> = line.chars().collect();
+            for (j, &c) in chars.iter().enumerate() {
+                let prev = j.checked_sub(1).map(|k| chars[k]);
+                let next = chars.get(j + 1).copied();
+                let after_japanese = prev.is_some_and(is_japanese);
+                match c {
+                    ' ' if after_japanese && next.is_some_and(is_japanese) => {}
+                    '!' if after_japanese => out.push('！'),
+                    '?' if after_japanese => out.push('？'),
+                    _ => out.push(c),
+                }
+            }
+        }
+        out
+    }
+}
+
+impl ReplyStage for Localize {
+    fn name(&self) -> &str {
+        "localize"
+    }
+
+    fn apply(&self, parts: Vec<String>) -> Vec<String> {
+        map_parts(parts, |part| match Language::detect(part) {
+            Some(Language::Japanese) => Self::japanese(part),
+            _ => part.to_string(),
+        })
+    }
+}
+
+/// Breaks parts longer than `max_chars` at paragraph breaks, line breaks
+/// or spaces, in that order of preference
+pub struct Split {
+    max_chars: usize,
+}
+
+impl Split {
+    fn split(&self, text: &str) -> Vec<String> {
+        let mut parts = Vec::new();
+        let mut rest = text.trim();
+        while rest.chars().count() > self.max_chars {
+            let limit = rest
+                .char_indices()
+                .nth(self.max_chars)
+                .map_or(rest.len(), |(i, _)| i);
+            let head = &rest[..limit];
+            let at = ["\n\n", "\n", " "]
+                .iter()
+                .find_map(|sep| head.rfind(sep).filter(|&i| i > 0))
+                .unwrap_or(limit);
+            parts.push(rest[..at].trim_end().to_string());
+            rest = rest[at..].trim_start();
+        }
+        if !rest.is_empty() {
+            parts.push(rest.to_string());
+        }
+        parts
+    }
+}
+
+impl ReplyStage for Split {
+    fn name(&self) -> &str {
+        "split"
+    }
+
+    fn apply(&self, parts: Vec<String>) -> Vec<String> {
+        if self.max_chars == 0 {
+            return parts;
+        }
+        parts.iter().flat_map(|part| self.split(part)).collect()
+    }
+}
+
+/// The stages a reply goes through, in order
+pub struct ReplyPipeline {
+    stages: Vec<Box<dyn ReplyStage>>,
+}
+
+impl ReplyPipeline {
+    pub fn new() -> Self {
+        Self { stages: Vec::new() }
+    }
+
+    /// The configured pipeline of a channel type
+    pub fn for_channel(config: &Config, channel: ChannelKind) -> Self {
+        let settings = channel.config(config);
+        let mut pipeline = Self::new();
+        for name in &settings.stages {
+            let stage: Box<dyn ReplyStage> = match name.as_str() {
+                "strip_tags" => {
+                    // The Discord bot acts on its tags before the pipeline runs
+                    let tags: Vec<String> = if channel == ChannelKind::Discord {
+                        Vec::new()
+                    } else {
+                        ACTION_TAGS
+                            .iter()
+                            .map(|t| t.to_string())
+                            .chain(config.tags.keys().map(|t| t.to_uppercase()))
+                            .collect()
+                    };
+                    Box::new(StripTags::new(&tags))
+                }
+                "guardrails" => Box::new(Guardrails { channel }),
+                "localize" => Box::new(Localize),
+                "split" => Box::new(Split {
+                    max_chars: settings.max_chars.or(channel.message_limit()).unwrap_or(0),
+                }),
+                other => {
+                    warn!(
+                        "Unknown reply stage {:?} in [replies.{}], skipping",
+                        other,
+                        channel.name()
+                    );
+                    continue;
+                }
+            };
+            pipeline.stages.push(stage);
+        }
+        pipeline
+    }
+
+    /// Add a stage after the existing ones
+    pub fn with_stage(mut self, stage: Box<dyn ReplyStage>) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    /// Names of the stages, in order
+    pub fn stage_names(&self) -> Vec<&str> {
+        self.stages.iter().map(|s| s.name()).collect()
+    }
+
+    /// The parts to send; empty if nothing is left of the reply
+    pub fn process(&self, reply: &str) -> Vec<String> {
+        let parts = map_parts(vec![reply.to_string()], |part| part.trim().to_string());
+        self.stages
+            .iter()
+            .fold(parts, |parts, stage| stage.apply(parts))
+    }
+
+    /// [`process`](Self::process) for channels that show the reply as one
+    /// message
+    pub fn process_joined(&self, reply: &str) -> String {
+        self.process(reply).join("\n\n")
+    }
+}
+
+impl Default for ReplyPipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stages() {
+        let strip = StripTags::new(&["REACT".to_string()]);
+        assert_eq!(
+            strip.apply(vec![
+                "<think>plan</think>Done! [REACT:👍]\nNO_REPLY\n[NOTE: kept]".to_string()
+            ]),
+            vec!["Done! \n[NOTE: kept]".to_string()]
+        );
+        assert!(strip.apply(vec!["NO_REPLY".to_string()]).is_empty());
+        assert!(
+            StripTags::new(&[])
+                .apply(vec!["<think>unfinished".to_string()])
+                .is_empty()
+        );
+
+        assert_eq!(
+            Localize.apply(vec!["今日は 晴れです!\n```\nok ?\n```\nIs it?".to_string()]),
+            vec!["今日は晴れです！\n```\nok ?\n```\nIs it?".to_string()]
+        );
+        assert_eq!(
+            Localize.apply(vec!["Hello world!".to_string()]),
+            vec!["Hello world!".to_string()]
+        );
+
+        let split = Split { max_chars: 12 };
+        assert_eq!(
+            split.apply(vec!["first para\n\nsecond one here".to_string()]),
+            vec!["first para", "second one", "here"]
+        );
+        assert_eq!(
+            split
+                .apply(vec!["あいうえおかきくけこさしすせそ".to_string()])
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_pipeline_per_channel() {
+        let mut config = Config::default();
+        config.replies.http.stages = vec!["strip_tags".to_string(), "shout".to_string()];
+        config.replies.discord.max_chars = Some(12);
+
+        let http = ReplyPipeline::for_channel(&config, ChannelKind::Http);
+        assert_eq!(http.stage_names(), vec!["strip_tags"]);
+        assert_eq!(http.process_joined("Sure [REACT:👍]"), "Sure");
+
+        // Discord keeps its tags for the bot and splits at max_chars
+        let discord = ReplyPipeline::for_channel(&config, ChannelKind::Discord);
+        assert_eq!(
+            discord.stage_names(),
+            vec!["strip_tags", "guardrails", "localize", "split"]
+        );
+        assert_eq!(
+            discord.process("Sure [REACT:x] thing"),
+            vec!["Sure", "[REACT:x]", "thing"]
+        );
+        assert!(discord.process("NO_REPLY").is_empty());
+
+        struct Upper;
+        impl ReplyStage for Upper {
+            fn name(&self) -> &str {
+                "upper"
+            }
+            fn apply(&self, parts: Vec<String>) -> Vec<String> {
+                parts.into_iter().map(|p| p.to_uppercase()).collect()
+            }
+        }
+        let custom = ReplyPipeline::new().with_stage(Box::new(Upper));
+        assert_eq!(custom.process(" hi "), vec!["HI"]);
+    }
+}
@@ -11,6 +11,7 @@ use std::sync::Mutex as StdMutex;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tracing::{debug, info};
 
+use super::resilience::ResilientProvider;
 use crate::config::Config;
 
 /// Image attachment for multimodal messages
@@ -215,7 +216,25 @@ fn normalize_model_id(provider: &str, model_id: &str) -> String {
     }
 }
 
+/// Create the provider for a model, with retries and a circuit breaker
+/// (see [`ResilientProvider`])
 pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvider>> {
+    let inner = create_base_provider(model, config)?;
+    let resilience = &config.providers.resilience;
+    let fallback = match resilience.fallback_model {
+        Some(ref fallback) if fallback != model => Some(create_base_provider(fallback, config)?),
+        _ => None,
+    };
+    // Aliases share the circuit of the model they stand for
+    Ok(Box::new(ResilientProvider::new(
+        &resolve_model_alias(model),
+        inner,
+        fallback,
+        resilience,
+    )?))
+}
+
+fn create_base_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvider>> {
     let workspace = config.workspace_path();
 
     // Resolve aliases first (e.g., "opus" → "anthropic/claude-opus-4-5")
@@ -0,0 +1,126 @@
+//! Per-message memory recall
+//!
+//! Before each turn the agent searches the memory index with the incoming
+//! message (plus a little recent context) and injects the most relevant
+//! snippets. The injected block is synthetic, like the security block: it is
+//! rebuilt every turn and never persisted in the session.
+
+use super::sanitize::{self, MemorySource};
+use crate::config::RecallConfig;
+use crate::memory::MemoryChunk;
+
+/// Files that are always loaded in full at session start; recalling
+/// snippets from them would only duplicate context.
+const ALWAYS_LOADED: &[&str] = &["MEMORY.md", "HEARTBEAT.md", "SOUL.md", "USER.md"];
+
+/// Maximum characters of prior user turns added to the recall query
+const QUERY_CONTEXT_CHARS: usize = 300;
+
+/// Build the search query from the incoming message and recent user turns
+pub fn build_recall_query(message: &str, recent_user_turns: &[String]) -> String {
+    let mut query = message.trim().to_string();
+    let mut budget = QUERY_CONTEXT_CHARS;
+
+    for turn in recent_user_turns.iter().rev() {
+        if budget == 0 {
+            break;
+        }
+        let snippet = crate::utils::safe_truncate(turn.trim(), budget);
+        budget = budget.saturating_sub(snippet.len());
+        query.push('\n');
+        query.push_str(snippet);
+    }
+
+    query
+}
+
+/// Filter search results by relevance and token budget and wrap them for
+/// injection. Returns `None` when nothing relevant was found.
+pub fn build_recall_context(
+    chunks: &[MemoryChunk],
+    config: &RecallConfig,
+    use_delimiters: bool,
+) -> Option<String> {
+    let mut used_tokens = 0;
+    let mut sections = Vec::new();
+
+    for chunk in chunks
+        .iter()
+        .filter(|c| c.score >= config.min_score)
+        .filter(|c| !ALWAYS_LOADED.contains(&c.file.as_str()))
+        .take(config.top_k)
+    {
+        // Same rough estimate the session uses (4 chars/token)
+        let tokens = chunk.content.len() / 4;
+        if used_tokens + tokens > config.max_tokens {
+            continue;
+        }
+        used_tokens += tokens;
+
+        let label = format!("{}#L{}-L{}", chunk.file, chunk.line_start, chunk.line_end);
+        if use_delimiters {
+            sections.push(sanitize::wrap_memory_content(
+                &label,
+                chunk.content.trim(),
+                MemorySource::DailyLog,
+            ));
+        } else {
+            sections.push(format!("## {}\n\n{}", label, chunk.content.trim()));
+        }
+    }
+
+    if sections.is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        "Possibly relevant memories (retrieved automatically for this message; \
+         ignore them if they don't apply):\n\n{}",
+        sections.join("\n\n")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(file: &str, content: &str, score: f64) -> MemoryChunk {
+        MemoryChunk::new(file.to_string(), 1, 3, content.to_string(), score)
+    }
+
+    fn config() -> RecallConfig {
+        RecallConfig {
+            enabled: true,
+            top_k: 2,
+            min_score: 0.5,
+            max_tokens: 100,
+        }
+    }
+
+    #[test]
+    fn test_filters_by_score_and_loaded_files() {
+        let chunks = vec![
+            chunk("MEMORY.md", "already in context", 0.9),
+            chunk("memory/2026-01-02.md", "low score", 0.1),
+            chunk("memory/2026-01-03.md", "bike repair shop on 5th", 0.8),
+        ];
+        let ctx = build_recall_context(&chunks, &config(), false).unwrap();
+        assert!(ctx.contains("bike repair shop"));
+        assert!(!ctx.contains("already in context"));
+        assert!(!ctx.contains("low score"));
+    }
+
+    #[test]
+    fn test_respects_token_budget() {
+        let big = "x".repeat(1000);
+        let chunks = vec![chunk("knowledge/a.md", &big, 0.9)];
+        assert!(build_recall_context(&chunks, &config(), true).is_none());
+    }
+
+    #[test]
+    fn test_build_recall_query() {
+        let turns = vec!["earlier question".to_string()];
+        let q = build_recall_query("  where is my bike?  ", &turns);
+        assert_eq!(q, "where is my bike?\nearlier question");
+    }
+}
@@ -0,0 +1,200 @@
+//! Adaptive reserve_tokens
+//!
+//! With `[agent.adaptive_reserve]`, the room kept free for the reply is
+//! sized from the replies actually seen instead of the static
+//! `agent.reserve_tokens`. The output tokens of every model call are
+//! recorded per scope (`discord:<channel_id>`, or `default` for sessions
+//! without a channel) in `~/.localgpt/reserve.sqlite`, keeping the most
+//! recent `window` calls. Once a scope has `min_samples`, its reserve is the
+//! configured percentile of those lengths times `margin`, between
+//! `min_tokens` and `agent.max_tokens` (no reply can be longer). Channels
+//! with short replies then use more of the context window before compacting,
+//! channels with long ones compact earlier. `GET /api/reserve` shows the
+//! statistics and the reserve of each scope.
+
+use anyhow::Result;
+use rusqlite::params;
+use serde::Serialize;
+use std::path::Path;
+
+use super::get_state_dir;
+use crate::config::AdaptiveReserveConfig;
+use crate::db::SqlitePool;
+
+/// Scope of sessions that aren't tied to a channel
+pub const DEFAULT_RESERVE_SCOPE: &str = "default";
+
+/// Reply lengths measured in one scope (output tokens)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CompletionStats {
+    pub scope: String,
+    pub samples: usize,
+    pub p50: u64,
+    pub p90: u64,
+    pub p95: u64,
+    pub p99: u64,
+    pub max: u64,
+    /// The configured percentile
+    #[serde(skip)]
+    percentile: u64,
+}
+
+impl CompletionStats {
+    /// Statistics of a scope's lengths at `percentile`
+    pub fn from_lengths(scope: &str, mut lengths: Vec<u64>, percentile: u8) -> Option<Self> {
+        if lengths.is_empty() {
+            return None;
+        }
+        lengths.sort_unstable();
+        Some(Self {
+            scope: scope.to_string(),
+            samples: lengths.len(),
+            p50: nearest_rank(&lengths, 50),
+            p90: nearest_rank(&lengths, 90),
+            p95: nearest_rank(&lengths, 95),
+            p99: nearest_rank(&lengths, 99),
+            max: *lengths.last().unwrap_or(&0),
+            percentile: nearest_rank(&lengths, percentile),
+        })
+    }
+}
+
+/// Nearest-rank percentile of sorted values
+fn nearest_rank(sorted: &[u64], percentile: u8) -> u64 {
+    let rank = (usize::from(percentile.min(100)) * sorted.len()).div_ceil(100);
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+impl AdaptiveReserveConfig {
+    /// The reserve for a scope with these statistics; None until it has
+    /// enough samples (the static `reserve_tokens` applies)
+    pub fn reserve_for(&self, stats: &CompletionStats, max_tokens: usize) -> Option<usize> {
+        if !self.enabled || stats.samples < self.min_samples.max(1) {
+            return None;
+        }
+        let reserve = (stats.percentile as f64 * self.margin.max(1.0)).ceil() as usize;
+        Some(reserve.clamp(self.min_tokens, max_tokens.max(self.min_tokens)))
+    }
+}
+
+#[derive(Clone)]
+pub struct ReserveStore {
+    pool: SqlitePool,
+}
+
+impl ReserveStore {
+    /// Open the shared database at `~/.localgpt/reserve.sqlite`
+    pub fn open_default() -> Result<Self> {
+        Self::open(&get_state_dir()?.join("reserve.sqlite"))
+    }
+
+    pub fn open(db_path: &Path) -> Result<Self> {
+        Self::init(SqlitePool::shared(db_path, None)?)
+    }
+
+    /// In-memory store (tests)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(SqlitePool::open_in_memory(None)?)
+    }
+
+    fn init(pool: SqlitePool) -> Result<Self> {
+        pool.get()?.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS completions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                scope TEXT NOT NULL,
+                output_tokens INTEGER NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_completions_scope ON completions(scope, id);
+            "#,
+        )?;
+
+        Ok(Self { pool })
+    }
+
+    /// Record a reply's length, keeping the scope's most recent `window`
+    pub fn record(&self, scope: &str, output_tokens: u64, window: usize) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO completions (scope, output_tokens, created_at) VALUES (?1, ?2, ?3)",
+            params![scope, output_tokens as i64, chrono::Utc::now().timestamp()],
+        )?;
+        tx.execute(
+            "DELETE FROM completions WHERE scope = ?1 AND id NOT IN
+                 (SELECT id FROM completions WHERE scope = ?1 ORDER BY id DESC LIMIT ?2)",
+            params![scope, window.max(1) as i64],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Statistics of one scope, None before its first reply
+    pub fn stats(&self, scope: &str, percentile: u8) -> Result<Option<CompletionStats>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare("SELECT output_tokens FROM completions WHERE scope = ?1")?;
+        let lengths = stmt
+            .query_map(params![scope], |row| row.get::<_, i64>(0))?
+            .map(|length| length.map(|l| l.max(0) as u64))
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(CompletionStats::from_lengths(scope, lengths, percentile))
+    }
+
+    /// Statistics of every scope, busiest first
+    pub fn all_stats(&self, percentile: u8) -> Result<Vec<CompletionStats>> {
+        let scopes: Vec<String> = {
+            let conn = self.pool.get()?;
+            let mut stmt = conn.prepare(
+                "SELECT scope FROM completions GROUP BY scope ORDER BY COUNT(*) DESC, scope",
+            )?;
+            let scopes = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            scopes
+        };
+        let mut all = Vec::new();
+        for scope in scopes {
+            all.extend(self.stats(&scope, percentile)?);
+        }
+        Ok(all)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_and_reserve() {
+        let store = ReserveStore::open_in_memory().unwrap();
+        for tokens in 1..=100 {
+            store.record("discord:1", tokens * 10, 50).unwrap();
+        }
+        store.record("default", 4000, 50).unwrap();
+
+        // Only the last 50 replies (510..=1000) are kept
+        let stats = store.stats("discord:1", 95).unwrap().unwrap();
+        assert_eq!(stats.samples, 50);
+        assert_eq!(stats.p50, 750);
+        assert_eq!(stats.p95, 980);
+        assert_eq!(stats.max, 1000);
+        assert!(store.stats("discord:2", 95).unwrap().is_none());
+        assert_eq!(store.all_stats(95).unwrap()[0].scope, "discord:1");
+
+        let mut config = AdaptiveReserveConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        assert_eq!(config.reserve_for(&stats, 4096), Some(1225));
+        config.min_tokens = 2000;
+        assert_eq!(config.reserve_for(&stats, 4096), Some(2000));
+        let long = store.stats("default", 95).unwrap().unwrap();
+        assert_eq!(config.reserve_for(&long, 4096), None);
+        config.min_samples = 1;
+        assert_eq!(config.reserve_for(&long, 4096), Some(4096));
+        config.enabled = false;
+        assert_eq!(config.reserve_for(&stats, 4096), None);
+    }
+}
@@ -0,0 +1,405 @@
+//! Retries and circuit breaking for provider calls
+//!
+//! [`create_provider`](super::create_provider) wraps every provider in a
+//! [`ResilientProvider`]. Transient failures (connection errors, timeouts,
+//! 5xx responses, overload and rate limits) are retried with exponential
+//! backoff. Failed requests in a row open a circuit shared by every agent
+//! using the model: until the cooldown has passed, calls go to the fallback
+//! model or get the configured apology instead of each waiting out its own
+//! retries. One call is then let through to probe the provider.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use super::providers::{LLMProvider, LLMResponse, Message, StreamChunk, StreamResult, ToolSchema};
+use crate::config::{ResilienceConfig, parse_duration};
+
+/// A half-open probe that hasn't finished by then (e.g. its task was
+/// cancelled) no longer blocks the next one
+const PROBE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Circuits by model, shared by all agents in the process
+static BREAKERS: LazyLock<Mutex<HashMap<String, Arc<CircuitBreaker>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    /// Cooldown is over; the next call probes the provider
+    HalfOpen,
+}
+
+/// A provider circuit as shown on `/health`
+#[derive(Debug, Clone, Serialize)]
+pub struct CircuitStatus {
+    pub model: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    /// Seconds until an open circuit lets a probe through
+    pub retry_in_secs: Option<u64>,
+}
+
+/// State of every provider circuit used so far, sorted by model
+pub fn circuit_statuses() -> Vec<CircuitStatus> {
+    let breakers = BREAKERS.lock().unwrap_or_else(|e| e.into_inner());
+    let mut statuses: Vec<CircuitStatus> = breakers
+        .iter()
+        .map(|(model, breaker)| breaker.status(model))
+        .collect();
+    statuses.sort_by(|a, b| a.model.cmp(&b.model));
+    statuses
+}
+
+fn breaker_for(model: &str, threshold: u32, cooldown: Duration) -> Arc<CircuitBreaker> {
+    let mut breakers = BREAKERS.lock().unwrap_or_else(|e| e.into_inner());
+    Arc::clone(
+        breakers
+            .entry(model.to_string())
+            .or_insert_with(|| Arc::new(CircuitBreaker::new(threshold, cooldown))),
+    )
+}
+
+#[derive(Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// When the in-flight half-open probe started
+    probe_started: Option<Instant>,
+    last_error: Option<String>,
+}
+
+pub struct CircuitBreaker {
+    /// 0 = never open
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Whether a call may go to the provider. After the cooldown a single
+    /// probe is allowed; everything else waits for its outcome.
+    pub fn allow(&self) -> bool {
+        let mut state = self.lock();
+        match state.opened_at {
+            None => true,
+            Some(opened) if opened.elapsed() < self.cooldown => false,
+            Some(_)
+                if state
+                    .probe_started
+                    .is_some_and(|t| t.elapsed() < PROBE_TIMEOUT) =>
+            {
+                false
+            }
+            Some(_) => {
+                state.probe_started = Some(Instant::now());
+                true
+            }
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut state = self.lock();
+        if state.opened_at.is_some() {
+            info!("Provider recovered, closing circuit");
+        }
+        *state = BreakerState::default();
+    }
+
+    pub fn record_failure(&self, error: &str) {
+        let mut state = self.lock();
+        state.consecutive_failures += 1;
+        state.last_error = Some(error.to_string());
+
+        let reopen = state.probe_started.is_some();
+        let trip = self.threshold > 0 && state.consecutive_failures >= self.threshold;
+        if reopen || (trip && state.opened_at.is_none()) {
+            warn!(
+                "Opening provider circuit for {:?} after {} failures: {}",
+                self.cooldown, state.consecutive_failures, error
+            );
+            state.opened_at = Some(Instant::now());
+        }
+        state.probe_started = None;
+    }
+
+    pub fn state(&self) -> CircuitState {
+        let state = self.lock();
+        match state.opened_at {
+            None => CircuitState::Closed,
+            Some(opened) if opened.elapsed() < self.cooldown => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    fn status(&self, model: &str) -> CircuitStatus {
+        let circuit = self.state();
+        let state = self.lock();
+        CircuitStatus {
+            model: model.to_string(),
+            state: circuit,
+            consecutive_failures: state.consecutive_failures,
+            last_error: state.last_error.clone(),
+            retry_in_secs: match (circuit, state.opened_at) {
+                (CircuitState::Open, Some(opened)) => {
+                    Some(self.cooldown.saturating_sub(opened.elapsed()).as_secs())
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Whether an error is worth retrying: the provider may answer next time
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            if e.is_timeout() || e.is_connect() || e.is_request() {
+                return true;
+            }
+            if let Some(status) = e.status() {
+                return status.is_server_error() || status.as_u16() == 429;
+            }
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            use std::io::ErrorKind;
+            if matches!(
+                e.kind(),
+                ErrorKind::ConnectionRefused
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::TimedOut
+            ) {
+                return true;
+            }
+        }
+    }
+
+    // API errors only arrive as text in the response body
+    let text = format!("{:#}", error).to_lowercase();
+    [
+        "overloaded",
+        "rate_limit",
+        "rate limit",
+        "server_error",
+        "api_error",
+        "service unavailable",
+        "bad gateway",
+        "timed out",
+        "connection refused",
+        "connection reset",
+    ]
+    .iter()
+    .any(|pattern| text.contains(pattern))
+}
+
+/// Delay before retry `attempt` (0-based): initial, then doubled, capped
+pub fn backoff_delay(config: &ResilienceConfig, attempt: u32) -> Duration {
+    let delay = config
+        .initial_backoff_ms
+        .saturating_mul(2u64.saturating_pow(attempt));
+    Duration::from_millis(delay.min(config.max_backoff_ms))
+}
+
+pub struct ResilientProvider {
+    inner: Box<dyn LLMProvider>,
+    fallback: Option<Box<dyn LLMProvider>>,
+    breaker: Arc<CircuitBreaker>,
+    config: ResilienceConfig,
+}
+
+impl ResilientProvider {
+    pub fn new(
+        model: &str,
+        inner: Box<dyn LLMProvider>,
+        fallback: Option<Box<dyn LLMProvider>>,
+        config: &ResilienceConfig,
+    ) -> Result<Self> {
+        let cooldown = parse_duration(&config.cooldown)
+            .map_err(|e| anyhow::anyhow!("Invalid providers.resilience.cooldown: {}", e))?;
+        Ok(Self {
+            inner,
+            fallback,
+            breaker: breaker_for(model, config.failure_threshold, cooldown),
+            config: config.clone(),
+        })
+    }
+
+    /// Run `call` against the provider with retries, recording the outcome
+    /// on the circuit. `None` means the circuit is open.
+    async fn guarded<'a, T, F, Fut>(&'a self, call: F) -> Option<Result<T>>
+    where
+        F: Fn(&'a dyn LLMProvider) -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        if !self.breaker.allow() {
+            return None;
+        }
+
+        let mut attempt = 0;
+        loop {
+            match call(self.inner.as_ref()).await {
+                Ok(value) => {
+                    self.breaker.record_success();
+                    return Some(Ok(value));
+                }
+                Err(e) if is_transient(&e) && attempt < self.config.max_retries => {
+                    let delay = backoff_delay(&self.config, attempt);
+                    warn!(
+                        "Provider call failed (attempt {}), retrying in {:?}: {}",
+                        attempt + 1,
+                        delay,
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    // Bad requests say nothing about the provider's health
+                    if is_transient(&e) {
+                        self.breaker.record_failure(&e.to_string());
+                    } else {
+                        self.breaker.record_success();
+                    }
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl LLMProvider for ResilientProvider {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<LLMResponse> {
+        if let Some(result) = self.guarded(|p| p.chat(messages, tools)).await {
+            return result;
+        }
+        match self.fallback {
+            Some(ref fallback) => fallback.chat(messages, tools).await,
+            None => Ok(LLMResponse::text(self.config.fallback_message.clone())),
+        }
+    }
+
+    async fn summarize(&self, text: &str) -> Result<String> {
+        if let Some(result) = self.guarded(|p| p.summarize(text)).await {
+            return result;
+        }
+        match self.fallback {
+            Some(ref fallback) => fallback.summarize(text).await,
+            None => anyhow::bail!("Provider unavailable (circuit open)"),
+        }
+    }
+
+    fn reset_session(&self) {
+        self.inner.reset_session();
+        if let Some(ref fallback) = self.fallback {
+            fallback.reset_session();
+        }
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        // Only opening the stream is retried; errors mid-stream are not
+        if let Some(result) = self.guarded(|p| p.chat_stream(messages, tools)).await {
+            return result;
+        }
+        match self.fallback {
+            Some(ref fallback) => fallback.chat_stream(messages, tools).await,
+            None => {
+                let text = self.config.fallback_message.clone();
+                Ok(Box::pin(futures::stream::once(async move {
+                    Ok(StreamChunk {
+                        delta: text,
+                        done: true,
+                        tool_calls: None,
+                    })
+                })))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_opens_and_probes() {
+        let breaker = CircuitBreaker::new(2, Duration::ZERO);
+        assert!(breaker.allow());
+        breaker.record_failure("timed out");
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.record_failure("timed out");
+
+        // Zero cooldown: immediately half-open, one probe at a time
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.allow());
+        assert!(!breaker.allow());
+
+        // A failed probe reopens, a successful one closes
+        breaker.record_failure("timed out");
+        assert!(breaker.allow());
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.allow());
+    }
+
+    #[test]
+    fn test_open_circuit_rejects_calls() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+        breaker.record_failure("503 Service Unavailable");
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allow());
+        assert_eq!(
+            breaker.status("ollama/llama3").retry_in_secs.map(|s| s > 0),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(&anyhow::anyhow!(
+            r#"Anthropic API error: {{"type":"overloaded_error","message":"Overloaded"}}"#
+        )));
+        assert!(is_transient(&anyhow::anyhow!(std::io::Error::from(
+            std::io::ErrorKind::ConnectionRefused
+        ))));
+        assert!(!is_transient(&anyhow::anyhow!(
+            r#"OpenAI API error: {{"type":"invalid_request_error"}}"#
+        )));
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let config = ResilienceConfig::default();
+        assert_eq!(backoff_delay(&config, 0), Duration::from_millis(500));
+        assert_eq!(backoff_delay(&config, 2), Duration::from_millis(2000));
+        assert_eq!(backoff_delay(&config, 10), Duration::from_millis(8000));
+    }
+}
@@ -0,0 +1,222 @@
+//! Web page screenshots
+//!
+//! With the `screenshot` build feature and `[tools.screenshot]`, the
+//! `screenshot` tool renders a URL in headless Chromium and has a vision
+//! model answer a question about the result ("what does this page look
+//! like?", "is the banner showing?"). Only `allowed_domains` and their
+//! subdomains can be rendered. Screenshots are kept in
+//! `~/.localgpt/screenshots/` and reused for `cache_minutes`, so asking
+//! several questions about one page renders it once.
+
+use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
+use base64::Engine;
+use chromiumoxide::browser::{Browser, BrowserConfig};
+use chromiumoxide::cdp::browser_protocol::page::CaptureScreenshotFormat;
+use chromiumoxide::page::ScreenshotParams;
+use futures::StreamExt;
+use serde_json::{Value, json};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tracing::debug;
+
+use super::providers::{
+    ImageAttachment, LLMResponseContent, Message, Role, ToolSchema, create_provider,
+};
+use super::tools::Tool;
+use crate::config::{Config, ScreenshotConfig};
+use crate::memory::hash_text;
+
+const DEFAULT_QUESTION: &str =
+    "Describe what this web page looks like: layout, visible text, images and anything broken.";
+
+pub struct ScreenshotTool {
+    config: ScreenshotConfig,
+    app_config: Config,
+    cache_dir: PathBuf,
+}
+
+impl ScreenshotTool {
+    pub fn new(config: ScreenshotConfig, app_config: Config, cache_dir: PathBuf) -> Self {
+        Self {
+            config,
+            app_config,
+            cache_dir,
+        }
+    }
+
+    /// The page's screenshot (PNG), from the cache while it is fresh
+    async fn screenshot(&self, url: &str) -> Result<Vec<u8>> {
+        let key = hash_text(&format!(
+            "{}|{}x{}",
+            url, self.config.width, self.config.height
+        ));
+        let path = self.cache_dir.join(format!("{}.png", &key[..32]));
+        let max_age = Duration::from_secs(self.config.cache_minutes * 60);
+        let fresh = std::fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age < max_age);
+        if fresh && let Ok(png) = std::fs::read(&path) {
+            debug!("Using cached screenshot of {}", url);
+            return Ok(png);
+        }
+
+        let png = tokio::time::timeout(
+            Duration::from_secs(self.config.timeout_secs),
+            capture(&self.config, url),
+        )
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "{} took longer than {}s to load",
+                url,
+                self.config.timeout_secs
+            )
+        })??;
+
+        if self.config.cache_minutes > 0 {
+            std::fs::create_dir_all(&self.cache_dir)?;
+            std::fs::write(&path, &png)?;
+        }
+        Ok(png)
+    }
+}
+
+#[async_trait]
+impl Tool for ScreenshotTool {
+    fn name(&self) -> &str {
+        "screenshot"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "screenshot".to_string(),
+            description: "Render a web page in a browser and look at it. Use to check how a \
+                          page looks or whether something is visible on it."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "url": {
+                        "type": "string",
+                        "description": "The http(s) URL to render"
+                    },
+                    "question": {
+                        "type": "string",
+                        "description": "What to look for (default: describe the page)"
+                    }
+                },
+                "required": ["url"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let url = args["url"].as_str().ok_or_else(|| anyhow!("Missing url"))?;
+        let question = args["question"].as_str().unwrap_or(DEFAULT_QUESTION);
+
+        let parsed = url::Url::parse(url).context("Invalid URL")?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            bail!("Only http and https URLs can be rendered");
+        }
+        let host = parsed.host_str().unwrap_or_default();
+        if !domain_allowed(&self.config.allowed_domains, host) {
+            bail!("{} is not in tools.screenshot.allowed_domains", host);
+        }
+
+        let png = self.screenshot(url).await?;
+        let model = self
+            .config
+            .vision_model
+            .clone()
+            .unwrap_or_else(|| self.app_config.agent.default_model.clone());
+        let provider = create_provider(&model, &self.app_config)?;
+        let messages = vec![Message {
+            role: Role::User,
+            content: format!("This is a screenshot of {}.\n\n{}", url, question),
+            tool_calls: None,
+            tool_call_id: None,
+            images: vec![ImageAttachment {
+                data: base64::engine::general_purpose::STANDARD.encode(&png),
+                media_type: "image/png".to_string(),
+            }],
+        }];
+        let response = provider.chat(&messages, None).await?;
+        let LLMResponseContent::Text(answer) = response.content else {
+            bail!("vision model returned tool calls");
+        };
+
+        Ok(format!(
+            "Screenshot of {} ({}x{}):\n{}",
+            url,
+            self.config.width,
+            self.config.height,
+            answer.trim()
+        ))
+    }
+}
+
+/// Render a page in a fresh headless browser
+async fn capture(config: &ScreenshotConfig, url: &str) -> Result<Vec<u8>> {
+    let mut builder = BrowserConfig::builder()
+        .window_size(config.width, config.height)
+        .viewport(None);
+    if let Some(ref path) = config.chrome_path {
+        builder = builder.chrome_executable(shellexpand::tilde(path).as_ref());
+    }
+    let browser_config = builder.build().map_err(|e| anyhow!(e))?;
+    let (mut browser, mut handler) = Browser::launch(browser_config)
+        .await
+        .context("Failed to start Chromium")?;
+    let events = tokio::spawn(async move { while handler.next().await.is_some() {} });
+
+    let result = async {
+        let page = browser.new_page(url).await?;
+        page.wait_for_navigation().await?;
+        page.screenshot(
+            ScreenshotParams::builder()
+                .format(CaptureScreenshotFormat::Png)
+                .build(),
+        )
+        .await
+    }
+    .await;
+
+    let _ = browser.close().await;
+    events.abort();
+    Ok(result?)
+}
+
+/// Whether a host is one of the allowed domains or a subdomain of one
+fn domain_allowed(allowed: &[String], host: &str) -> bool {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    allowed.iter().any(|domain| {
+        let domain = domain.trim().trim_start_matches("*.").to_ascii_lowercase();
+        domain == "*"
+            || host == domain
+            || host
+                .strip_suffix(&domain)
+                .is_some_and(|rest| rest.ends_with('.'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_allowed() {
+        let allowed = vec!["example.com".to_string(), "*.Docs.rs".to_string()];
+        assert!(domain_allowed(&allowed, "example.com"));
+        assert!(domain_allowed(&allowed, "www.example.com"));
+        assert!(domain_allowed(&allowed, "tokio.docs.rs"));
+        assert!(domain_allowed(&allowed, "docs.rs"));
+        assert!(!domain_allowed(&allowed, "badexample.com"));
+        assert!(!domain_allowed(&allowed, "example.com.evil.net"));
+        assert!(!domain_allowed(&[], "example.com"));
+        assert!(domain_allowed(&["*".to_string()], "localhost"));
+    }
+}
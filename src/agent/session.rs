@@ -2,6 +2,7 @@
 //!
 //! JSONL format matches Pi's SessionManager for OpenClaw compatibility:
 //! - Header: {type: "session", version, id, timestamp, cwd}
+//!   (plus LocalGPT extensions such as `modelChanges`)
 //! - Messages: {type: "message", message: {role, content, ...}}
 
 use anyhow::Result;
@@ -29,6 +30,15 @@ pub struct Session {
     token_count: usize,
     compaction_count: u32,
     memory_flush_compaction_count: u32,
+    model_changes: Vec<ModelChange>,
+}
+
+/// A model switch during a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelChange {
+    pub timestamp: DateTime<Utc>,
+    pub from: String,
+    pub to: String,
 }
 
 /// Message with metadata for persistence
@@ -141,6 +151,7 @@ impl Session {
             token_count: 0,
             compaction_count: 0,
             memory_flush_compaction_count: 0,
+            model_changes: Vec::new(),
         }
     }
 
@@ -148,6 +159,10 @@ impl Session {
         &self.id
     }
 
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
     pub fn token_count(&self) -> usize {
         self.token_count
     }
@@ -164,6 +179,19 @@ impl Session {
         self.memory_flush_compaction_count = self.compaction_count + 1;
     }
 
+    /// Model switches made during this session, oldest first
+    pub fn model_changes(&self) -> &[ModelChange] {
+        &self.model_changes
+    }
+
+    pub fn record_model_change(&mut self, from: &str, to: &str) {
+        self.model_changes.push(ModelChange {
+            timestamp: Utc::now(),
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+    }
+
     pub fn set_system_context(&mut self, context: String) {
         self.system_context = Some(context);
         self.recalculate_tokens();
@@ -306,7 +334,8 @@ impl Session {
             "cwd": self.cwd,
             // LocalGPT extensions (ignored by Pi but preserved)
             "compactionCount": self.compaction_count,
-            "memoryFlushCompactionCount": self.memory_flush_compaction_count
+            "memoryFlushCompactionCount": self.memory_flush_compaction_count,
+            "modelChanges": self.model_changes
         });
         writeln!(file, "{}", serde_json::to_string(&header)?)?;
 
@@ -423,6 +452,18 @@ impl Session {
         Self::load_from_path(&path, session_id)
     }
 
+    /// Load a saved session for a specific agent
+    pub fn load_for_agent(agent_id: &str, session_id: &str) -> Result<Self> {
+        let dir = get_sessions_dir_for_agent(agent_id)?;
+        let path = dir.join(format!("{}.jsonl", session_id));
+
+        if !path.exists() {
+            anyhow::bail!("Session not found: {}", session_id);
+        }
+
+        Self::load_from_path(&path, session_id)
+    }
+
     fn load_from_path(path: &PathBuf, session_id: &str) -> Result<Self> {
         let file = File::open(path)?;
         let reader = BufReader::new(file);
@@ -436,6 +477,7 @@ impl Session {
             token_count: 0,
             compaction_count: 0,
             memory_flush_compaction_count: 0,
+            model_changes: Vec::new(),
         };
 
         for line in reader.lines() {
@@ -466,6 +508,9 @@ impl Session {
                     if let Some(count) = entry["memoryFlushCompactionCount"].as_u64() {
                         session.memory_flush_compaction_count = count as u32;
                     }
+                    if let Ok(changes) = serde_json::from_value(entry["modelChanges"].clone()) {
+                        session.model_changes = changes;
+                    }
                 }
                 // Pi format message
                 Some("message") => {
@@ -799,4 +844,18 @@ mod tests {
         assert_eq!(msg_usage.output, 50);
         assert_eq!(msg_usage.total_tokens, 150);
     }
+
+    #[test]
+    fn test_model_changes_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("s.jsonl");
+
+        let mut session = Session::new();
+        session.record_model_change("claude-cli/opus", "openai/gpt-4o");
+        session.save_to_path(&path).unwrap();
+
+        let loaded = Session::load_from_path(&path, session.id()).unwrap();
+        assert_eq!(loaded.model_changes(), session.model_changes());
+        assert_eq!(loaded.model_changes()[0].to, "openai/gpt-4o");
+    }
 }
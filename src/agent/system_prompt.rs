@@ -5,6 +5,8 @@
 
 use std::path::Path;
 
+use super::locale::{Fragment, Language};
+
 /// Special tokens for silent replies
 pub const SILENT_REPLY_TOKEN: &str = "NO_REPLY";
 pub const HEARTBEAT_OK_TOKEN: &str = "HEARTBEAT_OK";
@@ -18,6 +20,14 @@ pub fn build_system_prompt(params: SystemPromptParams) -> String {
     lines.push("You are a personal assistant running inside LocalGPT.".to_string());
     lines.push(String::new());
 
+    // Reply language (non-English configurations)
+    let reply_language = params.language.text(Fragment::ReplyLanguage);
+    if !reply_language.is_empty() {
+        lines.push("## Language".to_string());
+        lines.push(reply_language.to_string());
+        lines.push(String::new());
+    }
+
     // Safety section (inspired by Anthropic's constitution)
     lines.push("## Safety".to_string());
     lines.push(
@@ -61,16 +71,7 @@ pub fn build_system_prompt(params: SystemPromptParams) -> String {
 
         // Tool call style guidance
         lines.push("## Tool Call Style".to_string());
-        lines.push(
-            "Default: do not narrate routine, low-risk tool calls (just call the tool)."
-                .to_string(),
-        );
-        lines.push(
-            "Narrate only when it helps: multi-step work, complex problems, sensitive actions \
-             (e.g., deletions), or when the user explicitly asks."
-                .to_string(),
-        );
-        lines.push("Keep narration brief and value-dense.".to_string());
+        lines.push(params.language.text(Fragment::ToolCallStyle).to_string());
         lines.push(String::new());
     }
 
@@ -112,11 +113,16 @@ pub fn build_system_prompt(params: SystemPromptParams) -> String {
     );
     lines.push("- HEARTBEAT.md: Pending tasks for autonomous execution".to_string());
     lines.push("- SOUL.md: Your persona and tone guidance (if present)".to_string());
-    lines.push("- memory/YYYY-MM-DD.md: Daily logs for session notes".to_string());
+    lines.push(
+        "- memory/YYYY-MM-DD/<topic>.md: Daily logs, one file per topic (written with memory_log)"
+            .to_string(),
+    );
     lines.push(String::new());
     lines.push(
-        "To save information: use write_file or edit_file to update memory files directly. \
-         Use MEMORY.md for important persistent facts (names, preferences). \
+        "To save information: use save_memory for single facts, preferences, events and tasks \
+         (it files them in MEMORY.md or today's log), memory_log for notes about the \
+         conversation, filed under a short topic name, and write_file or edit_file to \
+         reorganize MEMORY.md. \
          Sessions are auto-saved to memory/ when starting a new session."
             .to_string(),
     );
@@ -191,6 +197,11 @@ pub fn build_system_prompt(params: SystemPromptParams) -> String {
     lines.push(
         "- Multiple reactions: [REACT:☁\u{fe0f}][REACT:✨] Your message here".to_string(),
     );
+    lines.push(
+        "- The server's custom emoji by name: [REACT:pepela]. Custom emoji and stickers \
+         users send appear as :name: and [sent the \"name\" sticker]."
+            .to_string(),
+    );
     lines.push(String::new());
     lines.push("Use reactions when:".to_string());
     lines.push("- You want to acknowledge a message without a full reply".to_string());
@@ -211,8 +222,8 @@ pub fn build_system_prompt(params: SystemPromptParams) -> String {
     lines.push("### List Channels".to_string());
     lines.push("Format: [LIST:guild_id]".to_string());
     lines.push(
-        "Lists all text channels in the specified guild (server). \
-         Example: [LIST:123456789]"
+        "Lists the text, announcement and forum channels in the specified guild (server), \
+         each with its type. Example: [LIST:123456789]"
             .to_string(),
     );
     lines.push(String::new());
@@ -224,6 +235,24 @@ pub fn build_system_prompt(params: SystemPromptParams) -> String {
             .to_string(),
     );
     lines.push(String::new());
+    lines.push("### Post to Another Channel".to_string());
+    lines.push("Format: [POST:channel_id] message or [PUBLISH:channel_id] message".to_string());
+    lines.push(
+        "Posts the message to another channel. In a forum channel this starts a new post, \
+         titled with the message's first line. Use PUBLISH for announcement channels when \
+         asked to publish: the message is also sent to servers following the channel."
+            .to_string(),
+    );
+    lines.push(String::new());
+    lines.push("### Remind Someone".to_string());
+    lines.push("Format: [REMIND:user:when] message".to_string());
+    lines.push(
+        "Sends the message to the user by DM at the given time. user is me (whoever you are \
+         answering), a mention like <@123> or a user ID; when is a delay (30m, 2h, 1d) or a \
+         local time (2026-01-31 09:00). Example: [REMIND:me:2h] Check the build"
+            .to_string(),
+    );
+    lines.push(String::new());
     lines.push("Notes:".to_string());
     lines.push("- Only channels in configured guilds are accessible".to_string());
     lines.push(
@@ -235,6 +264,43 @@ pub fn build_system_prompt(params: SystemPromptParams) -> String {
     );
     lines.push(String::new());
 
+    // Discord Embeds section
+    lines.push("## Discord Embeds".to_string());
+    lines.push(
+        "For structured answers (search hits, memory lookups, lists of items), wrap a JSON \
+         object in [EMBED]...[/EMBED] to send it as a rich embed instead of a wall of text."
+            .to_string(),
+    );
+    lines.push(
+        "Format: [EMBED]{\"title\": \"...\", \"description\": \"...\", \"color\": \"#5865f2\", \
+         \"fields\": [{\"name\": \"...\", \"value\": \"...\", \"inline\": false}], \
+         \"footer\": \"...\"}[/EMBED]"
+            .to_string(),
+    );
+    lines.push(
+        "- Use one field per search hit or memory snippet (e.g. name = file, value = excerpt)"
+            .to_string(),
+    );
+    lines.push("- Text outside the block is sent as a normal message".to_string());
+    lines.push(String::new());
+
+    // Discord choices section
+    lines.push("## Discord Choices".to_string());
+    lines.push(
+        "To let the user pick from a few options (next step of a guided flow, a format, a \
+         time slot), add [CHOICES]...[/CHOICES] with a JSON object. Up to five short options \
+         are shown as buttons, more as a select menu; the pick comes back as the user's next \
+         message."
+            .to_string(),
+    );
+    lines.push(
+        "Format: [CHOICES]{\"prompt\": \"...\", \"options\": [\"...\", {\"label\": \"...\", \
+         \"description\": \"...\"}], \"multiple\": false}[/CHOICES]"
+            .to_string(),
+    );
+    lines.push("- One block per reply; ask the question in the prompt".to_string());
+    lines.push(String::new());
+
     // Runtime info
     lines.push("## Runtime".to_string());
     let mut runtime_parts = vec![format!("model={}", params.model)];
@@ -257,6 +323,7 @@ pub struct SystemPromptParams<'a> {
     pub current_time: Option<String>,
     pub timezone: Option<String>,
     pub skills_prompt: Option<String>,
+    pub language: Language,
 }
 
 impl<'a> SystemPromptParams<'a> {
@@ -281,9 +348,15 @@ impl<'a> SystemPromptParams<'a> {
                 Some(timezone)
             },
             skills_prompt: None,
+            language: Language::default(),
         }
     }
 
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
     pub fn with_tools(mut self, tools: Vec<&'a str>) -> Self {
         self.tool_names = tools;
         self
@@ -306,26 +379,36 @@ fn get_tool_summary(tool_name: &str) -> &'static str {
         "edit_file" => "Make precise edits to files",
         "memory_search" => "Semantically search MEMORY.md + memory/*.md",
         "memory_get" => "Fetch specific lines from memory files (use after memory_search)",
+        "memory_log" => "Add a note to today's log under a topic",
+        "save_memory" => "Remember a fact, preference, event or task",
         "web_fetch" => "Fetch and extract content from a URL",
+        "task_create" => "Track a new task/todo across sessions",
+        "task_update" => "Change a tracked task's details or status",
+        "task_complete" => "Mark a tracked task as done",
+        "task_list" => "List tracked tasks",
+        "weather" => "Current weather and forecast for a place",
+        "list_events" => "List upcoming calendar events",
+        "create_event" => "Add an event to the calendar",
+        "job_start" => "Run long work as a background job",
+        "job_status" => "Check a background job's progress or result",
+        "job_cancel" => "Cancel a background job",
+        "job_progress" => "Report progress on the current job",
         _ => "Tool",
     }
 }
 
 /// Build the heartbeat prompt for autonomous task polling
 /// If workspace_is_git is true, includes instruction to commit changes
-pub fn build_heartbeat_prompt(workspace_is_git: bool) -> String {
+pub fn build_heartbeat_prompt(workspace_is_git: bool, language: Language) -> String {
     let git_instruction = if workspace_is_git {
-        " After completing tasks that modify files, commit the changes with a descriptive message."
+        language.text(Fragment::HeartbeatGitInstruction)
     } else {
         ""
     };
-    format!(
-        "Read HEARTBEAT.md if it exists. Follow it strictly. \
-         Mark completed tasks with [x] — do NOT delete or clear tasks. \
-         Do not infer or repeat old tasks from prior chats.{} \
-         If nothing needs attention, reply {}.",
-        git_instruction, HEARTBEAT_OK_TOKEN
-    )
+    language
+        .text(Fragment::HeartbeatPrompt)
+        .replace("{git}", git_instruction)
+        .replace("{ok}", HEARTBEAT_OK_TOKEN)
 }
 
 /// Check if a response is a heartbeat acknowledgment (nothing to do)
@@ -0,0 +1,149 @@
+//! Limits for the agentic tool loop
+//!
+//! Within one turn the model may call tools, see the results and call more.
+//! [`ToolLoopGuard`] bounds that loop: a maximum number of rounds, refusal of
+//! calls repeated with identical arguments (a model stuck re-reading the same
+//! file), and a per-round budget for how much tool output is fed back.
+
+use std::collections::HashMap;
+
+use super::providers::ToolCall;
+use crate::config::ToolLoopConfig;
+
+/// Sent to the model instead of executing a repeated call
+pub(super) const REPEATED_CALL_OUTPUT: &str = "Not executed: this exact call was already made \
+     in this turn. Use the earlier result, or try something different.";
+
+/// Appended to the conversation when the loop is cut short
+pub(super) fn wrap_up_prompt(reason: &str) -> String {
+    format!(
+        "[Tool loop stopped: {}] Do not call any more tools. Answer now with the \
+         information you already have, and say briefly if something is incomplete.",
+        reason
+    )
+}
+
+pub(super) struct ToolLoopGuard {
+    max_iterations: usize,
+    max_repeated_calls: usize,
+    /// Output budget per round in characters (0 = unlimited)
+    budget_chars: usize,
+    iteration: usize,
+    call_counts: HashMap<(String, String), usize>,
+}
+
+impl ToolLoopGuard {
+    pub fn new(config: &ToolLoopConfig) -> Self {
+        Self {
+            max_iterations: config.max_iterations.max(1),
+            max_repeated_calls: config.max_repeated_calls.max(1),
+            // Same 4 chars/token estimate as session token counting
+            budget_chars: config.iteration_token_budget.saturating_mul(4),
+            iteration: 0,
+            call_counts: HashMap::new(),
+        }
+    }
+
+    /// Start the next round of tool calls. Returns false once the limit is
+    /// reached.
+    pub fn next_iteration(&mut self) -> bool {
+        self.iteration += 1;
+        self.iteration <= self.max_iterations
+    }
+
+    pub fn max_iterations(&self) -> usize {
+        self.max_iterations
+    }
+
+    /// Record a call; true if it has already run as often as allowed and
+    /// must not be executed again
+    pub fn is_repeat(&mut self, call: &ToolCall) -> bool {
+        let key = (call.name.clone(), normalize_arguments(&call.arguments));
+        let count = self.call_counts.entry(key).or_insert(0);
+        *count += 1;
+        *count > self.max_repeated_calls
+    }
+
+    /// Cut one tool output to its share of the round's budget
+    pub fn fit_output(&self, output: String, calls_in_round: usize) -> String {
+        if self.budget_chars == 0 {
+            return output;
+        }
+        let share = self.budget_chars / calls_in_round.max(1);
+        let Some((cut, _)) = output.char_indices().nth(share) else {
+            return output;
+        };
+        format!(
+            "{}\n\n[Output truncated: {} of {} characters shown (per-round tool output budget)]",
+            &output[..cut],
+            share,
+            output.chars().count()
+        )
+    }
+}
+
+/// Compare arguments by their JSON value so key order and whitespace don't
+/// hide a repeat
+fn normalize_arguments(arguments: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(arguments)
+        .map(|v| v.to_string())
+        .unwrap_or_else(|_| arguments.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: "1".to_string(),
+            name: name.to_string(),
+            arguments: arguments.to_string(),
+        }
+    }
+
+    fn config(max_iterations: usize, max_repeated_calls: usize, budget: usize) -> ToolLoopConfig {
+        ToolLoopConfig {
+            max_iterations,
+            max_repeated_calls,
+            iteration_token_budget: budget,
+        }
+    }
+
+    #[test]
+    fn test_iteration_limit() {
+        let mut guard = ToolLoopGuard::new(&config(2, 2, 0));
+        assert!(guard.next_iteration());
+        assert!(guard.next_iteration());
+        assert!(!guard.next_iteration());
+    }
+
+    #[test]
+    fn test_repeat_detection() {
+        let mut guard = ToolLoopGuard::new(&config(10, 2, 0));
+        let read = call("read_file", r#"{"path": "MEMORY.md"}"#);
+        assert!(!guard.is_repeat(&read));
+        // Same arguments, different formatting
+        assert!(!guard.is_repeat(&call("read_file", r#"{"path":"MEMORY.md"}"#)));
+        assert!(guard.is_repeat(&read));
+        assert!(!guard.is_repeat(&call("read_file", r#"{"path": "SOUL.md"}"#)));
+    }
+
+    #[test]
+    fn test_output_budget() {
+        let guard = ToolLoopGuard::new(&config(10, 2, 10));
+        assert_eq!(guard.fit_output("short".to_string(), 2), "short");
+
+        let fitted = guard.fit_output("x".repeat(100), 2);
+        assert!(fitted.starts_with(&"x".repeat(20)));
+        assert!(fitted.contains("20 of 100 characters"));
+
+        // Counted in characters, not bytes
+        let fitted = guard.fit_output("é".repeat(30), 2);
+        assert!(fitted.starts_with(&format!("{}\n", "é".repeat(20))));
+        assert!(fitted.contains("20 of 30 characters"));
+
+        let unlimited = ToolLoopGuard::new(&config(10, 2, 0));
+        assert_eq!(unlimited.fit_output("x".repeat(100), 5).len(), 100);
+    }
+}
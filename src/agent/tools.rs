@@ -1,15 +1,21 @@
 use anyhow::Result;
 use async_trait::async_trait;
 use serde_json::{Value, json};
+use std::borrow::Cow;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tracing::debug;
 
+use super::delegates::{DelegateTool, load_registry};
+use super::environment::WeatherTool;
 use super::providers::ToolSchema;
+use crate::calendar::{Calendar, NewEvent, format_event, format_event_list, parse_event_time};
 use crate::config::Config;
-use crate::memory::MemoryManager;
+use crate::jobs::{JobOrigin, JobStore, format_job_list};
+use crate::memory::{LogOrigin, MemoryCategory, MemoryManager};
 use crate::sandbox::{self, SandboxPolicy};
+use crate::tasks::{TaskStatus, TaskStore, TaskUpdate, format_task_list};
 
 #[derive(Debug, Clone)]
 pub struct ToolResult {
@@ -63,7 +69,7 @@ pub fn create_default_tools(
         Box::new(MemorySearchTool::new(workspace.clone()))
     };
 
-    Ok(vec![
+    let mut tools: Vec<Box<dyn Tool>> = vec![
         Box::new(BashTool::new(
             config.tools.bash_timeout_ms,
             state_dir.clone(),
@@ -72,13 +78,70 @@ pub fn create_default_tools(
         Box::new(ReadFileTool::new(sandbox_policy.clone())),
         Box::new(WriteFileTool::new(
             state_dir.clone(),
+            workspace.clone(),
             sandbox_policy.clone(),
         )),
-        Box::new(EditFileTool::new(state_dir, sandbox_policy)),
+        Box::new(EditFileTool::new(
+            state_dir.clone(),
+            workspace.clone(),
+            sandbox_policy,
+        )),
         memory_search_tool,
-        Box::new(MemoryGetTool::new(workspace)),
+        Box::new(MemoryGetTool::new(workspace.clone())),
         Box::new(WebFetchTool::new(config.tools.web_fetch_max_bytes)),
-    ])
+    ];
+
+    // Weather lookup, with the same location as the environment block
+    if let Some(ref weather) = config.context.weather
+        && weather.enabled
+    {
+        tools.push(Box::new(WeatherTool::new(weather.clone())));
+    }
+
+    // Web page screenshots looked at by a vision model
+    if let Some(ref screenshot) = config.tools.screenshot
+        && screenshot.enabled
+    {
+        #[cfg(feature = "screenshot")]
+        tools.push(Box::new(super::screenshot::ScreenshotTool::new(
+            screenshot.clone(),
+            config.clone(),
+            state_dir.join("screenshots"),
+        )));
+        #[cfg(not(feature = "screenshot"))]
+        tracing::warn!(
+            "tools.screenshot is configured, but LocalGPT was built without the screenshot feature"
+        );
+    }
+
+    // Delegation to specialist agents, only when AGENTS.md defines some
+    if !load_registry(&workspace).is_empty() {
+        tools.push(Box::new(DelegateTool::new(config.clone(), memory.clone())));
+    }
+
+    // Task tracking tools (shared SQLite store across all agents)
+    match TaskStore::open_default() {
+        Ok(store) => {
+            tools.push(Box::new(TaskCreateTool::new(store.clone())));
+            tools.push(Box::new(TaskUpdateTool::new(store.clone())));
+            tools.push(Box::new(TaskCompleteTool::new(store.clone())));
+            tools.push(Box::new(TaskListTool::new(store)));
+        }
+        Err(e) => tracing::warn!("Task store unavailable, task tools disabled: {}", e),
+    }
+
+    // Calendar tools, when a calendar is configured
+    if let Some(ref calendar_config) = config.calendar
+        && calendar_config.enabled
+    {
+        let calendar = Arc::new(Calendar::new(calendar_config));
+        if calendar.can_write() {
+            tools.push(Box::new(CreateEventTool::new(Arc::clone(&calendar))));
+        }
+        tools.push(Box::new(ListEventsTool::new(calendar)));
+    }
+
+    Ok(tools)
 }
 
 // Bash Tool
@@ -302,13 +365,19 @@ impl Tool for ReadFileTool {
 // Write File Tool
 pub struct WriteFileTool {
     state_dir: PathBuf,
+    workspace: PathBuf,
     sandbox_policy: Option<SandboxPolicy>,
 }
 
 impl WriteFileTool {
-    pub fn new(state_dir: PathBuf, sandbox_policy: Option<SandboxPolicy>) -> Self {
+    pub fn new(
+        state_dir: PathBuf,
+        workspace: PathBuf,
+        sandbox_policy: Option<SandboxPolicy>,
+    ) -> Self {
         Self {
             state_dir,
+            workspace,
             sandbox_policy,
         }
     }
@@ -390,7 +459,8 @@ impl Tool for WriteFileTool {
             fs::create_dir_all(parent)?;
         }
 
-        fs::write(&path, content)?;
+        let content = redact_memory(&self.workspace, &path, content);
+        write_to_workspace(&self.workspace, &path, &content, "write_file")?;
 
         Ok(format!(
             "Successfully wrote {} bytes to {}",
@@ -400,16 +470,42 @@ impl Tool for WriteFileTool {
     }
 }
 
+/// Files in the workspace are memory: mask secrets before writing them
+fn redact_memory<'a>(workspace: &Path, path: &Path, content: &'a str) -> Cow<'a, str> {
+    if path.starts_with(workspace) {
+        crate::security::redact("memory", &path.display().to_string(), content)
+    } else {
+        Cow::Borrowed(content)
+    }
+}
+
+/// Writes to workspace files are recorded in the memory history, so a bad
+/// overwrite can be rolled back
+fn write_to_workspace(workspace: &Path, path: &Path, content: &str, tool: &str) -> Result<()> {
+    if path.starts_with(workspace) {
+        crate::memory::write_memory_file(workspace, path, content, tool)
+    } else {
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
+
 // Edit File Tool
 pub struct EditFileTool {
     state_dir: PathBuf,
+    workspace: PathBuf,
     sandbox_policy: Option<SandboxPolicy>,
 }
 
 impl EditFileTool {
-    pub fn new(state_dir: PathBuf, sandbox_policy: Option<SandboxPolicy>) -> Self {
+    pub fn new(
+        state_dir: PathBuf,
+        workspace: PathBuf,
+        sandbox_policy: Option<SandboxPolicy>,
+    ) -> Self {
         Self {
             state_dir,
+            workspace,
             sandbox_policy,
         }
     }
@@ -499,6 +595,8 @@ impl Tool for EditFileTool {
         debug!("Editing file: {}", path);
 
         let content = fs::read_to_string(&path)?;
+        let new_string = redact_memory(&self.workspace, Path::new(&path), new_string);
+        let new_string = new_string.as_ref();
 
         let (new_content, count) = if replace_all {
             let count = content.matches(old_string).count();
@@ -509,7 +607,7 @@ impl Tool for EditFileTool {
             return Err(anyhow::anyhow!("old_string not found in file"));
         };
 
-        fs::write(&path, &new_content)?;
+        write_to_workspace(&self.workspace, Path::new(&path), &new_content, "edit_file")?;
 
         Ok(format!("Replaced {} occurrence(s) in {}", count, path))
     }
@@ -706,6 +804,152 @@ impl Tool for MemorySearchToolWithIndex {
     }
 }
 
+// Memory Log Tool - appends notes to today's log for a topic
+pub struct MemoryLogTool {
+    memory: Arc<MemoryManager>,
+    origin: LogOrigin,
+}
+
+impl MemoryLogTool {
+    pub fn new(memory: Arc<MemoryManager>, origin: LogOrigin) -> Self {
+        Self { memory, origin }
+    }
+}
+
+#[async_trait]
+impl Tool for MemoryLogTool {
+    fn name(&self) -> &str {
+        "memory_log"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "memory_log".to_string(),
+            description: "Add a note to today's daily log under a topic. Each topic has its own \
+                          file (memory/YYYY-MM-DD/<topic>.md); reuse the same topic name for \
+                          related notes and start a new one when the subject changes."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "topic": {
+                        "type": "string",
+                        "description": "Short topic label, e.g. 'Home network' or 'Trip to Kyoto'"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "The note (Markdown)"
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Optional keywords for later search"
+                    }
+                },
+                "required": ["topic", "content"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let topic = args["topic"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing topic"))?;
+        let content = args["content"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing content"))?;
+        let tags: Vec<String> = args["tags"]
+            .as_array()
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(|t| t.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let path = self
+            .memory
+            .append_topic_log(topic, content, &tags, &self.origin.get())?;
+        let relative = path
+            .strip_prefix(self.memory.workspace())
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        Ok(format!("Logged to {}", relative))
+    }
+}
+
+// Save Memory Tool - categorized entries routed by MemoryManager
+pub struct SaveMemoryTool {
+    memory: Arc<MemoryManager>,
+    origin: LogOrigin,
+}
+
+impl SaveMemoryTool {
+    pub fn new(memory: Arc<MemoryManager>, origin: LogOrigin) -> Self {
+        Self { memory, origin }
+    }
+}
+
+#[async_trait]
+impl Tool for SaveMemoryTool {
+    fn name(&self) -> &str {
+        "save_memory"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        let categories: Vec<&str> = MemoryCategory::ALL.iter().map(|c| c.name()).collect();
+        ToolSchema {
+            name: "save_memory".to_string(),
+            description: "Remember one thing for later sessions. Facts, preferences and tasks \
+                          are added to their section of MEMORY.md, events to today's log; each \
+                          entry records when and where it was saved. Save one item per call."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "category": {
+                        "type": "string",
+                        "enum": categories,
+                        "description": "fact (about the user or the world), preference (likes, \
+                                        dislikes, how to do things), event (something that \
+                                        happened), task (something to do later)"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "The entry, one short self-contained sentence"
+                    }
+                },
+                "required": ["category", "content"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let category = args["category"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing category"))?;
+        let category = MemoryCategory::parse(category)
+            .ok_or_else(|| anyhow::anyhow!("Unknown category: {}", category))?;
+        let content = args["content"]
+            .as_str()
+            .filter(|c| !c.trim().is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Missing content"))?;
+
+        let path = self
+            .memory
+            .save_entry(category, content, &self.origin.get())?;
+        let relative = path
+            .strip_prefix(self.memory.workspace())
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        Ok(format!("Saved {} to {}", category.name(), relative))
+    }
+}
+
 // Memory Get Tool - efficient snippet fetching after memory_search
 pub struct MemoryGetTool {
     workspace: PathBuf,
@@ -882,6 +1126,625 @@ impl Tool for WebFetchTool {
     }
 }
 
+// Task Create Tool
+pub struct TaskCreateTool {
+    store: TaskStore,
+}
+
+impl TaskCreateTool {
+    pub fn new(store: TaskStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Tool for TaskCreateTool {
+    fn name(&self) -> &str {
+        "task_create"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "task_create".to_string(),
+            description: "Create a tracked task/todo that persists across sessions and channels"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "title": {
+                        "type": "string",
+                        "description": "Short description of the task"
+                    },
+                    "notes": {
+                        "type": "string",
+                        "description": "Optional details or context"
+                    },
+                    "due": {
+                        "type": "string",
+                        "description": "Optional due date/time (e.g. 2026-02-01 or 2026-02-01 18:00)"
+                    }
+                },
+                "required": ["title"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let title = args["title"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing title"))?;
+
+        let task = self.store.create(
+            title,
+            args["notes"].as_str(),
+            args["due"].as_str(),
+            Some("agent"),
+        )?;
+
+        Ok(format!("Created task #{}: {}", task.id, task.title))
+    }
+}
+
+// Task Update Tool
+pub struct TaskUpdateTool {
+    store: TaskStore,
+}
+
+impl TaskUpdateTool {
+    pub fn new(store: TaskStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Tool for TaskUpdateTool {
+    fn name(&self) -> &str {
+        "task_update"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "task_update".to_string(),
+            description: "Update the title, notes, due date or status of a tracked task"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "integer",
+                        "description": "Task ID"
+                    },
+                    "title": { "type": "string" },
+                    "notes": { "type": "string" },
+                    "due": { "type": "string" },
+                    "status": {
+                        "type": "string",
+                        "enum": ["open", "done", "cancelled"]
+                    }
+                },
+                "required": ["id"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let id = args["id"]
+            .as_i64()
+            .ok_or_else(|| anyhow::anyhow!("Missing id"))?;
+
+        let status = match args["status"].as_str() {
+            Some(s) => {
+                Some(TaskStatus::parse(s).ok_or_else(|| anyhow::anyhow!("Invalid status: {}", s))?)
+            }
+            None => None,
+        };
+
+        let task = self
+            .store
+            .update(
+                id,
+                TaskUpdate {
+                    title: args["title"].as_str().map(String::from),
+                    notes: args["notes"].as_str().map(String::from),
+                    due: args["due"].as_str().map(String::from),
+                    status,
+                },
+            )?
+            .ok_or_else(|| anyhow::anyhow!("Task {} not found", id))?;
+
+        Ok(format!(
+            "Updated task #{} ({}): {}",
+            task.id,
+            task.status.as_str(),
+            task.title
+        ))
+    }
+}
+
+// Task Complete Tool
+pub struct TaskCompleteTool {
+    store: TaskStore,
+}
+
+impl TaskCompleteTool {
+    pub fn new(store: TaskStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Tool for TaskCompleteTool {
+    fn name(&self) -> &str {
+        "task_complete"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "task_complete".to_string(),
+            description: "Mark a tracked task as done".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "integer",
+                        "description": "Task ID"
+                    }
+                },
+                "required": ["id"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let id = args["id"]
+            .as_i64()
+            .ok_or_else(|| anyhow::anyhow!("Missing id"))?;
+
+        let task = self
+            .store
+            .complete(id)?
+            .ok_or_else(|| anyhow::anyhow!("Task {} not found", id))?;
+        Ok(format!("Completed task #{}: {}", task.id, task.title))
+    }
+}
+
+// Task List Tool
+pub struct TaskListTool {
+    store: TaskStore,
+}
+
+impl TaskListTool {
+    pub fn new(store: TaskStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Tool for TaskListTool {
+    fn name(&self) -> &str {
+        "task_list"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "task_list".to_string(),
+            description: "List tracked tasks (open tasks by default)".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "status": {
+                        "type": "string",
+                        "enum": ["open", "done", "cancelled", "all"],
+                        "description": "Filter by status (default: open)"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments).unwrap_or(json!({}));
+        let status = match args["status"].as_str().unwrap_or("open") {
+            "all" => None,
+            s => {
+                Some(TaskStatus::parse(s).ok_or_else(|| anyhow::anyhow!("Invalid status: {}", s))?)
+            }
+        };
+
+        let tasks = self.store.list(status)?;
+        if tasks.is_empty() {
+            return Ok("No tasks found".to_string());
+        }
+
+        Ok(format_task_list(&tasks))
+    }
+}
+
+// List Events Tool
+pub struct ListEventsTool {
+    calendar: Arc<Calendar>,
+}
+
+impl ListEventsTool {
+    pub fn new(calendar: Arc<Calendar>) -> Self {
+        Self { calendar }
+    }
+}
+
+#[async_trait]
+impl Tool for ListEventsTool {
+    fn name(&self) -> &str {
+        "list_events"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "list_events".to_string(),
+            description: "List upcoming events from the user's calendar".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "from": {
+                        "type": "string",
+                        "description": "Start date (YYYY-MM-DD, default: today)"
+                    },
+                    "days": {
+                        "type": "integer",
+                        "description": "Number of days to list (default: 7, max: 90)"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments).unwrap_or(json!({}));
+        let from_arg = args["from"]
+            .as_str()
+            .map(String::from)
+            .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+        let (from, _) = parse_event_time(&from_arg)
+            .ok_or_else(|| anyhow::anyhow!("Invalid date: {}", from_arg))?;
+        let days = args["days"].as_i64().unwrap_or(7).clamp(1, 90);
+        let to = from + chrono::Duration::days(days);
+
+        let events = self.calendar.list_events(from, to).await?;
+        if events.is_empty() {
+            return Ok(format!("No events in the next {} day(s)", days));
+        }
+
+        Ok(format_event_list(&events))
+    }
+}
+
+// Create Event Tool
+pub struct CreateEventTool {
+    calendar: Arc<Calendar>,
+}
+
+impl CreateEventTool {
+    pub fn new(calendar: Arc<Calendar>) -> Self {
+        Self { calendar }
+    }
+}
+
+#[async_trait]
+impl Tool for CreateEventTool {
+    fn name(&self) -> &str {
+        "create_event"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "create_event".to_string(),
+            description: "Add an event to the user's calendar".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "summary": {
+                        "type": "string",
+                        "description": "Event title"
+                    },
+                    "start": {
+                        "type": "string",
+                        "description": "Start in local time (YYYY-MM-DD HH:MM), or YYYY-MM-DD for an all-day event"
+                    },
+                    "end": {
+                        "type": "string",
+                        "description": "Optional end (same format as start)"
+                    },
+                    "duration_minutes": {
+                        "type": "integer",
+                        "description": "Length when no end is given (default: 60)"
+                    },
+                    "location": { "type": "string" },
+                    "description": { "type": "string" }
+                },
+                "required": ["summary", "start"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let summary = args["summary"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing summary"))?;
+        let start_arg = args["start"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing start"))?;
+        let (start, all_day) = parse_event_time(start_arg)
+            .ok_or_else(|| anyhow::anyhow!("Invalid start: {}", start_arg))?;
+
+        let end = if let Some(end_arg) = args["end"].as_str() {
+            let (end, _) = parse_event_time(end_arg)
+                .ok_or_else(|| anyhow::anyhow!("Invalid end: {}", end_arg))?;
+            end
+        } else if all_day {
+            start + chrono::Duration::days(1)
+        } else {
+            let minutes = args["duration_minutes"].as_i64().unwrap_or(60);
+            start + chrono::Duration::minutes(minutes)
+        };
+        if end < start {
+            anyhow::bail!("End is before start");
+        }
+
+        let event = self
+            .calendar
+            .create_event(NewEvent {
+                summary: summary.to_string(),
+                start,
+                end,
+                all_day,
+                location: args["location"].as_str().map(String::from),
+                description: args["description"].as_str().map(String::from),
+            })
+            .await?;
+
+        Ok(format!("Created event: {}", format_event(&event)))
+    }
+}
+
+/// Tools for starting and following background jobs. `origin` holds the
+/// Discord channel of the current turn, where the job reports back.
+pub fn create_job_tools(store: JobStore, origin: JobOrigin) -> Vec<Box<dyn Tool>> {
+    vec![
+        Box::new(JobStartTool::new(store.clone(), origin)),
+        Box::new(JobStatusTool::new(store.clone())),
+        Box::new(JobCancelTool::new(store)),
+    ]
+}
+
+// Job Start Tool
+pub struct JobStartTool {
+    store: JobStore,
+    origin: JobOrigin,
+}
+
+impl JobStartTool {
+    pub fn new(store: JobStore, origin: JobOrigin) -> Self {
+        Self { store, origin }
+    }
+}
+
+#[async_trait]
+impl Tool for JobStartTool {
+    fn name(&self) -> &str {
+        "job_start"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "job_start".to_string(),
+            description: "Start a background job for work that takes more than a few minutes \
+                          (summarizing many documents, ingesting files, long scripts). The job \
+                          runs in the daemon on a separate agent that does not see this \
+                          conversation, so put everything it needs in the prompt. Progress and \
+                          the result are posted to this channel when started from Discord; \
+                          otherwise check them with job_status."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "title": {
+                        "type": "string",
+                        "description": "Short name for the job"
+                    },
+                    "prompt": {
+                        "type": "string",
+                        "description": "Complete instructions, including paths, URLs and the expected output"
+                    }
+                },
+                "required": ["title", "prompt"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let title = args["title"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing title"))?;
+        let prompt = args["prompt"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing prompt"))?;
+
+        let channel_id = self.origin.get();
+        let job = self.store.create(title, prompt, channel_id.as_deref())?;
+
+        Ok(match channel_id {
+            Some(_) => format!(
+                "Queued job #{}: {}. Its progress and result will be posted to this channel.",
+                job.id, job.title
+            ),
+            None => format!(
+                "Queued job #{}: {}. Check on it with job_status.",
+                job.id, job.title
+            ),
+        })
+    }
+}
+
+// Job Status Tool
+pub struct JobStatusTool {
+    store: JobStore,
+}
+
+impl JobStatusTool {
+    pub fn new(store: JobStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Tool for JobStatusTool {
+    fn name(&self) -> &str {
+        "job_status"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "job_status".to_string(),
+            description: "Show a background job's status and result, or list recent jobs"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "integer",
+                        "description": "Job ID (omit to list recent jobs)"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments).unwrap_or(json!({}));
+
+        let Some(id) = args["id"].as_i64() else {
+            let jobs = self.store.recent(10)?;
+            if jobs.is_empty() {
+                return Ok("No jobs found".to_string());
+            }
+            return Ok(format_job_list(&jobs));
+        };
+
+        let job = self
+            .store
+            .get(id)?
+            .ok_or_else(|| anyhow::anyhow!("Job {} not found", id))?;
+        let mut text = format!("Job #{}: {} [{}]", job.id, job.title, job.status.as_str());
+        if let Some(ref progress) = job.progress {
+            text.push_str(&format!("\nProgress: {}", progress));
+        }
+        if let Some(ref error) = job.error {
+            text.push_str(&format!("\nError: {}", error));
+        }
+        if let Some(ref result) = job.result {
+            text.push_str(&format!("\nResult:\n{}", result));
+        }
+        Ok(text)
+    }
+}
+
+// Job Cancel Tool
+pub struct JobCancelTool {
+    store: JobStore,
+}
+
+impl JobCancelTool {
+    pub fn new(store: JobStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Tool for JobCancelTool {
+    fn name(&self) -> &str {
+        "job_cancel"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "job_cancel".to_string(),
+            description: "Cancel a queued or running background job".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "integer",
+                        "description": "Job ID"
+                    }
+                },
+                "required": ["id"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let id = args["id"]
+            .as_i64()
+            .ok_or_else(|| anyhow::anyhow!("Missing id"))?;
+
+        let job = self.store.cancel(id)?;
+        Ok(format!("Cancelled job #{}: {}", job.id, job.title))
+    }
+}
+
+// Job Progress Tool (only given to the agent running a job)
+pub struct JobProgressTool {
+    store: JobStore,
+    job_id: i64,
+}
+
+impl JobProgressTool {
+    pub fn new(store: JobStore, job_id: i64) -> Self {
+        Self { store, job_id }
+    }
+}
+
+#[async_trait]
+impl Tool for JobProgressTool {
+    fn name(&self) -> &str {
+        "job_progress"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "job_progress".to_string(),
+            description: "Report progress on your job; the user sees the latest note".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "note": {
+                        "type": "string",
+                        "description": "One line, e.g. \"Summarized 12 of 40 files\""
+                    }
+                },
+                "required": ["note"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let note = args["note"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing note"))?;
+
+        let line = note.lines().next().unwrap_or_default().trim();
+        self.store
+            .set_progress(self.job_id, crate::utils::safe_truncate(line, 200))?;
+        Ok("Progress recorded".to_string())
+    }
+}
+
 /// Extract relevant detail from tool arguments for display.
 /// Returns a human-readable summary of the key argument (file path, command, query, URL).
 pub fn extract_tool_detail(tool_name: &str, arguments: &str) -> Option<String> {
@@ -908,6 +1771,30 @@ pub fn extract_tool_detail(tool_name: &str, arguments: &str) -> Option<String> {
             .get("url")
             .and_then(|v| v.as_str())
             .map(|s| s.to_string()),
+        "task_create" => args
+            .get("title")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        "weather" => args
+            .get("location")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        "create_event" => args
+            .get("summary")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        "memory_log" => args
+            .get("topic")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        "job_start" => args
+            .get("title")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        "task_update" | "task_complete" | "job_status" | "job_cancel" => args
+            .get("id")
+            .and_then(|v| v.as_i64())
+            .map(|id| format!("#{}", id)),
         _ => None,
     }
 }
@@ -0,0 +1,164 @@
+//! CalDAV (RFC 4791) access to a single calendar collection
+//!
+//! Events are read with a `calendar-query` REPORT limited to a time range and
+//! asking the server to expand recurrences, and written with a plain PUT of
+//! a new `.ics` resource.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use reqwest::{Method, StatusCode};
+use std::sync::LazyLock;
+
+use super::{CalendarEvent, ics};
+use crate::config::CalDavConfig;
+
+static CALENDAR_DATA: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?s)<(?:[A-Za-z0-9_-]+:)?calendar-data[^>]*>(.*?)</(?:[A-Za-z0-9_-]+:)?calendar-data>",
+    )
+    .unwrap()
+});
+
+fn collection_url(config: &CalDavConfig) -> String {
+    format!("{}/", config.url.trim_end_matches('/'))
+}
+
+fn authorize(request: reqwest::RequestBuilder, config: &CalDavConfig) -> reqwest::RequestBuilder {
+    if config.username.is_empty() {
+        request
+    } else {
+        request.basic_auth(&config.username, Some(&config.password))
+    }
+}
+
+/// Fetch events overlapping `[from, to)`
+pub(super) async fn fetch_events(
+    client: &reqwest::Client,
+    config: &CalDavConfig,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<CalendarEvent>> {
+    let (start, end) = (ics::format_utc(from), ics::format_utc(to));
+    let body = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop>
+    <C:calendar-data><C:expand start="{start}" end="{end}"/></C:calendar-data>
+  </D:prop>
+  <C:filter>
+    <C:comp-filter name="VCALENDAR">
+      <C:comp-filter name="VEVENT">
+        <C:time-range start="{start}" end="{end}"/>
+      </C:comp-filter>
+    </C:comp-filter>
+  </C:filter>
+</C:calendar-query>"#
+    );
+
+    let report = Method::from_bytes(b"REPORT").expect("valid method");
+    let response = authorize(client.request(report, collection_url(config)), config)
+        .header("Depth", "1")
+        .header("Content-Type", "application/xml; charset=utf-8")
+        .body(body)
+        .send()
+        .await
+        .context("CalDAV request failed")?;
+
+    let status = response.status();
+    if status != StatusCode::MULTI_STATUS && !status.is_success() {
+        anyhow::bail!("CalDAV server returned {}", status);
+    }
+    let xml = response.text().await?;
+
+    Ok(parse_multistatus(&xml))
+}
+
+/// Store a new event under `<uid>.ics`
+pub(super) async fn put_event(
+    client: &reqwest::Client,
+    config: &CalDavConfig,
+    uid: &str,
+    body: String,
+) -> Result<()> {
+    let url = format!("{}{}.ics", collection_url(config), uid);
+    let response = authorize(client.put(&url), config)
+        .header("Content-Type", "text/calendar; charset=utf-8")
+        // Never overwrite an existing event
+        .header("If-None-Match", "*")
+        .body(body)
+        .send()
+        .await
+        .context("CalDAV request failed")?;
+
+    let status = response.status();
+    if !status.is_success() {
+        anyhow::bail!("CalDAV server rejected the event: {}", status);
+    }
+    Ok(())
+}
+
+/// Extract and parse every `calendar-data` element of a multistatus reply
+fn parse_multistatus(xml: &str) -> Vec<CalendarEvent> {
+    CALENDAR_DATA
+        .captures_iter(xml)
+        .flat_map(|caps| ics::parse_events(&xml_text(&caps[1])))
+        .collect()
+}
+
+/// Decode character data (CDATA sections and entities)
+fn xml_text(raw: &str) -> String {
+    let raw = raw.trim();
+    if let Some(inner) = raw
+        .strip_prefix("<![CDATA[")
+        .and_then(|s| s.strip_suffix("]]>"))
+    {
+        return inner.to_string();
+    }
+    raw.replace("&#13;", "\r")
+        .replace("&#xD;", "\r")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_multistatus() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  <d:response>
+    <d:href>/calendars/me/personal/a.ics</d:href>
+    <d:propstat><d:prop><cal:calendar-data>BEGIN:VCALENDAR&#13;
+BEGIN:VEVENT&#13;
+UID:a&#13;
+DTSTART:20260301T090000Z&#13;
+SUMMARY:Standup &amp; planning&#13;
+END:VEVENT&#13;
+END:VCALENDAR&#13;
+</cal:calendar-data></d:prop></d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/calendars/me/personal/b.ics</d:href>
+    <d:propstat><d:prop><C:calendar-data xmlns:C="urn:ietf:params:xml:ns:caldav"><![CDATA[BEGIN:VCALENDAR
+BEGIN:VEVENT
+UID:b
+DTSTART:20260302T090000Z
+SUMMARY:Review <draft>
+END:VEVENT
+END:VCALENDAR
+]]></C:calendar-data></d:prop></d:propstat>
+  </d:response>
+</d:multistatus>"#;
+
+        let events = parse_multistatus(xml);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].summary, "Standup & planning");
+        assert_eq!(events[1].summary, "Review <draft>");
+    }
+}
@@ -0,0 +1,332 @@
+//! Minimal iCalendar (RFC 5545) reading and writing
+//!
+//! Only what the calendar tools need: VEVENT components with summary, times,
+//! location and description. TZID parameters are not resolved; such times
+//! are read as local time, which is right for a personal calendar kept in
+//! the user's own zone. RRULE is not expanded.
+
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use super::{CalendarEvent, NewEvent};
+
+/// Parse all VEVENT components of an iCalendar document
+pub(super) fn parse_events(text: &str) -> Vec<CalendarEvent> {
+    let mut events = Vec::new();
+    let mut current: Option<EventBuilder> = None;
+    // Nested components inside an event (VALARM) are skipped
+    let mut nested = 0usize;
+
+    for line in unfold(text) {
+        let Some((name, params, value)) = split_property(&line) else {
+            continue;
+        };
+        match (name.as_str(), value) {
+            ("BEGIN", "VEVENT") if current.is_none() => current = Some(EventBuilder::default()),
+            ("BEGIN", _) if current.is_some() => nested += 1,
+            ("END", "VEVENT") if nested == 0 => {
+                if let Some(event) = current.take().and_then(EventBuilder::build) {
+                    events.push(event);
+                }
+            }
+            ("END", _) if nested > 0 => nested -= 1,
+            _ if nested > 0 => {}
+            _ => {
+                if let Some(ref mut builder) = current {
+                    builder.set(&name, params, value);
+                }
+            }
+        }
+    }
+
+    events
+}
+
+/// Serialize a new event as a complete VCALENDAR document
+pub(super) fn event_to_ics(event: &NewEvent, uid: &str) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:-//LocalGPT//{}//EN", env!("CARGO_PKG_VERSION")),
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", uid),
+        format!("DTSTAMP:{}", format_utc(Utc::now())),
+    ];
+    if event.all_day {
+        let start = event.start.with_timezone(&Local).date_naive();
+        let end = event
+            .end
+            .with_timezone(&Local)
+            .date_naive()
+            .max(start + Duration::days(1));
+        lines.push(format!("DTSTART;VALUE=DATE:{}", start.format("%Y%m%d")));
+        lines.push(format!("DTEND;VALUE=DATE:{}", end.format("%Y%m%d")));
+    } else {
+        lines.push(format!("DTSTART:{}", format_utc(event.start)));
+        lines.push(format!("DTEND:{}", format_utc(event.end)));
+    }
+    lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+    if let Some(ref location) = event.location {
+        lines.push(format!("LOCATION:{}", escape(location)));
+    }
+    if let Some(ref description) = event.description {
+        lines.push(format!("DESCRIPTION:{}", escape(description)));
+    }
+    lines.push("END:VEVENT".to_string());
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.join("\r\n") + "\r\n"
+}
+
+/// Format a UTC time in iCalendar basic format
+pub(super) fn format_utc(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+#[derive(Default)]
+struct EventBuilder {
+    uid: Option<String>,
+    summary: Option<String>,
+    start: Option<(DateTime<Utc>, bool)>,
+    end: Option<DateTime<Utc>>,
+    duration: Option<Duration>,
+    location: Option<String>,
+    description: Option<String>,
+}
+
+impl EventBuilder {
+    fn set(&mut self, name: &str, params: &str, value: &str) {
+        match name {
+            "UID" => self.uid = Some(value.to_string()),
+            "SUMMARY" => self.summary = Some(unescape(value)),
+            "LOCATION" => self.location = Some(unescape(value)).filter(|s| !s.is_empty()),
+            "DESCRIPTION" => self.description = Some(unescape(value)).filter(|s| !s.is_empty()),
+            "DTSTART" => self.start = parse_time(params, value),
+            "DTEND" => self.end = parse_time(params, value).map(|(t, _)| t),
+            "DURATION" => self.duration = parse_duration(value),
+            _ => {}
+        }
+    }
+
+    fn build(self) -> Option<CalendarEvent> {
+        let (start, all_day) = self.start?;
+        let end = self
+            .end
+            .or_else(|| self.duration.map(|d| start + d))
+            .unwrap_or(if all_day {
+                start + Duration::days(1)
+            } else {
+                start
+            });
+
+        Some(CalendarEvent {
+            uid: self.uid.unwrap_or_default(),
+            summary: self.summary.unwrap_or_else(|| "(no title)".to_string()),
+            start,
+            end,
+            all_day,
+            location: self.location,
+            description: self.description,
+        })
+    }
+}
+
+/// Join folded continuation lines (starting with a space or tab)
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in text.lines() {
+        let raw = raw.trim_end_matches('\r');
+        if let Some(rest) = raw.strip_prefix([' ', '\t'])
+            && let Some(last) = lines.last_mut()
+        {
+            last.push_str(rest);
+        } else if !raw.is_empty() {
+            lines.push(raw.to_string());
+        }
+    }
+    lines
+}
+
+/// Split `NAME;PARAM=X:VALUE` into its upper-cased name, params and value
+fn split_property(line: &str) -> Option<(String, &str, &str)> {
+    // The value starts at the first colon outside a quoted parameter
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(i),
+        _ => None,
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let (name, params) = head.split_once(';').unwrap_or((head, ""));
+    Some((name.to_ascii_uppercase(), params, value.trim()))
+}
+
+/// Parse a DATE or DATE-TIME value; the flag is true for all-day dates
+fn parse_time(params: &str, value: &str) -> Option<(DateTime<Utc>, bool)> {
+    let is_date = (params.to_ascii_uppercase().contains("VALUE=DATE") && !value.contains('T'))
+        || value.len() == 8;
+    if is_date {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        let midnight = Local
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+            .earliest()?;
+        return Some((midnight.with_timezone(&Utc), true));
+    }
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((Utc.from_utc_datetime(&naive), false));
+    }
+
+    // Floating or TZID time: read as local
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let local = Local.from_local_datetime(&naive).earliest()?;
+    Some((local.with_timezone(&Utc), false))
+}
+
+/// Parse a DURATION value such as `PT1H30M`, `P1D` or `P2W`
+fn parse_duration(value: &str) -> Option<Duration> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let value = value.strip_prefix('P')?;
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            unit => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                total += match unit {
+                    'W' => Duration::weeks(n),
+                    'D' => Duration::days(n),
+                    'H' => Duration::hours(n),
+                    'M' => Duration::minutes(n),
+                    'S' => Duration::seconds(n),
+                    _ => return None,
+                };
+            }
+        }
+    }
+
+    Some(if negative { -total } else { total })
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "BEGIN:VCALENDAR\r\n\
+VERSION:2.0\r\n\
+BEGIN:VEVENT\r\n\
+UID:abc-1\r\n\
+DTSTART:20260301T090000Z\r\n\
+DTEND:20260301T100000Z\r\n\
+SUMMARY:Dentist\\, check-up\r\n\
+LOCATION:Main St\r\n\
+DESCRIPTION:Bring the insurance card\\nand the form that is long enough \r\n \
+ to be folded\r\n\
+BEGIN:VALARM\r\n\
+DESCRIPTION:Alarm text\r\n\
+END:VALARM\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:abc-2\r\n\
+DTSTART;VALUE=DATE:20260302\r\n\
+SUMMARY:Holiday\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:abc-3\r\n\
+DTSTART:20260303T120000Z\r\n\
+DURATION:PT1H30M\r\n\
+SUMMARY:Lunch\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+    #[test]
+    fn test_parse_events() {
+        let events = parse_events(SAMPLE);
+        assert_eq!(events.len(), 3);
+
+        let dentist = &events[0];
+        assert_eq!(dentist.uid, "abc-1");
+        assert_eq!(dentist.summary, "Dentist, check-up");
+        assert_eq!(dentist.location.as_deref(), Some("Main St"));
+        assert_eq!(
+            dentist.description.as_deref(),
+            Some("Bring the insurance card\nand the form that is long enough to be folded")
+        );
+        assert_eq!(dentist.end - dentist.start, Duration::hours(1));
+        assert!(!dentist.all_day);
+
+        let holiday = &events[1];
+        assert!(holiday.all_day);
+        assert_eq!(
+            holiday.start.with_timezone(&Local).date_naive(),
+            NaiveDate::from_ymd_opt(2026, 3, 2).unwrap()
+        );
+        assert_eq!(holiday.end - holiday.start, Duration::days(1));
+
+        assert_eq!(events[2].end - events[2].start, Duration::minutes(90));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let start = Utc.with_ymd_and_hms(2026, 3, 1, 9, 0, 0).unwrap();
+        let event = NewEvent {
+            summary: "Call; Bob, re: plans".to_string(),
+            start,
+            end: start + Duration::minutes(30),
+            all_day: false,
+            location: None,
+            description: Some("Line one\nLine two".to_string()),
+        };
+
+        let ics = event_to_ics(&event, "uid-1@localgpt");
+        let parsed = parse_events(&ics);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].uid, "uid-1@localgpt");
+        assert_eq!(parsed[0].summary, event.summary);
+        assert_eq!(parsed[0].start, start);
+        assert_eq!(parsed[0].description, event.description);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("PT15M"), Some(Duration::minutes(15)));
+        assert_eq!(parse_duration("P1DT2H"), Some(Duration::hours(26)));
+        assert_eq!(parse_duration("-PT5M"), Some(Duration::minutes(-5)));
+        assert_eq!(parse_duration("1H"), None);
+    }
+}
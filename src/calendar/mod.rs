@@ -0,0 +1,316 @@
+//! Calendar integration
+//!
+//! Events are read from iCalendar feeds (`calendar.ics_urls`) and an optional
+//! CalDAV collection (`calendar.caldav`), which is also where new events are
+//! written. The agent gets `list_events`/`create_event` tools, and the
+//! heartbeat runner posts reminders before upcoming events.
+
+mod caldav;
+mod ics;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::config::CalendarConfig;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarEvent {
+    pub uid: String,
+    pub summary: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Date-only event; `start` is local midnight
+    pub all_day: bool,
+    pub location: Option<String>,
+    pub description: Option<String>,
+}
+
+/// An event to create
+#[derive(Debug, Clone)]
+pub struct NewEvent {
+    pub summary: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub all_day: bool,
+    pub location: Option<String>,
+    pub description: Option<String>,
+}
+
+pub struct Calendar {
+    client: reqwest::Client,
+    config: CalendarConfig,
+}
+
+impl Calendar {
+    pub fn new(config: &CalendarConfig) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            config: config.clone(),
+        }
+    }
+
+    /// Whether events can be created (a CalDAV collection is configured)
+    pub fn can_write(&self) -> bool {
+        self.config.caldav.is_some()
+    }
+
+    /// Events overlapping `[from, to)` from all sources, sorted by start.
+    /// A source that fails is logged and skipped so one broken feed doesn't
+    /// hide the rest; an error is only returned if every source failed.
+    pub async fn list_events(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<CalendarEvent>> {
+        let mut events = Vec::new();
+        let mut last_error = None;
+
+        for url in &self.config.ics_urls {
+            match self.fetch_ics(url).await {
+                Ok(feed) => events.extend(feed),
+                Err(e) => {
+                    warn!("Calendar feed {} failed: {}", url, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        if let Some(ref caldav) = self.config.caldav {
+            match caldav::fetch_events(&self.client, caldav, from, to).await {
+                Ok(found) => events.extend(found),
+                Err(e) => {
+                    warn!("CalDAV calendar {} failed: {}", caldav.url, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        if let Some(e) = last_error
+            && events.is_empty()
+        {
+            return Err(e);
+        }
+
+        events.retain(|e| e.start < to && (e.end > from || e.start >= from));
+        events.sort_by_key(|e| e.start);
+        // The same event may come from several sources
+        events.dedup_by(|a, b| !a.uid.is_empty() && a.uid == b.uid && a.start == b.start);
+        Ok(events)
+    }
+
+    /// Create an event in the CalDAV collection
+    pub async fn create_event(&self, event: NewEvent) -> Result<CalendarEvent> {
+        let caldav = self
+            .config
+            .caldav
+            .as_ref()
+            .context("Creating events needs [calendar.caldav] in the config")?;
+
+        let uid = format!("{}@localgpt", uuid::Uuid::new_v4());
+        caldav::put_event(&self.client, caldav, &uid, ics::event_to_ics(&event, &uid)).await?;
+
+        Ok(CalendarEvent {
+            uid,
+            summary: event.summary,
+            start: event.start,
+            end: event.end,
+            all_day: event.all_day,
+            location: event.location,
+            description: event.description,
+        })
+    }
+
+    async fn fetch_ics(&self, url: &str) -> Result<Vec<CalendarEvent>> {
+        let text = if url.starts_with("http://") || url.starts_with("https://") {
+            self.fetch_text(url).await?
+        } else if let Some(rest) = url.strip_prefix("webcal://") {
+            self.fetch_text(&format!("https://{}", rest)).await?
+        } else {
+            let path = shellexpand::tilde(url).to_string();
+            fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path))?
+        };
+        Ok(ics::parse_events(&text))
+    }
+
+    async fn fetch_text(&self, url: &str) -> Result<String> {
+        let response = self
+            .client
+            .get(url)
+            .header("User-Agent", "LocalGPT/0.1")
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.text().await?)
+    }
+}
+
+/// Parse a user or model supplied time: RFC 3339, `YYYY-MM-DD HH:MM` (local)
+/// or `YYYY-MM-DD` (all day). The flag is true for a date without a time.
+pub fn parse_event_time(s: &str) -> Option<(DateTime<Utc>, bool)> {
+    let s = s.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Some((time.with_timezone(&Utc), false));
+    }
+    for format in [
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+    ] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(s, format) {
+            let local = Local.from_local_datetime(&naive).earliest()?;
+            return Some((local.with_timezone(&Utc), false));
+        }
+    }
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    let midnight = Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .earliest()?;
+    Some((midnight.with_timezone(&Utc), true))
+}
+
+/// One line per event, in local time
+pub fn format_event(event: &CalendarEvent) -> String {
+    let start = event.start.with_timezone(&Local);
+    let end = event.end.with_timezone(&Local);
+    let mut line = if event.all_day {
+        format!("{} all day", start.format("%Y-%m-%d (%a)"))
+    } else if start.date_naive() == end.date_naive() {
+        format!(
+            "{}-{}",
+            start.format("%Y-%m-%d (%a) %H:%M"),
+            end.format("%H:%M")
+        )
+    } else {
+        format!(
+            "{} - {}",
+            start.format("%Y-%m-%d (%a) %H:%M"),
+            end.format("%Y-%m-%d %H:%M")
+        )
+    };
+    line.push_str(&format!("  {}", event.summary));
+    if let Some(ref location) = event.location {
+        line.push_str(&format!(" @ {}", location));
+    }
+    line
+}
+
+pub fn format_event_list(events: &[CalendarEvent]) -> String {
+    events
+        .iter()
+        .map(format_event)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Path of the record of reminders already sent for an agent
+pub fn reminders_path(state_dir: &Path, agent_id: &str) -> PathBuf {
+    state_dir
+        .join("agents")
+        .join(agent_id)
+        .join("calendar_reminders.json")
+}
+
+/// Timed events from `events` not reminded about yet, recording them as
+/// reminded. Entries for events that started over a day ago are dropped.
+pub fn take_due_reminders(
+    path: &Path,
+    events: Vec<CalendarEvent>,
+    now: DateTime<Utc>,
+) -> Result<Vec<CalendarEvent>> {
+    let mut sent: HashMap<String, i64> = fs::read_to_string(path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default();
+    let cutoff = (now - Duration::days(1)).timestamp();
+    sent.retain(|_, start| *start > cutoff);
+
+    let mut due = Vec::new();
+    for event in events {
+        if event.all_day || event.start < now {
+            continue;
+        }
+        let key = format!("{}@{}", event.uid, event.start.timestamp());
+        if sent.insert(key, event.start.timestamp()).is_none() {
+            due.push(event);
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string(&sent)?)?;
+    Ok(due)
+}
+
+/// Reminder text for a set of upcoming events
+pub fn format_reminder(events: &[CalendarEvent], now: DateTime<Utc>) -> String {
+    let mut message = String::from("**Upcoming**");
+    for event in events {
+        let minutes = (event.start - now).num_minutes().max(0);
+        message.push_str(&format!("\n- {} (in {} min)", format_event(event), minutes));
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(uid: &str, start: DateTime<Utc>, all_day: bool) -> CalendarEvent {
+        CalendarEvent {
+            uid: uid.to_string(),
+            summary: uid.to_string(),
+            start,
+            end: start + Duration::hours(1),
+            all_day,
+            location: None,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_take_due_reminders() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = reminders_path(dir.path(), "main");
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 8, 50, 0).unwrap();
+        let soon = now + Duration::minutes(10);
+
+        let events = vec![
+            event("standup", soon, false),
+            event("holiday", soon, true),
+            event("past", now - Duration::minutes(5), false),
+        ];
+        let due = take_due_reminders(&path, events.clone(), now).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].uid, "standup");
+
+        // Already reminded
+        assert!(take_due_reminders(&path, events, now).unwrap().is_empty());
+
+        // Same event on another day is a new occurrence
+        let tomorrow = vec![event("standup", soon + Duration::days(1), false)];
+        assert_eq!(take_due_reminders(&path, tomorrow, now).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_event_time() {
+        let (utc, all_day) = parse_event_time("2026-03-01T09:00:00Z").unwrap();
+        assert_eq!(utc, Utc.with_ymd_and_hms(2026, 3, 1, 9, 0, 0).unwrap());
+        assert!(!all_day);
+
+        let (local, all_day) = parse_event_time("2026-03-01 18:30").unwrap();
+        assert_eq!(
+            local.with_timezone(&Local).format("%H:%M").to_string(),
+            "18:30"
+        );
+        assert!(!all_day);
+
+        assert!(parse_event_time("2026-03-01").unwrap().1);
+        assert!(parse_event_time("tomorrow").is_none());
+    }
+}
@@ -6,9 +6,9 @@ use rustyline::error::ReadlineError;
 use std::io::{self, Write};
 
 use localgpt::agent::{
-    Agent, AgentConfig, ImageAttachment, Skill, extract_tool_detail, get_last_session_id_for_agent,
-    get_skills_summary, list_sessions_for_agent, load_skills, parse_skill_command,
-    search_sessions_for_agent,
+    Agent, AgentConfig, ExportFormat, ImageAttachment, Skill, extract_tool_detail,
+    get_last_session_id_for_agent, get_skills_summary, list_sessions_for_agent, load_skills,
+    parse_skill_command, search_sessions_for_agent,
 };
 use localgpt::concurrency::WorkspaceLock;
 use localgpt::config::Config;
@@ -118,6 +118,9 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
     let config = Config::load()?;
     // Embedding provider is automatically created based on config.memory.embedding_provider
     let memory = MemoryManager::new_with_full_config(&config.memory, Some(&config), agent_id)?;
+    if let Err(e) = localgpt::memory::watch_context_files(memory.workspace()) {
+        tracing::warn!("Failed to watch context files: {}", e);
+    }
 
     let agent_config = AgentConfig {
         model: args.model.unwrap_or(config.agent.default_model.clone()),
@@ -824,11 +827,16 @@ async fn handle_command(
         }
 
         "/export" => {
-            let markdown = agent.export_markdown();
             if parts.len() >= 2 {
                 let path = parts[1..].join(" ");
                 let expanded = shellexpand::tilde(&path).to_string();
-                match std::fs::write(&expanded, &markdown) {
+                let lower = expanded.to_lowercase();
+                let format = if lower.ends_with(".html") || lower.ends_with(".htm") {
+                    ExportFormat::Html
+                } else {
+                    ExportFormat::Markdown
+                };
+                match std::fs::write(&expanded, agent.export(format)) {
                     Ok(()) => {
                         println!("\nSession exported to: {}\n", expanded);
                         CommandResult::Continue
@@ -837,7 +845,7 @@ async fn handle_command(
                 }
             } else {
                 // Print to stdout
-                println!("\n{}", markdown);
+                println!("\n{}", agent.export_markdown());
                 CommandResult::Continue
             }
         }
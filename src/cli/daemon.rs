@@ -8,11 +8,16 @@ use daemonize::Daemonize;
 
 use localgpt::concurrency::TurnGate;
 use localgpt::config::Config;
+use localgpt::digest::DigestRunner;
 use localgpt::discord::SharedAgentMap;
 use localgpt::heartbeat::HeartbeatRunner;
+use localgpt::jobs::JobRunner;
 use localgpt::memory::MemoryManager;
 use localgpt::server::Server;
 
+/// How long shutdown waits for the Discord farewell to be posted
+const FAREWELL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+
 /// Synchronously stop the daemon (for use before Tokio runtime starts)
 pub fn stop_sync() -> Result<()> {
     let pid_file = get_pid_file()?;
@@ -89,10 +94,7 @@ pub fn daemonize_and_run(agent_id: &str) -> Result<()> {
     println!("  PID file: {}", pid_file.display());
     println!("  Log file: {}", log_file.display());
     if config.server.enabled {
-        println!(
-            "  Server: http://{}:{}",
-            config.server.bind, config.server.port
-        );
+        println!("  Server: {}", config.server.url());
     }
     println!("\nUse 'localgpt daemon status' to check status");
     println!("Use 'localgpt daemon stop' to stop\n");
@@ -127,13 +129,15 @@ pub fn daemonize_and_run(agent_id: &str) -> Result<()> {
 async fn run_daemon_server(config: Config, agent_id: &str) -> Result<()> {
     // Initialize logging in the daemon process
     // Disable ANSI colors since we're writing to a file
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::new("info"))
-        .with_ansi(false)
-        .init();
+    localgpt::logging::init(
+        tracing_subscriber::EnvFilter::new(&config.logging.level),
+        false,
+        Some(&config.logging),
+    );
 
     let memory = MemoryManager::new_with_full_config(&config.memory, Some(&config), agent_id)?;
     let _watcher = memory.start_watcher()?;
+    let _backfill = memory.spawn_embedding_backfill();
 
     println!("Daemon started successfully");
 
@@ -212,6 +216,69 @@ async fn run_daemon_services(config: &Config, agent_id: &str) -> Result<()> {
         None
     };
 
+    // Spawn the background job runner if enabled
+    let jobs_handle = if config.jobs.enabled {
+        let jobs_config = config.clone();
+        let jobs_agent_id = agent_id.to_string();
+        println!(
+            "  Jobs: enabled (max concurrent: {})",
+            config.jobs.max_concurrent.max(1)
+        );
+        Some(tokio::spawn(async move {
+            match JobRunner::new(&jobs_config, &jobs_agent_id) {
+                Ok(runner) => {
+                    if let Err(e) = runner.run().await {
+                        tracing::error!("Job runner error: {}", e);
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Failed to create job runner: {}", e);
+                }
+            }
+        }))
+    } else {
+        None
+    };
+
+    // Sample resource usage; jobs and heartbeats wait while the machine is busy
+    let monitor_handle = if config.monitor.enabled {
+        let monitor_config = config.clone();
+        Some(tokio::spawn(async move {
+            if let Err(e) = localgpt::monitor::run(monitor_config).await {
+                tracing::error!("Resource monitor error: {}", e);
+            }
+        }))
+    } else {
+        None
+    };
+
+    // Check model providers and the Discord API; changes go to the admin channel
+    let health_handle = if config.health.enabled {
+        let health_config = config.clone();
+        Some(tokio::spawn(async move {
+            if let Err(e) = localgpt::health::run(health_config).await {
+                tracing::error!("Health checks error: {}", e);
+            }
+        }))
+    } else {
+        None
+    };
+
+    // Collect errors into periodic digests
+    let digest_config = config.clone();
+    let digest_handle = tokio::spawn(async move {
+        match DigestRunner::new(&digest_config) {
+            Ok(runner) => {
+                if let Err(e) = runner.run().await {
+                    tracing::error!("Error digest runner error: {}", e);
+                }
+            }
+            Err(e) => {
+                tracing::error!("Failed to create error digest runner: {}", e);
+            }
+        }
+    });
+
     // Spawn Telegram bot in background if configured
     let telegram_handle = if config.telegram.as_ref().is_some_and(|t| t.enabled) {
         let tg_config = config.clone();
@@ -227,34 +294,52 @@ async fn run_daemon_services(config: &Config, agent_id: &str) -> Result<()> {
         None
     };
 
-    // Run server or wait for shutdown
+    // Run server until Ctrl+C or SIGTERM (`daemon stop`, systemd, launchd)
     if config.server.enabled {
-        println!(
-            "  Server: http://{}:{}",
-            config.server.bind, config.server.port
-        );
+        println!("  Server: {}", config.server.url());
         let mut server = Server::new_with_gate(config, turn_gate)?;
         if let Some(agents) = discord_agents {
             server = server.with_discord_agents(agents);
         }
-        server.run().await?;
-    } else if heartbeat_handle.is_some() {
-        // Server not enabled but heartbeat is - wait for Ctrl+C
+        tokio::select! {
+            result = server.run() => result?,
+            result = shutdown_signal() => result?,
+        }
+    } else if heartbeat_handle.is_some() || jobs_handle.is_some() {
+        // Server not enabled but heartbeat or jobs are - wait for Ctrl+C
         println!("  Server: disabled");
-        tokio::signal::ctrl_c().await?;
+        shutdown_signal().await?;
     } else {
         println!("  Neither server nor heartbeat is enabled. Use Ctrl+C to stop.");
-        tokio::signal::ctrl_c().await?;
+        shutdown_signal().await?;
     }
 
     // Abort background tasks on shutdown
     if let Some(handle) = heartbeat_handle {
         handle.abort();
     }
+    if let Some(handle) = jobs_handle {
+        handle.abort();
+    }
+    digest_handle.abort();
+    if let Some(handle) = monitor_handle {
+        handle.abort();
+    }
+    if let Some(handle) = health_handle {
+        handle.abort();
+    }
     if let Some(handle) = telegram_handle {
         handle.abort();
     }
     if let Some(handle) = discord_handle {
+        // Say goodbye while the gateway is still connected
+        let farewell = localgpt::discord::announce_shutdown(config);
+        if tokio::time::timeout(FAREWELL_TIMEOUT, farewell)
+            .await
+            .is_err()
+        {
+            tracing::warn!("Timed out posting the Discord farewell");
+        }
         handle.abort();
     }
 
@@ -267,6 +352,16 @@ pub struct DaemonArgs {
     pub command: DaemonCommands,
 }
 
+impl DaemonArgs {
+    /// Whether this command runs the daemon in this process
+    pub fn runs_daemon(&self) -> bool {
+        matches!(
+            self.command,
+            DaemonCommands::Start { .. } | DaemonCommands::Restart { .. }
+        )
+    }
+}
+
 #[derive(Subcommand)]
 pub enum DaemonCommands {
     /// Start the daemon
@@ -342,6 +437,7 @@ async fn start_daemon(foreground: bool, agent_id: &str) -> Result<()> {
     // Initialize components
     let memory = MemoryManager::new_with_full_config(&config.memory, Some(&config), agent_id)?;
     let _watcher = memory.start_watcher()?;
+    let _backfill = memory.spawn_embedding_backfill();
 
     println!("Daemon started successfully");
 
@@ -353,6 +449,26 @@ async fn start_daemon(foreground: bool, agent_id: &str) -> Result<()> {
     Ok(())
 }
 
+/// Wait for Ctrl+C, or SIGTERM on Unix, so that both shut down cleanly
+async fn shutdown_signal() -> Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result?,
+            _ = terminate.recv() => {}
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await?;
+        Ok(())
+    }
+}
+
 async fn stop_daemon() -> Result<()> {
     let pid_file = get_pid_file()?;
 
@@ -465,10 +581,7 @@ async fn show_status() -> Result<()> {
     }
     println!("  Server enabled: {}", config.server.enabled);
     if config.server.enabled {
-        println!(
-            "  Server address: http://{}:{}",
-            config.server.bind, config.server.port
-        );
+        println!("  Server address: {}", config.server.url());
     }
 
     Ok(())
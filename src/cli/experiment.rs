@@ -0,0 +1,62 @@
+//! CLI subcommand: `localgpt experiment`
+//!
+//! Compares the variants of an A/B experiment (`[agent.experiment]`).
+
+use anyhow::Result;
+use clap::Args;
+
+use localgpt::agent::{ExperimentStore, format_experiment_report};
+use localgpt::config::Config;
+
+#[derive(Args)]
+pub struct ExperimentArgs {
+    /// Experiment to report on (default: the configured one, else the most recent)
+    pub name: Option<String>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+
+    /// List recorded experiments
+    #[arg(long)]
+    pub list: bool,
+}
+
+pub async fn run(args: ExperimentArgs) -> Result<()> {
+    let store = ExperimentStore::open_default()?;
+    let experiments = store.experiments()?;
+
+    if args.list {
+        if experiments.is_empty() {
+            println!("No experiments recorded.");
+        }
+        for name in &experiments {
+            println!("{}", name);
+        }
+        return Ok(());
+    }
+
+    let configured = Config::load()
+        .ok()
+        .and_then(|c| c.agent.experiment)
+        .map(|e| e.name)
+        .filter(|name| !name.is_empty());
+    let Some(name) = args
+        .name
+        .or(configured)
+        .or_else(|| experiments.first().cloned())
+    else {
+        println!("No experiments recorded. Configure one under [agent.experiment].");
+        return Ok(());
+    };
+
+    let reports = store.report(&name)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else if reports.is_empty() {
+        println!("No turns recorded for experiment {}.", name);
+    } else {
+        print!("{}", format_experiment_report(&name, &reports));
+    }
+    Ok(())
+}
@@ -0,0 +1,152 @@
+//! Interactive first-run setup
+
+use anyhow::Result;
+use clap::Args;
+use std::io::Write;
+
+use localgpt::config::{
+    Config, SetupAnswers, SetupProvider, check_discord_token, check_provider, check_workspace,
+    write_config,
+};
+
+#[derive(Args)]
+pub struct InitArgs {
+    /// Overwrite an existing config
+    #[arg(short, long)]
+    pub force: bool,
+}
+
+pub async fn run(args: InitArgs) -> Result<()> {
+    let path = Config::config_path()?;
+    if path.exists() && !args.force {
+        anyhow::bail!(
+            "Config file already exists at {}. Use --force to start over.",
+            path.display()
+        );
+    }
+
+    println!("LocalGPT setup");
+    println!("--------------");
+    println!("Press Enter to accept the value in brackets.\n");
+
+    let mut answers = SetupAnswers::default();
+
+    // Model provider
+    loop {
+        println!("Model provider:");
+        for (i, provider) in SetupProvider::ALL.iter().enumerate() {
+            println!("  {}. {}", i + 1, provider.label());
+        }
+        let choice = ask("Choice", "1")?;
+        answers.provider = match choice.parse::<usize>() {
+            Ok(n) if (1..=SetupProvider::ALL.len()).contains(&n) => SetupProvider::ALL[n - 1],
+            _ => {
+                println!("Please enter a number from the list.\n");
+                continue;
+            }
+        };
+        let provider = answers.provider;
+
+        let endpoint_label = if provider == SetupProvider::ClaudeCli {
+            "Command"
+        } else {
+            "Endpoint"
+        };
+        answers.endpoint = ask(endpoint_label, provider.default_endpoint())?;
+        if provider.needs_api_key() {
+            let env_var = if provider == SetupProvider::Anthropic {
+                "${ANTHROPIC_API_KEY}"
+            } else {
+                "${OPENAI_API_KEY}"
+            };
+            answers.api_key = ask(
+                "API key (or ${VAR} to read it from the environment)",
+                env_var,
+            )?;
+        }
+        answers.model = ask("Model", provider.default_model())?;
+
+        print!("Checking {}... ", provider.label());
+        std::io::stdout().flush()?;
+        match check_provider(&answers).await {
+            Ok(found) => println!("✓ {}\n", found),
+            Err(e) => {
+                println!("✗ {}", e);
+                if confirm("Try again?", true)? {
+                    println!();
+                    continue;
+                }
+                println!();
+            }
+        }
+        break;
+    }
+
+    // Discord bot
+    loop {
+        answers.discord_token = ask("Discord bot token (empty to skip)", "")?;
+        if answers.discord_token.is_empty() {
+            println!();
+            break;
+        }
+        print!("Checking the token... ");
+        std::io::stdout().flush()?;
+        match check_discord_token(&answers.discord_token).await {
+            Ok(found) => println!("✓ {}\n", found),
+            Err(e) => {
+                println!("✗ {}", e);
+                if confirm("Try again?", true)? {
+                    continue;
+                }
+                println!();
+            }
+        }
+        break;
+    }
+
+    // Memory directory
+    loop {
+        answers.workspace = ask("Memory directory", &answers.workspace)?;
+        match check_workspace(&answers.workspace) {
+            Ok(dir) => {
+                println!("✓ {}\n", dir.display());
+                break;
+            }
+            Err(e) => println!("✗ {:#}", e),
+        }
+    }
+
+    write_config(&path, &answers)?;
+    println!("Wrote {}", path.display());
+    println!("\nNext steps:");
+    println!("  localgpt chat                 # talk to the assistant");
+    println!("  localgpt daemon start         # run the heartbeat, server and Discord bot");
+    println!("  localgpt service install      # start the daemon at login");
+    Ok(())
+}
+
+/// Ask for a value, returning `default` for an empty answer
+fn ask(question: &str, default: &str) -> Result<String> {
+    if default.is_empty() {
+        print!("{}: ", question);
+    } else {
+        print!("{} [{}]: ", question, default);
+    }
+    std::io::stdout().flush()?;
+
+    let mut input = String::new();
+    if std::io::stdin().read_line(&mut input)? == 0 {
+        anyhow::bail!("Setup cancelled");
+    }
+    let input = input.trim();
+    Ok(if input.is_empty() { default } else { input }.to_string())
+}
+
+fn confirm(question: &str, default: bool) -> Result<bool> {
+    let answer = ask(question, if default { "Y/n" } else { "y/N" })?.to_lowercase();
+    Ok(match answer.as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    })
+}
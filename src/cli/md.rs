@@ -29,7 +29,7 @@ pub enum MdCommands {
         #[arg(long)]
         json: bool,
 
-        /// Filter by action type (e.g., write_blocked, tamper_detected)
+        /// Filter by action type (e.g., write_blocked, tamper_detected, rate_limited)
         #[arg(long)]
         filter: Option<String>,
     },
@@ -1,8 +1,9 @@
 use anyhow::Result;
 use clap::{Args, Subcommand};
+use std::io::{IsTerminal, Write};
 
 use localgpt::config::Config;
-use localgpt::memory::MemoryManager;
+use localgpt::memory::{IndexCheck, MemoryHistory, MemoryManager, MemoryVersion};
 
 #[derive(Args)]
 pub struct MemoryArgs {
@@ -22,11 +23,19 @@ pub enum MemoryCommands {
         limit: usize,
     },
 
-    /// Reindex all memory files
+    /// Reindex all memory files and check the index for damage
     Reindex {
         /// Force full reindex (ignore file hashes)
         #[arg(short, long)]
         force: bool,
+
+        /// Files to index at once (default: number of CPUs, at most 4)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Remove orphaned index rows found by the check
+        #[arg(long)]
+        prune: bool,
     },
 
     /// Show memory statistics
@@ -38,6 +47,28 @@ pub enum MemoryCommands {
         #[arg(short, long, default_value = "10")]
         count: usize,
     },
+
+    /// List recorded versions of memory files
+    History {
+        /// Only this file (relative to the workspace, e.g. MEMORY.md)
+        file: Option<String>,
+
+        /// Number of versions to show
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+    },
+
+    /// Show what a version changed
+    Diff {
+        /// Version ID (from `memory history`)
+        version: i64,
+    },
+
+    /// Write a version's content back to its file
+    Restore {
+        /// Version ID (from `memory history`)
+        version: i64,
+    },
 }
 
 pub async fn run(args: MemoryArgs, agent_id: &str) -> Result<()> {
@@ -46,9 +77,14 @@ pub async fn run(args: MemoryArgs, agent_id: &str) -> Result<()> {
 
     match args.command {
         MemoryCommands::Search { query, limit } => search_memory(&memory, &query, limit).await,
-        MemoryCommands::Reindex { force } => reindex_memory(&memory, force).await,
+        MemoryCommands::Reindex { force, jobs, prune } => {
+            reindex_memory(&memory, force, jobs, prune).await
+        }
         MemoryCommands::Stats => show_stats(&memory).await,
         MemoryCommands::Recent { count } => show_recent(&memory, count).await,
+        MemoryCommands::History { file, limit } => show_history(&memory, file.as_deref(), limit),
+        MemoryCommands::Diff { version } => show_diff(&memory, version),
+        MemoryCommands::Restore { version } => restore_version(&memory, version),
     }
 }
 
@@ -89,13 +125,32 @@ async fn search_memory(memory: &MemoryManager, query: &str, limit: usize) -> Res
     Ok(())
 }
 
-async fn reindex_memory(memory: &MemoryManager, force: bool) -> Result<()> {
+async fn reindex_memory(
+    memory: &MemoryManager,
+    force: bool,
+    jobs: Option<usize>,
+    prune: bool,
+) -> Result<()> {
+    // The index has four database connections
+    let jobs = jobs.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(|n| n.get().min(4))
+            .unwrap_or(1)
+    });
     println!(
         "Reindexing memory files{}...",
         if force { " (full)" } else { "" }
     );
 
-    let stats = memory.reindex(force)?;
+    let show_progress = std::io::stderr().is_terminal();
+    let stats = memory.reindex_with(force, jobs, |done, total| {
+        if show_progress {
+            draw_progress(done, total);
+        }
+    })?;
+    if show_progress && stats.files_processed > 0 {
+        eprintln!();
+    }
 
     println!("Reindex complete:");
     println!("  Files processed: {}", stats.files_processed);
@@ -103,6 +158,21 @@ async fn reindex_memory(memory: &MemoryManager, force: bool) -> Result<()> {
     println!("  Chunks indexed: {}", stats.chunks_indexed);
     println!("  Duration: {:?}", stats.duration);
 
+    println!("\nChecking index...");
+    let check = memory.check_index()?;
+    report_check(&check);
+    if prune && (check.orphaned_chunks > 0 || check.orphaned_fts > 0) {
+        let removed = memory.prune_index()?;
+        println!("  Pruned {} orphaned rows", removed);
+    } else if !check.is_clean() {
+        if check.orphaned_chunks > 0 || check.orphaned_fts > 0 {
+            println!("  Run with --prune to remove orphaned rows");
+        }
+        if check.fts_error.is_some() || check.missing_fts > 0 || check.duplicate_chunks > 0 {
+            println!("  Run with --force to rebuild every file's entries");
+        }
+    }
+
     // Generate embeddings if provider is configured
     if memory.has_embeddings() {
         println!("\nGenerating embeddings...");
@@ -118,6 +188,45 @@ async fn reindex_memory(memory: &MemoryManager, force: bool) -> Result<()> {
     Ok(())
 }
 
+/// One-line progress bar on stderr, redrawn in place
+fn draw_progress(done: usize, total: usize) {
+    const WIDTH: usize = 30;
+    let filled = WIDTH * done / total.max(1);
+    eprint!(
+        "\r  [{}{}] {}/{}",
+        "#".repeat(filled),
+        "-".repeat(WIDTH - filled),
+        done,
+        total
+    );
+    let _ = std::io::stderr().flush();
+}
+
+fn report_check(check: &IndexCheck) {
+    match &check.fts_error {
+        Some(e) => println!("  Full-text index: damaged ({})", e),
+        None => println!("  Full-text index: ok"),
+    }
+    let counts = [
+        ("Orphaned chunks", check.orphaned_chunks),
+        ("Orphaned full-text rows", check.orphaned_fts),
+        ("Chunks missing from full-text index", check.missing_fts),
+        ("Duplicate chunks", check.duplicate_chunks),
+        ("Duplicate full-text rows", check.duplicate_fts),
+    ];
+    for (label, count) in counts {
+        if count > 0 {
+            println!("  {}: {}", label, count);
+        }
+    }
+    if !check.duplicate_files.is_empty() {
+        println!("  Files with identical content:");
+        for group in &check.duplicate_files {
+            println!("    {}", group.join(", "));
+        }
+    }
+}
+
 async fn show_stats(memory: &MemoryManager) -> Result<()> {
     let stats = memory.stats()?;
 
@@ -155,3 +264,71 @@ async fn show_recent(memory: &MemoryManager, count: usize) -> Result<()> {
 
     Ok(())
 }
+
+fn find_version(history: &MemoryHistory, id: i64) -> Result<MemoryVersion> {
+    history
+        .get(id)?
+        .ok_or_else(|| anyhow::anyhow!("No version {} in the memory history", id))
+}
+
+fn show_history(memory: &MemoryManager, file: Option<&str>, limit: usize) -> Result<()> {
+    let history = MemoryHistory::for_workspace(memory.workspace())?;
+    let versions = history.versions(file, limit)?;
+
+    if versions.is_empty() {
+        println!("No recorded versions");
+        return Ok(());
+    }
+
+    for version in versions {
+        let time = chrono::DateTime::from_timestamp(version.created_at, 0)
+            .map(|t| {
+                t.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        println!(
+            "#{:<6} {}  {}{}  ({})",
+            version.id,
+            time,
+            version.path,
+            if version.is_deletion() {
+                " [deleted]"
+            } else {
+                ""
+            },
+            version.source
+        );
+    }
+
+    Ok(())
+}
+
+fn show_diff(memory: &MemoryManager, id: i64) -> Result<()> {
+    let history = MemoryHistory::for_workspace(memory.workspace())?;
+    let version = find_version(&history, id)?;
+    let diff = history.diff(&version)?;
+
+    if diff.is_empty() {
+        println!("Version {} has the same content as the one before it", id);
+    } else {
+        print!("{}", diff);
+    }
+
+    Ok(())
+}
+
+fn restore_version(memory: &MemoryManager, id: i64) -> Result<()> {
+    let history = MemoryHistory::for_workspace(memory.workspace())?;
+    let version = find_version(&history, id)?;
+    let path = history.restore(&version)?;
+
+    println!("Restored {} to version {}", path.display(), id);
+    println!(
+        "The content it replaced is in `localgpt memory history {}`",
+        version.path
+    );
+
+    Ok(())
+}
@@ -4,9 +4,12 @@ pub mod config;
 pub mod daemon;
 #[cfg(feature = "desktop")]
 pub mod desktop;
+pub mod experiment;
+pub mod init;
 pub mod md;
 pub mod memory;
 pub mod sandbox;
+pub mod service;
 
 use clap::{Parser, Subcommand};
 
@@ -39,6 +42,9 @@ pub struct Cli {
 
 #[derive(Subcommand)]
 pub enum Commands {
+    /// Set up LocalGPT interactively (provider, Discord, memory directory)
+    Init(init::InitArgs),
+
     /// Start an interactive chat session
     Chat(chat::ChatArgs),
 
@@ -63,4 +69,10 @@ pub enum Commands {
 
     /// Shell sandbox management
     Sandbox(sandbox::SandboxArgs),
+
+    /// Run the daemon as a systemd, launchd or Task Scheduler service
+    Service(service::ServiceArgs),
+
+    /// Compare the variants of an A/B experiment
+    Experiment(experiment::ExperimentArgs),
 }
@@ -0,0 +1,614 @@
+//! Run the daemon as an OS service
+//!
+//! `localgpt service install` registers `localgpt daemon start --foreground`
+//! with the platform's service manager so it starts at login and is
+//! restarted if it crashes:
+//!
+//! - Linux: a systemd user unit, `~/.config/systemd/user/localgpt.service`
+//! - macOS: a launchd agent, `~/Library/LaunchAgents/com.localgpt.daemon.plist`
+//! - Windows: a Task Scheduler task run at logon, `LocalGPT`
+//!
+//! The unit runs the current executable with the current agent ID and
+//! carries over `PATH`, the `LOCALGPT_*` variables, and any variable that
+//! config.toml refers to as `${VAR}`. systemd and launchd stop the daemon
+//! with SIGTERM, which it handles like Ctrl+C.
+
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use localgpt::config::Config;
+use localgpt::security::REDACTED;
+
+#[cfg(target_os = "macos")]
+const LAUNCHD_LABEL: &str = "com.localgpt.daemon";
+#[cfg(windows)]
+const TASK_NAME: &str = "LocalGPT";
+
+/// Variables passed through besides those config.toml refers to
+const PASSED_VARS: &[&str] = &[
+    "PATH",
+    "LOCALGPT_WORKSPACE",
+    "LOCALGPT_PROFILE",
+    "FASTEMBED_CACHE_DIR",
+    "RUST_LOG",
+];
+
+#[derive(Args)]
+pub struct ServiceArgs {
+    #[command(subcommand)]
+    pub command: ServiceCommands,
+}
+
+#[derive(Subcommand)]
+pub enum ServiceCommands {
+    /// Register the daemon with the OS service manager and start it
+    Install {
+        /// Write the service definition without enabling or starting it
+        #[arg(long)]
+        no_start: bool,
+
+        /// Print the service definition instead of installing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Stop the service and remove its definition
+    Uninstall,
+
+    /// Show whether the service is installed and running
+    Status,
+}
+
+pub async fn run(args: ServiceArgs, agent_id: &str) -> Result<()> {
+    match args.command {
+        ServiceCommands::Install { no_start, dry_run } => install(agent_id, no_start, dry_run),
+        ServiceCommands::Uninstall => uninstall(),
+        ServiceCommands::Status => status(),
+    }
+}
+
+/// What the service runs
+#[derive(Clone)]
+struct ServiceSpec {
+    exe: PathBuf,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+}
+
+impl ServiceSpec {
+    fn current(agent_id: &str) -> Result<Self> {
+        let exe = std::env::current_exe()
+            .and_then(|p| p.canonicalize())
+            .context("Could not determine the localgpt executable")?;
+        let mut names: Vec<String> = PASSED_VARS.iter().map(|v| v.to_string()).collect();
+        if let Ok(content) = fs::read_to_string(Config::config_path()?) {
+            for name in config_env_vars(&content) {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        let env = names
+            .into_iter()
+            .filter_map(|name| std::env::var(&name).ok().map(|value| (name, value)))
+            .collect();
+
+        Ok(Self {
+            exe,
+            args: ["--agent", agent_id, "daemon", "start", "--foreground"]
+                .iter()
+                .map(|a| a.to_string())
+                .collect(),
+            env,
+        })
+    }
+
+    /// The spec as `--dry-run` prints it: the values of variables
+    /// config.toml refers to (API keys, tokens) are masked
+    #[cfg(any(target_os = "linux", target_os = "macos", test))]
+    fn masked(&self) -> Self {
+        let env = self
+            .env
+            .iter()
+            .map(|(name, value)| {
+                let value = if PASSED_VARS.contains(&name.as_str()) {
+                    value.clone()
+                } else {
+                    REDACTED.to_string()
+                };
+                (name.clone(), value)
+            })
+            .collect();
+        Self {
+            env,
+            ..self.clone()
+        }
+    }
+}
+
+/// Names of the variables config.toml refers to as `${VAR}`
+fn config_env_vars(content: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    for line in content.lines().filter(|l| !l.trim_start().starts_with('#')) {
+        let mut rest = line;
+        while let Some(start) = rest.find("${") {
+            rest = &rest[start + 2..];
+            let Some(end) = rest.find('}') else {
+                break;
+            };
+            let name = &rest[..end];
+            if !name.is_empty()
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && !names.iter().any(|n| n == name)
+            {
+                names.push(name.to_string());
+            }
+            rest = &rest[end + 1..];
+        }
+    }
+    names
+}
+
+fn install(agent_id: &str, no_start: bool, dry_run: bool) -> Result<()> {
+    let spec = ServiceSpec::current(agent_id)?;
+    platform::install(&spec, no_start, dry_run)
+}
+
+fn uninstall() -> Result<()> {
+    platform::uninstall()
+}
+
+fn status() -> Result<()> {
+    platform::status()
+}
+
+#[cfg(unix)]
+fn home_dir() -> Result<PathBuf> {
+    Ok(directories::BaseDirs::new()
+        .ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?
+        .home_dir()
+        .to_path_buf())
+}
+
+/// Write a service definition readable only by the user, since the
+/// environment may include API keys
+#[cfg(unix)]
+fn write_private(path: &std::path::Path, content: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, content)?;
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+/// Run a service manager command, failing with its output if it fails
+fn run_command(program: &str, args: &[&str]) -> Result<()> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+    use std::path::Path;
+
+    const UNIT_NAME: &str = "localgpt.service";
+
+    fn unit_path(home: &Path) -> PathBuf {
+        home.join(".config")
+            .join("systemd")
+            .join("user")
+            .join(UNIT_NAME)
+    }
+
+    /// Quote a word for an ExecStart= or Environment= line
+    fn quote(value: &str) -> String {
+        format!(
+            "\"{}\"",
+            value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('%', "%%")
+        )
+    }
+
+    fn unit(spec: &ServiceSpec) -> String {
+        let mut exec = quote(&spec.exe.to_string_lossy());
+        for arg in &spec.args {
+            exec.push(' ');
+            exec.push_str(&quote(arg));
+        }
+        let env: String = spec
+            .env
+            .iter()
+            .map(|(name, value)| format!("Environment={}\n", quote(&format!("{}={}", name, value))))
+            .collect();
+        format!(
+            "[Unit]\n\
+             Description=LocalGPT daemon\n\
+             After=network-online.target\n\
+             Wants=network-online.target\n\
+             \n\
+             [Service]\n\
+             Type=simple\n\
+             ExecStart={exec}\n\
+             WorkingDirectory=%h\n\
+             {env}\
+             Restart=on-failure\n\
+             RestartSec=5\n\
+             KillSignal=SIGTERM\n\
+             TimeoutStopSec=30\n\
+             \n\
+             [Install]\n\
+             WantedBy=default.target\n"
+        )
+    }
+
+    pub(super) fn install(spec: &ServiceSpec, no_start: bool, dry_run: bool) -> Result<()> {
+        let path = unit_path(&home_dir()?);
+        if dry_run {
+            println!("# {}\n{}", path.display(), unit(&spec.masked()));
+            return Ok(());
+        }
+
+        write_private(&path, &unit(spec))?;
+        run_command("systemctl", &["--user", "daemon-reload"])?;
+        if no_start {
+            println!("Start it with: systemctl --user enable --now {}", UNIT_NAME);
+            return Ok(());
+        }
+        run_command("systemctl", &["--user", "enable", "--now", UNIT_NAME])?;
+        println!("Service installed and started");
+        println!("  Logs: journalctl --user -u {} -f", UNIT_NAME);
+        println!(
+            "  To keep it running while logged out: loginctl enable-linger {}",
+            std::env::var("USER").unwrap_or_default()
+        );
+        Ok(())
+    }
+
+    pub(super) fn uninstall() -> Result<()> {
+        let path = unit_path(&home_dir()?);
+        if !path.exists() {
+            println!("Service is not installed");
+            return Ok(());
+        }
+        // Stops the daemon with SIGTERM
+        if let Err(e) = run_command("systemctl", &["--user", "disable", "--now", UNIT_NAME]) {
+            println!("Warning: {}", e);
+        }
+        fs::remove_file(&path)?;
+        run_command("systemctl", &["--user", "daemon-reload"])?;
+        println!("Service removed");
+        Ok(())
+    }
+
+    pub(super) fn status() -> Result<()> {
+        // systemctl exits non-zero for stopped units; the output says why
+        let status = Command::new("systemctl")
+            .args(["--user", "status", "--no-pager", UNIT_NAME])
+            .status()
+            .context("Failed to run systemctl")?;
+        if status.code() == Some(4) {
+            println!("Service is not installed. Run: localgpt service install");
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_unit_quoting() {
+            assert_eq!(
+                quote(r#"say "hi" 100% C:\x"#),
+                r#""say \"hi\" 100%% C:\\x""#
+            );
+
+            let spec = ServiceSpec {
+                exe: PathBuf::from("/opt/local gpt/localgpt"),
+                args: vec!["--agent".to_string(), "main".to_string()],
+                env: vec![("API_KEY".to_string(), "k%y".to_string())],
+            };
+            let unit = unit(&spec);
+            assert!(unit.contains("ExecStart=\"/opt/local gpt/localgpt\" \"--agent\" \"main\"\n"));
+            assert!(unit.contains("Environment=\"API_KEY=k%%y\"\n"));
+            assert!(unit.contains("WorkingDirectory=%h\n"));
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::*;
+    use std::path::Path;
+
+    fn plist_path(home: &Path) -> PathBuf {
+        home.join("Library")
+            .join("LaunchAgents")
+            .join(format!("{}.plist", LAUNCHD_LABEL))
+    }
+
+    /// `gui/<uid>`, the launchd domain of the logged-in user
+    fn domain() -> Result<String> {
+        let output = Command::new("id")
+            .arg("-u")
+            .output()
+            .context("Failed to run id")?;
+        Ok(format!(
+            "gui/{}",
+            String::from_utf8_lossy(&output.stdout).trim()
+        ))
+    }
+
+    fn escape(value: &str) -> String {
+        value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+
+    fn plist(spec: &ServiceSpec, home: &Path, log_file: &Path) -> String {
+        let args: String = std::iter::once(spec.exe.to_string_lossy().to_string())
+            .chain(spec.args.iter().cloned())
+            .map(|arg| format!("        <string>{}</string>\n", escape(&arg)))
+            .collect();
+        let env: String = spec
+            .env
+            .iter()
+            .map(|(name, value)| {
+                format!(
+                    "        <key>{}</key>\n        <string>{}</string>\n",
+                    escape(name),
+                    escape(value)
+                )
+            })
+            .collect();
+        let log = escape(&log_file.to_string_lossy());
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \
+             \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n\
+             <dict>\n\
+             \x20   <key>Label</key>\n\
+             \x20   <string>{label}</string>\n\
+             \x20   <key>ProgramArguments</key>\n\
+             \x20   <array>\n\
+             {args}\
+             \x20   </array>\n\
+             \x20   <key>EnvironmentVariables</key>\n\
+             \x20   <dict>\n\
+             {env}\
+             \x20   </dict>\n\
+             \x20   <key>WorkingDirectory</key>\n\
+             \x20   <string>{home}</string>\n\
+             \x20   <key>RunAtLoad</key>\n\
+             \x20   <true/>\n\
+             \x20   <key>KeepAlive</key>\n\
+             \x20   <dict>\n\
+             \x20       <key>SuccessfulExit</key>\n\
+             \x20       <false/>\n\
+             \x20   </dict>\n\
+             \x20   <key>ExitTimeOut</key>\n\
+             \x20   <integer>30</integer>\n\
+             \x20   <key>StandardOutPath</key>\n\
+             \x20   <string>{log}</string>\n\
+             \x20   <key>StandardErrorPath</key>\n\
+             \x20   <string>{log}</string>\n\
+             </dict>\n\
+             </plist>\n",
+            label = LAUNCHD_LABEL,
+            home = escape(&home.to_string_lossy()),
+        )
+    }
+
+    pub(super) fn install(spec: &ServiceSpec, no_start: bool, dry_run: bool) -> Result<()> {
+        let home = home_dir()?;
+        let log_file = localgpt::agent::get_state_dir()?
+            .join("logs")
+            .join("service.log");
+        let path = plist_path(&home);
+        if dry_run {
+            let plist = plist(&spec.masked(), &home, &log_file);
+            println!("<!-- {} -->\n{}", path.display(), plist);
+            return Ok(());
+        }
+
+        if let Some(dir) = log_file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        write_private(&path, &plist(spec, &home, &log_file))?;
+        let path = path.to_string_lossy();
+        if no_start {
+            println!("Start it with: launchctl bootstrap {} {}", domain()?, path);
+            return Ok(());
+        }
+        // Replace a loaded older definition
+        let _ = run_command(
+            "launchctl",
+            &["bootout", &format!("{}/{}", domain()?, LAUNCHD_LABEL)],
+        );
+        run_command("launchctl", &["bootstrap", &domain()?, &path])?;
+        println!("Service installed and started");
+        println!("  Logs: {}", log_file.display());
+        Ok(())
+    }
+
+    pub(super) fn uninstall() -> Result<()> {
+        let path = plist_path(&home_dir()?);
+        if !path.exists() {
+            println!("Service is not installed");
+            return Ok(());
+        }
+        // Stops the daemon with SIGTERM
+        if let Err(e) = run_command(
+            "launchctl",
+            &["bootout", &format!("{}/{}", domain()?, LAUNCHD_LABEL)],
+        ) {
+            println!("Warning: {}", e);
+        }
+        fs::remove_file(&path)?;
+        println!("Service removed");
+        Ok(())
+    }
+
+    pub(super) fn status() -> Result<()> {
+        let output = Command::new("launchctl")
+            .args(["print", &format!("{}/{}", domain()?, LAUNCHD_LABEL)])
+            .output()
+            .context("Failed to run launchctl")?;
+        if !output.status.success() {
+            println!("Service is not loaded. Run: localgpt service install");
+            return Ok(());
+        }
+        let text = String::from_utf8_lossy(&output.stdout);
+        for line in text.lines().map(str::trim).filter(|l| {
+            l.starts_with("state =")
+                || l.starts_with("pid =")
+                || l.starts_with("last exit code =")
+                || l.starts_with("runs =")
+        }) {
+            println!("{}", line);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+
+    /// Quote a word of the task's command line
+    fn quote(value: &str) -> String {
+        format!("\"{}\"", value.replace('"', "\\\""))
+    }
+
+    pub(super) fn install(spec: &ServiceSpec, no_start: bool, dry_run: bool) -> Result<()> {
+        let mut command = quote(&spec.exe.to_string_lossy());
+        for arg in &spec.args {
+            command.push(' ');
+            command.push_str(&quote(arg));
+        }
+        let args = [
+            "/Create", "/TN", TASK_NAME, "/TR", &command, "/SC", "ONLOGON", "/RL", "LIMITED", "/F",
+        ];
+        if dry_run {
+            println!("schtasks {}", args.join(" "));
+            return Ok(());
+        }
+
+        run_command("schtasks", &args)?;
+        println!("Scheduled task '{}' created (runs at logon)", TASK_NAME);
+        // Tasks get the user's environment, not this shell's
+        let session_only: Vec<&str> = spec
+            .env
+            .iter()
+            .filter(|(name, _)| name != "PATH")
+            .map(|(name, _)| name.as_str())
+            .collect();
+        if !session_only.is_empty() {
+            println!(
+                "  Make sure these are set as user environment variables (setx): {}",
+                session_only.join(", ")
+            );
+        }
+        if !no_start {
+            run_command("schtasks", &["/Run", "/TN", TASK_NAME])?;
+            println!("  Started");
+        }
+        Ok(())
+    }
+
+    pub(super) fn uninstall() -> Result<()> {
+        // Ending the task does not give the daemon a chance to shut down
+        // cleanly, so stop it through its PID file first
+        let _ = crate::cli::daemon::stop_sync();
+        let _ = run_command("schtasks", &["/End", "/TN", TASK_NAME]);
+        run_command("schtasks", &["/Delete", "/TN", TASK_NAME, "/F"])?;
+        println!("Scheduled task '{}' removed", TASK_NAME);
+        Ok(())
+    }
+
+    pub(super) fn status() -> Result<()> {
+        let status = Command::new("schtasks")
+            .args(["/Query", "/TN", TASK_NAME, "/V", "/FO", "LIST"])
+            .status()
+            .context("Failed to run schtasks")?;
+        if !status.success() {
+            println!("Service is not installed. Run: localgpt service install");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod platform {
+    use super::*;
+
+    pub(super) fn install(_spec: &ServiceSpec, _no_start: bool, _dry_run: bool) -> Result<()> {
+        anyhow::bail!("No supported service manager on this platform")
+    }
+
+    pub(super) fn uninstall() -> Result<()> {
+        anyhow::bail!("No supported service manager on this platform")
+    }
+
+    pub(super) fn status() -> Result<()> {
+        anyhow::bail!("No supported service manager on this platform")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_env_vars() {
+        let content = r#"
+api_key = "${OPENAI_API_KEY}"
+# token = "${COMMENTED_OUT}"
+url = "http://${HOST}:${PORT}/${HOST}"
+odd = "${not a name}"
+open = "${UNCLOSED"
+"#;
+        assert_eq!(
+            config_env_vars(content),
+            vec!["OPENAI_API_KEY", "HOST", "PORT"]
+        );
+    }
+
+    #[test]
+    fn test_dry_run_masks_secrets() {
+        let spec = ServiceSpec {
+            exe: PathBuf::from("/usr/bin/localgpt"),
+            args: Vec::new(),
+            env: vec![
+                ("PATH".to_string(), "/usr/bin".to_string()),
+                ("ANTHROPIC_API_KEY".to_string(), "sk-ant-secret".to_string()),
+            ],
+        };
+        let masked = spec.masked();
+        assert_eq!(masked.env[0].1, "/usr/bin");
+        assert_eq!(masked.env[1].1, REDACTED);
+        assert_eq!(spec.env[1].1, "sk-ant-secret");
+    }
+}
@@ -5,6 +5,7 @@
 pub enum Interface {
     Cli,
     Telegram,
+    Discord,
 }
 
 /// A slash command definition.
@@ -107,10 +108,10 @@ pub const COMMANDS: &[SlashCommand] = &[
     },
     SlashCommand {
         name: "export",
-        description: "Export session as markdown",
+        description: "Export session as markdown or HTML",
         aliases: &[],
         usage: "[file]",
-        interfaces: &[Interface::Cli],
+        interfaces: &[Interface::Cli, Interface::Discord],
     },
     SlashCommand {
         name: "attach",
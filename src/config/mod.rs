@@ -1,8 +1,13 @@
 mod migrate;
 mod schema;
+mod setup;
 
 pub use migrate::{has_openclaw_workspace, openclaw_config_path, try_migrate_openclaw_config};
 pub use schema::*;
+pub use setup::{
+    SetupAnswers, SetupProvider, check_discord_token, check_provider, check_workspace,
+    render_config, write_config,
+};
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
@@ -42,9 +47,30 @@ pub security: SecurityConfig,
     #[serde(default)]
     pub telegram: Option<TelegramConfig>,
 
+    #[serde(default)]
+    pub calendar: Option<CalendarConfig>,
+
+    #[serde(default)]
+    pub context: ContextConfig,
+
+    #[serde(default)]
+    pub jobs: JobsConfig,
+
+    #[serde(default)]
+    pub monitor: MonitorConfig,
+
+    #[serde(default)]
+    pub health: HealthConfig,
+
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+
     #[serde(default)]
     pub channels: ChannelsConfig,
 
+    #[serde(default)]
+    pub replies: RepliesConfig,
+
     #[serde(default)]
     pub tags: HashMap<String, TagGroup>,
 }
@@ -60,9 +86,117 @@ pub struct AgentConfig {
     #[serde(default = "default_reserve_tokens")]
     pub reserve_tokens: usize,
 
+    /// Size the reserve from measured reply lengths instead of
+    /// `reserve_tokens`
+    #[serde(default)]
+    pub adaptive_reserve: AdaptiveReserveConfig,
+
     /// Maximum tokens for LLM response
     #[serde(default = "default_max_tokens")]
     pub max_tokens: usize,
+
+    /// Models a session can be switched to with `/model` in Discord or the
+    /// desktop app's model picker (the default model is always allowed)
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+
+    /// Limits for the tool-calling loop within one turn
+    #[serde(default)]
+    pub tool_loop: ToolLoopConfig,
+
+    /// Language of built-in prompts and replies ("en", "ja"); also selects
+    /// SOUL.<language>.md when present
+    #[serde(default = "default_language")]
+    pub language: String,
+
+    /// Follow the language of each conversation's first message
+    #[serde(default)]
+    pub detect_language: bool,
+
+    /// Answer a share of turns with an alternate configuration and record
+    /// both variants for comparison
+    #[serde(default)]
+    pub experiment: Option<ExperimentConfig>,
+
+    /// Recent 👎 replies listed in the system prompt as examples to avoid
+    /// (0 = off)
+    #[serde(default)]
+    pub feedback_examples: usize,
+
+    /// Background summaries (archived Discord sessions) of up to this many
+    /// characters in total share one request (0 = one request each)
+    #[serde(default = "default_summary_batch_chars")]
+    pub summary_batch_chars: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdaptiveReserveConfig {
+    pub enabled: bool,
+
+    /// Percentile of recent reply lengths the reserve must cover
+    pub percentile: u8,
+
+    /// Headroom multiplied onto the percentile
+    pub margin: f64,
+
+    /// Smallest reserve, whatever the replies
+    pub min_tokens: usize,
+
+    /// Replies measured in a scope before its reserve is adjusted
+    pub min_samples: usize,
+
+    /// Most recent replies kept per scope
+    pub window: usize,
+}
+
+impl Default for AdaptiveReserveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            percentile: 95,
+            margin: 1.25,
+            min_tokens: 1024,
+            min_samples: 20,
+            window: 200,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExperimentConfig {
+    /// Name the results are recorded under; a new name starts a new comparison
+    #[serde(default)]
+    pub name: String,
+
+    /// Share of turns (0-100) answered by the alternate configuration
+    #[serde(default)]
+    pub percent: u8,
+
+    /// Model of the alternate configuration (default: the usual model)
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Extra system prompt instructions for the alternate configuration
+    #[serde(default)]
+    pub instructions: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolLoopConfig {
+    /// Tool-call rounds per turn before the model must answer
+    #[serde(default = "default_tool_loop_max_iterations")]
+    pub max_iterations: usize,
+
+    /// Times the exact same call (tool and arguments) may run in one turn;
+    /// further repeats are refused and count as a loop
+    #[serde(default = "default_tool_loop_max_repeated_calls")]
+    pub max_repeated_calls: usize,
+
+    /// Approximate tokens of tool output fed back per round, shared between
+    /// that round's calls (0 = unlimited)
+    #[serde(default = "default_tool_loop_iteration_token_budget")]
+    pub iteration_token_budget: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -91,6 +225,43 @@ pub struct ToolsConfig {
     /// Wrap tool outputs and memory content with XML-style delimiters
     #[serde(default = "default_true")]
     pub use_content_delimiters: bool,
+
+    /// `screenshot` tool (needs the `screenshot` build feature)
+    #[serde(default)]
+    pub screenshot: Option<ScreenshotConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreenshotConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Domains that may be rendered, subdomains included (`"*"` = any)
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+
+    /// Model that looks at the screenshots (default: agent.default_model)
+    #[serde(default)]
+    pub vision_model: Option<String>,
+
+    /// Viewport size in pixels
+    #[serde(default = "default_screenshot_width")]
+    pub width: u32,
+
+    #[serde(default = "default_screenshot_height")]
+    pub height: u32,
+
+    /// How long a page's screenshot is reused before rendering it again
+    #[serde(default = "default_screenshot_cache_minutes")]
+    pub cache_minutes: u64,
+
+    /// Give up on pages that take longer to load
+    #[serde(default = "default_screenshot_timeout_secs")]
+    pub timeout_secs: u64,
+
+    /// Chrome/Chromium binary (default: found on PATH)
+    #[serde(default)]
+    pub chrome_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -110,6 +281,31 @@ pub struct SecurityConfig {
     /// Skip injecting the hardcoded security suffix (default: false)
     #[serde(default)]
     pub disable_suffix: bool,
+
+    /// Masking of secrets in logs, memory files and the audit log
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    /// Mask secrets before they are written (default: true)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Mask common token formats: API keys, bot tokens, bearer tokens,
+    /// private keys (default: true)
+    #[serde(default = "default_true")]
+    pub builtin: bool,
+
+    /// Additional regexes to mask
+    #[serde(default)]
+    pub patterns: Vec<String>,
+
+    /// Write text unchanged and report each match to
+    /// logs/redactions.jsonl instead (default: false)
+    #[serde(default)]
+    pub test_mode: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -204,6 +400,74 @@ pub struct ProvidersConfig {
 
     #[serde(default)]
     pub glm: Option<GlmConfig>,
+
+    #[serde(default)]
+    pub resilience: ResilienceConfig,
+}
+
+/// Retries and circuit breaking for model provider calls
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResilienceConfig {
+    /// Extra attempts after a transient failure (timeouts, 5xx, overload)
+    #[serde(default = "default_resilience_max_retries")]
+    pub max_retries: u32,
+
+    /// Delay before the first retry; doubled for each further retry
+    #[serde(default = "default_resilience_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+
+    #[serde(default = "default_resilience_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+
+    /// Consecutive failed requests that open the circuit (0 = never open)
+    #[serde(default = "default_resilience_failure_threshold")]
+    pub failure_threshold: u32,
+
+    /// How long an open circuit rejects calls before one is let through
+    #[serde(default = "default_resilience_cooldown")]
+    pub cooldown: String,
+
+    /// Model to answer with while the circuit is open
+    #[serde(default)]
+    pub fallback_model: Option<String>,
+
+    /// Reply while the circuit is open and there is no fallback model
+    #[serde(default = "default_resilience_fallback_message")]
+    pub fallback_message: String,
+}
+
+fn default_resilience_max_retries() -> u32 {
+    2
+}
+fn default_resilience_initial_backoff_ms() -> u64 {
+    500
+}
+fn default_resilience_max_backoff_ms() -> u64 {
+    8000
+}
+fn default_resilience_failure_threshold() -> u32 {
+    5
+}
+fn default_resilience_cooldown() -> String {
+    "60s".to_string()
+}
+fn default_resilience_fallback_message() -> String {
+    "Sorry, I can't reach my language model right now. Please try again in a few minutes."
+        .to_string()
+}
+
+impl Default for ResilienceConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: default_resilience_max_retries(),
+            initial_backoff_ms: default_resilience_initial_backoff_ms(),
+            max_backoff_ms: default_resilience_max_backoff_ms(),
+            failure_threshold: default_resilience_failure_threshold(),
+            cooldown: default_resilience_cooldown(),
+            fallback_model: None,
+            fallback_message: default_resilience_fallback_message(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -261,6 +525,14 @@ pub struct HeartbeatConfig {
 
     #[serde(default)]
     pub timezone: Option<String>,
+
+    /// End-of-day self-reflection over the day's conversations
+    #[serde(default)]
+    pub reflection: ReflectionConfig,
+
+    /// Nightly memory index maintenance
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -270,119 +542,882 @@ pub struct ActiveHours {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MemoryConfig {
-    #[serde(default = "default_workspace")]
-    pub workspace: String,
+pub struct ReflectionConfig {
+    /// Run the daily reflection task (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Local time after which the reflection runs, once per day (HH:MM)
+    #[serde(default = "default_reflection_time")]
+    pub time: String,
+
+    /// Discord channel ID to post the end-of-day summary to (optional)
+    #[serde(default)]
+    pub notify_discord_channel: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceConfig {
+    /// Run index maintenance once per day (default: true)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Local time after which maintenance runs, once per day (HH:MM).
+    /// Runs regardless of active_hours, so pick a low-traffic time.
+    #[serde(default = "default_maintenance_time")]
+    pub time: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryConfig {
+    #[serde(default = "default_workspace")]
+    pub workspace: String,
+
+    /// Embedding provider: "local" (fastembed, default), "openai", or "none"
+    #[serde(default = "default_embedding_provider")]
+    pub embedding_provider: String,
+
+    #[serde(default = "default_embedding_model")]
+    pub embedding_model: String,
+
+    /// Cache directory for local embedding models (optional)
+    /// Default: ~/.cache/localgpt/models
+    /// Can also be set via FASTEMBED_CACHE_DIR environment variable
+    #[serde(default = "default_embedding_cache_dir")]
+    pub embedding_cache_dir: String,
+
+    #[serde(default = "default_chunk_size")]
+    pub chunk_size: usize,
+
+    #[serde(default = "default_chunk_overlap")]
+    pub chunk_overlap: usize,
+
+    /// Additional paths to index (relative to workspace or absolute)
+    /// Each path uses a glob pattern for file matching
+    #[serde(default = "default_index_paths")]
+    pub paths: Vec<MemoryIndexPath>,
+
+    /// Maximum messages to save in session memory files (0 = unlimited)
+    /// Similar to OpenClaw's hooks.session-memory.messages (default: 15)
+    #[serde(default = "default_session_max_messages")]
+    pub session_max_messages: usize,
+
+    /// Maximum characters per message in session memory (0 = unlimited)
+    /// Set to 0 to preserve full message content like OpenClaw
+    #[serde(default)]
+    pub session_max_chars: usize,
+
+    /// Automatic per-message retrieval of relevant memory snippets
+    #[serde(default)]
+    pub recall: RecallConfig,
+
+    /// Background embedding of chunks that have none (daemon only)
+    #[serde(default)]
+    pub backfill: EmbeddingBackfillConfig,
+
+    /// Obsidian vault layout (daily notes, wikilinks)
+    #[serde(default)]
+    pub obsidian: ObsidianConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingBackfillConfig {
+    /// Embed the archive in the background when the daemon starts (default: true)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Chunks per embedding request
+    #[serde(default = "default_backfill_batch_size")]
+    pub batch_size: usize,
+
+    /// Maximum embedding requests per minute
+    #[serde(default = "default_backfill_batches_per_minute")]
+    pub batches_per_minute: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecallConfig {
+    /// Search memory with each incoming message and inject top hits (default: true)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Maximum number of snippets to inject per message
+    #[serde(default = "default_recall_top_k")]
+    pub top_k: usize,
+
+    /// Minimum search score for a snippet to be injected.
+    /// Hybrid search scores range 0-1; FTS-only scores are BM25 (usually > 1).
+    #[serde(default = "default_recall_min_score")]
+    pub min_score: f64,
+
+    /// Token budget for injected snippets
+    #[serde(default = "default_recall_max_tokens")]
+    pub max_tokens: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObsidianConfig {
+    /// Lay out memory like an Obsidian vault (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Folder for daily notes, relative to the workspace ("" = vault root,
+    /// Obsidian's default)
+    #[serde(default)]
+    pub daily_folder: String,
+
+    /// Daily note file name, as a chrono format string (default: "%Y-%m-%d",
+    /// Obsidian's YYYY-MM-DD)
+    #[serde(default = "default_daily_format")]
+    pub daily_format: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryIndexPath {
+    pub path: String,
+    #[serde(default = "default_pattern")]
+    pub pattern: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    #[serde(default = "default_port")]
+    pub port: u16,
+
+    #[serde(default = "default_bind")]
+    pub bind: String,
+
+    /// Path prefix when served under a sub-path by a reverse proxy
+    /// (e.g. "/localgpt"). Empty = served at the root.
+    #[serde(default)]
+    pub base_path: String,
+
+    /// Serve HTTPS directly instead of plain HTTP
+    #[serde(default)]
+    pub tls: Option<ServerTlsConfig>,
+
+    /// Proxies whose X-Forwarded-For header is trusted (IP addresses or
+    /// CIDR ranges, e.g. "127.0.0.1", "10.0.0.0/8")
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+
+    /// Serve the read-only status dashboard at /dashboard
+    #[serde(default = "default_true")]
+    pub dashboard: bool,
+    /// Bearer token required by admin endpoints such as `PUT /api/features`
+    /// (None = those endpoints are refused)
+    #[serde(default)]
+    pub admin_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerTlsConfig {
+    /// PEM certificate chain
+    pub cert_path: String,
+
+    /// PEM private key
+    pub key_path: String,
+}
+
+impl ServerConfig {
+    /// Base path normalized to "" or "/prefix" (no trailing slash)
+    pub fn normalized_base_path(&self) -> String {
+        let trimmed = self.base_path.trim().trim_matches('/');
+        if trimmed.is_empty() {
+            String::new()
+        } else {
+            format!("/{}", trimmed)
+        }
+    }
+
+    /// URL the server is reachable at (before any reverse proxy)
+    pub fn url(&self) -> String {
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
+        format!(
+            "{}://{}:{}{}",
+            scheme,
+            self.bind,
+            self.port,
+            self.normalized_base_path()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    #[serde(default = "default_log_level")]
+    pub level: String,
+
+    #[serde(default = "default_log_file")]
+    pub file: String,
+
+    /// Days to keep log files (0 = keep forever, no auto-deletion)
+    #[serde(default)]
+    pub retention_days: u32,
+    /// Also write structured JSON logs, one file per subsystem and day
+    #[serde(default)]
+    pub json: bool,
+
+    /// Size at which a JSON log file continues in a new part (MB, 0 = no cap)
+    #[serde(default = "default_log_max_file_mb")]
+    pub max_file_mb: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelegramConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    pub api_token: String,
+}
+
+/// Providers injecting a small environment block into each prompt
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContextConfig {
+    #[serde(default)]
+    pub weather: Option<WeatherConfig>,
+
+    /// Local date, time and timezone
+    #[serde(default)]
+    pub clock: Option<ClockConfig>,
+
+    /// Charge and power state of the host's battery
+    #[serde(default)]
+    pub battery: Option<BatteryConfig>,
+
+    /// Current and next event from `[calendar]`
+    #[serde(default)]
+    pub calendar: Option<CalendarContextConfig>,
+
+    /// Pending Discord reminders and running background jobs
+    #[serde(default)]
+    pub timers: Option<TimersConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClockConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatteryConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarContextConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// How far ahead to look for the next event
+    #[serde(default = "default_calendar_lookahead_hours")]
+    pub lookahead_hours: u64,
+
+    /// How long fetched events are reused before fetching again
+    #[serde(default = "default_calendar_cache_minutes")]
+    pub cache_minutes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimersConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Most reminders and jobs to list
+    #[serde(default = "default_timers_limit")]
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeatherConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Place name, looked up with the Open-Meteo geocoding API
+    /// (ignored when latitude and longitude are set)
+    #[serde(default)]
+    pub location: Option<String>,
+
+    #[serde(default)]
+    pub latitude: Option<f64>,
+
+    #[serde(default)]
+    pub longitude: Option<f64>,
+
+    /// "metric" or "imperial"
+    #[serde(default = "default_weather_units")]
+    pub units: String,
+
+    /// How long a forecast is reused before fetching again
+    #[serde(default = "default_weather_cache_minutes")]
+    pub cache_minutes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Read-only iCalendar feeds (https://, webcal:// or a local .ics path)
+    #[serde(default)]
+    pub ics_urls: Vec<String>,
+
+    /// CalDAV calendar collection; required for creating events
+    #[serde(default)]
+    pub caldav: Option<CalDavConfig>,
+
+    /// Remind this many minutes before an event starts (0 = no reminders).
+    /// Reminders are checked on each heartbeat tick.
+    #[serde(default = "default_reminder_minutes")]
+    pub reminder_minutes: u64,
+
+    /// Discord channel ID to post reminders to (optional)
+    #[serde(default)]
+    pub notify_discord_channel: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalDavConfig {
+    /// URL of the calendar collection (not the account root)
+    pub url: String,
+
+    #[serde(default)]
+    pub username: String,
+
+    #[serde(default)]
+    pub password: String,
+}
+
+/// Long-running background jobs, executed by the daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobsConfig {
+    /// Offer the job tools to the agent (default: true)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Jobs run at the same time; the rest wait in the queue
+    #[serde(default = "default_jobs_max_concurrent")]
+    pub max_concurrent: usize,
+
+    /// A job still running after this long is stopped and marked failed
+    #[serde(default = "default_jobs_timeout")]
+    pub timeout: String,
+}
+
+/// System resource monitor; background work waits while the machine is busy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorConfig {
+    /// Sample resource usage in the daemon (default: true)
+    pub enabled: bool,
+
+    /// Time between samples
+    pub interval: String,
+
+    /// Jobs and heartbeat runs wait while CPU usage is at or above this
+    /// (0 = never)
+    pub busy_cpu_percent: u8,
+
+    /// Same for RAM usage
+    pub busy_memory_percent: u8,
+
+    /// Same for the utilization of any GPU
+    pub busy_gpu_percent: u8,
+}
+
+/// Connectivity checks of model providers and the Discord API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthConfig {
+    /// Check dependencies in the daemon (default: true)
+    pub enabled: bool,
+
+    /// Time between checks
+    pub interval: String,
+
+    /// Longest wait for a response before a dependency counts as down
+    pub timeout_secs: u64,
+
+    /// Responses slower than this count as degraded (0 = never)
+    pub slow_ms: u64,
+}
+
+/// Error digests: failures are collected and reported together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationsConfig {
+    /// How often collected errors are sent as one digest
+    #[serde(default = "default_digest_interval")]
+    pub digest_interval: String,
+
+    /// Discord channel digests are posted to (desktop and log only if unset)
+    #[serde(default)]
+    pub admin_channel: Option<String>,
+
+    /// Least severe errors that are included in digests
+    #[serde(default = "default_digest_min_severity")]
+    pub min_severity: Severity,
+}
+
+/// How urgently an error needs attention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// Expected to clear up by itself (rate limits, overload)
+    Warning,
+    Error,
+    /// Needs someone to act (bad credentials, missing configuration)
+    Critical,
+}
+
+/// Post-processing of replies, per channel type
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RepliesConfig {
+    pub discord: ReplyPipelineConfig,
+    pub telegram: ReplyPipelineConfig,
+    pub http: ReplyPipelineConfig,
+    pub desktop: ReplyPipelineConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplyPipelineConfig {
+    /// Stages to run, in order: strip_tags, guardrails, localize, split
+    pub stages: Vec<String>,
+
+    /// Longest part the split stage leaves (default: the channel's message
+    /// limit, none for HTTP and desktop)
+    pub max_chars: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChannelsConfig {
+    #[serde(default)]
+    pub discord: Option<DiscordChannelConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscordChannelConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Bot token (use ${DISCORD_BOT_TOKEN} for env var expansion)
+    pub token: String,
+
+    /// Whether to process messages from other bots
+    #[serde(default)]
+    pub allow_bots: bool,
+
+    /// Guild (server) allow-list with per-guild settings
+    #[serde(default)]
+    pub guilds: Vec<DiscordGuildConfig>,
+
+    /// Tell the agent when a user edits a message it already answered
+    /// (the old text is shown struck through)
+    #[serde(default)]
+    pub track_edits: bool,
+
+    /// Timeout for Discord REST API requests (seconds)
+    #[serde(default = "default_discord_request_timeout")]
+    pub request_timeout_secs: u64,
+
+    /// Retries for REST requests that fail with 5xx or rate limits
+    #[serde(default = "default_discord_max_retries")]
+    pub max_retries: u32,
+
+    /// Discord API version for the gateway and REST calls (9 or 10)
+    #[serde(default = "default_discord_api_version")]
+    pub api_version: u8,
+
+    /// What the agent may do on behalf of each Discord user
+    #[serde(default)]
+    pub permissions: DiscordPermissionsConfig,
+
+    /// Reply length and style constraints by channel ID
+    #[serde(default)]
+    pub styles: HashMap<String, DiscordChannelStyle>,
+
+    /// Label messages with a small model before the agent sees them
+    #[serde(default)]
+    pub classifier: DiscordClassifierConfig,
+
+    /// Greeting and farewell posted when the bot starts and stops
+    #[serde(default)]
+    pub announcements: DiscordAnnouncementsConfig,
+
+    /// Per-user quota of messages to the bot
+    #[serde(default)]
+    pub rate_limit: DiscordRateLimitConfig,
+
+    /// Recent channel messages given to the agent as context, by channel ID
+    #[serde(default)]
+    pub history: HashMap<String, DiscordHistoryConfig>,
+
+    /// Context always given to the agent, by channel ID (`/pin` adds more)
+    #[serde(default)]
+    pub pins: HashMap<String, DiscordPinnedContext>,
+
+    /// Archiving of idle channel agents
+    #[serde(default)]
+    pub sessions: DiscordSessionsConfig,
+
+    /// Messages still queued when the daemon stops are answered after the
+    /// restart if they are younger than this ("0" = drop them)
+    #[serde(default = "default_discord_replay_max_age")]
+    pub replay_max_age: String,
+
+    /// Reminder DMs scheduled by the agent with `[REMIND]`
+    #[serde(default)]
+    pub reminders: DiscordRemindersConfig,
+
+    /// Announce conversations (prompts, tool calls, replies) for the
+    /// desktop app to show live
+    #[serde(default)]
+    pub mirror: bool,
+}
+
+fn default_discord_request_timeout() -> u64 {
+    15
+}
+
+fn default_discord_replay_max_age() -> String {
+    "15m".to_string()
+}
+
+fn default_discord_max_retries() -> u32 {
+    2
+}
+
+fn default_discord_api_version() -> u8 {
+    crate::discord::rest::DEFAULT_API_VERSION
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscordGuildConfig {
+    pub guild_id: String,
+
+    /// Channel IDs the bot listens in (empty = all channels)
+    #[serde(default)]
+    pub channels: Vec<String>,
+
+    /// Whether the bot must be @mentioned to respond (can be changed at
+    /// runtime with `/settings`, like `ambient`, `language` and `persona`)
+    #[serde(default)]
+    pub require_mention: bool,
+
+    /// Read every message, even where a mention is required, and join in
+    /// only when there is something to add
+    #[serde(default)]
+    pub ambient: bool,
+
+    /// Language for this guild's conversations (default: agent.language)
+    #[serde(default)]
+    pub language: Option<String>,
+
+    /// Persona instructions added to this guild's conversations
+    #[serde(default)]
+    pub persona: Option<String>,
+
+    /// Text command prefix, e.g. "!lg" for `!lg reset` (None = only the
+    /// `/` and `!` command forms)
+    #[serde(default)]
+    pub command_prefix: Option<String>,
+
+    /// Send emoji-only replies as reactions instead of messages
+    #[serde(default = "default_true")]
+    pub emoji_reactions: bool,
+
+    /// Spam/toxicity moderation for this guild
+    #[serde(default)]
+    pub moderation: DiscordModerationConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscordModerationConfig {
+    /// Score every message in the guild's channels (not just ones for the bot)
+    pub enabled: bool,
+
+    /// "rules" (keyword/flood heuristics) or "llm" (ask the model)
+    pub scorer: String,
+
+    /// Model for the "llm" scorer (default: agent.default_model)
+    pub model: Option<String>,
+
+    /// Score (0.0-1.0) at which a message is flagged for review
+    pub flag_threshold: f32,
+
+    /// Score at which the message is deleted
+    pub delete_threshold: f32,
+
+    /// Score at which the author is also timed out
+    pub timeout_threshold: f32,
+
+    /// Timeout length (minutes)
+    pub timeout_minutes: u64,
+
+    /// Role IDs that are never moderated
+    pub protected_roles: Vec<String>,
+
+    /// Words or phrases that always score 1.0 (case-insensitive)
+    pub blocked_words: Vec<String>,
+
+    /// Channel to post moderation reports to
+    pub log_channel: Option<String>,
+}
+
+impl Default for DiscordModerationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            scorer: "rules".to_string(),
+            model: None,
+            flag_threshold: 0.5,
+            delete_threshold: 0.8,
+            timeout_threshold: 0.95,
+            timeout_minutes: 10,
+            protected_roles: Vec::new(),
+            blocked_words: Vec::new(),
+            log_channel: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscordAnnouncementsConfig {
+    /// Channel IDs to post in
+    pub channels: Vec<String>,
+
+    /// Posted once the bot is online (None = no greeting)
+    pub greeting: Option<String>,
+
+    /// Posted on graceful shutdown (None = no farewell)
+    pub farewell: Option<String>,
+
+    /// Treat greeting and farewell as instructions for the default model,
+    /// which writes the actual message
+    pub generate: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscordSessionsConfig {
+    /// Archive a channel's agent after this long without messages, e.g.
+    /// "6h" ("0" = never)
+    pub idle_ttl: String,
+
+    /// Most channel agents kept in memory; the least recently used are
+    /// archived first (0 = no limit)
+    pub max_agents: usize,
+}
+
+impl Default for DiscordSessionsConfig {
+    fn default() -> Self {
+        Self {
+            idle_ttl: "24h".to_string(),
+            max_agents: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscordRemindersConfig {
+    /// Let the agent schedule reminders
+    pub enabled: bool,
 
-    /// Embedding provider: "local" (fastembed, default), "openai", or "none"
-    #[serde(default = "default_embedding_provider")]
-    pub embedding_provider: String,
+    /// Users who accept reminders set by someone else (anyone can set
+    /// reminders for themselves)
+    pub opt_in: Vec<String>,
 
-    #[serde(default = "default_embedding_model")]
-    pub embedding_model: String,
+    /// Most pending reminders per user (0 = no limit)
+    pub max_pending: usize,
+}
 
-    /// Cache directory for local embedding models (optional)
-    /// Default: ~/.cache/localgpt/models
-    /// Can also be set via FASTEMBED_CACHE_DIR environment variable
-    #[serde(default = "default_embedding_cache_dir")]
-    pub embedding_cache_dir: String,
+impl Default for DiscordRemindersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            opt_in: Vec::new(),
+            max_pending: 10,
+        }
+    }
+}
 
-    #[serde(default = "default_chunk_size")]
-    pub chunk_size: usize,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscordRateLimitConfig {
+    /// Messages a user may send the bot per window (0 = no limit)
+    pub max_messages: u32,
 
-    #[serde(default = "default_chunk_overlap")]
-    pub chunk_overlap: usize,
+    /// Rolling window, e.g. "10m" or "1h"
+    pub window: String,
 
-    /// Additional paths to index (relative to workspace or absolute)
-    /// Each path uses a glob pattern for file matching
-    #[serde(default = "default_index_paths")]
-    pub paths: Vec<MemoryIndexPath>,
+    /// Role IDs that are never limited (e.g. admins)
+    pub exempt_roles: Vec<String>,
 
-    /// Maximum messages to save in session memory files (0 = unlimited)
-    /// Similar to OpenClaw's hooks.session-memory.messages (default: 15)
-    #[serde(default = "default_session_max_messages")]
-    pub session_max_messages: usize,
+    /// User IDs that are never limited
+    pub exempt_users: Vec<String>,
 
-    /// Maximum characters per message in session memory (0 = unlimited)
-    /// Set to 0 to preserve full message content like OpenClaw
-    #[serde(default)]
-    pub session_max_chars: usize,
+    /// Reply to the first message over the quota; `{retry}` is replaced by
+    /// the time until the user may send again
+    pub notice: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MemoryIndexPath {
-    pub path: String,
-    #[serde(default = "default_pattern")]
-    pub pattern: String,
+impl Default for DiscordRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_messages: 0,
+            window: "10m".to_string(),
+            exempt_roles: Vec::new(),
+            exempt_users: Vec::new(),
+            notice: "You're sending messages faster than I can answer them. \
+                     I'll pick up your next one in {retry}."
+                .to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ServerConfig {
-    #[serde(default = "default_true")]
+#[serde(default)]
+pub struct DiscordClassifierConfig {
     pub enabled: bool,
 
-    #[serde(default = "default_port")]
-    pub port: u16,
+    /// Model for the labels, ideally a small local one
+    /// (default: agent.default_model)
+    pub model: Option<String>,
 
-    #[serde(default = "default_bind")]
-    pub bind: String,
+    /// Labels whose messages get no reply: "question", "command",
+    /// "chit_chat", "memory_write" or "ignore"
+    pub skip: Vec<String>,
+
+    /// Save "memory_write" messages to MEMORY.md without asking the agent
+    pub direct_memory_writes: bool,
+}
+
+impl Default for DiscordClassifierConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model: None,
+            skip: vec!["ignore".to_string()],
+            direct_memory_writes: true,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LoggingConfig {
-    #[serde(default = "default_log_level")]
-    pub level: String,
+#[serde(default)]
+pub struct DiscordChannelStyle {
+    /// Maximum reply length in characters (0 = no limit beyond Discord's)
+    pub max_chars: usize,
 
-    #[serde(default = "default_log_file")]
-    pub file: String,
+    /// "brief", "normal" or "detailed"
+    pub verbosity: Option<String>,
 
-    /// Days to keep log files (0 = keep forever, no auto-deletion)
-    #[serde(default)]
-    pub retention_days: u32,
+    /// Turn code blocks into plain text (e.g. for voice-linked channels)
+    pub no_code_blocks: bool,
+
+    /// Extra formatting rules given to the agent
+    pub rules: Vec<String>,
+
+    /// What to do with over-long replies: "truncate" or "summarize"
+    pub overflow: String,
+}
+
+impl Default for DiscordChannelStyle {
+    fn default() -> Self {
+        Self {
+            max_chars: 0,
+            verbosity: None,
+            no_code_blocks: false,
+            rules: Vec::new(),
+            overflow: "truncate".to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TelegramConfig {
-    #[serde(default)]
-    pub enabled: bool,
+#[serde(default)]
+pub struct DiscordHistoryConfig {
+    /// Messages to include (at most 50, 0 = off)
+    pub messages: u32,
 
-    pub api_token: String,
+    /// Cap on the included text; the oldest messages are dropped first
+    pub max_chars: usize,
+}
+
+impl Default for DiscordHistoryConfig {
+    fn default() -> Self {
+        Self {
+            messages: 20,
+            max_chars: 4000,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
-pub struct ChannelsConfig {
-    #[serde(default)]
-    pub discord: Option<DiscordChannelConfig>,
+#[serde(default)]
+pub struct DiscordPinnedContext {
+    /// Markdown snippets
+    pub snippets: Vec<String>,
+
+    /// Files included whole (relative paths are in the workspace)
+    pub files: Vec<String>,
+}
+
+/// Actions the agent can take on behalf of a Discord user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscordCapability {
+    /// Command tags, the bash tool, background jobs and delegation
+    Commands,
+    /// `[POST:channel]` and `[PUBLISH:channel]` cross-posting
+    CrossPost,
+    /// write_file / edit_file / save_memory / memory_log (MEMORY.md, daily
+    /// and topic logs, workspace files)
+    MemoryWrite,
+    /// `/inspect`: the full prompt behind a reply, memories included
+    Inspect,
+    /// `/status` and other bot administration commands
+    Admin,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DiscordChannelConfig {
-    #[serde(default)]
-    pub enabled: bool,
-
-    /// Bot token (use ${DISCORD_BOT_TOKEN} for env var expansion)
-    pub token: String,
+#[serde(default)]
+pub struct DiscordPermissionsConfig {
+    /// Capabilities every user has
+    pub default: Vec<DiscordCapability>,
 
-    /// Whether to process messages from other bots
-    #[serde(default)]
-    pub allow_bots: bool,
+    /// Extra capabilities for specific roles or users
+    pub grants: Vec<DiscordPermissionGrant>,
+}
 
-    /// Guild (server) allow-list with per-guild settings
-    #[serde(default)]
-    pub guilds: Vec<DiscordGuildConfig>,
+impl Default for DiscordPermissionsConfig {
+    fn default() -> Self {
+        Self {
+            default: vec![DiscordCapability::CrossPost, DiscordCapability::MemoryWrite],
+            grants: Vec::new(),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DiscordGuildConfig {
-    pub guild_id: String,
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscordPermissionGrant {
+    /// Role IDs the grant applies to
+    pub roles: Vec<String>,
 
-    /// Channel IDs the bot listens in (empty = all channels)
-    #[serde(default)]
-    pub channels: Vec<String>,
+    /// User IDs the grant applies to
+    pub users: Vec<String>,
 
-    /// Whether the bot must be @mentioned to respond
-    #[serde(default)]
-    pub require_mention: bool,
+    pub capabilities: Vec<DiscordCapability>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -409,6 +1444,21 @@ fn default_reserve_tokens() -> usize {
 fn default_max_tokens() -> usize {
     4096
 }
+fn default_language() -> String {
+    "en".to_string()
+}
+fn default_summary_batch_chars() -> usize {
+    8000
+}
+fn default_tool_loop_max_iterations() -> usize {
+    10
+}
+fn default_tool_loop_max_repeated_calls() -> usize {
+    2
+}
+fn default_tool_loop_iteration_token_budget() -> usize {
+    16000
+}
 fn default_bash_timeout() -> u64 {
     30000 // 30 seconds
 }
@@ -439,12 +1489,74 @@ fn default_claude_cli_model() -> String {
 fn default_glm_base_url() -> String {
     "https://api.z.ai/api/coding/paas/v4".to_string()
 }
+fn default_weather_units() -> String {
+    "metric".to_string()
+}
+
+fn default_weather_cache_minutes() -> u64 {
+    30
+}
+
+fn default_calendar_lookahead_hours() -> u64 {
+    12
+}
+
+fn default_calendar_cache_minutes() -> u64 {
+    5
+}
+
+fn default_timers_limit() -> usize {
+    5
+}
+
+fn default_screenshot_width() -> u32 {
+    1280
+}
+
+fn default_screenshot_height() -> u32 {
+    800
+}
+
+fn default_screenshot_cache_minutes() -> u64 {
+    60
+}
+
+fn default_screenshot_timeout_secs() -> u64 {
+    30
+}
+
+fn default_reminder_minutes() -> u64 {
+    15
+}
+
+fn default_jobs_max_concurrent() -> usize {
+    1
+}
+
+fn default_jobs_timeout() -> String {
+    "2h".to_string()
+}
+
+fn default_digest_interval() -> String {
+    "15m".to_string()
+}
+
+fn default_digest_min_severity() -> Severity {
+    Severity::Warning
+}
+
 fn default_true() -> bool {
     true
 }
 fn default_interval() -> String {
     "30m".to_string()
 }
+fn default_reflection_time() -> String {
+    "21:00".to_string()
+}
+fn default_maintenance_time() -> String {
+    "03:30".to_string()
+}
 fn default_workspace() -> String {
     "~/.localgpt/workspace".to_string()
 }
@@ -475,6 +1587,24 @@ fn default_pattern() -> String {
 fn default_session_max_messages() -> usize {
     15 // Match OpenClaw's default
 }
+fn default_recall_top_k() -> usize {
+    3
+}
+fn default_recall_min_score() -> f64 {
+    0.1
+}
+fn default_recall_max_tokens() -> usize {
+    800
+}
+fn default_backfill_batch_size() -> usize {
+    32
+}
+fn default_backfill_batches_per_minute() -> u32 {
+    30
+}
+fn default_daily_format() -> String {
+    "%Y-%m-%d".to_string()
+}
 fn default_port() -> u16 {
     31327
 }
@@ -487,6 +1617,9 @@ fn default_log_level() -> String {
 fn default_log_file() -> String {
     "~/.localgpt/logs/agent.log".to_string()
 }
+fn default_log_max_file_mb() -> u64 {
+    50
+}
 fn default_sandbox_level() -> String {
     "auto".to_string()
 }
@@ -512,7 +1645,38 @@ impl Default for AgentConfig {
             default_model: default_model(),
             context_window: default_context_window(),
             reserve_tokens: default_reserve_tokens(),
+            adaptive_reserve: AdaptiveReserveConfig::default(),
             max_tokens: default_max_tokens(),
+            allowed_models: Vec::new(),
+            tool_loop: ToolLoopConfig::default(),
+            language: default_language(),
+            detect_language: false,
+            experiment: None,
+            feedback_examples: 0,
+            summary_batch_chars: default_summary_batch_chars(),
+        }
+    }
+}
+
+impl AgentConfig {
+    /// Models a session may switch to: the default model, then the allow-list
+    pub fn switchable_models(&self) -> Vec<String> {
+        let mut models = vec![self.default_model.clone()];
+        for model in &self.allowed_models {
+            if !models.contains(model) {
+                models.push(model.clone());
+            }
+        }
+        models
+    }
+}
+
+impl Default for ToolLoopConfig {
+    fn default() -> Self {
+        Self {
+            max_iterations: default_tool_loop_max_iterations(),
+            max_repeated_calls: default_tool_loop_max_repeated_calls(),
+            iteration_token_budget: default_tool_loop_iteration_token_budget(),
         }
     }
 }
@@ -526,6 +1690,7 @@ impl Default for ToolsConfig {
             tool_output_max_chars: default_tool_output_max_chars(),
             log_injection_warnings: default_true(),
             use_content_delimiters: default_true(),
+            screenshot: None,
         }
     }
 }
@@ -537,6 +1702,82 @@ impl Default for HeartbeatConfig {
             interval: default_interval(),
             active_hours: None,
             timezone: None,
+            reflection: ReflectionConfig::default(),
+            maintenance: MaintenanceConfig::default(),
+        }
+    }
+}
+
+impl Default for ReflectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            time: default_reflection_time(),
+            notify_discord_channel: None,
+        }
+    }
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            time: default_maintenance_time(),
+        }
+    }
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_concurrent: default_jobs_max_concurrent(),
+            timeout: default_jobs_timeout(),
+        }
+    }
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: "30s".to_string(),
+            busy_cpu_percent: 90,
+            busy_memory_percent: 95,
+            busy_gpu_percent: 90,
+        }
+    }
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: "5m".to_string(),
+            timeout_secs: 10,
+            slow_ms: 3000,
+        }
+    }
+}
+
+impl Default for ReplyPipelineConfig {
+    fn default() -> Self {
+        Self {
+            stages: ["strip_tags", "guardrails", "localize", "split"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            max_chars: None,
+        }
+    }
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            digest_interval: default_digest_interval(),
+            admin_channel: None,
+            min_severity: default_digest_min_severity(),
         }
     }
 }
@@ -553,6 +1794,51 @@ impl Default for MemoryConfig {
             paths: default_index_paths(),
             session_max_messages: default_session_max_messages(),
             session_max_chars: 0, // 0 = unlimited (preserve full content like OpenClaw)
+            recall: RecallConfig::default(),
+            backfill: EmbeddingBackfillConfig::default(),
+            obsidian: ObsidianConfig::default(),
+        }
+    }
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            builtin: true,
+            patterns: Vec::new(),
+            test_mode: false,
+        }
+    }
+}
+
+impl Default for ObsidianConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            daily_folder: String::new(),
+            daily_format: default_daily_format(),
+        }
+    }
+}
+
+impl Default for EmbeddingBackfillConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            batch_size: default_backfill_batch_size(),
+            batches_per_minute: default_backfill_batches_per_minute(),
+        }
+    }
+}
+
+impl Default for RecallConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            top_k: default_recall_top_k(),
+            min_score: default_recall_min_score(),
+            max_tokens: default_recall_max_tokens(),
         }
     }
 }
@@ -563,6 +1849,11 @@ impl Default for ServerConfig {
             enabled: default_true(),
             port: default_port(),
             bind: default_bind(),
+            base_path: String::new(),
+            tls: None,
+            trusted_proxies: Vec::new(),
+            dashboard: default_true(),
+            admin_token: None,
         }
     }
 }
@@ -573,6 +1864,8 @@ impl Default for LoggingConfig {
             level: default_log_level(),
             file: default_log_file(),
             retention_days: 0, // 0 = keep forever
+            json: false,
+            max_file_mb: default_log_max_file_mb(),
         }
     }
 }
@@ -600,6 +1893,9 @@ impl Config {
         // Expand environment variables in API keys
         config.expand_env_vars();
 
+        // Keep the configured secrets out of logs and memory from here on
+        crate::security::install_redactor(crate::security::Redactor::from_config(&config));
+
         Ok(config)
     }
 
@@ -649,9 +1945,15 @@ impl Config {
 if let Some(ref mut telegram) = self.telegram {
             telegram.api_token = expand_env(&telegram.api_token);
         }
+        if let Some(caldav) = self.calendar.as_mut().and_then(|c| c.caldav.as_mut()) {
+            caldav.password = expand_env(&caldav.password);
+        }
         if let Some(ref mut discord) = self.channels.discord {
             discord.token = expand_env(&discord.token);
         }
+        if let Some(ref mut token) = self.server.admin_token {
+            *token = expand_env(token);
+        }
     }
 
     pub fn get_value(&self, key: &str) -> Result<String> {
@@ -666,6 +1968,7 @@ if let Some(ref mut telegram) = self.telegram {
             ["server", "enabled"] => Ok(self.server.enabled.to_string()),
             ["server", "port"] => Ok(self.server.port.to_string()),
             ["server", "bind"] => Ok(self.server.bind.clone()),
+            ["server", "base_path"] => Ok(self.server.base_path.clone()),
             ["memory", "workspace"] => Ok(self.memory.workspace.clone()),
             ["logging", "level"] => Ok(self.logging.level.clone()),
             _ => anyhow::bail!("Unknown config key: {}", key),
@@ -684,6 +1987,7 @@ if let Some(ref mut telegram) = self.telegram {
             ["server", "enabled"] => self.server.enabled = value.parse()?,
             ["server", "port"] => self.server.port = value.parse()?,
             ["server", "bind"] => self.server.bind = value.to_string(),
+            ["server", "base_path"] => self.server.base_path = value.to_string(),
             ["memory", "workspace"] => self.memory.workspace = value.to_string(),
             ["logging", "level"] => self.logging.level = value.to_string(),
             _ => anyhow::bail!("Unknown config key: {}", key),
@@ -747,6 +2051,33 @@ const DEFAULT_CONFIG_TEMPLATE: &str = r#"# LocalGPT Configuration
 default_model = "claude-cli/opus"
 context_window = 128000
 reserve_tokens = 8000
+# allowed_models = ["claude-cli/sonnet", "openai/gpt-4o"]   # switchable with /model
+# language = "ja"                      # built-in prompts; SOUL.ja.md is used if present
+# detect_language = true               # follow the language of each conversation
+# feedback_examples = 3                 # recent 👎 replies shown as examples to avoid
+# summary_batch_chars = 8000            # small background summaries share a request; 0 = off
+
+# Size reserve_tokens from measured reply lengths per channel (GET /api/reserve)
+# [agent.adaptive_reserve]
+# enabled = true
+# percentile = 95
+# margin = 1.25                         # headroom on top of the percentile
+# min_tokens = 1024
+# min_samples = 20                      # replies measured before adjusting
+
+# A/B experiment: answer a share of Discord turns with an alternate setup
+# (compare with `localgpt experiment`)
+# [agent.experiment]
+# name = "sonnet-vs-opus"
+# percent = 20                          # share of turns for the alternate setup
+# model = "claude-cli/sonnet"
+# instructions = "Keep answers under five sentences."
+
+# Tool-calling loop limits per turn
+# [agent.tool_loop]
+# max_iterations = 10                   # tool rounds before the model must answer
+# max_repeated_calls = 2                # identical calls allowed before loop detection
+# iteration_token_budget = 16000        # tool output tokens per round (0 = unlimited)
 
 # Anthropic API (for anthropic/* models)
 # [providers.anthropic]
@@ -760,6 +2091,14 @@ reserve_tokens = 8000
 [providers.claude_cli]
 command = "claude"
 
+# Retries and circuit breaker for model calls
+# [providers.resilience]
+# max_retries = 2                       # extra attempts on timeouts, 5xx and overload
+# initial_backoff_ms = 500              # doubled per retry, up to max_backoff_ms
+# failure_threshold = 5                 # failed requests in a row that open the circuit
+# cooldown = "60s"                      # how long an open circuit short-circuits calls
+# fallback_model = "ollama/llama3"      # answer with this model while open
+
 [heartbeat]
 enabled = true
 interval = "30m"
@@ -769,6 +2108,19 @@ interval = "30m"
 # start = "09:00"
 # end = "22:00"
 
+# End-of-day reflection: review today's conversations, add follow-ups to
+# HEARTBEAT.md and optionally post a short summary (optional)
+# [heartbeat.reflection]
+# enabled = true
+# time = "21:00"
+# notify_discord_channel = "123456789012345678"
+
+# Nightly index maintenance: reindex changed files, prune orphaned rows,
+# backfill embeddings, VACUUM/ANALYZE the databases (runs outside active_hours)
+# [heartbeat.maintenance]
+# enabled = true
+# time = "03:30"
+
 [memory]
 # Workspace directory for memory files (MEMORY.md, HEARTBEAT.md, etc.)
 # Can also be set via environment variables:
@@ -780,13 +2132,52 @@ workspace = "~/.localgpt/workspace"
 # session_max_messages = 15    # Max messages to save (0 = unlimited)
 # session_max_chars = 0        # Max chars per message (0 = unlimited, preserves full content)
 
+# Per-message recall: search memory with each message and inject top hits
+# [memory.recall]
+# enabled = true
+# top_k = 3
+# min_score = 0.1
+# max_tokens = 800
+
+# Background embedding of chunks without embeddings (daemon only)
+# [memory.backfill]
+# enabled = true
+# batch_size = 32
+# batches_per_minute = 30
+
+# Obsidian vault layout: point `workspace` at a vault; notes the assistant
+# writes are linked from the day's daily note with [[wikilinks]]
+# [memory.obsidian]
+# enabled = true
+# daily_folder = "Daily"               # "" = vault root
+# daily_format = "%Y-%m-%d"            # chrono format for daily note names
+
 [server]
 enabled = true
 port = 31327
 bind = "127.0.0.1"
+# base_path = "/localgpt"               # when served under a sub-path by a reverse proxy
+# trusted_proxies = ["127.0.0.1"]       # honor X-Forwarded-For from these (IPs or CIDRs)
+# dashboard = true                      # read-only status page at /dashboard
+# admin_token = "${LOCALGPT_ADMIN_TOKEN}"  # bearer token for admin endpoints (feature toggles)
+
+# Serve HTTPS directly
+# [server.tls]
+# cert_path = "~/.localgpt/tls/cert.pem"
+# key_path = "~/.localgpt/tls/key.pem"
 
 [logging]
 level = "info"
+# json = true                          # structured logs in ~/.localgpt/logs/json/
+# max_file_mb = 50                      # roll a JSON log over at this size
+# retention_days = 14                   # 0 = keep forever
+
+# Mask secrets in logs, memory files and the audit log
+# [security.redaction]
+# enabled = true                        # default: true
+# builtin = true                        # API keys, bot/bearer tokens, private keys
+# patterns = ["INT-[0-9]{6}"]           # additional regexes
+# test_mode = false                     # report matches to logs/redactions.jsonl instead
 
 # Shell sandbox (kernel-enforced isolation for LLM-generated commands)
 # [sandbox]
@@ -806,4 +2197,72 @@ level = "info"
 # [telegram]
 # enabled = true
 # api_token = "${TELEGRAM_BOT_TOKEN}"
+
+# Current weather and sunrise/sunset in every prompt, plus a `weather` tool
+# (Open-Meteo, no API key needed)
+# [context.weather]
+# location = "Tokyo"                    # or set latitude/longitude
+# latitude = 35.68
+# longitude = 139.69
+# units = "metric"                      # metric | imperial
+# cache_minutes = 30
+
+# More environment blocks, refreshed for every prompt
+# [context.clock]                       # local time and timezone
+# [context.battery]                     # charge, charging or not, time left
+# [context.calendar]                    # current and next event from [calendar]
+# lookahead_hours = 12
+# cache_minutes = 5
+# [context.timers]                      # pending reminders and running jobs
+# limit = 5
+
+# Screenshot tool: render a page in headless Chromium and have a vision model
+# look at it (needs the `screenshot` build feature)
+# [tools.screenshot]
+# allowed_domains = ["example.com"]     # subdomains included, "*" = any
+# vision_model = "anthropic/claude-sonnet-4-5"   # default: agent.default_model
+# cache_minutes = 60
+
+# Calendar: list_events/create_event tools and reminders before events (optional)
+# [calendar]
+# ics_urls = ["https://calendar.example.com/me.ics"]   # read-only feeds
+# reminder_minutes = 15                 # 0 = no reminders
+# notify_discord_channel = "123456789012345678"
+#
+# [calendar.caldav]                     # read/write; needed for create_event
+# url = "https://caldav.example.com/calendars/me/personal/"
+# username = "me"
+# password = "${CALDAV_PASSWORD}"
+
+# Background jobs started with the job_start tool (run by the daemon)
+# [jobs]
+# max_concurrent = 1
+# timeout = "2h"
+
+# Resource monitor: background jobs and heartbeats wait while the machine is busy
+# [monitor]
+# interval = "30s"
+# busy_cpu_percent = 90                 # 0 = never wait on this
+# busy_memory_percent = 95
+# busy_gpu_percent = 90
+
+# Model providers and the Discord API are checked at startup and periodically;
+# changes are posted to notifications.admin_channel
+# [health]
+# interval = "5m"
+# timeout_secs = 10
+# slow_ms = 3000                        # slower responses count as degraded
+
+# Errors are collected and reported as a periodic digest (run by the daemon)
+# [notifications]
+# digest_interval = "15m"
+# admin_channel = "123456789012345678"  # Discord channel for digests
+# min_severity = "warning"              # warning, error or critical
+
+# Replies go through strip_tags, guardrails, localize and split before
+# they are sent; each channel type (discord, telegram, http, desktop) can
+# pick its stages and where split breaks long replies
+# [replies.discord]
+# stages = ["strip_tags", "guardrails", "localize", "split"]
+# max_chars = 2000                      # default: the channel's message limit
 "#;
@@ -0,0 +1,382 @@
+//! First-run setup shared by `localgpt init` and the desktop app
+//!
+//! Both ask for the same things: the model provider and its endpoint, an
+//! optional Discord bot token, and the memory directory. Each answer can
+//! be checked before it is saved (a request to the provider, a token
+//! lookup with Discord, creating the directory), and [`render_config`]
+//! fills the answers into the commented default config. There are no voice
+//! options to ask about, since this tree has no speech support.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::{DEFAULT_CONFIG_TEMPLATE, expand_env};
+use crate::discord::rest::DISCORD_API_ROOT;
+
+/// Timeout of each check
+const CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupProvider {
+    /// The `claude` CLI, signed in separately
+    ClaudeCli,
+    Anthropic,
+    /// OpenAI or any OpenAI-compatible server
+    OpenAI,
+    Ollama,
+}
+
+impl SetupProvider {
+    pub const ALL: [SetupProvider; 4] = [
+        SetupProvider::ClaudeCli,
+        SetupProvider::Anthropic,
+        SetupProvider::OpenAI,
+        SetupProvider::Ollama,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SetupProvider::ClaudeCli => "Claude CLI",
+            SetupProvider::Anthropic => "Anthropic API",
+            SetupProvider::OpenAI => "OpenAI-compatible API",
+            SetupProvider::Ollama => "Ollama",
+        }
+    }
+
+    /// Model prefix in `agent.default_model`
+    fn prefix(self) -> &'static str {
+        match self {
+            SetupProvider::ClaudeCli => "claude-cli",
+            SetupProvider::Anthropic => "anthropic",
+            SetupProvider::OpenAI => "openai",
+            SetupProvider::Ollama => "ollama",
+        }
+    }
+
+    pub fn default_endpoint(self) -> &'static str {
+        match self {
+            SetupProvider::ClaudeCli => "claude",
+            SetupProvider::Anthropic => "https://api.anthropic.com",
+            SetupProvider::OpenAI => "https://api.openai.com/v1",
+            SetupProvider::Ollama => "http://localhost:11434",
+        }
+    }
+
+    pub fn default_model(self) -> &'static str {
+        match self {
+            SetupProvider::ClaudeCli => "opus",
+            SetupProvider::Anthropic => "claude-sonnet-4-5",
+            SetupProvider::OpenAI => "gpt-4o",
+            SetupProvider::Ollama => "llama3",
+        }
+    }
+
+    /// Whether the provider takes an API key
+    pub fn needs_api_key(self) -> bool {
+        matches!(self, SetupProvider::Anthropic | SetupProvider::OpenAI)
+    }
+}
+
+/// Everything the setup asks for
+#[derive(Debug, Clone)]
+pub struct SetupAnswers {
+    pub provider: SetupProvider,
+    /// Base URL, or the command for the Claude CLI
+    pub endpoint: String,
+    /// API key, or `${VAR}` to read it from the environment
+    pub api_key: String,
+    pub model: String,
+    /// Discord bot token (empty = no Discord)
+    pub discord_token: String,
+    pub workspace: String,
+}
+
+impl Default for SetupAnswers {
+    fn default() -> Self {
+        let provider = SetupProvider::ClaudeCli;
+        Self {
+            provider,
+            endpoint: provider.default_endpoint().to_string(),
+            api_key: String::new(),
+            model: provider.default_model().to_string(),
+            discord_token: String::new(),
+            workspace: "~/.localgpt/workspace".to_string(),
+        }
+    }
+}
+
+fn client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder().timeout(CHECK_TIMEOUT).build()?)
+}
+
+/// Check that the provider answers with these credentials; returns a
+/// short description of what was found
+pub async fn check_provider(answers: &SetupAnswers) -> Result<String> {
+    let endpoint = answers.endpoint.trim().trim_end_matches('/');
+    let api_key = expand_env(answers.api_key.trim());
+    match answers.provider {
+        SetupProvider::ClaudeCli => {
+            let output = tokio::process::Command::new(endpoint)
+                .arg("--version")
+                .output()
+                .await
+                .with_context(|| format!("Could not run {}", endpoint))?;
+            if !output.status.success() {
+                anyhow::bail!("{} --version failed", endpoint);
+            }
+            Ok(format!(
+                "{} {}",
+                endpoint,
+                String::from_utf8_lossy(&output.stdout).trim()
+            ))
+        }
+        SetupProvider::Anthropic => {
+            let response = client()?
+                .get(format!("{}/v1/models", endpoint))
+                .header("x-api-key", &api_key)
+                .header("anthropic-version", "2023-06-01")
+                .send()
+                .await?;
+            count_models(response).await
+        }
+        SetupProvider::OpenAI => {
+            let response = client()?
+                .get(format!("{}/models", endpoint))
+                .bearer_auth(&api_key)
+                .send()
+                .await?;
+            count_models(response).await
+        }
+        SetupProvider::Ollama => {
+            let response = client()?
+                .get(format!("{}/api/tags", endpoint))
+                .send()
+                .await?;
+            let body: serde_json::Value = response.error_for_status()?.json().await?;
+            let model = answers.model.trim();
+            let installed = body["models"].as_array().is_some_and(|models| {
+                models.iter().any(|m| {
+                    m["name"]
+                        .as_str()
+                        .is_some_and(|name| name == model || name.split(':').next() == Some(model))
+                })
+            });
+            if !installed {
+                anyhow::bail!(
+                    "Ollama is running but has no model {} (ollama pull {})",
+                    model,
+                    model
+                );
+            }
+            Ok(format!("Ollama is running with {}", model))
+        }
+    }
+}
+
+async fn count_models(response: reqwest::Response) -> Result<String> {
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        anyhow::bail!("The API key was rejected ({})", status);
+    }
+    let body: serde_json::Value = response.error_for_status()?.json().await?;
+    let count = body["data"].as_array().map_or(0, |models| models.len());
+    Ok(format!("Connected, {} models available", count))
+}
+
+/// Look the bot token up with Discord; returns the bot's name
+pub async fn check_discord_token(token: &str) -> Result<String> {
+    let token = expand_env(token.trim());
+    let response = client()?
+        .get(format!("{}/v10/users/@me", DISCORD_API_ROOT))
+        .header("Authorization", format!("Bot {}", token))
+        .send()
+        .await?;
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        anyhow::bail!("Discord rejected the token");
+    }
+    let user: serde_json::Value = response.error_for_status()?.json().await?;
+    Ok(format!(
+        "Signed in as {}",
+        user["username"].as_str().unwrap_or("the bot")
+    ))
+}
+
+/// Create the memory directory if needed and check that it is writable
+pub fn check_workspace(workspace: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(shellexpand::tilde(workspace.trim()).to_string());
+    std::fs::create_dir_all(&path)
+        .with_context(|| format!("Could not create {}", path.display()))?;
+    let probe = path.join(".localgpt-write-test");
+    std::fs::write(&probe, b"").with_context(|| format!("{} is not writable", path.display()))?;
+    std::fs::remove_file(&probe).ok();
+    Ok(path)
+}
+
+/// The default config template with the answers filled in
+pub fn render_config(answers: &SetupAnswers) -> String {
+    let quote = |s: &str| toml::Value::String(s.trim().to_string()).to_string();
+    let provider = answers.provider;
+    let endpoint = answers.endpoint.trim();
+    let model = format!("{}/{}", provider.prefix(), answers.model.trim());
+
+    let mut config = DEFAULT_CONFIG_TEMPLATE.to_string();
+    replace_anchor(
+        &mut config,
+        "# Auto-created on first run. Edit as needed.",
+        "# Written by `localgpt init`. Edit as needed.",
+    );
+    replace_anchor(
+        &mut config,
+        "default_model = \"claude-cli/opus\"",
+        &format!("default_model = {}", quote(&model)),
+    );
+    replace_anchor(
+        &mut config,
+        "workspace = \"~/.localgpt/workspace\"",
+        &format!("workspace = {}", quote(&answers.workspace)),
+    );
+
+    match provider {
+        SetupProvider::ClaudeCli => replace_anchor(
+            &mut config,
+            "[providers.claude_cli]\ncommand = \"claude\"",
+            &format!(
+                "[providers.claude_cli]\ncommand = {}\nmodel = {}",
+                quote(endpoint),
+                quote(&answers.model)
+            ),
+        ),
+        SetupProvider::Anthropic => replace_anchor(
+            &mut config,
+            "# [providers.anthropic]\n# api_key = \"${ANTHROPIC_API_KEY}\"",
+            &format!(
+                "[providers.anthropic]\napi_key = {}\nbase_url = {}",
+                quote(&answers.api_key),
+                quote(endpoint)
+            ),
+        ),
+        SetupProvider::OpenAI => replace_anchor(
+            &mut config,
+            "# [providers.openai]\n# api_key = \"${OPENAI_API_KEY}\"",
+            &format!(
+                "[providers.openai]\napi_key = {}\nbase_url = {}",
+                quote(&answers.api_key),
+                quote(endpoint)
+            ),
+        ),
+        SetupProvider::Ollama => replace_anchor(
+            &mut config,
+            "# Retries and circuit breaker for model calls",
+            &format!(
+                "# Ollama (for ollama/* models)\n[providers.ollama]\nendpoint = {}\nmodel = {}\n\n\
+                 # Retries and circuit breaker for model calls",
+                quote(endpoint),
+                quote(&answers.model)
+            ),
+        ),
+    }
+
+    if !answers.discord_token.trim().is_empty() {
+        config = format!(
+            "{}\n# Discord bot (guild allow-list, commands etc.: see the README)\n\
+             [channels.discord]\nenabled = true\ntoken = {}\n",
+            config.trim_end(),
+            quote(&answers.discord_token)
+        );
+    }
+    config
+}
+
+/// Write the rendered config to `path`, readable only by the user since it
+/// may hold API keys and the bot token
+pub fn write_config(path: &Path, answers: &SetupAnswers) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, render_config(answers))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+/// Replace the first `anchor` in the template. The anchors are lines of
+/// [`DEFAULT_CONFIG_TEMPLATE`], so a missing one is a bug: the answer
+/// would silently not be written.
+fn replace_anchor(config: &mut String, anchor: &str, with: &str) {
+    assert!(
+        config.contains(anchor),
+        "config template has no {:?}",
+        anchor
+    );
+    *config = config.replacen(anchor, with, 1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_render_config() {
+        let answers = SetupAnswers {
+            provider: SetupProvider::OpenAI,
+            endpoint: "http://localhost:8080/v1".to_string(),
+            api_key: "${LOCAL_API_KEY}".to_string(),
+            model: "qwen2.5-7b".to_string(),
+            discord_token: "abc.def.ghi".to_string(),
+            workspace: "~/notes".to_string(),
+        };
+        let text = render_config(&answers);
+        assert!(text.contains("# Written by `localgpt init`."));
+
+        let config: Config = toml::from_str(&text).unwrap();
+        assert_eq!(config.agent.default_model, "openai/qwen2.5-7b");
+        assert_eq!(config.memory.workspace, "~/notes");
+        let openai = config.providers.openai.unwrap();
+        assert_eq!(openai.api_key, "${LOCAL_API_KEY}");
+        assert_eq!(openai.base_url, "http://localhost:8080/v1");
+        let discord = config.channels.discord.unwrap();
+        assert!(discord.enabled);
+        assert_eq!(discord.token, "abc.def.ghi");
+
+        let ollama = SetupAnswers {
+            provider: SetupProvider::Ollama,
+            endpoint: SetupProvider::Ollama.default_endpoint().to_string(),
+            model: "mistral".to_string(),
+            ..SetupAnswers::default()
+        };
+        let config: Config = toml::from_str(&render_config(&ollama)).unwrap();
+        assert_eq!(config.agent.default_model, "ollama/mistral");
+        assert_eq!(config.providers.ollama.unwrap().model, "mistral");
+        assert!(config.channels.discord.is_none());
+
+        let anthropic = SetupAnswers {
+            provider: SetupProvider::Anthropic,
+            endpoint: SetupProvider::Anthropic.default_endpoint().to_string(),
+            api_key: "${ANTHROPIC_API_KEY}".to_string(),
+            model: "claude-sonnet-4-5".to_string(),
+            ..SetupAnswers::default()
+        };
+        let config: Config = toml::from_str(&render_config(&anthropic)).unwrap();
+        assert_eq!(config.agent.default_model, "anthropic/claude-sonnet-4-5");
+        let provider = config.providers.anthropic.unwrap();
+        assert_eq!(provider.api_key, "${ANTHROPIC_API_KEY}");
+        assert_eq!(provider.base_url, "https://api.anthropic.com");
+
+        let claude_cli = SetupAnswers {
+            provider: SetupProvider::ClaudeCli,
+            endpoint: "/opt/bin/claude".to_string(),
+            model: "sonnet".to_string(),
+            ..SetupAnswers::default()
+        };
+        let config: Config = toml::from_str(&render_config(&claude_cli)).unwrap();
+        assert_eq!(config.agent.default_model, "claude-cli/sonnet");
+        let provider = config.providers.claude_cli.unwrap();
+        assert_eq!(provider.command, "/opt/bin/claude");
+        assert_eq!(provider.model, "sonnet");
+    }
+}
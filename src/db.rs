@@ -0,0 +1,315 @@
+//! Shared SQLite connection pool
+//!
+//! Every SQLite database (memory index, task list) is opened through
+//! [`SqlitePool`]. Connections use WAL journaling and a busy timeout, so the
+//! Discord bot, heartbeat, HTTP server and desktop app can read and write the
+//! same file at once without "database is locked" errors. Pools are shared
+//! per database path within a process. Statements can wait on a locked
+//! database, so async code calls stores through [`blocking`] (or
+//! [`SqlitePool::run`]) to keep that wait off the async runtime.
+
+use anyhow::{Result, anyhow};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::fs;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, LazyLock, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// How long SQLite retries a locked database before failing a statement
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait for a free connection
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connections per file-backed pool
+const DEFAULT_POOL_SIZE: usize = 4;
+
+/// Per-connection setup (e.g. loading an extension), run after the pragmas
+pub type ConnectionInit = fn(&Connection) -> Result<()>;
+
+static SHARED_POOLS: LazyLock<Mutex<HashMap<PathBuf, SqlitePool>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Clone)]
+pub struct SqlitePool {
+    inner: Arc<PoolInner>,
+}
+
+struct PoolInner {
+    /// None = in-memory database (single connection)
+    path: Option<PathBuf>,
+    max_size: usize,
+    init: Option<ConnectionInit>,
+    state: Mutex<PoolState>,
+    available: Condvar,
+}
+
+#[derive(Default)]
+struct PoolState {
+    idle: Vec<Connection>,
+    /// Connections currently open (idle or checked out)
+    open: usize,
+}
+
+impl SqlitePool {
+    /// Open a pool for a database file, creating the parent directory
+    pub fn open(path: &Path, init: Option<ConnectionInit>) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let pool = Self::new(Some(path.to_path_buf()), DEFAULT_POOL_SIZE, init);
+        // Fail early on a bad path and put the database in WAL mode
+        drop(pool.get()?);
+        Ok(pool)
+    }
+
+    /// The process-wide pool for a database file (opened on first use).
+    /// `init` only applies when this call opens the pool.
+    pub fn shared(path: &Path, init: Option<ConnectionInit>) -> Result<Self> {
+        let mut pools = SHARED_POOLS
+            .lock()
+            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        if let Some(pool) = pools.get(path) {
+            return Ok(pool.clone());
+        }
+        let pool = Self::open(path, init)?;
+        pools.insert(path.to_path_buf(), pool.clone());
+        Ok(pool)
+    }
+
+    /// A private in-memory database (tests)
+    pub fn open_in_memory(init: Option<ConnectionInit>) -> Result<Self> {
+        let pool = Self::new(None, 1, init);
+        drop(pool.get()?);
+        Ok(pool)
+    }
+
+    fn new(path: Option<PathBuf>, max_size: usize, init: Option<ConnectionInit>) -> Self {
+        Self {
+            inner: Arc::new(PoolInner {
+                path,
+                max_size,
+                init,
+                state: Mutex::new(PoolState::default()),
+                available: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Check out a connection, waiting if all are in use
+    pub fn get(&self) -> Result<PooledConnection> {
+        let deadline = Instant::now() + ACQUIRE_TIMEOUT;
+        let mut state = self
+            .inner
+            .state
+            .lock()
+            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+
+        loop {
+            if let Some(conn) = state.idle.pop() {
+                return Ok(self.wrap(conn));
+            }
+
+            if state.open < self.inner.max_size {
+                state.open += 1;
+                drop(state);
+                return match self.inner.connect() {
+                    Ok(conn) => Ok(self.wrap(conn)),
+                    Err(e) => {
+                        self.inner.release(None);
+                        Err(e)
+                    }
+                };
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                anyhow::bail!(
+                    "Timed out waiting for a database connection ({})",
+                    self.inner.describe()
+                );
+            }
+            state = self
+                .inner
+                .available
+                .wait_timeout(state, remaining)
+                .map_err(|e| anyhow!("Lock poisoned: {}", e))?
+                .0;
+        }
+    }
+
+    /// Run blocking database work on the blocking thread pool
+    pub async fn run<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let pool = self.clone();
+        blocking(move || {
+            let mut conn = pool.get()?;
+            f(&mut conn)
+        })
+        .await
+    }
+
+    fn wrap(&self, conn: Connection) -> PooledConnection {
+        PooledConnection {
+            conn: Some(conn),
+            pool: Arc::clone(&self.inner),
+        }
+    }
+}
+
+/// Run store calls (or other blocking database work) on the blocking
+/// thread pool
+pub async fn blocking<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| anyhow!("Database task panicked: {}", e))?
+}
+
+impl PoolInner {
+    fn connect(&self) -> Result<Connection> {
+        let conn = match self.path {
+            Some(ref path) => Connection::open(path)?,
+            None => Connection::open_in_memory()?,
+        };
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+
+        if self.path.is_some() {
+            // Readers don't block the writer (and vice versa) in WAL mode
+            let mode: String =
+                conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get(0))?;
+            if !mode.eq_ignore_ascii_case("wal") {
+                debug!("{}: journal_mode is {}, not WAL", self.describe(), mode);
+            }
+            conn.pragma_update(None, "synchronous", "NORMAL")?;
+        }
+
+        if let Some(init) = self.init {
+            init(&conn)?;
+        }
+        Ok(conn)
+    }
+
+    /// Return a connection to the pool, or give up its slot if `None`
+    fn release(&self, conn: Option<Connection>) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        match conn {
+            Some(conn) => state.idle.push(conn),
+            None => state.open -= 1,
+        }
+        self.available.notify_one();
+    }
+
+    fn describe(&self) -> String {
+        self.path
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| ":memory:".to_string())
+    }
+}
+
+/// A checked-out connection; returned to the pool on drop
+pub struct PooledConnection {
+    conn: Option<Connection>,
+    pool: Arc<PoolInner>,
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection already released")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("connection already released")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        self.pool.release(self.conn.take());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wal_and_concurrent_writers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.sqlite");
+        let pool = SqlitePool::open(&path, None).unwrap();
+
+        {
+            let conn = pool.get().unwrap();
+            let mode: String = conn
+                .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(mode.to_lowercase(), "wal");
+            conn.execute_batch("CREATE TABLE t (n INTEGER)").unwrap();
+        }
+
+        // A second pool on the same file (e.g. another process) writing at once
+        let other = SqlitePool::open(&path, None).unwrap();
+        let handles: Vec<_> = [pool.clone(), other]
+            .into_iter()
+            .map(|pool| {
+                std::thread::spawn(move || {
+                    for n in 0..50 {
+                        pool.get()
+                            .unwrap()
+                            .execute("INSERT INTO t (n) VALUES (?1)", [n])
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let count: i64 = pool
+            .get()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 100);
+    }
+
+    #[test]
+    fn test_connections_are_reused() {
+        let pool = SqlitePool::open_in_memory(None).unwrap();
+        pool.get()
+            .unwrap()
+            .execute_batch("CREATE TABLE t (n INTEGER)")
+            .unwrap();
+        // Same single in-memory connection, so the table is still there
+        pool.get()
+            .unwrap()
+            .execute("INSERT INTO t (n) VALUES (1)", [])
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run() {
+        let pool = SqlitePool::open_in_memory(None).unwrap();
+        let n: i64 = pool
+            .run(|conn| Ok(conn.query_row("SELECT 41 + 1", [], |row| row.get(0))?))
+            .await
+            .unwrap();
+        assert_eq!(n, 42);
+    }
+}
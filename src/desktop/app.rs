@@ -3,13 +3,18 @@
 use eframe::egui;
 
 use super::state::{Panel, UiState};
-use super::views::{ChatView, SessionsView, StatusView, chat::show_toolbar};
+use super::views::{
+    ChatView, HistoryView, SessionsView, SetupView, StatusView, chat::show_toolbar,
+};
 use super::worker::WorkerHandle;
 
 /// The main desktop application
 pub struct DesktopApp {
     state: UiState,
-    worker: WorkerHandle,
+    /// Not started until the first-run setup has written a config
+    worker: Option<WorkerHandle>,
+    setup: Option<SetupView>,
+    agent_id: Option<String>,
 }
 
 impl DesktopApp {
@@ -18,12 +23,20 @@ impl DesktopApp {
         // Configure fonts and visuals
         Self::configure_style(&cc.egui_ctx);
 
-        // Start the background worker
-        let worker = WorkerHandle::start(agent_id).expect("Failed to start worker");
+        // Image loaders for markdown images and tool screenshots
+        egui_extras::install_image_loaders(&cc.egui_ctx);
+
+        // On first run, ask for the config before starting the worker
+        let setup = SetupView::needed().then(SetupView::new);
+        let worker = setup
+            .is_none()
+            .then(|| WorkerHandle::start(agent_id.clone()).expect("Failed to start worker"));
 
         Self {
             state: UiState::new(),
             worker,
+            setup,
+            agent_id,
         }
     }
 
@@ -56,7 +69,10 @@ impl DesktopApp {
 
     /// Process all pending worker messages
     fn process_worker_messages(&mut self) {
-        while let Some(msg) = self.worker.try_recv() {
+        let Some(ref worker) = self.worker else {
+            return;
+        };
+        while let Some(msg) = worker.try_recv() {
             self.state.handle_worker_message(msg);
         }
     }
@@ -64,6 +80,19 @@ impl DesktopApp {
 
 impl eframe::App for DesktopApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if let Some(ref mut setup) = self.setup {
+            let saved = egui::CentralPanel::default()
+                .show(ctx, |ui| setup.show(ui))
+                .inner;
+            if saved {
+                self.setup = None;
+                self.worker = Some(
+                    WorkerHandle::start(self.agent_id.clone()).expect("Failed to start worker"),
+                );
+            }
+            return;
+        }
+
         // Process worker messages
         self.process_worker_messages();
 
@@ -73,25 +102,29 @@ impl eframe::App for DesktopApp {
         }
 
         // Top panel with toolbar
-        egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
-            show_toolbar(ui, &mut self.state);
-        });
+        let toolbar_msg = egui::TopBottomPanel::top("toolbar")
+            .show(ctx, |ui| show_toolbar(ui, &mut self.state))
+            .inner;
 
         // Main content
-        egui::CentralPanel::default().show(ctx, |ui| {
-            let msg = match self.state.active_panel {
+        let panel_msg = egui::CentralPanel::default()
+            .show(ctx, |ui| match self.state.active_panel {
                 Panel::Chat => ChatView::show(ui, &mut self.state),
                 Panel::Sessions => SessionsView::show(ui, &mut self.state),
                 Panel::Status => StatusView::show(ui, &mut self.state),
-            };
-
-            // Send any UI messages to worker
-            if let Some(msg) = msg
-                && let Err(e) = self.worker.send(msg)
-            {
+                Panel::History => HistoryView::show(ui, &mut self.state),
+            })
+            .inner;
+
+        // Send any UI messages to worker
+        let Some(ref worker) = self.worker else {
+            return;
+        };
+        for msg in [toolbar_msg, panel_msg].into_iter().flatten() {
+            if let Err(e) = worker.send(msg) {
                 self.state.error = Some(format!("Failed to send to worker: {}", e));
             }
-        });
+        }
     }
 
     fn save(&mut self, _storage: &mut dyn eframe::Storage) {
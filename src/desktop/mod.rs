@@ -2,7 +2,8 @@
 //!
 //! This module provides a native desktop application that embeds the LocalGPT agent
 //! directly - no HTTP, no daemon needed. The agent runs in a background thread
-//! and communicates with the UI via channels.
+//! and communicates with the UI via channels. With the Discord mirror on,
+//! the bot's conversations are followed live from the daemon's HTTP API.
 
 mod app;
 mod state;
@@ -1,6 +1,19 @@
 //! Application state shared between UI and worker
 
-use crate::agent::{SessionInfo, SessionStatus, ToolCall};
+use egui_commonmark::CommonMarkCache;
+
+use crate::agent::{ExportFormat, SessionInfo, SessionStatus, ToolCall};
+use crate::digest::Digest;
+use crate::discord::mirror::MirrorEvent;
+use crate::discord::shadow::ShadowEntry;
+use crate::features::FeatureState;
+use crate::health::DependencyStatus;
+use crate::heartbeat::{HeartbeatEvent, MaintenanceReport};
+use crate::memory::MemoryVersion;
+use crate::monitor::ResourceSample;
+
+/// Mirrored Discord events kept for the observer view
+const MAX_MIRROR_EVENTS: usize = 500;
 
 /// Message from UI to worker
 #[derive(Debug, Clone)]
@@ -11,6 +24,8 @@ pub enum UiMessage {
     NewSession,
     /// Resume a session by ID
     ResumeSession(String),
+    /// Export a saved session to the exports directory
+    ExportSession { id: String, format: ExportFormat },
     /// Approve pending tool calls
     ApproveTools(Vec<ToolCall>),
     /// Deny pending tool calls
@@ -31,6 +46,20 @@ pub enum UiMessage {
     ShowHelp,
     /// Show status info
     ShowStatus,
+    /// Turn Discord shadow mode on or off
+    SetShadowMode(bool),
+    /// Run a heartbeat now
+    RunHeartbeat,
+    /// Pause or resume the heartbeat runner
+    SetHeartbeatPaused(bool),
+    /// Turn a runtime feature toggle on or off
+    SetFeature { name: String, enabled: bool },
+    /// Request the memory history
+    RefreshHistory,
+    /// Show what a memory file version changed
+    ShowVersion(i64),
+    /// Write a memory file version back to its file
+    RestoreVersion(i64),
 }
 
 /// Message from worker to UI
@@ -39,11 +68,15 @@ pub enum WorkerMessage {
     /// Agent is ready
     Ready {
         model: String,
+        /// Models the user can switch to
+        models: Vec<String>,
         memory_chunks: usize,
         has_embeddings: bool,
     },
     /// Streaming content chunk
     ContentChunk(String),
+    /// The streamed reply after post-processing; replaces the chunks
+    ReplyText(String),
     /// Tool call started
     ToolCallStart {
         name: String,
@@ -63,10 +96,40 @@ pub enum WorkerMessage {
     Done,
     /// Error occurred
     Error(String),
+    /// The session's model was switched
+    ModelChanged(String),
     /// Session status update
     Status(SessionStatus),
+    /// Last index maintenance report (written by the heartbeat runner)
+    Maintenance(Option<MaintenanceReport>),
+    /// CPU, RAM and GPU usage
+    Resources(ResourceSample),
+    /// Reachability and latency of model providers and Discord
+    Dependencies(Vec<DependencyStatus>),
+    /// Recent error digests (written by the daemon), newest first
+    ErrorDigests(Vec<Digest>),
+    /// Runtime feature toggles
+    Features(Vec<FeatureState>),
+    /// Discord shadow mode state and recent captured actions
+    ShadowMode {
+        enabled: bool,
+        entries: Vec<ShadowEntry>,
+    },
+    /// Heartbeat runner state and recent events (oldest first)
+    Heartbeat {
+        paused: bool,
+        events: Vec<HeartbeatEvent>,
+    },
     /// Session list update
     Sessions(Vec<SessionInfo>),
+    /// Recent versions of memory files, newest first
+    MemoryHistory(Vec<MemoryVersion>),
+    /// Diff of a memory file version against the one before it
+    VersionDiff { id: i64, diff: String },
+    /// Whether the daemon's Discord mirror is connected
+    MirrorConnected(bool),
+    /// Something the Discord bot did, as it happened
+    Mirror(MirrorEvent),
     /// Session created/resumed
     SessionChanged { id: String, message_count: usize },
     /// System message for display (command output, help text, etc.)
@@ -78,7 +141,8 @@ pub enum WorkerMessage {
 pub struct ChatMessage {
     pub role: MessageRole,
     pub content: String,
-    pub tool_info: Option<ToolInfo>,
+    /// Tool calls made while producing this message
+    pub tools: Vec<ToolInfo>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -94,6 +158,8 @@ pub struct ToolInfo {
     pub name: String,
     pub detail: Option<String>,
     pub status: ToolStatus,
+    /// Full tool output (shown when the call is expanded)
+    pub output: String,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -127,16 +193,52 @@ pub struct UiState {
     pub current_session: Option<SessionInfo>,
     /// Model name
     pub model: String,
+    /// Models offered in the toolbar dropdown
+    pub models: Vec<String>,
     /// Memory chunk count
     pub memory_chunks: usize,
     /// Whether embeddings are enabled
     pub has_embeddings: bool,
     /// Session status
     pub status: Option<SessionStatus>,
+    /// Last index maintenance report
+    pub maintenance: Option<MaintenanceReport>,
+    /// Latest resource usage sample
+    pub resources: Option<ResourceSample>,
+    /// Latest dependency checks
+    pub dependencies: Vec<DependencyStatus>,
+    /// Recent error digests, newest first
+    pub error_digests: Vec<Digest>,
+    /// Runtime feature toggles
+    pub features: Vec<FeatureState>,
+    /// Whether Discord shadow mode is on
+    pub shadow_enabled: bool,
+    /// Recent actions captured by shadow mode
+    pub shadow_entries: Vec<ShadowEntry>,
+    /// Whether the heartbeat runner is paused
+    pub heartbeat_paused: bool,
+    /// Recent heartbeat events, oldest first
+    pub heartbeat_events: Vec<HeartbeatEvent>,
+    /// Timestamp of the event selected in the timeline
+    pub selected_heartbeat: Option<u64>,
+    /// A manual heartbeat run is in progress
+    pub heartbeat_running: bool,
+    /// Recent versions of memory files, newest first
+    pub memory_versions: Vec<MemoryVersion>,
+    /// Version shown in the history panel and its diff
+    pub selected_version: Option<(i64, String)>,
+    /// Whether the Discord mirror is connected; None if it is off
+    pub mirror_connected: Option<bool>,
+    /// Recent Discord events, oldest first
+    pub mirror_events: Vec<MirrorEvent>,
+    /// The Chat view shows the Discord conversations instead of ours
+    pub observing_discord: bool,
     /// Which panel is active
     pub active_panel: Panel,
     /// Scroll to bottom on next frame
     pub scroll_to_bottom: bool,
+    /// Markdown renderer state (code highlighting, images)
+    pub markdown_cache: CommonMarkCache,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
@@ -145,6 +247,7 @@ pub enum Panel {
     Chat,
     Sessions,
     Status,
+    History,
 }
 
 impl UiState {
@@ -157,10 +260,12 @@ impl UiState {
         match msg {
             WorkerMessage::Ready {
                 model,
+                models,
                 memory_chunks,
                 has_embeddings,
             } => {
                 self.model = model;
+                self.models = models;
                 self.memory_chunks = memory_chunks;
                 self.has_embeddings = has_embeddings;
                 self.is_loading = false;
@@ -169,6 +274,9 @@ impl UiState {
                 self.streaming_content.push_str(&content);
                 self.scroll_to_bottom = true;
             }
+            WorkerMessage::ReplyText(text) => {
+                self.streaming_content = text;
+            }
             WorkerMessage::ToolCallStart {
                 name,
                 id: _,
@@ -178,6 +286,7 @@ impl UiState {
                     name,
                     detail,
                     status: ToolStatus::Running,
+                    output: String::new(),
                 });
             }
             WorkerMessage::ToolCallEnd {
@@ -187,13 +296,18 @@ impl UiState {
                 warnings: _,
             } => {
                 // Update tool status
-                if let Some(tool) = self.active_tools.iter_mut().find(|t| t.name == name) {
+                if let Some(tool) = self
+                    .active_tools
+                    .iter_mut()
+                    .find(|t| t.name == name && t.status == ToolStatus::Running)
+                {
                     let preview = if output.len() > 100 {
                         format!("{}...", crate::utils::safe_truncate(&output, 100))
                     } else {
-                        output
+                        output.clone()
                     };
                     tool.status = ToolStatus::Completed(preview);
+                    tool.output = output;
                 }
             }
             WorkerMessage::ToolsPendingApproval(calls) => {
@@ -201,15 +315,14 @@ impl UiState {
                 self.is_loading = false;
             }
             WorkerMessage::Done => {
-                // Finalize streaming content as assistant message
-                if !self.streaming_content.is_empty() {
+                // Finalize streaming content and tool calls as assistant message
+                if !self.streaming_content.is_empty() || !self.active_tools.is_empty() {
                     self.messages.push(ChatMessage {
                         role: MessageRole::Assistant,
                         content: std::mem::take(&mut self.streaming_content),
-                        tool_info: None,
+                        tools: std::mem::take(&mut self.active_tools),
                     });
                 }
-                self.active_tools.clear();
                 self.is_loading = false;
                 self.scroll_to_bottom = true;
             }
@@ -218,12 +331,54 @@ impl UiState {
                 self.is_loading = false;
                 self.streaming_content.clear();
             }
+            WorkerMessage::ModelChanged(model) => {
+                self.model = model;
+            }
             WorkerMessage::Status(status) => {
                 self.status = Some(status);
             }
+            WorkerMessage::Maintenance(report) => {
+                self.maintenance = report;
+            }
+            WorkerMessage::Resources(sample) => {
+                self.resources = Some(sample);
+            }
+            WorkerMessage::Dependencies(statuses) => {
+                self.dependencies = statuses;
+            }
+            WorkerMessage::ErrorDigests(digests) => {
+                self.error_digests = digests;
+            }
+            WorkerMessage::Features(features) => {
+                self.features = features;
+            }
+            WorkerMessage::ShadowMode { enabled, entries } => {
+                self.shadow_enabled = enabled;
+                self.shadow_entries = entries;
+            }
+            WorkerMessage::Heartbeat { paused, events } => {
+                self.heartbeat_paused = paused;
+                self.heartbeat_events = events;
+                self.heartbeat_running = false;
+            }
             WorkerMessage::Sessions(sessions) => {
                 self.sessions = sessions;
             }
+            WorkerMessage::MemoryHistory(versions) => {
+                self.memory_versions = versions;
+            }
+            WorkerMessage::VersionDiff { id, diff } => {
+                self.selected_version = Some((id, diff));
+            }
+            WorkerMessage::MirrorConnected(connected) => {
+                self.mirror_connected = Some(connected);
+            }
+            WorkerMessage::Mirror(event) => {
+                self.mirror_events.push(event);
+                if self.mirror_events.len() > MAX_MIRROR_EVENTS {
+                    self.mirror_events.remove(0);
+                }
+            }
             WorkerMessage::SessionChanged { id, message_count } => {
                 self.current_session = Some(SessionInfo {
                     id,
@@ -239,7 +394,7 @@ impl UiState {
                 self.messages.push(ChatMessage {
                     role: MessageRole::System,
                     content: text,
-                    tool_info: None,
+                    tools: Vec::new(),
                 });
                 self.scroll_to_bottom = true;
             }
@@ -251,7 +406,7 @@ impl UiState {
         self.messages.push(ChatMessage {
             role: MessageRole::User,
             content,
-            tool_info: None,
+            tools: Vec::new(),
         });
         self.scroll_to_bottom = true;
     }
@@ -1,8 +1,23 @@
 //! Chat view - message display and input
 
 use eframe::egui::{self, Color32, RichText, ScrollArea, TextEdit, Ui};
+use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
+use regex::Regex;
+use std::sync::LazyLock;
 
-use crate::desktop::state::{ChatMessage, MessageRole, Panel, ToolStatus, UiMessage, UiState};
+use crate::desktop::state::{
+    ChatMessage, MessageRole, Panel, ToolInfo, ToolStatus, UiMessage, UiState,
+};
+use crate::discord::mirror::MirrorEventKind;
+
+/// Tool output longer than this is cut in the expanded view
+const MAX_TOOL_OUTPUT_CHARS: usize = 4000;
+
+/// Image files or URLs mentioned in tool output
+static IMAGE_REF_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)(?:https?://|file://|~/|/)[^\s"'<>()\[\]]+\.(?:png|jpe?g|gif|webp)\b"#)
+        .unwrap()
+});
 
 pub struct ChatView;
 
@@ -10,6 +25,26 @@ impl ChatView {
     pub fn show(ui: &mut Ui, state: &mut UiState) -> Option<UiMessage> {
         let mut message_to_send = None;
 
+        // With the Discord mirror on, switch between our chat and the bot's
+        if let Some(connected) = state.mirror_connected {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut state.observing_discord, false, "This chat");
+                ui.selectable_value(&mut state.observing_discord, true, "Discord (live)");
+                if !connected {
+                    ui.label(
+                        RichText::new("daemon not reachable")
+                            .small()
+                            .color(Color32::GRAY),
+                    );
+                }
+            });
+            ui.separator();
+            if state.observing_discord {
+                Self::show_mirror(ui, state);
+                return None;
+            }
+        }
+
         // Main chat area
         let available_height = ui.available_height() - 60.0; // Reserve space for input
 
@@ -23,8 +58,8 @@ impl ChatView {
                 ui.set_min_width(ui.available_width());
 
                 // Show messages
-                for msg in &state.messages {
-                    Self::render_message(ui, msg);
+                for (index, msg) in state.messages.iter().enumerate() {
+                    Self::render_message(ui, index, msg, &mut state.markdown_cache);
                     ui.add_space(8.0);
                 }
 
@@ -37,7 +72,11 @@ impl ChatView {
                                 .color(Color32::from_rgb(100, 149, 237)),
                         );
                     });
-                    ui.label(&state.streaming_content);
+                    CommonMarkViewer::new().show(
+                        ui,
+                        &mut state.markdown_cache,
+                        &state.streaming_content,
+                    );
                     ui.add_space(8.0);
                 }
 
@@ -169,7 +208,7 @@ impl ChatView {
                     state.messages.push(ChatMessage {
                         role: MessageRole::System,
                         content: format!("Current model: {}", state.model),
-                        tool_info: None,
+                        tools: Vec::new(),
                     });
                     state.scroll_to_bottom = true;
                     None // No message to send to worker
@@ -183,7 +222,7 @@ impl ChatView {
                     state.messages.push(ChatMessage {
                         role: MessageRole::System,
                         content: "Usage: /memory <query>".to_string(),
-                        tool_info: None,
+                        tools: Vec::new(),
                     });
                     state.scroll_to_bottom = true;
                     None
@@ -199,7 +238,7 @@ impl ChatView {
                     state.messages.push(ChatMessage {
                         role: MessageRole::System,
                         content: "Usage: /resume <session-id>".to_string(),
-                        tool_info: None,
+                        tools: Vec::new(),
                     });
                     state.scroll_to_bottom = true;
                     None
@@ -218,7 +257,7 @@ impl ChatView {
                         "Unknown command: {}. Type /help for available commands.",
                         cmd
                     ),
-                    tool_info: None,
+                    tools: Vec::new(),
                 });
                 state.scroll_to_bottom = true;
                 None
@@ -226,7 +265,79 @@ impl ChatView {
         }
     }
 
-    fn render_message(ui: &mut Ui, msg: &ChatMessage) {
+    /// The Discord bot's conversations as they happen; read-only
+    fn show_mirror(ui: &mut Ui, state: &mut UiState) {
+        if state.mirror_events.is_empty() {
+            ui.label(RichText::new("Nothing from Discord yet").color(Color32::GRAY));
+            return;
+        }
+
+        ScrollArea::vertical()
+            .id_salt("discord_mirror")
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                ui.set_min_width(ui.available_width());
+
+                for (index, event) in state.mirror_events.iter().enumerate() {
+                    let channel = RichText::new(format!("#{}", event.channel_id))
+                        .small()
+                        .color(Color32::GRAY);
+                    match &event.kind {
+                        MirrorEventKind::Prompt { authors, content } => {
+                            ui.add_space(8.0);
+                            ui.horizontal(|ui| {
+                                ui.label(
+                                    RichText::new(authors.join(", "))
+                                        .strong()
+                                        .color(Color32::from_rgb(52, 152, 219)),
+                                );
+                                ui.label(channel);
+                            });
+                            ui.label(content);
+                        }
+                        MirrorEventKind::ToolCallStart { name, detail } => {
+                            let text = match detail {
+                                Some(detail) => format!("Running: {}: {}", name, detail),
+                                None => format!("Running: {}", name),
+                            };
+                            ui.label(RichText::new(text).small().color(Color32::GRAY));
+                        }
+                        MirrorEventKind::ToolCallEnd { name, output } => {
+                            let tool = ToolInfo {
+                                name: name.clone(),
+                                detail: None,
+                                status: ToolStatus::Completed(String::new()),
+                                output: output.clone(),
+                            };
+                            Self::render_tool_call(ui, (usize::MAX, index), &tool);
+                        }
+                        MirrorEventKind::Reply { text } => {
+                            ui.horizontal(|ui| {
+                                ui.label(
+                                    RichText::new("Assistant")
+                                        .strong()
+                                        .color(Color32::from_rgb(100, 149, 237)),
+                                );
+                                ui.label(channel);
+                            });
+                            CommonMarkViewer::new().show(ui, &mut state.markdown_cache, text);
+                        }
+                        MirrorEventKind::Error { message } => {
+                            ui.horizontal(|ui| {
+                                ui.label(
+                                    RichText::new("Error: ").color(Color32::from_rgb(231, 76, 60)),
+                                );
+                                ui.label(message);
+                                ui.label(channel);
+                            });
+                        }
+                    }
+                }
+            });
+    }
+
+    fn render_message(ui: &mut Ui, index: usize, msg: &ChatMessage, cache: &mut CommonMarkCache) {
         let (label, color) = match msg.role {
             MessageRole::User => ("You", Color32::from_rgb(52, 152, 219)),
             MessageRole::Assistant => ("Assistant", Color32::from_rgb(100, 149, 237)),
@@ -235,36 +346,132 @@ impl ChatView {
 
         ui.horizontal(|ui| {
             ui.label(RichText::new(label).strong().color(color));
+            if msg.role == MessageRole::Assistant
+                && !msg.content.is_empty()
+                && ui
+                    .small_button("Copy")
+                    .on_hover_text("Copy the whole reply")
+                    .clicked()
+            {
+                ui.ctx().copy_text(msg.content.clone());
+            }
         });
 
-        // Render content with basic markdown-like formatting
-        ui.label(&msg.content);
+        // Tool calls made before the final answer
+        for (tool_index, tool) in msg.tools.iter().enumerate() {
+            Self::render_tool_call(ui, (index, tool_index), tool);
+        }
 
-        // Show tool info if any
-        if let Some(ref tool_info) = msg.tool_info {
-            ui.horizontal(|ui| {
-                ui.label(
-                    RichText::new(format!("[{}]", tool_info.name))
-                        .small()
-                        .color(Color32::GRAY),
-                );
+        // User input is shown as typed; replies are markdown (code blocks
+        // are highlighted and get their own copy button)
+        match msg.role {
+            MessageRole::User => {
+                ui.label(&msg.content);
+            }
+            _ if !msg.content.is_empty() => {
+                CommonMarkViewer::new().show(ui, cache, &msg.content);
+            }
+            _ => {}
+        }
+    }
+
+    /// A finished tool call: collapsed by default, with any images it
+    /// produced shown inline
+    fn render_tool_call(ui: &mut Ui, id: (usize, usize), tool: &ToolInfo) {
+        let title = match tool.detail {
+            Some(ref detail) => format!("{}: {}", tool.name, detail),
+            None => tool.name.clone(),
+        };
+        let (title, output) = match tool.status {
+            ToolStatus::Running => (RichText::new(title).color(Color32::GRAY), ""),
+            ToolStatus::Completed(_) => (
+                RichText::new(title).color(Color32::from_rgb(46, 204, 113)),
+                tool.output.as_str(),
+            ),
+            ToolStatus::Error(ref err) => (
+                RichText::new(title).color(Color32::from_rgb(231, 76, 60)),
+                err.as_str(),
+            ),
+        };
+
+        egui::CollapsingHeader::new(title.small())
+            .id_salt(("tool_call", id))
+            .default_open(false)
+            .show(ui, |ui| {
+                let shown = if output.len() > MAX_TOOL_OUTPUT_CHARS {
+                    format!(
+                        "{}\n… ({} bytes total)",
+                        crate::utils::safe_truncate(output, MAX_TOOL_OUTPUT_CHARS),
+                        output.len()
+                    )
+                } else {
+                    output.to_string()
+                };
+                ui.label(RichText::new(shown).monospace().small());
             });
+
+        for uri in image_uris(output) {
+            ui.add(egui::Image::new(uri).max_width(480.0).max_height(360.0));
         }
     }
 }
 
-/// Top toolbar with panel tabs
-pub fn show_toolbar(ui: &mut Ui, state: &mut UiState) {
+/// Loadable URIs for images referenced in tool output (existing local
+/// files and http(s) URLs)
+fn image_uris(output: &str) -> Vec<String> {
+    let mut uris: Vec<String> = Vec::new();
+    for m in IMAGE_REF_RE.find_iter(output) {
+        let reference = m.as_str();
+        let uri = if reference.starts_with("http://")
+            || reference.starts_with("https://")
+            || reference.starts_with("file://")
+        {
+            reference.to_string()
+        } else {
+            let path = shellexpand::tilde(reference).to_string();
+            if !std::path::Path::new(&path).is_file() {
+                continue;
+            }
+            format!("file://{}", path)
+        };
+        if !uris.contains(&uri) {
+            uris.push(uri);
+        }
+    }
+    uris
+}
+
+/// Top toolbar with panel tabs and the model dropdown
+pub fn show_toolbar(ui: &mut Ui, state: &mut UiState) -> Option<UiMessage> {
+    let mut msg = None;
+
     ui.horizontal(|ui| {
         ui.selectable_value(&mut state.active_panel, Panel::Chat, "Chat");
         ui.selectable_value(&mut state.active_panel, Panel::Sessions, "Sessions");
         ui.selectable_value(&mut state.active_panel, Panel::Status, "Status");
+        ui.selectable_value(&mut state.active_panel, Panel::History, "History");
 
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-            if !state.model.is_empty() {
+            if state.models.len() > 1 {
+                ui.add_enabled_ui(!state.is_loading, |ui| {
+                    egui::ComboBox::from_id_salt("model")
+                        .selected_text(RichText::new(&state.model).small())
+                        .show_ui(ui, |ui| {
+                            for model in &state.models {
+                                if ui.selectable_label(*model == state.model, model).clicked()
+                                    && *model != state.model
+                                {
+                                    msg = Some(UiMessage::SetModel(model.clone()));
+                                }
+                            }
+                        });
+                });
+            } else if !state.model.is_empty() {
                 ui.label(RichText::new(&state.model).small().color(Color32::GRAY));
             }
         });
     });
     ui.separator();
+
+    msg
 }
@@ -0,0 +1,111 @@
+//! Memory history view - recorded versions of memory files, their diffs
+//! and rollback
+
+use eframe::egui::{Color32, RichText, ScrollArea, TextStyle, Ui};
+
+use crate::desktop::state::{UiMessage, UiState};
+
+pub struct HistoryView;
+
+impl HistoryView {
+    pub fn show(ui: &mut Ui, state: &mut UiState) -> Option<UiMessage> {
+        let mut message_to_send = None;
+
+        ui.heading("Memory History");
+        ui.add_space(10.0);
+
+        if ui.button("Refresh").clicked() {
+            message_to_send = Some(UiMessage::RefreshHistory);
+        }
+
+        ui.add_space(10.0);
+
+        if state.memory_versions.is_empty() {
+            ui.label(RichText::new("No recorded versions").color(Color32::GRAY));
+            return message_to_send;
+        }
+
+        ui.columns(2, |columns| {
+            ScrollArea::vertical()
+                .id_salt("history_list")
+                .auto_shrink([false, false])
+                .show(&mut columns[0], |ui| {
+                    for version in &state.memory_versions {
+                        let selected = state
+                            .selected_version
+                            .as_ref()
+                            .is_some_and(|(id, _)| *id == version.id);
+                        let time = chrono::DateTime::from_timestamp(version.created_at, 0)
+                            .map(|t| {
+                                t.with_timezone(&chrono::Local)
+                                    .format("%m-%d %H:%M")
+                                    .to_string()
+                            })
+                            .unwrap_or_default();
+                        let mut label = format!("#{} {} {}", version.id, time, version.path);
+                        if version.is_deletion() {
+                            label.push_str(" [deleted]");
+                        }
+                        if ui
+                            .selectable_label(selected, label)
+                            .on_hover_text(&version.source)
+                            .clicked()
+                        {
+                            message_to_send = Some(UiMessage::ShowVersion(version.id));
+                        }
+                    }
+                });
+
+            let ui = &mut columns[1];
+            let Some((id, ref diff)) = state.selected_version else {
+                ui.label(
+                    RichText::new("Select a version to see what changed").color(Color32::GRAY),
+                );
+                return;
+            };
+            let deletion = state
+                .memory_versions
+                .iter()
+                .any(|v| v.id == id && v.is_deletion());
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(format!("Version #{}", id)).strong());
+                if !deletion
+                    && ui
+                        .small_button("Restore")
+                        .on_hover_text("Write this version back to the file")
+                        .clicked()
+                {
+                    message_to_send = Some(UiMessage::RestoreVersion(id));
+                }
+            });
+            ScrollArea::vertical()
+                .id_salt("history_diff")
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    if diff.is_empty() {
+                        ui.label(RichText::new("No changes").color(Color32::GRAY));
+                    }
+                    for line in diff.lines() {
+                        let color = if line.starts_with("+++") || line.starts_with("---") {
+                            Color32::GRAY
+                        } else if line.starts_with('+') {
+                            Color32::from_rgb(46, 204, 113)
+                        } else if line.starts_with('-') {
+                            Color32::from_rgb(231, 76, 60)
+                        } else if line.starts_with("@@") {
+                            Color32::from_rgb(52, 152, 219)
+                        } else {
+                            ui.visuals().text_color()
+                        };
+                        ui.label(
+                            RichText::new(line)
+                                .text_style(TextStyle::Monospace)
+                                .color(color),
+                        );
+                    }
+                });
+        });
+
+        message_to_send
+    }
+}
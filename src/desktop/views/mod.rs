@@ -1,9 +1,13 @@
 //! UI views
 
 pub mod chat;
+mod history;
 mod sessions;
+mod setup;
 mod status;
 
 pub use chat::ChatView;
+pub use history::HistoryView;
 pub use sessions::SessionsView;
+pub use setup::SetupView;
 pub use status::StatusView;
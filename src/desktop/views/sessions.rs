@@ -2,6 +2,7 @@
 
 use eframe::egui::{Color32, RichText, ScrollArea, Ui};
 
+use crate::agent::ExportFormat;
 use crate::desktop::state::{UiMessage, UiState};
 
 pub struct SessionsView;
@@ -78,6 +79,26 @@ impl SessionsView {
                                         Some(UiMessage::ResumeSession(session.id.clone()));
                                 }
                             }
+                            if ui
+                                .small_button("MD")
+                                .on_hover_text("Export as markdown")
+                                .clicked()
+                            {
+                                message_to_send = Some(UiMessage::ExportSession {
+                                    id: session.id.clone(),
+                                    format: ExportFormat::Markdown,
+                                });
+                            }
+                            if ui
+                                .small_button("HTML")
+                                .on_hover_text("Export as HTML")
+                                .clicked()
+                            {
+                                message_to_send = Some(UiMessage::ExportSession {
+                                    id: session.id.clone(),
+                                    format: ExportFormat::Html,
+                                });
+                            }
                         });
                     }
                 });
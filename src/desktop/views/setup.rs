@@ -0,0 +1,220 @@
+//! First-run setup view, shown instead of the chat until a config exists
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use eframe::egui::{self, Color32, RichText, Ui};
+
+use crate::config::{
+    Config, SetupAnswers, SetupProvider, check_discord_token, check_provider, check_workspace,
+    write_config,
+};
+
+/// State of one answer's check
+enum Check {
+    NotRun,
+    Running,
+    Passed(String),
+    Failed(String),
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+    Provider,
+    Discord,
+}
+
+pub struct SetupView {
+    answers: SetupAnswers,
+    provider_check: Check,
+    discord_check: Check,
+    workspace_error: Option<String>,
+    results_tx: Sender<(Field, Result<String, String>)>,
+    results_rx: Receiver<(Field, Result<String, String>)>,
+}
+
+impl Default for SetupView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SetupView {
+    /// Whether this is a first run (no config file yet)
+    pub fn needed() -> bool {
+        Config::config_path().is_ok_and(|path| !path.exists())
+    }
+
+    pub fn new() -> Self {
+        let (results_tx, results_rx) = mpsc::channel();
+        Self {
+            answers: SetupAnswers::default(),
+            provider_check: Check::NotRun,
+            discord_check: Check::NotRun,
+            workspace_error: None,
+            results_tx,
+            results_rx,
+        }
+    }
+
+    /// Show the form; returns true once the config has been written
+    pub fn show(&mut self, ui: &mut Ui) -> bool {
+        while let Ok((field, result)) = self.results_rx.try_recv() {
+            let check = match result {
+                Ok(found) => Check::Passed(found),
+                Err(e) => Check::Failed(e),
+            };
+            match field {
+                Field::Provider => self.provider_check = check,
+                Field::Discord => self.discord_check = check,
+            }
+        }
+
+        let mut saved = false;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.heading("Welcome to LocalGPT");
+            ui.label("Answer a few questions to create your config. Each answer can be checked before saving.");
+            ui.add_space(10.0);
+
+            self.show_provider(ui);
+            ui.add_space(10.0);
+            self.show_discord(ui);
+            ui.add_space(10.0);
+
+            ui.group(|ui| {
+                ui.label(RichText::new("Memory directory").strong());
+                ui.text_edit_singleline(&mut self.answers.workspace);
+                if let Some(ref error) = self.workspace_error {
+                    ui.label(RichText::new(error).color(Color32::from_rgb(231, 76, 60)));
+                }
+            });
+            ui.add_space(10.0);
+
+            if ui.button("Save and start").clicked() {
+                saved = self.save();
+            }
+        });
+        saved
+    }
+
+    fn show_provider(&mut self, ui: &mut Ui) {
+        ui.group(|ui| {
+            ui.label(RichText::new("Model provider").strong());
+            let previous = self.answers.provider;
+            egui::ComboBox::from_id_salt("setup_provider")
+                .selected_text(previous.label())
+                .show_ui(ui, |ui| {
+                    for provider in SetupProvider::ALL {
+                        ui.selectable_value(&mut self.answers.provider, provider, provider.label());
+                    }
+                });
+            let provider = self.answers.provider;
+            if provider != previous {
+                self.answers.endpoint = provider.default_endpoint().to_string();
+                self.answers.model = provider.default_model().to_string();
+                self.provider_check = Check::NotRun;
+            }
+
+            egui::Grid::new("setup_provider_fields")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label(if provider == SetupProvider::ClaudeCli {
+                        "Command"
+                    } else {
+                        "Endpoint"
+                    });
+                    ui.text_edit_singleline(&mut self.answers.endpoint);
+                    ui.end_row();
+                    if provider.needs_api_key() {
+                        ui.label("API key");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.answers.api_key)
+                                .password(true)
+                                .hint_text("key, or ${VAR} from the environment"),
+                        );
+                        ui.end_row();
+                    }
+                    ui.label("Model");
+                    ui.text_edit_singleline(&mut self.answers.model);
+                    ui.end_row();
+                });
+
+            if ui.button("Check").clicked() {
+                self.provider_check = Check::Running;
+                let answers = self.answers.clone();
+                self.spawn_check(ui.ctx(), Field::Provider, async move {
+                    check_provider(&answers).await
+                });
+            }
+            show_check(ui, &self.provider_check);
+        });
+    }
+
+    fn show_discord(&mut self, ui: &mut Ui) {
+        ui.group(|ui| {
+            ui.label(RichText::new("Discord bot (optional)").strong());
+            ui.add(
+                egui::TextEdit::singleline(&mut self.answers.discord_token)
+                    .password(true)
+                    .hint_text("bot token, empty to skip"),
+            );
+            if !self.answers.discord_token.trim().is_empty() && ui.button("Check").clicked() {
+                self.discord_check = Check::Running;
+                let token = self.answers.discord_token.clone();
+                self.spawn_check(ui.ctx(), Field::Discord, async move {
+                    check_discord_token(&token).await
+                });
+            }
+            show_check(ui, &self.discord_check);
+        });
+    }
+
+    /// Run a network check off the UI thread
+    fn spawn_check<F>(&self, ctx: &egui::Context, field: Field, check: F)
+    where
+        F: std::future::Future<Output = anyhow::Result<String>> + Send + 'static,
+    {
+        let tx = self.results_tx.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let result = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(anyhow::Error::from)
+                .and_then(|rt| rt.block_on(check))
+                .map_err(|e| e.to_string());
+            let _ = tx.send((field, result));
+            ctx.request_repaint();
+        });
+    }
+
+    /// Write the config; false (with the error shown) if that failed
+    fn save(&mut self) -> bool {
+        let result = check_workspace(&self.answers.workspace)
+            .and_then(|_| write_config(&Config::config_path()?, &self.answers));
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                self.workspace_error = Some(format!("{:#}", e));
+                false
+            }
+        }
+    }
+}
+
+fn show_check(ui: &mut Ui, check: &Check) {
+    match check {
+        Check::NotRun => {}
+        Check::Running => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Checking...");
+            });
+        }
+        Check::Passed(found) => {
+            ui.label(RichText::new(format!("✓ {}", found)).color(Color32::from_rgb(46, 204, 113)));
+        }
+        Check::Failed(error) => {
+            ui.label(RichText::new(format!("✗ {}", error)).color(Color32::from_rgb(231, 76, 60)));
+        }
+    }
+}
@@ -1,8 +1,12 @@
 //! Status view - show model, memory, and session stats
 
-use eframe::egui::{Color32, ProgressBar, RichText, Ui};
+use eframe::egui::{self, Color32, ProgressBar, RichText, Ui};
 
 use crate::desktop::state::{UiMessage, UiState};
+use crate::digest::{Digest, Severity};
+use crate::health::{DependencyStatus, HealthState};
+use crate::heartbeat::{HeartbeatEvent, HeartbeatStatus};
+use crate::monitor::ResourceSample;
 
 pub struct StatusView;
 
@@ -44,6 +48,139 @@ impl StatusView {
 
         ui.add_space(10.0);
 
+        // Resource usage (the daemon holds back background work when busy)
+        ui.group(|ui| {
+            ui.label(RichText::new("Resources").strong());
+            match state.resources {
+                Some(ref sample) => Self::show_resources(ui, sample),
+                None => {
+                    ui.label(RichText::new("Not sampled yet").color(Color32::GRAY));
+                }
+            }
+        });
+
+        ui.add_space(10.0);
+
+        // Model providers and Discord (checked when the status is refreshed)
+        ui.group(|ui| {
+            ui.label(RichText::new("Dependencies").strong());
+            if state.dependencies.is_empty() {
+                ui.label(RichText::new("Nothing to check").color(Color32::GRAY));
+            }
+            for status in &state.dependencies {
+                Self::show_dependency(ui, status);
+            }
+        });
+        ui.add_space(10.0);
+
+        // Heartbeat timeline
+        ui.group(|ui| {
+            if let Some(msg) = Self::show_heartbeat(ui, state) {
+                message_to_send = Some(msg);
+            }
+        });
+
+        ui.add_space(10.0);
+
+        // Index maintenance (run nightly by the heartbeat runner)
+        ui.group(|ui| {
+            ui.label(RichText::new("Index Maintenance").strong());
+            match state.maintenance {
+                Some(ref report) => {
+                    let finished = chrono::DateTime::from_timestamp(report.finished_at, 0)
+                        .map(|t| {
+                            t.with_timezone(&chrono::Local)
+                                .format("%Y-%m-%d %H:%M")
+                                .to_string()
+                        })
+                        .unwrap_or_else(|| report.date.clone());
+                    ui.label(format!(
+                        "Last run: {} ({:.1}s)",
+                        finished,
+                        report.duration_ms as f64 / 1000.0
+                    ));
+                    for step in &report.steps {
+                        let color = if step.ok {
+                            Color32::from_rgb(46, 204, 113)
+                        } else {
+                            Color32::from_rgb(231, 76, 60)
+                        };
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new(&step.name).color(color));
+                            ui.label(RichText::new(&step.detail).small());
+                        });
+                    }
+                }
+                None => {
+                    ui.label(RichText::new("Not run yet").color(Color32::GRAY));
+                }
+            }
+        });
+
+        ui.add_space(10.0);
+
+        // Error digests (collected by the daemon)
+        ui.group(|ui| {
+            ui.label(RichText::new("Errors").strong());
+            if state.error_digests.is_empty() {
+                ui.label(RichText::new("No errors reported").color(Color32::GRAY));
+            }
+            for digest in &state.error_digests {
+                Self::show_digest(ui, digest);
+            }
+        });
+
+        ui.add_space(10.0);
+
+        // Runtime feature toggles (shared with the daemon)
+        ui.group(|ui| {
+            ui.label(RichText::new("Features").strong());
+            for feature in &state.features {
+                let mut enabled = feature.enabled;
+                if ui
+                    .checkbox(&mut enabled, feature.name)
+                    .on_hover_text(feature.description)
+                    .changed()
+                {
+                    message_to_send = Some(UiMessage::SetFeature {
+                        name: feature.name.to_string(),
+                        enabled,
+                    });
+                }
+            }
+        });
+
+        ui.add_space(10.0);
+
+        // Discord shadow mode: capture outbound actions instead of executing them
+        ui.group(|ui| {
+            ui.label(RichText::new("Discord Shadow Mode").strong());
+            let mut enabled = state.shadow_enabled;
+            if ui
+                .checkbox(
+                    &mut enabled,
+                    "Capture actions for review instead of sending",
+                )
+                .changed()
+            {
+                message_to_send = Some(UiMessage::SetShadowMode(enabled));
+            }
+            if state.shadow_entries.is_empty() {
+                ui.label(RichText::new("No captured actions").color(Color32::GRAY));
+            }
+            for entry in state.shadow_entries.iter().rev() {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(&entry.action).strong());
+                    ui.label(RichText::new(&entry.target).small());
+                    ui.label(
+                        RichText::new(crate::utils::safe_truncate(&entry.content, 120)).small(),
+                    );
+                });
+            }
+        });
+
+        ui.add_space(10.0);
+
         // Session info
         if let Some(ref status) = state.status {
             ui.group(|ui| {
@@ -88,4 +225,212 @@ impl StatusView {
 
         message_to_send
     }
+
+    /// Recent heartbeat runs as a row of colored cells (newest on the right);
+    /// clicking a cell shows the full event
+    fn show_resources(ui: &mut Ui, sample: &ResourceSample) {
+        let bar = |ui: &mut Ui, label: &str, percent: f32, text: String| {
+            ui.horizontal(|ui| {
+                ui.label(label);
+                ui.add(ProgressBar::new(percent / 100.0).text(text));
+            });
+        };
+        match sample.cpu_percent {
+            Some(cpu) => bar(ui, "CPU:", cpu, format!("{:.0}%", cpu)),
+            None => {
+                ui.label(RichText::new("CPU: refresh to measure").color(Color32::GRAY));
+            }
+        }
+        if let (Some(percent), Some(used), Some(total)) = (
+            sample.memory_percent(),
+            sample.memory_used_mb,
+            sample.memory_total_mb,
+        ) {
+            bar(ui, "RAM:", percent, format!("{} / {} MB", used, total));
+        }
+        for gpu in &sample.gpus {
+            ui.label(RichText::new(&gpu.name).small());
+            bar(
+                ui,
+                "GPU:",
+                gpu.utilization_percent,
+                format!("{:.0}%", gpu.utilization_percent),
+            );
+            let vram = if gpu.vram_total_mb > 0 {
+                gpu.vram_used_mb as f32 * 100.0 / gpu.vram_total_mb as f32
+            } else {
+                0.0
+            };
+            bar(
+                ui,
+                "VRAM:",
+                vram,
+                format!("{} / {} MB", gpu.vram_used_mb, gpu.vram_total_mb),
+            );
+        }
+    }
+
+    fn show_dependency(ui: &mut Ui, status: &DependencyStatus) {
+        let (color, text) = match (status.state, status.latency_ms) {
+            (HealthState::Up, Some(ms)) => (Color32::from_rgb(46, 204, 113), format!("{} ms", ms)),
+            (HealthState::Degraded, Some(ms)) => {
+                (Color32::from_rgb(241, 196, 15), format!("slow, {} ms", ms))
+            }
+            _ => (Color32::from_rgb(231, 76, 60), "down".to_string()),
+        };
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(&status.name).color(color));
+            ui.label(RichText::new(text).small());
+        })
+        .response
+        .on_hover_text(status.error.as_deref().unwrap_or(&status.url));
+    }
+
+    fn show_heartbeat(ui: &mut Ui, state: &mut UiState) -> Option<UiMessage> {
+        let mut message_to_send = None;
+
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Heartbeat").strong());
+            if state.heartbeat_running {
+                ui.spinner();
+            } else if ui.small_button("Run now").clicked() {
+                state.heartbeat_running = true;
+                message_to_send = Some(UiMessage::RunHeartbeat);
+            }
+            let mut paused = state.heartbeat_paused;
+            if ui.checkbox(&mut paused, "Paused").changed() {
+                message_to_send = Some(UiMessage::SetHeartbeatPaused(paused));
+            }
+        });
+
+        if state.heartbeat_events.is_empty() {
+            ui.label(RichText::new("No heartbeat runs recorded").color(Color32::GRAY));
+            return message_to_send;
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 2.0;
+            for event in &state.heartbeat_events {
+                let selected = state.selected_heartbeat == Some(event.ts);
+                let cell = egui::Button::new("")
+                    .fill(status_color(&event.status))
+                    .stroke(if selected {
+                        egui::Stroke::new(2.0, Color32::WHITE)
+                    } else {
+                        egui::Stroke::NONE
+                    })
+                    .min_size(egui::vec2(10.0, 18.0));
+                let response = ui.add(cell).on_hover_text(format!(
+                    "{} — {:?} ({:.1}s)",
+                    format_ts(event.ts),
+                    event.status,
+                    event.duration_ms as f64 / 1000.0
+                ));
+                if response.clicked() {
+                    state.selected_heartbeat = if selected { None } else { Some(event.ts) };
+                }
+            }
+        });
+
+        let selected = state
+            .selected_heartbeat
+            .and_then(|ts| state.heartbeat_events.iter().find(|e| e.ts == ts))
+            .or(state.heartbeat_events.last());
+        if let Some(event) = selected {
+            ui.add_space(5.0);
+            Self::show_heartbeat_event(ui, event);
+        }
+
+        message_to_send
+    }
+
+    fn show_digest(ui: &mut Ui, digest: &Digest) {
+        ui.label(
+            RichText::new(format_time(&digest.created_at, "%m-%d %H:%M"))
+                .small()
+                .strong(),
+        );
+        for entry in &digest.entries {
+            ui.horizontal(|ui| {
+                ui.label(
+                    RichText::new(format!("{:?}", entry.severity))
+                        .color(severity_color(entry.severity)),
+                );
+                ui.label(RichText::new(&entry.source).strong());
+                ui.label(
+                    RichText::new(format!(
+                        "×{}, first {}, last {}",
+                        entry.count,
+                        format_time(&entry.first_seen, "%H:%M"),
+                        format_time(&entry.last_seen, "%H:%M")
+                    ))
+                    .small(),
+                );
+            });
+            ui.label(RichText::new(crate::utils::safe_truncate(&entry.message, 200)).small());
+        }
+    }
+
+    fn show_heartbeat_event(ui: &mut Ui, event: &HeartbeatEvent) {
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new(format!("{:?}", event.status)).color(status_color(&event.status)),
+            );
+            ui.label(format!(
+                "{} ({:.1}s)",
+                format_ts(event.ts),
+                event.duration_ms as f64 / 1000.0
+            ));
+        });
+        if let Some(ref reason) = event.reason {
+            ui.label(RichText::new(reason).small());
+        }
+        if !event.actions.is_empty() {
+            ui.label(RichText::new("Actions:").small().strong());
+            for action in &event.actions {
+                ui.label(RichText::new(format!("  - {}", action)).small());
+            }
+        }
+        if let Some(text) = event.response.as_ref().or(event.preview.as_ref()) {
+            egui::ScrollArea::vertical()
+                .id_salt("heartbeat_response")
+                .max_height(160.0)
+                .show(ui, |ui| {
+                    ui.label(RichText::new(text).monospace().small());
+                });
+        }
+    }
+}
+
+fn status_color(status: &HeartbeatStatus) -> Color32 {
+    match status {
+        HeartbeatStatus::Sent => Color32::from_rgb(100, 149, 237),
+        HeartbeatStatus::Ok => Color32::from_rgb(46, 204, 113),
+        HeartbeatStatus::Skipped => Color32::GRAY,
+        HeartbeatStatus::Failed => Color32::from_rgb(231, 76, 60),
+    }
+}
+
+fn severity_color(severity: Severity) -> Color32 {
+    match severity {
+        Severity::Warning => Color32::from_rgb(241, 196, 15),
+        Severity::Error => Color32::from_rgb(230, 126, 34),
+        Severity::Critical => Color32::from_rgb(231, 76, 60),
+    }
+}
+
+fn format_time(time: &chrono::DateTime<chrono::Utc>, format: &str) -> String {
+    time.with_timezone(&chrono::Local)
+        .format(format)
+        .to_string()
+}
+
+fn format_ts(ts_ms: u64) -> String {
+    chrono::DateTime::from_timestamp_millis(ts_ms as i64)
+        .map(|t| {
+            t.with_timezone(&chrono::Local)
+                .format("%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_default()
 }
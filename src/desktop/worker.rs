@@ -6,19 +6,38 @@
 use std::pin::pin;
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 use anyhow::Result;
 use futures::StreamExt;
 
 use crate::agent::{
-    Agent, AgentConfig, DEFAULT_AGENT_ID, StreamEvent, ToolCall, extract_tool_detail,
-    list_sessions_for_agent,
+    Agent, AgentConfig, ChannelKind, DEFAULT_AGENT_ID, ExportFormat, ExportOptions, ReplyPipeline,
+    Session, StreamEvent, ToolCall, export_file_name, export_session, extract_tool_detail,
+    get_state_dir, list_sessions_for_agent,
 };
 use crate::config::Config;
-use crate::memory::MemoryManager;
+use crate::digest::load_recent_digests;
+use crate::discord::mirror::MirrorEvent;
+use crate::discord::shadow;
+use crate::features::FeatureStore;
+use crate::health;
+use crate::heartbeat::{
+    HeartbeatRunner, is_heartbeat_paused, load_heartbeat_history, load_last_maintenance_report,
+    set_heartbeat_paused,
+};
+use crate::memory::{MemoryHistory, MemoryManager};
+use crate::monitor::Sampler;
+use crate::ws::Backoff;
 
 use super::state::{UiMessage, WorkerMessage};
 
+/// Error digests shown in the status panel
+const RECENT_DIGESTS: usize = 10;
+
+/// Memory file versions listed in the history panel
+const RECENT_VERSIONS: usize = 100;
+
 /// Handle to the background worker
 pub struct WorkerHandle {
     /// Send commands to the worker
@@ -78,6 +97,10 @@ async fn worker_loop(
     // Initialize agent
     let config = Config::load()?;
     let memory = MemoryManager::new_with_full_config(&config.memory, Some(&config), &agent_id)?;
+    if let Err(e) = crate::memory::watch_context_files(memory.workspace()) {
+        tracing::warn!("Failed to watch context files: {}", e);
+    }
+    let history = MemoryHistory::for_workspace(memory.workspace())?;
 
     let agent_config = AgentConfig {
         model: config.agent.default_model.clone(),
@@ -91,6 +114,7 @@ async fn worker_loop(
     // Send ready message
     let _ = tx.send(WorkerMessage::Ready {
         model: agent.model().to_string(),
+        models: config.agent.switchable_models(),
         memory_chunks: agent.memory_chunk_count(),
         has_embeddings: agent.has_embeddings(),
     });
@@ -102,9 +126,37 @@ async fn worker_loop(
 
     // Send initial status
     let _ = tx.send(WorkerMessage::Status(agent.session_status()));
+    let _ = tx.send(WorkerMessage::Maintenance(load_last_maintenance_report(
+        &agent_id,
+    )));
+    // CPU usage is measured between samples, so the first has none
+    let mut sampler = Sampler::new();
+    let _ = tx.send(WorkerMessage::Resources(sampler.sample()));
+    let _ = tx.send(WorkerMessage::Dependencies(
+        health::check_all(&config).await,
+    ));
+    let _ = tx.send(shadow_status());
+    let _ = tx.send(heartbeat_status(&agent_id));
+    let _ = tx.send(WorkerMessage::ErrorDigests(load_recent_digests(
+        RECENT_DIGESTS,
+    )));
+    let features = FeatureStore::open_default()?;
+    let _ = tx.send(WorkerMessage::Features(features.list()));
+    let _ = tx.send(history_list(&history));
+
+    // Discord conversations answered by the daemon, shown read-only
+    if config.channels.discord.as_ref().is_some_and(|d| d.mirror) {
+        let _ = tx.send(WorkerMessage::MirrorConnected(false));
+        start_mirror(
+            format!("{}/api/discord/mirror", config.server.url()),
+            config.server.admin_token.clone(),
+            tx.clone(),
+        );
+    }
 
     // Track tools requiring approval
     let approval_tools: Vec<String> = agent.approval_required_tools().to_vec();
+    let reply_pipeline = ReplyPipeline::for_channel(&config, ChannelKind::Desktop);
 
     // Main loop
     while let Ok(msg) = rx.recv() {
@@ -117,11 +169,13 @@ async fn worker_loop(
                     Ok(stream) => {
                         let mut stream = pin!(stream);
                         let mut pending_tools: Vec<ToolCall> = Vec::new();
+                        let mut reply = String::new();
 
                         while let Some(result) = stream.next().await {
                             match result {
                                 Ok(event) => match event {
                                     StreamEvent::Content(text) => {
+                                        reply.push_str(&text);
                                         let _ = tx.send(WorkerMessage::ContentChunk(text));
                                     }
                                     StreamEvent::ToolCallStart {
@@ -166,6 +220,9 @@ async fn worker_loop(
                                             ));
                                             pending_tools.clear();
                                         } else {
+                                            let text = reply_pipeline
+                                                .process_joined(&std::mem::take(&mut reply));
+                                            let _ = tx.send(WorkerMessage::ReplyText(text));
                                             let _ = tx.send(WorkerMessage::Done);
                                         }
                                         should_auto_save = true;
@@ -198,6 +255,8 @@ async fn worker_loop(
             },
             UiMessage::ResumeSession(session_id) => match agent.resume_session(&session_id).await {
                 Ok(()) => {
+                    // Resuming restores the model the session was switched to
+                    let _ = tx.send(WorkerMessage::ModelChanged(agent.model().to_string()));
                     let status = agent.session_status();
                     let _ = tx.send(WorkerMessage::SessionChanged {
                         id: status.id.clone(),
@@ -209,6 +268,22 @@ async fn worker_loop(
                     let _ = tx.send(WorkerMessage::Error(e.to_string()));
                 }
             },
+            UiMessage::ExportSession { id, format } => {
+                match export_saved_session(&agent_id, &id, format, &config.agent.default_model) {
+                    Ok(path) => {
+                        let _ = tx.send(WorkerMessage::SystemMessage(format!(
+                            "Session exported to: {}",
+                            path.display()
+                        )));
+                    }
+                    Err(e) => {
+                        let _ = tx.send(WorkerMessage::SystemMessage(format!(
+                            "Export failed: {}",
+                            e
+                        )));
+                    }
+                }
+            }
             UiMessage::ApproveTools(_tools) => {
                 // Tool approval is handled in chat loop
                 // For now, just send done
@@ -224,9 +299,101 @@ async fn worker_loop(
             }
             UiMessage::RefreshStatus => {
                 let _ = tx.send(WorkerMessage::Status(agent.session_status()));
+                let _ = tx.send(WorkerMessage::Maintenance(load_last_maintenance_report(
+                    &agent_id,
+                )));
+                let _ = tx.send(WorkerMessage::Resources(sampler.sample()));
+                let _ = tx.send(WorkerMessage::Dependencies(
+                    health::check_all(&config).await,
+                ));
+                let _ = tx.send(shadow_status());
+                let _ = tx.send(heartbeat_status(&agent_id));
+                let _ = tx.send(WorkerMessage::ErrorDigests(load_recent_digests(
+                    RECENT_DIGESTS,
+                )));
+                let _ = tx.send(WorkerMessage::Features(features.list()));
+            }
+            UiMessage::SetFeature { name, enabled } => {
+                if let Err(e) = features.set_by_name(&name, enabled, "desktop") {
+                    let _ = tx.send(WorkerMessage::Error(format!(
+                        "Failed to switch {}: {}",
+                        name, e
+                    )));
+                }
+                // Some toggles are also shown in their own sections
+                let _ = tx.send(WorkerMessage::Features(features.list()));
+                let _ = tx.send(shadow_status());
+                let _ = tx.send(heartbeat_status(&agent_id));
             }
-            UiMessage::SetModel(name) => match agent.set_model(&name) {
+            UiMessage::RefreshHistory => {
+                let _ = tx.send(history_list(&history));
+            }
+            UiMessage::ShowVersion(id) => {
+                let diff = history.get(id).and_then(|version| match version {
+                    Some(version) => history.diff(&version),
+                    None => Err(anyhow::anyhow!("Version {} not found", id)),
+                });
+                match diff {
+                    Ok(diff) => {
+                        let _ = tx.send(WorkerMessage::VersionDiff { id, diff });
+                    }
+                    Err(e) => {
+                        let _ = tx.send(WorkerMessage::Error(e.to_string()));
+                    }
+                }
+            }
+            UiMessage::RestoreVersion(id) => {
+                let restored = history.get(id).and_then(|version| match version {
+                    Some(version) => history.restore(&version),
+                    None => Err(anyhow::anyhow!("Version {} not found", id)),
+                });
+                match restored {
+                    Ok(path) => {
+                        let _ = tx.send(WorkerMessage::SystemMessage(format!(
+                            "Restored {} to version {}",
+                            path.display(),
+                            id
+                        )));
+                    }
+                    Err(e) => {
+                        let _ = tx.send(WorkerMessage::Error(format!("Restore failed: {}", e)));
+                    }
+                }
+                let _ = tx.send(history_list(&history));
+            }
+            UiMessage::SetShadowMode(enabled) => {
+                if let Err(e) = shadow::set_enabled(enabled) {
+                    let _ = tx.send(WorkerMessage::Error(format!(
+                        "Failed to switch shadow mode: {}",
+                        e
+                    )));
+                }
+                let _ = tx.send(shadow_status());
+            }
+            UiMessage::RunHeartbeat => {
+                // Runs here rather than in the daemon; the workspace lock
+                // keeps it from overlapping a daemon run
+                let result = match HeartbeatRunner::new_with_agent(&config, &agent_id) {
+                    Ok(runner) => runner.run_once().await.map(|_| ()),
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    let _ = tx.send(WorkerMessage::Error(format!("Heartbeat failed: {}", e)));
+                }
+                let _ = tx.send(heartbeat_status(&agent_id));
+            }
+            UiMessage::SetHeartbeatPaused(paused) => {
+                if let Err(e) = set_heartbeat_paused(&agent_id, paused) {
+                    let _ = tx.send(WorkerMessage::Error(format!(
+                        "Failed to pause heartbeat: {}",
+                        e
+                    )));
+                }
+                let _ = tx.send(heartbeat_status(&agent_id));
+            }
+            UiMessage::SetModel(name) => match agent.switch_model(&name) {
                 Ok(()) => {
+                    let _ = tx.send(WorkerMessage::ModelChanged(agent.model().to_string()));
                     let _ = tx.send(WorkerMessage::SystemMessage(format!(
                         "Model set to: {}",
                         agent.model()
@@ -341,3 +508,100 @@ Available commands:
 
     Ok(())
 }
+
+/// Write a saved session to `<state_dir>/exports/` and return the file path
+fn export_saved_session(
+    agent_id: &str,
+    session_id: &str,
+    format: ExportFormat,
+    model: &str,
+) -> Result<std::path::PathBuf> {
+    let session = Session::load_for_agent(agent_id, session_id)?;
+    let opts = ExportOptions {
+        model: Some(model.to_string()),
+        ..Default::default()
+    };
+
+    let dir = get_state_dir()?.join("exports");
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(export_file_name(&session, format));
+    std::fs::write(&path, export_session(&session, format, &opts))?;
+    Ok(path)
+}
+
+/// Follow the daemon's Discord mirror on a thread of its own (the worker
+/// loop blocks between UI messages)
+fn start_mirror(url: String, token: Option<String>, tx: Sender<WorkerMessage>) {
+    thread::spawn(move || {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create tokio runtime");
+        rt.block_on(follow_mirror(&url, token.as_deref(), &tx));
+    });
+}
+
+/// Forward mirrored events until the UI is gone, reconnecting whenever the
+/// daemon can't be reached. The endpoint needs the admin token.
+async fn follow_mirror(url: &str, token: Option<&str>, tx: &Sender<WorkerMessage>) {
+    let client = reqwest::Client::new();
+    let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(60));
+    loop {
+        let mut request = client.get(url);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        match request.send().await.and_then(|r| r.error_for_status()) {
+            Ok(response) => {
+                backoff.reset();
+                if tx.send(WorkerMessage::MirrorConnected(true)).is_err() {
+                    return;
+                }
+                let mut body = response.bytes_stream();
+                let mut buffer: Vec<u8> = Vec::new();
+                while let Some(Ok(chunk)) = body.next().await {
+                    buffer.extend_from_slice(&chunk);
+                    while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+                        let line: Vec<u8> = buffer.drain(..=end).collect();
+                        let line = String::from_utf8_lossy(&line);
+                        if let Some(data) = line.strip_prefix("data:")
+                            && let Ok(event) = serde_json::from_str::<MirrorEvent>(data.trim())
+                            && tx.send(WorkerMessage::Mirror(event)).is_err()
+                        {
+                            return;
+                        }
+                    }
+                }
+            }
+            Err(e) => tracing::debug!("Discord mirror unavailable: {}", e),
+        }
+        if tx.send(WorkerMessage::MirrorConnected(false)).is_err() {
+            return;
+        }
+        tokio::time::sleep(backoff.next_delay()).await;
+    }
+}
+
+/// Recent versions of memory files
+fn history_list(history: &MemoryHistory) -> WorkerMessage {
+    match history.versions(None, RECENT_VERSIONS) {
+        Ok(versions) => WorkerMessage::MemoryHistory(versions),
+        Err(e) => WorkerMessage::Error(format!("Failed to load memory history: {}", e)),
+    }
+}
+
+/// Discord shadow mode state and its most recent captured actions
+fn shadow_status() -> WorkerMessage {
+    WorkerMessage::ShadowMode {
+        enabled: shadow::is_enabled(),
+        entries: shadow::load_entries(20),
+    }
+}
+
+/// Heartbeat runner state and its recent history
+fn heartbeat_status(agent_id: &str) -> WorkerMessage {
+    WorkerMessage::Heartbeat {
+        paused: is_heartbeat_paused(agent_id),
+        events: load_heartbeat_history(agent_id, 48),
+    }
+}
@@ -0,0 +1,301 @@
+//! Error digests
+//!
+//! Failures are reported with [`report`] instead of being posted one by one
+//! (a flapping provider used to produce an error message per chat message).
+//! Reports are grouped by source and message, with numbers masked so
+//! "timed out after 31s" and "after 32s" count as one error, and classified
+//! by [`Severity`]. The daemon's [`DigestRunner`] sends what was collected as
+//! a single digest every `notifications.digest_interval`: to the Discord
+//! admin channel, and to `logs/error_digests.jsonl` in the state directory,
+//! which the desktop app shows.
+
+mod runner;
+
+pub use runner::DigestRunner;
+
+use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+pub use crate::config::Severity;
+
+/// Digests kept in the history file
+const MAX_DIGESTS: usize = 100;
+
+/// Contexts (channels, jobs) listed per entry
+const MAX_CONTEXTS: usize = 5;
+
+/// Longest error text kept per entry
+const MAX_MESSAGE_CHARS: usize = 300;
+
+static COLLECTOR: LazyLock<Mutex<Vec<DigestEntry>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+
+/// One distinct error and how often it happened
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestEntry {
+    /// Component that reported it ("discord", "heartbeat", "jobs", ...)
+    pub source: String,
+    /// The first occurrence's text
+    pub message: String,
+    pub severity: Severity,
+    pub count: u64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// Where it happened, e.g. "channel 123" (first few distinct)
+    pub contexts: Vec<String>,
+    #[serde(skip)]
+    fingerprint: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Digest {
+    pub created_at: DateTime<Utc>,
+    /// Most severe first, then most frequent
+    pub entries: Vec<DigestEntry>,
+}
+
+/// Collect a failure for the next digest
+pub fn report(source: &str, context: &str, error: &str) {
+    let mut entries = COLLECTOR.lock().unwrap_or_else(|e| e.into_inner());
+    add_entry(&mut entries, source, context, error, Utc::now());
+}
+
+/// Take everything collected since the last digest
+pub fn take_pending() -> Vec<DigestEntry> {
+    let mut entries = COLLECTOR.lock().unwrap_or_else(|e| e.into_inner());
+    std::mem::take(&mut *entries)
+}
+
+fn add_entry(
+    entries: &mut Vec<DigestEntry>,
+    source: &str,
+    context: &str,
+    error: &str,
+    now: DateTime<Utc>,
+) {
+    let fingerprint = format!("{}:{}", source, mask_numbers(error));
+    if let Some(entry) = entries.iter_mut().find(|e| e.fingerprint == fingerprint) {
+        entry.count += 1;
+        entry.last_seen = now;
+        if !context.is_empty()
+            && entry.contexts.len() < MAX_CONTEXTS
+            && !entry.contexts.iter().any(|c| c == context)
+        {
+            entry.contexts.push(context.to_string());
+        }
+        return;
+    }
+
+    entries.push(DigestEntry {
+        source: source.to_string(),
+        message: crate::utils::safe_truncate(error, MAX_MESSAGE_CHARS).to_string(),
+        severity: classify(error),
+        count: 1,
+        first_seen: now,
+        last_seen: now,
+        contexts: if context.is_empty() {
+            Vec::new()
+        } else {
+            vec![context.to_string()]
+        },
+        fingerprint,
+    });
+}
+
+fn mask_numbers(text: &str) -> String {
+    let mut masked = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            if !masked.ends_with('#') {
+                masked.push('#');
+            }
+        } else {
+            masked.push(c);
+        }
+    }
+    masked
+}
+
+/// Guess how urgent an error is from its text
+pub fn classify(error: &str) -> Severity {
+    let text = error.to_lowercase();
+    let has = |patterns: &[&str]| patterns.iter().any(|p| text.contains(p));
+
+    if has(&[
+        "401",
+        "403",
+        "unauthorized",
+        "forbidden",
+        "api key",
+        "api_key",
+        "x-api-key",
+        "authentication",
+        "not configured",
+        "permission denied",
+    ]) {
+        Severity::Critical
+    } else if has(&["rate limit", "rate_limit", "429", "overloaded"]) {
+        Severity::Warning
+    } else {
+        Severity::Error
+    }
+}
+
+/// Build a digest from collected entries, dropping those below `min_severity`
+pub fn build_digest(entries: Vec<DigestEntry>, min_severity: Severity) -> Option<Digest> {
+    let mut entries: Vec<DigestEntry> = entries
+        .into_iter()
+        .filter(|e| e.severity >= min_severity)
+        .collect();
+    if entries.is_empty() {
+        return None;
+    }
+    entries.sort_by(|a, b| b.severity.cmp(&a.severity).then(b.count.cmp(&a.count)));
+    Some(Digest {
+        created_at: Utc::now(),
+        entries,
+    })
+}
+
+/// Discord text for a digest
+pub fn format_digest(digest: &Digest) -> String {
+    let total: u64 = digest.entries.iter().map(|e| e.count).sum();
+    let mut text = format!(
+        "**Error digest**: {} error{} ({} distinct)",
+        total,
+        if total == 1 { "" } else { "s" },
+        digest.entries.len()
+    );
+
+    for entry in &digest.entries {
+        let icon = match entry.severity {
+            Severity::Critical => "🔴",
+            Severity::Error => "🟠",
+            Severity::Warning => "🟡",
+        };
+        let first = entry.first_seen.with_timezone(&Local).format("%H:%M");
+        let last = entry.last_seen.with_timezone(&Local).format("%H:%M");
+        let seen = if entry.count == 1 {
+            format!("at {}", first)
+        } else {
+            format!("×{}, first {}, last {}", entry.count, first, last)
+        };
+        text.push_str(&format!("\n{} **{}** {}", icon, entry.source, seen));
+        if !entry.contexts.is_empty() {
+            text.push_str(&format!(" ({})", entry.contexts.join(", ")));
+        }
+        text.push_str(&format!("\n> {}", entry.message.replace('\n', " ")));
+    }
+    text
+}
+
+/// Path of the digest history
+pub fn digests_path(state_dir: &Path) -> PathBuf {
+    state_dir.join("logs").join("error_digests.jsonl")
+}
+
+/// Append a digest, trimming the file once it grows well past the limit
+pub fn append_digest(path: &Path, digest: &Digest) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(digest)?)?;
+    drop(file);
+
+    let text = fs::read_to_string(path)?;
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.len() > MAX_DIGESTS * 2 {
+        let kept = lines[lines.len() - MAX_DIGESTS..].join("\n");
+        fs::write(path, kept + "\n")?;
+    }
+    Ok(())
+}
+
+/// The most recent digests, newest first
+pub fn load_recent_digests(limit: usize) -> Vec<Digest> {
+    let Ok(text) =
+        crate::agent::get_state_dir().and_then(|dir| Ok(fs::read_to_string(digests_path(&dir))?))
+    else {
+        return Vec::new();
+    };
+    text.lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_repeated_errors_are_grouped() {
+        let now = Utc::now();
+        let mut entries = Vec::new();
+        add_entry(
+            &mut entries,
+            "discord",
+            "channel 1",
+            "Timed out after 31s",
+            now,
+        );
+        add_entry(
+            &mut entries,
+            "discord",
+            "channel 2",
+            "Timed out after 32s",
+            now + Duration::minutes(5),
+        );
+        add_entry(
+            &mut entries,
+            "discord",
+            "channel 1",
+            "Timed out after 33s",
+            now,
+        );
+        add_entry(&mut entries, "heartbeat", "", "Timed out after 31s", now);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].count, 3);
+        assert_eq!(entries[0].message, "Timed out after 31s");
+        assert_eq!(entries[0].contexts, vec!["channel 1", "channel 2"]);
+        assert_eq!(entries[0].last_seen, now + Duration::minutes(5));
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify(r#"Anthropic API error: {"type":"authentication_error"}"#),
+            Severity::Critical
+        );
+        assert_eq!(
+            classify("OpenAI API error: rate_limit_exceeded"),
+            Severity::Warning
+        );
+        assert_eq!(classify("Claude CLI failed: exit 1"), Severity::Error);
+    }
+
+    #[test]
+    fn test_build_digest() {
+        let now = Utc::now();
+        let mut entries = Vec::new();
+        add_entry(&mut entries, "discord", "", "Overloaded", now);
+        add_entry(&mut entries, "jobs", "job #2", "Invalid API key", now);
+        add_entry(&mut entries, "heartbeat", "", "Claude CLI failed", now);
+
+        let digest = build_digest(entries.clone(), Severity::Error).unwrap();
+        assert_eq!(digest.entries.len(), 2);
+        assert_eq!(digest.entries[0].source, "jobs");
+        assert!(format_digest(&digest).starts_with("**Error digest**: 2 errors (2 distinct)"));
+
+        assert!(build_digest(entries, Severity::Critical).is_some());
+        assert!(build_digest(Vec::new(), Severity::Warning).is_none());
+    }
+}
@@ -0,0 +1,81 @@
+//! Sends collected errors as periodic digests
+
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::{append_digest, build_digest, digests_path, format_digest, take_pending};
+use crate::agent::get_state_dir;
+use crate::config::{Config, NotificationsConfig, parse_duration};
+use crate::discord::rest::DiscordRest;
+
+pub struct DigestRunner {
+    config: NotificationsConfig,
+    /// None without an admin channel or Discord config
+    rest: Option<Arc<dyn DiscordRest>>,
+    interval: Duration,
+}
+
+impl DigestRunner {
+    pub fn new(config: &Config) -> Result<Self> {
+        let notifications = config.notifications.clone();
+        let interval = parse_duration(&notifications.digest_interval)
+            .map_err(|e| anyhow::anyhow!("Invalid notifications digest_interval: {}", e))?;
+
+        let rest = match (&notifications.admin_channel, &config.channels.discord) {
+            (Some(_), Some(_)) => match crate::discord::rest_client(config) {
+                Ok(rest) => Some(rest),
+                Err(e) => {
+                    warn!("Error digests will not be posted to Discord: {}", e);
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        Ok(Self {
+            config: notifications,
+            rest,
+            interval,
+        })
+    }
+
+    pub async fn run(self) -> Result<()> {
+        let mut ticker = tokio::time::interval(self.interval);
+        // The first tick fires immediately; nothing is collected yet
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            self.send_digest().await;
+        }
+    }
+
+    async fn send_digest(&self) {
+        let Some(digest) = build_digest(take_pending(), self.config.min_severity) else {
+            return;
+        };
+        info!(
+            "Error digest: {} distinct errors since the last one",
+            digest.entries.len()
+        );
+
+        match get_state_dir() {
+            Ok(dir) => {
+                if let Err(e) = append_digest(&digests_path(&dir), &digest) {
+                    warn!("Failed to write error digest: {}", e);
+                }
+            }
+            Err(e) => warn!("Failed to write error digest: {}", e),
+        }
+
+        if let (Some(rest), Some(channel_id)) = (&self.rest, &self.config.admin_channel)
+            && let Err(e) = rest
+                .send_message(channel_id, &format_digest(&digest), None)
+                .await
+        {
+            warn!("Failed to post error digest to Discord: {}", e);
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! Slash-style commands handled by the bot itself
+//!
+//! Messages such as `/export html` are intercepted before batching and never
+//! reach the agent. Commands may also start with `!` (`!model`). Any other
+//! text starting with `/` or `!` is passed through as a normal message.
+//!
+//! A guild can also set a `command_prefix` such as `!lg`. Messages starting
+//! with it (`!lg reset`) are always commands: they count as addressed to the
+//! bot even where a mention is required, and unknown names get the list of
+//! commands instead of going to the agent.
+
+use crate::agent::ExportFormat;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum DiscordCommand {
+    /// Start the channel's conversation over
+    Reset,
+    /// Upload the channel's current session as a file
+    Export(ExportFormat),
+    /// Show the channel's model (None) or switch it
+    Model(Option<String>),
+    /// Upload the prompt behind the reply to a message (None = the
+    /// channel's latest reply)
+    Inspect(Option<String>),
+    /// Post the bot's diagnostics (admins only)
+    Status,
+    /// List the channel's pinned context
+    Pins,
+    /// Pin a snippet, or a workspace file if `file` is set (admins only)
+    Pin { file: bool, content: String },
+    /// Remove a pin by ID (admins only)
+    Unpin(i64),
+    /// Show the channel's settings (`name` None) or change one (admins
+    /// only); `channel` scopes the change to this channel instead of the
+    /// guild, `value` "reset" removes it
+    Settings {
+        channel: bool,
+        name: Option<String>,
+        value: Option<String>,
+    },
+    /// A known command with bad arguments; the string is the usage hint
+    Invalid(String),
+}
+
+/// Whether `content` starts with a guild's command prefix (as a whole word,
+/// case-insensitive)
+pub(super) fn has_prefix(content: &str, prefix: &str) -> bool {
+    strip_prefix(content, prefix).is_some()
+}
+
+fn strip_prefix<'a>(content: &'a str, prefix: &str) -> Option<&'a str> {
+    let content = content.trim_start();
+    let prefix = prefix.trim();
+    if prefix.is_empty() || !content.get(..prefix.len())?.eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+    let rest = &content[prefix.len()..];
+    (rest.is_empty() || rest.starts_with(char::is_whitespace)).then_some(rest)
+}
+
+impl DiscordCommand {
+    /// Parse a (mention-stripped) message that starts with a guild's
+    /// command prefix; `None` if it doesn't
+    pub fn parse_prefixed(content: &str, prefix: &str) -> Option<Self> {
+        let rest = strip_prefix(content, prefix)?.trim();
+        let prefix = prefix.trim();
+        let usage = || {
+            let names = [
+                "reset",
+                "status",
+                "model [name]",
+                "export [md|html]",
+                "inspect [id]",
+                "pin <text>",
+                "unpin <id>",
+                "settings",
+            ];
+            let list: Vec<String> = names
+                .iter()
+                .map(|name| format!("`{} {}`", prefix, name))
+                .collect();
+            format!("Commands: {}", list.join(", "))
+        };
+        if rest.is_empty() {
+            return Some(DiscordCommand::Invalid(usage()));
+        }
+        let command = Self::parse(&format!("!{}", rest));
+        Some(command.unwrap_or_else(|| DiscordCommand::Invalid(usage())))
+    }
+
+    /// Parse a (mention-stripped) message; `None` if it isn't a bot command
+    pub fn parse(content: &str) -> Option<Self> {
+        let mut parts = content.split_whitespace();
+        let name = parts.next()?.strip_prefix(['/', '!'])?;
+        // Everything after the name, line breaks included
+        let rest = content
+            .trim()
+            .split_once(char::is_whitespace)
+            .map(|(_, rest)| rest.trim())
+            .unwrap_or("");
+
+        match name.to_lowercase().as_str() {
+            "export" => match parts.next() {
+                None => Some(DiscordCommand::Export(ExportFormat::Markdown)),
+                Some(arg) => Some(
+                    ExportFormat::parse(arg)
+                        .map(DiscordCommand::Export)
+                        .unwrap_or_else(|| {
+                            DiscordCommand::Invalid("Usage: `/export [md|html]`".to_string())
+                        }),
+                ),
+            },
+            "model" => Some(DiscordCommand::Model(parts.next().map(String::from))),
+            "inspect" => Some(DiscordCommand::Inspect(parts.next().map(String::from))),
+            "reset" => Some(DiscordCommand::Reset),
+            "status" => Some(DiscordCommand::Status),
+            "pins" => Some(DiscordCommand::Pins),
+            "pin" => match rest.split_once(char::is_whitespace) {
+                _ if rest.is_empty() => Some(DiscordCommand::Pins),
+                Some((kind, path)) if kind.eq_ignore_ascii_case("file") => {
+                    Some(DiscordCommand::Pin {
+                        file: true,
+                        content: path.trim().to_string(),
+                    })
+                }
+                _ if rest.eq_ignore_ascii_case("file") => Some(DiscordCommand::Invalid(
+                    "Usage: `/pin <text>` or `/pin file <path>`".to_string(),
+                )),
+                _ => Some(DiscordCommand::Pin {
+                    file: false,
+                    content: rest.to_string(),
+                }),
+            },
+            "settings" => {
+                let (channel, rest) = match rest.split_once(char::is_whitespace) {
+                    Some((scope, rest)) if scope.eq_ignore_ascii_case("channel") => {
+                        (true, rest.trim())
+                    }
+                    _ if rest.eq_ignore_ascii_case("channel") => (true, ""),
+                    _ => (false, rest),
+                };
+                let (name, value) = match rest.split_once(char::is_whitespace) {
+                    Some((name, value)) => (Some(name), Some(value.trim())),
+                    None => ((!rest.is_empty()).then_some(rest), None),
+                };
+                match (name, value) {
+                    (Some(_), None) => Some(DiscordCommand::Invalid(
+                        "Usage: `/settings [channel] <name> <value|reset>`".to_string(),
+                    )),
+                    _ => Some(DiscordCommand::Settings {
+                        channel,
+                        name: name.map(String::from),
+                        value: value.map(String::from),
+                    }),
+                }
+            }
+            "unpin" => Some(
+                parts
+                    .next()
+                    .and_then(|id| id.trim_start_matches('#').parse().ok())
+                    .map(DiscordCommand::Unpin)
+                    .unwrap_or_else(|| DiscordCommand::Invalid("Usage: `/unpin <id>`".to_string())),
+            ),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_export() {
+        assert_eq!(
+            DiscordCommand::parse("/export"),
+            Some(DiscordCommand::Export(ExportFormat::Markdown))
+        );
+        assert_eq!(
+            DiscordCommand::parse(" /export HTML "),
+            Some(DiscordCommand::Export(ExportFormat::Html))
+        );
+        assert!(matches!(
+            DiscordCommand::parse("/export pdf"),
+            Some(DiscordCommand::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_model() {
+        assert_eq!(
+            DiscordCommand::parse("!model"),
+            Some(DiscordCommand::Model(None))
+        );
+        assert_eq!(
+            DiscordCommand::parse("/model openai/gpt-4o"),
+            Some(DiscordCommand::Model(Some("openai/gpt-4o".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_parse_inspect() {
+        assert_eq!(
+            DiscordCommand::parse("/inspect"),
+            Some(DiscordCommand::Inspect(None))
+        );
+        assert_eq!(
+            DiscordCommand::parse("/inspect 1234567890"),
+            Some(DiscordCommand::Inspect(Some("1234567890".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_parse_status() {
+        assert_eq!(
+            DiscordCommand::parse("/status"),
+            Some(DiscordCommand::Status)
+        );
+        assert_eq!(
+            DiscordCommand::parse("!STATUS"),
+            Some(DiscordCommand::Status)
+        );
+    }
+
+    #[test]
+    fn test_parse_pins() {
+        assert_eq!(DiscordCommand::parse("/pin"), Some(DiscordCommand::Pins));
+        assert_eq!(
+            DiscordCommand::parse("/pin House rules:\n- be kind"),
+            Some(DiscordCommand::Pin {
+                file: false,
+                content: "House rules:\n- be kind".to_string()
+            })
+        );
+        assert_eq!(
+            DiscordCommand::parse("!pin FILE docs/README.md"),
+            Some(DiscordCommand::Pin {
+                file: true,
+                content: "docs/README.md".to_string()
+            })
+        );
+        assert_eq!(
+            DiscordCommand::parse("/unpin #3"),
+            Some(DiscordCommand::Unpin(3))
+        );
+        assert!(matches!(
+            DiscordCommand::parse("/unpin all"),
+            Some(DiscordCommand::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_settings() {
+        assert_eq!(
+            DiscordCommand::parse("/settings"),
+            Some(DiscordCommand::Settings {
+                channel: false,
+                name: None,
+                value: None
+            })
+        );
+        assert_eq!(
+            DiscordCommand::parse("!settings channel persona Talk like a pirate.\nBe brief."),
+            Some(DiscordCommand::Settings {
+                channel: true,
+                name: Some("persona".to_string()),
+                value: Some("Talk like a pirate.\nBe brief.".to_string())
+            })
+        );
+        assert!(matches!(
+            DiscordCommand::parse("/settings ambient"),
+            Some(DiscordCommand::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_prefixed() {
+        assert_eq!(
+            DiscordCommand::parse_prefixed("!lg reset", "!lg"),
+            Some(DiscordCommand::Reset)
+        );
+        assert_eq!(
+            DiscordCommand::parse_prefixed("  !LG model openai/gpt-4o", "!lg"),
+            Some(DiscordCommand::Model(Some("openai/gpt-4o".to_string())))
+        );
+        assert_eq!(
+            DiscordCommand::parse_prefixed("!lg\nstatus", "!lg"),
+            Some(DiscordCommand::Status)
+        );
+        // Unknown or missing names get the command list
+        assert!(matches!(
+            DiscordCommand::parse_prefixed("!lg dance", "!lg"),
+            Some(DiscordCommand::Invalid(usage)) if usage.contains("`!lg reset`")
+        ));
+        assert!(matches!(
+            DiscordCommand::parse_prefixed("!lg", "!lg"),
+            Some(DiscordCommand::Invalid(_))
+        ));
+        assert_eq!(DiscordCommand::parse_prefixed("!lgtm", "!lg"), None);
+        assert_eq!(DiscordCommand::parse_prefixed("/reset", "!lg"), None);
+        assert!(has_prefix("!lg status", "!lg"));
+        assert!(!has_prefix("hi !lg status", "!lg"));
+    }
+
+    #[test]
+    fn test_non_commands_pass_through() {
+        assert_eq!(DiscordCommand::parse("export this please"), None);
+        assert_eq!(DiscordCommand::parse("/shrug"), None);
+        assert_eq!(DiscordCommand::parse("!!!"), None);
+        assert_eq!(DiscordCommand::parse(""), None);
+    }
+}
@@ -0,0 +1,297 @@
+//! Buttons and select menus for agent choices
+//!
+//! The agent can offer choices by wrapping a JSON object in
+//! `[CHOICES]...[/CHOICES]`, e.g.
+//! `{"prompt": "Which format?", "options": ["PDF", "HTML"]}`. Up to five
+//! short options become buttons; more options (up to 25), options with a
+//! description or `"multiple": true` become a select menu. The choices are
+//! sent as a message of their own after the reply.
+//!
+//! When someone picks, the message is edited to show the choice and loses
+//! its components, and the picked labels are queued as that user's message,
+//! so the agent carries on with the answer. The labels are read back from
+//! the message in the interaction, so choices keep working after a restart.
+
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::LazyLock;
+
+/// Custom ID of the select menu; buttons add `:<index>`
+const CUSTOM_ID: &str = "lg:choice";
+
+/// Up to this many options are shown as buttons
+const MAX_BUTTONS: usize = 5;
+/// Options in a select menu
+const MAX_OPTIONS: usize = 25;
+
+// Discord component limits (characters)
+const BUTTON_LABEL_LIMIT: usize = 80;
+const OPTION_LABEL_LIMIT: usize = 100;
+const OPTION_DESCRIPTION_LIMIT: usize = 100;
+const PLACEHOLDER_LIMIT: usize = 150;
+
+static CHOICES_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)\[CHOICES\](.*?)\[/CHOICES\]").unwrap());
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ChoiceSpec {
+    /// Text of the message carrying the components
+    #[serde(default)]
+    pub prompt: Option<String>,
+    pub options: Vec<ChoiceOption>,
+    /// Shown in an empty select menu
+    #[serde(default)]
+    pub placeholder: Option<String>,
+    /// Allow picking several options (select menu)
+    #[serde(default)]
+    pub multiple: bool,
+}
+
+/// An option given either as its label or as `{"label", "description"}`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum ChoiceOption {
+    Label(String),
+    Detailed {
+        label: String,
+        #[serde(default)]
+        description: Option<String>,
+    },
+}
+
+impl ChoiceOption {
+    pub fn label(&self) -> &str {
+        match self {
+            ChoiceOption::Label(label) | ChoiceOption::Detailed { label, .. } => label,
+        }
+    }
+
+    fn description(&self) -> Option<&str> {
+        match self {
+            ChoiceOption::Label(_) => None,
+            ChoiceOption::Detailed { description, .. } => description.as_deref(),
+        }
+    }
+}
+
+impl ChoiceSpec {
+    fn options(&self) -> impl Iterator<Item = &ChoiceOption> {
+        self.options
+            .iter()
+            .filter(|o| !o.label().trim().is_empty())
+            .take(MAX_OPTIONS)
+    }
+
+    fn uses_buttons(&self) -> bool {
+        !self.multiple
+            && self.options().count() <= MAX_BUTTONS
+            && self.options().all(|o| {
+                o.description().is_none() && o.label().chars().count() <= BUTTON_LABEL_LIMIT
+            })
+    }
+
+    /// Discord action rows holding the buttons or the select menu
+    pub fn to_components(&self) -> Value {
+        let row: Vec<Value> = if self.uses_buttons() {
+            self.options()
+                .enumerate()
+                .map(|(i, option)| {
+                    json!({
+                        "type": 2,
+                        "style": 1,
+                        "label": option.label(),
+                        "custom_id": format!("{}:{}", CUSTOM_ID, i),
+                    })
+                })
+                .collect()
+        } else {
+            let options: Vec<Value> = self
+                .options()
+                .enumerate()
+                .map(|(i, option)| {
+                    let mut entry = json!({
+                        "label": truncate(option.label(), OPTION_LABEL_LIMIT),
+                        "value": i.to_string(),
+                    });
+                    if let Some(description) = option.description() {
+                        entry["description"] =
+                            json!(truncate(description, OPTION_DESCRIPTION_LIMIT));
+                    }
+                    entry
+                })
+                .collect();
+            let mut menu = json!({
+                "type": 3,
+                "custom_id": CUSTOM_ID,
+                "min_values": 1,
+                "max_values": if self.multiple { options.len() } else { 1 },
+                "options": options,
+            });
+            if let Some(ref placeholder) = self.placeholder {
+                menu["placeholder"] = json!(truncate(placeholder, PLACEHOLDER_LIMIT));
+            }
+            vec![menu]
+        };
+        json!([{"type": 1, "components": row}])
+    }
+
+    /// Numbered list used when components can't be sent
+    pub fn to_plain_text(&self) -> String {
+        let mut lines: Vec<String> = self.prompt.iter().cloned().collect();
+        lines.extend(
+            self.options()
+                .enumerate()
+                .map(|(i, option)| match option.description() {
+                    Some(description) => format!("{}. {} — {}", i + 1, option.label(), description),
+                    None => format!("{}. {}", i + 1, option.label()),
+                }),
+        );
+        lines.join("\n")
+    }
+}
+
+/// Extract the first valid `[CHOICES]{...}[/CHOICES]` block from a response.
+///
+/// Returns the remaining text and the choices. Blocks that aren't valid
+/// choice JSON, and any further choice blocks, are left in the text as
+/// plain text.
+pub fn extract_choices(response: &str) -> (String, Option<ChoiceSpec>) {
+    let mut choices: Option<ChoiceSpec> = None;
+
+    let text = CHOICES_RE
+        .replace_all(response, |caps: &regex::Captures| {
+            let body = strip_code_fence(caps[1].trim());
+            match serde_json::from_str::<ChoiceSpec>(body) {
+                Ok(spec) if spec.options().next().is_none() => spec.prompt.unwrap_or_default(),
+                Ok(spec) if choices.is_none() => {
+                    choices = Some(spec);
+                    String::new()
+                }
+                Ok(spec) => spec.to_plain_text(),
+                Err(_) => body.to_string(),
+            }
+        })
+        .trim()
+        .to_string();
+
+    (text, choices)
+}
+
+/// Labels picked in a component interaction on a choice message, or `None`
+/// if the component isn't one of ours. `components` are the message's.
+pub(super) fn selected_labels(
+    custom_id: &str,
+    values: &[String],
+    components: &Value,
+) -> Option<Vec<String>> {
+    if custom_id != CUSTOM_ID && !custom_id.starts_with(&format!("{}:", CUSTOM_ID)) {
+        return None;
+    }
+    let component = components
+        .as_array()?
+        .iter()
+        .filter_map(|row| row["components"].as_array())
+        .flatten()
+        .find(|c| c["custom_id"] == custom_id)?;
+
+    let labels: Vec<String> = match component["options"].as_array() {
+        // Select menu: values are option indexes
+        Some(options) => values
+            .iter()
+            .filter_map(|value| options.iter().find(|o| o["value"] == value.as_str()))
+            .filter_map(|o| o["label"].as_str().map(String::from))
+            .collect(),
+        None => component["label"]
+            .as_str()
+            .map(String::from)
+            .into_iter()
+            .collect(),
+    };
+    (!labels.is_empty()).then_some(labels)
+}
+
+/// Content of a choice message once `user_id` picked `labels`
+pub(super) fn answered_content(prompt: &str, labels: &[String], user_id: &str) -> String {
+    let picked = labels
+        .iter()
+        .map(|l| format!("**{}**", l))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let answer = format!("→ {} (<@{}>)", picked, user_id);
+    let prompt = truncate(prompt.trim(), 1900);
+    if prompt.is_empty() {
+        answer
+    } else {
+        format!("{}\n{}", prompt, answer)
+    }
+}
+
+/// Allow the JSON to be wrapped in a ```json fence inside the tags
+fn strip_code_fence(body: &str) -> &str {
+    body.strip_prefix("```json")
+        .or_else(|| body.strip_prefix("```"))
+        .and_then(|b| b.strip_suffix("```"))
+        .map(str::trim)
+        .unwrap_or(body)
+}
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        return s.to_string();
+    }
+    let mut out: String = s.chars().take(max_chars - 1).collect();
+    out.push('…');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choices_round_trip() {
+        let (text, choices) = extract_choices(
+            "Ready to export.\n[CHOICES]{\"prompt\": \"Which format?\", \"options\": [\"PDF\", \"HTML\"]}[/CHOICES]",
+        );
+        assert_eq!(text, "Ready to export.");
+        let choices = choices.unwrap();
+        let components = choices.to_components();
+        let buttons = &components[0]["components"];
+        assert_eq!(buttons[1]["type"], 2);
+        assert_eq!(buttons[1]["custom_id"], "lg:choice:1");
+        assert_eq!(
+            selected_labels("lg:choice:1", &[], &components),
+            Some(vec!["HTML".to_string()])
+        );
+        assert_eq!(selected_labels("other:1", &[], &components), None);
+        assert_eq!(
+            answered_content("Which format?", &["HTML".to_string()], "42"),
+            "Which format?\n→ **HTML** (<@42>)"
+        );
+
+        // Descriptions or several picks make a select menu
+        let (_, choices) = extract_choices(
+            "[CHOICES]\n```json\n{\"options\": [{\"label\": \"Tea\", \"description\": \"Green\"}, \"Coffee\", \"Water\"], \"multiple\": true}\n```\n[/CHOICES]",
+        );
+        let components = choices.unwrap().to_components();
+        let menu = &components[0]["components"][0];
+        assert_eq!(menu["type"], 3);
+        assert_eq!(menu["max_values"], 3);
+        assert_eq!(menu["options"][0]["description"], "Green");
+        assert_eq!(
+            selected_labels(
+                "lg:choice",
+                &["0".to_string(), "2".to_string()],
+                &components
+            ),
+            Some(vec!["Tea".to_string(), "Water".to_string()])
+        );
+
+        // Invalid blocks stay as text, a second block becomes a list
+        let (text, _) = extract_choices(
+            "[CHOICES]{\"options\": [\"A\"]}[/CHOICES] [CHOICES]{\"options\": [\"B\", \"C\"]}[/CHOICES] [CHOICES]oops[/CHOICES]",
+        );
+        assert_eq!(text, "1. B\n2. C oops");
+    }
+}
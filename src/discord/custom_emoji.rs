@@ -0,0 +1,126 @@
+//! Custom emoji and stickers
+//!
+//! Discord sends custom emoji as `<:name:id>` (`<a:name:id>` when animated)
+//! and stickers only as `sticker_items`, neither of which means anything to
+//! the agent. Incoming messages get readable descriptions instead: the
+//! emoji become `:name:`, a message of nothing but custom emoji becomes
+//! "[sent the :name: emoji]" and each sticker adds a "[sent the "name"
+//! sticker]" line. In the other direction `[REACT:name]` (or
+//! `[REACT::name:]`) may name one of the guild's custom emoji; it is looked
+//! up in the guild's emoji list and sent in the `name:id` form the reaction
+//! API needs.
+
+use regex::Regex;
+use serde::Deserialize;
+use std::sync::LazyLock;
+
+use super::rest::DiscordEmoji;
+
+static CUSTOM_EMOJI_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<a?:(\w+):(\d+)>").unwrap());
+
+/// `name:id`, the form the reaction API takes for custom emoji
+static EMOJI_ID_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\w+:\d+$").unwrap());
+
+static EMOJI_NAME_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\w+$").unwrap());
+
+/// A sticker attached to a message (`sticker_items` in MESSAGE_CREATE)
+#[derive(Debug, Clone, Deserialize)]
+pub(super) struct StickerItem {
+    #[allow(dead_code)]
+    pub id: String,
+    pub name: String,
+}
+
+/// The message text as the agent should see it
+pub(super) fn describe(content: &str, stickers: &[StickerItem]) -> String {
+    let names: Vec<String> = CUSTOM_EMOJI_RE
+        .captures_iter(content)
+        .map(|c| format!(":{}:", &c[1]))
+        .collect();
+
+    let mut parts = Vec::new();
+    if !names.is_empty() && CUSTOM_EMOJI_RE.replace_all(content, "").trim().is_empty() {
+        parts.push(format!("[sent the {} emoji]", names.join(" ")));
+    } else {
+        let text = CUSTOM_EMOJI_RE.replace_all(content, ":$1:");
+        if !text.trim().is_empty() {
+            parts.push(text.trim().to_string());
+        }
+    }
+    for sticker in stickers {
+        parts.push(format!("[sent the \"{}\" sticker]", sticker.name));
+    }
+    parts.join("\n")
+}
+
+/// The custom emoji name a `[REACT:]` emoji refers to, if it is a bare name
+/// (`pepela` or `:pepela:`) that needs the guild's emoji list
+pub(super) fn lookup_name(emoji: &str) -> Option<&str> {
+    let name = emoji.trim().trim_matches(':');
+    EMOJI_NAME_RE.is_match(name).then_some(name)
+}
+
+/// The reaction API form of a `[REACT:]` emoji. Unicode emoji pass through,
+/// `<:name:id>` becomes `name:id` and names are looked up in the guild's
+/// emoji (exact match first). None for a name the guild doesn't have.
+pub(super) fn resolve_reaction(emoji: &str, guild_emojis: &[DiscordEmoji]) -> Option<String> {
+    let emoji = emoji.trim();
+    if let Some(c) = CUSTOM_EMOJI_RE.captures(emoji)
+        && c[0].len() == emoji.len()
+    {
+        return Some(format!("{}:{}", &c[1], &c[2]));
+    }
+    if EMOJI_ID_RE.is_match(emoji) {
+        return Some(emoji.to_string());
+    }
+    let Some(name) = lookup_name(emoji) else {
+        return Some(emoji.to_string());
+    };
+
+    let named = |matches: &dyn Fn(&str) -> bool| {
+        guild_emojis.iter().find_map(|e| match (&e.id, &e.name) {
+            (Some(id), Some(n)) if matches(n) => Some(format!("{}:{}", n, id)),
+            _ => None,
+        })
+    };
+    named(&|n| n == name).or_else(|| named(&|n| n.eq_ignore_ascii_case(name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_and_resolve() {
+        assert_eq!(
+            describe("<:pepela:123> <a:dance:456>", &[]),
+            "[sent the :pepela: :dance: emoji]"
+        );
+        assert_eq!(describe("nice <:pepela:123>!", &[]), "nice :pepela:!");
+        let wave = StickerItem {
+            id: "9".to_string(),
+            name: "Wave".to_string(),
+        };
+        assert_eq!(
+            describe("", std::slice::from_ref(&wave)),
+            "[sent the \"Wave\" sticker]"
+        );
+        assert_eq!(describe("hi", &[wave]), "hi\n[sent the \"Wave\" sticker]");
+
+        let guild = vec![DiscordEmoji {
+            id: Some("123".to_string()),
+            name: Some("PepeLa".to_string()),
+            animated: false,
+        }];
+        assert_eq!(resolve_reaction("👍", &guild).as_deref(), Some("👍"));
+        assert_eq!(resolve_reaction("<:x:7>", &guild).as_deref(), Some("x:7"));
+        assert_eq!(resolve_reaction("x:7", &guild).as_deref(), Some("x:7"));
+        assert_eq!(
+            resolve_reaction(":pepela:", &guild).as_deref(),
+            Some("PepeLa:123")
+        );
+        assert_eq!(resolve_reaction("missing", &guild), None);
+        assert!(lookup_name("👍").is_none());
+    }
+}
@@ -0,0 +1,234 @@
+//! Edit and delete tracking for Discord messages
+//!
+//! MESSAGE_UPDATE / MESSAGE_DELETE events can arrive while a message is still
+//! waiting in the batch queue, while the agent is generating a reply, or
+//! after the reply was sent. The tracker applies edits to queued messages,
+//! drops deleted ones, lets the processor skip replies to messages deleted
+//! mid-generation, and (optionally) collects notes about edits to messages
+//! the agent already answered.
+
+use std::collections::{HashMap, VecDeque};
+
+use super::QueuedMessage;
+
+/// Number of processed messages remembered for late edits/deletes
+const PROCESSED_HISTORY: usize = 200;
+
+/// Maximum characters of old/new content quoted in an edit note
+const NOTE_SNIPPET_CHARS: usize = 300;
+
+#[derive(Debug, Clone, PartialEq)]
+enum QueuedState {
+    Unchanged,
+    Edited(String),
+    Deleted,
+}
+
+struct ProcessedMessage {
+    message_id: String,
+    channel_id: String,
+    author_name: String,
+    content: String,
+    deleted: bool,
+}
+
+pub(super) struct MessageTracker {
+    /// Whether to tell the agent about edits to messages it already saw
+    track_edits: bool,
+    /// Messages enqueued but not yet handed to the agent
+    queued: HashMap<String, QueuedState>,
+    /// Recently processed messages (oldest first)
+    processed: VecDeque<ProcessedMessage>,
+    /// Edit notes to prepend to the channel's next batch (channel_id → notes)
+    notes: HashMap<String, Vec<String>>,
+}
+
+impl MessageTracker {
+    pub fn new(track_edits: bool) -> Self {
+        Self {
+            track_edits,
+            queued: HashMap::new(),
+            processed: VecDeque::new(),
+            notes: HashMap::new(),
+        }
+    }
+
+    /// Record that a message was put on the batch queue
+    pub fn mark_queued(&mut self, message_id: &str) {
+        self.queued
+            .insert(message_id.to_string(), QueuedState::Unchanged);
+    }
+
+    /// Forget a message that could not be enqueued
+    pub fn forget(&mut self, message_id: &str) {
+        self.queued.remove(message_id);
+    }
+
+    /// Record a MESSAGE_UPDATE with the new (mention-stripped) content
+    pub fn record_edit(&mut self, message_id: &str, content: String) {
+        if let Some(state) = self.queued.get_mut(message_id) {
+            if *state != QueuedState::Deleted {
+                *state = QueuedState::Edited(content);
+            }
+            return;
+        }
+
+        let track_edits = self.track_edits;
+        let Some(msg) = self
+            .processed
+            .iter_mut()
+            .find(|m| m.message_id == message_id)
+        else {
+            return;
+        };
+
+        if msg.deleted || msg.content == content {
+            return;
+        }
+
+        if track_edits {
+            let note = format_edit_note(&msg.author_name, &msg.content, &content);
+            self.notes
+                .entry(msg.channel_id.clone())
+                .or_default()
+                .push(note);
+        }
+        msg.content = content;
+    }
+
+    /// Record a MESSAGE_DELETE
+    pub fn record_delete(&mut self, message_id: &str) {
+        if let Some(state) = self.queued.get_mut(message_id) {
+            *state = QueuedState::Deleted;
+        } else if let Some(msg) = self
+            .processed
+            .iter_mut()
+            .find(|m| m.message_id == message_id)
+        {
+            msg.deleted = true;
+        }
+    }
+
+    /// Apply pending edits to a collected batch and drop deleted messages.
+    /// The returned messages are considered processed from now on.
+    pub fn take_batch(&mut self, batch: Vec<QueuedMessage>) -> Vec<QueuedMessage> {
+        let mut result = Vec::with_capacity(batch.len());
+
+        for mut msg in batch {
+            match self.queued.remove(&msg.message_id) {
+                Some(QueuedState::Deleted) => continue,
+                Some(QueuedState::Edited(content)) => msg.content = content,
+                Some(QueuedState::Unchanged) | None => {}
+            }
+
+            if msg.content.is_empty() && msg.image_urls.is_empty() {
+                continue;
+            }
+
+            self.processed.push_back(ProcessedMessage {
+                message_id: msg.message_id.clone(),
+                channel_id: msg.channel_id.clone(),
+                author_name: msg.author_name.clone(),
+                content: msg.content.clone(),
+                deleted: false,
+            });
+            result.push(msg);
+        }
+
+        while self.processed.len() > PROCESSED_HISTORY {
+            self.processed.pop_front();
+        }
+
+        result
+    }
+
+    /// Whether a processed message has since been deleted
+    pub fn is_deleted(&self, message_id: &str) -> bool {
+        self.processed
+            .iter()
+            .any(|m| m.message_id == message_id && m.deleted)
+    }
+
+    /// Take the edit notes collected for a channel
+    pub fn take_notes(&mut self, channel_id: &str) -> Vec<String> {
+        self.notes.remove(channel_id).unwrap_or_default()
+    }
+}
+
+/// Describe an edit with the old content struck through
+fn format_edit_note(author: &str, old: &str, new: &str) -> String {
+    format!(
+        "[{} edited an earlier message] ~~{}~~ → {}",
+        author,
+        crate::utils::safe_truncate(old, NOTE_SNIPPET_CHARS),
+        crate::utils::safe_truncate(new, NOTE_SNIPPET_CHARS)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queued(id: &str, content: &str) -> QueuedMessage {
+        QueuedMessage {
+            channel_id: "c1".to_string(),
+            guild_id: None,
+            message_id: id.to_string(),
+            author_id: "100".to_string(),
+            author_name: "alice".to_string(),
+            author_roles: Vec::new(),
+            content: content.to_string(),
+            image_urls: Vec::new(),
+            mention_count: 0,
+            addressed: true,
+        }
+    }
+
+    #[test]
+    fn test_edits_and_deletes_in_queue() {
+        let mut tracker = MessageTracker::new(false);
+        tracker.mark_queued("1");
+        tracker.mark_queued("2");
+        tracker.record_edit("1", "fixed typo".to_string());
+        tracker.record_delete("2");
+
+        let batch = tracker.take_batch(vec![queued("1", "fixd typo"), queued("2", "oops")]);
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].content, "fixed typo");
+    }
+
+    #[test]
+    fn test_delete_after_processing() {
+        let mut tracker = MessageTracker::new(false);
+        tracker.mark_queued("1");
+        tracker.take_batch(vec![queued("1", "hello")]);
+        assert!(!tracker.is_deleted("1"));
+
+        tracker.record_delete("1");
+        assert!(tracker.is_deleted("1"));
+    }
+
+    #[test]
+    fn test_edit_notes_for_processed_messages() {
+        let mut tracker = MessageTracker::new(true);
+        tracker.mark_queued("1");
+        tracker.take_batch(vec![queued("1", "meet at 5")]);
+        tracker.record_edit("1", "meet at 6".to_string());
+
+        let notes = tracker.take_notes("c1");
+        assert_eq!(
+            notes,
+            vec!["[alice edited an earlier message] ~~meet at 5~~ → meet at 6"]
+        );
+        assert!(tracker.take_notes("c1").is_empty());
+    }
+
+    #[test]
+    fn test_edit_notes_disabled() {
+        let mut tracker = MessageTracker::new(false);
+        tracker.mark_queued("1");
+        tracker.take_batch(vec![queued("1", "meet at 5")]);
+        tracker.record_edit("1", "meet at 6".to_string());
+        assert!(tracker.take_notes("c1").is_empty());
+    }
+}
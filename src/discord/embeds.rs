@@ -0,0 +1,258 @@
+//! Rich embed responses
+//!
+//! The agent can wrap a JSON object in `[EMBED]...[/EMBED]` to have it sent
+//! as a Discord embed (title, description, fields, footer, color). Blocks
+//! that fail to parse, or that Discord rejects, fall back to plain text so
+//! nothing the agent wrote is lost.
+
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+/// Discord allows at most 10 embeds per message
+pub const MAX_EMBEDS: usize = 10;
+
+// Discord embed limits (characters)
+const TITLE_LIMIT: usize = 256;
+const DESCRIPTION_LIMIT: usize = 4096;
+const FIELD_NAME_LIMIT: usize = 256;
+const FIELD_VALUE_LIMIT: usize = 1024;
+const FIELD_COUNT_LIMIT: usize = 25;
+const FOOTER_LIMIT: usize = 2048;
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct EmbedSpec {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub color: Option<EmbedColor>,
+    #[serde(default)]
+    pub fields: Vec<EmbedField>,
+    #[serde(default)]
+    pub footer: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EmbedField {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub inline: bool,
+}
+
+/// Embed color given either as an integer or a "#rrggbb" string
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum EmbedColor {
+    Int(u32),
+    Hex(String),
+}
+
+impl EmbedColor {
+    pub fn value(&self) -> Option<u32> {
+        match self {
+            EmbedColor::Int(v) => Some(*v & 0xFF_FFFF),
+            EmbedColor::Hex(s) => u32::from_str_radix(s.trim().trim_start_matches('#'), 16).ok(),
+        }
+    }
+}
+
+impl EmbedSpec {
+    fn is_empty(&self) -> bool {
+        self.title.is_none() && self.description.is_none() && self.fields.is_empty()
+    }
+
+    /// Convert to a Discord embed object, truncating fields to Discord's limits
+    pub fn to_discord_json(&self) -> Value {
+        let mut embed = serde_json::Map::new();
+
+        if let Some(ref title) = self.title {
+            embed.insert("title".into(), json!(truncate(title, TITLE_LIMIT)));
+        }
+        if let Some(ref description) = self.description {
+            embed.insert(
+                "description".into(),
+                json!(truncate(description, DESCRIPTION_LIMIT)),
+            );
+        }
+        if let Some(ref url) = self.url
+            && url.starts_with("https://")
+        {
+            embed.insert("url".into(), json!(url));
+        }
+        if let Some(color) = self.color.as_ref().and_then(EmbedColor::value) {
+            embed.insert("color".into(), json!(color));
+        }
+        if !self.fields.is_empty() {
+            let fields: Vec<Value> = self
+                .fields
+                .iter()
+                .take(FIELD_COUNT_LIMIT)
+                .map(|f| {
+                    json!({
+                        "name": truncate(&f.name, FIELD_NAME_LIMIT),
+                        "value": truncate(&f.value, FIELD_VALUE_LIMIT),
+                        "inline": f.inline,
+                    })
+                })
+                .collect();
+            embed.insert("fields".into(), Value::Array(fields));
+        }
+        if let Some(ref footer) = self.footer {
+            embed.insert(
+                "footer".into(),
+                json!({"text": truncate(footer, FOOTER_LIMIT)}),
+            );
+        }
+
+        Value::Object(embed)
+    }
+
+    /// Plain-text rendering used when embeds can't be sent
+    pub fn to_plain_text(&self) -> String {
+        let mut lines = Vec::new();
+
+        if let Some(ref title) = self.title {
+            lines.push(format!("**{}**", title));
+        }
+        if let Some(ref description) = self.description {
+            lines.push(description.clone());
+        }
+        for field in &self.fields {
+            lines.push(format!("**{}**: {}", field.name, field.value));
+        }
+        if let Some(ref url) = self.url {
+            lines.push(format!("<{}>", url));
+        }
+        if let Some(ref footer) = self.footer {
+            lines.push(format!("_{}_", footer));
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// Extract `[EMBED]{...}[/EMBED]` blocks from a response.
+///
+/// Returns the remaining text and the parsed embeds. Blocks that aren't
+/// valid embed JSON are left in the text (without the tags) as a fallback.
+pub fn extract_embeds(response: &str) -> (String, Vec<EmbedSpec>) {
+    let re = Regex::new(r"(?s)\[EMBED\](.*?)\[/EMBED\]").unwrap();
+    let mut embeds = Vec::new();
+
+    let text = re
+        .replace_all(response, |caps: &regex::Captures| {
+            let body = strip_code_fence(caps[1].trim());
+            match serde_json::from_str::<EmbedSpec>(body) {
+                Ok(spec) if !spec.is_empty() && embeds.len() < MAX_EMBEDS => {
+                    embeds.push(spec);
+                    String::new()
+                }
+                Ok(spec) if !spec.is_empty() => spec.to_plain_text(),
+                _ => body.to_string(),
+            }
+        })
+        .trim()
+        .to_string();
+
+    (text, embeds)
+}
+
+/// Plain-text fallback for a message whose embeds were rejected
+pub fn fallback_text(text: &str, embeds: &[EmbedSpec]) -> String {
+    std::iter::once(text.to_string())
+        .chain(embeds.iter().map(EmbedSpec::to_plain_text))
+        .filter(|s| !s.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Allow the JSON to be wrapped in a ```json fence inside the tags
+fn strip_code_fence(body: &str) -> &str {
+    body.strip_prefix("```json")
+        .or_else(|| body.strip_prefix("```"))
+        .and_then(|b| b.strip_suffix("```"))
+        .map(str::trim)
+        .unwrap_or(body)
+}
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        return s.to_string();
+    }
+    let mut out: String = s.chars().take(max_chars - 1).collect();
+    out.push('…');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_embeds() {
+        let response = r##"Here you go.
+[EMBED]{"title": "Weather", "color": "#3498db", "fields": [{"name": "Tokyo", "value": "12°C", "inline": true}], "footer": "via wttr.in"}[/EMBED]"##;
+        let (text, embeds) = extract_embeds(response);
+        assert_eq!(text, "Here you go.");
+        assert_eq!(embeds.len(), 1);
+
+        let json = embeds[0].to_discord_json();
+        assert_eq!(json["title"], "Weather");
+        assert_eq!(json["color"], 0x3498db);
+        assert_eq!(json["fields"][0]["inline"], true);
+        assert_eq!(json["footer"]["text"], "via wttr.in");
+    }
+
+    #[test]
+    fn test_invalid_embed_falls_back_to_text() {
+        let (text, embeds) = extract_embeds("[EMBED]not json at all[/EMBED]");
+        assert!(embeds.is_empty());
+        assert_eq!(text, "not json at all");
+    }
+
+    #[test]
+    fn test_fenced_embed_json() {
+        let (_, embeds) = extract_embeds("[EMBED]\n```json\n{\"title\": \"Hi\"}\n```\n[/EMBED]");
+        assert_eq!(embeds[0].title.as_deref(), Some("Hi"));
+    }
+
+    #[test]
+    fn test_limits_are_enforced() {
+        let spec = EmbedSpec {
+            title: Some("t".repeat(300)),
+            fields: (0..30)
+                .map(|i| EmbedField {
+                    name: i.to_string(),
+                    value: "v".to_string(),
+                    inline: false,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let json = spec.to_discord_json();
+        assert_eq!(json["title"].as_str().unwrap().chars().count(), TITLE_LIMIT);
+        assert_eq!(json["fields"].as_array().unwrap().len(), FIELD_COUNT_LIMIT);
+    }
+
+    #[test]
+    fn test_fallback_text() {
+        let spec = EmbedSpec {
+            title: Some("Results".to_string()),
+            fields: vec![EmbedField {
+                name: "a.md".to_string(),
+                value: "snippet".to_string(),
+                inline: false,
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            fallback_text("Found:", &[spec]),
+            "Found:\n\n**Results**\n**a.md**: snippet"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Emoji detection for emoji-only replies
+//!
+//! Replies that consist only of emoji are sent as reactions (unless the guild
+//! disables it). Detection works on grapheme clusters so ZWJ sequences, skin
+//! tones, flags and keycaps count as a single emoji, while short non-ASCII
+//! text such as a one-word Japanese reply does not.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+const ZWJ: char = '\u{200D}';
+const VARIATION_SELECTOR_16: char = '\u{FE0F}';
+const COMBINING_KEYCAP: char = '\u{20E3}';
+
+/// Return the emoji in `text` if it consists only of emoji (and whitespace)
+pub fn emoji_only(text: &str) -> Option<Vec<&str>> {
+    let graphemes: Vec<&str> = text
+        .graphemes(true)
+        .filter(|g| !g.chars().all(char::is_whitespace))
+        .collect();
+
+    if graphemes.is_empty() || !graphemes.iter().all(|g| is_emoji_grapheme(g)) {
+        return None;
+    }
+
+    Some(graphemes)
+}
+
+/// Whether a single grapheme cluster is an emoji
+pub fn is_emoji_grapheme(grapheme: &str) -> bool {
+    let mut chars = grapheme.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    let rest: Vec<char> = chars.collect();
+
+    // Keycaps: 0-9, # or * followed by (optional VS16 and) U+20E3
+    if first.is_ascii_digit() || first == '#' || first == '*' {
+        return rest.last() == Some(&COMBINING_KEYCAP)
+            && rest[..rest.len() - 1]
+                .iter()
+                .all(|&c| c == VARIATION_SELECTOR_16);
+    }
+
+    // Flags: a pair of regional indicators
+    if is_regional_indicator(first) {
+        return rest.len() == 1 && is_regional_indicator(rest[0]);
+    }
+
+    if !is_pictographic(first) {
+        return false;
+    }
+
+    // Text-presentation pictographs (e.g. © or ☺) need VS16 unless they
+    // default to emoji presentation
+    if !has_emoji_presentation(first) && !rest.contains(&VARIATION_SELECTOR_16) {
+        return false;
+    }
+
+    rest.iter().all(|&c| {
+        c == ZWJ || c == VARIATION_SELECTOR_16 || is_skin_tone(c) || is_tag(c) || is_pictographic(c)
+    })
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+fn is_skin_tone(c: char) -> bool {
+    ('\u{1F3FB}'..='\u{1F3FF}').contains(&c)
+}
+
+/// Tag characters used in subdivision flags (e.g. 🏴󠁧󠁢󠁳󠁣󠁴󠁿)
+fn is_tag(c: char) -> bool {
+    ('\u{E0020}'..='\u{E007F}').contains(&c)
+}
+
+/// Approximation of the Extended_Pictographic property
+fn is_pictographic(c: char) -> bool {
+    matches!(c,
+        '\u{00A9}' | '\u{00AE}' | '\u{203C}' | '\u{2049}' | '\u{2122}' | '\u{2139}'
+        | '\u{2194}'..='\u{2199}'
+        | '\u{21A9}'..='\u{21AA}'
+        | '\u{231A}'..='\u{231B}'
+        | '\u{2328}' | '\u{23CF}'
+        | '\u{23E9}'..='\u{23F3}'
+        | '\u{23F8}'..='\u{23FA}'
+        | '\u{24C2}'
+        | '\u{25AA}'..='\u{25AB}'
+        | '\u{25B6}' | '\u{25C0}'
+        | '\u{25FB}'..='\u{25FE}'
+        | '\u{2600}'..='\u{27BF}'
+        | '\u{2934}'..='\u{2935}'
+        | '\u{2B05}'..='\u{2B07}'
+        | '\u{2B1B}'..='\u{2B1C}'
+        | '\u{2B50}' | '\u{2B55}'
+        | '\u{3030}' | '\u{303D}' | '\u{3297}' | '\u{3299}'
+        | '\u{1F000}'..='\u{1F0FF}'
+        | '\u{1F10D}'..='\u{1F10F}'
+        | '\u{1F12F}'
+        | '\u{1F16C}'..='\u{1F171}'
+        | '\u{1F17E}'..='\u{1F17F}'
+        | '\u{1F18E}'
+        | '\u{1F191}'..='\u{1F19A}'
+        | '\u{1F1AD}'..='\u{1F1E5}'
+        | '\u{1F201}'..='\u{1F20F}'
+        | '\u{1F21A}' | '\u{1F22F}'
+        | '\u{1F232}'..='\u{1F23A}'
+        | '\u{1F23C}'..='\u{1F23F}'
+        | '\u{1F249}'..='\u{1F3FA}'
+        | '\u{1F400}'..='\u{1F53D}'
+        | '\u{1F546}'..='\u{1F64F}'
+        | '\u{1F680}'..='\u{1F6FF}'
+        | '\u{1F774}'..='\u{1F77F}'
+        | '\u{1F7D5}'..='\u{1F7FF}'
+        | '\u{1F80C}'..='\u{1F80F}'
+        | '\u{1F848}'..='\u{1F84F}'
+        | '\u{1F85A}'..='\u{1F85F}'
+        | '\u{1F888}'..='\u{1F88F}'
+        | '\u{1F8AE}'..='\u{1F8FF}'
+        | '\u{1F90C}'..='\u{1F93A}'
+        | '\u{1F93C}'..='\u{1F945}'
+        | '\u{1F947}'..='\u{1FAFF}'
+        | '\u{1FC00}'..='\u{1FFFD}'
+    )
+}
+
+/// Pictographs that render as emoji without VS16. Everything in the
+/// supplementary planes does; in the BMP only a known subset does.
+fn has_emoji_presentation(c: char) -> bool {
+    c >= '\u{1F000}'
+        || matches!(c,
+            '\u{231A}'..='\u{231B}'
+            | '\u{23E9}'..='\u{23EC}'
+            | '\u{23F0}' | '\u{23F3}'
+            | '\u{25FD}'..='\u{25FE}'
+            | '\u{2614}'..='\u{2615}'
+            | '\u{2648}'..='\u{2653}'
+            | '\u{267F}' | '\u{2693}' | '\u{26A1}'
+            | '\u{26AA}'..='\u{26AB}'
+            | '\u{26BD}'..='\u{26BE}'
+            | '\u{26C4}'..='\u{26C5}'
+            | '\u{26CE}' | '\u{26D4}' | '\u{26EA}'
+            | '\u{26F2}'..='\u{26F3}'
+            | '\u{26F5}' | '\u{26FA}' | '\u{26FD}' | '\u{2705}'
+            | '\u{270A}'..='\u{270B}'
+            | '\u{2728}' | '\u{274C}' | '\u{274E}'
+            | '\u{2753}'..='\u{2755}'
+            | '\u{2757}'
+            | '\u{2795}'..='\u{2797}'
+            | '\u{27B0}' | '\u{27BF}'
+            | '\u{2B1B}'..='\u{2B1C}'
+            | '\u{2B50}' | '\u{2B55}'
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emoji_only() {
+        assert_eq!(emoji_only("😊"), Some(vec!["😊"]));
+        assert_eq!(emoji_only(" ☁\u{fe0f} ✨ "), Some(vec!["☁\u{fe0f}", "✨"]));
+        // ZWJ family, skin tone, flag and keycap are single emoji
+        assert_eq!(emoji_only("👨\u{200d}👩\u{200d}👧").unwrap().len(), 1);
+        assert_eq!(emoji_only("👍🏽").unwrap().len(), 1);
+        assert_eq!(emoji_only("🇯🇵").unwrap().len(), 1);
+        assert_eq!(emoji_only("1\u{fe0f}\u{20e3}").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_short_non_ascii_text_is_not_emoji() {
+        assert_eq!(emoji_only("はい"), None);
+        assert_eq!(emoji_only("了解"), None);
+        assert_eq!(emoji_only("ok 👍"), None);
+        assert_eq!(emoji_only("café"), None);
+        assert_eq!(emoji_only("→"), None);
+        assert_eq!(emoji_only(""), None);
+    }
+
+    #[test]
+    fn test_text_presentation_needs_variation_selector() {
+        assert_eq!(emoji_only("©"), None);
+        assert!(emoji_only("©\u{fe0f}").is_some());
+        assert!(emoji_only("⚡").is_some());
+    }
+}
@@ -0,0 +1,388 @@
+//! In-process fake Discord for end-to-end tests
+//!
+//! [`FakeDiscord`] serves a gateway WebSocket and the REST API on local
+//! ports, so a real [`DiscordBot`](super::DiscordBot) pointed at it with
+//! `with_endpoints` runs its whole event loop without a token: HELLO,
+//! IDENTIFY/RESUME and heartbeats are answered, events are dispatched once
+//! the session is ready, and every REST call is recorded and answered with
+//! a plausible response (created messages get fresh ids, lists are empty,
+//! everything else is 204). A scenario is a list of [`Step`]s: dispatch
+//! events, then wait for the calls the bot should make in response.
+
+use anyhow::{Result, bail};
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Json, Response};
+use futures::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{Mutex, mpsc};
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::debug;
+
+/// The bot user the fake logs in as
+pub(super) const BOT_ID: &str = "100";
+const BOT_NAME: &str = "localgpt";
+
+/// Prefix of REST paths, stripped from recorded calls
+const API_PREFIX: &str = "/api/v10";
+
+/// How long [`FakeDiscord::wait_for`] waits for a call
+const WAIT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A REST call made by the bot (path without the API prefix or query)
+#[derive(Debug, Clone)]
+pub(super) struct Call {
+    pub method: String,
+    pub path: String,
+    /// JSON body, Null when empty
+    pub body: Value,
+}
+
+/// One step of a scripted scenario
+pub(super) enum Step {
+    /// Dispatch a gateway event (sent once the session is ready)
+    Dispatch(&'static str, Value),
+    /// Wait for a REST call whose body contains every field of `body`
+    Expect {
+        method: &'static str,
+        path: String,
+        body: Value,
+    },
+}
+
+struct Shared {
+    /// Calls so far, and whether `wait_for` already returned them
+    calls: std::sync::Mutex<Vec<(Call, bool)>>,
+    /// Events waiting for the gateway session (one session at a time)
+    events: Mutex<mpsc::UnboundedReceiver<(String, Value)>>,
+    next_id: AtomicU64,
+}
+
+pub(super) struct FakeDiscord {
+    /// Gateway host for `DiscordBot::with_endpoints`
+    pub gateway_url: String,
+    /// REST base URL for `DiscordBot::with_endpoints`
+    pub api_url: String,
+    events: mpsc::UnboundedSender<(String, Value)>,
+    shared: Arc<Shared>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl FakeDiscord {
+    /// Start the gateway and REST servers on free local ports
+    pub async fn start() -> Result<Self> {
+        let (events, events_rx) = mpsc::unbounded_channel();
+        let shared = Arc::new(Shared {
+            calls: Default::default(),
+            events: Mutex::new(events_rx),
+            next_id: AtomicU64::new(1000),
+        });
+
+        let gateway = TcpListener::bind("127.0.0.1:0").await?;
+        let gateway_url = format!("ws://{}", gateway.local_addr()?);
+        let rest = TcpListener::bind("127.0.0.1:0").await?;
+        let api_url = format!("http://{}{}", rest.local_addr()?, API_PREFIX);
+
+        let app = Router::new()
+            .fallback(handle_rest)
+            .with_state(Arc::clone(&shared));
+        let tasks = vec![
+            tokio::spawn(serve_gateway(
+                gateway,
+                gateway_url.clone(),
+                Arc::clone(&shared),
+            )),
+            tokio::spawn(async move {
+                let _ = axum::serve(rest, app).await;
+            }),
+        ];
+
+        Ok(Self {
+            gateway_url,
+            api_url,
+            events,
+            shared,
+            tasks,
+        })
+    }
+
+    /// Queue a gateway event for the bot
+    pub fn dispatch(&self, event: &str, data: Value) {
+        let _ = self.events.send((event.to_string(), data));
+    }
+
+    /// Every REST call so far
+    pub fn calls(&self) -> Vec<Call> {
+        let calls = self.shared.calls.lock().unwrap();
+        calls.iter().map(|(call, _)| call.clone()).collect()
+    }
+
+    /// The first call to `method path` not returned before, waiting for it
+    /// if it hasn't been made yet
+    pub async fn wait_for(&self, method: &str, path: &str) -> Result<Call> {
+        let deadline = Instant::now() + WAIT_TIMEOUT;
+        loop {
+            {
+                let mut calls = self.shared.calls.lock().unwrap();
+                let found = calls
+                    .iter_mut()
+                    .find(|(call, taken)| !*taken && call.method == method && call.path == path);
+                if let Some((call, taken)) = found {
+                    *taken = true;
+                    return Ok(call.clone());
+                }
+            }
+            if Instant::now() >= deadline {
+                let made: Vec<String> = self
+                    .calls()
+                    .iter()
+                    .map(|c| format!("{} {}", c.method, c.path))
+                    .collect();
+                bail!("no {} {} call; calls made: {:?}", method, path, made);
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    /// Play a scripted scenario
+    pub async fn play(&self, steps: Vec<Step>) -> Result<()> {
+        for step in steps {
+            match step {
+                Step::Dispatch(event, data) => self.dispatch(event, data),
+                Step::Expect { method, path, body } => {
+                    let call = self.wait_for(method, &path).await?;
+                    if !contains(&call.body, &body) {
+                        bail!("{} {} sent {}, expected {}", method, path, call.body, body);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for FakeDiscord {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// A MESSAGE_CREATE payload from a user (a DM unless given a guild)
+pub(super) fn message(id: &str, channel_id: &str, author_id: &str, content: &str) -> Value {
+    json!({
+        "id": id,
+        "channel_id": channel_id,
+        "content": content,
+        "author": {"id": author_id, "username": format!("user{}", author_id)},
+        "mentions": [],
+        "attachments": [],
+    })
+}
+
+/// Whether `actual` has every field of `expected` (recursively for objects)
+fn contains(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => expected
+            .iter()
+            .all(|(key, value)| actual.get(key).is_some_and(|a| contains(a, value))),
+        _ => actual == expected,
+    }
+}
+
+// ─── REST ───────────────────────────────────────────────────────────
+
+async fn handle_rest(
+    State(shared): State<Arc<Shared>>,
+    method: Method,
+    uri: Uri,
+    body: Bytes,
+) -> Response {
+    let path = uri
+        .path()
+        .strip_prefix(API_PREFIX)
+        .unwrap_or(uri.path())
+        .to_string();
+    let id = shared.next_id.fetch_add(1, Ordering::Relaxed).to_string();
+    let response = respond(method.as_str(), &path, &id);
+    let call = Call {
+        method: method.to_string(),
+        path,
+        body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+    };
+    shared.calls.lock().unwrap().push((call, false));
+
+    match response {
+        Some(json) => Json(json).into_response(),
+        None => StatusCode::NO_CONTENT.into_response(),
+    }
+}
+
+/// The response body for a call, None for 204
+fn respond(method: &str, path: &str, id: &str) -> Option<Value> {
+    let parts: Vec<&str> = path.split('/').filter(|p| !p.is_empty()).collect();
+    match (method, parts.as_slice()) {
+        ("POST", ["channels", channel_id, "messages"]) => {
+            Some(json!({"id": id, "channel_id": channel_id}))
+        }
+        ("GET", ["channels", _, "messages" | "pins"])
+        | ("GET", ["guilds", _, "channels" | "emojis"]) => Some(json!([])),
+        ("GET", ["channels", channel_id]) => {
+            Some(json!({"id": channel_id, "type": 0, "name": "general"}))
+        }
+        ("POST", ["users", "@me", "channels"]) => Some(json!({"id": id, "type": 1})),
+        ("GET", ["users", "@me"]) => Some(json!({"id": BOT_ID, "username": BOT_NAME, "bot": true})),
+        // GATEWAY_MESSAGE_CONTENT (1 << 19) enabled
+        ("GET", ["applications", "@me"]) => {
+            Some(json!({"id": BOT_ID, "name": BOT_NAME, "flags": 1 << 19}))
+        }
+        _ => None,
+    }
+}
+
+// ─── Gateway ────────────────────────────────────────────────────────
+
+async fn serve_gateway(listener: TcpListener, url: String, shared: Arc<Shared>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        let url = url.clone();
+        let shared = Arc::clone(&shared);
+        tokio::spawn(async move {
+            if let Err(e) = gateway_session(stream, &url, &shared).await {
+                debug!("Fake gateway session ended: {}", e);
+            }
+        });
+    }
+}
+
+async fn gateway_session(stream: TcpStream, url: &str, shared: &Shared) -> Result<()> {
+    let ws = tokio_tungstenite::accept_async(stream).await?;
+    let (mut sink, mut frames) = ws.split();
+    let text = |payload: Value| Message::Text(payload.to_string());
+
+    sink.send(text(json!({"op": 10, "d": {"heartbeat_interval": 45000}})))
+        .await?;
+
+    // Held for the whole session so a reconnect picks up where it left off
+    let mut events = shared.events.lock().await;
+    let mut seq = 0u64;
+    let mut ready = false;
+    loop {
+        tokio::select! {
+            frame = frames.next() => {
+                let Some(frame) = frame else { break };
+                let Message::Text(text) = frame? else { continue };
+                let payload: Value = serde_json::from_str(&text)?;
+                match payload["op"].as_u64() {
+                    Some(1) => sink.send(text(json!({"op": 11}))).await?,
+                    Some(2) => {
+                        seq += 1;
+                        let ready_data = json!({
+                            "v": 10,
+                            "session_id": "fake-session",
+                            "resume_gateway_url": url,
+                            "user": {"id": BOT_ID, "username": BOT_NAME, "bot": true},
+                            "guilds": [],
+                        });
+                        sink.send(text(json!({"op": 0, "t": "READY", "s": seq, "d": ready_data})))
+                            .await?;
+                        ready = true;
+                    }
+                    Some(6) => {
+                        seq += 1;
+                        sink.send(text(json!({"op": 0, "t": "RESUMED", "s": seq, "d": {}})))
+                            .await?;
+                        ready = true;
+                    }
+                    _ => {}
+                }
+            }
+            Some((event, data)) = events.recv(), if ready => {
+                seq += 1;
+                sink.send(text(json!({"op": 0, "t": event, "s": seq, "d": data})))
+                    .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::discord::{DiscordBot, HandlerContext, MessageHandler, QueuedMessage, tags};
+    use async_trait::async_trait;
+
+    /// Answers each batch with a reaction tag and the batch's contents
+    struct EchoHandler;
+
+    #[async_trait]
+    impl MessageHandler for EchoHandler {
+        async fn handle(&self, batch: &[QueuedMessage], ctx: &HandlerContext) {
+            let Some(last) = batch.last() else { return };
+            let said: Vec<&str> = batch.iter().map(|m| m.content.as_str()).collect();
+            let reply = format!("[REACT:👍] You said: {}", said.join(" / "));
+            let parsed =
+                tags::process_reply_tags(&reply, &ctx.tag_registry, &ctx.config, false).await;
+            for emoji in &parsed.reactions {
+                let _ = ctx
+                    .rest
+                    .add_reaction(&last.channel_id, &last.message_id, emoji)
+                    .await;
+            }
+            let _ = ctx
+                .rest
+                .send_message(&last.channel_id, &parsed.text, None)
+                .await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bot_against_fake_discord() {
+        let fake = FakeDiscord::start().await.unwrap();
+        let mut config = Config::default();
+        config.channels.discord =
+            Some(serde_json::from_value(json!({"enabled": true, "token": "fake-token"})).unwrap());
+        let mut bot = DiscordBot::new(config)
+            .unwrap()
+            .with_endpoints(&fake.gateway_url, &fake.api_url)
+            .unwrap()
+            .with_channel_handler("42", Arc::new(EchoHandler));
+        let bot = tokio::spawn(async move { bot.run().await });
+
+        // Both messages land in one batch; the bot's own message is ignored
+        fake.play(vec![
+            Step::Dispatch("MESSAGE_CREATE", message("1", "42", "7", "hello")),
+            Step::Dispatch("MESSAGE_CREATE", message("2", "42", BOT_ID, "echo")),
+            Step::Dispatch("MESSAGE_CREATE", message("3", "42", "7", "again")),
+            Step::Expect {
+                method: "PUT",
+                path: "/channels/42/messages/3/reactions/%F0%9F%91%8D/@me".to_string(),
+                body: Value::Null,
+            },
+            Step::Expect {
+                method: "POST",
+                path: "/channels/42/messages".to_string(),
+                body: json!({"content": "You said: hello / again"}),
+            },
+        ])
+        .await
+        .unwrap();
+
+        let replies = fake
+            .calls()
+            .iter()
+            .filter(|c| c.method == "POST" && c.path == "/channels/42/messages")
+            .count();
+        assert_eq!(replies, 1);
+        bot.abort();
+    }
+}
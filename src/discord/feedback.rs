@@ -0,0 +1,83 @@
+//! Reactions as feedback
+//!
+//! A 👍 or 👎 on one of the bot's replies is stored against the prompt
+//! behind the reply (replies are keyed to their prompt when sent) and
+//! counted for the reply's experiment variant, if it had one. Removing the
+//! reaction takes the feedback back. Other emoji are ignored.
+
+use anyhow::Result;
+use tracing::{debug, warn};
+
+use crate::agent::{
+    ExperimentStore, Feedback, FeedbackStore, PromptStore, prompt_hash, prompt_request,
+};
+
+/// Whether an emoji is positive (👍) or negative (👎) feedback, with any
+/// skin tone
+pub(super) fn polarity(emoji: &str) -> Option<bool> {
+    if emoji.starts_with('👍') {
+        Some(true)
+    } else if emoji.starts_with('👎') {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Record a reaction added to one of the bot's messages
+pub(super) fn reaction_added(message_id: &str, user_id: &str, positive: bool) {
+    if let Err(e) = record(message_id, user_id, positive) {
+        warn!("Failed to record feedback on message {}: {}", message_id, e);
+    }
+}
+
+/// Take back feedback when its reaction is removed
+pub(super) fn reaction_removed(message_id: &str, user_id: &str, positive: bool) {
+    let result = FeedbackStore::open_default()
+        .and_then(|store| store.remove(message_id, user_id, positive))
+        .and_then(|prompt_id| match prompt_id {
+            Some(id) => ExperimentStore::open_default()
+                .and_then(|store| store.remove_reaction(id, positive))
+                .map(drop),
+            None => Ok(()),
+        });
+    if let Err(e) = result {
+        warn!("Failed to remove feedback on message {}: {}", message_id, e);
+    }
+}
+
+fn record(message_id: &str, user_id: &str, positive: bool) -> Result<()> {
+    let Some(prompt) = PromptStore::open_default()?.get(message_id)? else {
+        debug!("No recorded prompt behind message {}", message_id);
+        return Ok(());
+    };
+
+    let hash = prompt_hash(&prompt.messages);
+    let recorded = FeedbackStore::open_default()?.record(&Feedback {
+        message_id,
+        user_id,
+        positive,
+        prompt_id: prompt.id,
+        prompt_hash: &hash,
+        scope: &prompt.scope,
+        request: prompt_request(&prompt.messages),
+        response: &prompt.response,
+    })?;
+    if recorded {
+        ExperimentStore::open_default()?.record_reaction(prompt.id, positive)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polarity() {
+        assert_eq!(polarity("👍"), Some(true));
+        assert_eq!(polarity("👍🏽"), Some(true));
+        assert_eq!(polarity("👎"), Some(false));
+        assert_eq!(polarity("❤️"), None);
+    }
+}
@@ -0,0 +1,917 @@
+//! Gateway connection and event intake
+//!
+//! Connects to the Discord gateway, keeps the heartbeat going, tracks
+//! resume state and turns MESSAGE_CREATE/UPDATE/DELETE dispatches into
+//! queued messages and tracker updates for the processor. Reactions on the
+//! bot's messages are passed on as feedback, and picks on the agent's
+//! choice buttons and menus (INTERACTION_CREATE) are queued as messages.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+use tracing::{debug, error, info, warn};
+
+use super::commands;
+use super::components;
+use super::custom_emoji::{self, StickerItem};
+use super::feedback;
+use super::lifecycle::{self, Lifecycle};
+use super::mentions::BotIdentity;
+use super::outbox;
+use super::ratelimit::Decision;
+use super::rest::DiscordUser;
+use super::settings;
+use super::{DiscordBot, QueuedMessage};
+use crate::features::Feature;
+use crate::ws::{self, WsReceiver, WsSender};
+
+const GATEWAY_URL: &str = "wss://gateway.discord.gg";
+
+// Gateway opcodes
+const OP_DISPATCH: u8 = 0;
+const OP_HEARTBEAT: u8 = 1;
+const OP_IDENTIFY: u8 = 2;
+const OP_RESUME: u8 = 6;
+const OP_RECONNECT: u8 = 7;
+const OP_INVALID_SESSION: u8 = 9;
+const OP_HELLO: u8 = 10;
+const OP_HEARTBEAT_ACK: u8 = 11;
+
+// Interaction type and response types
+const INTERACTION_MESSAGE_COMPONENT: u8 = 3;
+const INTERACTION_CHANNEL_MESSAGE: u8 = 4;
+/// Acknowledge a component without changing its message
+const INTERACTION_DEFERRED_UPDATE: u8 = 6;
+const INTERACTION_UPDATE_MESSAGE: u8 = 7;
+/// Message flag: only the user who interacted sees the message
+const EPHEMERAL: u64 = 1 << 6;
+
+/// Intents: GUILDS (1<<0, for the bot's nickname and role) +
+/// GUILD_MESSAGES (1<<9) + GUILD_MESSAGE_REACTIONS (1<<10) +
+/// MESSAGE_CONTENT (1<<15)
+const INTENTS: u64 = 34305;
+
+/// Gateway URL for an API version, on the default host or the session's
+/// resume host
+pub(super) fn gateway_url(host: Option<&str>, api_version: u8) -> String {
+    format!(
+        "{}/?v={}&encoding=json",
+        host.unwrap_or(GATEWAY_URL).trim_end_matches('/'),
+        api_version
+    )
+}
+
+/// A gateway close that reconnecting won't fix
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub(super) struct FatalClose(&'static str);
+
+/// Close codes that need the configuration or the Developer Portal fixed
+fn fatal_close(code: u16) -> Option<FatalClose> {
+    let reason = match code {
+        4004 => "Discord rejected the bot token; check channels.discord.token",
+        4012 => "Discord does not serve the configured channels.discord.api_version",
+        4013 | 4014 => {
+            "Discord refused the bot's intents; enable Message Content Intent under \
+             Bot → Privileged Gateway Intents in the Developer Portal"
+        }
+        _ => return None,
+    };
+    Some(FatalClose(reason))
+}
+
+// ─── Gateway payloads ───────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+struct GatewayPayload {
+    op: u8,
+    d: Option<serde_json::Value>,
+    s: Option<u64>,
+    t: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct GatewayCommand {
+    op: u8,
+    d: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelloData {
+    heartbeat_interval: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReadyData {
+    session_id: String,
+    resume_gateway_url: String,
+    user: ReadyUser,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReadyUser {
+    id: String,
+    username: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageCreateData {
+    id: String,
+    channel_id: String,
+    guild_id: Option<String>,
+    content: String,
+    author: DiscordUser,
+    mentions: Option<Vec<MentionUser>>,
+    #[serde(default)]
+    mention_roles: Vec<String>,
+    #[serde(default)]
+    attachments: Vec<DiscordAttachment>,
+    #[serde(default)]
+    sticker_items: Vec<StickerItem>,
+    /// Guild member info (guild messages only)
+    member: Option<GuildMember>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GuildMember {
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+/// MESSAGE_UPDATE payload (partial message; content is absent for
+/// embed-only updates)
+#[derive(Debug, Deserialize)]
+struct MessageUpdateData {
+    id: String,
+    channel_id: String,
+    guild_id: Option<String>,
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageDeleteData {
+    id: String,
+    channel_id: String,
+}
+
+/// MESSAGE_REACTION_ADD/REMOVE payload
+#[derive(Debug, Deserialize)]
+struct ReactionData {
+    user_id: String,
+    message_id: String,
+    /// Author of the reacted message (ADD only)
+    message_author_id: Option<String>,
+    emoji: ReactionEmoji,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReactionEmoji {
+    /// Unicode emoji, or the name of a custom one
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiscordAttachment {
+    #[allow(dead_code)]
+    id: String,
+    url: String,
+    content_type: Option<String>,
+    #[allow(dead_code)]
+    filename: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MentionUser {
+    id: String,
+}
+
+/// GUILD_CREATE payload (the parts about the bot). Without the
+/// GUILD_MEMBERS intent, `members` holds only the bot itself.
+#[derive(Debug, Deserialize)]
+struct GuildCreateData {
+    id: String,
+    #[serde(default)]
+    roles: Vec<GuildRole>,
+    #[serde(default)]
+    members: Vec<GuildMemberEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GuildRole {
+    id: String,
+    tags: Option<RoleTags>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RoleTags {
+    /// Set on the role Discord manages for a bot
+    bot_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GuildMemberEntry {
+    user: MentionUser,
+    nick: Option<String>,
+}
+
+/// GUILD_MEMBER_UPDATE payload
+#[derive(Debug, Deserialize)]
+struct GuildMemberUpdateData {
+    guild_id: String,
+    user: MentionUser,
+    nick: Option<String>,
+}
+
+/// INTERACTION_CREATE payload (the parts used for message components)
+#[derive(Debug, Deserialize)]
+struct InteractionData {
+    id: String,
+    token: String,
+    #[serde(rename = "type")]
+    kind: u8,
+    channel_id: Option<String>,
+    guild_id: Option<String>,
+    /// Who interacted, in a guild
+    member: Option<InteractionMember>,
+    /// Who interacted, in a DM
+    user: Option<DiscordUser>,
+    data: Option<ComponentData>,
+    /// The message the component is on
+    message: Option<InteractionMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InteractionMember {
+    user: DiscordUser,
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ComponentData {
+    custom_id: String,
+    /// Picked option values (select menus)
+    #[serde(default)]
+    values: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InteractionMessage {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    components: serde_json::Value,
+}
+
+// ─── Session state ──────────────────────────────────────────────────
+
+/// Resume state carried across reconnects
+#[derive(Default)]
+pub(super) struct SessionState {
+    sequence: Option<u64>,
+    session_id: Option<String>,
+    pub resume_url: Option<String>,
+    bot: BotIdentity,
+    /// Whether the greeting was posted (only after the first READY)
+    greeted: bool,
+    /// Whether the current connection got READY or RESUMED
+    pub ready: bool,
+}
+
+impl DiscordBot {
+    pub(super) async fn connect_and_run(&self, url: &str, state: &mut SessionState) -> Result<()> {
+        let (sender, mut receiver) = ws::connect(url)
+            .await
+            .context("Failed to connect to Discord gateway")?;
+        info!("Connected to Discord gateway");
+        state.ready = false;
+
+        // Wait for HELLO
+        let heartbeat_interval = self.wait_for_hello(&mut receiver).await?;
+        info!(
+            "Received HELLO, heartbeat interval: {}ms",
+            heartbeat_interval
+        );
+
+        // Send IDENTIFY or RESUME
+        if let Some(ref sid) = state.session_id {
+            if let Some(seq) = state.sequence {
+                self.send_resume(&sender, sid, seq).await?;
+                info!("Sent RESUME for session {}", sid);
+            } else {
+                self.send_identify(&sender).await?;
+                info!("Sent IDENTIFY");
+            }
+        } else {
+            self.send_identify(&sender).await?;
+            info!("Sent IDENTIFY");
+        }
+
+        // Jitter: first heartbeat at interval * random(0..1), then every interval
+        let heartbeat_handle = sender.spawn_heartbeat(
+            Duration::from_millis(heartbeat_interval / 2),
+            Duration::from_millis(heartbeat_interval),
+            || serde_json::json!({"op": OP_HEARTBEAT, "d": null}),
+        );
+
+        // Event loop
+        let result = self.event_loop(&mut receiver, &sender, state).await;
+
+        heartbeat_handle.abort();
+        result
+    }
+
+    async fn wait_for_hello(&self, receiver: &mut WsReceiver) -> Result<u64> {
+        while let Some(payload) = receiver.recv_json::<GatewayPayload>().await? {
+            if payload.op == OP_HELLO {
+                let hello: HelloData =
+                    serde_json::from_value(payload.d.context("HELLO payload missing data")?)?;
+                return Ok(hello.heartbeat_interval);
+            }
+        }
+        anyhow::bail!("Gateway closed before sending HELLO")
+    }
+
+    async fn send_identify(&self, sender: &WsSender) -> Result<()> {
+        let identify = GatewayCommand {
+            op: OP_IDENTIFY,
+            d: serde_json::json!({
+                "token": self.discord_config.token,
+                "intents": INTENTS,
+                "properties": {
+                    "os": std::env::consts::OS,
+                    "browser": "localgpt",
+                    "device": "localgpt"
+                }
+            }),
+        };
+        sender.send_json(&identify).await
+    }
+
+    async fn send_resume(&self, sender: &WsSender, session_id: &str, sequence: u64) -> Result<()> {
+        let resume = GatewayCommand {
+            op: OP_RESUME,
+            d: serde_json::json!({
+                "token": self.discord_config.token,
+                "session_id": session_id,
+                "seq": sequence
+            }),
+        };
+        sender.send_json(&resume).await
+    }
+
+    async fn event_loop(
+        &self,
+        receiver: &mut WsReceiver,
+        sender: &WsSender,
+        state: &mut SessionState,
+    ) -> Result<()> {
+        while let Some(payload) = receiver.recv_json::<GatewayPayload>().await? {
+            // Update sequence
+            if let Some(s) = payload.s {
+                state.sequence = Some(s);
+            }
+
+            match payload.op {
+                OP_DISPATCH => {
+                    if let Some(ref event_name) = payload.t {
+                        self.handle_dispatch(event_name, payload.d, state).await;
+                    }
+                }
+                OP_HEARTBEAT => {
+                    // Server requesting immediate heartbeat
+                    sender
+                        .send_json(&serde_json::json!({"op": OP_HEARTBEAT, "d": state.sequence}))
+                        .await?;
+                }
+                OP_RECONNECT => {
+                    info!("Received RECONNECT, will reconnect");
+                    return Err(anyhow::anyhow!("Server requested reconnect"));
+                }
+                OP_INVALID_SESSION => {
+                    let resumable = payload.d.and_then(|v| v.as_bool()).unwrap_or(false);
+                    if !resumable {
+                        info!("Invalid session (not resumable), resetting state");
+                        state.session_id = None;
+                        state.sequence = None;
+                    }
+                    return Err(anyhow::anyhow!("Invalid session"));
+                }
+                OP_HEARTBEAT_ACK => {
+                    debug!("Heartbeat ACK received");
+                }
+                _ => {
+                    debug!("Unhandled opcode: {}", payload.op);
+                }
+            }
+        }
+
+        match receiver.close_code().and_then(fatal_close) {
+            Some(fatal) => Err(fatal.into()),
+            None => Err(anyhow::anyhow!("Gateway connection closed")),
+        }
+    }
+
+    async fn handle_dispatch(
+        &self,
+        event_name: &str,
+        data: Option<serde_json::Value>,
+        state: &mut SessionState,
+    ) {
+        match event_name {
+            "READY" => {
+                if let Some(d) = data {
+                    match serde_json::from_value::<ReadyData>(d) {
+                        Ok(ready) => {
+                            info!(
+                                "READY: logged in as {} ({})",
+                                ready.user.username, ready.user.id
+                            );
+                            state.session_id = Some(ready.session_id);
+                            state.resume_url = Some(ready.resume_gateway_url);
+                            state.bot.id = Some(ready.user.id);
+                            state.bot.username = Some(ready.user.username);
+                            state.ready = true;
+
+                            // Deliver what failed while disconnected
+                            tokio::spawn(outbox::flush_default(Arc::clone(&self.rest)));
+
+                            if !state.greeted {
+                                state.greeted = true;
+                                let config = self.config.clone();
+                                let rest = Arc::clone(&self.rest);
+                                tokio::spawn(async move {
+                                    lifecycle::announce(&config, rest.as_ref(), Lifecycle::Online)
+                                        .await;
+                                });
+                            }
+                        }
+                        Err(e) => error!("Failed to parse READY: {}", e),
+                    }
+                }
+            }
+            "MESSAGE_CREATE" => {
+                if let Some(d) = data {
+                    match serde_json::from_value::<MessageCreateData>(d) {
+                        Ok(msg) => {
+                            self.handle_message_create(&msg, state).await;
+                        }
+                        Err(e) => error!("Failed to parse MESSAGE_CREATE: {}", e),
+                    }
+                }
+            }
+            "GUILD_CREATE" => {
+                if let Some(d) = data {
+                    match serde_json::from_value::<GuildCreateData>(d) {
+                        Ok(guild) => self.handle_guild_create(guild, state),
+                        Err(e) => error!("Failed to parse GUILD_CREATE: {}", e),
+                    }
+                }
+            }
+            "GUILD_MEMBER_UPDATE" => {
+                if let Some(d) = data {
+                    match serde_json::from_value::<GuildMemberUpdateData>(d) {
+                        Ok(member) if state.bot.id.as_ref() == Some(&member.user.id) => {
+                            debug!("Nickname in guild {}: {:?}", member.guild_id, member.nick);
+                            state.bot.set_nick(&member.guild_id, member.nick);
+                        }
+                        Ok(_) => {}
+                        Err(e) => error!("Failed to parse GUILD_MEMBER_UPDATE: {}", e),
+                    }
+                }
+            }
+            "MESSAGE_UPDATE" => {
+                if let Some(d) = data {
+                    match serde_json::from_value::<MessageUpdateData>(d) {
+                        Ok(update) => self.handle_message_update(&update, state),
+                        Err(e) => error!("Failed to parse MESSAGE_UPDATE: {}", e),
+                    }
+                }
+            }
+            "MESSAGE_DELETE" => {
+                if let Some(d) = data {
+                    match serde_json::from_value::<MessageDeleteData>(d) {
+                        Ok(delete) => {
+                            debug!(
+                                "Message {} deleted in channel {}",
+                                delete.id, delete.channel_id
+                            );
+                            self.tracker.lock().unwrap().record_delete(&delete.id);
+                        }
+                        Err(e) => error!("Failed to parse MESSAGE_DELETE: {}", e),
+                    }
+                }
+            }
+            "MESSAGE_REACTION_ADD" | "MESSAGE_REACTION_REMOVE" => {
+                if let Some(d) = data {
+                    match serde_json::from_value::<ReactionData>(d) {
+                        Ok(reaction) => self.handle_reaction(
+                            &reaction,
+                            event_name == "MESSAGE_REACTION_ADD",
+                            state,
+                        ),
+                        Err(e) => error!("Failed to parse {}: {}", event_name, e),
+                    }
+                }
+            }
+            "INTERACTION_CREATE" => {
+                if let Some(d) = data {
+                    match serde_json::from_value::<InteractionData>(d) {
+                        Ok(interaction) => self.handle_interaction(&interaction),
+                        Err(e) => error!("Failed to parse INTERACTION_CREATE: {}", e),
+                    }
+                }
+            }
+            "RESUMED" => {
+                info!("Session resumed successfully");
+                state.ready = true;
+            }
+            _ => {
+                debug!("Unhandled event: {}", event_name);
+            }
+        }
+    }
+
+    async fn handle_message_create(&self, msg: &MessageCreateData, state: &SessionState) {
+        // Ignore messages from bots (unless allow_bots is set)
+        if msg.author.bot.unwrap_or(false) && !self.discord_config.allow_bots {
+            return;
+        }
+
+        // Ignore messages from ourselves
+        if let Some(ref bot_id) = state.bot.id {
+            if msg.author.id == *bot_id {
+                return;
+            }
+        }
+
+        // Paused at runtime (feature toggle)
+        if self
+            .features
+            .as_ref()
+            .is_some_and(|f| f.is_enabled(Feature::DiscordPaused))
+        {
+            debug!("Discord paused, ignoring message {}", msg.id);
+            return;
+        }
+
+        let mentioned: Vec<String> = msg
+            .mentions
+            .iter()
+            .flatten()
+            .map(|m| m.id.clone())
+            .collect();
+        let mentions_bot = state.bot.is_mentioned(
+            msg.guild_id.as_deref(),
+            &msg.content,
+            &mentioned,
+            &msg.mention_roles,
+        );
+
+        // Whether the agent should see this message. Moderated guilds also
+        // queue messages not addressed to the bot so they can be scored.
+        let mut addressed = true;
+
+        // Check guild allow-list
+        if !self.discord_config.guilds.is_empty() {
+            let guild_id = match &msg.guild_id {
+                Some(id) => id,
+                None => return, // DM - skip if guilds are configured
+            };
+
+            let guild_config = self
+                .discord_config
+                .guilds
+                .iter()
+                .find(|g| g.guild_id == *guild_id);
+
+            match guild_config {
+                None => return, // Guild not in allow-list
+                Some(gc) => {
+                    // Check channel filter
+                    if !gc.channels.is_empty() && !gc.channels.contains(&msg.channel_id) {
+                        return;
+                    }
+
+                    // Check require_mention as currently set (prefixed
+                    // commands are addressed to the bot too, ambient
+                    // channels see everything)
+                    let prefixed = gc
+                        .command_prefix
+                        .as_deref()
+                        .is_some_and(|prefix| commands::has_prefix(&msg.content, prefix));
+                    let settings = settings::for_channel(Some(gc), &msg.channel_id);
+                    if settings.require_mention && !settings.ambient && !mentions_bot && !prefixed {
+                        if !gc.moderation.enabled {
+                            return;
+                        }
+                        addressed = false;
+                    }
+                }
+            }
+        }
+
+        // Collect image attachment URLs
+        let image_urls: Vec<String> = msg
+            .attachments
+            .iter()
+            .filter(|a| {
+                a.content_type
+                    .as_deref()
+                    .map(|ct| ct.starts_with("image/"))
+                    .unwrap_or(false)
+            })
+            .map(|a| a.url.clone())
+            .collect();
+
+        // Skip empty messages (no text, images or stickers)
+        let content = msg.content.trim();
+        if content.is_empty() && image_urls.is_empty() && msg.sticker_items.is_empty() {
+            return;
+        }
+
+        let author_roles = msg
+            .member
+            .as_ref()
+            .map(|m| m.roles.clone())
+            .unwrap_or_default();
+
+        // Enforce the user's quota before the message takes a queue slot
+        if addressed
+            && let Decision::Defer {
+                retry_after,
+                notify,
+            } = self
+                .rate_limiter
+                .check(&msg.author.id, &author_roles, std::time::Instant::now())
+        {
+            info!(
+                "Deferring message from {} in channel {}: over quota",
+                msg.author.username, msg.channel_id
+            );
+            if notify {
+                let notice = format!(
+                    "<@{}> {}",
+                    msg.author.id,
+                    self.rate_limiter.notice(retry_after)
+                );
+                if let Err(e) = self.rest.send_message(&msg.channel_id, &notice, None).await {
+                    warn!("Failed to send rate limit notice: {}", e);
+                }
+            }
+            return;
+        }
+
+        // Strip mentions of the bot from content and describe custom emoji
+        // and stickers
+        let cleaned = custom_emoji::describe(
+            &state.bot.strip(msg.guild_id.as_deref(), content),
+            &msg.sticker_items,
+        );
+
+        info!(
+            "Message from {} in channel {}: {}{}",
+            msg.author.username,
+            msg.channel_id,
+            if cleaned.len() > 80 {
+                let truncated: String = cleaned.chars().take(40).collect();
+                format!("{}...", truncated)
+            } else {
+                cleaned.clone()
+            },
+            if image_urls.is_empty() {
+                String::new()
+            } else {
+                format!(" [+{} image(s)]", image_urls.len())
+            }
+        );
+
+        // Enqueue message for processing (non-blocking)
+        let queued = QueuedMessage {
+            channel_id: msg.channel_id.clone(),
+            guild_id: msg.guild_id.clone(),
+            message_id: msg.id.clone(),
+            author_id: msg.author.id.clone(),
+            author_name: msg.author.username.clone(),
+            author_roles,
+            content: cleaned,
+            image_urls,
+            mention_count: msg.mentions.as_ref().map_or(0, Vec::len),
+            addressed,
+        };
+
+        self.enqueue(queued);
+    }
+
+    /// Hand a message to the processor, keeping it for replay until handled
+    fn enqueue(&self, queued: QueuedMessage) {
+        let id = queued.message_id.clone();
+        self.tracker.lock().unwrap().mark_queued(&id);
+        // Kept until handled, for replay if the daemon stops first
+        if let Some(ref store) = self.pending
+            && let Err(e) = store.save(&queued)
+        {
+            warn!("Failed to persist queued message {}: {}", id, e);
+        }
+
+        match self.queue_tx.try_send(queued) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(queued)) => {
+                warn!("Message queue full, dropping oldest message");
+                // Drain one to make room, then send
+                if self.queue_tx.try_send(queued).is_err() {
+                    self.tracker.lock().unwrap().forget(&id);
+                    if let Some(ref store) = self.pending {
+                        let _ = store.remove(std::slice::from_ref(&id));
+                    }
+                }
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                error!("Message queue closed unexpectedly");
+                self.tracker.lock().unwrap().forget(&id);
+            }
+        }
+    }
+
+    /// A pick on the agent's choices: show it on the message and queue the
+    /// picked labels as the user's answer
+    fn handle_interaction(&self, interaction: &InteractionData) {
+        if interaction.kind != INTERACTION_MESSAGE_COMPONENT {
+            debug!("Unhandled interaction type {}", interaction.kind);
+            return;
+        }
+        let (Some(channel_id), Some(data), Some(message)) = (
+            &interaction.channel_id,
+            &interaction.data,
+            &interaction.message,
+        ) else {
+            self.acknowledge(interaction);
+            return;
+        };
+        let Some(labels) =
+            components::selected_labels(&data.custom_id, &data.values, &message.components)
+        else {
+            debug!("Ignoring component {}", data.custom_id);
+            self.acknowledge(interaction);
+            return;
+        };
+        let (user, roles) = match (&interaction.member, &interaction.user) {
+            (Some(member), _) => (&member.user, member.roles.clone()),
+            (None, Some(user)) => (user, Vec::new()),
+            (None, None) => {
+                self.acknowledge(interaction);
+                return;
+            }
+        };
+
+        if self
+            .features
+            .as_ref()
+            .is_some_and(|f| f.is_enabled(Feature::DiscordPaused))
+        {
+            debug!("Discord paused, ignoring interaction {}", interaction.id);
+            self.acknowledge(interaction);
+            return;
+        }
+        // Same guild and channel allow-list as for messages
+        if !self.discord_config.guilds.is_empty() {
+            let allowed = interaction.guild_id.as_ref().is_some_and(|guild_id| {
+                self.discord_config.guilds.iter().any(|g| {
+                    g.guild_id == *guild_id
+                        && (g.channels.is_empty() || g.channels.contains(channel_id))
+                })
+            });
+            if !allowed {
+                self.acknowledge(interaction);
+                return;
+            }
+        }
+
+        // Over quota: only the user sees the notice, the choices stay open
+        if let Decision::Defer { retry_after, .. } =
+            self.rate_limiter
+                .check(&user.id, &roles, std::time::Instant::now())
+        {
+            let notice = serde_json::json!({
+                "type": INTERACTION_CHANNEL_MESSAGE,
+                "data": {"content": self.rate_limiter.notice(retry_after), "flags": EPHEMERAL},
+            });
+            self.answer_interaction(interaction, notice);
+            return;
+        }
+        let content = components::answered_content(&message.content, &labels, &user.id);
+        let update = serde_json::json!({
+            "type": INTERACTION_UPDATE_MESSAGE,
+            "data": {"content": content, "components": []},
+        });
+        self.answer_interaction(interaction, update);
+
+        info!(
+            "Choice from {} in channel {}: {}",
+            user.username,
+            channel_id,
+            labels.join(", ")
+        );
+        self.enqueue(QueuedMessage {
+            channel_id: channel_id.clone(),
+            guild_id: interaction.guild_id.clone(),
+            message_id: interaction.id.clone(),
+            author_id: user.id.clone(),
+            author_name: user.username.clone(),
+            author_roles: roles,
+            content: labels.join(", "),
+            image_urls: Vec::new(),
+            mention_count: 0,
+            addressed: true,
+        });
+    }
+
+    fn handle_message_update(&self, update: &MessageUpdateData, state: &SessionState) {
+        // Embed unfurls also arrive as updates, without content
+        let Some(ref content) = update.content else {
+            return;
+        };
+
+        debug!(
+            "Message {} edited in channel {}",
+            update.id, update.channel_id
+        );
+        let cleaned = state.bot.strip(update.guild_id.as_deref(), content);
+        self.tracker
+            .lock()
+            .unwrap()
+            .record_edit(&update.id, cleaned);
+    }
+
+    /// 👍/👎 on the bot's replies are feedback on them
+    fn handle_reaction(&self, reaction: &ReactionData, added: bool, state: &SessionState) {
+        let Some(ref bot_id) = state.bot.id else {
+            return;
+        };
+        let Some(positive) = reaction.emoji.name.as_deref().and_then(feedback::polarity) else {
+            return;
+        };
+        // The bot's own reactions aren't feedback; removals carry no author
+        // but only match feedback recorded on the bot's messages
+        if reaction.user_id == *bot_id
+            || (added && reaction.message_author_id.as_ref() != Some(bot_id))
+        {
+            return;
+        }
+
+        debug!(
+            "Feedback {} on message {} by {}",
+            if positive { "👍" } else { "👎" },
+            reaction.message_id,
+            reaction.user_id
+        );
+        let message_id = reaction.message_id.clone();
+        let user_id = reaction.user_id.clone();
+        // SQLite writes stay off the event loop
+        tokio::task::spawn_blocking(move || {
+            if added {
+                feedback::reaction_added(&message_id, &user_id, positive);
+            } else {
+                feedback::reaction_removed(&message_id, &user_id, positive);
+            }
+        });
+    }
+
+    /// Send an interaction response without holding up the event loop
+    fn answer_interaction(&self, interaction: &InteractionData, response: serde_json::Value) {
+        let rest = Arc::clone(&self.rest);
+        let (id, token) = (interaction.id.clone(), interaction.token.clone());
+        tokio::spawn(async move {
+            if let Err(e) = rest.respond_to_interaction(&id, &token, response).await {
+                warn!("Failed to answer interaction {}: {}", id, e);
+            }
+        });
+    }
+
+    /// Acknowledge an interaction that is ignored, so Discord doesn't show
+    /// it as failed
+    fn acknowledge(&self, interaction: &InteractionData) {
+        let ack = serde_json::json!({"type": INTERACTION_DEFERRED_UPDATE});
+        self.answer_interaction(interaction, ack);
+    }
+
+    /// Learn the bot's nickname and managed role in a guild
+    fn handle_guild_create(&self, guild: GuildCreateData, state: &mut SessionState) {
+        let Some(bot_id) = state.bot.id.clone() else {
+            return;
+        };
+        if let Some(role) = guild
+            .roles
+            .into_iter()
+            .find(|r| r.tags.as_ref().and_then(|t| t.bot_id.as_ref()) == Some(&bot_id))
+        {
+            state.bot.set_role(&guild.id, role.id);
+        }
+        if let Some(me) = guild.members.into_iter().find(|m| m.user.id == bot_id) {
+            state.bot.set_nick(&guild.id, me.nick);
+        }
+        debug!("Guild {} available", guild.id);
+    }
+}
@@ -0,0 +1,223 @@
+//! Intent classification
+//!
+//! With `[channels.discord.classifier] enabled = true`, each message for the
+//! bot is first labelled by a (preferably small, local) model as a question,
+//! command, chit-chat, memory write or something to ignore. Messages with a
+//! label in `skip` get no reply and cost no call to the main model.
+//! Memory writes ("remember that I ...") from users allowed to write memory
+//! are appended to MEMORY.md directly and acknowledged with a reaction.
+//! When the classifier fails, the message goes to the agent as usual.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use super::QueuedMessage;
+use super::permissions::Permissions;
+use super::processor::{HandlerContext, MessageHandler};
+use crate::agent::{LLMProvider, LLMResponseContent, Message, Role, create_provider};
+use crate::config::{Config, DiscordCapability, DiscordClassifierConfig};
+
+/// Reaction on messages saved to MEMORY.md
+const SAVED_REACTION: &str = "📝";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Intent {
+    Question,
+    Command,
+    ChitChat,
+    MemoryWrite,
+    Ignore,
+}
+
+impl Intent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Intent::Question => "question",
+            Intent::Command => "command",
+            Intent::ChitChat => "chit_chat",
+            Intent::MemoryWrite => "memory_write",
+            Intent::Ignore => "ignore",
+        }
+    }
+}
+
+/// Read the label out of the model's reply (the first one mentioned wins)
+fn parse_intent(text: &str) -> Option<Intent> {
+    let text = text.to_lowercase().replace(['-', ' '], "_");
+    [
+        Intent::MemoryWrite,
+        Intent::ChitChat,
+        Intent::Question,
+        Intent::Command,
+        Intent::Ignore,
+    ]
+    .into_iter()
+    .filter_map(|intent| text.find(intent.as_str()).map(|pos| (pos, intent)))
+    .min_by_key(|(pos, _)| *pos)
+    .map(|(_, intent)| intent)
+}
+
+async fn classify(provider: &dyn LLMProvider, msg: &QueuedMessage) -> Result<Intent> {
+    let prompt = format!(
+        "Label this chat message sent to an assistant with exactly one word:\n\
+         - question: asks for information or an opinion\n\
+         - command: asks the assistant to do something\n\
+         - chit_chat: greetings, thanks, small talk\n\
+         - memory_write: tells the assistant a fact or preference to remember\n\
+         - ignore: not meant for the assistant, or nothing to answer\n\n\
+         Message from {}:\n{}",
+        msg.author_name, msg.content
+    );
+    let messages = vec![Message {
+        role: Role::User,
+        content: prompt,
+        tool_calls: None,
+        tool_call_id: None,
+        images: Vec::new(),
+    }];
+
+    let response = provider.chat(&messages, None).await?;
+    let LLMResponseContent::Text(text) = response.content else {
+        anyhow::bail!("classifier model returned tool calls");
+    };
+    parse_intent(&text).ok_or_else(|| anyhow::anyhow!("no label in classifier reply: {}", text))
+}
+
+/// Append a message to MEMORY.md as a bullet
+fn remember(workspace: &Path, msg: &QueuedMessage) -> Result<()> {
+    let note = msg.content.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(workspace.join("MEMORY.md"))?;
+    writeln!(
+        file,
+        "- {} ({}, {})",
+        note,
+        msg.author_name,
+        chrono::Local::now().format("%Y-%m-%d")
+    )?;
+    Ok(())
+}
+
+/// Labels messages for the bot and passes on those that need a reply
+pub struct IntentHandler {
+    inner: Arc<dyn MessageHandler>,
+    config: DiscordClassifierConfig,
+    provider: Box<dyn LLMProvider>,
+}
+
+impl IntentHandler {
+    pub fn new(config: &Config, inner: Arc<dyn MessageHandler>) -> Result<Self> {
+        let classifier = config
+            .channels
+            .discord
+            .as_ref()
+            .map(|d| d.classifier.clone())
+            .unwrap_or_default();
+        let model = classifier
+            .model
+            .clone()
+            .unwrap_or_else(|| config.agent.default_model.clone());
+        Ok(Self {
+            inner,
+            provider: create_provider(&model, config)?,
+            config: classifier,
+        })
+    }
+
+    /// Whether a message should reach the agent
+    async fn route(&self, msg: &QueuedMessage, ctx: &HandlerContext) -> bool {
+        if !msg.addressed {
+            return true;
+        }
+        let intent = match classify(self.provider.as_ref(), msg).await {
+            Ok(intent) => intent,
+            Err(e) => {
+                warn!("Classifier failed, passing message on: {}", e);
+                return true;
+            }
+        };
+
+        if self.config.skip.iter().any(|s| s == intent.as_str()) {
+            info!(
+                "Skipping message {} from {} ({})",
+                msg.message_id,
+                msg.author_name,
+                intent.as_str()
+            );
+            return false;
+        }
+
+        if intent == Intent::MemoryWrite && self.config.direct_memory_writes {
+            let permissions = Permissions::for_user(
+                &ctx.config()
+                    .channels
+                    .discord
+                    .as_ref()
+                    .map(|d| d.permissions.clone())
+                    .unwrap_or_default(),
+                &msg.author_id,
+                &msg.author_roles,
+            );
+            if !permissions.allows(DiscordCapability::MemoryWrite) {
+                return true;
+            }
+            if let Err(e) = remember(&ctx.config().workspace_path(), msg) {
+                warn!("Failed to save message to MEMORY.md, passing it on: {}", e);
+                return true;
+            }
+            info!(
+                "Saved message {} from {} to MEMORY.md",
+                msg.message_id, msg.author_name
+            );
+            let _ = ctx
+                .rest()
+                .add_reaction(&msg.channel_id, &msg.message_id, SAVED_REACTION)
+                .await;
+            return false;
+        }
+
+        true
+    }
+}
+
+#[async_trait]
+impl MessageHandler for IntentHandler {
+    async fn handle(&self, batch: &[QueuedMessage], ctx: &HandlerContext) {
+        let mut kept = Vec::with_capacity(batch.len());
+        for msg in batch {
+            if self.route(msg, ctx).await {
+                kept.push(msg.clone());
+            }
+        }
+        if !kept.is_empty() {
+            self.inner.handle(&kept, ctx).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_intent() {
+        assert_eq!(parse_intent("question"), Some(Intent::Question));
+        assert_eq!(parse_intent("Chit-chat."), Some(Intent::ChitChat));
+        assert_eq!(
+            parse_intent("memory_write (the user states a preference)"),
+            Some(Intent::MemoryWrite)
+        );
+        assert_eq!(
+            parse_intent("Label: ignore, not a question"),
+            Some(Intent::Ignore)
+        );
+        assert_eq!(parse_intent("I am not sure"), None);
+    }
+}
@@ -0,0 +1,121 @@
+//! Greeting and farewell announcements
+//!
+//! `[channels.discord.announcements]` posts a greeting in the configured
+//! channels when the bot first connects (not on reconnects) and a farewell
+//! when the daemon shuts down gracefully. With `generate = true` the
+//! configured texts are instructions and the default model writes the
+//! message; if that fails, the instruction itself is posted.
+
+use anyhow::Result;
+use tracing::{info, warn};
+
+use super::rest::DiscordRest;
+use crate::agent::{LLMResponseContent, Message, Role, create_provider};
+use crate::config::Config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lifecycle {
+    Online,
+    Offline,
+}
+
+/// Post the greeting or farewell to every announcement channel
+pub async fn announce(config: &Config, rest: &dyn DiscordRest, event: Lifecycle) {
+    let Some(announcements) = config.channels.discord.as_ref().map(|d| &d.announcements) else {
+        return;
+    };
+    let text = match event {
+        Lifecycle::Online => announcements.greeting.as_deref(),
+        Lifecycle::Offline => announcements.farewell.as_deref(),
+    };
+    let Some(text) = text.filter(|t| !t.trim().is_empty()) else {
+        return;
+    };
+    if announcements.channels.is_empty() {
+        return;
+    }
+
+    let message = if announcements.generate {
+        match generate(config, event, text).await {
+            Ok(message) => message,
+            Err(e) => {
+                warn!(
+                    "Failed to write announcement, posting it as configured: {}",
+                    e
+                );
+                text.to_string()
+            }
+        }
+    } else {
+        text.to_string()
+    };
+
+    for channel_id in &announcements.channels {
+        match rest.send_message(channel_id, &message, None).await {
+            Ok(_) => info!("Posted {:?} announcement to channel {}", event, channel_id),
+            Err(e) => warn!(
+                "Failed to post announcement to channel {}: {}",
+                channel_id, e
+            ),
+        }
+    }
+}
+
+async fn generate(config: &Config, event: Lifecycle, instruction: &str) -> Result<String> {
+    let situation = match event {
+        Lifecycle::Online => "You just came online in a Discord server.",
+        Lifecycle::Offline => "You are about to go offline in a Discord server.",
+    };
+    let prompt = format!(
+        "{} Write the single short message to post in the channel, following this \
+         guidance: {}\nReply with the message only.",
+        situation, instruction
+    );
+    let messages = vec![Message {
+        role: Role::User,
+        content: prompt,
+        tool_calls: None,
+        tool_call_id: None,
+        images: Vec::new(),
+    }];
+
+    let provider = create_provider(&config.agent.default_model, config)?;
+    let response = provider.chat(&messages, None).await?;
+    match response.content {
+        LLMResponseContent::Text(text) if !text.trim().is_empty() => Ok(text.trim().to_string()),
+        _ => anyhow::bail!("model returned no text"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discord::rest::MockDiscordRest;
+
+    #[tokio::test]
+    async fn test_greeting_posted_to_each_channel() {
+        let config: Config = toml::from_str(
+            r#"
+            [channels.discord]
+            token = "test-token"
+
+            [channels.discord.announcements]
+            channels = ["1", "2"]
+            greeting = "Back online!"
+            "#,
+        )
+        .unwrap();
+
+        let mut rest = MockDiscordRest::new();
+        rest.expect_send_message()
+            .withf(|_, content, _| content == "Back online!")
+            .times(2)
+            .returning(|_, _, _| Ok(Vec::new()));
+        announce(&config, &rest, Lifecycle::Online).await;
+
+        // No farewell configured
+        let mut rest = MockDiscordRest::new();
+        rest.expect_send_message().never();
+        announce(&config, &rest, Lifecycle::Offline).await;
+    }
+}
@@ -0,0 +1,148 @@
+//! Recognizing the bot in message text
+//!
+//! Besides the plain user mention (`<@id>`), members address the bot with
+//! the nickname form (`<@!id>`), with the bot's managed role (`<@&id>`,
+//! offered next to the bot by Discord's autocomplete), or by typing its
+//! server nickname as text (`@Nick, ...`), which Discord doesn't turn into a
+//! mention. All of these count as mentioning the bot and are removed before
+//! the command parser or the agent sees the message. Nicknames and managed
+//! roles are learned per guild from GUILD_CREATE.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+struct GuildIdentity {
+    nick: Option<String>,
+    role_id: Option<String>,
+}
+
+/// Who the bot is, globally and in each guild
+#[derive(Debug, Default)]
+pub(super) struct BotIdentity {
+    /// Set from READY
+    pub id: Option<String>,
+    pub username: Option<String>,
+    guilds: HashMap<String, GuildIdentity>,
+}
+
+impl BotIdentity {
+    /// Record the bot's nickname in a guild (None = no nickname)
+    pub fn set_nick(&mut self, guild_id: &str, nick: Option<String>) {
+        self.guilds.entry(guild_id.to_string()).or_default().nick =
+            nick.filter(|n| !n.trim().is_empty());
+    }
+
+    /// Record the role Discord manages for the bot in a guild
+    pub fn set_role(&mut self, guild_id: &str, role_id: String) {
+        self.guilds.entry(guild_id.to_string()).or_default().role_id = Some(role_id);
+    }
+
+    fn guild(&self, guild_id: Option<&str>) -> Option<&GuildIdentity> {
+        guild_id.and_then(|g| self.guilds.get(g))
+    }
+
+    /// Mention tokens that refer to the bot
+    fn mention_tokens(&self, guild_id: Option<&str>) -> Vec<String> {
+        let mut tokens = Vec::new();
+        if let Some(ref id) = self.id {
+            tokens.push(format!("<@{}>", id));
+            tokens.push(format!("<@!{}>", id));
+        }
+        if let Some(role_id) = self.guild(guild_id).and_then(|g| g.role_id.as_ref()) {
+            tokens.push(format!("<@&{}>", role_id));
+        }
+        tokens
+    }
+
+    /// Length of a typed `@Name` at the start of `content`, if any, with
+    /// the punctuation after it
+    fn typed_name_len(&self, guild_id: Option<&str>, content: &str) -> Option<usize> {
+        let rest = content.strip_prefix('@')?;
+        let nick = self.guild(guild_id).and_then(|g| g.nick.as_deref());
+        [nick, self.username.as_deref()]
+            .into_iter()
+            .flatten()
+            .find_map(|name| {
+                let head = rest.get(..name.len())?;
+                let after = &rest[name.len()..];
+                let ends_word = after
+                    .chars()
+                    .next()
+                    .is_none_or(|c| c.is_whitespace() || matches!(c, ',' | ':'));
+                (head.eq_ignore_ascii_case(name) && ends_word).then(|| {
+                    1 + name.len() + after.len() - after.trim_start_matches([',', ':']).len()
+                })
+            })
+    }
+
+    /// Whether a message mentions the bot; `users` and `roles` are the
+    /// message's `mentions` and `mention_roles`
+    pub fn is_mentioned(
+        &self,
+        guild_id: Option<&str>,
+        content: &str,
+        users: &[String],
+        roles: &[String],
+    ) -> bool {
+        self.id.as_ref().is_some_and(|id| users.contains(id))
+            || self
+                .guild(guild_id)
+                .and_then(|g| g.role_id.as_ref())
+                .is_some_and(|role| roles.contains(role))
+            || self
+                .typed_name_len(guild_id, content.trim_start())
+                .is_some()
+    }
+
+    /// `content` without the ways of mentioning the bot
+    pub fn strip(&self, guild_id: Option<&str>, content: &str) -> String {
+        let text = content.trim_start();
+        let text = match self.typed_name_len(guild_id, text) {
+            Some(len) => &text[len..],
+            None => text,
+        };
+        let mut cleaned = text.to_string();
+        for token in self.mention_tokens(guild_id) {
+            cleaned = cleaned.replace(&token, "");
+        }
+        cleaned.trim().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mention_forms() {
+        let mut bot = BotIdentity {
+            id: Some("42".to_string()),
+            username: Some("LocalGPT".to_string()),
+            ..Default::default()
+        };
+        bot.set_nick("g1", Some("Jeeves".to_string()));
+        bot.set_role("g1", "7".to_string());
+        let g1 = Some("g1");
+
+        assert_eq!(bot.strip(g1, "<@42> hello"), "hello");
+        assert_eq!(bot.strip(g1, "<@!42> !lg status"), "!lg status");
+        assert_eq!(bot.strip(g1, "<@&7> what's up"), "what's up");
+        assert_eq!(bot.strip(g1, "@jeeves, tea please"), "tea please");
+        assert_eq!(bot.strip(g1, "@LocalGPT: hi"), "hi");
+        // Other users' mentions and names are left alone
+        assert_eq!(bot.strip(g1, "@Jeevesy hi <@43>"), "@Jeevesy hi <@43>");
+        // The managed role and nickname only apply in their guild
+        assert_eq!(
+            bot.strip(Some("g2"), "<@&7> @Jeeves hi"),
+            "<@&7> @Jeeves hi"
+        );
+
+        assert!(bot.is_mentioned(g1, "hi", &["42".to_string()], &[]));
+        assert!(bot.is_mentioned(g1, "hi", &[], &["7".to_string()]));
+        assert!(bot.is_mentioned(g1, "@Jeeves hi", &[], &[]));
+        assert!(!bot.is_mentioned(g1, "hi @Jeeves", &[], &["8".to_string()]));
+
+        bot.set_nick("g1", None);
+        assert_eq!(bot.strip(g1, "@Jeeves hi"), "@Jeeves hi");
+    }
+}
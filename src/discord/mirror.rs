@@ -0,0 +1,147 @@
+//! Live mirror of Discord conversations
+//!
+//! With `[channels.discord] mirror = true`, the bot announces what it does
+//! in each channel (the messages it answers, its tool calls, its replies
+//! and errors) to [`subscribe`]rs in the same process. The daemon serves
+//! these at `GET /api/discord/mirror` as server-sent events to holders of
+//! the admin token, and the desktop app shows them read-only in its Chat
+//! view. Nothing is kept: an observer sees what happens while it is
+//! connected.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, LazyLock};
+use tokio::sync::broadcast;
+
+use crate::agent::{StreamEvent, ToolObserver, extract_tool_detail};
+use crate::config::Config;
+
+/// Longest tool output sent to observers, in bytes
+const MAX_OUTPUT_BYTES: usize = 500;
+
+static EVENTS: LazyLock<broadcast::Sender<MirrorEvent>> =
+    LazyLock::new(|| broadcast::channel(256).0);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MirrorEvent {
+    pub channel_id: String,
+    #[serde(flatten)]
+    pub kind: MirrorEventKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MirrorEventKind {
+    /// Messages the agent is about to answer
+    Prompt {
+        authors: Vec<String>,
+        content: String,
+    },
+    ToolCallStart {
+        name: String,
+        detail: Option<String>,
+    },
+    /// Output cut to a preview
+    ToolCallEnd {
+        name: String,
+        output: String,
+    },
+    /// The reply as posted
+    Reply {
+        text: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Events published from now on
+pub fn subscribe() -> broadcast::Receiver<MirrorEvent> {
+    EVENTS.subscribe()
+}
+
+fn enabled(config: &Config) -> bool {
+    config.channels.discord.as_ref().is_some_and(|d| d.mirror)
+}
+
+/// Announce an event if the mirror is on and someone is watching
+pub(super) fn publish(config: &Config, channel_id: &str, kind: MirrorEventKind) {
+    if enabled(config) && EVENTS.receiver_count() > 0 {
+        let _ = EVENTS.send(MirrorEvent {
+            channel_id: channel_id.to_string(),
+            kind,
+        });
+    }
+}
+
+/// Tool observer for a channel's agent, or None if the mirror is off
+pub(super) fn tool_observer(config: &Config, channel_id: &str) -> Option<ToolObserver> {
+    if !enabled(config) {
+        return None;
+    }
+    let config = config.clone();
+    let channel_id = channel_id.to_string();
+    Some(Arc::new(move |event: &StreamEvent| {
+        if let Some(kind) = tool_event(event) {
+            publish(&config, &channel_id, kind);
+        }
+    }))
+}
+
+fn tool_event(event: &StreamEvent) -> Option<MirrorEventKind> {
+    match event {
+        StreamEvent::ToolCallStart {
+            name, arguments, ..
+        } => Some(MirrorEventKind::ToolCallStart {
+            name: name.clone(),
+            detail: extract_tool_detail(name, arguments),
+        }),
+        StreamEvent::ToolCallEnd { name, output, .. } => Some(MirrorEventKind::ToolCallEnd {
+            name: name.clone(),
+            output: crate::utils::safe_truncate(output, MAX_OUTPUT_BYTES).to_string(),
+        }),
+        StreamEvent::Content(_) | StreamEvent::Done => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mirror_events() {
+        let mut receiver = subscribe();
+        let config = Config::default();
+        publish(&config, "42", MirrorEventKind::Reply { text: "hi".into() });
+        assert!(tool_observer(&config, "42").is_none());
+
+        let config: Config = toml::from_str(
+            r#"
+            [channels.discord]
+            token = "test-token"
+            mirror = true
+            "#,
+        )
+        .unwrap();
+        let observer = tool_observer(&config, "42").unwrap();
+        observer(&StreamEvent::ToolCallEnd {
+            name: "bash".into(),
+            id: "1".into(),
+            output: "x".repeat(600),
+            warnings: Vec::new(),
+        });
+        observer(&StreamEvent::Done);
+
+        // Only the event published with the mirror on arrives
+        let event = receiver.try_recv().unwrap();
+        assert_eq!(event.channel_id, "42");
+        assert!(matches!(
+            event.kind,
+            MirrorEventKind::ToolCallEnd { ref output, .. } if output.len() == MAX_OUTPUT_BYTES
+        ));
+        assert!(receiver.try_recv().is_err());
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "tool_call_end");
+        assert_eq!(serde_json::from_value::<MirrorEvent>(json).unwrap(), event);
+    }
+}
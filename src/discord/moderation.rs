@@ -0,0 +1,461 @@
+//! Moderation mode
+//!
+//! Guilds with `[channels.discord.guilds.moderation] enabled = true` have
+//! every message in their channels scored for spam/toxicity before the agent
+//! sees it, either by simple rules (blocked words, flooding, mass mentions,
+//! link spam, shouting) or by asking the model. Depending on the score a
+//! message is flagged for review, deleted, or deleted with the author timed
+//! out. Every action is appended to `discord/moderation.jsonl` in the state
+//! directory and, if configured, reported to a log channel.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use super::QueuedMessage;
+use super::processor::{HandlerContext, MessageHandler};
+use crate::agent::{LLMProvider, LLMResponseContent, Message, Role, create_provider};
+use crate::config::{Config, DiscordModerationConfig};
+
+/// Window for the flood/repeat rules
+const FLOOD_WINDOW: Duration = Duration::from_secs(60);
+
+/// Maximum bytes of message content kept in the moderation log
+const LOG_EXCERPT_BYTES: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ModerationAction {
+    None,
+    Flag,
+    Delete,
+    Timeout,
+}
+
+impl ModerationAction {
+    fn for_score(score: f32, cfg: &DiscordModerationConfig) -> Self {
+        if score >= cfg.timeout_threshold {
+            ModerationAction::Timeout
+        } else if score >= cfg.delete_threshold {
+            ModerationAction::Delete
+        } else if score >= cfg.flag_threshold {
+            ModerationAction::Flag
+        } else {
+            ModerationAction::None
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ModerationAction::None => "none",
+            ModerationAction::Flag => "flag",
+            ModerationAction::Delete => "delete",
+            ModerationAction::Timeout => "timeout",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Verdict {
+    score: f32,
+    #[serde(default)]
+    reason: String,
+}
+
+impl Verdict {
+    fn clean() -> Self {
+        Self {
+            score: 0.0,
+            reason: String::new(),
+        }
+    }
+}
+
+/// Recent messages per author for the flood/repeat rules
+#[derive(Default)]
+struct RecentMessages {
+    by_author: HashMap<String, VecDeque<(Instant, String)>>,
+}
+
+impl RecentMessages {
+    /// Record a message and return (messages in window, identical messages in window)
+    fn record(&mut self, author_id: &str, content: &str, now: Instant) -> (usize, usize) {
+        // Forget authors with nothing left in the window
+        self.by_author.retain(|_, history| {
+            while history
+                .front()
+                .is_some_and(|(t, _)| now.duration_since(*t) > FLOOD_WINDOW)
+            {
+                history.pop_front();
+            }
+            !history.is_empty()
+        });
+        let history = self.by_author.entry(author_id.to_string()).or_default();
+        history.push_back((now, content.to_lowercase()));
+
+        let normalized = content.to_lowercase();
+        let repeats = history.iter().filter(|(_, c)| *c == normalized).count();
+        (history.len(), repeats)
+    }
+}
+
+/// Score a message with keyword and spam heuristics
+fn score_rules(
+    msg: &QueuedMessage,
+    cfg: &DiscordModerationConfig,
+    recent: &mut RecentMessages,
+) -> Verdict {
+    let mut verdict = Verdict::clean();
+    let mut raise = |score: f32, reason: String| {
+        if score > verdict.score {
+            verdict = Verdict { score, reason };
+        }
+    };
+
+    let lower = msg.content.to_lowercase();
+    if let Some(word) = cfg
+        .blocked_words
+        .iter()
+        .find(|w| !w.is_empty() && lower.contains(&w.to_lowercase()))
+    {
+        raise(1.0, format!("blocked word: {}", word));
+    }
+
+    let (in_window, repeats) = recent.record(&msg.author_id, &msg.content, Instant::now());
+    if repeats >= 3 && !msg.content.trim().is_empty() {
+        raise(0.9, format!("same message posted {} times", repeats));
+    } else if in_window >= 10 {
+        raise(0.7, format!("{} messages in a minute", in_window));
+    }
+
+    if msg.mention_count >= 5 {
+        raise(0.85, format!("mass mention ({} users)", msg.mention_count));
+    }
+
+    let links = Regex::new(r"https?://")
+        .unwrap()
+        .find_iter(&msg.content)
+        .count();
+    if links >= 4 {
+        raise(0.6, format!("{} links", links));
+    }
+
+    let letters: Vec<char> = msg.content.chars().filter(|c| c.is_alphabetic()).collect();
+    let upper = letters.iter().filter(|c| c.is_uppercase()).count();
+    if letters.len() >= 20 && upper * 10 >= letters.len() * 8 {
+        raise(0.5, "excessive caps".to_string());
+    }
+
+    verdict
+}
+
+/// Ask the model to score a message
+async fn score_llm(provider: &dyn LLMProvider, msg: &QueuedMessage) -> Result<Verdict> {
+    let prompt = format!(
+        "Rate the following Discord message for spam, scams, harassment, hate or other \
+         toxic content. Reply with only a JSON object: \
+         {{\"score\": <0.0 = fine, 1.0 = clearly abusive>, \"reason\": \"<short reason>\"}}\n\n\
+         Message from {}:\n{}",
+        msg.author_name, msg.content
+    );
+    let messages = vec![Message {
+        role: Role::User,
+        content: prompt,
+        tool_calls: None,
+        tool_call_id: None,
+        images: Vec::new(),
+    }];
+
+    let response = provider.chat(&messages, None).await?;
+    let LLMResponseContent::Text(text) = response.content else {
+        anyhow::bail!("moderation model returned tool calls");
+    };
+    parse_verdict(&text)
+}
+
+/// Extract the first JSON object from the model's reply
+fn parse_verdict(text: &str) -> Result<Verdict> {
+    let start = text
+        .find('{')
+        .ok_or_else(|| anyhow::anyhow!("no JSON in moderation reply: {}", text))?;
+    let end = text
+        .rfind('}')
+        .filter(|&end| end > start)
+        .ok_or_else(|| anyhow::anyhow!("no JSON in moderation reply: {}", text))?;
+    let mut verdict: Verdict = serde_json::from_str(&text[start..=end])?;
+    verdict.score = verdict.score.clamp(0.0, 1.0);
+    Ok(verdict)
+}
+
+/// Scores messages from moderated guilds before passing the rest on
+pub struct ModerationHandler {
+    inner: Arc<dyn MessageHandler>,
+    recent: Mutex<RecentMessages>,
+    /// Providers for "llm" scorers, keyed by model
+    providers: HashMap<String, Box<dyn LLMProvider>>,
+    log_path: Option<PathBuf>,
+}
+
+impl ModerationHandler {
+    pub fn new(config: &Config, inner: Arc<dyn MessageHandler>) -> Self {
+        let mut providers = HashMap::new();
+        let guilds = config.channels.discord.iter().flat_map(|d| d.guilds.iter());
+        for guild in guilds.filter(|g| g.moderation.enabled && g.moderation.scorer == "llm") {
+            let model = moderation_model(config, &guild.moderation);
+            if providers.contains_key(&model) {
+                continue;
+            }
+            match create_provider(&model, config) {
+                Ok(provider) => {
+                    providers.insert(model, provider);
+                }
+                Err(e) => warn!(
+                    "Moderation model {} unavailable, using rules instead: {}",
+                    model, e
+                ),
+            }
+        }
+
+        Self {
+            inner,
+            recent: Mutex::new(RecentMessages::default()),
+            providers,
+            log_path: crate::agent::get_state_dir()
+                .ok()
+                .map(|dir| dir.join("discord").join("moderation.jsonl")),
+        }
+    }
+
+    /// Score and act on one message. Returns false if it was removed.
+    async fn moderate(&self, msg: &QueuedMessage, ctx: &HandlerContext) -> bool {
+        let Some(guild_id) = msg.guild_id.as_deref() else {
+            return true;
+        };
+        let Some(cfg) = ctx
+            .config()
+            .channels
+            .discord
+            .as_ref()
+            .and_then(|d| d.guilds.iter().find(|g| g.guild_id == guild_id))
+            .map(|g| &g.moderation)
+            .filter(|m| m.enabled)
+        else {
+            return true;
+        };
+
+        if msg
+            .author_roles
+            .iter()
+            .any(|r| cfg.protected_roles.contains(r))
+        {
+            return true;
+        }
+
+        let rules = score_rules(msg, cfg, &mut self.recent.lock().unwrap());
+        let verdict = match self.providers.get(&moderation_model(ctx.config(), cfg)) {
+            Some(provider) if cfg.scorer == "llm" && rules.score < 1.0 => {
+                match score_llm(provider.as_ref(), msg).await {
+                    Ok(llm) if llm.score >= rules.score => llm,
+                    Ok(_) => rules,
+                    Err(e) => {
+                        warn!("LLM moderation failed, using rules: {}", e);
+                        rules
+                    }
+                }
+            }
+            _ => rules,
+        };
+
+        let action = ModerationAction::for_score(verdict.score, cfg);
+        if action == ModerationAction::None {
+            return true;
+        }
+
+        info!(
+            "Moderation: {} message {} from {} (score {:.2}: {})",
+            action.as_str(),
+            msg.message_id,
+            msg.author_name,
+            verdict.score,
+            verdict.reason
+        );
+
+        let rest = ctx.rest();
+        let mut errors = Vec::new();
+        if action >= ModerationAction::Delete
+            && let Err(e) = rest.delete_message(&msg.channel_id, &msg.message_id).await
+        {
+            errors.push(format!("delete failed: {}", e));
+        }
+        if action == ModerationAction::Timeout {
+            let duration = Duration::from_secs(cfg.timeout_minutes * 60);
+            if let Err(e) = rest
+                .timeout_member(guild_id, &msg.author_id, duration)
+                .await
+            {
+                errors.push(format!("timeout failed: {}", e));
+            }
+        }
+
+        self.log_action(msg, &verdict, action, &errors);
+
+        if let Some(ref log_channel) = cfg.log_channel {
+            let report = format!(
+                "🚩 **{}** message from <@{}> in <#{}> (score {:.2}: {}){}\n> {}",
+                action.as_str(),
+                msg.author_id,
+                msg.channel_id,
+                verdict.score,
+                verdict.reason,
+                if errors.is_empty() {
+                    String::new()
+                } else {
+                    format!(" — {}", errors.join(", "))
+                },
+                crate::utils::safe_truncate(&msg.content, LOG_EXCERPT_BYTES).replace('\n', "\n> ")
+            );
+            if let Err(e) = rest.send_message(log_channel, &report, None).await {
+                warn!("Failed to post moderation report: {}", e);
+            }
+        }
+
+        // A failed delete leaves the message up, so let the agent see it
+        action < ModerationAction::Delete || !errors.is_empty()
+    }
+
+    fn log_action(
+        &self,
+        msg: &QueuedMessage,
+        verdict: &Verdict,
+        action: ModerationAction,
+        errors: &[String],
+    ) {
+        let Some(ref path) = self.log_path else {
+            return;
+        };
+        let entry = json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "guild_id": msg.guild_id,
+            "channel_id": msg.channel_id,
+            "message_id": msg.message_id,
+            "author_id": msg.author_id,
+            "author_name": msg.author_name,
+            "content": crate::utils::safe_truncate(&msg.content, LOG_EXCERPT_BYTES),
+            "score": verdict.score,
+            "reason": verdict.reason,
+            "action": action.as_str(),
+            "errors": errors,
+        });
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| OpenOptions::new().create(true).append(true).open(path))
+            .and_then(|mut f| writeln!(f, "{}", entry));
+        if let Err(e) = result {
+            warn!("Failed to write moderation log: {}", e);
+        }
+    }
+}
+
+fn moderation_model(config: &Config, cfg: &DiscordModerationConfig) -> String {
+    cfg.model
+        .clone()
+        .unwrap_or_else(|| config.agent.default_model.clone())
+}
+
+#[async_trait]
+impl MessageHandler for ModerationHandler {
+    async fn handle(&self, batch: &[QueuedMessage], ctx: &HandlerContext) {
+        let mut kept = Vec::with_capacity(batch.len());
+        for msg in batch {
+            if self.moderate(msg, ctx).await {
+                kept.push(msg.clone());
+            }
+        }
+        if !kept.is_empty() {
+            self.inner.handle(&kept, ctx).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(author_id: &str, content: &str) -> QueuedMessage {
+        QueuedMessage {
+            channel_id: "c1".to_string(),
+            guild_id: Some("g1".to_string()),
+            message_id: "m1".to_string(),
+            author_id: author_id.to_string(),
+            author_name: "alice".to_string(),
+            author_roles: Vec::new(),
+            content: content.to_string(),
+            image_urls: Vec::new(),
+            mention_count: 0,
+            addressed: false,
+        }
+    }
+
+    #[test]
+    fn test_rules() {
+        let cfg = DiscordModerationConfig {
+            blocked_words: vec!["free nitro".to_string()],
+            ..Default::default()
+        };
+        let mut recent = RecentMessages::default();
+
+        let verdict = score_rules(&message("1", "hello there"), &cfg, &mut recent);
+        assert_eq!(verdict.score, 0.0);
+
+        let verdict = score_rules(&message("2", "Get FREE Nitro here"), &cfg, &mut recent);
+        assert_eq!(verdict.score, 1.0);
+        assert_eq!(
+            ModerationAction::for_score(verdict.score, &cfg),
+            ModerationAction::Timeout
+        );
+
+        let mut spam = message("3", "join my server");
+        spam.mention_count = 8;
+        let verdict = score_rules(&spam, &cfg, &mut recent);
+        assert_eq!(
+            ModerationAction::for_score(verdict.score, &cfg),
+            ModerationAction::Delete
+        );
+    }
+
+    #[test]
+    fn test_repeated_messages() {
+        let cfg = DiscordModerationConfig::default();
+        let mut recent = RecentMessages::default();
+        for _ in 0..2 {
+            let verdict = score_rules(&message("1", "buy now"), &cfg, &mut recent);
+            assert_eq!(verdict.score, 0.0);
+        }
+        let verdict = score_rules(&message("1", "BUY NOW"), &cfg, &mut recent);
+        assert_eq!(verdict.score, 0.9);
+
+        // Authors whose window is over are dropped
+        let later = Instant::now() + FLOOD_WINDOW * 2;
+        assert_eq!(recent.record("2", "hi", later), (1, 1));
+        assert_eq!(recent.by_author.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_verdict() {
+        let verdict =
+            parse_verdict("Sure:\n```json\n{\"score\": 1.4, \"reason\": \"scam link\"}\n```")
+                .unwrap();
+        assert_eq!(verdict.score, 1.0);
+        assert_eq!(verdict.reason, "scam link");
+        assert!(parse_verdict("looks fine").is_err());
+    }
+}
@@ -0,0 +1,289 @@
+//! Outbox for messages that could not be sent
+//!
+//! A reply or heartbeat summary that Discord surely didn't get (network
+//! down, rate limited) is saved to `discord/outbox.sqlite` in the state
+//! directory instead of being lost. After a timeout or a 5xx the message may
+//! have been posted, so it isn't saved, as a resend could post it twice. The bot sends saved messages
+//! again, oldest first, when the gateway reconnects and every minute while
+//! it runs. Messages that Discord rejects outright, or that are still
+//! undelivered after a day, are dropped and reported in the error digest.
+//! A long message that failed halfway is resent whole.
+
+use anyhow::Result;
+use reqwest::Method;
+use rusqlite::params;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::rest::{DiscordRest, RestError, RestResult};
+use crate::db::SqlitePool;
+
+/// Oldest message still worth delivering (seconds)
+const MAX_AGE_SECS: i64 = 24 * 60 * 60;
+
+/// Messages sent per flush
+const FLUSH_BATCH: usize = 20;
+
+/// How often the running bot retries saved messages
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct OutboxEntry {
+    pub id: i64,
+    pub channel_id: String,
+    pub content: String,
+    pub embeds: Option<Vec<serde_json::Value>>,
+    pub attempts: u32,
+    pub created_at: i64,
+}
+
+#[derive(Clone)]
+pub struct Outbox {
+    pool: SqlitePool,
+}
+
+impl Outbox {
+    /// Open the shared outbox at `~/.localgpt/discord/outbox.sqlite`
+    pub fn open_default() -> Result<Self> {
+        Self::open(
+            &crate::agent::get_state_dir()?
+                .join("discord")
+                .join("outbox.sqlite"),
+        )
+    }
+
+    pub fn open(db_path: &Path) -> Result<Self> {
+        Self::init(SqlitePool::shared(db_path, None)?)
+    }
+
+    /// In-memory outbox (tests)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(SqlitePool::open_in_memory(None)?)
+    }
+
+    fn init(pool: SqlitePool) -> Result<Self> {
+        pool.get()?.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS outbox (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                channel_id TEXT NOT NULL,
+                content TEXT NOT NULL,
+                embeds TEXT,
+                attempts INTEGER NOT NULL DEFAULT 1,
+                last_error TEXT,
+                created_at INTEGER NOT NULL
+            );
+            "#,
+        )?;
+
+        Ok(Self { pool })
+    }
+
+    pub fn push(
+        &self,
+        channel_id: &str,
+        content: &str,
+        embeds: Option<&[serde_json::Value]>,
+        error: &str,
+    ) -> Result<i64> {
+        let embeds = embeds.map(serde_json::to_string).transpose()?;
+        let conn = self.pool.get()?;
+        conn.execute(
+            "INSERT INTO outbox (channel_id, content, embeds, last_error, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                channel_id,
+                content,
+                embeds,
+                error,
+                chrono::Utc::now().timestamp()
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Saved messages, oldest first
+    pub fn pending(&self, limit: usize) -> Result<Vec<OutboxEntry>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, channel_id, content, embeds, attempts, created_at FROM outbox
+             ORDER BY id LIMIT ?1",
+        )?;
+        let entries = stmt
+            .query_map(params![limit as i64], |row| {
+                let embeds: Option<String> = row.get(3)?;
+                Ok(OutboxEntry {
+                    id: row.get(0)?,
+                    channel_id: row.get(1)?,
+                    content: row.get(2)?,
+                    embeds: embeds.and_then(|e| serde_json::from_str(&e).ok()),
+                    attempts: row.get(4)?,
+                    created_at: row.get(5)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    pub fn remove(&self, id: i64) -> Result<()> {
+        self.pool
+            .get()?
+            .execute("DELETE FROM outbox WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    fn record_failure(&self, id: i64, error: &str) -> Result<()> {
+        self.pool.get()?.execute(
+            "UPDATE outbox SET attempts = attempts + 1, last_error = ?2 WHERE id = ?1",
+            params![id, error],
+        )?;
+        Ok(())
+    }
+}
+
+/// Keep a message whose send failed with `error`, if it can be resent
+/// without posting it twice.
+/// Returns whether it was saved.
+pub fn save_failed(
+    channel_id: &str,
+    content: &str,
+    embeds: Option<&[serde_json::Value]>,
+    error: &RestError,
+) -> bool {
+    if !error.may_resend(&Method::POST) {
+        return false;
+    }
+    match Outbox::open_default()
+        .and_then(|outbox| outbox.push(channel_id, content, embeds, &error.to_string()))
+    {
+        Ok(id) => {
+            warn!(
+                "Message to channel {} failed ({}), saved to outbox as #{}",
+                channel_id, error, id
+            );
+            true
+        }
+        Err(e) => {
+            warn!("Failed to save message to outbox: {}", e);
+            false
+        }
+    }
+}
+
+/// Send a message, saving it to the outbox if Discord can't be reached
+pub async fn send_or_save(
+    rest: &dyn DiscordRest,
+    channel_id: &str,
+    content: &str,
+    embeds: Option<Vec<serde_json::Value>>,
+) -> RestResult<()> {
+    match rest.send_message(channel_id, content, embeds.clone()).await {
+        Err(e) if save_failed(channel_id, content, embeds.as_deref(), &e) => Ok(()),
+        result => result.map(drop),
+    }
+}
+
+/// Send saved messages, oldest first, stopping at the first one that still
+/// can't be delivered. Returns how many were sent.
+pub async fn flush(rest: &dyn DiscordRest, outbox: &Outbox) -> Result<usize> {
+    let now = chrono::Utc::now().timestamp();
+    let mut sent = 0;
+
+    for entry in outbox.pending(FLUSH_BATCH)? {
+        if now - entry.created_at > MAX_AGE_SECS {
+            warn!(
+                "Dropping outbox message #{} to channel {}: undelivered for a day",
+                entry.id, entry.channel_id
+            );
+            crate::digest::report(
+                "discord",
+                &format!("channel {}", entry.channel_id),
+                "Outbox message dropped after a day without delivery",
+            );
+            outbox.remove(entry.id)?;
+            continue;
+        }
+
+        match rest
+            .send_message(&entry.channel_id, &entry.content, entry.embeds.clone())
+            .await
+        {
+            Ok(_) => {
+                outbox.remove(entry.id)?;
+                sent += 1;
+            }
+            Err(e) if e.may_resend(&Method::POST) => {
+                outbox.record_failure(entry.id, &e.to_string())?;
+                break;
+            }
+            Err(e) => {
+                warn!(
+                    "Dropping outbox message #{} to channel {}: {}",
+                    entry.id, entry.channel_id, e
+                );
+                crate::digest::report(
+                    "discord",
+                    &format!("channel {}", entry.channel_id),
+                    &e.to_string(),
+                );
+                outbox.remove(entry.id)?;
+            }
+        }
+    }
+
+    if sent > 0 {
+        info!("Delivered {} message(s) from the outbox", sent);
+    }
+    Ok(sent)
+}
+
+/// Flush the shared outbox, logging failures
+pub(super) async fn flush_default(rest: Arc<dyn DiscordRest>) {
+    let result = match Outbox::open_default() {
+        Ok(outbox) => flush(rest.as_ref(), &outbox).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warn!("Failed to flush the Discord outbox: {}", e);
+    }
+}
+
+/// Retry saved messages periodically while the bot runs
+pub(super) async fn run(rest: Arc<dyn DiscordRest>) {
+    let mut ticker = tokio::time::interval(FLUSH_INTERVAL);
+    loop {
+        ticker.tick().await;
+        flush_default(Arc::clone(&rest)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discord::rest::MockDiscordRest;
+
+    #[tokio::test]
+    async fn test_flush_stops_while_discord_is_down() {
+        let outbox = Outbox::open_in_memory().unwrap();
+        outbox.push("1", "first", None, "timeout").unwrap();
+        outbox.push("2", "second", None, "timeout").unwrap();
+
+        let mut rest = MockDiscordRest::new();
+        rest.expect_send_message()
+            .times(1)
+            .returning(|_, _, _| Err(RestError::RateLimited(Duration::from_secs(5))));
+        assert_eq!(flush(&rest, &outbox).await.unwrap(), 0);
+        let pending = outbox.pending(10).unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].attempts, 2);
+
+        let mut rest = MockDiscordRest::new();
+        rest.expect_send_message()
+            .times(2)
+            .returning(|_, _, _| Ok(Vec::new()));
+        assert_eq!(flush(&rest, &outbox).await.unwrap(), 2);
+        assert!(outbox.pending(10).unwrap().is_empty());
+    }
+}
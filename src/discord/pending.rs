@@ -0,0 +1,190 @@
+//! Queued messages kept across restarts
+//!
+//! A message accepted by the gateway is written to
+//! `discord/pending.sqlite` before it is queued and removed once its batch
+//! has been handled. The daemon stops the bot by aborting it, so whatever
+//! was waiting in the queue or in a batch at that moment is still in the
+//! store on the next start: messages younger than `replay_max_age` are
+//! queued again and answered, older ones are dropped. Questions asked while
+//! the daemon restarts for a deploy still get answers.
+
+use anyhow::Result;
+use rusqlite::params;
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use super::QueuedMessage;
+use crate::db::SqlitePool;
+
+#[derive(Clone)]
+pub struct PendingStore {
+    pool: SqlitePool,
+}
+
+impl PendingStore {
+    /// Open the shared store at `~/.localgpt/discord/pending.sqlite`
+    pub fn open_default() -> Result<Self> {
+        Self::open(
+            &crate::agent::get_state_dir()?
+                .join("discord")
+                .join("pending.sqlite"),
+        )
+    }
+
+    pub fn open(db_path: &Path) -> Result<Self> {
+        Self::init(SqlitePool::shared(db_path, None)?)
+    }
+
+    /// In-memory store (tests)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(SqlitePool::open_in_memory(None)?)
+    }
+
+    fn init(pool: SqlitePool) -> Result<Self> {
+        pool.get()?.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS pending_messages (
+                message_id TEXT PRIMARY KEY,
+                queued_at INTEGER NOT NULL,
+                message TEXT NOT NULL
+            );
+            "#,
+        )?;
+
+        Ok(Self { pool })
+    }
+
+    /// Remember a message until its batch is handled
+    pub fn save(&self, msg: &QueuedMessage) -> Result<()> {
+        self.pool.get()?.execute(
+            "INSERT OR REPLACE INTO pending_messages (message_id, queued_at, message)
+             VALUES (?1, ?2, ?3)",
+            params![
+                msg.message_id,
+                chrono::Utc::now().timestamp(),
+                serde_json::to_string(msg)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Forget handled messages
+    pub fn remove(&self, message_ids: &[String]) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        for id in message_ids {
+            tx.execute(
+                "DELETE FROM pending_messages WHERE message_id = ?1",
+                params![id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Drop messages older than `max_age` and return the rest, oldest first.
+    /// They stay stored until handled again.
+    pub fn fresh(&self, max_age: Duration) -> Result<Vec<QueuedMessage>> {
+        let cutoff = chrono::Utc::now().timestamp() - max_age.as_secs() as i64;
+        let conn = self.pool.get()?;
+        let dropped = conn.execute(
+            "DELETE FROM pending_messages WHERE queued_at < ?1",
+            params![cutoff],
+        )?;
+        if dropped > 0 {
+            info!(
+                "Dropped {} pending Discord message(s) too old to answer",
+                dropped
+            );
+        }
+
+        let mut stmt =
+            conn.prepare("SELECT message FROM pending_messages ORDER BY queued_at, rowid")?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows
+            .iter()
+            .filter_map(|json| serde_json::from_str(json).ok())
+            .collect())
+    }
+}
+
+/// Queue the messages left over from the last run
+pub(super) async fn replay(
+    store: PendingStore,
+    max_age: Duration,
+    queue: mpsc::Sender<QueuedMessage>,
+) {
+    let messages = match store.fresh(max_age) {
+        Ok(messages) => messages,
+        Err(e) => {
+            warn!("Failed to load pending Discord messages: {}", e);
+            return;
+        }
+    };
+    if messages.is_empty() {
+        return;
+    }
+
+    info!(
+        "Replaying {} Discord message(s) queued before the restart",
+        messages.len()
+    );
+    for msg in messages {
+        if queue.send(msg).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str) -> QueuedMessage {
+        QueuedMessage {
+            channel_id: "42".to_string(),
+            guild_id: None,
+            message_id: id.to_string(),
+            author_id: "7".to_string(),
+            author_name: "alice".to_string(),
+            author_roles: Vec::new(),
+            content: format!("question {}", id),
+            image_urls: Vec::new(),
+            mention_count: 0,
+            addressed: true,
+        }
+    }
+
+    #[test]
+    fn test_save_remove_and_expire() {
+        let store = PendingStore::open_in_memory().unwrap();
+        store.save(&message("1")).unwrap();
+        store.save(&message("2")).unwrap();
+        store.save(&message("3")).unwrap();
+        store.remove(&["2".to_string()]).unwrap();
+
+        let fresh = store.fresh(Duration::from_secs(600)).unwrap();
+        let ids: Vec<&str> = fresh.iter().map(|m| m.message_id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "3"]);
+        assert_eq!(fresh[0].content, "question 1");
+        // Still stored until handled
+        assert_eq!(store.fresh(Duration::from_secs(600)).unwrap().len(), 2);
+
+        store
+            .pool
+            .get()
+            .unwrap()
+            .execute(
+                "UPDATE pending_messages SET queued_at = 0 WHERE message_id = '1'",
+                [],
+            )
+            .unwrap();
+        let fresh = store.fresh(Duration::from_secs(600)).unwrap();
+        assert_eq!(fresh.len(), 1);
+        assert_eq!(fresh[0].message_id, "3");
+    }
+}
@@ -0,0 +1,153 @@
+//! Per-user capabilities
+//!
+//! Anything in a reply can be steered by whoever wrote the prompt, so
+//! side-effecting tags and tools only run when the message authors hold the
+//! matching capability from `[channels.discord.permissions]`. A batch mixing
+//! several authors gets only the capabilities they all share.
+
+use std::collections::HashSet;
+
+use super::QueuedMessage;
+use crate::config::{DiscordCapability, DiscordPermissionsConfig};
+
+/// Capabilities the agent may use while answering a batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Permissions {
+    granted: HashSet<DiscordCapability>,
+}
+
+impl Permissions {
+    /// Capabilities of a single user
+    pub fn for_user(config: &DiscordPermissionsConfig, user_id: &str, roles: &[String]) -> Self {
+        let mut granted: HashSet<DiscordCapability> = config.default.iter().copied().collect();
+        for grant in &config.grants {
+            let applies = grant.users.iter().any(|u| u == user_id)
+                || grant.roles.iter().any(|r| roles.contains(r));
+            if applies {
+                granted.extend(grant.capabilities.iter().copied());
+            }
+        }
+        Self { granted }
+    }
+
+    /// Capabilities shared by every author in a batch
+    pub fn for_batch(config: &DiscordPermissionsConfig, batch: &[&QueuedMessage]) -> Self {
+        let mut authors = batch
+            .iter()
+            .map(|m| Self::for_user(config, &m.author_id, &m.author_roles));
+        let Some(mut shared) = authors.next() else {
+            return Self {
+                granted: HashSet::new(),
+            };
+        };
+        for other in authors {
+            shared.granted.retain(|c| other.granted.contains(c));
+        }
+        shared
+    }
+
+    pub fn allows(&self, capability: DiscordCapability) -> bool {
+        self.granted.contains(&capability)
+    }
+
+    /// Agent tools that must be refused
+    pub fn denied_tools(&self) -> Vec<String> {
+        let gated: [(DiscordCapability, &[&str]); 2] = [
+            // Background jobs and delegated agents get tools of their own,
+            // bash included
+            (
+                DiscordCapability::Commands,
+                &["bash", "job_start", "delegate"],
+            ),
+            (
+                DiscordCapability::MemoryWrite,
+                &["write_file", "edit_file", "save_memory", "memory_log"],
+            ),
+        ];
+        gated
+            .iter()
+            .filter(|(capability, _)| !self.allows(*capability))
+            .flat_map(|(_, tools)| tools.iter().map(|t| t.to_string()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DiscordPermissionGrant;
+
+    fn message(author_id: &str, roles: &[&str]) -> QueuedMessage {
+        QueuedMessage {
+            channel_id: "c1".to_string(),
+            guild_id: Some("g1".to_string()),
+            message_id: "m1".to_string(),
+            author_id: author_id.to_string(),
+            author_name: author_id.to_string(),
+            author_roles: roles.iter().map(|r| r.to_string()).collect(),
+            content: String::new(),
+            image_urls: Vec::new(),
+            mention_count: 0,
+            addressed: true,
+        }
+    }
+
+    fn config() -> DiscordPermissionsConfig {
+        DiscordPermissionsConfig {
+            grants: vec![
+                DiscordPermissionGrant {
+                    roles: vec!["admin".to_string()],
+                    users: Vec::new(),
+                    capabilities: vec![DiscordCapability::Commands],
+                },
+                DiscordPermissionGrant {
+                    roles: Vec::new(),
+                    users: vec!["owner".to_string()],
+                    capabilities: vec![DiscordCapability::Commands],
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_grants() {
+        let config = config();
+
+        let user = Permissions::for_user(&config, "someone", &[]);
+        assert!(user.allows(DiscordCapability::CrossPost));
+        assert!(!user.allows(DiscordCapability::Commands));
+        assert_eq!(user.denied_tools(), vec!["bash", "job_start", "delegate"]);
+
+        let admin = Permissions::for_user(&config, "someone", &["admin".to_string()]);
+        assert!(admin.allows(DiscordCapability::Commands));
+        assert!(admin.denied_tools().is_empty());
+
+        assert!(Permissions::for_user(&config, "owner", &[]).allows(DiscordCapability::Commands));
+    }
+
+    #[test]
+    fn test_memory_write_tools() {
+        let config = DiscordPermissionsConfig {
+            default: vec![DiscordCapability::Commands],
+            ..Default::default()
+        };
+        let user = Permissions::for_user(&config, "someone", &[]);
+        assert_eq!(
+            user.denied_tools(),
+            vec!["write_file", "edit_file", "save_memory", "memory_log"]
+        );
+    }
+
+    #[test]
+    fn test_batch_shares_capabilities() {
+        let config = config();
+        let admin = message("a", &["admin"]);
+        let other = message("b", &[]);
+
+        assert!(Permissions::for_batch(&config, &[&admin]).allows(DiscordCapability::Commands));
+        let mixed = Permissions::for_batch(&config, &[&admin, &other]);
+        assert!(!mixed.allows(DiscordCapability::Commands));
+        assert!(mixed.allows(DiscordCapability::MemoryWrite));
+    }
+}
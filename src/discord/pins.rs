@@ -0,0 +1,283 @@
+//! Pinned context
+//!
+//! Snippets and files always given to a channel's agent, e.g. the project
+//! README in a dev channel or the house rules in a community channel. They
+//! come from `[channels.discord.pins."<channel_id>"]` and from `/pin`, which
+//! keeps them in `discord/pins.sqlite` in the state directory. Files are
+//! read every turn, so edits apply without a restart. Relative paths are
+//! resolved against the workspace; files pinned with `/pin` must be inside
+//! it.
+
+use anyhow::{Result, bail};
+use rusqlite::params;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::config::{Config, DiscordPinnedContext};
+use crate::db::SqlitePool;
+
+/// Longest file included (characters); the rest is cut
+const MAX_FILE_CHARS: usize = 8000;
+
+/// Longest snippet preview in `/pin` listings (characters)
+const PREVIEW_CHARS: usize = 80;
+
+/// A pin added with `/pin`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pin {
+    pub id: i64,
+    /// Whether `content` is a file path rather than a snippet
+    pub file: bool,
+    pub content: String,
+}
+
+#[derive(Clone)]
+pub struct PinStore {
+    pool: SqlitePool,
+}
+
+impl PinStore {
+    /// Open the shared store at `~/.localgpt/discord/pins.sqlite`
+    pub fn open_default() -> Result<Self> {
+        Self::open(
+            &crate::agent::get_state_dir()?
+                .join("discord")
+                .join("pins.sqlite"),
+        )
+    }
+
+    pub fn open(db_path: &Path) -> Result<Self> {
+        Self::init(SqlitePool::shared(db_path, None)?)
+    }
+
+    /// In-memory store (tests)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(SqlitePool::open_in_memory(None)?)
+    }
+
+    fn init(pool: SqlitePool) -> Result<Self> {
+        pool.get()?.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS pins (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                channel_id TEXT NOT NULL,
+                file INTEGER NOT NULL,
+                content TEXT NOT NULL,
+                created_by TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_pins_channel ON pins(channel_id);
+            "#,
+        )?;
+
+        Ok(Self { pool })
+    }
+
+    pub fn add(
+        &self,
+        channel_id: &str,
+        file: bool,
+        content: &str,
+        created_by: &str,
+    ) -> Result<i64> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "INSERT INTO pins (channel_id, file, content, created_by, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                channel_id,
+                file,
+                content,
+                created_by,
+                chrono::Utc::now().timestamp()
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// A channel's pins, oldest first
+    pub fn list(&self, channel_id: &str) -> Result<Vec<Pin>> {
+        let conn = self.pool.get()?;
+        let mut stmt =
+            conn.prepare("SELECT id, file, content FROM pins WHERE channel_id = ?1 ORDER BY id")?;
+        let pins = stmt
+            .query_map(params![channel_id], |row| {
+                Ok(Pin {
+                    id: row.get(0)?,
+                    file: row.get(1)?,
+                    content: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(pins)
+    }
+
+    /// Remove one of a channel's pins. Returns false if there was none
+    /// with that ID.
+    pub fn remove(&self, channel_id: &str, id: i64) -> Result<bool> {
+        let removed = self.pool.get()?.execute(
+            "DELETE FROM pins WHERE channel_id = ?1 AND id = ?2",
+            params![channel_id, id],
+        )?;
+        Ok(removed > 0)
+    }
+}
+
+/// The channel's pinned context, formatted for the agent, if it has any
+pub(super) fn context_for(config: &Config, channel_id: &str) -> Option<String> {
+    let configured = pins_config(config, channel_id);
+    let mut snippets: Vec<String> = configured.map(|p| p.snippets.clone()).unwrap_or_default();
+    let mut files: Vec<String> = configured.map(|p| p.files.clone()).unwrap_or_default();
+    match PinStore::open_default().and_then(|store| store.list(channel_id)) {
+        Ok(pins) => {
+            for pin in pins {
+                if pin.file {
+                    files.push(pin.content);
+                } else {
+                    snippets.push(pin.content);
+                }
+            }
+        }
+        Err(e) => warn!("Failed to load pins of channel {}: {}", channel_id, e),
+    }
+
+    render(&config.workspace_path(), &snippets, &files)
+}
+
+/// The pins configured for a channel in the config file
+pub(super) fn pins_config<'a>(
+    config: &'a Config,
+    channel_id: &str,
+) -> Option<&'a DiscordPinnedContext> {
+    config.channels.discord.as_ref()?.pins.get(channel_id)
+}
+
+fn render(workspace: &Path, snippets: &[String], files: &[String]) -> Option<String> {
+    let mut sections: Vec<String> = snippets
+        .iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    for path in files {
+        match std::fs::read_to_string(resolve(workspace, path)) {
+            Ok(text) if text.chars().count() > MAX_FILE_CHARS => {
+                let cut: String = text.chars().take(MAX_FILE_CHARS).collect();
+                sections.push(format!("### {}\n{}\n[…cut]", path, cut));
+            }
+            Ok(text) => sections.push(format!("### {}\n{}", path, text.trim_end())),
+            Err(e) => warn!("Failed to read pinned file {}: {}", path, e),
+        }
+    }
+
+    if sections.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "[Pinned context for this channel]\n\n{}",
+        sections.join("\n\n")
+    ))
+}
+
+/// A pinned file's path: `~` is expanded, relative paths are in the
+/// workspace
+pub(super) fn resolve(workspace: &Path, path: &str) -> PathBuf {
+    workspace.join(shellexpand::tilde(path).as_ref())
+}
+
+/// Check that a file pinned with `/pin` exists and is in the workspace
+pub(super) fn check_file(workspace: &Path, path: &str) -> Result<()> {
+    let Ok(file) = resolve(workspace, path).canonicalize() else {
+        bail!("no such file");
+    };
+    if !file.is_file() {
+        bail!("not a file");
+    }
+    if !file.starts_with(workspace.canonicalize()?) {
+        bail!("only files in the workspace can be pinned");
+    }
+    Ok(())
+}
+
+/// The `/pin` listing of a channel's pins
+pub(super) fn format_pins(configured: Option<&DiscordPinnedContext>, pins: &[Pin]) -> String {
+    let mut lines = Vec::new();
+    if let Some(configured) = configured {
+        for snippet in &configured.snippets {
+            lines.push(format!("- (config) {}", preview(snippet)));
+        }
+        for file in &configured.files {
+            lines.push(format!("- (config) file `{}`", file));
+        }
+    }
+    for pin in pins {
+        if pin.file {
+            lines.push(format!("- `#{}` file `{}`", pin.id, pin.content));
+        } else {
+            lines.push(format!("- `#{}` {}", pin.id, preview(&pin.content)));
+        }
+    }
+
+    if lines.is_empty() {
+        return "Nothing is pinned in this channel. \
+                Add context with `/pin <text>` or `/pin file <path>`."
+            .to_string();
+    }
+    format!(
+        "Pinned context:\n{}\nRemove a pin with `/unpin <id>`.",
+        lines.join("\n")
+    )
+}
+
+fn preview(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > PREVIEW_CHARS {
+        let cut: String = text.chars().take(PREVIEW_CHARS).collect();
+        format!("{}…", cut)
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_and_render() {
+        let store = PinStore::open_in_memory().unwrap();
+        let id = store.add("1", false, "Be nice.", "alice").unwrap();
+        store.add("1", true, "README.md", "alice").unwrap();
+        store.add("2", false, "Other channel", "bob").unwrap();
+        let pins = store.list("1").unwrap();
+        assert_eq!(pins.len(), 2);
+        assert!(pins[1].file);
+        assert!(!store.remove("2", id).unwrap());
+        assert!(store.remove("1", id).unwrap());
+        assert_eq!(store.list("1").unwrap().len(), 1);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("secret.md"), "outside").unwrap();
+        let workspace = dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(workspace.join("README.md"), "# Project\n\nDetails.\n").unwrap();
+        let context = render(
+            &workspace,
+            &["House rules: no spoilers.".to_string()],
+            &["README.md".to_string(), "missing.md".to_string()],
+        )
+        .unwrap();
+        assert!(context.starts_with("[Pinned context for this channel]"));
+        assert!(context.contains("House rules: no spoilers."));
+        assert!(context.contains("### README.md\n# Project\n\nDetails."));
+        assert!(render(&workspace, &[], &["missing.md".to_string()]).is_none());
+
+        assert!(check_file(&workspace, "README.md").is_ok());
+        assert!(check_file(&workspace, "missing.md").is_err());
+        assert!(check_file(&workspace, "../secret.md").is_err());
+
+        let listing = format_pins(None, &store.list("1").unwrap());
+        assert!(listing.contains("file `README.md`"));
+        assert!(format_pins(None, &[]).starts_with("Nothing is pinned"));
+    }
+}
@@ -0,0 +1,95 @@
+//! Startup checks
+//!
+//! Before the bot connects to the gateway it asks the REST API about its own
+//! application, so configuration mistakes show up as one actionable error
+//! instead of a reconnect loop: an unsupported `api_version`, a rejected
+//! token, or the Message Content privileged intent left disabled (the bot
+//! would then see every message with empty text). If Discord can't be
+//! reached the check only warns; the gateway retries on its own.
+
+use anyhow::{Result, bail};
+use tracing::{info, warn};
+
+use super::rest::{DiscordRest, MIN_API_VERSION, RestError};
+
+/// GATEWAY_MESSAGE_CONTENT (verified bots) and
+/// GATEWAY_MESSAGE_CONTENT_LIMITED (bots in fewer than 100 servers)
+const MESSAGE_CONTENT_FLAGS: u64 = (1 << 18) | (1 << 19);
+
+pub(super) fn check_api_version(version: u8) -> Result<()> {
+    if version < MIN_API_VERSION {
+        bail!(
+            "Discord API v{} is no longer served; set channels.discord.api_version to {} or later",
+            version,
+            MIN_API_VERSION
+        );
+    }
+    Ok(())
+}
+
+/// Verify the token and the privileged intents the bot relies on
+pub(super) async fn check(rest: &dyn DiscordRest) -> Result<()> {
+    match rest.get_application().await {
+        Ok(app) if app.flags & MESSAGE_CONTENT_FLAGS == 0 => bail!(
+            "Message Content intent is disabled for {} ({}): enable it under Bot → \
+             Privileged Gateway Intents at https://discord.com/developers/applications/{}/bot",
+            app.name,
+            app.id,
+            app.id
+        ),
+        Ok(app) => {
+            info!("Discord application {} has the intents it needs", app.name);
+            Ok(())
+        }
+        Err(RestError::Api { status: 401, .. }) => {
+            bail!("Discord rejected the bot token (401); check channels.discord.token")
+        }
+        Err(e) => {
+            warn!("Could not verify the bot's Discord intents: {}", e);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discord::rest::{DiscordApplication, MockDiscordRest};
+
+    fn rest_with_flags(flags: u64) -> MockDiscordRest {
+        let mut rest = MockDiscordRest::new();
+        rest.expect_get_application().returning(move || {
+            Ok(DiscordApplication {
+                id: "42".to_string(),
+                name: "localgpt".to_string(),
+                flags,
+            })
+        });
+        rest
+    }
+
+    #[tokio::test]
+    async fn test_message_content_intent_required() {
+        assert!(check(&rest_with_flags(1 << 19)).await.is_ok());
+        let err = check(&rest_with_flags(1 << 23)).await.unwrap_err();
+        assert!(err.to_string().contains("applications/42/bot"));
+
+        let mut rest = MockDiscordRest::new();
+        rest.expect_get_application().returning(|| {
+            Err(RestError::Api {
+                status: 401,
+                body: String::new(),
+            })
+        });
+        assert!(
+            check(&rest)
+                .await
+                .unwrap_err()
+                .to_string()
+                .contains("token")
+        );
+
+        assert!(check_api_version(10).is_ok());
+        assert!(check_api_version(8).is_err());
+    }
+}
@@ -0,0 +1,1514 @@
+//! Batch processing and per-channel message handlers
+//!
+//! Queued messages are collected into short batches, bot commands are
+//! answered directly, and each channel's batch is routed to a
+//! [`MessageHandler`]. The default [`AgentHandler`] runs the channel's agent
+//! and posts its reply; other handlers can be plugged in per channel with
+//! [`DiscordBot::with_channel_handler`](super::DiscordBot::with_channel_handler).
+
+use async_trait::async_trait;
+use base64::Engine;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+use super::commands::DiscordCommand;
+use super::components::ChoiceSpec;
+use super::edits::MessageTracker;
+use super::embeds::{self, EmbedSpec};
+use super::mirror::{self, MirrorEventKind};
+use super::pending::PendingStore;
+use super::permissions::Permissions;
+use super::rest::{DiscordRest, RestError, RestResult};
+use super::sessions::{self, ActivityMap};
+use super::status::BotStatus;
+use super::tags::TagRegistry;
+use super::{
+    QueuedMessage, SharedAgentMap, custom_emoji, emoji, history, outbox, pins, reminders, settings,
+    style, tags,
+};
+use crate::agent::{
+    Agent, AgentConfig as AgentCfg, ChannelKind, ExperimentStore, ExperimentTurn, ExportOptions,
+    Fragment, ImageAttachment, LLMResponseContent, Language, Message, PromptStore, ReplyPipeline,
+    Role, Variant, circuit_statuses, format_prompt_record,
+};
+use crate::config::{Config, DiscordCapability, DiscordChannelStyle, ExperimentConfig};
+use crate::db;
+use crate::heartbeat::{get_last_heartbeat_event, now_ms};
+use crate::memory::{LogContext, MemoryManager};
+use crate::security::{AuditAction, append_audit_entry_with_detail};
+
+/// Batch delay: wait this long after first message to collect more
+const BATCH_DELAY: Duration = Duration::from_secs(3);
+
+/// Reaction on a message that could not be answered
+const FAILURE_REACTION: &str = "⚠️";
+
+/// Handles one channel's batch of queued messages
+#[async_trait]
+pub trait MessageHandler: Send + Sync {
+    async fn handle(&self, batch: &[QueuedMessage], ctx: &HandlerContext);
+}
+
+/// Shared state available to every handler
+pub struct HandlerContext {
+    pub(super) config: Config,
+    /// Plain HTTP client for downloading attachments
+    pub(super) http: Arc<reqwest::Client>,
+    pub(super) rest: Arc<dyn DiscordRest>,
+    pub(super) tracker: Arc<std::sync::Mutex<MessageTracker>>,
+    pub(super) agents: SharedAgentMap,
+    /// Last message per channel agent, for archiving idle ones
+    pub(super) activity: ActivityMap,
+    /// When the bot started processing, for `/status`
+    pub(super) started: Instant,
+    /// Queued messages kept for replay; handled ones are removed
+    pub(super) pending: Option<PendingStore>,
+    /// Tags recognized in replies
+    pub(super) tag_registry: Arc<TagRegistry>,
+}
+
+impl HandlerContext {
+    pub(super) fn new(
+        config: Config,
+        http: Arc<reqwest::Client>,
+        rest: Arc<dyn DiscordRest>,
+        tracker: Arc<std::sync::Mutex<MessageTracker>>,
+        agents: SharedAgentMap,
+    ) -> Self {
+        Self {
+            tag_registry: Arc::new(TagRegistry::new(&config)),
+            config,
+            http,
+            rest,
+            tracker,
+            agents,
+            activity: Default::default(),
+            started: Instant::now(),
+            pending: None,
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn rest(&self) -> &dyn DiscordRest {
+        self.rest.as_ref()
+    }
+
+    pub fn agents(&self) -> &SharedAgentMap {
+        &self.agents
+    }
+
+    /// Mark a message whose reply failed and collect the error for the next
+    /// digest (see [`crate::digest`]) instead of posting it to the channel
+    pub async fn report_error(&self, channel_id: &str, message_id: &str, error: &anyhow::Error) {
+        crate::digest::report(
+            "discord",
+            &format!("channel {}", channel_id),
+            &format!("{:#}", error),
+        );
+        if let Err(e) = self
+            .rest
+            .add_reaction(channel_id, message_id, FAILURE_REACTION)
+            .await
+        {
+            debug!("Failed to mark message {} as failed: {}", message_id, e);
+        }
+    }
+}
+
+/// Picks the handler for a channel (per-channel override or the default)
+pub(super) struct MessageRouter {
+    default: Arc<dyn MessageHandler>,
+    channels: HashMap<String, Arc<dyn MessageHandler>>,
+}
+
+impl MessageRouter {
+    pub fn new(
+        default: Arc<dyn MessageHandler>,
+        channels: HashMap<String, Arc<dyn MessageHandler>>,
+    ) -> Self {
+        Self { default, channels }
+    }
+
+    pub fn handler_for(&self, channel_id: &str) -> &dyn MessageHandler {
+        self.channels
+            .get(channel_id)
+            .unwrap_or(&self.default)
+            .as_ref()
+    }
+}
+
+pub(super) async fn queue_processor(
+    mut rx: mpsc::Receiver<QueuedMessage>,
+    ctx: HandlerContext,
+    router: MessageRouter,
+) {
+    while let Some(first_msg) = rx.recv().await {
+        // Collect batch: wait BATCH_DELAY, gathering any additional messages
+        let mut batch = vec![first_msg];
+        let deadline = tokio::time::Instant::now() + BATCH_DELAY;
+
+        loop {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(msg)) => batch.push(msg),
+                Ok(None) => {
+                    // Channel closed
+                    info!("Queue processor shutting down (channel closed)");
+                    return;
+                }
+                Err(_) => break, // Timeout reached, process the batch
+            }
+        }
+
+        info!("Collected batch of {} message(s)", batch.len());
+        let message_ids: Vec<String> = batch.iter().map(|m| m.message_id.clone()).collect();
+
+        // Apply edits made while queued and drop deleted messages
+        let batch = ctx.tracker.lock().unwrap().take_batch(batch);
+        if batch.is_empty() {
+            info!("All messages in batch were deleted, skipping");
+            forget_pending(&ctx, &message_ids);
+            continue;
+        }
+
+        // Group messages by channel_id to prevent cross-channel mixing.
+        // Bot commands are handled directly and never reach the agent.
+        let mut by_channel: HashMap<String, Vec<QueuedMessage>> = HashMap::new();
+        for msg in batch {
+            if msg.addressed
+                && let Some(command) = parse_command(&ctx.config, &msg)
+            {
+                handle_command(command, &msg, &ctx, rx.len()).await;
+                continue;
+            }
+            by_channel
+                .entry(msg.channel_id.clone())
+                .or_default()
+                .push(msg);
+        }
+
+        for (chan_id, channel_batch) in &by_channel {
+            info!(
+                "Processing {} message(s) for channel {}",
+                channel_batch.len(),
+                chan_id
+            );
+            router
+                .handler_for(chan_id)
+                .handle(channel_batch, &ctx)
+                .await;
+        }
+        forget_pending(&ctx, &message_ids);
+    }
+    info!("Queue processor shutting down (channel closed)");
+}
+
+/// Handled messages need no replay after a restart
+fn forget_pending(ctx: &HandlerContext, message_ids: &[String]) {
+    if let Some(ref store) = ctx.pending
+        && let Err(e) = store.remove(message_ids)
+    {
+        warn!("Failed to clear handled pending messages: {}", e);
+    }
+}
+
+/// The message as a bot command: prefixed with its guild's
+/// `command_prefix`, or in the `/` and `!` forms
+fn parse_command(config: &Config, msg: &QueuedMessage) -> Option<DiscordCommand> {
+    let prefix = msg.guild_id.as_ref().and_then(|guild_id| {
+        config
+            .channels
+            .discord
+            .as_ref()?
+            .guilds
+            .iter()
+            .find(|g| g.guild_id == *guild_id)?
+            .command_prefix
+            .as_deref()
+    });
+    prefix
+        .and_then(|prefix| DiscordCommand::parse_prefixed(&msg.content, prefix))
+        .or_else(|| DiscordCommand::parse(&msg.content))
+}
+
+/// Answer a bot command; `queued` is the number of messages waiting
+async fn handle_command(
+    command: DiscordCommand,
+    msg: &QueuedMessage,
+    ctx: &HandlerContext,
+    queued: usize,
+) {
+    let channel_id = &msg.channel_id;
+    let result = match command {
+        DiscordCommand::Invalid(usage) => reply_text(ctx, channel_id, &usage).await,
+        DiscordCommand::Reset => {
+            let reply = if user_permissions(ctx, msg).allows(DiscordCapability::Admin) {
+                reset_command(ctx, channel_id).await
+            } else {
+                "`/reset` needs the `admin` permission.".to_string()
+            };
+            reply_text(ctx, channel_id, &reply).await
+        }
+        DiscordCommand::Model(model) => {
+            // Anyone may see the model; switching it changes the channel for everyone
+            let reply = if model.is_some()
+                && !user_permissions(ctx, msg).allows(DiscordCapability::Admin)
+            {
+                "Switching the model needs the `admin` permission.".to_string()
+            } else {
+                model_command(ctx, channel_id, model).await
+            };
+            reply_text(ctx, channel_id, &reply).await
+        }
+        DiscordCommand::Inspect(message_id) => {
+            if user_permissions(ctx, msg).allows(DiscordCapability::Inspect) {
+                inspect_command(ctx, channel_id, message_id).await
+            } else {
+                let denied = "`/inspect` needs the `inspect` permission.";
+                reply_text(ctx, channel_id, denied).await
+            }
+        }
+        DiscordCommand::Status => {
+            if user_permissions(ctx, msg).allows(DiscordCapability::Admin) {
+                status_command(ctx, channel_id, queued).await
+            } else {
+                let denied = "`/status` needs the `admin` permission.";
+                reply_text(ctx, channel_id, denied).await
+            }
+        }
+        DiscordCommand::Pins => {
+            let channel = channel_id.to_string();
+            let stored = db::blocking(move || pins::PinStore::open_default()?.list(&channel)).await;
+            let reply = match stored {
+                Ok(stored) => {
+                    pins::format_pins(pins::pins_config(&ctx.config, channel_id), &stored)
+                }
+                Err(e) => {
+                    warn!("Failed to load pins of channel {}: {}", channel_id, e);
+                    "Failed to load the pins.".to_string()
+                }
+            };
+            reply_text(ctx, channel_id, &reply).await
+        }
+        DiscordCommand::Pin { file, content } => {
+            let reply = if user_permissions(ctx, msg).allows(DiscordCapability::Admin) {
+                pin_command(ctx, msg, file, content).await
+            } else {
+                "`/pin` needs the `admin` permission.".to_string()
+            };
+            reply_text(ctx, channel_id, &reply).await
+        }
+        DiscordCommand::Unpin(id) => {
+            let reply = if !user_permissions(ctx, msg).allows(DiscordCapability::Admin) {
+                "`/unpin` needs the `admin` permission.".to_string()
+            } else {
+                let channel = channel_id.to_string();
+                let removed =
+                    db::blocking(move || pins::PinStore::open_default()?.remove(&channel, id))
+                        .await;
+                match removed {
+                    Ok(true) => format!("Removed pin #{}.", id),
+                    Ok(false) => format!("There is no pin #{} in this channel.", id),
+                    Err(e) => {
+                        warn!("Failed to remove pin {} in {}: {}", id, channel_id, e);
+                        "Failed to remove the pin.".to_string()
+                    }
+                }
+            };
+            reply_text(ctx, channel_id, &reply).await
+        }
+        DiscordCommand::Settings {
+            channel,
+            name,
+            value,
+        } => {
+            let reply = settings_command(ctx, msg, channel, name, value).await;
+            reply_text(ctx, channel_id, &reply).await
+        }
+        DiscordCommand::Export(format) => {
+            let agents = Arc::clone(&ctx.agents);
+            let ch_id = channel_id.clone();
+            let opts = ExportOptions {
+                title: format!("Discord conversation (channel {})", channel_id),
+                model: Some(ctx.config.agent.default_model.clone()),
+                ..Default::default()
+            };
+
+            // Agent is not Send; render on a blocking thread like chat turns
+            let export = tokio::task::spawn_blocking(move || {
+                let rt = tokio::runtime::Handle::current();
+                rt.block_on(async {
+                    let guard = agents.lock().await;
+                    guard.get(&ch_id).map(|agent| {
+                        (
+                            agent.export_file_name(format),
+                            agent.export_with(format, &opts),
+                        )
+                    })
+                })
+            })
+            .await;
+
+            match export {
+                Ok(Some((filename, body))) => {
+                    info!(
+                        "Exporting session for channel {} as {}",
+                        channel_id, filename
+                    );
+                    ctx.rest
+                        .send_file(channel_id, &filename, body.into_bytes(), "")
+                        .await
+                }
+                Ok(None) => reply_text(ctx, channel_id, "Nothing to export yet.").await,
+                Err(e) => {
+                    error!("Export task panicked: {}", e);
+                    return;
+                }
+            }
+        }
+    };
+
+    if let Err(e) = result {
+        warn!("Failed to respond to command in {}: {}", channel_id, e);
+    }
+}
+
+/// What the author of a command may do
+fn user_permissions(ctx: &HandlerContext, msg: &QueuedMessage) -> Permissions {
+    Permissions::for_user(
+        &ctx.config
+            .channels
+            .discord
+            .as_ref()
+            .map(|d| d.permissions.clone())
+            .unwrap_or_default(),
+        &msg.author_id,
+        &msg.author_roles,
+    )
+}
+
+/// Pin a snippet or workspace file in the message's channel; the reply text
+async fn pin_command(
+    ctx: &HandlerContext,
+    msg: &QueuedMessage,
+    file: bool,
+    content: String,
+) -> String {
+    if file && let Err(e) = pins::check_file(&ctx.config.workspace_path(), &content) {
+        return format!("Can't pin `{}`: {}.", content, e);
+    }
+    let (channel_id, author_id) = (msg.channel_id.clone(), msg.author_id.clone());
+    let added = db::blocking(move || {
+        pins::PinStore::open_default()?.add(&channel_id, file, &content, &author_id)
+    })
+    .await;
+    match added {
+        Ok(id) => format!(
+            "Pinned as #{}. It's given to me with every message in this channel.",
+            id
+        ),
+        Err(e) => {
+            warn!("Failed to pin in {}: {}", msg.channel_id, e);
+            "Failed to save the pin.".to_string()
+        }
+    }
+}
+
+/// Show the channel's settings or change one; the reply text
+async fn settings_command(
+    ctx: &HandlerContext,
+    msg: &QueuedMessage,
+    channel: bool,
+    name: Option<String>,
+    value: Option<String>,
+) -> String {
+    let Some(ref guild_id) = msg.guild_id else {
+        return "Settings are per server; there are none in direct messages.".to_string();
+    };
+    let (Some(name), Some(value)) = (name, value) else {
+        let guild = settings::guild_config(&ctx.config, guild_id).cloned();
+        let channel_id = msg.channel_id.clone();
+        let values = db::blocking(move || {
+            settings::SettingsStore::open_default()?.resolve(guild.as_ref(), Some(&channel_id))
+        })
+        .await;
+        return match values {
+            Ok(values) => settings::format_settings(&values),
+            Err(e) => {
+                warn!("Failed to load settings of {}: {}", msg.channel_id, e);
+                "Failed to load the settings.".to_string()
+            }
+        };
+    };
+    if !user_permissions(ctx, msg).allows(DiscordCapability::Admin) {
+        return "Changing settings needs the `admin` permission.".to_string();
+    }
+    let Some(setting) = settings::Setting::parse(&name) else {
+        let names: Vec<String> = settings::Setting::ALL
+            .iter()
+            .map(|s| format!("`{}`", s.name()))
+            .collect();
+        return format!("Unknown setting `{}`. Settings: {}", name, names.join(", "));
+    };
+
+    let reset = value.eq_ignore_ascii_case("reset");
+    let scope = if channel {
+        "this channel"
+    } else {
+        "this server"
+    };
+    let guild_id = guild_id.clone();
+    let channel_id = channel.then(|| msg.channel_id.clone());
+    let author_id = msg.author_id.clone();
+    let changed = db::blocking(move || {
+        settings::SettingsStore::open_default()?.set(
+            &guild_id,
+            channel_id.as_deref(),
+            setting,
+            (!reset).then_some(value.as_str()),
+            &author_id,
+        )
+    })
+    .await;
+    match changed {
+        Ok(()) if reset => format!("Reset `{}` for {}.", setting.name(), scope),
+        Ok(()) => format!("Set `{}` for {}.", setting.name(), scope),
+        Err(e) => format!("Could not change `{}`: {}", setting.name(), e),
+    }
+}
+
+/// Post the bot's diagnostics as an embed
+async fn status_command(ctx: &HandlerContext, channel_id: &str, queued: usize) -> RestResult<()> {
+    let (model, memory_chunks, sessions) = {
+        let agents = ctx.agents.lock().await;
+        let model = agents
+            .get(channel_id)
+            .map(|a| a.model().to_string())
+            .unwrap_or_else(|| ctx.config.agent.default_model.clone());
+        let chunks = agents.values().next().map(|a| a.memory_chunk_count());
+        (model, chunks, agents.len())
+    };
+    let status = BotStatus {
+        uptime: ctx.started.elapsed(),
+        model,
+        memory_chunks,
+        sessions,
+        queued,
+        heartbeat_enabled: ctx.config.heartbeat.enabled,
+        heartbeat: get_last_heartbeat_event(),
+        providers: circuit_statuses(),
+    };
+    let embed = status.to_embed(now_ms()).to_discord_json();
+    ctx.rest
+        .send_message(channel_id, "", Some(vec![embed]))
+        .await
+        .map(drop)
+}
+
+/// Send a plain-text command reply
+async fn reply_text(ctx: &HandlerContext, channel_id: &str, text: &str) -> RestResult<()> {
+    ctx.rest
+        .send_message(channel_id, text, None)
+        .await
+        .map(drop)
+}
+
+/// Show or switch the channel agent's model; the reply text
+async fn model_command(ctx: &HandlerContext, channel_id: &str, model: Option<String>) -> String {
+    let agents = Arc::clone(&ctx.agents);
+    let config = ctx.config.clone();
+    let ch_id = channel_id.to_string();
+
+    // Agent is not Send; run on a blocking thread like chat turns
+    let result = tokio::task::spawn_blocking(move || {
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async {
+            let mut guard = agents.lock().await;
+            let agent = channel_agent(&mut guard, &ch_id, &config).await?;
+            let reply = match model {
+                Some(model) => {
+                    agent.switch_model(&model)?;
+                    format!("Switched this conversation to `{}`.", agent.model())
+                }
+                None => format!(
+                    "Current model: `{}`\nAvailable: {}",
+                    agent.model(),
+                    config
+                        .agent
+                        .switchable_models()
+                        .iter()
+                        .map(|m| format!("`{}`", m))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            };
+            anyhow::Ok(reply)
+        })
+    })
+    .await;
+
+    match result {
+        Ok(Ok(reply)) => {
+            info!("Model command in channel {}: {}", channel_id, reply);
+            reply
+        }
+        Ok(Err(e)) => format!("Could not switch models: {}", e),
+        Err(e) => {
+            error!("Model command panicked: {}", e);
+            Language::from_config(&ctx.config.agent.language)
+                .text(Fragment::ErrorApology)
+                .to_string()
+        }
+    }
+}
+
+/// Save the channel's conversation to memory and drop its agent and
+/// archived session, so the next message starts a new session
+async fn reset_command(ctx: &HandlerContext, channel_id: &str) -> String {
+    let agents = Arc::clone(&ctx.agents);
+    let ch_id = channel_id.to_string();
+
+    // Agent is not Send; run on a blocking thread like chat turns
+    let result = tokio::task::spawn_blocking(move || {
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async {
+            let agent = agents.lock().await.remove(&ch_id);
+            sessions::ArchiveStore::open_default()?.forget(&ch_id)?;
+            match agent {
+                Some(agent) => agent.save_session_to_memory().await,
+                None => Ok(None),
+            }
+        })
+    })
+    .await;
+    ctx.activity.lock().unwrap().remove(channel_id);
+
+    match result {
+        Ok(Ok(saved)) => {
+            info!("Reset the conversation of channel {}", channel_id);
+            match saved {
+                Some(path) => format!(
+                    "Started a new conversation. The last one was saved to `{}`.",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                ),
+                None => "Started a new conversation.".to_string(),
+            }
+        }
+        Ok(Err(e)) => {
+            warn!("Failed to reset channel {}: {}", channel_id, e);
+            format!("Could not reset the conversation: {}", e)
+        }
+        Err(e) => {
+            error!("Reset command panicked: {}", e);
+            Language::from_config(&ctx.config.agent.language)
+                .text(Fragment::ErrorApology)
+                .to_string()
+        }
+    }
+}
+
+/// Upload the prompt behind a reply in this channel as a file
+async fn inspect_command(
+    ctx: &HandlerContext,
+    channel_id: &str,
+    message_id: Option<String>,
+) -> RestResult<()> {
+    let scope = prompt_scope(channel_id);
+    let (id, latest_in) = (message_id.clone(), scope.clone());
+    let record = db::blocking(move || {
+        let store = PromptStore::open_default()?;
+        match id {
+            Some(ref id) => store.get(id),
+            None => store.latest(&latest_in),
+        }
+    })
+    .await;
+
+    match record {
+        // Prompts from other channels are not shown here
+        Ok(Some(record)) if record.scope == scope => {
+            let filename = format!("prompt-{}.md", record.id);
+            let summary = format!(
+                "Prompt #{}: {} messages sent to `{}`",
+                record.id,
+                record.messages.len(),
+                record.model
+            );
+            let body = format_prompt_record(&record).into_bytes();
+            ctx.rest
+                .send_file(channel_id, &filename, body, &summary)
+                .await
+        }
+        Ok(_) => {
+            let reply = match message_id {
+                Some(id) => format!("No recorded prompt for message {}.", id),
+                None => "No recorded prompt in this channel yet.".to_string(),
+            };
+            reply_text(ctx, channel_id, &reply).await
+        }
+        Err(e) => {
+            warn!("Failed to read recorded prompts: {}", e);
+            reply_text(ctx, channel_id, "Could not read recorded prompts.").await
+        }
+    }
+}
+
+fn prompt_scope(channel_id: &str) -> String {
+    format!("discord:{}", channel_id)
+}
+
+/// Keep the prompt behind a reply for `/inspect`, keyed by the batch's
+/// message IDs. Returns the record's ID and the model that answered.
+async fn record_prompt(
+    ctx: &HandlerContext,
+    channel_id: &str,
+    batch: &[&QueuedMessage],
+    response: &str,
+    model: Option<&str>,
+) -> (Option<i64>, String) {
+    let agents = ctx.agents.lock().await;
+    let Some(agent) = agents.get(channel_id) else {
+        return (None, String::new());
+    };
+    let model = model.unwrap_or(agent.model()).to_string();
+    let Some(messages) = agent.last_prompt().map(<[Message]>::to_vec) else {
+        return (None, model);
+    };
+    drop(agents);
+
+    let scope = prompt_scope(channel_id);
+    let message_ids: Vec<String> = batch.iter().map(|m| m.message_id.clone()).collect();
+    let (recorded_model, response) = (model.clone(), response.to_string());
+    let result = db::blocking(move || {
+        PromptStore::open_default()?.record(
+            &scope,
+            &message_ids,
+            &recorded_model,
+            &messages,
+            &response,
+        )
+    })
+    .await;
+    match result {
+        Ok(id) => (Some(id), model),
+        Err(e) => {
+            warn!("Failed to record prompt for channel {}: {}", channel_id, e);
+            (None, model)
+        }
+    }
+}
+
+/// Record a reply's experiment variant for `localgpt experiment` and tag
+/// it in the audit log
+async fn record_experiment_turn(
+    ctx: &HandlerContext,
+    experiment: &ExperimentConfig,
+    turn: &ExperimentTurn<'_>,
+) {
+    let (name, scope, model) = (
+        turn.experiment.to_string(),
+        turn.scope.to_string(),
+        turn.model.to_string(),
+    );
+    let (variant, prompt_id, latency_ms, reply_chars) = (
+        turn.variant,
+        turn.prompt_id,
+        turn.latency_ms,
+        turn.reply_chars,
+    );
+    let recorded = db::blocking(move || {
+        ExperimentStore::open_default()?.record(&ExperimentTurn {
+            experiment: &name,
+            variant,
+            scope: &scope,
+            prompt_id,
+            model: &model,
+            latency_ms,
+            reply_chars,
+        })
+    })
+    .await;
+    if let Err(e) = recorded {
+        warn!("Failed to record experiment turn: {}", e);
+    }
+
+    let workspace = ctx.config.workspace_path();
+    let Some(state_dir) = workspace.parent() else {
+        return;
+    };
+    let detail = format!(
+        "experiment {}: {} ({}) answered {}{} in {}ms",
+        experiment.name,
+        turn.variant.as_str(),
+        turn.model,
+        turn.scope,
+        turn.prompt_id
+            .map(|id| format!(", prompt #{}", id))
+            .unwrap_or_default(),
+        turn.latency_ms
+    );
+    if let Err(e) = append_audit_entry_with_detail(
+        state_dir,
+        AuditAction::ExperimentVariant,
+        "",
+        "discord",
+        Some(&detail),
+    ) {
+        warn!("Failed to record experiment variant in audit log: {}", e);
+    }
+}
+
+/// Default handler: run the channel's agent and post its reply
+pub struct AgentHandler;
+
+#[async_trait]
+impl MessageHandler for AgentHandler {
+    async fn handle(&self, batch: &[QueuedMessage], ctx: &HandlerContext) {
+        // Messages queued only for moderation are not for the agent
+        let batch: Vec<&QueuedMessage> = batch.iter().filter(|m| m.addressed).collect();
+        let Some(&last_msg) = batch.last() else {
+            return;
+        };
+
+        // Use the last message's channel_id and message_id for reactions/replies
+        let channel_id = &last_msg.channel_id;
+        let last_message_id = &last_msg.message_id;
+        let rest = ctx.rest.as_ref();
+
+        // Build combined prompt: format each message as [author] content
+        let combined_content = if batch.len() == 1 {
+            batch[0].content.clone()
+        } else {
+            batch
+                .iter()
+                .map(|m| format!("[{}] {}", m.author_name, m.content))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        // Tell the agent about edits to messages it already answered
+        let edit_notes = ctx.tracker.lock().unwrap().take_notes(channel_id);
+        let combined_content = if edit_notes.is_empty() {
+            combined_content
+        } else {
+            format!("{}\n\n{}", edit_notes.join("\n"), combined_content)
+        };
+
+        // Recent channel messages, for discussions the agent wasn't part of
+        let answered: Vec<&str> = batch.iter().map(|m| m.message_id.as_str()).collect();
+        let combined_content =
+            match history::context_for(&ctx.config, rest, channel_id, &answered).await {
+                Some(history) => format!("{}\n\n{}", history, combined_content),
+                None => combined_content,
+            };
+
+        let images = download_images(&ctx.http, &batch).await;
+
+        let permissions = Permissions::for_batch(
+            &ctx.config
+                .channels
+                .discord
+                .as_ref()
+                .map(|d| d.permissions.clone())
+                .unwrap_or_default(),
+            &batch,
+        );
+        let denied_tools = permissions.denied_tools();
+
+        let mut participants: Vec<String> = Vec::new();
+        for msg in &batch {
+            if !participants.contains(&msg.author_name) {
+                participants.push(msg.author_name.clone());
+            }
+        }
+        mirror::publish(
+            &ctx.config,
+            channel_id,
+            MirrorEventKind::Prompt {
+                authors: participants.clone(),
+                content: batch
+                    .iter()
+                    .map(|m| m.content.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            },
+        );
+        let log_context = LogContext {
+            channel: Some(format!("discord:{}", channel_id)),
+            participants,
+            message_id: Some(last_message_id.clone()),
+        };
+
+        // A/B experiment: the message being answered picks the variant
+        let experiment = ctx.config.agent.experiment.as_ref();
+        let experiment = experiment.filter(|e| e.is_active());
+        let variant = experiment.map(|e| e.assign(last_message_id));
+        let treatment = experiment
+            .filter(|_| variant == Some(Variant::Treatment))
+            .cloned();
+        let started = Instant::now();
+
+        // Send typing indicator
+        let _ = rest.send_typing(channel_id).await;
+
+        let mut response = match chat_with_channel_agent(
+            ctx,
+            last_msg.guild_id.as_deref(),
+            channel_id,
+            combined_content,
+            images,
+            denied_tools.clone(),
+            log_context.clone(),
+            treatment.clone(),
+        )
+        .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                error!("Failed to generate response: {}", e);
+                let message = format!("{:#}", e);
+                mirror::publish(&ctx.config, channel_id, MirrorEventKind::Error { message });
+                ctx.report_error(channel_id, last_message_id, &e).await;
+                return;
+            }
+        };
+
+        // Tool output loop: process [LIST:...] and [READ:...] tags (native
+        // tool calls are looped inside the agent under the same limits)
+        let mut previous_output = String::new();
+        for iteration in 0..ctx.config.agent.tool_loop.max_iterations.max(1) {
+            let tool_output = tags::execute_tool_tags(&response, &ctx.config, rest).await;
+            if tool_output.is_empty() {
+                break;
+            }
+            if tool_output == previous_output {
+                warn!(
+                    "Tag loop detected in channel {}: same tags requested again",
+                    channel_id
+                );
+                break;
+            }
+            previous_output = tool_output.clone();
+            info!(
+                "Tool output loop iteration {} for channel {}",
+                iteration + 1,
+                channel_id
+            );
+            let _ = rest.send_typing(channel_id).await;
+
+            match chat_with_channel_agent(
+                ctx,
+                last_msg.guild_id.as_deref(),
+                channel_id,
+                tool_output,
+                Vec::new(),
+                denied_tools.clone(),
+                log_context.clone(),
+                treatment.clone(),
+            )
+            .await
+            {
+                Ok(r) => response = r,
+                Err(e) => {
+                    error!("Tool output loop error: {}", e);
+                    crate::digest::report(
+                        "discord",
+                        &format!("channel {}", channel_id),
+                        &format!("Tool output loop: {:#}", e),
+                    );
+                    break;
+                }
+            }
+        }
+
+        let latency = started.elapsed();
+        let treatment_model = treatment.as_ref().and_then(|t| t.model.as_deref());
+        let (prompt_id, model) =
+            record_prompt(ctx, channel_id, &batch, &response, treatment_model).await;
+        if let (Some(experiment), Some(variant)) = (experiment, variant) {
+            let scope = prompt_scope(channel_id);
+            record_experiment_turn(
+                ctx,
+                experiment,
+                &ExperimentTurn {
+                    experiment: &experiment.name,
+                    variant,
+                    scope: &scope,
+                    prompt_id,
+                    model: &model,
+                    latency_ms: latency.as_millis() as u64,
+                    reply_chars: response.chars().count(),
+                },
+            )
+            .await;
+        }
+
+        // The user deleted the message while we were generating: don't reply
+        if ctx.tracker.lock().unwrap().is_deleted(last_message_id) {
+            info!(
+                "Message {} in channel {} was deleted, skipping reply",
+                last_message_id, channel_id
+            );
+            return;
+        }
+
+        let reply = tags::process_reply_tags(
+            &response,
+            &ctx.tag_registry,
+            &ctx.config,
+            permissions.allows(DiscordCapability::Commands),
+        )
+        .await;
+
+        // Send cross-channel posts (security: only to channels in configured guilds)
+        for post in &reply.cross_posts {
+            let target_channel = &post.channel_id;
+            if !permissions.allows(DiscordCapability::CrossPost) {
+                warn!(
+                    "Cross-post to channel {} denied: requester lacks the cross_post permission",
+                    target_channel
+                );
+            } else if tags::cross_post_allowed(&ctx.config, target_channel) {
+                info!(
+                    "Cross-posting to channel {}: {}",
+                    target_channel,
+                    if post.message.chars().count() > 40 {
+                        format!("{}...", post.message.chars().take(40).collect::<String>())
+                    } else {
+                        post.message.clone()
+                    }
+                );
+                if let Err(e) = tags::deliver_cross_post(rest, post).await {
+                    error!("Failed to cross-post to channel {}: {}", target_channel, e);
+                }
+            } else {
+                warn!(
+                    "Cross-post to channel {} denied: not in allowed guild channels",
+                    target_channel
+                );
+            }
+        }
+
+        // Schedule [REMIND] reminders; the ones that can't be are explained
+        // below the reply
+        let mut reminder_notes = Vec::new();
+        if !reply.reminders.is_empty() {
+            let settings = ctx
+                .config
+                .channels
+                .discord
+                .as_ref()
+                .map(|d| d.reminders.clone())
+                .unwrap_or_default();
+            let (requests, requester) = (reply.reminders.clone(), last_msg.clone());
+            let scheduled = db::blocking(move || {
+                let store = reminders::ReminderStore::open_default()?;
+                Ok(requests
+                    .iter()
+                    .filter_map(|request| {
+                        let now = chrono::Local::now();
+                        reminders::schedule(&settings, &store, request, &requester, now).err()
+                    })
+                    .collect::<Vec<String>>())
+            })
+            .await;
+            match scheduled {
+                Ok(notes) => reminder_notes = notes,
+                Err(e) => {
+                    warn!("Reminder store unavailable: {}", e);
+                    reminder_notes.push("I couldn't save the reminder.".to_string());
+                }
+            }
+        }
+
+        // Add reactions to the last message in batch. Custom emoji named in
+        // [REACT:] need the guild's emoji IDs.
+        let guild_emojis = match &last_msg.guild_id {
+            Some(guild_id)
+                if reply
+                    .reactions
+                    .iter()
+                    .any(|e| custom_emoji::lookup_name(e).is_some()) =>
+            {
+                rest.list_guild_emojis(guild_id).await.unwrap_or_else(|e| {
+                    warn!("Failed to list emoji of guild {}: {}", guild_id, e);
+                    Vec::new()
+                })
+            }
+            _ => Vec::new(),
+        };
+        for emoji in &reply.reactions {
+            let Some(emoji) = custom_emoji::resolve_reaction(emoji, &guild_emojis) else {
+                warn!("Skipping reaction {}: not an emoji of this guild", emoji);
+                continue;
+            };
+            if let Err(e) = rest.add_reaction(channel_id, last_message_id, &emoji).await {
+                error!("Failed to add reaction {}: {}", emoji, e);
+            }
+        }
+
+        // Send text reply unless nothing is left after post-processing
+        let mut text = reply.text;
+        for note in reminder_notes {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(&note);
+        }
+        let text = match style::style_for(&ctx.config, channel_id) {
+            Some(style) => apply_style(ctx, channel_id, style, text).await,
+            None => text,
+        };
+        if !text.is_empty() {
+            let kind = MirrorEventKind::Reply { text: text.clone() };
+            mirror::publish(&ctx.config, channel_id, kind);
+        }
+        let parts = ReplyPipeline::for_channel(&ctx.config, ChannelKind::Discord).process(&text);
+        if parts.is_empty() && reply.embeds.is_empty() && reply.choices.is_none() {
+            return;
+        }
+
+        // Emoji-only replies become a reaction unless the guild opts out
+        let emoji_reactions = last_msg
+            .guild_id
+            .as_ref()
+            .and_then(|gid| {
+                ctx.config
+                    .channels
+                    .discord
+                    .as_ref()?
+                    .guilds
+                    .iter()
+                    .find(|g| g.guild_id == *gid)
+            })
+            .is_none_or(|g| g.emoji_reactions);
+        let emoji_reply = match parts.as_slice() {
+            [part] if emoji_reactions && reply.embeds.is_empty() => emoji::emoji_only(part),
+            _ => None,
+        };
+
+        let mut sent = Vec::new();
+        if let Some(emojis) = emoji_reply {
+            // Convert emoji-only text to reaction instead of message
+            let first_emoji = emojis[0];
+            if let Err(e) = rest
+                .add_reaction(channel_id, last_message_id, first_emoji)
+                .await
+            {
+                error!("Failed to add emoji-only reaction {}: {}", first_emoji, e);
+            }
+        } else {
+            // Embeds go with the last part
+            let last = parts.len().saturating_sub(1);
+            for (i, part) in parts.iter().enumerate() {
+                let embeds = if i == last { &reply.embeds[..] } else { &[] };
+                sent.extend(send_reply(rest, channel_id, part, embeds).await);
+            }
+            if parts.is_empty() && !reply.embeds.is_empty() {
+                sent = send_reply(rest, channel_id, "", &reply.embeds).await;
+            }
+        }
+        // Choices follow as a message of their own
+        if let Some(ref choices) = reply.choices {
+            sent.extend(send_choices(rest, channel_id, choices).await);
+        }
+        // Key the reply to its prompt too, for `/inspect` and reaction feedback
+        if let Some(prompt_id) = prompt_id
+            && !sent.is_empty()
+            && let Err(e) =
+                db::blocking(move || PromptStore::open_default()?.add_keys(prompt_id, &sent)).await
+        {
+            warn!("Failed to key replies in channel {}: {}", channel_id, e);
+        }
+    }
+}
+
+/// The channel's agent, created on first use
+async fn channel_agent<'a>(
+    agents: &'a mut HashMap<String, Agent>,
+    channel_id: &str,
+    config: &Config,
+) -> anyhow::Result<&'a mut Agent> {
+    if !agents.contains_key(channel_id) {
+        let agent_config = AgentCfg {
+            model: config.agent.default_model.clone(),
+            context_window: config.agent.context_window,
+            reserve_tokens: config.agent.reserve_tokens,
+        };
+        let memory = MemoryManager::new_with_full_config(&config.memory, Some(config), "discord")?;
+        let mut agent = Agent::new(agent_config, config, memory).await?;
+        match sessions::archived_session(channel_id) {
+            Some(session_id) => match agent.resume_session(&session_id).await {
+                Ok(()) => info!("Resumed archived session of channel {}", channel_id),
+                Err(e) => {
+                    warn!("Failed to resume archived session of {}: {}", channel_id, e);
+                    agent.new_session().await?;
+                }
+            },
+            None => agent.new_session().await?,
+        }
+        agents.insert(channel_id.to_string(), agent);
+        info!("Created new Agent for channel {}", channel_id);
+    }
+    Ok(agents.get_mut(channel_id).unwrap())
+}
+
+/// Run one turn on the channel's agent, creating it on first use, with
+/// the channel's settings. `log_context` is recorded with the memories
+/// written during the turn; `treatment` is the experiment configuration to
+/// answer with, if any.
+#[allow(clippy::too_many_arguments)]
+async fn chat_with_channel_agent(
+    ctx: &HandlerContext,
+    guild_id: Option<&str>,
+    channel_id: &str,
+    message: String,
+    images: Vec<ImageAttachment>,
+    denied_tools: Vec<String>,
+    log_context: LogContext,
+    treatment: Option<ExperimentConfig>,
+) -> anyhow::Result<String> {
+    let guild_id = guild_id.map(String::from);
+    let channel_id = channel_id.to_string();
+    let config = ctx.config.clone();
+    let agents = Arc::clone(&ctx.agents);
+    ctx.activity
+        .lock()
+        .unwrap()
+        .insert(channel_id.clone(), Instant::now());
+
+    // Agent futures are not Send; drive them on a blocking thread
+    tokio::task::spawn_blocking(move || {
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async {
+            let settings = settings::for_channel(
+                guild_id
+                    .as_deref()
+                    .and_then(|g| settings::guild_config(&config, g)),
+                &channel_id,
+            );
+            let mut agents_guard = agents.lock().await;
+            let agent = channel_agent(&mut agents_guard, &channel_id, &config).await?;
+            agent.set_denied_tools(denied_tools);
+            agent.set_job_channel(Some(channel_id.clone()));
+            agent.set_log_context(log_context);
+            agent.set_tool_observer(mirror::tool_observer(&config, &channel_id));
+            // The channel's language, or the configured one unless each
+            // conversation follows its first message
+            let language = settings.language.or_else(|| {
+                (!config.agent.detect_language)
+                    .then(|| Language::parse(&config.agent.language).unwrap_or_default())
+            });
+            if let Some(language) = language {
+                agent.set_language(language).await?;
+            }
+            let treatment = treatment.unwrap_or_default();
+            let instructions: Vec<String> = [
+                settings.instructions(),
+                pins::context_for(&config, &channel_id),
+                style::style_for(&config, &channel_id).and_then(style::instructions),
+                treatment.instructions.clone(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            agent.set_turn_instructions(
+                (!instructions.is_empty()).then(|| instructions.join("\n\n")),
+            );
+            let usual_model = agent.model().to_string();
+            if let Some(ref model) = treatment.model {
+                agent.set_model_for_turn(model)?;
+            }
+
+            // SOUL.md/MEMORY.md/AGENTS.md edits are applied by the agent
+            // itself from the context watcher's reload events
+            let result = agent.chat_with_images(&message, images).await;
+
+            if treatment.model.is_some()
+                && let Err(e) = agent.set_model_for_turn(&usual_model)
+            {
+                warn!("Failed to restore model {}: {}", usual_model, e);
+            }
+            result
+        })
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Agent task panicked: {}", e))?
+}
+
+/// Enforce a channel's reply style on the final text
+async fn apply_style(
+    ctx: &HandlerContext,
+    channel_id: &str,
+    style: &DiscordChannelStyle,
+    text: String,
+) -> String {
+    let text = if style.no_code_blocks {
+        style::strip_code_blocks(&text)
+    } else {
+        text
+    };
+    if !style::too_long(style, &text) {
+        return text;
+    }
+
+    let text = if style.overflow == "summarize" {
+        match shorten_with_channel_agent(ctx, channel_id, text.clone(), style.max_chars).await {
+            Ok(summary) => summary,
+            Err(e) => {
+                warn!("Failed to summarize long reply, truncating: {}", e);
+                text
+            }
+        }
+    } else {
+        text
+    };
+    style::truncate(&text, style.max_chars)
+}
+
+/// Ask the channel agent's model to rewrite a reply within `max_chars`
+async fn shorten_with_channel_agent(
+    ctx: &HandlerContext,
+    channel_id: &str,
+    text: String,
+    max_chars: usize,
+) -> anyhow::Result<String> {
+    let channel_id = channel_id.to_string();
+    let agents = Arc::clone(&ctx.agents);
+    let request = vec![Message {
+        role: Role::User,
+        content: format!(
+            "Rewrite the following reply in under {} characters, keeping the key points \
+             and the same tone. Reply with the rewritten text only.\n\n{}",
+            max_chars, text
+        ),
+        tool_calls: None,
+        tool_call_id: None,
+        images: Vec::new(),
+    }];
+
+    tokio::task::spawn_blocking(move || {
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async {
+            let guard = agents.lock().await;
+            let agent = guard
+                .get(&channel_id)
+                .ok_or_else(|| anyhow::anyhow!("No agent for channel {}", channel_id))?;
+            match agent.provider().chat(&request, None).await?.content {
+                LLMResponseContent::Text(shortened) => Ok(shortened.trim().to_string()),
+                LLMResponseContent::ToolCalls(_) => {
+                    anyhow::bail!("model returned tool calls instead of a reply")
+                }
+            }
+        })
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Shorten task panicked: {}", e))?
+}
+
+/// Download and base64-encode the image attachments of a batch
+async fn download_images(http: &reqwest::Client, batch: &[&QueuedMessage]) -> Vec<ImageAttachment> {
+    let mut images: Vec<ImageAttachment> = Vec::new();
+    for url in batch.iter().flat_map(|m| m.image_urls.iter()) {
+        match http.get(url).send().await {
+            Ok(resp) if resp.status().is_success() => {
+                let content_type = resp
+                    .headers()
+                    .get("content-type")
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("image/png")
+                    .to_string();
+                match resp.bytes().await {
+                    Ok(bytes) => {
+                        let data = base64::engine::general_purpose::STANDARD.encode(&bytes);
+                        images.push(ImageAttachment {
+                            data,
+                            media_type: content_type,
+                        });
+                        info!("Downloaded image attachment ({} bytes)", bytes.len());
+                    }
+                    Err(e) => warn!("Failed to read image bytes from {}: {}", url, e),
+                }
+            }
+            Ok(resp) => warn!("Failed to download image {}: HTTP {}", url, resp.status()),
+            Err(e) => warn!("Failed to download image {}: {}", url, e),
+        }
+    }
+    images
+}
+
+/// Send the agent's choices as buttons or a select menu, or as a numbered
+/// list if Discord rejects the components. Returns the IDs of the messages
+/// sent.
+async fn send_choices(
+    rest: &dyn DiscordRest,
+    channel_id: &str,
+    choices: &ChoiceSpec,
+) -> Vec<String> {
+    let prompt = choices.prompt.as_deref().unwrap_or_default();
+    match rest
+        .send_components(channel_id, prompt, choices.to_components())
+        .await
+    {
+        Ok(id) => vec![id],
+        Err(RestError::Api { status: 400, body }) => {
+            warn!("Discord rejected choices ({}), sending as a list", body);
+            rest.send_message(channel_id, &choices.to_plain_text(), None)
+                .await
+                .unwrap_or_else(|e| {
+                    error!("Failed to send choices to channel {}: {}", channel_id, e);
+                    Vec::new()
+                })
+        }
+        Err(e) => {
+            error!("Failed to send choices to channel {}: {}", channel_id, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Send a reply with the agent's rich embeds plus image embeds for any
+/// image URLs in the text. Falls back to plain text if Discord rejects
+/// the embeds. Returns the IDs of the messages sent.
+pub(super) async fn send_reply(
+    rest: &dyn DiscordRest,
+    channel_id: &str,
+    text: &str,
+    rich_embeds: &[EmbedSpec],
+) -> Vec<String> {
+    // Detect image URLs in the response text for embeds
+    let img_url_re = Regex::new(r"https://\S+\.(?:png|jpg|jpeg|gif|webp)").unwrap();
+    let image_embeds: Vec<serde_json::Value> = img_url_re
+        .find_iter(text)
+        .map(|m| serde_json::json!({"image": {"url": m.as_str()}}))
+        .collect();
+
+    let embeds: Vec<serde_json::Value> = rich_embeds
+        .iter()
+        .map(EmbedSpec::to_discord_json)
+        .chain(image_embeds.iter().cloned())
+        .take(embeds::MAX_EMBEDS)
+        .collect();
+    let embeds_opt = if embeds.is_empty() {
+        None
+    } else {
+        Some(embeds)
+    };
+
+    match rest
+        .send_message(channel_id, text, embeds_opt.clone())
+        .await
+    {
+        Ok(ids) => ids,
+        Err(RestError::Api { status: 400, body }) if !rich_embeds.is_empty() => {
+            warn!("Discord rejected embeds ({}), sending as plain text", body);
+            let plain = embeds::fallback_text(text, rich_embeds);
+            let image_embeds_opt = if image_embeds.is_empty() {
+                None
+            } else {
+                Some(image_embeds)
+            };
+            match rest
+                .send_message(channel_id, &plain, image_embeds_opt)
+                .await
+            {
+                Ok(ids) => ids,
+                Err(e) => {
+                    error!("Failed to send Discord message: {}", e);
+                    Vec::new()
+                }
+            }
+        }
+        Err(e) => {
+            if !outbox::save_failed(channel_id, text, embeds_opt.as_deref(), &e) {
+                error!("Failed to send Discord message: {}", e);
+            }
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discord::rest::MockDiscordRest;
+
+    /// Records the channels it was asked to handle
+    struct RecordingHandler(std::sync::Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl MessageHandler for RecordingHandler {
+        async fn handle(&self, batch: &[QueuedMessage], _ctx: &HandlerContext) {
+            self.0.lock().unwrap().push(batch[0].channel_id.clone());
+        }
+    }
+
+    fn queued(channel_id: &str) -> QueuedMessage {
+        QueuedMessage {
+            channel_id: channel_id.to_string(),
+            guild_id: None,
+            message_id: "1".to_string(),
+            author_id: "100".to_string(),
+            author_name: "alice".to_string(),
+            author_roles: Vec::new(),
+            content: "hi".to_string(),
+            image_urls: Vec::new(),
+            mention_count: 0,
+            addressed: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_router_uses_channel_handler() {
+        let default = Arc::new(RecordingHandler(Default::default()));
+        let special = Arc::new(RecordingHandler(Default::default()));
+        let router = MessageRouter::new(
+            default.clone(),
+            HashMap::from([("2".to_string(), special.clone() as Arc<dyn MessageHandler>)]),
+        );
+        let ctx = HandlerContext::new(
+            Config::default(),
+            Arc::new(reqwest::Client::new()),
+            Arc::new(MockDiscordRest::new()),
+            Arc::new(std::sync::Mutex::new(MessageTracker::new(false))),
+            Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+        );
+
+        for channel in ["1", "2", "3"] {
+            router
+                .handler_for(channel)
+                .handle(&[queued(channel)], &ctx)
+                .await;
+        }
+
+        assert_eq!(*default.0.lock().unwrap(), vec!["1", "3"]);
+        assert_eq!(*special.0.lock().unwrap(), vec!["2"]);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_reported_not_posted() {
+        // Each failed message gets a reaction; nothing is posted to the channel
+        let mut rest = MockDiscordRest::new();
+        rest.expect_add_reaction()
+            .times(2)
+            .returning(|_, _, _| Ok(()));
+        rest.expect_send_message().times(0);
+        let ctx = HandlerContext::new(
+            Config::default(),
+            Arc::new(reqwest::Client::new()),
+            Arc::new(rest),
+            Arc::new(std::sync::Mutex::new(MessageTracker::new(false))),
+            Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+        );
+
+        let error = anyhow::anyhow!("provider timed out");
+        ctx.report_error("1", "m1", &error).await;
+        ctx.report_error("1", "m2", &error).await;
+    }
+}
@@ -0,0 +1,200 @@
+//! Per-user rate limiting
+//!
+//! `[channels.discord.rate_limit]` caps how many messages one user can send
+//! the bot within a rolling window. The check runs before a message is
+//! queued, so a single user can't fill the queue, starve other channels or
+//! run up model costs. The first message over the quota gets a polite
+//! notice; later ones are dropped quietly until the window frees up.
+//! Deferred messages don't count towards the quota. Every time a user hits
+//! the limit, an entry with their running counters goes to the security
+//! audit log.
+
+use anyhow::Result;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::config::{DiscordRateLimitConfig, parse_duration};
+use crate::security::{AuditAction, append_audit_entry_with_detail};
+
+/// What to do with a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Decision {
+    Allow,
+    Defer {
+        /// Until the oldest counted message leaves the window
+        retry_after: Duration,
+        /// Whether this is the first deferral since the user was last allowed
+        notify: bool,
+    },
+}
+
+#[derive(Default)]
+struct Usage {
+    /// Accepted messages within the window, oldest first
+    recent: VecDeque<Instant>,
+    /// Whether the user is currently over the quota
+    limited: bool,
+    /// Times the user hit the limit since the bot started
+    episodes: u64,
+    /// Messages deferred since the bot started
+    deferred: u64,
+}
+
+pub(super) struct RateLimiter {
+    config: DiscordRateLimitConfig,
+    window: Duration,
+    users: Mutex<HashMap<String, Usage>>,
+    /// Where the audit log lives (None = not recorded)
+    state_dir: Option<PathBuf>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &DiscordRateLimitConfig, state_dir: Option<PathBuf>) -> Result<Self> {
+        let window = parse_duration(&config.window)
+            .map_err(|e| anyhow::anyhow!("Invalid rate_limit window: {}", e))?;
+        Ok(Self {
+            config: config.clone(),
+            window,
+            users: Mutex::new(HashMap::new()),
+            state_dir,
+        })
+    }
+
+    fn is_exempt(&self, user_id: &str, roles: &[String]) -> bool {
+        self.config.exempt_users.iter().any(|u| u == user_id)
+            || self.config.exempt_roles.iter().any(|r| roles.contains(r))
+    }
+
+    /// Count a message from `user_id` or tell why it must wait
+    pub fn check(&self, user_id: &str, roles: &[String], now: Instant) -> Decision {
+        if self.config.max_messages == 0 || self.is_exempt(user_id, roles) {
+            return Decision::Allow;
+        }
+
+        let mut users = self.users.lock().unwrap_or_else(|e| e.into_inner());
+        let usage = users.entry(user_id.to_string()).or_default();
+        while usage
+            .recent
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= self.window)
+        {
+            usage.recent.pop_front();
+        }
+
+        if usage.recent.len() < self.config.max_messages as usize {
+            usage.recent.push_back(now);
+            usage.limited = false;
+            return Decision::Allow;
+        }
+
+        let oldest = usage.recent.front().copied().unwrap_or(now);
+        let notify = !usage.limited;
+        usage.limited = true;
+        usage.deferred += 1;
+        if notify {
+            usage.episodes += 1;
+            self.audit(user_id, usage);
+        }
+        Decision::Defer {
+            retry_after: self.window.saturating_sub(now.duration_since(oldest)),
+            notify,
+        }
+    }
+
+    /// The notice for a deferred user
+    pub fn notice(&self, retry_after: Duration) -> String {
+        // Round up so "in 0s" is never shown
+        let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+        self.config
+            .notice
+            .replace("{retry}", &crate::jobs::format_elapsed(seconds as i64))
+    }
+
+    fn audit(&self, user_id: &str, usage: &Usage) {
+        let Some(ref state_dir) = self.state_dir else {
+            return;
+        };
+        let detail = format!(
+            "discord user {}: over {} messages per {} (limited {} times, {} messages deferred)",
+            user_id, self.config.max_messages, self.config.window, usage.episodes, usage.deferred
+        );
+        if let Err(e) = append_audit_entry_with_detail(
+            state_dir,
+            AuditAction::RateLimited,
+            "",
+            "discord",
+            Some(&detail),
+        ) {
+            warn!("Failed to record rate limit in the audit log: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::read_audit_log;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_quota_per_rolling_window() {
+        let state_dir = TempDir::new().unwrap();
+        let limiter = RateLimiter::new(
+            &DiscordRateLimitConfig {
+                max_messages: 2,
+                window: "1m".to_string(),
+                exempt_roles: vec!["admin".to_string()],
+                ..Default::default()
+            },
+            Some(state_dir.path().to_path_buf()),
+        )
+        .unwrap();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(limiter.check("u1", &[], at(0)), Decision::Allow);
+        assert_eq!(limiter.check("u1", &[], at(10)), Decision::Allow);
+        assert_eq!(
+            limiter.check("u1", &[], at(20)),
+            Decision::Defer {
+                retry_after: Duration::from_secs(40),
+                notify: true,
+            }
+        );
+        assert_eq!(
+            limiter.check("u1", &[], at(30)),
+            Decision::Defer {
+                retry_after: Duration::from_secs(30),
+                notify: false,
+            }
+        );
+
+        // Other users and exempt roles are unaffected
+        assert_eq!(limiter.check("u2", &[], at(30)), Decision::Allow);
+        let admin = vec!["admin".to_string()];
+        for secs in 0..5 {
+            assert_eq!(limiter.check("u3", &admin, at(secs)), Decision::Allow);
+        }
+
+        // The first message leaves the window
+        assert_eq!(limiter.check("u1", &[], at(60)), Decision::Allow);
+        assert!(matches!(
+            limiter.check("u1", &[], at(61)),
+            Decision::Defer { notify: true, .. }
+        ));
+
+        let entries = read_audit_log(state_dir.path()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].action, AuditAction::RateLimited);
+        assert!(
+            entries[1]
+                .detail
+                .as_deref()
+                .unwrap()
+                .ends_with("(limited 2 times, 3 messages deferred)")
+        );
+    }
+}
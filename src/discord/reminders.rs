@@ -0,0 +1,409 @@
+//! Reminders sent by DM
+//!
+//! `[REMIND:user:when] message` in a reply schedules a direct message.
+//! `user` is `me` (the author of the message being answered), a `<@id>`
+//! mention or a user ID; `when` is a delay ("90m", "1d") or a local time
+//! ("2026-10-17 09:00"). Reminders are stored in `discord/reminders.sqlite`
+//! and checked every 30 seconds while the bot runs, so a restart doesn't
+//! lose them. Anyone can set reminders for themselves; only users listed in
+//! `reminders.opt_in` can be sent reminders set by someone else. A user
+//! who doesn't accept DMs from the bot is mentioned in the channel the
+//! reminder was set in instead.
+
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use regex::Regex;
+use rusqlite::params;
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+use tokio::time::Duration;
+use tracing::{info, warn};
+
+use super::QueuedMessage;
+use super::rest::{DiscordRest, RestError};
+use crate::config::{DiscordRemindersConfig, parse_duration};
+use crate::db::SqlitePool;
+
+/// How often due reminders are looked for
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+static MENTION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^<@!?(\d+)>$").unwrap());
+
+/// A `[REMIND:user:when]` tag from a reply
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReminderRequest {
+    pub user: String,
+    pub when: String,
+    pub message: String,
+}
+
+/// A scheduled reminder
+#[derive(Debug, Clone)]
+pub struct Reminder {
+    pub id: i64,
+    pub user_id: String,
+    /// Channel the reminder was set in
+    pub channel_id: String,
+    pub requested_by: String,
+    pub message: String,
+    /// Unix seconds
+    pub due_at: i64,
+}
+
+#[derive(Clone)]
+pub struct ReminderStore {
+    pool: SqlitePool,
+}
+
+impl ReminderStore {
+    /// Open the shared store at `~/.localgpt/discord/reminders.sqlite`
+    pub fn open_default() -> Result<Self> {
+        Self::open(
+            &crate::agent::get_state_dir()?
+                .join("discord")
+                .join("reminders.sqlite"),
+        )
+    }
+
+    pub fn open(db_path: &Path) -> Result<Self> {
+        Self::init(SqlitePool::shared(db_path, None)?)
+    }
+
+    /// In-memory store (tests)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(SqlitePool::open_in_memory(None)?)
+    }
+
+    fn init(pool: SqlitePool) -> Result<Self> {
+        pool.get()?.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS reminders (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id TEXT NOT NULL,
+                channel_id TEXT NOT NULL,
+                requested_by TEXT NOT NULL,
+                message TEXT NOT NULL,
+                due_at INTEGER NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_reminders_due ON reminders(due_at);
+            "#,
+        )?;
+
+        Ok(Self { pool })
+    }
+
+    pub fn add(
+        &self,
+        user_id: &str,
+        channel_id: &str,
+        requested_by: &str,
+        message: &str,
+        due_at: i64,
+    ) -> Result<i64> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "INSERT INTO reminders (user_id, channel_id, requested_by, message, due_at, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                user_id,
+                channel_id,
+                requested_by,
+                message,
+                due_at,
+                chrono::Utc::now().timestamp()
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Number of reminders waiting for a user
+    pub fn pending_for(&self, user_id: &str) -> Result<usize> {
+        let count: i64 = self.pool.get()?.query_row(
+            "SELECT COUNT(*) FROM reminders WHERE user_id = ?1",
+            params![user_id],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// Reminders due at `now` (unix seconds), earliest first
+    pub fn due(&self, now: i64) -> Result<Vec<Reminder>> {
+        self.query("WHERE due_at <= ?1 ORDER BY due_at, id", params![now])
+    }
+
+    /// The next `limit` reminders to be sent, earliest first
+    pub fn upcoming(&self, limit: usize) -> Result<Vec<Reminder>> {
+        self.query("ORDER BY due_at, id LIMIT ?1", params![limit as i64])
+    }
+
+    fn query(&self, clause: &str, args: impl rusqlite::Params) -> Result<Vec<Reminder>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, user_id, channel_id, requested_by, message, due_at FROM reminders {}",
+            clause
+        ))?;
+        let reminders = stmt
+            .query_map(args, |row| {
+                Ok(Reminder {
+                    id: row.get(0)?,
+                    user_id: row.get(1)?,
+                    channel_id: row.get(2)?,
+                    requested_by: row.get(3)?,
+                    message: row.get(4)?,
+                    due_at: row.get(5)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(reminders)
+    }
+
+    pub fn remove(&self, id: i64) -> Result<()> {
+        self.pool
+            .get()?
+            .execute("DELETE FROM reminders WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+}
+
+/// When a reminder is due: a delay from `now` or a local date and time
+fn parse_when(when: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let when = when.trim();
+    if let Ok(delay) = parse_duration(when) {
+        return Some(now + chrono::Duration::from_std(delay).ok()?);
+    }
+    [
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(when, format).ok())
+    .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+    .filter(|due| *due > now)
+}
+
+/// Schedule a requested reminder. Err is a note for the requester.
+pub(super) fn schedule(
+    config: &DiscordRemindersConfig,
+    store: &ReminderStore,
+    request: &ReminderRequest,
+    requester: &QueuedMessage,
+    now: DateTime<Local>,
+) -> Result<DateTime<Local>, String> {
+    if !config.enabled {
+        return Err("Reminders are not enabled on this bot.".to_string());
+    }
+
+    let target = request.user.trim();
+    let user_id = if target.eq_ignore_ascii_case("me") {
+        requester.author_id.clone()
+    } else if let Some(c) = MENTION_RE.captures(target) {
+        c[1].to_string()
+    } else if !target.is_empty() && target.chars().all(|c| c.is_ascii_digit()) {
+        target.to_string()
+    } else {
+        return Err(format!("I don't know who \"{}\" is.", target));
+    };
+    if user_id != requester.author_id && !config.opt_in.contains(&user_id) {
+        return Err(format!(
+            "<@{}> hasn't opted in to reminders from others.",
+            user_id
+        ));
+    }
+
+    let Some(due) = parse_when(&request.when, now) else {
+        return Err(format!(
+            "I couldn't schedule a reminder for \"{}\".",
+            request.when
+        ));
+    };
+
+    let store_error = |e: anyhow::Error| {
+        warn!("Failed to store reminder for {}: {}", user_id, e);
+        "I couldn't save the reminder.".to_string()
+    };
+    if config.max_pending > 0
+        && store.pending_for(&user_id).map_err(store_error)? >= config.max_pending
+    {
+        return Err(format!(
+            "<@{}> already has {} reminders pending.",
+            user_id, config.max_pending
+        ));
+    }
+    store
+        .add(
+            &user_id,
+            &requester.channel_id,
+            &requester.author_id,
+            request.message.trim(),
+            due.timestamp(),
+        )
+        .map_err(store_error)?;
+
+    info!("Reminder for {} scheduled at {}", user_id, due);
+    Ok(due)
+}
+
+/// Send due reminders until the bot stops
+pub(super) async fn run(rest: Arc<dyn DiscordRest>) {
+    let store = match ReminderStore::open_default() {
+        Ok(store) => store,
+        Err(e) => {
+            warn!("Reminder store unavailable: {}", e);
+            return;
+        }
+    };
+
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        deliver_due(&store, rest.as_ref(), chrono::Utc::now().timestamp()).await;
+    }
+}
+
+async fn deliver_due(store: &ReminderStore, rest: &dyn DiscordRest, now: i64) {
+    let due = match store.due(now) {
+        Ok(due) => due,
+        Err(e) => {
+            warn!("Failed to load due reminders: {}", e);
+            return;
+        }
+    };
+
+    for reminder in due {
+        let text = if reminder.requested_by == reminder.user_id {
+            format!("⏰ Reminder: {}", reminder.message)
+        } else {
+            format!(
+                "⏰ Reminder from <@{}>: {}",
+                reminder.requested_by, reminder.message
+            )
+        };
+        let sent = match rest.create_dm(&reminder.user_id).await {
+            Ok(dm) => rest.send_message(&dm, &text, None).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        match sent {
+            Ok(()) => info!("Sent reminder {} to {}", reminder.id, reminder.user_id),
+            // DMs closed to the bot: remind where it was set instead
+            Err(RestError::Forbidden(_)) => {
+                let text = format!("<@{}> {} (your DMs are closed)", reminder.user_id, text);
+                if let Err(e) = rest.send_message(&reminder.channel_id, &text, None).await {
+                    warn!("Failed to deliver reminder {}: {}", reminder.id, e);
+                }
+            }
+            Err(e) if e.is_retryable() => {
+                warn!("Reminder {} not sent, retrying: {}", reminder.id, e);
+                continue;
+            }
+            Err(e) => warn!("Failed to deliver reminder {}: {}", reminder.id, e),
+        }
+        if let Err(e) = store.remove(reminder.id) {
+            warn!("Failed to remove reminder {}: {}", reminder.id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discord::rest::MockDiscordRest;
+
+    fn requester() -> QueuedMessage {
+        QueuedMessage {
+            channel_id: "42".to_string(),
+            guild_id: Some("1".to_string()),
+            message_id: "500".to_string(),
+            author_id: "7".to_string(),
+            author_name: "alice".to_string(),
+            author_roles: Vec::new(),
+            content: "remind me".to_string(),
+            image_urls: Vec::new(),
+            mention_count: 0,
+            addressed: true,
+        }
+    }
+
+    fn request(user: &str, when: &str) -> ReminderRequest {
+        ReminderRequest {
+            user: user.to_string(),
+            when: when.to_string(),
+            message: "stand-up".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_schedule_and_deliver() {
+        let store = ReminderStore::open_in_memory().unwrap();
+        let now = Local.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let mut config = DiscordRemindersConfig {
+            enabled: true,
+            opt_in: vec!["8".to_string()],
+            max_pending: 2,
+        };
+
+        let due = schedule(&config, &store, &request("me", "90m"), &requester(), now).unwrap();
+        assert_eq!(due, now + chrono::Duration::minutes(90));
+        let at = schedule(
+            &config,
+            &store,
+            &request("<@8>", "2026-10-17 09:00"),
+            &requester(),
+            now,
+        )
+        .unwrap();
+        assert_eq!(at.format("%d %H:%M").to_string(), "17 09:00");
+        assert!(schedule(&config, &store, &request("<@9>", "1h"), &requester(), now).is_err());
+        assert!(
+            schedule(
+                &config,
+                &store,
+                &request("me", "yesterday"),
+                &requester(),
+                now
+            )
+            .is_err()
+        );
+        assert!(
+            schedule(
+                &config,
+                &store,
+                &request("me", "2026-10-15 09:00"),
+                &requester(),
+                now
+            )
+            .is_err()
+        );
+        config.max_pending = 1;
+        assert!(schedule(&config, &store, &request("me", "1h"), &requester(), now).is_err());
+        config.enabled = false;
+        assert!(schedule(&config, &store, &request("8", "1h"), &requester(), now).is_err());
+
+        // User 7's DMs are open, user 8's are closed
+        let mut rest = MockDiscordRest::new();
+        rest.expect_create_dm().returning(|user| match user {
+            "7" => Ok("dm7".to_string()),
+            _ => Err(RestError::Forbidden(
+                "Cannot send messages to this user".to_string(),
+            )),
+        });
+        rest.expect_send_message()
+            .withf(|channel, text, _| channel == "dm7" && text == "⏰ Reminder: stand-up")
+            .times(1)
+            .returning(|_, _, _| Ok(vec!["1".to_string()]));
+        rest.expect_send_message()
+            .withf(|channel, text, _| {
+                channel == "42" && text.starts_with("<@8> ⏰ Reminder from <@7>: stand-up")
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(vec!["2".to_string()]));
+
+        deliver_due(&store, &rest, now.timestamp()).await;
+        assert_eq!(store.due(i64::MAX).unwrap().len(), 2);
+        deliver_due(&store, &rest, i64::MAX).await;
+        assert!(store.due(i64::MAX).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,843 @@
+//! Discord REST API client
+//!
+//! All HTTP calls to the Discord API go through [`DiscordRest`], implemented
+//! by [`RestClient`]. Requests time out after `request_timeout_secs` and are
+//! retried on 5xx responses and rate limits. The trait keeps the message
+//! processing code testable without a network connection.
+
+use async_trait::async_trait;
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::config::DiscordChannelConfig;
+
+pub const DISCORD_API_ROOT: &str = "https://discord.com/api";
+
+/// API version used unless `api_version` says otherwise
+pub const DEFAULT_API_VERSION: u8 = 10;
+
+/// Oldest API version Discord still serves
+pub const MIN_API_VERSION: u8 = 9;
+
+/// Discord message length limit (characters)
+pub const MESSAGE_LIMIT: usize = 2000;
+
+/// Upper bound for honoring a 429 `retry_after` before giving up
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(30);
+
+/// Channel types the agent can post to
+pub const CHANNEL_TEXT: u8 = 0;
+pub const CHANNEL_ANNOUNCEMENT: u8 = 5;
+pub const CHANNEL_FORUM: u8 = 15;
+
+/// Forum post titles are limited to 100 characters
+const THREAD_NAME_LIMIT: usize = 100;
+
+// ─── Response types ─────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordUser {
+    pub id: String,
+    pub username: String,
+    #[serde(default)]
+    pub bot: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordChannelInfo {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub channel_type: u8,
+    #[serde(default)]
+    pub name: String,
+    pub topic: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordMessageEntry {
+    #[serde(default)]
+    pub id: String,
+    pub content: String,
+    pub author: DiscordUser,
+    pub timestamp: String,
+}
+
+/// The bot's application (`GET /applications/@me`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordApplication {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    /// Application flags, including which privileged intents are enabled
+    #[serde(default)]
+    pub flags: u64,
+}
+
+/// A guild's custom emoji (`GET /guilds/{id}/emojis`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordEmoji {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    #[serde(default)]
+    pub animated: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChannelDetail {
+    guild_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatedMessage {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RateLimitBody {
+    retry_after: f64,
+}
+
+// ─── Errors ─────────────────────────────────────────────────────────
+
+#[derive(Debug, thiserror::Error)]
+pub enum RestError {
+    #[error("request failed: {0}")]
+    Transport(#[from] reqwest::Error),
+
+    #[error("rate limited (retry after {0:?})")]
+    RateLimited(Duration),
+
+    #[error("missing permissions: {0}")]
+    Forbidden(String),
+
+    #[error("not found: {0}")]
+    NotFound(String),
+
+    #[error("Discord API error {status}: {body}")]
+    Api { status: u16, body: String },
+
+    #[error("channel has no guild (DM channel?)")]
+    NoGuild,
+}
+
+impl RestError {
+    /// Whether retrying the same request may succeed
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            RestError::Transport(e) => e.is_timeout() || e.is_connect(),
+            RestError::RateLimited(_) => true,
+            RestError::Api { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// Whether a `method` request may be sent again after this error.
+    /// POST and PATCH aren't idempotent: after a timeout or a 5xx Discord
+    /// may have acted on them already, so they are only resent when it
+    /// surely didn't (rate limited, or never connected).
+    pub fn may_resend(&self, method: &reqwest::Method) -> bool {
+        if *method != reqwest::Method::POST && *method != reqwest::Method::PATCH {
+            return self.is_retryable();
+        }
+        match self {
+            RestError::Transport(e) => e.is_connect(),
+            RestError::RateLimited(_) => true,
+            _ => false,
+        }
+    }
+}
+
+pub type RestResult<T> = std::result::Result<T, RestError>;
+
+// ─── Trait ──────────────────────────────────────────────────────────
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait DiscordRest: Send + Sync {
+    /// Send a message, split at the 2000-character limit, and return the
+    /// IDs of the messages sent. Embeds are attached to the last chunk only.
+    async fn send_message(
+        &self,
+        channel_id: &str,
+        content: &str,
+        embeds: Option<Vec<serde_json::Value>>,
+    ) -> RestResult<Vec<String>>;
+
+    /// Send a single message (cut at the character limit) and return its ID
+    async fn post_message(&self, channel_id: &str, content: &str) -> RestResult<String>;
+
+    /// Send a single message with components (buttons, select menus) and
+    /// return its ID
+    async fn send_components(
+        &self,
+        channel_id: &str,
+        content: &str,
+        components: serde_json::Value,
+    ) -> RestResult<String>;
+
+    /// Answer a component interaction (within 3 seconds of receiving it)
+    async fn respond_to_interaction(
+        &self,
+        interaction_id: &str,
+        token: &str,
+        response: serde_json::Value,
+    ) -> RestResult<()>;
+
+    /// Replace the text of one of the bot's messages (cut at the limit)
+    async fn edit_message(
+        &self,
+        channel_id: &str,
+        message_id: &str,
+        content: &str,
+    ) -> RestResult<()>;
+
+    async fn add_reaction(&self, channel_id: &str, message_id: &str, emoji: &str)
+    -> RestResult<()>;
+
+    async fn send_typing(&self, channel_id: &str) -> RestResult<()>;
+
+    async fn list_channels(&self, guild_id: &str) -> RestResult<Vec<DiscordChannelInfo>>;
+
+    /// The guild's custom emoji
+    async fn list_guild_emojis(&self, guild_id: &str) -> RestResult<Vec<DiscordEmoji>>;
+
+    /// Read recent messages in chronological order (limit clamped to 1..=50)
+    async fn read_messages(
+        &self,
+        channel_id: &str,
+        limit: u32,
+    ) -> RestResult<Vec<DiscordMessageEntry>>;
+
+    /// Look up the guild a channel belongs to
+    async fn get_channel_guild(&self, channel_id: &str) -> RestResult<String>;
+
+    /// Look up a channel's name, type and topic
+    async fn get_channel(&self, channel_id: &str) -> RestResult<DiscordChannelInfo>;
+
+    /// The bot's own application, with its intent flags
+    async fn get_application(&self) -> RestResult<DiscordApplication>;
+
+    /// Open the DM channel with a user and return its ID (fails with
+    /// Forbidden when the user doesn't accept DMs from the bot)
+    async fn create_dm(&self, user_id: &str) -> RestResult<String>;
+
+    /// Start a post (thread) in a forum channel and return its ID
+    async fn create_forum_post(
+        &self,
+        channel_id: &str,
+        title: &str,
+        content: &str,
+    ) -> RestResult<String>;
+
+    /// Publish a message in an announcement channel to following servers
+    async fn crosspost_message(&self, channel_id: &str, message_id: &str) -> RestResult<()>;
+
+    /// Upload a file attachment with an optional message
+    async fn send_file(
+        &self,
+        channel_id: &str,
+        filename: &str,
+        data: Vec<u8>,
+        content: &str,
+    ) -> RestResult<()>;
+
+    async fn delete_message(&self, channel_id: &str, message_id: &str) -> RestResult<()>;
+
+    /// Time out a guild member (requires Moderate Members)
+    async fn timeout_member(
+        &self,
+        guild_id: &str,
+        user_id: &str,
+        duration: Duration,
+    ) -> RestResult<()>;
+}
+
+/// Request body for [`RestClient::execute`]
+enum RequestBody<'a> {
+    Empty,
+    Json(&'a serde_json::Value),
+    /// Pre-encoded multipart/form-data body
+    Multipart {
+        boundary: &'a str,
+        data: &'a [u8],
+    },
+}
+
+// ─── Client ─────────────────────────────────────────────────────────
+
+pub struct RestClient {
+    http: reqwest::Client,
+    token: String,
+    base_url: String,
+    max_retries: u32,
+}
+
+impl RestClient {
+    pub fn new(config: &DiscordChannelConfig) -> RestResult<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .build()?;
+
+        Ok(Self {
+            http,
+            token: config.token.clone(),
+            base_url: format!("{}/v{}", DISCORD_API_ROOT, config.api_version),
+            max_retries: config.max_retries,
+        })
+    }
+
+    /// Override the API base URL (e.g. for a local test server)
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Send a request, retrying on rate limits and connection failures, and
+    /// for idempotent methods also on timeouts and 5xx.
+    async fn execute(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: RequestBody<'_>,
+    ) -> RestResult<reqwest::Response> {
+        let url = format!("{}{}", self.base_url, path);
+        let mut attempt = 0;
+
+        loop {
+            let mut request = self
+                .http
+                .request(method.clone(), &url)
+                .header("Authorization", format!("Bot {}", self.token));
+            request = match body {
+                RequestBody::Json(json) => request.json(json),
+                RequestBody::Multipart { boundary, data } => request
+                    .header(
+                        "Content-Type",
+                        format!("multipart/form-data; boundary={}", boundary),
+                    )
+                    .body(data.to_vec()),
+                RequestBody::Empty => request.header("Content-Length", "0"),
+            };
+
+            let result = match request.send().await {
+                Ok(resp) => check_status(resp).await,
+                Err(e) => Err(RestError::from(e)),
+            };
+
+            match result {
+                Ok(resp) => return Ok(resp),
+                Err(e) if e.may_resend(&method) && attempt < self.max_retries => {
+                    let delay = retry_delay(&e, attempt);
+                    warn!(
+                        "Discord {} {} failed ({}), retrying in {:?}",
+                        method, path, e, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Map a non-success HTTP response to a typed error
+async fn check_status(resp: reqwest::Response) -> RestResult<reqwest::Response> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+
+    let body = resp.text().await.unwrap_or_default();
+    Err(match status.as_u16() {
+        429 => {
+            let secs = serde_json::from_str::<RateLimitBody>(&body)
+                .map(|b| b.retry_after)
+                .unwrap_or(1.0);
+            RestError::RateLimited(Duration::from_secs_f64(secs.max(0.0)))
+        }
+        403 => RestError::Forbidden(body),
+        404 => RestError::NotFound(body),
+        code => RestError::Api { status: code, body },
+    })
+}
+
+/// Delay before the next attempt: Discord's `retry_after` for rate limits,
+/// exponential backoff (0.5s, 1s, 2s, ...) otherwise.
+pub fn retry_delay(error: &RestError, attempt: u32) -> Duration {
+    match error {
+        RestError::RateLimited(after) => (*after).min(MAX_RATE_LIMIT_WAIT),
+        _ => Duration::from_millis(500 * 2u64.pow(attempt.min(6))),
+    }
+}
+
+#[async_trait]
+impl DiscordRest for RestClient {
+    async fn send_message(
+        &self,
+        channel_id: &str,
+        content: &str,
+        embeds: Option<Vec<serde_json::Value>>,
+    ) -> RestResult<Vec<String>> {
+        let chunks = split_message(content, MESSAGE_LIMIT);
+        let path = format!("/channels/{}/messages", channel_id);
+
+        let mut ids = Vec::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            // Attach embeds only to the last chunk
+            let body = match embeds {
+                Some(ref embeds) if i == chunks.len() - 1 => {
+                    serde_json::json!({"content": chunk, "embeds": embeds})
+                }
+                _ => serde_json::json!({"content": chunk}),
+            };
+            let resp = self
+                .execute(reqwest::Method::POST, &path, RequestBody::Json(&body))
+                .await?;
+            let created: CreatedMessage = resp.json().await?;
+            ids.push(created.id);
+        }
+
+        Ok(ids)
+    }
+
+    async fn post_message(&self, channel_id: &str, content: &str) -> RestResult<String> {
+        let path = format!("/channels/{}/messages", channel_id);
+        let body = serde_json::json!({"content": first_chunk(content)});
+        let resp = self
+            .execute(reqwest::Method::POST, &path, RequestBody::Json(&body))
+            .await?;
+        let created: CreatedMessage = resp.json().await?;
+        Ok(created.id)
+    }
+
+    async fn send_components(
+        &self,
+        channel_id: &str,
+        content: &str,
+        components: serde_json::Value,
+    ) -> RestResult<String> {
+        let path = format!("/channels/{}/messages", channel_id);
+        let body = serde_json::json!({"content": first_chunk(content), "components": components});
+        let resp = self
+            .execute(reqwest::Method::POST, &path, RequestBody::Json(&body))
+            .await?;
+        let created: CreatedMessage = resp.json().await?;
+        Ok(created.id)
+    }
+
+    async fn respond_to_interaction(
+        &self,
+        interaction_id: &str,
+        token: &str,
+        response: serde_json::Value,
+    ) -> RestResult<()> {
+        let path = format!("/interactions/{}/{}/callback", interaction_id, token);
+        self.execute(reqwest::Method::POST, &path, RequestBody::Json(&response))
+            .await?;
+        Ok(())
+    }
+
+    async fn edit_message(
+        &self,
+        channel_id: &str,
+        message_id: &str,
+        content: &str,
+    ) -> RestResult<()> {
+        let path = format!("/channels/{}/messages/{}", channel_id, message_id);
+        let body = serde_json::json!({"content": first_chunk(content)});
+        self.execute(reqwest::Method::PATCH, &path, RequestBody::Json(&body))
+            .await?;
+        Ok(())
+    }
+
+    async fn add_reaction(
+        &self,
+        channel_id: &str,
+        message_id: &str,
+        emoji: &str,
+    ) -> RestResult<()> {
+        let encoded_emoji = utf8_percent_encode(emoji, NON_ALPHANUMERIC).to_string();
+        let path = format!(
+            "/channels/{}/messages/{}/reactions/{}/@me",
+            channel_id, message_id, encoded_emoji
+        );
+        self.execute(reqwest::Method::PUT, &path, RequestBody::Empty)
+            .await?;
+        Ok(())
+    }
+
+    async fn send_typing(&self, channel_id: &str) -> RestResult<()> {
+        let path = format!("/channels/{}/typing", channel_id);
+        self.execute(reqwest::Method::POST, &path, RequestBody::Empty)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_channels(&self, guild_id: &str) -> RestResult<Vec<DiscordChannelInfo>> {
+        let path = format!("/guilds/{}/channels", guild_id);
+        let resp = self
+            .execute(reqwest::Method::GET, &path, RequestBody::Empty)
+            .await?;
+        Ok(resp.json().await?)
+    }
+
+    async fn list_guild_emojis(&self, guild_id: &str) -> RestResult<Vec<DiscordEmoji>> {
+        let path = format!("/guilds/{}/emojis", guild_id);
+        let resp = self
+            .execute(reqwest::Method::GET, &path, RequestBody::Empty)
+            .await?;
+        Ok(resp.json().await?)
+    }
+
+    async fn read_messages(
+        &self,
+        channel_id: &str,
+        limit: u32,
+    ) -> RestResult<Vec<DiscordMessageEntry>> {
+        let path = format!(
+            "/channels/{}/messages?limit={}",
+            channel_id,
+            limit.clamp(1, 50)
+        );
+        let resp = self
+            .execute(reqwest::Method::GET, &path, RequestBody::Empty)
+            .await?;
+        let mut messages: Vec<DiscordMessageEntry> = resp.json().await?;
+        // Discord returns newest first; reverse for chronological order
+        messages.reverse();
+        debug!("Read {} messages from {}", messages.len(), channel_id);
+        Ok(messages)
+    }
+
+    async fn get_channel_guild(&self, channel_id: &str) -> RestResult<String> {
+        let path = format!("/channels/{}", channel_id);
+        let resp = self
+            .execute(reqwest::Method::GET, &path, RequestBody::Empty)
+            .await?;
+        let info: ChannelDetail = resp.json().await?;
+        info.guild_id.ok_or(RestError::NoGuild)
+    }
+
+    async fn get_channel(&self, channel_id: &str) -> RestResult<DiscordChannelInfo> {
+        let path = format!("/channels/{}", channel_id);
+        let resp = self
+            .execute(reqwest::Method::GET, &path, RequestBody::Empty)
+            .await?;
+        Ok(resp.json().await?)
+    }
+
+    async fn get_application(&self) -> RestResult<DiscordApplication> {
+        let path = "/applications/@me";
+        let resp = self
+            .execute(reqwest::Method::GET, path, RequestBody::Empty)
+            .await?;
+        Ok(resp.json().await?)
+    }
+
+    async fn create_dm(&self, user_id: &str) -> RestResult<String> {
+        let body = serde_json::json!({"recipient_id": user_id});
+        let resp = self
+            .execute(
+                reqwest::Method::POST,
+                "/users/@me/channels",
+                RequestBody::Json(&body),
+            )
+            .await?;
+        let channel: DiscordChannelInfo = resp.json().await?;
+        Ok(channel.id)
+    }
+
+    async fn create_forum_post(
+        &self,
+        channel_id: &str,
+        title: &str,
+        content: &str,
+    ) -> RestResult<String> {
+        let path = format!("/channels/{}/threads", channel_id);
+        let name: String = title.chars().take(THREAD_NAME_LIMIT).collect();
+        let body = serde_json::json!({
+            "name": name,
+            "message": {"content": first_chunk(content)},
+        });
+        let resp = self
+            .execute(reqwest::Method::POST, &path, RequestBody::Json(&body))
+            .await?;
+        let created: CreatedMessage = resp.json().await?;
+        Ok(created.id)
+    }
+
+    async fn crosspost_message(&self, channel_id: &str, message_id: &str) -> RestResult<()> {
+        let path = format!("/channels/{}/messages/{}/crosspost", channel_id, message_id);
+        self.execute(reqwest::Method::POST, &path, RequestBody::Empty)
+            .await?;
+        Ok(())
+    }
+
+    async fn send_file(
+        &self,
+        channel_id: &str,
+        filename: &str,
+        data: Vec<u8>,
+        content: &str,
+    ) -> RestResult<()> {
+        let path = format!("/channels/{}/messages", channel_id);
+        let boundary = format!("localgpt-{}", uuid::Uuid::new_v4().simple());
+        let payload = serde_json::json!({
+            "content": content,
+            "attachments": [{"id": 0, "filename": filename}],
+        });
+        let body = multipart_body(&boundary, &payload, filename, &data);
+        self.execute(
+            reqwest::Method::POST,
+            &path,
+            RequestBody::Multipart {
+                boundary: &boundary,
+                data: &body,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_message(&self, channel_id: &str, message_id: &str) -> RestResult<()> {
+        let path = format!("/channels/{}/messages/{}", channel_id, message_id);
+        self.execute(reqwest::Method::DELETE, &path, RequestBody::Empty)
+            .await?;
+        Ok(())
+    }
+
+    async fn timeout_member(
+        &self,
+        guild_id: &str,
+        user_id: &str,
+        duration: Duration,
+    ) -> RestResult<()> {
+        let path = format!("/guilds/{}/members/{}", guild_id, user_id);
+        let until = chrono::Utc::now()
+            + chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::minutes(10));
+        let body = serde_json::json!({"communication_disabled_until": until.to_rfc3339()});
+        self.execute(reqwest::Method::PATCH, &path, RequestBody::Json(&body))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Encode a `payload_json` part and a single `files[0]` part as
+/// multipart/form-data (reqwest is built without its multipart feature)
+fn multipart_body(
+    boundary: &str,
+    payload: &serde_json::Value,
+    filename: &str,
+    data: &[u8],
+) -> Vec<u8> {
+    let filename = filename.replace(['"', '\r', '\n'], "_");
+    let mut body = Vec::with_capacity(data.len() + 512);
+
+    body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+    body.extend_from_slice(b"Content-Disposition: form-data; name=\"payload_json\"\r\n");
+    body.extend_from_slice(b"Content-Type: application/json\r\n\r\n");
+    body.extend_from_slice(payload.to_string().as_bytes());
+    body.extend_from_slice(b"\r\n");
+
+    body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+    body.extend_from_slice(
+        format!(
+            "Content-Disposition: form-data; name=\"files[0]\"; filename=\"{}\"\r\n",
+            filename
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(b"Content-Type: application/octet-stream\r\n\r\n");
+    body.extend_from_slice(data);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+    body
+}
+
+// ─── Formatting helpers ─────────────────────────────────────────────
+
+/// Name of a channel type the agent can post to
+pub fn channel_kind(channel_type: u8) -> Option<&'static str> {
+    match channel_type {
+        CHANNEL_TEXT => Some("text"),
+        CHANNEL_ANNOUNCEMENT => Some("announcement"),
+        CHANNEL_FORUM => Some("forum"),
+        _ => None,
+    }
+}
+
+/// Format text, announcement and forum channels for the agent, with their type
+pub fn format_channel_list(channels: &[DiscordChannelInfo]) -> String {
+    channels
+        .iter()
+        .filter_map(|c| {
+            let kind = channel_kind(c.channel_type)?;
+            Some(match c.topic.as_deref().filter(|t| !t.is_empty()) {
+                Some(topic) => format!("{} (ID: {}) [{}] - {}", c.name, c.id, kind, topic),
+                None => format!("{} (ID: {}) [{}]", c.name, c.id, kind),
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Format message history as `[author HH:MM] content` lines
+pub fn format_message_history(messages: &[DiscordMessageEntry]) -> String {
+    messages
+        .iter()
+        .map(|m| {
+            let time = extract_time_from_timestamp(&m.timestamp);
+            format!("[{} {}] {}", m.author.username, time, m.content)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Split a message into chunks respecting the Discord character limit.
+/// Tries to split at newline boundaries when possible.
+pub fn split_message(content: &str, max_len: usize) -> Vec<String> {
+    if content.len() <= max_len {
+        return vec![content.to_string()];
+    }
+
+    let mut chunks = Vec::new();
+    let mut remaining = content;
+
+    while !remaining.is_empty() {
+        if remaining.len() <= max_len {
+            chunks.push(remaining.to_string());
+            break;
+        }
+
+        // Try to find a newline to split at (char-boundary safe)
+        let byte_max = remaining
+            .char_indices()
+            .take_while(|(i, _)| *i < max_len)
+            .last()
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(remaining.len().min(max_len));
+        let safe_slice = &remaining[..byte_max];
+        let split_at = safe_slice.rfind('\n').unwrap_or(byte_max);
+
+        let (chunk, rest) = remaining.split_at(split_at);
+        chunks.push(chunk.to_string());
+        remaining = rest.trim_start_matches('\n');
+    }
+
+    chunks
+}
+
+/// The part of `content` that fits in one message
+fn first_chunk(content: &str) -> String {
+    split_message(content, MESSAGE_LIMIT)
+        .into_iter()
+        .next()
+        .unwrap_or_default()
+}
+
+/// Extract HH:MM from a Discord ISO 8601 timestamp
+pub fn extract_time_from_timestamp(ts: &str) -> String {
+    // Discord timestamp format: "2026-02-09T10:30:00.000000+00:00"
+    if let Some(t_pos) = ts.find('T') {
+        let time_part = &ts[t_pos + 1..];
+        if time_part.len() >= 5 {
+            return time_part[..5].to_string();
+        }
+    }
+    "??:??".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_message_prefers_newlines() {
+        let text = format!("{}\n{}", "a".repeat(15), "b".repeat(15));
+        let chunks = split_message(&text, 20);
+        assert_eq!(chunks, vec!["a".repeat(15), "b".repeat(15)]);
+    }
+
+    #[test]
+    fn test_split_message_multibyte() {
+        let text = "あ".repeat(10); // 30 bytes
+        let chunks = split_message(&text, 10);
+        assert!(chunks.iter().all(|c| c.len() <= 10));
+        assert_eq!(chunks.concat(), text);
+    }
+
+    #[test]
+    fn test_extract_time_from_timestamp() {
+        assert_eq!(
+            extract_time_from_timestamp("2026-02-09T10:30:00.000000+00:00"),
+            "10:30"
+        );
+        assert_eq!(extract_time_from_timestamp("garbage"), "??:??");
+    }
+
+    #[test]
+    fn test_format_channel_list_filters_non_text() {
+        let channels = vec![
+            DiscordChannelInfo {
+                id: "1".into(),
+                channel_type: 0,
+                name: "general".into(),
+                topic: Some("chat".into()),
+            },
+            DiscordChannelInfo {
+                id: "2".into(),
+                channel_type: 2,
+                name: "voice".into(),
+                topic: None,
+            },
+            DiscordChannelInfo {
+                id: "3".into(),
+                channel_type: 15,
+                name: "help".into(),
+                topic: None,
+            },
+        ];
+        assert_eq!(
+            format_channel_list(&channels),
+            "general (ID: 1) [text] - chat\nhelp (ID: 3) [forum]"
+        );
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let server_error = RestError::Api {
+            status: 502,
+            body: String::new(),
+        };
+        assert!(server_error.is_retryable());
+        assert!(server_error.may_resend(&reqwest::Method::GET));
+        assert!(!server_error.may_resend(&reqwest::Method::POST));
+        assert_eq!(retry_delay(&server_error, 0), Duration::from_millis(500));
+        assert_eq!(retry_delay(&server_error, 2), Duration::from_secs(2));
+
+        let limited = RestError::RateLimited(Duration::from_secs(120));
+        assert!(limited.is_retryable());
+        assert!(limited.may_resend(&reqwest::Method::PATCH));
+        assert_eq!(retry_delay(&limited, 0), MAX_RATE_LIMIT_WAIT);
+
+        assert!(!RestError::Forbidden(String::new()).is_retryable());
+        assert!(!RestError::NotFound(String::new()).is_retryable());
+    }
+
+    #[test]
+    fn test_multipart_body() {
+        let payload = serde_json::json!({"content": "hi"});
+        let body = multipart_body("XYZ", &payload, "a\"b.md", b"# Title");
+        let text = String::from_utf8(body).unwrap();
+
+        assert!(text.starts_with("--XYZ\r\n"));
+        assert!(text.contains(
+            "name=\"payload_json\"\r\nContent-Type: application/json\r\n\r\n{\"content\":\"hi\"}\r\n"
+        ));
+        assert!(text.contains("name=\"files[0]\"; filename=\"a_b.md\""));
+        assert!(text.ends_with("# Title\r\n--XYZ--\r\n"));
+    }
+}
@@ -0,0 +1,445 @@
+//! Archiving idle channel agents
+//!
+//! Every channel the bot talks in gets its own agent, which stays in memory
+//! until the bot stops. In large servers that adds up, so once a minute
+//! agents idle for `sessions.idle_ttl` are archived, as are the least
+//! recently used ones beyond `sessions.max_agents`. Archiving saves the
+//! session to disk, remembers it per channel in `discord/sessions.sqlite`
+//! and writes a summary of the conversation to the workspace's `memory/`
+//! directory. Summaries of agents archived in the same sweep are batched
+//! into shared requests (see [`summarize_batch`]). The next message in the
+//! channel creates a new agent that resumes the archived session; when that
+//! one is archived, only the messages added since are summarized.
+
+use anyhow::Result;
+use rusqlite::{OptionalExtension, params};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use super::SharedAgentMap;
+use crate::agent::{Agent, Role, SessionMessage, summarize_batch};
+use crate::config::{Config, parse_duration};
+use crate::db::SqlitePool;
+use crate::memory::{Vault, write_memory_file};
+use crate::security::redact;
+
+/// How often idle agents are looked for
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Last message per channel, for finding idle agents
+pub(super) type ActivityMap = Arc<std::sync::Mutex<HashMap<String, Instant>>>;
+
+/// Archived session of each channel
+#[derive(Clone)]
+pub struct ArchiveStore {
+    pool: SqlitePool,
+}
+
+impl ArchiveStore {
+    /// Open the shared store at `~/.localgpt/discord/sessions.sqlite`
+    pub fn open_default() -> Result<Self> {
+        Self::open(
+            &crate::agent::get_state_dir()?
+                .join("discord")
+                .join("sessions.sqlite"),
+        )
+    }
+
+    pub fn open(db_path: &Path) -> Result<Self> {
+        Self::init(SqlitePool::shared(db_path, None)?)
+    }
+
+    /// In-memory store (tests)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(SqlitePool::open_in_memory(None)?)
+    }
+
+    fn init(pool: SqlitePool) -> Result<Self> {
+        let conn = pool.get()?;
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS archived_sessions (
+                channel_id TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                archived_at INTEGER NOT NULL,
+                summarized INTEGER NOT NULL DEFAULT 0
+            );
+            "#,
+        )?;
+        // Stores created before summaries were incremental
+        if conn
+            .prepare("SELECT summarized FROM archived_sessions LIMIT 0")
+            .is_err()
+        {
+            conn.execute(
+                "ALTER TABLE archived_sessions ADD COLUMN summarized INTEGER NOT NULL DEFAULT 0",
+                [],
+            )?;
+        }
+        drop(conn);
+
+        Ok(Self { pool })
+    }
+
+    /// Remember the channel's session. The count of summarized messages
+    /// carries over when the same session is archived again.
+    pub fn record(&self, channel_id: &str, session_id: &str) -> Result<()> {
+        self.pool.get()?.execute(
+            "INSERT INTO archived_sessions (channel_id, session_id, archived_at)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT(channel_id) DO UPDATE SET
+                 summarized = CASE WHEN session_id = excluded.session_id
+                                   THEN summarized ELSE 0 END,
+                 session_id = excluded.session_id,
+                 archived_at = excluded.archived_at",
+            params![channel_id, session_id, chrono::Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    /// How many messages of the channel's session are already summarized
+    pub fn summarized(&self, channel_id: &str) -> Result<usize> {
+        let count: Option<i64> = self
+            .pool
+            .get()?
+            .query_row(
+                "SELECT summarized FROM archived_sessions WHERE channel_id = ?1",
+                params![channel_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(count.unwrap_or(0) as usize)
+    }
+
+    pub fn set_summarized(&self, channel_id: &str, count: usize) -> Result<()> {
+        self.pool.get()?.execute(
+            "UPDATE archived_sessions SET summarized = ?2 WHERE channel_id = ?1",
+            params![channel_id, count as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Drop the channel's archived session, so it is not resumed
+    pub fn forget(&self, channel_id: &str) -> Result<()> {
+        self.pool.get()?.execute(
+            "DELETE FROM archived_sessions WHERE channel_id = ?1",
+            params![channel_id],
+        )?;
+        Ok(())
+    }
+
+    /// The channel's last archived session, if any
+    pub fn session_for(&self, channel_id: &str) -> Result<Option<String>> {
+        let session_id = self
+            .pool
+            .get()?
+            .query_row(
+                "SELECT session_id FROM archived_sessions WHERE channel_id = ?1",
+                params![channel_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(session_id)
+    }
+}
+
+/// The session a new agent for the channel should resume
+pub(super) fn archived_session(channel_id: &str) -> Option<String> {
+    ArchiveStore::open_default()
+        .and_then(|store| store.session_for(channel_id))
+        .unwrap_or_else(|e| {
+            warn!(
+                "Failed to look up archived session of {}: {}",
+                channel_id, e
+            );
+            None
+        })
+}
+
+/// Channels whose agents should be archived: idle for `ttl` or longer,
+/// then the least recently active beyond `max_agents`. Channels without
+/// recorded activity count as active now.
+fn to_archive(
+    live: &[String],
+    activity: &HashMap<String, Instant>,
+    now: Instant,
+    ttl: Option<Duration>,
+    max_agents: usize,
+) -> Vec<String> {
+    let mut by_age: Vec<(&String, Duration)> = live
+        .iter()
+        .map(|channel| {
+            let idle = activity
+                .get(channel)
+                .map_or(Duration::ZERO, |last| now.saturating_duration_since(*last));
+            (channel, idle)
+        })
+        .collect();
+    by_age.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+    let over_limit = if max_agents > 0 {
+        live.len().saturating_sub(max_agents)
+    } else {
+        0
+    };
+    by_age
+        .into_iter()
+        .enumerate()
+        .filter(|(i, (_, idle))| *i < over_limit || ttl.is_some_and(|ttl| *idle >= ttl))
+        .map(|(_, (channel, _))| channel.clone())
+        .collect()
+}
+
+/// Archive idle agents until the bot stops
+pub(super) async fn run(config: Config, agents: SharedAgentMap, activity: ActivityMap) {
+    let settings = config
+        .channels
+        .discord
+        .as_ref()
+        .map(|d| d.sessions.clone())
+        .unwrap_or_default();
+    let ttl = match parse_duration(&settings.idle_ttl) {
+        Ok(ttl) if !ttl.is_zero() => Some(ttl),
+        Ok(_) => None,
+        Err(_) if settings.idle_ttl.trim() == "0" => None,
+        Err(e) => {
+            warn!("Invalid sessions.idle_ttl, idle agents are kept: {}", e);
+            None
+        }
+    };
+    if ttl.is_none() && settings.max_agents == 0 {
+        return;
+    }
+
+    let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let config = config.clone();
+        let agents = Arc::clone(&agents);
+        let activity = Arc::clone(&activity);
+        let max_agents = settings.max_agents;
+
+        // Agent futures are not Send; drive them on a blocking thread
+        let result = tokio::task::spawn_blocking(move || {
+            let rt = tokio::runtime::Handle::current();
+            rt.block_on(sweep(&config, &agents, &activity, ttl, max_agents))
+        })
+        .await;
+        if let Err(e) = result {
+            warn!("Session archiving panicked: {}", e);
+        }
+    }
+}
+
+async fn sweep(
+    config: &Config,
+    agents: &SharedAgentMap,
+    activity: &ActivityMap,
+    ttl: Option<Duration>,
+    max_agents: usize,
+) {
+    // Save and remove under the lock so the next message resumes the saved
+    // session; summaries are written after it is released
+    let (store, archived): (ArchiveStore, Vec<(String, Agent)>) = {
+        let mut guard = agents.lock().await;
+        let live: Vec<String> = guard.keys().cloned().collect();
+        let expired = {
+            let now = Instant::now();
+            let mut activity = activity.lock().unwrap();
+            // Agents created without a message (e.g. by /model) age from now
+            for channel in &live {
+                activity.entry(channel.clone()).or_insert(now);
+            }
+            to_archive(&live, &activity, now, ttl, max_agents)
+        };
+        if expired.is_empty() {
+            return;
+        }
+
+        let store = match ArchiveStore::open_default() {
+            Ok(store) => store,
+            Err(e) => {
+                warn!("Session archive unavailable, keeping idle agents: {}", e);
+                return;
+            }
+        };
+        let mut archived = Vec::new();
+        for channel_id in expired {
+            let Some(agent) = guard.remove(&channel_id) else {
+                continue;
+            };
+            let saved = match agent.save_session().await {
+                Ok(_) => store.record(&channel_id, &agent.session_status().id),
+                Err(e) => Err(e),
+            };
+            match saved {
+                Ok(()) => {
+                    activity.lock().unwrap().remove(&channel_id);
+                    archived.push((channel_id, agent));
+                }
+                Err(e) => {
+                    warn!("Failed to archive session of {}: {}", channel_id, e);
+                    guard.insert(channel_id, agent);
+                }
+            }
+        }
+        (store, archived)
+    };
+
+    // Agents on the same model share summary requests
+    type Pending = (String, Agent, String, usize);
+    let mut by_model: HashMap<String, Vec<Pending>> = HashMap::new();
+    for (channel_id, agent) in archived {
+        info!("Archived idle agent of channel {}", channel_id);
+        let summarized = store.summarized(&channel_id).unwrap_or_else(|e| {
+            warn!("Failed to look up summary of {}: {}", channel_id, e);
+            0
+        });
+        let messages = agent.raw_session_messages();
+        let count = messages.len();
+        match transcript(messages, summarized) {
+            Some(text) => by_model
+                .entry(agent.model().to_string())
+                .or_default()
+                .push((channel_id, agent, text, count)),
+            None => debug!("Nothing new to summarize for channel {}", channel_id),
+        }
+    }
+    for group in by_model.into_values() {
+        let texts: Vec<String> = group.iter().map(|(_, _, text, _)| text.clone()).collect();
+        let provider = group[0].1.provider();
+        let summaries = summarize_batch(provider, &texts, config.agent.summary_batch_chars).await;
+        for ((channel_id, _, _, count), summary) in group.iter().zip(summaries) {
+            let written = summary
+                .and_then(|summary| write_summary(config, channel_id, &summary))
+                .and_then(|()| store.set_summarized(channel_id, *count));
+            if let Err(e) = written {
+                warn!("Failed to summarize session of {}: {}", channel_id, e);
+            }
+        }
+    }
+}
+
+/// The conversation after its first `summarized` messages as plain text,
+/// if there is any. A session with fewer messages than that was compacted
+/// since, so all of it is used.
+fn transcript(messages: &[SessionMessage], summarized: usize) -> Option<String> {
+    let skip = if summarized <= messages.len() {
+        summarized
+    } else {
+        0
+    };
+    let transcript = messages
+        .iter()
+        .skip(skip)
+        .map(|m| &m.message)
+        .filter_map(|m| match m.role {
+            Role::User => Some(format!("User: {}", m.content)),
+            Role::Assistant => Some(format!("Assistant: {}", m.content)),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    (!transcript.is_empty()).then_some(transcript)
+}
+
+/// Append a summary of a channel's conversation to the memory directory
+fn write_summary(config: &Config, channel_id: &str, summary: &str) -> Result<()> {
+    let now = chrono::Local::now();
+    let workspace = config.workspace_path();
+    let path = workspace.join("memory").join(format!(
+        "{}-discord-{}.md",
+        now.format("%Y-%m-%d"),
+        channel_id
+    ));
+    let mut content = std::fs::read_to_string(&path).unwrap_or_default();
+    if content.is_empty() {
+        content = format!("# Discord channel {}\n", channel_id);
+    }
+    let summary = redact("memory", &path.display().to_string(), summary);
+    content.push_str(&format!(
+        "\n## Session summary ({})\n\n{}\n",
+        now.format("%H:%M"),
+        summary.trim()
+    ));
+    write_memory_file(&workspace, &path, &content, "session_summary")?;
+    debug!("Wrote session summary to {}", path.display());
+    if let Some(vault) = Vault::from_config(&workspace, &config.memory.obsidian)
+        && let Err(e) = vault.link_from_daily_note(
+            now.date_naive(),
+            &path,
+            &format!("Discord channel {}", channel_id),
+        )
+    {
+        warn!("Failed to link {} from daily note: {}", path.display(), e);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_selection_and_store() {
+        let now = Instant::now() + Duration::from_secs(7200);
+        let live: Vec<String> = ["a", "b", "c", "d"].iter().map(|c| c.to_string()).collect();
+        let activity = HashMap::from([
+            ("a".to_string(), now - Duration::from_secs(7200)),
+            ("b".to_string(), now - Duration::from_secs(60)),
+            ("c".to_string(), now - Duration::from_secs(600)),
+        ]);
+        let hour = Some(Duration::from_secs(3600));
+
+        assert_eq!(to_archive(&live, &activity, now, hour, 0), vec!["a"]);
+        assert_eq!(to_archive(&live, &activity, now, hour, 2), vec!["a", "c"]);
+        assert_eq!(to_archive(&live, &activity, now, None, 3), vec!["a"]);
+        assert!(to_archive(&live, &activity, now, None, 0).is_empty());
+
+        let store = ArchiveStore::open_in_memory().unwrap();
+        assert_eq!(store.session_for("a").unwrap(), None);
+        store.record("a", "s1").unwrap();
+        store.record("a", "s2").unwrap();
+        assert_eq!(store.session_for("a").unwrap().as_deref(), Some("s2"));
+        store.forget("a").unwrap();
+        assert_eq!(store.session_for("a").unwrap(), None);
+
+        // The summarized count survives archiving the same session again
+        store.record("a", "s1").unwrap();
+        store.set_summarized("a", 4).unwrap();
+        store.record("a", "s1").unwrap();
+        assert_eq!(store.summarized("a").unwrap(), 4);
+        store.record("a", "s2").unwrap();
+        assert_eq!(store.summarized("a").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_transcript_skips_summarized() {
+        let message = |role, content: &str| {
+            SessionMessage::new(crate::agent::Message {
+                role,
+                content: content.to_string(),
+                tool_calls: None,
+                tool_call_id: None,
+                images: Vec::new(),
+            })
+        };
+        let messages = vec![
+            message(Role::User, "hi"),
+            message(Role::Tool, "{}"),
+            message(Role::Assistant, "hello"),
+            message(Role::User, "bye"),
+        ];
+        assert_eq!(transcript(&messages, 3).as_deref(), Some("User: bye"));
+        assert_eq!(transcript(&messages, 4), None);
+        // Compacted since: everything is new
+        assert_eq!(
+            transcript(&messages, 9).as_deref(),
+            Some("User: hi\n\nAssistant: hello\n\nUser: bye")
+        );
+    }
+}
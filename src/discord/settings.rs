@@ -0,0 +1,413 @@
+//! Runtime guild and channel settings
+//!
+//! `require_mention`, `ambient`, `language` and `persona` start out as
+//! configured in `[[channels.discord.guilds]]` and can be changed while the
+//! bot runs, with `/settings` (admins only) or
+//! `PUT /api/discord/settings/<guild_id>`. Changes are kept in
+//! `discord/settings.sqlite` in the state directory and read for every
+//! message, so they apply at once. A value set for a channel overrides the
+//! guild's, which overrides the config.
+
+use anyhow::{Result, anyhow, bail};
+use rusqlite::params;
+use serde::Serialize;
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::agent::Language;
+use crate::config::{Config, DiscordGuildConfig};
+use crate::db::SqlitePool;
+
+/// Longest persona (characters)
+const MAX_PERSONA_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    /// Only answer messages that mention the bot
+    RequireMention,
+    /// Read every message and join in when there is something to add
+    Ambient,
+    /// Language of the prompts and the persona file
+    Language,
+    /// Extra persona instructions for the channel's agent
+    Persona,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 4] = [
+        Setting::RequireMention,
+        Setting::Ambient,
+        Setting::Language,
+        Setting::Persona,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Setting::RequireMention => "require_mention",
+            Setting::Ambient => "ambient",
+            Setting::Language => "language",
+            Setting::Persona => "persona",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase().replace('-', "_");
+        Self::ALL.into_iter().find(|s| s.name() == name)
+    }
+
+    /// The stored form of `value`, or why it isn't valid
+    pub fn normalize(self, value: &str) -> Result<String> {
+        let value = value.trim();
+        match self {
+            Setting::RequireMention | Setting::Ambient => match value.to_lowercase().as_str() {
+                "true" | "on" | "yes" | "1" => Ok("true".to_string()),
+                "false" | "off" | "no" | "0" => Ok("false".to_string()),
+                _ => bail!("{} is on or off", self.name()),
+            },
+            Setting::Language => Language::parse(value)
+                .map(|l| l.code().to_string())
+                .ok_or_else(|| anyhow!("No translations for language {:?}", value)),
+            Setting::Persona => {
+                if value.is_empty() {
+                    bail!("The persona is empty");
+                }
+                if value.chars().count() > MAX_PERSONA_CHARS {
+                    bail!(
+                        "The persona is longer than {} characters",
+                        MAX_PERSONA_CHARS
+                    );
+                }
+                Ok(value.to_string())
+            }
+        }
+    }
+
+    /// The configured value (None = unset)
+    fn default_value(self, guild: Option<&DiscordGuildConfig>) -> Option<String> {
+        let guild = guild?;
+        match self {
+            Setting::RequireMention => Some(guild.require_mention.to_string()),
+            Setting::Ambient => Some(guild.ambient.to_string()),
+            Setting::Language => guild.language.clone(),
+            Setting::Persona => guild.persona.clone(),
+        }
+    }
+}
+
+/// A setting's effective value and where it comes from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SettingValue {
+    pub name: &'static str,
+    pub value: Option<String>,
+    /// "config", "guild" or "channel"
+    pub source: &'static str,
+}
+
+/// The effective settings of a channel
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    pub require_mention: bool,
+    pub ambient: bool,
+    /// None = `agent.language`
+    pub language: Option<Language>,
+    pub persona: Option<String>,
+}
+
+impl Settings {
+    fn from_values(values: &[SettingValue]) -> Self {
+        let get = |setting: Setting| {
+            values
+                .iter()
+                .find(|v| v.name == setting.name())
+                .and_then(|v| v.value.as_deref())
+        };
+        Self {
+            require_mention: get(Setting::RequireMention) == Some("true"),
+            ambient: get(Setting::Ambient) == Some("true"),
+            language: get(Setting::Language).and_then(Language::parse),
+            persona: get(Setting::Persona).map(String::from),
+        }
+    }
+
+    /// Turn instructions for the channel's agent
+    pub fn instructions(&self) -> Option<String> {
+        let mut lines = Vec::new();
+        if let Some(ref persona) = self.persona {
+            lines.push(format!("Persona for this channel:\n{}", persona));
+        }
+        if self.ambient {
+            lines.push(
+                "You see every message in this channel, not only the ones addressed to you. \
+                 Reply NO_REPLY unless you are addressed or have something useful to add."
+                    .to_string(),
+            );
+        }
+        (!lines.is_empty()).then(|| lines.join("\n\n"))
+    }
+}
+
+/// The config of a guild
+pub fn guild_config<'a>(config: &'a Config, guild_id: &str) -> Option<&'a DiscordGuildConfig> {
+    config
+        .channels
+        .discord
+        .as_ref()?
+        .guilds
+        .iter()
+        .find(|g| g.guild_id == guild_id)
+}
+
+/// The effective settings of a channel; the configured ones if the store
+/// can't be read
+pub fn for_channel(guild: Option<&DiscordGuildConfig>, channel_id: &str) -> Settings {
+    let values =
+        SettingsStore::open_default().and_then(|store| store.resolve(guild, Some(channel_id)));
+    match values {
+        Ok(values) => Settings::from_values(&values),
+        Err(e) => {
+            warn!(
+                "Failed to read the settings of channel {}: {}",
+                channel_id, e
+            );
+            Settings::from_values(&resolve_defaults(guild))
+        }
+    }
+}
+
+fn resolve_defaults(guild: Option<&DiscordGuildConfig>) -> Vec<SettingValue> {
+    Setting::ALL
+        .into_iter()
+        .map(|setting| SettingValue {
+            name: setting.name(),
+            value: setting.default_value(guild),
+            source: "config",
+        })
+        .collect()
+}
+
+#[derive(Clone)]
+pub struct SettingsStore {
+    pool: SqlitePool,
+}
+
+impl SettingsStore {
+    /// Open the shared store at `~/.localgpt/discord/settings.sqlite`
+    pub fn open_default() -> Result<Self> {
+        Self::open(
+            &crate::agent::get_state_dir()?
+                .join("discord")
+                .join("settings.sqlite"),
+        )
+    }
+
+    pub fn open(db_path: &Path) -> Result<Self> {
+        Self::init(SqlitePool::shared(db_path, None)?)
+    }
+
+    /// In-memory store (tests)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(SqlitePool::open_in_memory(None)?)
+    }
+
+    fn init(pool: SqlitePool) -> Result<Self> {
+        pool.get()?.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS settings (
+                guild_id TEXT NOT NULL,
+                channel_id TEXT NOT NULL,  -- '' = the whole guild
+                name TEXT NOT NULL,
+                value TEXT NOT NULL,
+                updated_by TEXT NOT NULL,
+                updated_at INTEGER NOT NULL,
+                PRIMARY KEY (guild_id, channel_id, name)
+            );
+            "#,
+        )?;
+
+        Ok(Self { pool })
+    }
+
+    /// Set a guild's (`channel_id` None) or a channel's value; `value` None
+    /// removes it, so the guild's or configured value applies again
+    pub fn set(
+        &self,
+        guild_id: &str,
+        channel_id: Option<&str>,
+        setting: Setting,
+        value: Option<&str>,
+        updated_by: &str,
+    ) -> Result<()> {
+        let conn = self.pool.get()?;
+        let channel = channel_id.unwrap_or("");
+        match value {
+            Some(value) => {
+                let value = setting.normalize(value)?;
+                conn.execute(
+                    "INSERT INTO settings (guild_id, channel_id, name, value, updated_by, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                     ON CONFLICT(guild_id, channel_id, name)
+                     DO UPDATE SET value = ?4, updated_by = ?5, updated_at = ?6",
+                    params![
+                        guild_id,
+                        channel,
+                        setting.name(),
+                        value,
+                        updated_by,
+                        chrono::Utc::now().timestamp()
+                    ],
+                )?;
+            }
+            None => {
+                conn.execute(
+                    "DELETE FROM settings WHERE guild_id = ?1 AND channel_id = ?2 AND name = ?3",
+                    params![guild_id, channel, setting.name()],
+                )?;
+            }
+        }
+        info!(
+            "Setting {} of guild {}{} set to {:?} by {}",
+            setting.name(),
+            guild_id,
+            channel_id
+                .map(|c| format!(", channel {}", c))
+                .unwrap_or_default(),
+            value,
+            updated_by
+        );
+        Ok(())
+    }
+
+    /// Every setting's effective value in a guild, or in one of its
+    /// channels
+    pub fn resolve(
+        &self,
+        guild: Option<&DiscordGuildConfig>,
+        channel_id: Option<&str>,
+    ) -> Result<Vec<SettingValue>> {
+        let mut values = resolve_defaults(guild);
+        let Some(guild) = guild else {
+            return Ok(values);
+        };
+
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT channel_id, name, value FROM settings
+             WHERE guild_id = ?1 AND (channel_id = '' OR channel_id = ?2)
+             ORDER BY channel_id",
+        )?;
+        let rows = stmt.query_map(params![guild.guild_id, channel_id.unwrap_or("")], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+        // Guild rows sort before channel rows, so channel values win
+        for row in rows {
+            let (channel, name, value) = row?;
+            if let Some(entry) = values.iter_mut().find(|v| v.name == name) {
+                entry.value = Some(value);
+                entry.source = if channel.is_empty() {
+                    "guild"
+                } else {
+                    "channel"
+                };
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// `/settings` listing
+pub(super) fn format_settings(values: &[SettingValue]) -> String {
+    let lines: Vec<String> = values
+        .iter()
+        .map(|v| {
+            let value = match v.value {
+                Some(ref value) if value.contains('\n') || value.chars().count() > 60 => {
+                    let preview: String = value.chars().take(60).collect();
+                    format!("`{}…`", preview.replace('\n', " "))
+                }
+                Some(ref value) => format!("`{}`", value),
+                None => "not set".to_string(),
+            };
+            format!("**{}**: {} ({})", v.name, value, v.source)
+        })
+        .collect();
+    format!(
+        "Settings for this channel:\n{}\n\nChange with `/settings [channel] <name> <value|reset>`.",
+        lines.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guild() -> DiscordGuildConfig {
+        DiscordGuildConfig {
+            guild_id: "g1".to_string(),
+            channels: Vec::new(),
+            require_mention: true,
+            ambient: false,
+            language: None,
+            persona: None,
+            command_prefix: None,
+            emoji_reactions: true,
+            moderation: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_overrides() {
+        let store = SettingsStore::open_in_memory().unwrap();
+        let guild = guild();
+
+        let settings = Settings::from_values(&store.resolve(Some(&guild), Some("c1")).unwrap());
+        assert!(settings.require_mention);
+        assert_eq!(settings.language, None);
+
+        store
+            .set("g1", None, Setting::RequireMention, Some("off"), "u1")
+            .unwrap();
+        store
+            .set("g1", Some("c1"), Setting::RequireMention, Some("on"), "u1")
+            .unwrap();
+        store
+            .set("g1", None, Setting::Language, Some("Japanese"), "u1")
+            .unwrap();
+        assert!(
+            store
+                .set("g1", None, Setting::Ambient, Some("maybe"), "u1")
+                .is_err()
+        );
+
+        let values = store.resolve(Some(&guild), Some("c1")).unwrap();
+        assert_eq!(
+            values[0],
+            SettingValue {
+                name: "require_mention",
+                value: Some("true".to_string()),
+                source: "channel",
+            }
+        );
+        assert_eq!(values[2].value.as_deref(), Some("ja"));
+        assert_eq!(values[2].source, "guild");
+
+        // Other channels get the guild's value
+        let other = Settings::from_values(&store.resolve(Some(&guild), Some("c2")).unwrap());
+        assert!(!other.require_mention);
+        assert_eq!(other.language, Some(Language::Japanese));
+
+        // Reset goes back to the config
+        store
+            .set("g1", None, Setting::RequireMention, None, "u1")
+            .unwrap();
+        let other = Settings::from_values(&store.resolve(Some(&guild), Some("c2")).unwrap());
+        assert!(other.require_mention);
+        assert_eq!(
+            Setting::parse("Require-Mention"),
+            Some(Setting::RequireMention)
+        );
+    }
+}
@@ -0,0 +1,395 @@
+//! Shadow mode
+//!
+//! While shadow mode is on the bot reads and answers messages as usual, but
+//! every outbound effect (messages, reactions, uploads, moderation actions,
+//! command tags) is appended to `discord/shadow.jsonl` in the state directory
+//! instead of being executed. The switch is a marker file, so the HTTP server,
+//! the desktop app and the bot all see the same state without a restart.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::rest::{
+    DiscordApplication, DiscordChannelInfo, DiscordEmoji, DiscordMessageEntry, DiscordRest,
+    RestResult,
+};
+
+const MARKER_FILE: &str = "shadow_mode";
+const LOG_FILE: &str = "shadow.jsonl";
+
+/// An effect captured instead of executed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShadowEntry {
+    pub timestamp: String,
+    /// "send_message", "edit_message", "add_reaction", "send_file",
+    /// "create_forum_post", "crosspost_message", "delete_message",
+    /// "timeout_member", "respond_to_interaction" or "command"
+    pub action: String,
+    /// Channel, message or member the action targets
+    pub target: String,
+    pub content: String,
+}
+
+fn discord_state_dir() -> Option<PathBuf> {
+    crate::agent::get_state_dir()
+        .ok()
+        .map(|dir| dir.join("discord"))
+}
+
+/// Whether shadow mode is currently on
+pub fn is_enabled() -> bool {
+    discord_state_dir().is_some_and(|dir| dir.join(MARKER_FILE).exists())
+}
+
+/// Turn shadow mode on or off (takes effect immediately for a running bot)
+pub fn set_enabled(enabled: bool) -> Result<()> {
+    let dir = discord_state_dir().ok_or_else(|| anyhow::anyhow!("No state directory"))?;
+    set_enabled_in(&dir, enabled)?;
+    info!("Discord shadow mode {}", if enabled { "on" } else { "off" });
+    Ok(())
+}
+
+fn set_enabled_in(dir: &Path, enabled: bool) -> Result<()> {
+    let marker = dir.join(MARKER_FILE);
+    if enabled {
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(&marker, chrono::Utc::now().to_rfc3339())?;
+    } else if marker.exists() {
+        std::fs::remove_file(&marker)?;
+    }
+    Ok(())
+}
+
+/// Append a captured effect to the review log
+pub fn record(action: &str, target: &str, content: &str) {
+    if let Some(dir) = discord_state_dir() {
+        record_in(&dir, action, target, content);
+    }
+}
+
+fn record_in(dir: &Path, action: &str, target: &str, content: &str) {
+    info!("[shadow] {} → {}", action, target);
+    let entry = ShadowEntry {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        action: action.to_string(),
+        target: target.to_string(),
+        content: content.to_string(),
+    };
+    let result = std::fs::create_dir_all(dir)
+        .and_then(|_| {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(dir.join(LOG_FILE))
+        })
+        .and_then(|mut f| writeln!(f, "{}", serde_json::to_string(&entry).unwrap_or_default()));
+    if let Err(e) = result {
+        warn!("Failed to write shadow log: {}", e);
+    }
+}
+
+/// The most recent captured effects, oldest first
+pub fn load_entries(limit: usize) -> Vec<ShadowEntry> {
+    discord_state_dir()
+        .map(|dir| load_entries_in(&dir, limit))
+        .unwrap_or_default()
+}
+
+fn load_entries_in(dir: &Path, limit: usize) -> Vec<ShadowEntry> {
+    let Ok(file) = std::fs::File::open(dir.join(LOG_FILE)) else {
+        return Vec::new();
+    };
+    let entries: Vec<ShadowEntry> = BufReader::new(file)
+        .lines()
+        .map_while(|line| line.ok())
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect();
+    let skip = entries.len().saturating_sub(limit);
+    entries.into_iter().skip(skip).collect()
+}
+
+/// Passes reads through and captures writes while shadow mode is on
+pub(super) struct ShadowRest {
+    inner: Arc<dyn DiscordRest>,
+    /// Directory holding the marker and log (None = shadow mode unavailable)
+    dir: Option<PathBuf>,
+}
+
+impl ShadowRest {
+    pub fn new(inner: Arc<dyn DiscordRest>) -> Self {
+        Self {
+            inner,
+            dir: discord_state_dir(),
+        }
+    }
+
+    /// Returns true (after logging) if the effect must not be executed
+    fn capture(&self, action: &str, target: &str, content: &str) -> bool {
+        match self.dir {
+            Some(ref dir) if dir.join(MARKER_FILE).exists() => {
+                record_in(dir, action, target, content);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn active(&self) -> bool {
+        self.dir
+            .as_ref()
+            .is_some_and(|dir| dir.join(MARKER_FILE).exists())
+    }
+}
+
+#[async_trait]
+impl DiscordRest for ShadowRest {
+    async fn send_message(
+        &self,
+        channel_id: &str,
+        content: &str,
+        embeds: Option<Vec<serde_json::Value>>,
+    ) -> RestResult<Vec<String>> {
+        let logged = match embeds {
+            Some(ref embeds) if !embeds.is_empty() => {
+                format!("{}\n{}", content, serde_json::Value::from(embeds.clone()))
+            }
+            _ => content.to_string(),
+        };
+        if self.capture("send_message", channel_id, &logged) {
+            return Ok(Vec::new());
+        }
+        self.inner.send_message(channel_id, content, embeds).await
+    }
+
+    async fn post_message(&self, channel_id: &str, content: &str) -> RestResult<String> {
+        if self.capture("send_message", channel_id, content) {
+            // Later edits of this message are captured too
+            return Ok(format!("shadow-{}", uuid::Uuid::new_v4().simple()));
+        }
+        self.inner.post_message(channel_id, content).await
+    }
+
+    async fn send_components(
+        &self,
+        channel_id: &str,
+        content: &str,
+        components: serde_json::Value,
+    ) -> RestResult<String> {
+        let logged = format!("{}\n{}", content, components);
+        if self.capture("send_message", channel_id, &logged) {
+            return Ok(format!("shadow-{}", uuid::Uuid::new_v4().simple()));
+        }
+        self.inner
+            .send_components(channel_id, content, components)
+            .await
+    }
+
+    async fn respond_to_interaction(
+        &self,
+        interaction_id: &str,
+        token: &str,
+        response: serde_json::Value,
+    ) -> RestResult<()> {
+        if self.capture(
+            "respond_to_interaction",
+            interaction_id,
+            &response.to_string(),
+        ) {
+            return Ok(());
+        }
+        self.inner
+            .respond_to_interaction(interaction_id, token, response)
+            .await
+    }
+
+    async fn edit_message(
+        &self,
+        channel_id: &str,
+        message_id: &str,
+        content: &str,
+    ) -> RestResult<()> {
+        let target = format!("{}/{}", channel_id, message_id);
+        if self.capture("edit_message", &target, content) {
+            return Ok(());
+        }
+        self.inner
+            .edit_message(channel_id, message_id, content)
+            .await
+    }
+
+    async fn add_reaction(
+        &self,
+        channel_id: &str,
+        message_id: &str,
+        emoji: &str,
+    ) -> RestResult<()> {
+        if self.capture(
+            "add_reaction",
+            &format!("{}/{}", channel_id, message_id),
+            emoji,
+        ) {
+            return Ok(());
+        }
+        self.inner.add_reaction(channel_id, message_id, emoji).await
+    }
+
+    async fn send_typing(&self, channel_id: &str) -> RestResult<()> {
+        // Not worth reviewing, just don't show it
+        if self.active() {
+            return Ok(());
+        }
+        self.inner.send_typing(channel_id).await
+    }
+
+    async fn list_channels(&self, guild_id: &str) -> RestResult<Vec<DiscordChannelInfo>> {
+        self.inner.list_channels(guild_id).await
+    }
+
+    async fn list_guild_emojis(&self, guild_id: &str) -> RestResult<Vec<DiscordEmoji>> {
+        self.inner.list_guild_emojis(guild_id).await
+    }
+
+    async fn read_messages(
+        &self,
+        channel_id: &str,
+        limit: u32,
+    ) -> RestResult<Vec<DiscordMessageEntry>> {
+        self.inner.read_messages(channel_id, limit).await
+    }
+
+    async fn get_channel_guild(&self, channel_id: &str) -> RestResult<String> {
+        self.inner.get_channel_guild(channel_id).await
+    }
+
+    async fn get_channel(&self, channel_id: &str) -> RestResult<DiscordChannelInfo> {
+        self.inner.get_channel(channel_id).await
+    }
+
+    async fn get_application(&self) -> RestResult<DiscordApplication> {
+        self.inner.get_application().await
+    }
+
+    /// Opening a DM channel is invisible to the user; only messages to it
+    /// are captured
+    async fn create_dm(&self, user_id: &str) -> RestResult<String> {
+        self.inner.create_dm(user_id).await
+    }
+
+    async fn create_forum_post(
+        &self,
+        channel_id: &str,
+        title: &str,
+        content: &str,
+    ) -> RestResult<String> {
+        let logged = format!("{}\n{}", title, content);
+        if self.capture("create_forum_post", channel_id, &logged) {
+            return Ok(format!("shadow-{}", uuid::Uuid::new_v4().simple()));
+        }
+        self.inner
+            .create_forum_post(channel_id, title, content)
+            .await
+    }
+
+    async fn crosspost_message(&self, channel_id: &str, message_id: &str) -> RestResult<()> {
+        if self.capture(
+            "crosspost_message",
+            &format!("{}/{}", channel_id, message_id),
+            "",
+        ) {
+            return Ok(());
+        }
+        self.inner.crosspost_message(channel_id, message_id).await
+    }
+
+    async fn send_file(
+        &self,
+        channel_id: &str,
+        filename: &str,
+        data: Vec<u8>,
+        content: &str,
+    ) -> RestResult<()> {
+        let logged = format!("{} ({} bytes) {}", filename, data.len(), content);
+        if self.capture("send_file", channel_id, logged.trim_end()) {
+            return Ok(());
+        }
+        self.inner
+            .send_file(channel_id, filename, data, content)
+            .await
+    }
+
+    async fn delete_message(&self, channel_id: &str, message_id: &str) -> RestResult<()> {
+        if self.capture(
+            "delete_message",
+            &format!("{}/{}", channel_id, message_id),
+            "",
+        ) {
+            return Ok(());
+        }
+        self.inner.delete_message(channel_id, message_id).await
+    }
+
+    async fn timeout_member(
+        &self,
+        guild_id: &str,
+        user_id: &str,
+        duration: Duration,
+    ) -> RestResult<()> {
+        let target = format!("{}/{}", guild_id, user_id);
+        let logged = format!("{} seconds", duration.as_secs());
+        if self.capture("timeout_member", &target, &logged) {
+            return Ok(());
+        }
+        self.inner.timeout_member(guild_id, user_id, duration).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discord::rest::MockDiscordRest;
+
+    #[tokio::test]
+    async fn test_writes_captured_while_enabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut inner = MockDiscordRest::new();
+        inner
+            .expect_send_message()
+            .times(1)
+            .returning(|_, _, _| Ok(vec!["m0".to_string()]));
+        inner
+            .expect_get_channel_guild()
+            .times(1)
+            .returning(|_| Ok("g1".to_string()));
+
+        let rest = ShadowRest {
+            inner: Arc::new(inner),
+            dir: Some(dir.path().to_path_buf()),
+        };
+
+        // Off: passes through
+        rest.send_message("c1", "live", None).await.unwrap();
+
+        // On: captured, reads still pass through
+        set_enabled_in(dir.path(), true).unwrap();
+        rest.send_message("c1", "shadowed", None).await.unwrap();
+        rest.delete_message("c1", "m1").await.unwrap();
+        assert_eq!(rest.get_channel_guild("c1").await.unwrap(), "g1");
+
+        let entries = load_entries_in(dir.path(), 10);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, "send_message");
+        assert_eq!(entries[0].content, "shadowed");
+        assert_eq!(entries[1].target, "c1/m1");
+
+        set_enabled_in(dir.path(), false).unwrap();
+        assert!(!rest.active());
+        assert_eq!(load_entries_in(dir.path(), 1).len(), 1);
+    }
+}
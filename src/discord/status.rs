@@ -0,0 +1,175 @@
+//! `/status` diagnostics
+//!
+//! A snapshot of the bot's health for admins: uptime, the channel's model,
+//! the memory index, how many messages wait in the queue, the last
+//! heartbeat run and the state of each provider circuit. It is sent as an
+//! embed, yellow when anything needs attention.
+
+use tokio::time::Duration;
+
+use super::embeds::{EmbedColor, EmbedField, EmbedSpec};
+use crate::agent::{CircuitState, CircuitStatus};
+use crate::heartbeat::{HeartbeatEvent, HeartbeatStatus};
+use crate::jobs::format_elapsed;
+
+const HEALTHY_COLOR: u32 = 0x2E_CC71;
+const DEGRADED_COLOR: u32 = 0xF1_C40F;
+
+pub(super) struct BotStatus {
+    pub uptime: Duration,
+    /// The channel's model
+    pub model: String,
+    /// Indexed memory chunks (None before any agent has started)
+    pub memory_chunks: Option<usize>,
+    /// Channels with an active agent session
+    pub sessions: usize,
+    /// Messages waiting behind the current batch
+    pub queued: usize,
+    pub heartbeat_enabled: bool,
+    pub heartbeat: Option<HeartbeatEvent>,
+    pub providers: Vec<CircuitStatus>,
+}
+
+impl BotStatus {
+    /// Render as an embed; `now_ms` dates the last heartbeat
+    pub fn to_embed(&self, now_ms: u64) -> EmbedSpec {
+        let heartbeat = match (&self.heartbeat, self.heartbeat_enabled) {
+            (_, false) => "disabled".to_string(),
+            (None, true) => "no run yet".to_string(),
+            (Some(event), true) => {
+                let ago = now_ms.saturating_sub(event.ts) / 1000;
+                let mut text = format!(
+                    "{} {} ago",
+                    heartbeat_status_str(&event.status),
+                    format_elapsed(ago as i64)
+                );
+                if let Some(ref reason) = event.reason {
+                    text.push_str(&format!(" ({})", reason));
+                }
+                text
+            }
+        };
+
+        let providers = if self.providers.is_empty() {
+            "no calls yet".to_string()
+        } else {
+            self.providers
+                .iter()
+                .map(|p| match p.state {
+                    CircuitState::Closed => format!("🟢 {}", p.model),
+                    CircuitState::HalfOpen => format!("🟡 {} (probing)", p.model),
+                    CircuitState::Open => format!(
+                        "🔴 {} ({} failures{})",
+                        p.model,
+                        p.consecutive_failures,
+                        p.retry_in_secs
+                            .map(|s| format!(", retry in {}s", s))
+                            .unwrap_or_default()
+                    ),
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        let degraded = self
+            .providers
+            .iter()
+            .any(|p| p.state != CircuitState::Closed)
+            || self
+                .heartbeat
+                .as_ref()
+                .is_some_and(|e| e.status == HeartbeatStatus::Failed);
+
+        let field = |name: &str, value: String, inline: bool| EmbedField {
+            name: name.to_string(),
+            value,
+            inline,
+        };
+        EmbedSpec {
+            title: Some("LocalGPT status".to_string()),
+            color: Some(EmbedColor::Int(if degraded {
+                DEGRADED_COLOR
+            } else {
+                HEALTHY_COLOR
+            })),
+            fields: vec![
+                field("Uptime", format_elapsed(self.uptime.as_secs() as i64), true),
+                field("Model", self.model.clone(), true),
+                field(
+                    "Memory index",
+                    self.memory_chunks
+                        .map(|n| format!("{} chunks", n))
+                        .unwrap_or_else(|| "not loaded".to_string()),
+                    true,
+                ),
+                field("Sessions", self.sessions.to_string(), true),
+                field("Queue", format!("{} waiting", self.queued), true),
+                field("Heartbeat", heartbeat, false),
+                field("Providers", providers, false),
+            ],
+            footer: Some(format!("v{}", env!("CARGO_PKG_VERSION"))),
+            ..Default::default()
+        }
+    }
+}
+
+fn heartbeat_status_str(status: &HeartbeatStatus) -> &'static str {
+    match status {
+        HeartbeatStatus::Sent => "sent",
+        HeartbeatStatus::Ok => "ok",
+        HeartbeatStatus::Skipped => "skipped",
+        HeartbeatStatus::Failed => "failed",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_embed() {
+        let mut status = BotStatus {
+            uptime: Duration::from_secs(3 * 3600 + 120),
+            model: "claude-cli/opus".to_string(),
+            memory_chunks: Some(1234),
+            sessions: 2,
+            queued: 0,
+            heartbeat_enabled: true,
+            heartbeat: Some(HeartbeatEvent {
+                ts: 1_000_000,
+                status: HeartbeatStatus::Ok,
+                duration_ms: 900,
+                preview: None,
+                reason: None,
+                actions: Vec::new(),
+                response: None,
+            }),
+            providers: vec![CircuitStatus {
+                model: "claude-cli/opus".to_string(),
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                last_error: None,
+                retry_in_secs: None,
+            }],
+        };
+        let embed = status.to_embed(1_000_000 + 300_000);
+        assert_eq!(embed.color, Some(EmbedColor::Int(HEALTHY_COLOR)));
+        let value = |name: &str| {
+            embed
+                .fields
+                .iter()
+                .find(|f| f.name == name)
+                .unwrap()
+                .value
+                .clone()
+        };
+        assert_eq!(value("Memory index"), "1234 chunks");
+        assert!(value("Heartbeat").starts_with("ok "));
+        assert_eq!(value("Providers"), "🟢 claude-cli/opus");
+
+        status.providers[0].state = CircuitState::Open;
+        status.providers[0].consecutive_failures = 5;
+        let embed = status.to_embed(1_000_000);
+        assert_eq!(embed.color, Some(EmbedColor::Int(DEGRADED_COLOR)));
+    }
+}
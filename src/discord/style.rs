@@ -0,0 +1,124 @@
+//! Per-channel reply style
+//!
+//! `[channels.discord.styles."<channel_id>"]` constrains replies in a
+//! channel. The constraints are given to the agent as instructions each turn
+//! and enforced again on the final text, since models don't always comply.
+
+use regex::Regex;
+
+use crate::config::{Config, DiscordChannelStyle};
+
+/// The style configured for a channel, if any
+pub(super) fn style_for<'a>(
+    config: &'a Config,
+    channel_id: &str,
+) -> Option<&'a DiscordChannelStyle> {
+    config.channels.discord.as_ref()?.styles.get(channel_id)
+}
+
+/// Instructions for the agent describing the channel's constraints
+pub(super) fn instructions(style: &DiscordChannelStyle) -> Option<String> {
+    let mut lines = Vec::new();
+    match style.verbosity.as_deref() {
+        Some("brief") => lines.push("Keep replies short: one or two sentences.".to_string()),
+        Some("detailed") => lines.push("Detailed, thorough replies are welcome.".to_string()),
+        _ => {}
+    }
+    if style.max_chars > 0 {
+        lines.push(format!(
+            "Replies must be under {} characters.",
+            style.max_chars
+        ));
+    }
+    if style.no_code_blocks {
+        lines.push("Do not use code blocks or other markdown formatting.".to_string());
+    }
+    lines.extend(style.rules.iter().cloned());
+
+    if lines.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "[Channel reply style]\n{}",
+        lines
+            .iter()
+            .map(|l| format!("- {}", l))
+            .collect::<Vec<_>>()
+            .join("\n")
+    ))
+}
+
+/// Replace fenced code blocks with their contents and drop inline backticks
+pub(super) fn strip_code_blocks(text: &str) -> String {
+    let fence_re = Regex::new(r"(?s)```[^\n`]*\n?(.*?)\n?```").unwrap();
+    let text = fence_re.replace_all(text, "$1");
+    text.replace('`', "").trim().to_string()
+}
+
+/// Whether a reply is over the channel's length limit
+pub(super) fn too_long(style: &DiscordChannelStyle, text: &str) -> bool {
+    style.max_chars > 0 && text.chars().count() > style.max_chars
+}
+
+/// Cut a reply to `max_chars`, preferring a sentence or word boundary
+pub(super) fn truncate(text: &str, max_chars: usize) -> String {
+    if max_chars == 0 || text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    // Leave room for the ellipsis
+    let cut: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    let sentence_end = cut
+        .rfind(['.', '!', '?', '。', '\n'])
+        .map(|i| i + cut[i..].chars().next().map_or(1, char::len_utf8));
+    match sentence_end {
+        // Only use the sentence boundary if it keeps most of the text
+        Some(end) if end * 2 >= cut.len() => cut[..end].trim_end().to_string(),
+        _ => match cut.rfind(char::is_whitespace) {
+            Some(space) if space * 2 >= cut.len() => format!("{}…", cut[..space].trim_end()),
+            _ => format!("{}…", cut),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instructions() {
+        assert_eq!(instructions(&DiscordChannelStyle::default()), None);
+
+        let style = DiscordChannelStyle {
+            max_chars: 300,
+            verbosity: Some("brief".to_string()),
+            no_code_blocks: true,
+            rules: vec!["Answer in Japanese.".to_string()],
+            ..Default::default()
+        };
+        let text = instructions(&style).unwrap();
+        assert!(text.contains("under 300 characters"));
+        assert!(text.contains("one or two sentences"));
+        assert!(text.contains("code blocks"));
+        assert!(text.ends_with("- Answer in Japanese."));
+    }
+
+    #[test]
+    fn test_strip_code_blocks() {
+        assert_eq!(
+            strip_code_blocks("Run this:\n```bash\nls -la\n```\nthen `cd` back."),
+            "Run this:\nls -la\nthen cd back."
+        );
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(
+            truncate("First sentence. Second sentence is long.", 30),
+            "First sentence."
+        );
+        assert_eq!(truncate("one two three four five", 15), "one two three…");
+        assert_eq!(truncate("あいうえおかきくけこ", 5), "あいうえ…");
+    }
+}
@@ -0,0 +1,807 @@
+//! Response tag handling
+//!
+//! The agent talks to Discord through inline tags: `[LIST:guild]` and
+//! `[READ:channel:count]` feed data back into the conversation,
+//! `[POST:channel]`, `[PUBLISH:channel]`, `[REACT:emoji]`,
+//! `[REMIND:user:when]` and configured command tags act on the final reply.
+//! A post to a forum channel starts a new forum post; `[PUBLISH]` to an
+//! announcement channel also publishes the message to following servers.
+//!
+//! Every tag has the same grammar, `[NAME]` or `[NAME:args]`, optionally
+//! followed by a body that runs to the next tag (the message of `[POST]`
+//! and `[REMIND]`). A [`TagRegistry`] knows which names are tags, whether
+//! they take a body and the handler that turns a match into an action on
+//! the [`ParsedReply`]. The built-in tags and the command tags in
+//! `[tags.*]` are registered from the config; other tags can be added with
+//! [`TagRegistry::register`]. Registered tags are always removed from the
+//! text sent to Discord, bracketed text that isn't a tag is left alone.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, LazyLock};
+use tracing::{error, info, warn};
+
+use super::components::{self, ChoiceSpec};
+use super::embeds::{self, EmbedSpec};
+use super::reminders::ReminderRequest;
+use super::rest::{
+    CHANNEL_ANNOUNCEMENT, CHANNEL_FORUM, DiscordRest, RestResult, format_channel_list,
+    format_message_history,
+};
+use super::shadow;
+use crate::config::{Config, TagGroup};
+
+/// `[NAME]` or `[NAME:args]`
+static TAG_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[([A-Z][A-Z0-9_-]*)(?::([^\]]*))?\]").unwrap());
+
+static LIST_ARGS_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\d+$").unwrap());
+static READ_ARGS_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(\d+)(?::(\d+))?$").unwrap());
+static USER_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(me|<@!?\d+>|\d+)$").unwrap());
+
+/// A reply with all action tags removed
+#[derive(Debug, Default)]
+pub struct ParsedReply {
+    /// Text left to send (may be empty)
+    pub text: String,
+    pub embeds: Vec<EmbedSpec>,
+    /// `[CHOICES]` to offer as buttons or a select menu
+    pub choices: Option<ChoiceSpec>,
+    /// `[POST:channel_id]` and `[PUBLISH:channel_id]` messages
+    pub cross_posts: Vec<CrossPost>,
+    /// `[REACT:emoji]` reactions for the triggering message
+    pub reactions: Vec<String>,
+    /// `[REMIND:user:when]` reminders to schedule
+    pub reminders: Vec<ReminderRequest>,
+    /// Configured command tags, run if the requester may
+    pub commands: Vec<TagMatch>,
+}
+
+/// A message for another channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrossPost {
+    pub channel_id: String,
+    pub message: String,
+    /// Crosspost to following servers (announcement channels only)
+    pub publish: bool,
+}
+
+/// A registered tag found in a reply
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagMatch {
+    /// Tag name, uppercase
+    pub name: String,
+    /// Everything after the first colon (empty for `[NAME]`)
+    pub args: String,
+    /// Text after the tag up to the next one, for tags that take a body
+    pub body: String,
+}
+
+/// Turns a tag into an action on the reply
+pub type TagHandler = Arc<dyn Fn(&TagMatch, &mut ParsedReply) + Send + Sync>;
+
+#[derive(Clone)]
+struct TagSpec {
+    takes_body: bool,
+    handler: TagHandler,
+}
+
+/// The tags the agent can use in replies
+#[derive(Clone)]
+pub struct TagRegistry {
+    specs: HashMap<String, TagSpec>,
+}
+
+impl TagRegistry {
+    /// The built-in tags and the command tags in `config.tags`
+    pub fn new(config: &Config) -> Self {
+        let mut registry = Self {
+            specs: HashMap::new(),
+        };
+        for name in ["POST", "PUBLISH"] {
+            registry.register(name, true, Arc::new(cross_post_tag));
+        }
+        registry.register("REMIND", true, Arc::new(remind_tag));
+        registry.register(
+            "REACT",
+            false,
+            Arc::new(|tag, reply| {
+                if !tag.args.is_empty() {
+                    reply.reactions.push(tag.args.clone());
+                }
+            }),
+        );
+        // Executed before the final reply (execute_tool_tags); only stripped here
+        for name in ["LIST", "READ"] {
+            registry.register(name, false, Arc::new(|_, _| {}));
+        }
+        for name in config.tags.keys() {
+            registry.register(
+                name,
+                false,
+                Arc::new(|tag, reply| reply.commands.push(tag.clone())),
+            );
+        }
+        registry
+    }
+
+    /// Add a tag, replacing any tag of the same name
+    pub fn register(&mut self, name: &str, takes_body: bool, handler: TagHandler) {
+        self.specs.insert(
+            name.to_uppercase(),
+            TagSpec {
+                takes_body,
+                handler,
+            },
+        );
+    }
+
+    /// The registered tags in `text`, and the text without them
+    pub fn scan(&self, text: &str) -> (Vec<TagMatch>, String) {
+        let found: Vec<(Range<usize>, TagMatch)> = find_tags(text)
+            .into_iter()
+            .filter(|(_, tag)| self.specs.contains_key(&tag.name))
+            .collect();
+
+        let mut tags = Vec::with_capacity(found.len());
+        let mut stripped = String::with_capacity(text.len());
+        let mut pos = 0;
+        for (i, (range, mut tag)) in found.iter().cloned().enumerate() {
+            stripped.push_str(&text[pos..range.start]);
+            pos = range.end;
+            if self.specs[&tag.name].takes_body {
+                let end = found.get(i + 1).map_or(text.len(), |(next, _)| next.start);
+                tag.body = text[range.end..end].trim().to_string();
+                pos = end;
+            }
+            tags.push(tag);
+        }
+        stripped.push_str(&text[pos..]);
+        (tags, stripped)
+    }
+
+    fn handle(&self, tag: &TagMatch, reply: &mut ParsedReply) {
+        if let Some(spec) = self.specs.get(&tag.name) {
+            (spec.handler)(tag, reply);
+        }
+    }
+}
+
+/// Every `[NAME]`/`[NAME:args]` in `text`, registered or not
+fn find_tags(text: &str) -> Vec<(Range<usize>, TagMatch)> {
+    TAG_RE
+        .captures_iter(text)
+        .map(|c| {
+            let tag = TagMatch {
+                name: c[1].to_string(),
+                args: c.get(2).map_or("", |m| m.as_str()).trim().to_string(),
+                body: String::new(),
+            };
+            (c.get(0).unwrap().range(), tag)
+        })
+        .collect()
+}
+
+fn cross_post_tag(tag: &TagMatch, reply: &mut ParsedReply) {
+    if tag.args.is_empty() || !tag.args.chars().all(|c| c.is_ascii_digit()) {
+        warn!("Ignoring [{}:{}]: not a channel ID", tag.name, tag.args);
+    } else if !tag.body.is_empty() {
+        reply.cross_posts.push(CrossPost {
+            channel_id: tag.args.clone(),
+            message: tag.body.clone(),
+            publish: tag.name == "PUBLISH",
+        });
+    }
+}
+
+/// `[REMIND:user:when]`: the user is `me`, a mention or an ID, so the first
+/// colon ends it
+fn remind_tag(tag: &TagMatch, reply: &mut ParsedReply) {
+    match tag.args.split_once(':') {
+        Some((user, when)) if USER_RE.is_match(user.trim()) && !when.trim().is_empty() => {
+            if !tag.body.is_empty() {
+                reply.reminders.push(ReminderRequest {
+                    user: user.trim().to_string(),
+                    when: when.trim().to_string(),
+                    message: tag.body.clone(),
+                });
+            }
+        }
+        _ => warn!(
+            "Ignoring [REMIND:{}]: expected [REMIND:user:when]",
+            tag.args
+        ),
+    }
+}
+
+/// Strip action tags from the agent's final reply. Command tags are
+/// executed here (fire-and-forget, errors logged only) if `allow_commands`;
+/// everything else is returned for the caller to act on.
+pub(super) async fn process_reply_tags(
+    response: &str,
+    registry: &TagRegistry,
+    config: &Config,
+    allow_commands: bool,
+) -> ParsedReply {
+    // Extract [EMBED] and [CHOICES] blocks first: their JSON may contain
+    // brackets
+    let (response, embeds) = embeds::extract_embeds(response);
+    let (response, choices) = components::extract_choices(&response);
+
+    let (tags, text) = registry.scan(&response);
+    let mut reply = ParsedReply {
+        text: text.trim().to_string(),
+        embeds,
+        choices,
+        ..Default::default()
+    };
+    for tag in &tags {
+        registry.handle(tag, &mut reply);
+    }
+
+    if allow_commands {
+        execute_command_tags(&reply.commands, &config.tags).await;
+    } else if !reply.commands.is_empty() {
+        info!("Skipping command tags: requester lacks the commands permission");
+    }
+
+    reply
+}
+
+/// Cross-posts may only target channels in configured guilds
+pub(super) fn cross_post_allowed(config: &Config, target_channel: &str) -> bool {
+    config
+        .channels
+        .discord
+        .as_ref()
+        .map(|dc| {
+            dc.guilds
+                .iter()
+                .any(|g| g.channels.is_empty() || g.channels.iter().any(|c| c == target_channel))
+        })
+        .unwrap_or(false)
+}
+
+/// Send a cross-post according to the target's channel type: a forum gets a
+/// new post titled with the first line, an announcement channel publishes
+/// the message if asked to
+pub(super) async fn deliver_cross_post(rest: &dyn DiscordRest, post: &CrossPost) -> RestResult<()> {
+    let channel_type = rest.get_channel(&post.channel_id).await?.channel_type;
+
+    if channel_type == CHANNEL_FORUM {
+        let (title, body) = match post.message.split_once('\n') {
+            Some((title, body)) if !body.trim().is_empty() => (title.trim(), body.trim()),
+            _ => (post.message.as_str(), post.message.as_str()),
+        };
+        let title = title.trim_start_matches('#').trim();
+        rest.create_forum_post(&post.channel_id, title, body)
+            .await?;
+        return Ok(());
+    }
+
+    if !post.publish {
+        rest.send_message(&post.channel_id, &post.message, None)
+            .await?;
+        return Ok(());
+    }
+    let message_id = rest.post_message(&post.channel_id, &post.message).await?;
+    if channel_type == CHANNEL_ANNOUNCEMENT {
+        rest.crosspost_message(&post.channel_id, &message_id)
+            .await?;
+    } else {
+        warn!(
+            "Channel {} is not an announcement channel; message posted but not published",
+            post.channel_id
+        );
+    }
+    Ok(())
+}
+
+/// Execute command tags found in a response. Tag names come from config HashMap keys.
+async fn execute_command_tags(commands: &[TagMatch], tags: &HashMap<String, TagGroup>) {
+    for tag in commands {
+        let Some(group) = command_group(tags, &tag.name) else {
+            continue;
+        };
+
+        match match_command_template(&tag.args, &group.patterns, group.binary.as_deref()) {
+            Some(cmd) if shadow::is_enabled() => shadow::record("command", &tag.name, &cmd),
+            Some(cmd) => run_command(group.config_swap.as_deref(), &cmd).await,
+            None => warn!("Unknown {} command: {}", tag.name, tag.args),
+        }
+    }
+}
+
+/// Look up the config group for a tag. The registry uppercases tag names, so
+/// the config key is compared the same way rather than assumed lowercase.
+fn command_group<'a>(tags: &'a HashMap<String, TagGroup>, name: &str) -> Option<&'a TagGroup> {
+    tags.iter()
+        .find(|(key, _)| key.to_uppercase() == name)
+        .map(|(_, group)| group)
+}
+
+/// Match tag content against a group's configured patterns and return the expanded command.
+fn match_command_template(
+    tag_content: &str,
+    patterns: &HashMap<String, String>,
+    binary: Option<&str>,
+) -> Option<String> {
+    let tag_parts: Vec<&str> = tag_content.splitn(20, ':').collect();
+    for (pattern, template) in patterns {
+        let pattern_parts: Vec<&str> = pattern.splitn(20, ':').collect();
+        if let Some(bindings) = match_pattern(&pattern_parts, &tag_parts) {
+            let mut result = if let Some(bin) = binary {
+                template.replace("{binary}", bin)
+            } else {
+                template.clone()
+            };
+            for (key, value) in &bindings {
+                result = result.replace(&format!("{{{}}}", key), value);
+            }
+            return Some(result);
+        }
+    }
+    None
+}
+
+/// Try to match tag parts against a pattern. Returns bound placeholders on success.
+/// The last placeholder in a pattern greedily captures all remaining segments.
+fn match_pattern(pattern_parts: &[&str], tag_parts: &[&str]) -> Option<Vec<(String, String)>> {
+    if tag_parts.len() < pattern_parts.len() {
+        return None;
+    }
+    let mut bindings = Vec::new();
+    let mut tag_idx = 0;
+    for (i, pp) in pattern_parts.iter().enumerate() {
+        if tag_idx >= tag_parts.len() {
+            return None;
+        }
+        if pp.starts_with('{') && pp.ends_with('}') {
+            let key = &pp[1..pp.len() - 1];
+            if i == pattern_parts.len() - 1 {
+                // Last placeholder captures all remaining segments
+                let remaining = tag_parts[tag_idx..].join(":");
+                bindings.push((key.to_string(), remaining));
+            } else {
+                bindings.push((key.to_string(), tag_parts[tag_idx].to_string()));
+            }
+        } else if tag_parts[tag_idx] != *pp {
+            return None;
+        }
+        tag_idx += 1;
+    }
+    Some(bindings)
+}
+
+/// Run a command, optionally with config swap.
+/// If config_swap is Some(dir):
+///   1. Backup ~/.nostaro/config.toml if it exists
+///   2. Copy dir/config.toml → ~/.nostaro/config.toml
+///   3. Execute command via sh -c
+///   4. Restore original or remove copied file
+/// If config_swap is None, just execute the command directly.
+async fn run_command(config_swap: Option<&str>, command: &str) {
+    if let Some(config_dir) = config_swap {
+        let config_dir_expanded = shellexpand::tilde(config_dir).to_string();
+        let nostaro_dir = shellexpand::tilde("~/.nostaro").to_string();
+        let target_config = format!("{}/config.toml", nostaro_dir);
+        let source_config = format!("{}/config.toml", config_dir_expanded);
+
+        // Check if source config exists
+        if !tokio::fs::metadata(&source_config).await.is_ok() {
+            error!("Config swap source not found: {}", source_config);
+            return;
+        }
+
+        // Ensure ~/.nostaro directory exists
+        if let Err(e) = tokio::fs::create_dir_all(&nostaro_dir).await {
+            error!("Failed to create dir {}: {}", nostaro_dir, e);
+            return;
+        }
+
+        // Check if original config exists (for backup/restore)
+        let original_exists = tokio::fs::metadata(&target_config).await.is_ok();
+        let backup_path = format!("{}.localgpt-backup", target_config);
+
+        // Backup original if it exists
+        if original_exists {
+            if let Err(e) = tokio::fs::copy(&target_config, &backup_path).await {
+                error!("Failed to backup config: {}", e);
+                return;
+            }
+        }
+
+        // Copy source config to target
+        if let Err(e) = tokio::fs::copy(&source_config, &target_config).await {
+            error!("Failed to copy config: {}", e);
+            if original_exists {
+                let _ = tokio::fs::rename(&backup_path, &target_config).await;
+            }
+            return;
+        }
+
+        info!("Executing command (config swap): {}", command);
+
+        // Execute command via shell
+        let result = tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .output()
+            .await;
+
+        match result {
+            Ok(output) => {
+                if output.status.success() {
+                    let stdout = String::from_utf8_lossy(&output.stdout);
+                    info!("Command success: {}", stdout.trim());
+                } else {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    error!("Command failed (exit {}): {}", output.status, stderr.trim());
+                }
+            }
+            Err(e) => {
+                error!("Failed to execute command: {}", e);
+            }
+        }
+
+        // Restore original config or remove copied file
+        if original_exists {
+            if let Err(e) = tokio::fs::rename(&backup_path, &target_config).await {
+                error!("Failed to restore config backup: {}", e);
+            }
+        } else {
+            if let Err(e) = tokio::fs::remove_file(&target_config).await {
+                error!("Failed to remove swapped config: {}", e);
+            }
+        }
+    } else {
+        // No config swap — just execute directly
+        info!("Executing command: {}", command);
+
+        let result = tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .output()
+            .await;
+
+        match result {
+            Ok(output) => {
+                if output.status.success() {
+                    let stdout = String::from_utf8_lossy(&output.stdout);
+                    info!("Command success: {}", stdout.trim());
+                } else {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    error!("Command failed (exit {}): {}", output.status, stderr.trim());
+                }
+            }
+            Err(e) => {
+                error!("Failed to execute command: {}", e);
+            }
+        }
+    }
+}
+/// Execute [LIST:...] and [READ:...] tool tags found in a response.
+/// Returns a tool_output string to feed back to the agent, or empty if no tags found.
+pub(super) async fn execute_tool_tags(
+    response: &str,
+    config: &Config,
+    rest: &dyn DiscordRest,
+) -> String {
+    let mut outputs = Vec::new();
+
+    let tags = find_tags(response);
+
+    // [LIST:guild_id]
+    for (_, tag) in tags.iter().filter(|(_, t)| t.name == "LIST") {
+        if !LIST_ARGS_RE.is_match(&tag.args) {
+            continue;
+        }
+        let guild_id = tag.args.clone();
+        let allowed = config
+            .channels
+            .discord
+            .as_ref()
+            .map(|dc| dc.guilds.iter().any(|g| g.guild_id == guild_id))
+            .unwrap_or(false);
+
+        if allowed {
+            match rest.list_channels(&guild_id).await {
+                Ok(channels) => {
+                    let result = format_channel_list(&channels);
+                    info!("Listed channels for guild {}", guild_id);
+                    outputs.push(format!(
+                        "<tool_output>\n[LIST:{}] channels:\n{}\n</tool_output>",
+                        guild_id, result
+                    ));
+                }
+                Err(e) => {
+                    error!("Failed to list channels for guild {}: {}", guild_id, e);
+                    outputs.push(format!(
+                        "<tool_output>\n[LIST:{}] error: {}\n</tool_output>",
+                        guild_id, e
+                    ));
+                }
+            }
+        } else {
+            warn!("LIST denied for guild {}: not in allowed list", guild_id);
+            outputs.push(format!(
+                "<tool_output>\n[LIST:{}] error: guild not in allowed list\n</tool_output>",
+                guild_id
+            ));
+        }
+    }
+
+    // [READ:channel_id] and [READ:channel_id:count]
+    for (_, tag) in tags.iter().filter(|(_, t)| t.name == "READ") {
+        let Some(cap) = READ_ARGS_RE.captures(&tag.args) else {
+            continue;
+        };
+        let channel_id = cap[1].to_string();
+        let count: u32 = cap
+            .get(2)
+            .and_then(|m| m.as_str().parse().ok())
+            .unwrap_or(10)
+            .min(50);
+
+        // Security: verify channel belongs to an allowed guild
+        let allowed = match rest.get_channel_guild(&channel_id).await {
+            Ok(guild_id) => config
+                .channels
+                .discord
+                .as_ref()
+                .map(|dc| dc.guilds.iter().any(|g| g.guild_id == guild_id))
+                .unwrap_or(false),
+            Err(e) => {
+                warn!("Could not verify guild for channel {}: {}", channel_id, e);
+                false
+            }
+        };
+
+        if allowed {
+            match rest.read_messages(&channel_id, count).await {
+                Ok(messages) => {
+                    let result = format_message_history(&messages);
+                    info!("Read {} messages from channel {}", count, channel_id);
+                    outputs.push(format!(
+                        "<tool_output>\n[READ:{}] messages:\n{}\n</tool_output>",
+                        channel_id, result
+                    ));
+                }
+                Err(e) => {
+                    error!("Failed to read messages from channel {}: {}", channel_id, e);
+                    outputs.push(format!(
+                        "<tool_output>\n[READ:{}] error: {}\n</tool_output>",
+                        channel_id, e
+                    ));
+                }
+            }
+        } else {
+            warn!(
+                "READ denied for channel {}: not in allowed guild",
+                channel_id
+            );
+            outputs.push(format!(
+                "<tool_output>\n[READ:{}] error: channel not in allowed guild\n</tool_output>",
+                channel_id
+            ));
+        }
+    }
+
+    outputs.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DiscordChannelConfig, DiscordGuildConfig};
+    use crate::discord::rest::{DiscordChannelInfo, MockDiscordRest, RestError};
+
+    fn config_with_guild(guild_id: &str) -> Config {
+        let mut config = Config::default();
+        config.channels.discord = Some(DiscordChannelConfig {
+            enabled: true,
+            token: "test-token".to_string(),
+            allow_bots: false,
+            guilds: vec![DiscordGuildConfig {
+                guild_id: guild_id.to_string(),
+                channels: vec!["42".to_string()],
+                require_mention: false,
+                ambient: false,
+                language: None,
+                persona: None,
+                command_prefix: None,
+                emoji_reactions: true,
+                moderation: Default::default(),
+            }],
+            track_edits: false,
+            request_timeout_secs: 15,
+            max_retries: 0,
+            api_version: 10,
+            permissions: Default::default(),
+            styles: Default::default(),
+            classifier: Default::default(),
+            announcements: Default::default(),
+            rate_limit: Default::default(),
+            history: Default::default(),
+            pins: Default::default(),
+            sessions: Default::default(),
+            replay_max_age: "15m".to_string(),
+            reminders: Default::default(),
+            mirror: false,
+        });
+        config
+    }
+
+    #[tokio::test]
+    async fn test_read_denied_outside_allowed_guilds() {
+        let mut rest = MockDiscordRest::new();
+        rest.expect_get_channel_guild()
+            .returning(|_| Ok("999".to_string()));
+        rest.expect_read_messages().never();
+
+        let config = config_with_guild("111");
+        let output = execute_tool_tags("[READ:42:5]", &config, &rest).await;
+        assert!(output.contains("channel not in allowed guild"));
+    }
+
+    #[tokio::test]
+    async fn test_list_reports_rest_errors() {
+        let mut rest = MockDiscordRest::new();
+        rest.expect_list_channels()
+            .returning(|_| Err(RestError::Forbidden("Missing Access".to_string())));
+
+        let config = config_with_guild("111");
+        let output = execute_tool_tags("[LIST:111]", &config, &rest).await;
+        assert!(output.contains("[LIST:111] error: missing permissions"));
+    }
+
+    #[tokio::test]
+    async fn test_list_formats_channels() {
+        let mut rest = MockDiscordRest::new();
+        rest.expect_list_channels().returning(|_| {
+            Ok(vec![DiscordChannelInfo {
+                id: "7".to_string(),
+                channel_type: 0,
+                name: "general".to_string(),
+                topic: None,
+            }])
+        });
+
+        let config = config_with_guild("111");
+        let output = execute_tool_tags("[LIST:111]", &config, &rest).await;
+        assert!(output.contains("general (ID: 7) [text]"));
+    }
+
+    #[tokio::test]
+    async fn test_process_reply_tags() {
+        let config = config_with_guild("111");
+        let registry = TagRegistry::new(&config);
+        let reply = process_reply_tags(
+            "Done! [REACT:👍] [READ:42] [POST:42] Heads up, deploy finished",
+            &registry,
+            &config,
+            false,
+        )
+        .await;
+
+        assert_eq!(reply.text, "Done!");
+        assert_eq!(reply.reactions, vec!["👍"]);
+        assert_eq!(
+            reply.cross_posts,
+            vec![CrossPost {
+                channel_id: "42".to_string(),
+                message: "Heads up, deploy finished".to_string(),
+                publish: false,
+            }]
+        );
+        assert!(cross_post_allowed(&config, "42"));
+        assert!(!cross_post_allowed(&config, "43"));
+
+        let reply = process_reply_tags(
+            "Sure! [REMIND:<@8>:2026-10-17 09:00] Stand-up in 5 minutes",
+            &registry,
+            &config,
+            false,
+        )
+        .await;
+        assert_eq!(reply.text, "Sure!");
+        assert_eq!(
+            reply.reminders,
+            vec![ReminderRequest {
+                user: "<@8>".to_string(),
+                when: "2026-10-17 09:00".to_string(),
+                message: "Stand-up in 5 minutes".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_tag_registration() {
+        let config = config_with_guild("111");
+        let mut registry = TagRegistry::new(&config);
+        registry.register(
+            "pin",
+            true,
+            Arc::new(|tag, reply| {
+                reply
+                    .reactions
+                    .push(format!("pinned {}: {}", tag.args, tag.body))
+            }),
+        );
+
+        let reply = process_reply_tags(
+            "Noted [see [docs]] [PIN:42] Release [v1.2] notes [REACT:📌][TTS:en] hi",
+            &registry,
+            &config,
+            false,
+        )
+        .await;
+        // Unregistered tags and other brackets stay in the text
+        assert_eq!(reply.text, "Noted [see [docs]] [TTS:en] hi");
+        assert_eq!(
+            reply.reactions,
+            vec!["pinned 42: Release [v1.2] notes", "📌"]
+        );
+    }
+
+    #[test]
+    fn test_command_group_ignores_key_case() {
+        let group = TagGroup {
+            binary: Some("hass".to_string()),
+            config_swap: None,
+            patterns: HashMap::from([(
+                "on:{entity}".to_string(),
+                "{binary} on {entity}".to_string(),
+            )]),
+        };
+        let tags = HashMap::from([("Home".to_string(), group)]);
+
+        let found = command_group(&tags, "HOME").expect("Home should match HOME");
+        assert_eq!(
+            match_command_template("on:lamp", &found.patterns, found.binary.as_deref()),
+            Some("hass on lamp".to_string())
+        );
+        assert!(command_group(&tags, "HOMES").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cross_post_by_channel_type() {
+        let mut rest = MockDiscordRest::new();
+        rest.expect_get_channel().returning(|id| {
+            Ok(DiscordChannelInfo {
+                id: id.to_string(),
+                channel_type: if id == "15" { 15 } else { 5 },
+                name: String::new(),
+                topic: None,
+            })
+        });
+        rest.expect_create_forum_post()
+            .withf(|channel, title, content| {
+                channel == "15" && title == "Release 1.2" && content == "Changes: faster search"
+            })
+            .times(1)
+            .returning(|_, _, _| Ok("900".to_string()));
+        rest.expect_post_message()
+            .times(1)
+            .returning(|_, _| Ok("901".to_string()));
+        rest.expect_crosspost_message()
+            .withf(|channel, message| channel == "5" && message == "901")
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let forum = CrossPost {
+            channel_id: "15".to_string(),
+            message: "# Release 1.2\nChanges: faster search".to_string(),
+            publish: false,
+        };
+        deliver_cross_post(&rest, &forum).await.unwrap();
+
+        let announcement = CrossPost {
+            channel_id: "5".to_string(),
+            message: "Release 1.2 is out".to_string(),
+            publish: true,
+        };
+        deliver_cross_post(&rest, &announcement).await.unwrap();
+    }
+}
@@ -0,0 +1,244 @@
+//! Runtime feature toggles
+//!
+//! Switches that can be flipped while the daemon runs, from the desktop
+//! Status view or `PUT /api/features/<name>`. Toggles that already had a
+//! switch of their own (heartbeat pause, Discord shadow mode) are read and
+//! written through it, so older tools keep working; the others live in
+//! `features.sqlite` in the state directory. Every change is recorded there
+//! and announced to [`subscribe`]rs in the same process.
+
+use anyhow::{Result, anyhow};
+use rusqlite::{OptionalExtension, params};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::LazyLock;
+use tokio::sync::broadcast;
+use tracing::info;
+
+use crate::agent::{DEFAULT_AGENT_ID, get_state_dir};
+use crate::db::SqlitePool;
+
+static CHANGES: LazyLock<broadcast::Sender<FeatureChange>> =
+    LazyLock::new(|| broadcast::channel(16).0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// Heartbeat runner skips its ticks
+    HeartbeatPaused,
+    /// Discord writes are captured instead of executed
+    ShadowMode,
+    /// The Discord bot ignores new messages
+    DiscordPaused,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 3] = [
+        Feature::HeartbeatPaused,
+        Feature::ShadowMode,
+        Feature::DiscordPaused,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::HeartbeatPaused => "heartbeat.paused",
+            Feature::ShadowMode => "shadow_mode",
+            Feature::DiscordPaused => "discord.paused",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Feature::HeartbeatPaused => "Skip heartbeat runs",
+            Feature::ShadowMode => "Capture Discord actions for review instead of sending them",
+            Feature::DiscordPaused => "Ignore new Discord messages",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureState {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureChange {
+    pub name: String,
+    pub enabled: bool,
+    /// Who changed it ("http", "desktop", ...)
+    pub source: String,
+    pub changed_at: i64,
+}
+
+/// Changes made in this process from now on
+pub fn subscribe() -> broadcast::Receiver<FeatureChange> {
+    CHANGES.subscribe()
+}
+
+#[derive(Clone)]
+pub struct FeatureStore {
+    pool: SqlitePool,
+}
+
+impl FeatureStore {
+    /// Open the shared store at `~/.localgpt/features.sqlite`
+    pub fn open_default() -> Result<Self> {
+        Self::open(&get_state_dir()?.join("features.sqlite"))
+    }
+
+    pub fn open(db_path: &Path) -> Result<Self> {
+        Self::init(SqlitePool::shared(db_path, None)?)
+    }
+
+    /// In-memory store (tests)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(SqlitePool::open_in_memory(None)?)
+    }
+
+    fn init(pool: SqlitePool) -> Result<Self> {
+        pool.get()?.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS features (
+                name TEXT PRIMARY KEY,
+                enabled INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS feature_changes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                enabled INTEGER NOT NULL,
+                source TEXT NOT NULL,
+                changed_at INTEGER NOT NULL
+            );
+            "#,
+        )?;
+
+        Ok(Self { pool })
+    }
+
+    pub fn is_enabled(&self, feature: Feature) -> bool {
+        match feature {
+            Feature::HeartbeatPaused => crate::heartbeat::is_heartbeat_paused(DEFAULT_AGENT_ID),
+            Feature::ShadowMode => crate::discord::shadow::is_enabled(),
+            Feature::DiscordPaused => self.stored(feature).unwrap_or(false),
+        }
+    }
+
+    fn stored(&self, feature: Feature) -> Result<bool> {
+        let enabled: Option<bool> = self
+            .pool
+            .get()?
+            .query_row(
+                "SELECT enabled FROM features WHERE name = ?1",
+                params![feature.name()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(enabled.unwrap_or(false))
+    }
+
+    /// Turn a feature on or off and tell subscribers
+    pub fn set(&self, feature: Feature, enabled: bool, source: &str) -> Result<()> {
+        match feature {
+            Feature::HeartbeatPaused => {
+                crate::heartbeat::set_heartbeat_paused(DEFAULT_AGENT_ID, enabled)?
+            }
+            Feature::ShadowMode => crate::discord::shadow::set_enabled(enabled)?,
+            Feature::DiscordPaused => {}
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let conn = self.pool.get()?;
+        conn.execute(
+            "INSERT INTO features (name, enabled, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(name) DO UPDATE SET enabled = ?2, updated_at = ?3",
+            params![feature.name(), enabled, now],
+        )?;
+        conn.execute(
+            "INSERT INTO feature_changes (name, enabled, source, changed_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![feature.name(), enabled, source, now],
+        )?;
+
+        info!(
+            "Feature {} turned {} ({})",
+            feature.name(),
+            if enabled { "on" } else { "off" },
+            source
+        );
+        let _ = CHANGES.send(FeatureChange {
+            name: feature.name().to_string(),
+            enabled,
+            source: source.to_string(),
+            changed_at: now,
+        });
+        Ok(())
+    }
+
+    /// Set a feature by name
+    pub fn set_by_name(&self, name: &str, enabled: bool, source: &str) -> Result<()> {
+        let feature = Feature::parse(name).ok_or_else(|| anyhow!("Unknown feature: {}", name))?;
+        self.set(feature, enabled, source)
+    }
+
+    pub fn list(&self) -> Vec<FeatureState> {
+        Feature::ALL
+            .into_iter()
+            .map(|feature| FeatureState {
+                name: feature.name(),
+                description: feature.description(),
+                enabled: self.is_enabled(feature),
+            })
+            .collect()
+    }
+
+    /// The most recent changes, newest first
+    pub fn recent_changes(&self, limit: usize) -> Result<Vec<FeatureChange>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT name, enabled, source, changed_at FROM feature_changes
+             ORDER BY id DESC LIMIT ?1",
+        )?;
+        let changes = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok(FeatureChange {
+                    name: row.get(0)?,
+                    enabled: row.get(1)?,
+                    source: row.get(2)?,
+                    changed_at: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stored_toggle() {
+        let store = FeatureStore::open_in_memory().unwrap();
+        let mut changes = subscribe();
+        assert!(!store.is_enabled(Feature::DiscordPaused));
+
+        store.set_by_name("discord.paused", true, "test").unwrap();
+        assert!(store.is_enabled(Feature::DiscordPaused));
+        store.set(Feature::DiscordPaused, false, "test").unwrap();
+        assert!(!store.is_enabled(Feature::DiscordPaused));
+
+        let recent = store.recent_changes(10).unwrap();
+        assert_eq!(recent.len(), 2);
+        assert!(!recent[0].enabled);
+        assert_eq!(changes.try_recv().unwrap().name, "discord.paused");
+
+        assert!(store.set_by_name("voice.enabled", true, "test").is_err());
+    }
+}
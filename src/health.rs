@@ -0,0 +1,277 @@
+//! Dependency health checks
+//!
+//! The daemon checks the services it depends on when it starts and every
+//! `health.interval` after that: each configured HTTP model provider
+//! (OpenAI, Anthropic, Ollama, GLM) and, with Discord configured, the
+//! Discord API. A check is one GET with `health.timeout_secs`; any HTTP
+//! response counts as reachable, since only the connection and its latency
+//! are of interest here. A reachable service answering slower than
+//! `health.slow_ms` is degraded.
+//!
+//! The latest results are served at `GET /health?detail=true` and shown in
+//! the desktop status view, which runs its own checks. When a dependency
+//! degrades or goes down, and again when it recovers, a warning is posted
+//! to `notifications.admin_channel`.
+//!
+//! There are no speech-to-text or text-to-speech servers in this tree, so
+//! there is nothing to check for them yet.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::config::{Config, parse_duration};
+use crate::discord::rest::{DISCORD_API_ROOT, DiscordRest};
+use crate::heartbeat::now_ms;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    Up,
+    /// Reachable but slower than `health.slow_ms`
+    Degraded,
+    Down,
+}
+
+/// The result of checking one dependency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DependencyStatus {
+    /// "openai", "ollama", "discord", ...
+    pub name: String,
+    pub url: String,
+    pub state: HealthState,
+    /// Time to the response (None when there was none)
+    pub latency_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Timestamp in milliseconds
+    pub checked_at: u64,
+}
+
+/// The daemon's latest results
+static LATEST: RwLock<Vec<DependencyStatus>> = RwLock::new(Vec::new());
+
+/// The results of the daemon's latest check
+pub fn latest_statuses() -> Vec<DependencyStatus> {
+    LATEST.read().map(|guard| guard.clone()).unwrap_or_default()
+}
+
+fn store_statuses(statuses: Vec<DependencyStatus>) {
+    if let Ok(mut guard) = LATEST.write() {
+        *guard = statuses;
+    }
+}
+
+/// Name and URL of each dependency to check
+pub fn dependencies(config: &Config) -> Vec<(String, String)> {
+    let providers = &config.providers;
+    let mut deps = Vec::new();
+    if let Some(ref openai) = providers.openai {
+        deps.push(("openai".to_string(), openai.base_url.clone()));
+    }
+    if let Some(ref anthropic) = providers.anthropic {
+        deps.push(("anthropic".to_string(), anthropic.base_url.clone()));
+    }
+    if let Some(ref ollama) = providers.ollama {
+        deps.push(("ollama".to_string(), ollama.endpoint.clone()));
+    }
+    if let Some(ref glm) = providers.glm {
+        deps.push(("glm".to_string(), glm.base_url.clone()));
+    }
+    if let Some(ref discord) = config.channels.discord
+        && discord.enabled
+    {
+        deps.push((
+            "discord".to_string(),
+            format!("{}/v{}/gateway", DISCORD_API_ROOT, discord.api_version),
+        ));
+    }
+    deps
+}
+
+/// Check every dependency once, concurrently
+pub async fn check_all(config: &Config) -> Vec<DependencyStatus> {
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(config.health.timeout_secs))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Failed to create health check client: {}", e);
+            return Vec::new();
+        }
+    };
+    let slow = Duration::from_millis(config.health.slow_ms);
+    let checks = dependencies(config)
+        .into_iter()
+        .map(|(name, url)| check(&client, name, url, slow));
+    futures::future::join_all(checks).await
+}
+
+async fn check(
+    client: &reqwest::Client,
+    name: String,
+    url: String,
+    slow: Duration,
+) -> DependencyStatus {
+    let started = Instant::now();
+    let result = client.get(&url).send().await;
+    let elapsed = started.elapsed();
+    let (state, latency_ms, error) = match result {
+        Ok(_) => (
+            classify(elapsed, slow),
+            Some(elapsed.as_millis() as u64),
+            None,
+        ),
+        Err(e) => (HealthState::Down, None, Some(e.to_string())),
+    };
+    DependencyStatus {
+        name,
+        url,
+        state,
+        latency_ms,
+        error,
+        checked_at: now_ms(),
+    }
+}
+
+fn classify(latency: Duration, slow: Duration) -> HealthState {
+    if !slow.is_zero() && latency > slow {
+        HealthState::Degraded
+    } else {
+        HealthState::Up
+    }
+}
+
+/// Warnings for dependencies whose state changed since the last check.
+/// Dependencies seen for the first time are only reported if not up.
+fn changes(previous: &[DependencyStatus], current: &[DependencyStatus]) -> Vec<String> {
+    current
+        .iter()
+        .filter_map(|status| {
+            let before = previous
+                .iter()
+                .find(|p| p.name == status.name)
+                .map(|p| p.state)
+                .unwrap_or(HealthState::Up);
+            if before == status.state {
+                return None;
+            }
+            let detail = match (status.state, status.latency_ms, &status.error) {
+                (HealthState::Down, _, Some(error)) => format!("down: {}", error),
+                (HealthState::Degraded, Some(ms), _) => format!("slow: {}ms", ms),
+                (HealthState::Up, Some(ms), _) => format!("recovered ({}ms)", ms),
+                _ => format!("{:?}", status.state).to_lowercase(),
+            };
+            let icon = match status.state {
+                HealthState::Up => "✅",
+                HealthState::Degraded => "⚠️",
+                HealthState::Down => "🔴",
+            };
+            Some(format!(
+                "{} {} ({}) {}",
+                icon, status.name, status.url, detail
+            ))
+        })
+        .collect()
+}
+
+/// Check dependencies every `health.interval` for the daemon's lifetime
+pub async fn run(config: Config) -> Result<()> {
+    let interval = parse_duration(&config.health.interval)
+        .map_err(|e| anyhow::anyhow!("Invalid health interval: {}", e))?;
+    let rest: Option<Arc<dyn DiscordRest>> = match config.notifications.admin_channel {
+        Some(_) if config.channels.discord.is_some() => crate::discord::rest_client(&config)
+            .inspect_err(|e| warn!("Health warnings will not be posted to Discord: {}", e))
+            .ok(),
+        _ => None,
+    };
+    info!("Checking dependencies every {}", config.health.interval);
+
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        let statuses = check_all(&config).await;
+        for status in &statuses {
+            debug!(
+                "Health: {} {:?} in {:?}ms",
+                status.name, status.state, status.latency_ms
+            );
+        }
+
+        let warnings = changes(&latest_statuses(), &statuses);
+        store_statuses(statuses);
+        if warnings.is_empty() {
+            continue;
+        }
+        for warning in &warnings {
+            warn!("Dependency health changed: {}", warning);
+        }
+        if let (Some(rest), Some(channel_id)) = (&rest, &config.notifications.admin_channel)
+            && let Err(e) = rest
+                .send_message(
+                    channel_id,
+                    &format!("**Dependency health**\n{}", warnings.join("\n")),
+                    None,
+                )
+                .await
+        {
+            warn!("Failed to post health warning to Discord: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(name: &str, state: HealthState, latency_ms: Option<u64>) -> DependencyStatus {
+        DependencyStatus {
+            name: name.to_string(),
+            url: format!("http://{}.test", name),
+            state,
+            latency_ms,
+            error: (state == HealthState::Down).then(|| "connection refused".to_string()),
+            checked_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_classify_and_changes() {
+        let slow = Duration::from_millis(2000);
+        assert_eq!(classify(Duration::from_millis(150), slow), HealthState::Up);
+        assert_eq!(
+            classify(Duration::from_millis(2500), slow),
+            HealthState::Degraded
+        );
+        assert_eq!(
+            classify(Duration::from_secs(60), Duration::ZERO),
+            HealthState::Up
+        );
+
+        // First check: only problems are reported
+        let first = vec![
+            status("ollama", HealthState::Up, Some(12)),
+            status("discord", HealthState::Down, None),
+        ];
+        assert_eq!(
+            changes(&[], &first),
+            vec!["🔴 discord (http://discord.test) down: connection refused"]
+        );
+
+        let second = vec![
+            status("ollama", HealthState::Degraded, Some(4100)),
+            status("discord", HealthState::Up, Some(80)),
+        ];
+        assert_eq!(
+            changes(&first, &second),
+            vec![
+                "⚠️ ollama (http://ollama.test) slow: 4100ms",
+                "✅ discord (http://discord.test) recovered (80ms)",
+            ]
+        );
+        assert!(changes(&second, &second).is_empty());
+    }
+}
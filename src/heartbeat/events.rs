@@ -1,10 +1,10 @@
 //! Heartbeat event tracking for UI status display
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::sync::RwLock;
 
 /// Heartbeat event status
-#[derive(Debug, Clone, Serialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum HeartbeatStatus {
     /// Heartbeat ran and sent a response
@@ -18,7 +18,7 @@ pub enum HeartbeatStatus {
 }
 
 /// A heartbeat event for tracking/display
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HeartbeatEvent {
     /// Timestamp in milliseconds
     pub ts: u64,
@@ -32,6 +32,12 @@ pub struct HeartbeatEvent {
     /// Reason for skip/failure
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<String>,
+    /// Tool calls made during the run (e.g. "edit_file: HEARTBEAT.md")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<String>,
+    /// Full response text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
 }
 
 /// Global state for last heartbeat event
@@ -0,0 +1,125 @@
+//! Persistent heartbeat history and pause switch
+//!
+//! Every event the runner emits is also appended to
+//! `agents/<id>/heartbeat_events.jsonl` in the state directory, so the desktop
+//! app can show a timeline of runs made by the daemon. Pausing is a marker
+//! file next to it, checked by the runner before every tick.
+
+use anyhow::Result;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::events::HeartbeatEvent;
+
+/// Events kept in the history file
+const MAX_EVENTS: usize = 200;
+
+fn agent_dir(state_dir: &Path, agent_id: &str) -> PathBuf {
+    state_dir.join("agents").join(agent_id)
+}
+
+/// Path of the heartbeat history for an agent
+pub fn history_path(state_dir: &Path, agent_id: &str) -> PathBuf {
+    agent_dir(state_dir, agent_id).join("heartbeat_events.jsonl")
+}
+
+fn pause_marker(state_dir: &Path, agent_id: &str) -> PathBuf {
+    agent_dir(state_dir, agent_id).join("heartbeat_paused")
+}
+
+/// Append an event, trimming the file to the most recent events once it
+/// grows well past the limit
+pub fn append_event(path: &Path, event: &HeartbeatEvent) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(event)?)?;
+    drop(file);
+
+    let text = fs::read_to_string(path)?;
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.len() > MAX_EVENTS * 2 {
+        let kept = lines[lines.len() - MAX_EVENTS..].join("\n");
+        fs::write(path, kept + "\n")?;
+    }
+    Ok(())
+}
+
+/// The most recent events, oldest first
+pub fn read_events(path: &Path, limit: usize) -> Vec<HeartbeatEvent> {
+    let Ok(text) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let events: Vec<HeartbeatEvent> = text
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let skip = events.len().saturating_sub(limit);
+    events.into_iter().skip(skip).collect()
+}
+
+/// Load the most recent heartbeat events for an agent, oldest first
+pub fn load_heartbeat_history(agent_id: &str, limit: usize) -> Vec<HeartbeatEvent> {
+    crate::agent::get_state_dir()
+        .map(|dir| read_events(&history_path(&dir, agent_id), limit))
+        .unwrap_or_default()
+}
+
+/// Whether the heartbeat runner for an agent is paused
+pub fn is_heartbeat_paused(agent_id: &str) -> bool {
+    crate::agent::get_state_dir().is_ok_and(|dir| pause_marker(&dir, agent_id).exists())
+}
+
+/// Pause or resume the heartbeat runner (takes effect on its next tick)
+pub fn set_heartbeat_paused(agent_id: &str, paused: bool) -> Result<()> {
+    let marker = pause_marker(&crate::agent::get_state_dir()?, agent_id);
+    if paused {
+        if let Some(parent) = marker.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&marker, chrono::Utc::now().to_rfc3339())?;
+    } else if marker.exists() {
+        fs::remove_file(&marker)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heartbeat::HeartbeatStatus;
+
+    fn event(ts: u64) -> HeartbeatEvent {
+        HeartbeatEvent {
+            ts,
+            status: HeartbeatStatus::Sent,
+            duration_ms: 1200,
+            preview: Some("Reminder sent".to_string()),
+            reason: None,
+            actions: vec!["edit_file: HEARTBEAT.md".to_string()],
+            response: Some("Reminder sent to Discord.".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_history_roundtrip_and_trim() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = history_path(dir.path(), "main");
+        assert!(read_events(&path, 10).is_empty());
+
+        for ts in 0..(MAX_EVENTS * 2 + 1) as u64 {
+            append_event(&path, &event(ts)).unwrap();
+        }
+
+        let all = read_events(&path, usize::MAX);
+        assert_eq!(all.len(), MAX_EVENTS);
+        assert_eq!(all.last().unwrap().ts, (MAX_EVENTS * 2) as u64);
+        assert_eq!(all[0].actions, vec!["edit_file: HEARTBEAT.md"]);
+
+        let recent = read_events(&path, 3);
+        assert_eq!(recent.len(), 3);
+        assert_eq!(recent[2].ts, (MAX_EVENTS * 2) as u64);
+    }
+}
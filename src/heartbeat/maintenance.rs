@@ -0,0 +1,180 @@
+//! Nightly memory index maintenance
+//!
+//! Once per day (after `heartbeat.maintenance.time`) the runner re-indexes
+//! changed markdown files, prunes orphaned index rows, backfills embeddings
+//! for new or modified chunks and compacts the SQLite databases. The last
+//! report is saved to the agent's state directory so the desktop Status view
+//! can show it.
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tracing::{info, warn};
+
+use crate::memory::MemoryManager;
+use crate::tasks::TaskStore;
+
+/// Chunks embedded per batch during the backfill step
+const EMBEDDING_BATCH_SIZE: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceStep {
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceReport {
+    /// Day the maintenance ran for (YYYY-MM-DD)
+    pub date: String,
+    /// Unix timestamp (seconds) when the run finished
+    pub finished_at: i64,
+    pub duration_ms: u64,
+    pub steps: Vec<MaintenanceStep>,
+}
+
+impl MaintenanceReport {
+    pub fn ran_on(&self, date: NaiveDate) -> bool {
+        self.date == date.format("%Y-%m-%d").to_string()
+    }
+
+    pub fn failed_steps(&self) -> usize {
+        self.steps.iter().filter(|s| !s.ok).count()
+    }
+}
+
+/// Run all maintenance steps. Individual failures are recorded in the
+/// report rather than aborting the remaining steps.
+pub async fn run_maintenance(memory: &MemoryManager, date: NaiveDate) -> MaintenanceReport {
+    let start = Instant::now();
+    let mut steps = Vec::new();
+
+    record(&mut steps, "reindex", || {
+        let stats = memory.reindex(false)?;
+        Ok(format!(
+            "{} of {} files changed, {} chunks",
+            stats.files_updated, stats.files_processed, stats.chunks_indexed
+        ))
+    });
+
+    record(&mut steps, "prune", || {
+        let removed = memory.prune_index()?;
+        Ok(format!("{} orphaned rows removed", removed))
+    });
+
+    if memory.has_embeddings() {
+        let step_start = Instant::now();
+        let result = memory
+            .generate_embeddings(EMBEDDING_BATCH_SIZE)
+            .await
+            .map(|(processed, embedded)| format!("{} of {} chunks embedded", embedded, processed));
+        push_step(&mut steps, "embeddings", step_start, result);
+    }
+
+    record(&mut steps, "optimize index", || {
+        let (before, after) = memory.optimize_index()?;
+        Ok(format!("{} KB -> {} KB", before / 1024, after / 1024))
+    });
+
+    record(&mut steps, "optimize tasks", || {
+        TaskStore::open_default()?.optimize()?;
+        Ok("ok".to_string())
+    });
+
+    MaintenanceReport {
+        date: date.format("%Y-%m-%d").to_string(),
+        finished_at: chrono::Utc::now().timestamp(),
+        duration_ms: start.elapsed().as_millis() as u64,
+        steps,
+    }
+}
+
+fn record(steps: &mut Vec<MaintenanceStep>, name: &str, f: impl FnOnce() -> Result<String>) {
+    let start = Instant::now();
+    let result = f();
+    push_step(steps, name, start, result);
+}
+
+fn push_step(steps: &mut Vec<MaintenanceStep>, name: &str, start: Instant, result: Result<String>) {
+    let duration_ms = start.elapsed().as_millis() as u64;
+    let (ok, detail) = match result {
+        Ok(detail) => {
+            info!(
+                "Index maintenance: {} — {} ({} ms)",
+                name, detail, duration_ms
+            );
+            (true, detail)
+        }
+        Err(e) => {
+            warn!("Index maintenance: {} failed: {}", name, e);
+            (false, e.to_string())
+        }
+    };
+    steps.push(MaintenanceStep {
+        name: name.to_string(),
+        ok,
+        detail,
+        duration_ms,
+    });
+}
+
+/// Path of the last maintenance report for an agent
+pub fn report_path(state_dir: &Path, agent_id: &str) -> PathBuf {
+    state_dir
+        .join("agents")
+        .join(agent_id)
+        .join("maintenance.json")
+}
+
+pub fn read_report(path: &Path) -> Option<MaintenanceReport> {
+    let text = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+pub fn write_report(path: &Path, report: &MaintenanceReport) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(report)?)?;
+    Ok(())
+}
+
+/// Load the last maintenance report for an agent, if any
+pub fn load_last_maintenance_report(agent_id: &str) -> Option<MaintenanceReport> {
+    let state_dir = crate::agent::get_state_dir().ok()?;
+    read_report(&report_path(&state_dir, agent_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = report_path(dir.path(), "main");
+        let date = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+
+        let mut steps = Vec::new();
+        record(&mut steps, "ok step", || Ok("done".to_string()));
+        record(&mut steps, "bad step", || Err(anyhow::anyhow!("disk full")));
+
+        let report = MaintenanceReport {
+            date: "2026-03-01".to_string(),
+            finished_at: 0,
+            duration_ms: 5,
+            steps,
+        };
+        write_report(&path, &report).unwrap();
+
+        let loaded = read_report(&path).unwrap();
+        assert!(loaded.ran_on(date));
+        assert_eq!(loaded.failed_steps(), 1);
+        assert_eq!(loaded.steps[1].detail, "disk full");
+    }
+}
@@ -1,5 +1,12 @@
 mod events;
+mod history;
+mod maintenance;
+mod reflection;
 mod runner;
 
-pub use events::{HeartbeatEvent, HeartbeatStatus, emit_heartbeat_event, get_last_heartbeat_event};
+pub use events::{
+    HeartbeatEvent, HeartbeatStatus, emit_heartbeat_event, get_last_heartbeat_event, now_ms,
+};
+pub use history::{is_heartbeat_paused, load_heartbeat_history, set_heartbeat_paused};
+pub use maintenance::{MaintenanceReport, MaintenanceStep, load_last_maintenance_report};
 pub use runner::HeartbeatRunner;
@@ -0,0 +1,229 @@
+//! End-of-day self-reflection
+//!
+//! Once per day (after `heartbeat.reflection.time`) the agent reviews the
+//! day's conversations from memory, extracts follow-ups and unresolved
+//! questions into HEARTBEAT.md, and optionally posts a short summary.
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::agent::HEARTBEAT_OK_TOKEN;
+
+/// Maximum characters of conversation text fed into the reflection prompt
+const MAX_TRANSCRIPT_CHARS: usize = 40_000;
+
+/// Parsed result of a reflection turn
+#[derive(Debug, Default, PartialEq)]
+pub struct Reflection {
+    pub followups: Vec<String>,
+    pub summary: Option<String>,
+}
+
+/// Collect the day's daily log, topic logs and saved session files from
+/// `memory/`.
+///
+/// Returns an empty string when nothing was recorded that day.
+pub fn collect_day_conversations(workspace: &Path, date: NaiveDate) -> Result<String> {
+    let memory_dir = workspace.join("memory");
+    if !memory_dir.exists() {
+        return Ok(String::new());
+    }
+
+    let prefix = date.format("%Y-%m-%d").to_string();
+    let mut files: Vec<PathBuf> = fs::read_dir(&memory_dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension().is_some_and(|ext| ext == "md")
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(&prefix))
+        })
+        .collect();
+    // Topic logs live in memory/YYYY-MM-DD/
+    if let Ok(entries) = fs::read_dir(memory_dir.join(&prefix)) {
+        files.extend(
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "md")),
+        );
+    }
+    files.sort();
+
+    let mut content = String::new();
+    for path in files {
+        let Ok(text) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let name = path.strip_prefix(&memory_dir).unwrap_or(&path).display();
+        content.push_str(&format!("## {}\n\n{}\n\n", name, text.trim()));
+        if content.len() > MAX_TRANSCRIPT_CHARS {
+            content = crate::utils::safe_truncate(&content, MAX_TRANSCRIPT_CHARS).to_string();
+            content.push_str("\n\n[truncated]");
+            break;
+        }
+    }
+
+    Ok(content)
+}
+
+/// Build the reflection prompt for a given day
+pub fn build_reflection_prompt(date: NaiveDate, conversations: &str) -> String {
+    format!(
+        "End-of-day reflection for {date}.\n\n\
+         Review today's conversations below. Extract concrete follow-ups and \
+         questions that were left unresolved. Do not repeat tasks that were \
+         completed. Reply in exactly this format:\n\n\
+         FOLLOWUPS:\n\
+         - <one follow-up per line>\n\n\
+         SUMMARY:\n\
+         <two or three sentences summarizing the day>\n\n\
+         If there is nothing worth following up, reply {ok}.\n\n\
+         <conversations>\n{conversations}\n</conversations>",
+        date = date.format("%Y-%m-%d"),
+        ok = HEARTBEAT_OK_TOKEN,
+        conversations = conversations,
+    )
+}
+
+/// Parse the FOLLOWUPS/SUMMARY sections out of a reflection response
+pub fn parse_reflection(response: &str) -> Reflection {
+    let mut reflection = Reflection::default();
+    let mut summary_lines = Vec::new();
+    let mut section = "";
+
+    for line in response.lines() {
+        let trimmed = line.trim();
+        match trimmed.to_ascii_uppercase().as_str() {
+            "FOLLOWUPS:" | "FOLLOW-UPS:" => {
+                section = "followups";
+                continue;
+            }
+            "SUMMARY:" => {
+                section = "summary";
+                continue;
+            }
+            _ => {}
+        }
+
+        match section {
+            "followups" => {
+                let item = trimmed
+                    .trim_start_matches("- [ ]")
+                    .trim_start_matches(['-', '*'])
+                    .trim();
+                if !item.is_empty() && !item.eq_ignore_ascii_case("none") {
+                    reflection.followups.push(item.to_string());
+                }
+            }
+            "summary" if !trimmed.is_empty() => summary_lines.push(trimmed),
+            _ => {}
+        }
+    }
+
+    if !summary_lines.is_empty() {
+        reflection.summary = Some(summary_lines.join(" "));
+    }
+
+    reflection
+}
+
+/// Append follow-ups to HEARTBEAT.md under a dated heading.
+///
+/// Returns `false` without touching the file if that day's heading already
+/// exists (the reflection ran before) or there is nothing to add.
+pub fn append_followups(
+    heartbeat_path: &Path,
+    date: NaiveDate,
+    followups: &[String],
+) -> Result<bool> {
+    if followups.is_empty() {
+        return Ok(false);
+    }
+
+    let heading = format!("## Follow-ups from {}", date.format("%Y-%m-%d"));
+    let existing = if heartbeat_path.exists() {
+        fs::read_to_string(heartbeat_path)?
+    } else {
+        "# Heartbeat Tasks\n".to_string()
+    };
+
+    if existing.contains(&heading) {
+        return Ok(false);
+    }
+
+    let mut content = existing.trim_end().to_string();
+    content.push_str(&format!("\n\n{}\n\n", heading));
+    for item in followups {
+        content.push_str(&format!("- [ ] {}\n", item));
+    }
+
+    fs::write(heartbeat_path, content)?;
+    Ok(true)
+}
+
+/// Path of the marker file recording the last day reflection ran
+pub fn last_run_path(state_dir: &Path, agent_id: &str) -> PathBuf {
+    state_dir
+        .join("agents")
+        .join(agent_id)
+        .join("reflection_last_run")
+}
+
+/// Read the date the reflection last ran, if any
+pub fn read_last_run(path: &Path) -> Option<NaiveDate> {
+    let text = fs::read_to_string(path).ok()?;
+    NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d").ok()
+}
+
+/// Record that the reflection ran for `date`
+pub fn write_last_run(path: &Path, date: NaiveDate) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, date.format("%Y-%m-%d").to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reflection() {
+        let response = "FOLLOWUPS:\n- Reply to Alice about the trip\n- [ ] Check backup logs\n\nSUMMARY:\nPlanned the trip.\nFixed the backup script.";
+        let r = parse_reflection(response);
+        assert_eq!(
+            r.followups,
+            vec!["Reply to Alice about the trip", "Check backup logs"]
+        );
+        assert_eq!(
+            r.summary.as_deref(),
+            Some("Planned the trip. Fixed the backup script.")
+        );
+    }
+
+    #[test]
+    fn test_parse_reflection_ok() {
+        let r = parse_reflection(HEARTBEAT_OK_TOKEN);
+        assert_eq!(r, Reflection::default());
+    }
+
+    #[test]
+    fn test_append_followups_is_idempotent() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("HEARTBEAT.md");
+        let date = NaiveDate::from_ymd_opt(2026, 1, 15).unwrap();
+        let items = vec!["Call the plumber".to_string()];
+
+        assert!(append_followups(&path, date, &items).unwrap());
+        assert!(!append_followups(&path, date, &items).unwrap());
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("## Follow-ups from 2026-01-15"));
+        assert_eq!(content.matches("- [ ] Call the plumber").count(), 1);
+    }
+}
@@ -9,9 +9,13 @@ use tokio::time::sleep;
 use tracing::{debug, info, warn};
 
 use super::events::{HeartbeatEvent, HeartbeatStatus, emit_heartbeat_event, now_ms};
+use super::history::{self, is_heartbeat_paused};
+use super::{maintenance, reflection};
 use crate::agent::{
-    Agent, AgentConfig, HEARTBEAT_OK_TOKEN, SessionStore, build_heartbeat_prompt, is_heartbeat_ok,
+    Agent, AgentConfig, HEARTBEAT_OK_TOKEN, Language, SessionStore, build_heartbeat_prompt,
+    extract_tool_detail, get_state_dir, is_heartbeat_ok,
 };
+use crate::calendar::{self, Calendar};
 use crate::concurrency::{TurnGate, WorkspaceLock};
 use crate::config::{Config, parse_duration, parse_time};
 use crate::memory::MemoryManager;
@@ -20,6 +24,10 @@ pub struct HeartbeatRunner {
     config: Config,
     interval: Duration,
     active_hours: Option<(NaiveTime, NaiveTime)>,
+    /// Time of day after which the daily reflection runs (None = disabled)
+    reflection_time: Option<NaiveTime>,
+    /// Time of day after which index maintenance runs (None = disabled)
+    maintenance_time: Option<NaiveTime>,
     workspace: PathBuf,
     agent_id: String,
     /// Cached MemoryManager to avoid reinitializing embedding provider on every heartbeat
@@ -67,6 +75,22 @@ impl HeartbeatRunner {
             None
         };
 
+        let reflection_time = if config.heartbeat.reflection.enabled {
+            let (h, m) = parse_time(&config.heartbeat.reflection.time)
+                .map_err(|e| anyhow::anyhow!("Invalid reflection time: {}", e))?;
+            NaiveTime::from_hms_opt(h as u32, m as u32, 0)
+        } else {
+            None
+        };
+
+        let maintenance_time = if config.heartbeat.maintenance.enabled {
+            let (h, m) = parse_time(&config.heartbeat.maintenance.time)
+                .map_err(|e| anyhow::anyhow!("Invalid maintenance time: {}", e))?;
+            NaiveTime::from_hms_opt(h as u32, m as u32, 0)
+        } else {
+            None
+        };
+
         let workspace = config.workspace_path();
 
         // Create MemoryManager once and reuse it to avoid reinitializing embedding provider
@@ -77,6 +101,8 @@ impl HeartbeatRunner {
             config: config.clone(),
             interval,
             active_hours,
+            reflection_time,
+            maintenance_time,
             workspace,
             agent_id: agent_id.to_string(),
             memory,
@@ -96,15 +122,46 @@ impl HeartbeatRunner {
             // Sleep until next interval
             sleep(self.interval).await;
 
+            if is_heartbeat_paused(&self.agent_id) {
+                debug!("Heartbeat runner paused");
+                continue;
+            }
+
+            // Reminders are for events, not the agent's schedule, so they
+            // don't wait for active hours either
+            self.maybe_send_calendar_reminders().await;
+
+            // Maintenance is scheduled for low-traffic hours, which are
+            // usually outside active hours
+            self.maybe_run_maintenance().await;
+
             // Check active hours
             if !self.in_active_hours() {
                 debug!("Outside active hours, skipping heartbeat");
-                emit_heartbeat_event(HeartbeatEvent {
+                self.record_event(HeartbeatEvent {
                     ts: now_ms(),
                     status: HeartbeatStatus::Skipped,
                     duration_ms: 0,
                     preview: None,
                     reason: Some("outside active hours".to_string()),
+                    actions: Vec::new(),
+                    response: None,
+                });
+                continue;
+            }
+
+            // Don't compete with someone using the machine; the next
+            // interval tries again
+            if let Some(busy) = crate::monitor::busy_reason(&self.config) {
+                debug!("Machine busy ({}), skipping heartbeat", busy);
+                self.record_event(HeartbeatEvent {
+                    ts: now_ms(),
+                    status: HeartbeatStatus::Skipped,
+                    duration_ms: 0,
+                    preview: None,
+                    reason: Some(format!("machine busy: {}", busy)),
+                    actions: Vec::new(),
+                    response: None,
                 });
                 continue;
             }
@@ -112,7 +169,7 @@ impl HeartbeatRunner {
             // Run heartbeat with timing
             let start = Instant::now();
             match self.run_once_internal().await {
-                Ok((response, status)) => {
+                Ok((response, status, actions)) => {
                     let duration_ms = start.elapsed().as_millis() as u64;
                     let preview = if response.len() > 200 {
                         Some(format!("{}...", crate::utils::safe_truncate(&response, 200)))
@@ -120,12 +177,14 @@ impl HeartbeatRunner {
                         Some(response.clone())
                     };
 
-                    emit_heartbeat_event(HeartbeatEvent {
+                    self.record_event(HeartbeatEvent {
                         ts: now_ms(),
                         status,
                         duration_ms,
                         preview,
                         reason: None,
+                        actions,
+                        response: Some(response.clone()),
                     });
 
                     if is_heartbeat_ok(&response) {
@@ -136,17 +195,298 @@ impl HeartbeatRunner {
                 }
                 Err(e) => {
                     let duration_ms = start.elapsed().as_millis() as u64;
-                    emit_heartbeat_event(HeartbeatEvent {
+                    self.record_event(HeartbeatEvent {
                         ts: now_ms(),
                         status: HeartbeatStatus::Failed,
                         duration_ms,
                         preview: None,
                         reason: Some(e.to_string()),
+                        actions: Vec::new(),
+                        response: None,
                     });
                     warn!("Heartbeat error: {}", e);
+                    crate::digest::report("heartbeat", &self.agent_id, &e.to_string());
+                }
+            }
+
+            self.maybe_run_reflection().await;
+        }
+    }
+
+    /// Run the daily reflection if it is enabled, due, and not yet done today
+    async fn maybe_run_reflection(&self) {
+        let Some(reflection_time) = self.reflection_time else {
+            return;
+        };
+
+        let now = Local::now();
+        if now.time() < reflection_time {
+            return;
+        }
+
+        let today = now.date_naive();
+        let marker = match get_state_dir() {
+            Ok(dir) => reflection::last_run_path(&dir, &self.agent_id),
+            Err(e) => {
+                warn!("Reflection skipped: {}", e);
+                return;
+            }
+        };
+        if reflection::read_last_run(&marker) == Some(today) {
+            return;
+        }
+
+        let start = Instant::now();
+        match self.run_reflection(today).await {
+            Ok(Some(summary)) => {
+                if let Err(e) = reflection::write_last_run(&marker, today) {
+                    warn!("Failed to record reflection run: {}", e);
+                }
+                self.record_event(HeartbeatEvent {
+                    ts: now_ms(),
+                    status: HeartbeatStatus::Sent,
+                    duration_ms: start.elapsed().as_millis() as u64,
+                    preview: Some(crate::utils::safe_truncate(&summary, 200).to_string()),
+                    reason: Some("daily reflection".to_string()),
+                    actions: Vec::new(),
+                    response: Some(summary),
+                });
+            }
+            Ok(None) => {
+                // Workspace busy; retry on the next tick
+            }
+            Err(e) => {
+                warn!("Reflection error: {}", e);
+                crate::digest::report("heartbeat", &self.agent_id, &format!("Reflection: {}", e));
+                self.record_event(HeartbeatEvent {
+                    ts: now_ms(),
+                    status: HeartbeatStatus::Failed,
+                    duration_ms: start.elapsed().as_millis() as u64,
+                    preview: None,
+                    reason: Some(format!("daily reflection: {}", e)),
+                    actions: Vec::new(),
+                    response: None,
+                });
+            }
+        }
+    }
+
+    /// Remind about calendar events starting before the next tick (plus the
+    /// configured lead time), once per event
+    async fn maybe_send_calendar_reminders(&self) {
+        let Some(ref calendar_config) = self.config.calendar else {
+            return;
+        };
+        if !calendar_config.enabled || calendar_config.reminder_minutes == 0 {
+            return;
+        }
+
+        let now = chrono::Utc::now();
+        let lead = chrono::Duration::minutes(calendar_config.reminder_minutes as i64)
+            + chrono::Duration::from_std(self.interval).unwrap_or_default();
+        let events = match Calendar::new(calendar_config)
+            .list_events(now, now + lead)
+            .await
+        {
+            Ok(events) => events,
+            Err(e) => {
+                warn!("Calendar reminders skipped: {}", e);
+                return;
+            }
+        };
+
+        let due = match get_state_dir().and_then(|dir| {
+            calendar::take_due_reminders(
+                &calendar::reminders_path(&dir, &self.agent_id),
+                events,
+                now,
+            )
+        }) {
+            Ok(due) => due,
+            Err(e) => {
+                warn!("Calendar reminders skipped: {}", e);
+                return;
+            }
+        };
+        if due.is_empty() {
+            return;
+        }
+
+        let message = calendar::format_reminder(&due, now);
+        info!("Calendar reminder for {} event(s)", due.len());
+        if let Some(ref channel_id) = calendar_config.notify_discord_channel
+            && let Err(e) =
+                crate::discord::send_channel_message(&self.config, channel_id, &message).await
+        {
+            warn!("Failed to post calendar reminder to Discord: {}", e);
+        }
+
+        self.record_event(HeartbeatEvent {
+            ts: now_ms(),
+            status: HeartbeatStatus::Sent,
+            duration_ms: 0,
+            preview: Some(crate::utils::safe_truncate(&message, 200).to_string()),
+            reason: Some("calendar reminder".to_string()),
+            actions: Vec::new(),
+            response: Some(message),
+        });
+    }
+
+    /// Run index maintenance if it is enabled, due, and not yet done today
+    async fn maybe_run_maintenance(&self) {
+        let Some(maintenance_time) = self.maintenance_time else {
+            return;
+        };
+
+        let now = Local::now();
+        if now.time() < maintenance_time {
+            return;
+        }
+
+        let today = now.date_naive();
+        let report_path = match get_state_dir() {
+            Ok(dir) => maintenance::report_path(&dir, &self.agent_id),
+            Err(e) => {
+                warn!("Index maintenance skipped: {}", e);
+                return;
+            }
+        };
+        if maintenance::read_report(&report_path).is_some_and(|r| r.ran_on(today)) {
+            return;
+        }
+
+        match self.run_maintenance(today).await {
+            Ok(Some(report)) => {
+                if let Err(e) = maintenance::write_report(&report_path, &report) {
+                    warn!("Failed to save maintenance report: {}", e);
                 }
             }
+            Ok(None) => {
+                // Workspace busy; retry on the next tick
+            }
+            Err(e) => warn!("Index maintenance error: {}", e),
+        }
+    }
+
+    /// Re-index, prune and compact the memory index and task database.
+    ///
+    /// Returns `None` if skipped because another turn holds the workspace.
+    pub async fn run_maintenance(
+        &self,
+        date: chrono::NaiveDate,
+    ) -> Result<Option<maintenance::MaintenanceReport>> {
+        if let Some(ref gate) = self.turn_gate
+            && gate.is_busy()
+        {
+            debug!("Skipping index maintenance: agent turn in flight");
+            return Ok(None);
+        }
+        let Some(_ws_guard) = self.workspace_lock.try_acquire()? else {
+            debug!("Skipping index maintenance: workspace locked by another process");
+            return Ok(None);
+        };
+        let _gate_permit = match self.turn_gate {
+            Some(ref gate) => match gate.try_acquire() {
+                Some(permit) => Some(permit),
+                None => return Ok(None),
+            },
+            None => None,
+        };
+
+        info!("Starting index maintenance");
+        let report = maintenance::run_maintenance(&self.memory, date).await;
+        info!(
+            "Index maintenance finished in {} ms ({} step(s) failed)",
+            report.duration_ms,
+            report.failed_steps()
+        );
+
+        self.record_event(HeartbeatEvent {
+            ts: now_ms(),
+            status: if report.failed_steps() == 0 {
+                HeartbeatStatus::Ok
+            } else {
+                HeartbeatStatus::Failed
+            },
+            duration_ms: report.duration_ms,
+            preview: None,
+            reason: Some("index maintenance".to_string()),
+            actions: Vec::new(),
+            response: None,
+        });
+
+        Ok(Some(report))
+    }
+
+    /// Review the day's conversations and record follow-ups.
+    ///
+    /// Returns the summary text when the reflection completed, or `None` if
+    /// it was skipped because another turn holds the workspace.
+    pub async fn run_reflection(&self, date: chrono::NaiveDate) -> Result<Option<String>> {
+        if let Some(ref gate) = self.turn_gate
+            && gate.is_busy()
+        {
+            debug!("Skipping reflection: agent turn in flight");
+            return Ok(None);
+        }
+        let Some(_ws_guard) = self.workspace_lock.try_acquire()? else {
+            debug!("Skipping reflection: workspace locked by another process");
+            return Ok(None);
+        };
+        let _gate_permit = match self.turn_gate {
+            Some(ref gate) => match gate.try_acquire() {
+                Some(permit) => Some(permit),
+                None => return Ok(None),
+            },
+            None => None,
+        };
+
+        let conversations = reflection::collect_day_conversations(&self.workspace, date)?;
+        if conversations.trim().is_empty() {
+            debug!("No conversations recorded today, nothing to reflect on");
+            return Ok(Some("No conversations today.".to_string()));
+        }
+
+        let agent_config = AgentConfig {
+            model: self.config.agent.default_model.clone(),
+            context_window: self.config.agent.context_window,
+            reserve_tokens: self.config.agent.reserve_tokens,
+        };
+        let mut agent = Agent::new(agent_config, &self.config, self.memory.clone()).await?;
+        agent.new_session().await?;
+
+        let prompt = reflection::build_reflection_prompt(date, &conversations);
+        let response = agent.chat(&prompt).await?;
+        if is_heartbeat_ok(&response) {
+            return Ok(Some("Nothing to follow up on.".to_string()));
+        }
+
+        let result = reflection::parse_reflection(&response);
+        let heartbeat_path = self.workspace.join("HEARTBEAT.md");
+        if reflection::append_followups(&heartbeat_path, date, &result.followups)? {
+            info!(
+                "Reflection added {} follow-up(s) to HEARTBEAT.md",
+                result.followups.len()
+            );
+        }
+
+        let summary = result
+            .summary
+            .unwrap_or_else(|| format!("{} follow-up(s) recorded.", result.followups.len()));
+
+        if let Some(ref channel_id) = self.config.heartbeat.reflection.notify_discord_channel {
+            let mut message = format!("**End of day — {}**\n{}", date.format("%Y-%m-%d"), summary);
+            for item in &result.followups {
+                message.push_str(&format!("\n- {}", item));
+            }
+            if let Err(e) =
+                crate::discord::send_channel_message(&self.config, channel_id, &message).await
+            {
+                warn!("Failed to post reflection summary to Discord: {}", e);
+            }
         }
+
+        Ok(Some(summary))
     }
 
     /// Run a single heartbeat cycle (public API, emits events)
@@ -154,7 +494,7 @@ impl HeartbeatRunner {
         let start = Instant::now();
 
         match self.run_once_internal().await {
-            Ok((response, status)) => {
+            Ok((response, status, actions)) => {
                 let duration_ms = start.elapsed().as_millis() as u64;
                 let preview = if response.len() > 200 {
                     Some(format!("{}...", crate::utils::safe_truncate(&response, 200)))
@@ -162,38 +502,47 @@ impl HeartbeatRunner {
                     Some(response.clone())
                 };
 
-                emit_heartbeat_event(HeartbeatEvent {
+                self.record_event(HeartbeatEvent {
                     ts: now_ms(),
                     status,
                     duration_ms,
                     preview,
                     reason: None,
+                    actions,
+                    response: Some(response.clone()),
                 });
 
                 Ok(response)
             }
             Err(e) => {
                 let duration_ms = start.elapsed().as_millis() as u64;
-                emit_heartbeat_event(HeartbeatEvent {
+                self.record_event(HeartbeatEvent {
                     ts: now_ms(),
                     status: HeartbeatStatus::Failed,
                     duration_ms,
                     preview: None,
                     reason: Some(e.to_string()),
+                    actions: Vec::new(),
+                    response: None,
                 });
                 Err(e)
             }
         }
     }
 
-    /// Internal heartbeat execution (returns response and status)
-    async fn run_once_internal(&self) -> Result<(String, HeartbeatStatus)> {
+    /// Internal heartbeat execution (returns response, status and the tool
+    /// calls made)
+    async fn run_once_internal(&self) -> Result<(String, HeartbeatStatus, Vec<String>)> {
         // Skip if an in-process agent turn is already in flight
         if let Some(ref gate) = self.turn_gate
             && gate.is_busy()
         {
             debug!("Skipping heartbeat: agent turn in flight (TurnGate busy)");
-            return Ok((HEARTBEAT_OK_TOKEN.to_string(), HeartbeatStatus::Skipped));
+            return Ok((
+                HEARTBEAT_OK_TOKEN.to_string(),
+                HeartbeatStatus::Skipped,
+                Vec::new(),
+            ));
         }
 
         // Try to acquire the cross-process workspace lock (non-blocking)
@@ -201,7 +550,11 @@ impl HeartbeatRunner {
             Some(guard) => guard,
             None => {
                 debug!("Skipping heartbeat: workspace locked by another process");
-                return Ok((HEARTBEAT_OK_TOKEN.to_string(), HeartbeatStatus::Skipped));
+                return Ok((
+                    HEARTBEAT_OK_TOKEN.to_string(),
+                    HeartbeatStatus::Skipped,
+                    Vec::new(),
+                ));
             }
         };
 
@@ -212,7 +565,11 @@ impl HeartbeatRunner {
                 Some(permit) => Some(permit),
                 None => {
                     debug!("Skipping heartbeat: agent turn started between check and acquire");
-                    return Ok((HEARTBEAT_OK_TOKEN.to_string(), HeartbeatStatus::Skipped));
+                    return Ok((
+                        HEARTBEAT_OK_TOKEN.to_string(),
+                        HeartbeatStatus::Skipped,
+                        Vec::new(),
+                    ));
                 }
             }
         } else {
@@ -224,13 +581,21 @@ impl HeartbeatRunner {
 
         if !heartbeat_path.exists() {
             debug!("No HEARTBEAT.md found");
-            return Ok((HEARTBEAT_OK_TOKEN.to_string(), HeartbeatStatus::Skipped));
+            return Ok((
+                HEARTBEAT_OK_TOKEN.to_string(),
+                HeartbeatStatus::Skipped,
+                Vec::new(),
+            ));
         }
 
         let content = fs::read_to_string(&heartbeat_path)?;
         if content.trim().is_empty() {
             debug!("HEARTBEAT.md is empty");
-            return Ok((HEARTBEAT_OK_TOKEN.to_string(), HeartbeatStatus::Skipped));
+            return Ok((
+                HEARTBEAT_OK_TOKEN.to_string(),
+                HeartbeatStatus::Skipped,
+                Vec::new(),
+            ));
         }
 
         // Create agent for heartbeat (clone the cached MemoryManager to share the embedding provider)
@@ -247,12 +612,14 @@ impl HeartbeatRunner {
         let workspace_is_git = self.workspace.join(".git").exists();
 
         // Send heartbeat prompt
-        let heartbeat_prompt = build_heartbeat_prompt(workspace_is_git);
+        let language = Language::from_config(&self.config.agent.language);
+        let heartbeat_prompt = build_heartbeat_prompt(workspace_is_git, language);
         let response = agent.chat(&heartbeat_prompt).await?;
+        let actions = tool_actions(&agent);
 
         // Determine status based on response
         if is_heartbeat_ok(&response) {
-            return Ok((response, HeartbeatStatus::Ok));
+            return Ok((response, HeartbeatStatus::Ok, actions));
         }
 
         // For actual alerts, check for deduplication
@@ -267,7 +634,7 @@ impl HeartbeatRunner {
                     "Skipping duplicate heartbeat (same text within 24h): {}",
                     &response[..response.len().min(100)]
                 );
-                return Ok((response, HeartbeatStatus::Skipped));
+                return Ok((response, HeartbeatStatus::Skipped, actions));
             }
 
             // Record the heartbeat (re-read from disk to avoid clobbering)
@@ -279,7 +646,18 @@ impl HeartbeatRunner {
             }
         }
 
-        Ok((response, HeartbeatStatus::Sent))
+        Ok((response, HeartbeatStatus::Sent, actions))
+    }
+
+    /// Emit an event and append it to this agent's heartbeat history
+    fn record_event(&self, event: HeartbeatEvent) {
+        if let Ok(dir) = get_state_dir() {
+            let path = history::history_path(&dir, &self.agent_id);
+            if let Err(e) = history::append_event(&path, &event) {
+                warn!("Failed to write heartbeat history: {}", e);
+            }
+        }
+        emit_heartbeat_event(event);
     }
 
     fn in_active_hours(&self) -> bool {
@@ -299,6 +677,22 @@ impl HeartbeatRunner {
     }
 }
 
+/// Tool calls made in the agent's session, as "name: detail"
+fn tool_actions(agent: &Agent) -> Vec<String> {
+    agent
+        .raw_session_messages()
+        .iter()
+        .filter_map(|m| m.message.tool_calls.as_ref())
+        .flatten()
+        .map(
+            |call| match extract_tool_detail(&call.name, &call.arguments) {
+                Some(detail) => format!("{}: {}", call.name, detail),
+                None => call.name.clone(),
+            },
+        )
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
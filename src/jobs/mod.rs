@@ -0,0 +1,431 @@
+//! Background jobs
+//!
+//! Work too long for a chat turn (summarizing a pile of documents, ingesting
+//! files, running a script) is handed to a job with the `job_start` tool.
+//! Jobs are queued in a SQLite table shared by every process, and the daemon's
+//! [`JobRunner`] executes them on a fresh agent outside the conversation.
+//! A job started from Discord posts a status message that is edited as the
+//! job reports progress, and its result is sent to the channel when it ends.
+//! Jobs interrupted by a restart are queued again when the daemon starts.
+
+mod runner;
+
+pub use runner::JobRunner;
+
+use anyhow::{Result, anyhow};
+use rusqlite::{OptionalExtension, Row, params};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::agent::get_state_dir;
+use crate::db::SqlitePool;
+
+/// A job interrupted this many times is given up on instead of requeued
+const MAX_ATTEMPTS: i64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Done => "done",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "queued" => Some(JobStatus::Queued),
+            "running" => Some(JobStatus::Running),
+            "done" => Some(JobStatus::Done),
+            "failed" => Some(JobStatus::Failed),
+            "cancelled" => Some(JobStatus::Cancelled),
+            _ => None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Done | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: i64,
+    pub title: String,
+    /// Full instructions for the job agent
+    pub prompt: String,
+    pub status: JobStatus,
+    /// Latest progress note from the job agent
+    pub progress: Option<String>,
+    pub result: Option<String>,
+    pub error: Option<String>,
+    /// Discord channel to report to (None = only visible through job_status)
+    pub channel_id: Option<String>,
+    /// The status message being edited in `channel_id`
+    pub message_id: Option<String>,
+    /// Times the job was started (more than one after a restart)
+    pub attempts: i64,
+    pub created_at: i64,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+}
+
+#[derive(Clone)]
+pub struct JobStore {
+    pool: SqlitePool,
+}
+
+impl JobStore {
+    /// Open the shared job database at `~/.localgpt/jobs.sqlite`
+    pub fn open_default() -> Result<Self> {
+        Self::open(&get_state_dir()?.join("jobs.sqlite"))
+    }
+
+    pub fn open(db_path: &Path) -> Result<Self> {
+        Self::init(SqlitePool::shared(db_path, None)?)
+    }
+
+    /// In-memory store (tests)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(SqlitePool::open_in_memory(None)?)
+    }
+
+    fn init(pool: SqlitePool) -> Result<Self> {
+        pool.get()?.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                title TEXT NOT NULL,
+                prompt TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'queued',
+                progress TEXT,
+                result TEXT,
+                error TEXT,
+                channel_id TEXT,
+                message_id TEXT,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL,
+                started_at INTEGER,
+                finished_at INTEGER
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_jobs_status ON jobs(status);
+            "#,
+        )?;
+
+        Ok(Self { pool })
+    }
+
+    pub fn create(&self, title: &str, prompt: &str, channel_id: Option<&str>) -> Result<Job> {
+        let title = title.trim();
+        if title.is_empty() || prompt.trim().is_empty() {
+            return Err(anyhow!("A job needs a title and a prompt"));
+        }
+
+        let id = {
+            let conn = self.pool.get()?;
+            conn.execute(
+                "INSERT INTO jobs (title, prompt, status, channel_id, created_at)
+                 VALUES (?1, ?2, 'queued', ?3, ?4)",
+                params![title, prompt, channel_id, chrono::Utc::now().timestamp()],
+            )?;
+            conn.last_insert_rowid()
+        };
+
+        self.get(id)?
+            .ok_or_else(|| anyhow!("Job {} disappeared after insert", id))
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<Job>> {
+        let conn = self.pool.get()?;
+        let job = conn
+            .query_row(
+                &format!("SELECT {} FROM jobs WHERE id = ?1", COLUMNS),
+                params![id],
+                row_to_job,
+            )
+            .optional()?;
+        Ok(job)
+    }
+
+    /// The most recent jobs, newest first
+    pub fn recent(&self, limit: usize) -> Result<Vec<Job>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM jobs ORDER BY id DESC LIMIT ?1",
+            COLUMNS
+        ))?;
+        let jobs = stmt
+            .query_map(params![limit as i64], row_to_job)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(jobs)
+    }
+
+    /// Queued and running jobs, oldest first
+    pub fn active(&self) -> Result<Vec<Job>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM jobs WHERE status IN ('queued', 'running') ORDER BY id",
+            COLUMNS
+        ))?;
+        let jobs = stmt
+            .query_map([], row_to_job)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(jobs)
+    }
+
+    /// Mark the oldest queued job as running and return it
+    pub fn claim_next(&self) -> Result<Option<Job>> {
+        let id = {
+            let conn = self.pool.get()?;
+            let next: Option<i64> = conn
+                .query_row(
+                    "SELECT id FROM jobs WHERE status = 'queued' ORDER BY id LIMIT 1",
+                    [],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(id) = next else {
+                return Ok(None);
+            };
+            // Guarded by status in case another runner got there first
+            let claimed = conn.execute(
+                "UPDATE jobs SET status = 'running', attempts = attempts + 1, started_at = ?1
+                 WHERE id = ?2 AND status = 'queued'",
+                params![chrono::Utc::now().timestamp(), id],
+            )?;
+            if claimed == 0 {
+                return Ok(None);
+            }
+            id
+        };
+        self.get(id)
+    }
+
+    pub fn set_progress(&self, id: i64, progress: &str) -> Result<()> {
+        self.pool.get()?.execute(
+            "UPDATE jobs SET progress = ?1 WHERE id = ?2 AND status = 'running'",
+            params![progress, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_message_id(&self, id: i64, message_id: &str) -> Result<()> {
+        self.pool.get()?.execute(
+            "UPDATE jobs SET message_id = ?1 WHERE id = ?2",
+            params![message_id, id],
+        )?;
+        Ok(())
+    }
+
+    /// Record how a running job ended. A job cancelled meanwhile stays cancelled.
+    pub fn finish(&self, id: i64, outcome: std::result::Result<String, String>) -> Result<Job> {
+        let (status, result, error) = match outcome {
+            Ok(result) => (JobStatus::Done, Some(result), None),
+            Err(error) => (JobStatus::Failed, None, Some(error)),
+        };
+        self.pool.get()?.execute(
+            "UPDATE jobs SET status = ?1, result = ?2, error = ?3, finished_at = ?4
+             WHERE id = ?5 AND status = 'running'",
+            params![
+                status.as_str(),
+                result,
+                error,
+                chrono::Utc::now().timestamp(),
+                id
+            ],
+        )?;
+        self.get(id)?.ok_or_else(|| anyhow!("Job {} not found", id))
+    }
+
+    /// Cancel a queued or running job (the runner stops a running one)
+    pub fn cancel(&self, id: i64) -> Result<Job> {
+        let job = self
+            .get(id)?
+            .ok_or_else(|| anyhow!("Job {} not found", id))?;
+        if job.status.is_finished() {
+            return Err(anyhow!("Job {} is already {}", id, job.status.as_str()));
+        }
+        self.pool.get()?.execute(
+            "UPDATE jobs SET status = 'cancelled', finished_at = ?1
+             WHERE id = ?2 AND status IN ('queued', 'running')",
+            params![chrono::Utc::now().timestamp(), id],
+        )?;
+        self.get(id)?.ok_or_else(|| anyhow!("Job {} not found", id))
+    }
+
+    /// Requeue jobs left running by a previous process, giving up on those
+    /// interrupted too often. Returns the requeued jobs.
+    pub fn recover_interrupted(&self) -> Result<Vec<Job>> {
+        {
+            let conn = self.pool.get()?;
+            conn.execute(
+                "UPDATE jobs SET status = 'failed', finished_at = ?1,
+                                 error = 'Interrupted too many times'
+                 WHERE status = 'running' AND attempts >= ?2",
+                params![chrono::Utc::now().timestamp(), MAX_ATTEMPTS],
+            )?;
+        }
+
+        let interrupted: Vec<Job> = {
+            let conn = self.pool.get()?;
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM jobs WHERE status = 'running' ORDER BY id",
+                COLUMNS
+            ))?;
+            stmt.query_map([], row_to_job)?
+                .collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        self.pool.get()?.execute(
+            "UPDATE jobs SET status = 'queued', progress = NULL WHERE status = 'running'",
+            [],
+        )?;
+        Ok(interrupted)
+    }
+}
+
+const COLUMNS: &str = "id, title, prompt, status, progress, result, error, channel_id, \
+                       message_id, attempts, created_at, started_at, finished_at";
+
+fn row_to_job(row: &Row) -> rusqlite::Result<Job> {
+    let status: String = row.get(3)?;
+    Ok(Job {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        prompt: row.get(2)?,
+        status: JobStatus::parse(&status).unwrap_or(JobStatus::Failed),
+        progress: row.get(4)?,
+        result: row.get(5)?,
+        error: row.get(6)?,
+        channel_id: row.get(7)?,
+        message_id: row.get(8)?,
+        attempts: row.get(9)?,
+        created_at: row.get(10)?,
+        started_at: row.get(11)?,
+        finished_at: row.get(12)?,
+    })
+}
+
+/// The Discord channel the current turn came from, shared between an agent
+/// and its `job_start` tool so jobs report back where they were asked for
+#[derive(Clone, Default)]
+pub struct JobOrigin(Arc<Mutex<Option<String>>>);
+
+impl JobOrigin {
+    pub fn set(&self, channel_id: Option<String>) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = channel_id;
+    }
+
+    pub fn get(&self) -> Option<String> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// One line per job for tool output
+pub fn format_job_list(jobs: &[Job]) -> String {
+    jobs.iter()
+        .map(|job| {
+            let mut line = format!("- #{} [{}] {}", job.id, job.status.as_str(), job.title);
+            if job.status == JobStatus::Running
+                && let Some(ref progress) = job.progress
+            {
+                line.push_str(&format!(" — {}", progress));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Elapsed time as `42s`, `5m` or `1h 20m`
+pub fn format_elapsed(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    if seconds < 60 {
+        format!("{}s", seconds)
+    } else if seconds < 3600 {
+        format!("{}m", seconds / 60)
+    } else {
+        format!("{}h {}m", seconds / 3600, seconds % 3600 / 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_lifecycle() {
+        let store = JobStore::open_in_memory().unwrap();
+        let job = store
+            .create("Summarize notes", "Summarize memory/*.md", Some("123"))
+            .unwrap();
+        assert_eq!(job.status, JobStatus::Queued);
+
+        let claimed = store.claim_next().unwrap().unwrap();
+        assert_eq!(claimed.id, job.id);
+        assert_eq!(claimed.status, JobStatus::Running);
+        assert_eq!(claimed.attempts, 1);
+        assert!(store.claim_next().unwrap().is_none());
+
+        store.set_progress(job.id, "3 of 10 files").unwrap();
+        let done = store.finish(job.id, Ok("All done".to_string())).unwrap();
+        assert_eq!(done.status, JobStatus::Done);
+        assert_eq!(done.result.as_deref(), Some("All done"));
+        assert_eq!(done.progress.as_deref(), Some("3 of 10 files"));
+        assert!(store.cancel(job.id).is_err());
+    }
+
+    #[test]
+    fn test_cancelled_job_stays_cancelled() {
+        let store = JobStore::open_in_memory().unwrap();
+        let job = store.create("Ingest", "Ingest ~/inbox", None).unwrap();
+        store.claim_next().unwrap();
+        store.cancel(job.id).unwrap();
+
+        let finished = store.finish(job.id, Ok("late".to_string())).unwrap();
+        assert_eq!(finished.status, JobStatus::Cancelled);
+        assert!(finished.result.is_none());
+    }
+
+    #[test]
+    fn test_recover_interrupted() {
+        let store = JobStore::open_in_memory().unwrap();
+        let job = store.create("Long one", "Do it", None).unwrap();
+
+        for _ in 1..MAX_ATTEMPTS {
+            store.claim_next().unwrap().unwrap();
+            assert_eq!(store.recover_interrupted().unwrap().len(), 1);
+            let requeued = store.get(job.id).unwrap().unwrap();
+            assert_eq!(requeued.status, JobStatus::Queued);
+        }
+
+        store.claim_next().unwrap().unwrap();
+        assert!(store.recover_interrupted().unwrap().is_empty());
+        let given_up = store.get(job.id).unwrap().unwrap();
+        assert_eq!(given_up.status, JobStatus::Failed);
+        assert!(store.claim_next().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(42), "42s");
+        assert_eq!(format_elapsed(300), "5m");
+        assert_eq!(format_elapsed(4800), "1h 20m");
+    }
+}
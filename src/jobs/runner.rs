@@ -0,0 +1,330 @@
+//! Executes queued jobs inside the daemon
+
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+
+use super::{Job, JobStatus, JobStore, format_elapsed};
+use crate::agent::{Agent, AgentConfig, JobProgressTool};
+use crate::config::{Config, parse_duration};
+use crate::discord::outbox::send_or_save;
+use crate::discord::rest::DiscordRest;
+use crate::memory::MemoryManager;
+
+/// How long an empty queue waits before looking again
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often a running job's status message is refreshed (it is only
+/// edited when the text changed)
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Longest error text shown in a status message
+const MAX_ERROR_CHARS: usize = 300;
+
+pub struct JobRunner {
+    config: Config,
+    store: JobStore,
+    /// Shared by every job agent to avoid reloading the embedding provider
+    memory: MemoryManager,
+    /// None when Discord is not configured (jobs are still run)
+    rest: Option<Arc<dyn DiscordRest>>,
+    timeout: Duration,
+}
+
+impl JobRunner {
+    pub fn new(config: &Config, agent_id: &str) -> Result<Self> {
+        let timeout = parse_duration(&config.jobs.timeout)
+            .map_err(|e| anyhow::anyhow!("Invalid jobs timeout: {}", e))?;
+
+        let rest = match config.channels.discord {
+            Some(_) => match crate::discord::rest_client(config) {
+                Ok(rest) => Some(rest),
+                Err(e) => {
+                    warn!("Job progress will not be posted to Discord: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        Ok(Self {
+            config: config.clone(),
+            store: JobStore::open_default()?,
+            memory: MemoryManager::new_with_full_config(&config.memory, Some(config), agent_id)?,
+            rest,
+            timeout,
+        })
+    }
+
+    /// Requeue jobs interrupted by the last shutdown, then run queued jobs
+    /// as they arrive, at most `jobs.max_concurrent` at a time and only
+    /// while the machine isn't busy
+    pub async fn run(self) -> Result<()> {
+        for job in self.store.recover_interrupted()? {
+            info!("Requeued job #{} ({}) after a restart", job.id, job.title);
+        }
+
+        let slots = Arc::new(Semaphore::new(self.config.jobs.max_concurrent.max(1)));
+        let runner = Arc::new(self);
+
+        let mut holding = false;
+        loop {
+            let permit = Arc::clone(&slots).acquire_owned().await?;
+
+            // Queued jobs wait while the machine is busy with something else
+            if let Some(reason) = crate::monitor::busy_reason(&runner.config) {
+                if !holding {
+                    info!("Machine busy ({}), queued jobs will wait", reason);
+                    holding = true;
+                }
+                drop(permit);
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            } else if holding {
+                info!("Machine no longer busy, running queued jobs");
+                holding = false;
+            }
+
+            match runner.store.claim_next() {
+                Ok(Some(job)) => {
+                    let runner = Arc::clone(&runner);
+                    tokio::spawn(async move {
+                        runner.run_job(job).await;
+                        drop(permit);
+                    });
+                }
+                Ok(None) => {
+                    drop(permit);
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+                Err(e) => {
+                    drop(permit);
+                    warn!("Failed to read the job queue: {}", e);
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+            }
+        }
+    }
+
+    async fn run_job(&self, job: Job) {
+        info!("Starting job #{}: {}", job.id, job.title);
+        let started = Instant::now();
+        let message_id = self.open_status_message(&job).await;
+
+        let mut task = tokio::spawn(execute(
+            self.config.clone(),
+            self.memory.clone(),
+            self.store.clone(),
+            job.clone(),
+        ));
+        let mut ticker = tokio::time::interval(PROGRESS_INTERVAL);
+        let mut shown = status_message(&job, Duration::ZERO);
+
+        let outcome = loop {
+            tokio::select! {
+                joined = &mut task => break match joined {
+                    Ok(Ok(result)) => Ok(result),
+                    Ok(Err(e)) => Err(e.to_string()),
+                    Err(e) => Err(format!("Job task panicked: {}", e)),
+                },
+                _ = ticker.tick() => {
+                    if started.elapsed() >= self.timeout {
+                        task.abort();
+                        break Err(format!("Timed out after {}", self.config.jobs.timeout));
+                    }
+                    let Ok(Some(current)) = self.store.get(job.id) else {
+                        continue;
+                    };
+                    if current.status == JobStatus::Cancelled {
+                        task.abort();
+                        break Err("Cancelled".to_string());
+                    }
+                    let text = status_message(&current, started.elapsed());
+                    if text != shown {
+                        self.update_status_message(&job, message_id.as_deref(), &text)
+                            .await;
+                        shown = text;
+                    }
+                }
+            }
+        };
+
+        let finished = match self.store.finish(job.id, outcome) {
+            Ok(finished) => finished,
+            Err(e) => {
+                warn!("Failed to record the end of job #{}: {}", job.id, e);
+                return;
+            }
+        };
+        info!(
+            "Job #{} {} after {}",
+            job.id,
+            finished.status.as_str(),
+            format_elapsed(started.elapsed().as_secs() as i64)
+        );
+        if finished.status == JobStatus::Failed
+            && let Some(ref error) = finished.error
+        {
+            crate::digest::report("jobs", &format!("job #{}", job.id), error);
+        }
+        self.report(&finished, message_id.as_deref(), started.elapsed())
+            .await;
+    }
+
+    /// Post the job's status message, or reuse the one from before a restart
+    async fn open_status_message(&self, job: &Job) -> Option<String> {
+        let (Some(rest), Some(channel_id)) = (&self.rest, &job.channel_id) else {
+            return None;
+        };
+        let text = status_message(job, Duration::ZERO);
+
+        if let Some(ref message_id) = job.message_id {
+            self.update_status_message(job, Some(message_id), &text)
+                .await;
+            return Some(message_id.clone());
+        }
+
+        match rest.post_message(channel_id, &text).await {
+            Ok(message_id) => {
+                if let Err(e) = self.store.set_message_id(job.id, &message_id) {
+                    warn!("Failed to save status message of job #{}: {}", job.id, e);
+                }
+                Some(message_id)
+            }
+            Err(e) => {
+                warn!("Failed to post status of job #{}: {}", job.id, e);
+                None
+            }
+        }
+    }
+
+    async fn update_status_message(&self, job: &Job, message_id: Option<&str>, text: &str) {
+        let (Some(rest), Some(channel_id), Some(message_id)) =
+            (&self.rest, &job.channel_id, message_id)
+        else {
+            return;
+        };
+        if let Err(e) = rest.edit_message(channel_id, message_id, text).await {
+            warn!("Failed to update status of job #{}: {}", job.id, e);
+        }
+    }
+
+    /// Show the final status and deliver the result to the job's channel
+    async fn report(&self, job: &Job, message_id: Option<&str>, elapsed: Duration) {
+        let (Some(rest), Some(channel_id)) = (&self.rest, &job.channel_id) else {
+            return;
+        };
+        let status = status_message(job, elapsed);
+
+        let delivery = match (message_id, job.result.as_deref()) {
+            (Some(_), Some(result)) => {
+                self.update_status_message(job, message_id, &status).await;
+                format!("**Job #{} result: {}**\n\n{}", job.id, job.title, result)
+            }
+            (Some(_), None) => {
+                self.update_status_message(job, message_id, &status).await;
+                return;
+            }
+            (None, Some(result)) => format!("{}\n\n{}", status, result),
+            (None, None) => status,
+        };
+
+        if let Err(e) = send_or_save(rest.as_ref(), channel_id, &delivery, None).await {
+            warn!("Failed to deliver the result of job #{}: {}", job.id, e);
+        }
+    }
+}
+
+/// Run the job on a fresh agent and return its final answer
+async fn execute(
+    config: Config,
+    memory: MemoryManager,
+    store: JobStore,
+    job: Job,
+) -> Result<String> {
+    let agent_config = AgentConfig {
+        model: config.agent.default_model.clone(),
+        context_window: config.agent.context_window,
+        reserve_tokens: config.agent.reserve_tokens,
+    };
+    let mut agent = Agent::new(agent_config, &config, memory).await?;
+    agent.new_session().await?;
+
+    // Jobs report progress, but don't start more jobs
+    agent.set_denied_tools(vec!["job_start".to_string()]);
+    agent.add_tool(Box::new(JobProgressTool::new(store, job.id)));
+
+    agent.chat(&job_prompt(&job)).await
+}
+
+fn job_prompt(job: &Job) -> String {
+    let mut prompt = format!(
+        "You are running background job #{} (\"{}\"). Nobody is watching this conversation, \
+         so don't ask questions: make reasonable choices and mention them in your answer. \
+         After each major step, call job_progress with a one-line note. Your final reply is \
+         delivered to the user as the job's result.",
+        job.id, job.title
+    );
+    if job.attempts > 1 {
+        prompt.push_str(
+            " The job was interrupted by a restart before; partial work from the earlier \
+             attempt may already be in the workspace.",
+        );
+    }
+    format!("{}\n\n{}", prompt, job.prompt)
+}
+
+/// The text of a job's Discord status message
+fn status_message(job: &Job, elapsed: Duration) -> String {
+    let elapsed = format_elapsed(elapsed.as_secs() as i64);
+    let head = format!("**Job #{}: {}**", job.id, job.title);
+    match job.status {
+        JobStatus::Queued | JobStatus::Running => {
+            let mut text = format!("⏳ {} — running for {}", head, elapsed);
+            if let Some(ref progress) = job.progress {
+                text.push_str(&format!("\n> {}", progress));
+            }
+            text
+        }
+        JobStatus::Done => format!("✅ {} — done in {}", head, elapsed),
+        JobStatus::Failed => {
+            let error = job.error.as_deref().unwrap_or("unknown error");
+            format!(
+                "❌ {} — failed after {}: {}",
+                head,
+                elapsed,
+                crate::utils::safe_truncate(error, MAX_ERROR_CHARS)
+            )
+        }
+        JobStatus::Cancelled => format!("🚫 {} — cancelled after {}", head, elapsed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_message() {
+        let store = JobStore::open_in_memory().unwrap();
+        store
+            .create("Ingest inbox", "Ingest ~/inbox", None)
+            .unwrap();
+        let job = store.claim_next().unwrap().unwrap();
+        store.set_progress(job.id, "12 of 40 files").unwrap();
+
+        let running = store.get(job.id).unwrap().unwrap();
+        assert_eq!(
+            status_message(&running, Duration::from_secs(150)),
+            "⏳ **Job #1: Ingest inbox** — running for 2m\n> 12 of 40 files"
+        );
+
+        let failed = store.finish(job.id, Err("disk full".to_string())).unwrap();
+        assert_eq!(
+            status_message(&failed, Duration::from_secs(150)),
+            "❌ **Job #1: Ingest inbox** — failed after 2m: disk full"
+        );
+    }
+}
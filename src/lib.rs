@@ -3,22 +3,41 @@
 //! This crate provides the core functionality for LocalGPT, including:
 //! - Agent core with LLM provider abstraction
 //! - Memory system with markdown files and SQLite index
+//! - Pooled SQLite connections (WAL) shared by all stores
 //! - Heartbeat runner for continuous operation
+//! - Calendar integration (iCalendar feeds and CalDAV)
+//! - Background jobs run by the daemon, with progress posted to Discord
+//! - Error digests instead of per-failure notifications
+//! - System resource monitor that holds back background work under load
+//! - Health checks of model providers and the Discord API
+//! - Runtime feature toggles
+//! - Structured JSON logs per subsystem with a runtime-adjustable level
+//! - Reconnecting WebSocket client shared by integrations
 //! - HTTP server for UI integration
 //! - Desktop GUI (egui-based)
 
 pub mod agent;
+pub mod calendar;
 pub mod commands;
 pub mod concurrency;
 pub mod config;
+pub mod db;
 #[cfg(feature = "desktop")]
 pub mod desktop;
+pub mod digest;
 pub mod discord;
+pub mod features;
+pub mod health;
 pub mod heartbeat;
+pub mod jobs;
+pub mod logging;
 pub mod memory;
+pub mod monitor;
 pub mod sandbox;
 pub mod security;
 pub mod server;
+pub mod tasks;
 pub mod utils;
+pub mod ws;
 
 pub use config::Config;
@@ -0,0 +1,291 @@
+//! Logging setup
+//!
+//! Besides the console (or the daemon's log file), `[logging] json = true`
+//! writes structured JSON logs to `logs/json/` in the state directory, one
+//! file per subsystem and day (`discord-2026-01-31.jsonl`). A file that
+//! grows past `max_file_mb` continues in a numbered part
+//! (`discord-2026-01-31.1.jsonl`), and files older than `retention_days`
+//! are deleted. The level filter can be changed at runtime, e.g. through
+//! `PUT /api/logging`, without restarting the daemon. Every output masks
+//! secrets first (see [`crate::security::redact`]).
+
+use anyhow::Result;
+use chrono::{Local, NaiveDate};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Layered, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, Registry, fmt, reload};
+
+use crate::config::LoggingConfig;
+
+/// Modules with their own JSON log file; everything else goes to `localgpt`
+const SUBSYSTEMS: &[&str] = &["agent", "discord", "heartbeat", "jobs", "memory", "server"];
+
+/// File for events that belong to no subsystem
+const GENERAL: &str = "localgpt";
+
+type Layers = Vec<Box<dyn Layer<Registry> + Send + Sync>>;
+
+static FILTER: OnceLock<reload::Handle<EnvFilter, Layered<Layers, Registry>>> = OnceLock::new();
+
+/// Install the global subscriber: `filter` applies to every output and can
+/// be replaced later with [`set_filter`]
+pub fn init(filter: EnvFilter, ansi: bool, config: Option<&LoggingConfig>) {
+    let mut layers: Layers = vec![
+        fmt::layer()
+            .with_ansi(ansi)
+            .with_writer(Redacting(io::stdout))
+            .boxed(),
+    ];
+
+    if let Some(config) = config.filter(|c| c.json) {
+        match json_dir() {
+            Ok(dir) => {
+                for name in SUBSYSTEMS.iter().copied().chain([GENERAL]) {
+                    let writer = Arc::new(RollingFile::new(
+                        &dir,
+                        name,
+                        config.max_file_mb * 1024 * 1024,
+                        config.retention_days,
+                    ));
+                    layers.push(
+                        fmt::layer()
+                            .json()
+                            .with_ansi(false)
+                            .with_writer(Redacting(writer))
+                            .with_filter(filter_fn(move |meta| subsystem(meta.target()) == name))
+                            .boxed(),
+                    );
+                }
+            }
+            Err(e) => eprintln!("JSON logs disabled: {}", e),
+        }
+    }
+
+    let (filter, handle) = reload::Layer::new(filter);
+    if Registry::default()
+        .with(layers)
+        .with(filter)
+        .try_init()
+        .is_ok()
+    {
+        let _ = FILTER.set(handle);
+    }
+}
+
+/// The active filter directives, if logging was set up with [`init`]
+pub fn current_filter() -> Option<String> {
+    FILTER.get()?.with_current(|f| f.to_string()).ok()
+}
+
+/// Replace the filter, e.g. `info,localgpt::discord=debug`
+pub fn set_filter(directives: &str) -> Result<()> {
+    let filter = EnvFilter::try_new(directives)?;
+    FILTER
+        .get()
+        .ok_or_else(|| anyhow::anyhow!("Logging is not adjustable in this process"))?
+        .reload(filter)?;
+    Ok(())
+}
+
+/// Directory of the JSON logs
+pub fn json_dir() -> Result<PathBuf> {
+    Ok(crate::agent::get_state_dir()?.join("logs").join("json"))
+}
+
+/// The file an event target is logged to
+fn subsystem(target: &str) -> &'static str {
+    let module = target
+        .strip_prefix("localgpt::")
+        .and_then(|rest| rest.split("::").next());
+    module
+        .and_then(|m| SUBSYSTEMS.iter().find(|s| **s == m).copied())
+        .unwrap_or(GENERAL)
+}
+
+/// Passes each formatted event through [`crate::security::redact`]
+struct Redacting<M>(M);
+
+struct RedactingWriter<W>(W);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Redacting<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter(self.0.make_writer())
+    }
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        self.0
+            .write_all(crate::security::redact("log", "", &text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// A log file rolled over daily and when it reaches `max_bytes`
+struct RollingFile {
+    dir: PathBuf,
+    name: &'static str,
+    max_bytes: u64,
+    retention_days: u32,
+    current: Mutex<Option<OpenFile>>,
+}
+
+struct OpenFile {
+    date: NaiveDate,
+    part: u32,
+    file: File,
+    size: u64,
+}
+
+impl RollingFile {
+    fn new(dir: &Path, name: &'static str, max_bytes: u64, retention_days: u32) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            name,
+            max_bytes,
+            retention_days,
+            current: Mutex::new(None),
+        }
+    }
+
+    fn path(&self, date: NaiveDate, part: u32) -> PathBuf {
+        let date = date.format("%Y-%m-%d");
+        if part == 0 {
+            self.dir.join(format!("{}-{}.jsonl", self.name, date))
+        } else {
+            self.dir
+                .join(format!("{}-{}.{}.jsonl", self.name, date, part))
+        }
+    }
+
+    /// Open the day's latest part, or the next one if it is full
+    fn open(&self, date: NaiveDate, mut part: u32) -> io::Result<OpenFile> {
+        fs::create_dir_all(&self.dir)?;
+        while self.path(date, part + 1).exists() {
+            part += 1;
+        }
+        loop {
+            let path = self.path(date, part);
+            let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            if size < self.max_bytes || self.max_bytes == 0 {
+                let file = OpenOptions::new().create(true).append(true).open(&path)?;
+                return Ok(OpenFile {
+                    date,
+                    part,
+                    file,
+                    size,
+                });
+            }
+            part += 1;
+        }
+    }
+
+    /// Delete this subsystem's files from before the retention period
+    fn prune(&self, today: NaiveDate) {
+        if self.retention_days == 0 {
+            return;
+        }
+        let cutoff = (today - chrono::Duration::days(self.retention_days as i64))
+            .format("%Y-%m-%d")
+            .to_string();
+        let prefix = format!("{}-", self.name);
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if let Some(date) = name.strip_prefix(&prefix).and_then(|rest| rest.get(..10))
+                && date < cutoff.as_str()
+            {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+}
+
+impl Write for &RollingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        let today = Local::now().date_naive();
+
+        let open = match current.take() {
+            Some(open) if open.date != today => {
+                self.prune(today);
+                self.open(today, 0)?
+            }
+            Some(open) if self.max_bytes > 0 && open.size >= self.max_bytes => {
+                self.open(today, open.part + 1)?
+            }
+            Some(open) => open,
+            None => {
+                self.prune(today);
+                self.open(today, 0)?
+            }
+        };
+        let open = current.insert(open);
+        open.file.write_all(buf)?;
+        open.size += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self
+            .current
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+        {
+            Some(open) => open.file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_subsystem() {
+        assert_eq!(subsystem("localgpt::discord::gateway"), "discord");
+        assert_eq!(subsystem("localgpt::agent"), "agent");
+        assert_eq!(subsystem("localgpt::cli::daemon"), GENERAL);
+        assert_eq!(subsystem("hyper::proto"), GENERAL);
+    }
+
+    #[test]
+    fn test_rolls_over_at_size_cap() {
+        let dir = TempDir::new().unwrap();
+        let log = RollingFile::new(dir.path(), "discord", 10, 0);
+        let mut writer = &log;
+        writer.write_all(b"{\"n\":1}\n").unwrap();
+        writer.write_all(b"{\"n\":2}\n").unwrap();
+        writer.write_all(b"{\"n\":3}\n").unwrap();
+
+        let today = Local::now().date_naive();
+        let first = fs::read_to_string(log.path(today, 0)).unwrap();
+        let second = fs::read_to_string(log.path(today, 1)).unwrap();
+        assert_eq!(first, "{\"n\":1}\n{\"n\":2}\n");
+        assert_eq!(second, "{\"n\":3}\n");
+
+        // A restart continues in the latest part
+        let log = RollingFile::new(dir.path(), "discord", 10, 0);
+        (&log).write_all(b"{\"n\":4}\n").unwrap();
+        assert!(!log.path(today, 2).exists());
+    }
+}
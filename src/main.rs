@@ -44,14 +44,22 @@ fn main() -> Result<()> {
 async fn async_main(cli: Cli) -> Result<()> {
     // Initialize logging
     let log_level = if cli.verbose { "debug" } else { "info" };
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(log_level)),
-        )
-        .init();
+    // A daemon in the foreground also writes the JSON logs
+    let logging = match cli.command {
+        Commands::Daemon(ref args) if args.runs_daemon() => {
+            localgpt::Config::load().ok().map(|c| c.logging)
+        }
+        _ => None,
+    };
+    localgpt::logging::init(
+        tracing_subscriber::EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(log_level)),
+        true,
+        logging.as_ref(),
+    );
 
     match cli.command {
+        Commands::Init(args) => cli::init::run(args).await,
         Commands::Chat(args) => cli::chat::run(args, &cli.agent).await,
         Commands::Ask(args) => cli::ask::run(args, &cli.agent).await,
         #[cfg(feature = "desktop")]
@@ -61,5 +69,7 @@ async fn async_main(cli: Cli) -> Result<()> {
         Commands::Config(args) => cli::config::run(args).await,
         Commands::Md(args) => cli::md::run(args).await,
         Commands::Sandbox(args) => cli::sandbox::run(args).await,
+        Commands::Service(args) => cli::service::run(args, &cli.agent).await,
+        Commands::Experiment(args) => cli::experiment::run(args).await,
     }
 }
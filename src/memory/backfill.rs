@@ -0,0 +1,180 @@
+//! Background embedding backfill
+//!
+//! When the daemon starts, chunks that have no embedding yet (e.g. the
+//! archive indexed before semantic search was enabled) are embedded in the
+//! background, `batch_size` chunks per request and at most
+//! `batches_per_minute` requests, so chat never waits on it. Progress is
+//! stored per model in the index database and picked up again after a
+//! restart; failed requests back off exponentially.
+
+use serde::Serialize;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use super::MemoryManager;
+
+/// Longest wait after repeated failures
+const MAX_BACKOFF: Duration = Duration::from_secs(600);
+
+/// Give up after this many failed batches in a row
+const MAX_CONSECUTIVE_FAILURES: usize = 8;
+
+/// Saved backfill progress (one row per embedding model)
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct BackfillState {
+    pub model: String,
+    /// "running", "complete" or "failed"
+    pub status: String,
+    /// Chunks embedded by the backfill so far (across restarts)
+    pub embedded: usize,
+    pub failed_batches: usize,
+    pub last_error: Option<String>,
+    pub started_at: i64,
+    pub updated_at: i64,
+}
+
+/// Backfill progress for display (HTTP API, CLI)
+#[derive(Debug, Clone, Serialize)]
+pub struct BackfillProgress {
+    pub model: String,
+    /// "idle" (never ran), "running", "complete" or "failed"
+    pub status: String,
+    pub total_chunks: usize,
+    /// Chunks that have an embedding for this model
+    pub embedded_chunks: usize,
+    /// Chunks embedded by the backfill worker
+    pub backfilled: usize,
+    pub failed_batches: usize,
+    pub last_error: Option<String>,
+    pub started_at: Option<i64>,
+    pub updated_at: Option<i64>,
+}
+
+impl MemoryManager {
+    /// Current backfill progress for the configured embedding model
+    pub fn embedding_backfill_progress(&self) -> anyhow::Result<BackfillProgress> {
+        let model = self
+            .embedding_provider
+            .as_ref()
+            .map(|p| p.model().to_string())
+            .unwrap_or_default();
+        let state = self.index.load_backfill_state(&model)?;
+
+        Ok(BackfillProgress {
+            total_chunks: self.index.chunk_count()?,
+            embedded_chunks: self.index.embedded_chunk_count(&model)?,
+            status: state
+                .as_ref()
+                .map_or_else(|| "idle".to_string(), |s| s.status.clone()),
+            backfilled: state.as_ref().map_or(0, |s| s.embedded),
+            failed_batches: state.as_ref().map_or(0, |s| s.failed_batches),
+            last_error: state.as_ref().and_then(|s| s.last_error.clone()),
+            started_at: state.as_ref().map(|s| s.started_at),
+            updated_at: state.as_ref().map(|s| s.updated_at),
+            model,
+        })
+    }
+
+    /// Start the backfill worker. Returns None if there is no embedding
+    /// provider or backfill is disabled.
+    pub fn spawn_embedding_backfill(&self) -> Option<JoinHandle<()>> {
+        if !self.config.backfill.enabled || self.embedding_provider.is_none() {
+            return None;
+        }
+        let memory = self.clone();
+        Some(tokio::spawn(async move {
+            if let Err(e) = memory.run_backfill().await {
+                warn!("Embedding backfill stopped: {}", e);
+            }
+        }))
+    }
+
+    async fn run_backfill(&self) -> anyhow::Result<()> {
+        let Some(provider) = self.embedding_provider.clone() else {
+            return Ok(());
+        };
+        let batch_size = self.config.backfill.batch_size.max(1);
+        let interval = batch_interval(self.config.backfill.batches_per_minute);
+
+        let now = chrono::Utc::now().timestamp();
+        let mut state = match self.index.load_backfill_state(provider.model())? {
+            Some(state) => state,
+            None => BackfillState {
+                model: provider.model().to_string(),
+                status: "running".to_string(),
+                embedded: 0,
+                failed_batches: 0,
+                last_error: None,
+                started_at: now,
+                updated_at: now,
+            },
+        };
+        state.status = "running".to_string();
+
+        let mut backoff = interval;
+        let mut failures = 0;
+        loop {
+            let batch = self.embed_next_batch(provider.as_ref(), batch_size).await?;
+            if batch.fetched == 0 {
+                state.status = "complete".to_string();
+                state.updated_at = chrono::Utc::now().timestamp();
+                self.index.save_backfill_state(&state)?;
+                if state.embedded > 0 {
+                    info!("Embedding backfill complete: {} chunks", state.embedded);
+                }
+                return Ok(());
+            }
+
+            state.embedded += batch.embedded;
+            state.updated_at = chrono::Utc::now().timestamp();
+
+            // Nothing stored: the provider failed, or every store failed
+            let error = batch.error.or_else(|| {
+                (batch.embedded == 0).then(|| "no embeddings could be stored".to_string())
+            });
+            match error {
+                Some(e) => {
+                    failures += 1;
+                    state.failed_batches += 1;
+                    state.last_error = Some(e.clone());
+                    if failures >= MAX_CONSECUTIVE_FAILURES {
+                        state.status = "failed".to_string();
+                        self.index.save_backfill_state(&state)?;
+                        anyhow::bail!("{} failed batches in a row, last: {}", failures, e);
+                    }
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    warn!(
+                        "Embedding backfill batch failed ({}), retrying in {}s",
+                        e,
+                        backoff.as_secs()
+                    );
+                }
+                None => {
+                    failures = 0;
+                    backoff = interval;
+                }
+            }
+
+            self.index.save_backfill_state(&state)?;
+            tokio::time::sleep(backoff).await;
+        }
+    }
+}
+
+/// Delay between batches for a requests-per-minute limit
+fn batch_interval(batches_per_minute: u32) -> Duration {
+    Duration::from_secs(60) / batches_per_minute.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_interval() {
+        assert_eq!(batch_interval(30), Duration::from_secs(2));
+        assert_eq!(batch_interval(0), Duration::from_secs(60));
+        assert_eq!(batch_interval(120), Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,160 @@
+//! Workspace context file watcher
+//!
+//! Watches the files that make up an agent's system context (SOUL.md,
+//! MEMORY.md, AGENTS.md, ...) and broadcasts debounced reload events to every
+//! live `Agent` in the process. One watcher is shared per process, no matter
+//! how many agents (Discord channels, HTTP sessions, desktop) subscribe.
+
+use anyhow::Result;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, mpsc};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+/// Workspace files that are baked into the system context
+pub const CONTEXT_FILES: &[&str] = &[
+    "SOUL.md",
+    "MEMORY.md",
+    "AGENTS.md",
+    "IDENTITY.md",
+    "USER.md",
+    "TOOLS.md",
+];
+
+/// Quiet period before a batch of changes is broadcast
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Context files that changed on disk
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextReloadEvent {
+    /// File names relative to the workspace (e.g. "SOUL.md")
+    pub files: Vec<String>,
+}
+
+static RELOAD_TX: Lazy<broadcast::Sender<ContextReloadEvent>> =
+    Lazy::new(|| broadcast::channel(16).0);
+
+/// The process-wide watcher and the workspace it watches
+static WATCHER: Lazy<Mutex<Option<(PathBuf, RecommendedWatcher)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Subscribe to context reload events
+pub fn subscribe_context_reloads() -> broadcast::Receiver<ContextReloadEvent> {
+    RELOAD_TX.subscribe()
+}
+
+/// Broadcast a reload event to all subscribed agents
+pub fn emit_context_reload(event: ContextReloadEvent) {
+    // Err only means there are no live subscribers
+    let _ = RELOAD_TX.send(event);
+}
+
+/// Start watching the workspace context files (idempotent).
+///
+/// Calling this again for the same workspace is a no-op; a different
+/// workspace replaces the previous watcher.
+pub fn watch_context_files(workspace: &Path) -> Result<()> {
+    let mut guard = WATCHER
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+
+    if let Some((ref watched, _)) = *guard
+        && watched == workspace
+    {
+        return Ok(());
+    }
+
+    let (tx, rx) = mpsc::channel::<String>();
+
+    let mut watcher =
+        notify::recommended_watcher(move |res: Result<Event, notify::Error>| match res {
+            Ok(event) => {
+                if !matches!(
+                    event.kind,
+                    EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_)
+                ) {
+                    return;
+                }
+                for path in event.paths {
+                    if let Some(name) = context_file_name(&path) {
+                        let _ = tx.send(name);
+                    }
+                }
+            }
+            Err(e) => warn!("Context watch error: {:?}", e),
+        })?;
+
+    // Non-recursive: context files live at the workspace root
+    watcher.watch(workspace, RecursiveMode::NonRecursive)?;
+    info!("Watching context files in: {}", workspace.display());
+
+    std::thread::spawn(move || {
+        // Ends when the watcher (and with it the sender) is dropped
+        while let Ok(first) = rx.recv() {
+            let mut files = BTreeSet::from([first]);
+
+            // Debounce: editors often write a file several times in a row
+            let mut last = Instant::now();
+            while let Some(remaining) = DEBOUNCE.checked_sub(last.elapsed()) {
+                match rx.recv_timeout(remaining) {
+                    Ok(name) => {
+                        files.insert(name);
+                        last = Instant::now();
+                    }
+                    Err(mpsc::RecvTimeoutError::Timeout) => break,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                }
+            }
+
+            debug!("Context files changed: {:?}", files);
+            emit_context_reload(ContextReloadEvent {
+                files: files.into_iter().collect(),
+            });
+        }
+    });
+
+    *guard = Some((workspace.to_path_buf(), watcher));
+    Ok(())
+}
+
+/// Return the file name if `path` is one of the watched context files
+/// (including per-language personas such as `SOUL.ja.md`)
+fn context_file_name(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    let localized_soul = name.starts_with("SOUL.") && name.ends_with(".md");
+    (CONTEXT_FILES.contains(&name) || localized_soul).then(|| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_file_name() {
+        assert_eq!(
+            context_file_name(Path::new("/ws/SOUL.md")).as_deref(),
+            Some("SOUL.md")
+        );
+        assert_eq!(
+            context_file_name(Path::new("/ws/memory/2026-01-01.md")),
+            None
+        );
+        assert_eq!(context_file_name(Path::new("/ws/.SOUL.md.swp")), None);
+        assert_eq!(
+            context_file_name(Path::new("/ws/SOUL.ja.md")).as_deref(),
+            Some("SOUL.ja.md")
+        );
+    }
+
+    #[test]
+    fn test_emit_reaches_subscribers() {
+        let mut rx = subscribe_context_reloads();
+        emit_context_reload(ContextReloadEvent {
+            files: vec!["AGENTS.md".to_string()],
+        });
+        assert_eq!(rx.try_recv().unwrap().files, vec!["AGENTS.md"]);
+    }
+}
@@ -0,0 +1,187 @@
+//! Categorized memory entries written with `save_memory`
+//!
+//! | Category | Written to |
+//! |----------|------------|
+//! | fact | `MEMORY.md`, `## Facts` |
+//! | preference | `MEMORY.md`, `## Preferences` |
+//! | task | `MEMORY.md`, `## Tasks` (as an open checkbox) |
+//! | event | today's `Events` topic log |
+//!
+//! Each entry is one list item (or log entry) ending with its provenance:
+//! when it was saved, the channel and the message it came from. Sections
+//! missing from `MEMORY.md` are added at the end of the file.
+
+use anyhow::Result;
+use chrono::Local;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::history::write_memory_file;
+use super::topic_log::LogContext;
+use crate::security::redact;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryCategory {
+    Fact,
+    Preference,
+    Event,
+    Task,
+}
+
+impl MemoryCategory {
+    pub const ALL: [MemoryCategory; 4] = [
+        MemoryCategory::Fact,
+        MemoryCategory::Preference,
+        MemoryCategory::Event,
+        MemoryCategory::Task,
+    ];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "fact" | "facts" => Some(MemoryCategory::Fact),
+            "preference" | "preferences" => Some(MemoryCategory::Preference),
+            "event" | "events" => Some(MemoryCategory::Event),
+            "task" | "tasks" | "todo" => Some(MemoryCategory::Task),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MemoryCategory::Fact => "fact",
+            MemoryCategory::Preference => "preference",
+            MemoryCategory::Event => "event",
+            MemoryCategory::Task => "task",
+        }
+    }
+
+    /// `MEMORY.md` section, or the topic of the log for events
+    pub fn section(self) -> &'static str {
+        match self {
+            MemoryCategory::Fact => "Facts",
+            MemoryCategory::Preference => "Preferences",
+            MemoryCategory::Event => "Events",
+            MemoryCategory::Task => "Tasks",
+        }
+    }
+}
+
+/// Where an entry came from, e.g. `2026-03-01 14:05, discord:42, message 99`
+pub(super) fn provenance(context: &LogContext) -> String {
+    let mut parts = vec![Local::now().format("%Y-%m-%d %H:%M").to_string()];
+    parts.extend(context.channel.clone());
+    parts.extend(
+        context
+            .message_id
+            .as_ref()
+            .map(|id| format!("message {}", id)),
+    );
+    parts.join(", ")
+}
+
+/// Append an entry to the category's section of `MEMORY.md`
+pub(super) fn append_to_memory_file(
+    workspace: &Path,
+    category: MemoryCategory,
+    content: &str,
+    context: &LogContext,
+) -> Result<PathBuf> {
+    let path = workspace.join("MEMORY.md");
+    let content = redact("memory", &path.display().to_string(), content);
+    // One list item: later lines are indented under it
+    let text = content.trim().lines().collect::<Vec<_>>().join("\n  ");
+    let checkbox = if category == MemoryCategory::Task {
+        "[ ] "
+    } else {
+        ""
+    };
+    let item = format!("- {}{} _({})_", checkbox, text, provenance(context));
+
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    write_memory_file(
+        workspace,
+        &path,
+        &insert_into_section(&existing, category.section(), &item),
+        "save_memory",
+    )?;
+    Ok(path)
+}
+
+/// `text` with `item` added at the end of the `## heading` section
+fn insert_into_section(text: &str, heading: &str, item: &str) -> String {
+    let heading_line = format!("## {}", heading);
+    let lines: Vec<&str> = text.lines().collect();
+    let Some(start) = lines.iter().position(|l| l.trim_end() == heading_line) else {
+        let body = text.trim_end();
+        let sep = if body.is_empty() { "" } else { "\n\n" };
+        return format!("{}{}{}\n\n{}\n", body, sep, heading_line, item);
+    };
+
+    // The section ends at the next heading of the same or a higher level
+    let end = lines[start + 1..]
+        .iter()
+        .position(|l| l.starts_with("# ") || l.starts_with("## "))
+        .map_or(lines.len(), |i| start + 1 + i);
+    // After the section's last non-empty line
+    let last = (start + 1..end)
+        .rev()
+        .find(|&i| !lines[i].trim().is_empty() && lines[i].trim() != "---")
+        .unwrap_or(start);
+
+    let mut out: Vec<&str> = lines[..=last].to_vec();
+    if last == start {
+        out.push("");
+    }
+    out.push(item);
+    out.extend_from_slice(&lines[last + 1..]);
+    let mut result = out.join("\n");
+    result.push('\n');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_entries_go_to_their_section() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("MEMORY.md");
+        fs::write(
+            &path,
+            "# MEMORY.md\n\n## Facts\n\n- Lives in Osaka\n\n## Notes\n\nFree text.\n",
+        )
+        .unwrap();
+        let context = LogContext {
+            channel: Some("discord:42".to_string()),
+            message_id: Some("99".to_string()),
+            ..Default::default()
+        };
+
+        append_to_memory_file(dir.path(), MemoryCategory::Fact, "Has a cat", &context).unwrap();
+        append_to_memory_file(
+            dir.path(),
+            MemoryCategory::Task,
+            "Renew passport",
+            &LogContext::default(),
+        )
+        .unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        let facts = text.find("## Facts").unwrap();
+        let cat = text.find("- Has a cat _(").unwrap();
+        let notes = text.find("## Notes").unwrap();
+        assert!(facts < cat && cat < notes);
+        assert!(text.contains("- Lives in Osaka\n- Has a cat _("));
+        assert!(text.contains(", discord:42, message 99)_\n\n## Notes"));
+        assert!(text.contains("Free text.\n\n## Tasks\n\n- [ ] Renew passport _("));
+        assert!(text.ends_with(")_\n"));
+
+        assert_eq!(
+            MemoryCategory::parse("Preferences"),
+            Some(MemoryCategory::Preference)
+        );
+        assert_eq!(MemoryCategory::parse("mood"), None);
+    }
+}
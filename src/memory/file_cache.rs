@@ -0,0 +1,154 @@
+//! Process-wide cache of workspace context files
+//!
+//! SOUL.md, MEMORY.md and the other context files are read on every agent
+//! creation and session start. Contents are kept in a small LRU keyed by
+//! path and shared as `Arc<str>` between agents; an entry is reused only
+//! while the file's modification time and size are unchanged.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+use std::time::SystemTime;
+
+/// Maximum number of cached files
+const CACHE_CAPACITY: usize = 64;
+
+/// Files larger than this are read directly and not cached
+const MAX_CACHED_BYTES: u64 = 1024 * 1024;
+
+static CACHE: LazyLock<Mutex<FileCache>> =
+    LazyLock::new(|| Mutex::new(FileCache::new(CACHE_CAPACITY)));
+
+/// Read a file through the shared cache. Missing files read as empty.
+pub fn read_cached(path: &Path) -> Result<Arc<str>> {
+    CACHE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .read(path)
+}
+
+struct Entry {
+    modified: SystemTime,
+    len: u64,
+    content: Arc<str>,
+    last_used: u64,
+}
+
+struct FileCache {
+    entries: HashMap<PathBuf, Entry>,
+    capacity: usize,
+    /// Monotonic counter for LRU ordering
+    clock: u64,
+}
+
+impl FileCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity,
+            clock: 0,
+        }
+    }
+
+    fn read(&mut self, path: &Path) -> Result<Arc<str>> {
+        let metadata = match fs::metadata(path) {
+            Ok(m) => m,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.entries.remove(path);
+                return Ok(Arc::from(""));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let modified = metadata.modified()?;
+        let len = metadata.len();
+
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(path)
+            && entry.modified == modified
+            && entry.len == len
+        {
+            entry.last_used = self.clock;
+            return Ok(Arc::clone(&entry.content));
+        }
+
+        let content: Arc<str> = Arc::from(fs::read_to_string(path)?);
+        if len > MAX_CACHED_BYTES {
+            self.entries.remove(path);
+            return Ok(content);
+        }
+
+        if self.entries.len() >= self.capacity
+            && !self.entries.contains_key(path)
+            && let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(p, _)| p.clone())
+        {
+            self.entries.remove(&oldest);
+        }
+        self.entries.insert(
+            path.to_path_buf(),
+            Entry {
+                modified,
+                len,
+                content: Arc::clone(&content),
+                last_used: self.clock,
+            },
+        );
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_invalidation_and_sharing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("SOUL.md");
+        let mut cache = FileCache::new(2);
+
+        assert_eq!(&*cache.read(&path).unwrap(), "");
+
+        fs::write(&path, "calm").unwrap();
+        let first = cache.read(&path).unwrap();
+        let second = cache.read(&path).unwrap();
+        assert_eq!(&*first, "calm");
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // Size changes even if the mtime resolution is coarse
+        fs::write(&path, "cheerful").unwrap();
+        assert_eq!(&*cache.read(&path).unwrap(), "cheerful");
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(&*cache.read(&path).unwrap(), "");
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths: Vec<PathBuf> = ["a.md", "b.md", "c.md"]
+            .iter()
+            .map(|name| {
+                let path = dir.path().join(name);
+                fs::write(&path, *name).unwrap();
+                path
+            })
+            .collect();
+        let mut cache = FileCache::new(2);
+
+        cache.read(&paths[0]).unwrap();
+        cache.read(&paths[1]).unwrap();
+        cache.read(&paths[0]).unwrap();
+        cache.read(&paths[2]).unwrap();
+
+        // b.md was least recently used
+        assert!(cache.entries.contains_key(&paths[0]));
+        assert!(!cache.entries.contains_key(&paths[1]));
+        assert!(cache.entries.contains_key(&paths[2]));
+    }
+}
@@ -0,0 +1,385 @@
+//! Version history for memory files
+//!
+//! Every change to a workspace file made by the agent (`write_file`,
+//! `edit_file`, `save_memory`, topic logs, daily notes, session summaries)
+//! is recorded in `memory/history.sqlite` in the state directory, and the
+//! memory watcher
+//! records edits and deletions made by hand or with shell commands. File
+//! contents are stored once per distinct content, keyed by their SHA-256;
+//! a version points to its content, or to none when the file was deleted.
+//!
+//! A version can be shown as a diff against the one before it and restored,
+//! which writes its content back (and is itself recorded, so a restore can
+//! be undone the same way).
+
+use anyhow::{Result, bail};
+use rusqlite::{OptionalExtension, params};
+use serde::Serialize;
+use similar::TextDiff;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tracing::warn;
+
+use super::embeddings::hash_text;
+use crate::db::SqlitePool;
+
+/// One recorded state of a file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MemoryVersion {
+    pub id: i64,
+    /// Relative to the workspace
+    pub path: String,
+    /// SHA-256 of the content; None if the file was deleted
+    pub hash: Option<String>,
+    /// What made the change: a tool name, "watcher", "restore", ...
+    pub source: String,
+    pub created_at: i64,
+}
+
+impl MemoryVersion {
+    pub fn is_deletion(&self) -> bool {
+        self.hash.is_none()
+    }
+}
+
+#[derive(Clone)]
+pub struct MemoryHistory {
+    pool: SqlitePool,
+    workspace: PathBuf,
+}
+
+impl MemoryHistory {
+    /// The history of a workspace, kept in `<state_dir>/memory/history.sqlite`
+    pub fn for_workspace(workspace: &Path) -> Result<Self> {
+        let state_dir = workspace
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("Workspace has no parent directory"))?;
+        Self::open(&state_dir.join("memory").join("history.sqlite"), workspace)
+    }
+
+    pub fn open(db_path: &Path, workspace: &Path) -> Result<Self> {
+        Self::init(SqlitePool::shared(db_path, None)?, workspace)
+    }
+
+    /// In-memory history (tests)
+    pub fn open_in_memory(workspace: &Path) -> Result<Self> {
+        Self::init(SqlitePool::open_in_memory(None)?, workspace)
+    }
+
+    fn init(pool: SqlitePool, workspace: &Path) -> Result<Self> {
+        pool.get()?.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS blobs (
+                hash TEXT PRIMARY KEY,
+                content TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS versions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                path TEXT NOT NULL,
+                hash TEXT,
+                source TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_versions_path ON versions(path, id);
+            "#,
+        )?;
+
+        Ok(Self {
+            pool,
+            workspace: workspace.to_path_buf(),
+        })
+    }
+
+    /// Path as stored: relative to the workspace when inside it, with `.`
+    /// and `..` resolved
+    fn key(&self, path: &Path) -> String {
+        let path = normalize(path);
+        path.strip_prefix(&self.workspace)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    /// Record a file's content, or its deletion if `content` is None.
+    /// Returns the new version's ID, or None if it matches the latest one.
+    pub fn record(&self, path: &Path, content: Option<&str>, source: &str) -> Result<Option<i64>> {
+        let key = self.key(path);
+        let hash = content.map(hash_text);
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+
+        let latest: Option<Option<String>> = tx
+            .query_row(
+                "SELECT hash FROM versions WHERE path = ?1 ORDER BY id DESC LIMIT 1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        // Nothing to record for a deletion of a file never seen
+        if latest.as_ref() == Some(&hash) || (latest.is_none() && hash.is_none()) {
+            return Ok(None);
+        }
+
+        if let (Some(hash), Some(content)) = (&hash, content) {
+            tx.execute(
+                "INSERT OR IGNORE INTO blobs (hash, content) VALUES (?1, ?2)",
+                params![hash, content],
+            )?;
+        }
+        tx.execute(
+            "INSERT INTO versions (path, hash, source, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![key, hash, source, chrono::Utc::now().timestamp()],
+        )?;
+        let id = tx.last_insert_rowid();
+        tx.commit()?;
+        Ok(Some(id))
+    }
+
+    /// Write a file, recording what it held before (if that was never
+    /// recorded, e.g. a hand edit while nothing watched) and what it holds
+    /// after
+    pub fn write(&self, path: &Path, content: &str, source: &str) -> Result<()> {
+        if let Ok(previous) = fs::read_to_string(path) {
+            self.record(path, Some(&previous), "snapshot")?;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
+        self.record(path, Some(content), source)?;
+        Ok(())
+    }
+
+    /// Versions, newest first: of one file, or of all files if `path` is None
+    pub fn versions(&self, path: Option<&str>, limit: usize) -> Result<Vec<MemoryVersion>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, path, hash, source, created_at FROM versions
+             WHERE ?1 IS NULL OR path = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let versions = stmt
+            .query_map(params![path, limit as i64], version_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(versions)
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<MemoryVersion>> {
+        let version = self
+            .pool
+            .get()?
+            .query_row(
+                "SELECT id, path, hash, source, created_at FROM versions WHERE id = ?1",
+                params![id],
+                version_from_row,
+            )
+            .optional()?;
+        Ok(version)
+    }
+
+    /// The file's content at a version; None for a deletion
+    pub fn content(&self, version: &MemoryVersion) -> Result<Option<String>> {
+        let Some(ref hash) = version.hash else {
+            return Ok(None);
+        };
+        let content = self.pool.get()?.query_row(
+            "SELECT content FROM blobs WHERE hash = ?1",
+            params![hash],
+            |row| row.get(0),
+        )?;
+        Ok(Some(content))
+    }
+
+    /// The version of the same file recorded before `version`
+    pub fn previous(&self, version: &MemoryVersion) -> Result<Option<MemoryVersion>> {
+        let previous = self
+            .pool
+            .get()?
+            .query_row(
+                "SELECT id, path, hash, source, created_at FROM versions
+                 WHERE path = ?1 AND id < ?2 ORDER BY id DESC LIMIT 1",
+                params![version.path, version.id],
+                version_from_row,
+            )
+            .optional()?;
+        Ok(previous)
+    }
+
+    /// Unified diff of a version against the one before it
+    pub fn diff(&self, version: &MemoryVersion) -> Result<String> {
+        let before = match self.previous(version)? {
+            Some(previous) => self.content(&previous)?.unwrap_or_default(),
+            None => String::new(),
+        };
+        let after = self.content(version)?.unwrap_or_default();
+        Ok(unified_diff(&version.path, &before, &after))
+    }
+
+    /// Write a version's content back to its file. Returns the file's path.
+    pub fn restore(&self, version: &MemoryVersion) -> Result<PathBuf> {
+        let Some(content) = self.content(version)? else {
+            bail!(
+                "Version {} is the deletion of {}; restore a version before it",
+                version.id,
+                version.path
+            );
+        };
+        let relative = Path::new(&version.path);
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            bail!(
+                "Version {} is of {}, which is not inside the workspace",
+                version.id,
+                version.path
+            );
+        }
+        let path = self.workspace.join(relative);
+        self.write(&path, &content, "restore")?;
+        Ok(path)
+    }
+
+    /// Record the current content of `files` where it differs from their
+    /// latest version (the baseline for changes made while nothing watched)
+    pub fn snapshot(&self, files: &[PathBuf]) -> usize {
+        let mut recorded = 0;
+        for path in files {
+            let Ok(content) = fs::read_to_string(path) else {
+                continue;
+            };
+            match self.record(path, Some(&content), "snapshot") {
+                Ok(Some(_)) => recorded += 1,
+                Ok(None) => {}
+                Err(e) => warn!("Failed to record {}: {}", path.display(), e),
+            }
+        }
+        recorded
+    }
+}
+
+fn version_from_row(row: &rusqlite::Row) -> rusqlite::Result<MemoryVersion> {
+    Ok(MemoryVersion {
+        id: row.get(0)?,
+        path: row.get(1)?,
+        hash: row.get(2)?,
+        source: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
+
+/// Diff of two versions of a file, in unified format
+fn unified_diff(path: &str, before: &str, after: &str) -> String {
+    TextDiff::from_lines(before, after)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", path), &format!("b/{}", path))
+        .to_string()
+}
+
+/// `path` with `.` and `..` components resolved, without touching the disk
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Write a workspace file through its history. A failure to record is
+/// logged; the write itself still happens.
+pub fn write_memory_file(workspace: &Path, path: &Path, content: &str, source: &str) -> Result<()> {
+    match MemoryHistory::for_workspace(workspace) {
+        Ok(history) => match history.write(path, content, source) {
+            Ok(()) => return Ok(()),
+            Err(e) => warn!("Failed to record {} in history: {}", path.display(), e),
+        },
+        Err(e) => warn!("Failed to open memory history: {}", e),
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_restore() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let workspace = dir.path();
+        let history = MemoryHistory::open_in_memory(workspace)?;
+        let path = workspace.join("MEMORY.md");
+
+        // A hand-written file is recorded before the first overwrite
+        fs::write(&path, "# Memory\n\n- likes tea\n")?;
+        history.write(&path, "# Memory\n\n- likes coffee\n", "write_file")?;
+        history.write(&path, "# Memory\n\n- likes coffee\n", "write_file")?;
+        let versions = history.versions(Some("MEMORY.md"), 10)?;
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].source, "write_file");
+        assert_eq!(versions[1].source, "snapshot");
+
+        let diff = history.diff(&versions[0])?;
+        assert!(diff.contains("--- a/MEMORY.md"));
+        assert!(diff.contains("\n-- likes tea\n+- likes coffee\n"));
+
+        // Deleted files can be brought back from an earlier version
+        fs::remove_file(&path)?;
+        let deleted = history.record(&path, None, "watcher")?.unwrap();
+        let deleted = history.get(deleted)?.unwrap();
+        assert!(deleted.is_deletion());
+        assert!(history.restore(&deleted).is_err());
+        history.restore(&versions[1])?;
+        assert_eq!(fs::read_to_string(&path)?, "# Memory\n\n- likes tea\n");
+        assert_eq!(history.versions(None, 10)?.len(), 4);
+
+        // Deletions of unknown files and unchanged snapshots add nothing
+        assert_eq!(
+            history.record(&workspace.join("other.md"), None, "watcher")?,
+            None
+        );
+        assert_eq!(history.snapshot(&[path]), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_stays_in_workspace() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let workspace = dir.path().join("workspace");
+        let history = MemoryHistory::open_in_memory(&workspace)?;
+
+        // `..` is resolved before the path is stored
+        let id = history
+            .record(
+                &workspace.join("memory/../notes.md"),
+                Some("notes"),
+                "write_file",
+            )?
+            .unwrap();
+        assert_eq!(history.get(id)?.unwrap().path, "notes.md");
+
+        for path in [
+            workspace.join("../outside.md"),
+            PathBuf::from("../outside.md"),
+        ] {
+            let id = history
+                .record(&path, Some("outside"), "write_file")?
+                .unwrap();
+            let version = history.get(id)?.unwrap();
+            assert!(history.restore(&version).is_err());
+        }
+        assert!(!dir.path().join("outside.md").exists());
+        Ok(())
+    }
+}
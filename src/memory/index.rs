@@ -1,19 +1,20 @@
-use anyhow::{Result, anyhow};
-use rusqlite::{Connection, params};
+use anyhow::Result;
+use rusqlite::{Connection, OptionalExtension, params};
 use sha2::{Digest, Sha256};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+use super::backfill::BackfillState;
 use super::embeddings::{cosine_similarity, deserialize_embedding, serialize_embedding};
 use super::search::MemoryChunk;
+use crate::db::SqlitePool;
 
 #[derive(Clone)]
 pub struct MemoryIndex {
-    conn: Arc<Mutex<Connection>>,
+    pool: SqlitePool,
     workspace: PathBuf,
     db_path: PathBuf,
     /// Whether sqlite-vec extension is loaded for fast vector search
@@ -32,15 +33,44 @@ pub struct ReindexStats {
     pub duration: Duration,
 }
 
+/// Consistency of the index, from [`MemoryIndex::check`]
+#[derive(Debug, Default)]
+pub struct IndexCheck {
+    /// FTS5 integrity-check failure (None = the full-text index is sound)
+    pub fts_error: Option<String>,
+    /// Chunks of files that are not indexed
+    pub orphaned_chunks: usize,
+    /// Full-text rows without a chunk
+    pub orphaned_fts: usize,
+    /// Chunks missing from the full-text index
+    pub missing_fts: usize,
+    /// Extra chunks covering the same lines of a file
+    pub duplicate_chunks: usize,
+    /// Extra full-text rows of the same chunk
+    pub duplicate_fts: usize,
+    /// Indexed files with identical content (e.g. a restored backup next
+    /// to the original), as groups of paths
+    pub duplicate_files: Vec<Vec<String>>,
+}
+
+impl IndexCheck {
+    /// Whether the index needs no repair (duplicate files are left to the
+    /// user)
+    pub fn is_clean(&self) -> bool {
+        self.fts_error.is_none()
+            && self.orphaned_chunks == 0
+            && self.orphaned_fts == 0
+            && self.missing_fts == 0
+            && self.duplicate_chunks == 0
+            && self.duplicate_fts == 0
+    }
+}
+
 impl MemoryIndex {
     /// Create a new memory index with database at the specified path
     pub fn new_with_db_path(workspace: &Path, db_path: &Path) -> Result<Self> {
-        // Ensure parent directory exists
-        if let Some(parent) = db_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-
-        let conn = Connection::open(db_path)?;
+        let pool = SqlitePool::shared(db_path, Some(Self::init_connection))?;
+        let conn = pool.get()?;
 
         // Check if we need to migrate from old schema
         let needs_migration = Self::needs_schema_migration(&conn)?;
@@ -97,6 +127,17 @@ impl MemoryIndex {
             CREATE INDEX IF NOT EXISTS idx_chunks_path ON chunks(path);
             CREATE INDEX IF NOT EXISTS idx_chunks_source ON chunks(source);
             CREATE INDEX IF NOT EXISTS idx_embedding_cache_updated_at ON embedding_cache(updated_at);
+
+            -- Background embedding backfill progress (one row per model)
+            CREATE TABLE IF NOT EXISTS embedding_backfill (
+                model TEXT PRIMARY KEY,
+                status TEXT NOT NULL,
+                embedded INTEGER NOT NULL DEFAULT 0,
+                failed_batches INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT,
+                started_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
             "#,
         )?;
 
@@ -107,8 +148,10 @@ impl MemoryIndex {
         Self::ensure_column(&conn, "files", "source", "TEXT NOT NULL DEFAULT 'memory'")?;
         Self::ensure_column(&conn, "chunks", "source", "TEXT NOT NULL DEFAULT 'memory'")?;
 
-        // Try to load sqlite-vec extension for fast vector search
-        let has_vec_extension = Self::try_load_sqlite_vec(&conn);
+        // sqlite-vec is loaded per connection by init_connection
+        let has_vec_extension = conn
+            .query_row("SELECT vec_version()", [], |row| row.get::<_, String>(0))
+            .is_ok();
         if has_vec_extension {
             debug!("sqlite-vec extension loaded successfully");
             Self::ensure_vec_table(&conn)?;
@@ -116,8 +159,9 @@ impl MemoryIndex {
             debug!("sqlite-vec extension not available, using in-memory vector search");
         }
 
+        drop(conn);
         Ok(Self {
-            conn: Arc::new(Mutex::new(conn)),
+            pool,
             workspace: workspace.to_path_buf(),
             db_path: db_path.to_path_buf(),
             has_vec_extension,
@@ -133,6 +177,12 @@ impl MemoryIndex {
         self
     }
 
+    /// Set up each pooled connection: try to load sqlite-vec for fast vector search
+    fn init_connection(conn: &Connection) -> Result<()> {
+        Self::try_load_sqlite_vec(conn);
+        Ok(())
+    }
+
     /// Try to load sqlite-vec extension
     #[allow(unsafe_code)]
     fn try_load_sqlite_vec(conn: &Connection) -> bool {
@@ -202,10 +252,7 @@ impl MemoryIndex {
             .to_string_lossy()
             .to_string();
 
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.pool.get()?;
 
         // Check if file has changed
         if !force {
@@ -287,10 +334,7 @@ impl MemoryIndex {
 
     /// Remove a file and its chunks from the index (for deleted files)
     pub fn remove_file(&self, relative_path: &str) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.pool.get()?;
 
         Self::delete_chunks_for_path(&conn, relative_path)?;
         conn.execute("DELETE FROM files WHERE path = ?1", params![relative_path])?;
@@ -301,10 +345,7 @@ impl MemoryIndex {
 
     /// Get all indexed file paths
     pub fn indexed_files(&self) -> Result<Vec<String>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.pool.get()?;
 
         let mut stmt = conn.prepare("SELECT path FROM files")?;
         let rows = stmt.query_map([], |row| row.get(0))?;
@@ -342,10 +383,23 @@ impl MemoryIndex {
             None => return Ok(Vec::new()),
         };
 
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        self.search_fts_match(&fts_query, limit)
+    }
+
+    /// Search using FTS5, matching chunks that contain any of the query terms
+    /// (ranked by BM25). Suited to long conversational queries where
+    /// requiring every term would match nothing.
+    pub fn search_any(&self, query: &str, limit: usize) -> Result<Vec<MemoryChunk>> {
+        let fts_query = match build_fts_query_any(query) {
+            Some(q) => q,
+            None => return Ok(Vec::new()),
+        };
+
+        self.search_fts_match(&fts_query, limit)
+    }
+
+    fn search_fts_match(&self, fts_query: &str, limit: usize) -> Result<Vec<MemoryChunk>> {
+        let conn = self.pool.get()?;
 
         // OpenClaw-compatible: use 'path', 'start_line', 'end_line', 'text' columns
         let mut stmt = conn.prepare(
@@ -358,7 +412,7 @@ impl MemoryIndex {
             "#,
         )?;
 
-        let rows = stmt.query_map(params![&fts_query, limit as i64], |row| {
+        let rows = stmt.query_map(params![fts_query, limit as i64], |row| {
             Ok(MemoryChunk {
                 file: row.get(0)?,
                 line_start: row.get(1)?,
@@ -378,10 +432,7 @@ impl MemoryIndex {
 
     /// Get total chunk count
     pub fn chunk_count(&self) -> Result<usize> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.pool.get()?;
         let count: i64 = conn.query_row("SELECT COUNT(*) FROM chunks", [], |row| row.get(0))?;
         Ok(count as usize)
     }
@@ -394,10 +445,7 @@ impl MemoryIndex {
             .to_string_lossy()
             .to_string();
 
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.pool.get()?;
         let count: i64 = conn.query_row(
             "SELECT COUNT(*) FROM chunks WHERE path = ?1",
             params![&relative_path],
@@ -415,6 +463,106 @@ impl MemoryIndex {
         }
     }
 
+    /// Remove chunk, FTS and vector rows that no longer belong to an indexed file.
+    /// Returns the number of rows removed.
+    pub fn prune_orphans(&self) -> Result<usize> {
+        let conn = self.pool.get()?;
+
+        let mut removed = conn.execute(
+            "DELETE FROM chunks WHERE path NOT IN (SELECT path FROM files)",
+            [],
+        )?;
+        removed += conn.execute(
+            "DELETE FROM chunks_fts WHERE id NOT IN (SELECT id FROM chunks)",
+            [],
+        )?;
+        if self.has_vec_extension {
+            removed += conn
+                .execute(
+                    "DELETE FROM chunks_vec WHERE id NOT IN (SELECT id FROM chunks)",
+                    [],
+                )
+                .unwrap_or(0);
+        }
+
+        Ok(removed)
+    }
+
+    /// Verify the full-text index and count orphaned and duplicate rows
+    pub fn check(&self) -> Result<IndexCheck> {
+        let conn = self.pool.get()?;
+        let count = |sql: &str| -> Result<usize> {
+            let n: i64 = conn.query_row(sql, [], |row| row.get(0))?;
+            Ok(n as usize)
+        };
+
+        let fts_error = conn
+            .execute(
+                "INSERT INTO chunks_fts(chunks_fts) VALUES('integrity-check')",
+                [],
+            )
+            .err()
+            .map(|e| e.to_string());
+
+        let mut check = IndexCheck {
+            fts_error,
+            orphaned_chunks: count(
+                "SELECT COUNT(*) FROM chunks WHERE path NOT IN (SELECT path FROM files)",
+            )?,
+            orphaned_fts: count(
+                "SELECT COUNT(*) FROM chunks_fts WHERE id NOT IN (SELECT id FROM chunks)",
+            )?,
+            missing_fts: count(
+                "SELECT COUNT(*) FROM chunks WHERE id NOT IN (SELECT id FROM chunks_fts)",
+            )?,
+            duplicate_chunks: count(
+                "SELECT COALESCE(SUM(n - 1), 0) FROM (
+                     SELECT COUNT(*) AS n FROM chunks
+                     GROUP BY path, start_line, end_line HAVING n > 1)",
+            )?,
+            duplicate_fts: count(
+                "SELECT COALESCE(SUM(n - 1), 0) FROM (
+                     SELECT COUNT(*) AS n FROM chunks_fts GROUP BY id HAVING n > 1)",
+            )?,
+            duplicate_files: Vec::new(),
+        };
+
+        let mut stmt = conn.prepare(
+            "SELECT hash, path FROM files
+             WHERE size > 0 AND hash IN (
+                 SELECT hash FROM files WHERE size > 0 GROUP BY hash HAVING COUNT(*) > 1)
+             ORDER BY hash, path",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut last_hash = None;
+        for row in rows {
+            let (hash, path) = row?;
+            if last_hash.as_ref() != Some(&hash) {
+                check.duplicate_files.push(Vec::new());
+                last_hash = Some(hash);
+            }
+            if let Some(group) = check.duplicate_files.last_mut() {
+                group.push(path);
+            }
+        }
+
+        Ok(check)
+    }
+
+    /// Merge FTS segments, refresh query planner statistics and reclaim
+    /// free pages. Returns the database size in bytes before and after.
+    pub fn optimize(&self) -> Result<(u64, u64)> {
+        let before = self.size_bytes()?;
+        {
+            let conn = self.pool.get()?;
+            conn.execute("INSERT INTO chunks_fts(chunks_fts) VALUES('optimize')", [])?;
+            conn.execute_batch("ANALYZE; VACUUM;")?;
+        }
+        Ok((before, self.size_bytes()?))
+    }
+
     /// Get the database path
     pub fn db_path(&self) -> &Path {
         &self.db_path
@@ -618,10 +766,7 @@ impl MemoryIndex {
 
     /// Get chunks that need embeddings (OpenClaw-compatible: id is TEXT, text column)
     pub fn chunks_without_embeddings(&self, limit: usize) -> Result<Vec<(String, String)>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.pool.get()?;
 
         let mut stmt = conn.prepare(
             "SELECT id, text FROM chunks WHERE embedding = '' OR embedding IS NULL LIMIT ?1",
@@ -641,10 +786,7 @@ impl MemoryIndex {
 
     /// Store embedding for a chunk (OpenClaw-compatible: id is TEXT, model column)
     pub fn store_embedding(&self, chunk_id: &str, embedding: &[f32], model: &str) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.pool.get()?;
 
         let embedding_json = serialize_embedding(embedding);
         let now = std::time::SystemTime::now()
@@ -679,10 +821,7 @@ impl MemoryIndex {
         model: &str,
         text_hash: &str,
     ) -> Result<Option<Vec<f32>>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.pool.get()?;
 
         let result: Option<String> = conn
             .query_row(
@@ -704,10 +843,7 @@ impl MemoryIndex {
         text_hash: &str,
         embedding: &[f32],
     ) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.pool.get()?;
 
         let embedding_json = serialize_embedding(embedding);
         let dims = embedding.len() as i32;
@@ -737,10 +873,7 @@ impl MemoryIndex {
         model: &str,
         limit: usize,
     ) -> Result<Vec<MemoryChunk>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.pool.get()?;
 
         // Try sqlite-vec fast path if available
         if self.has_vec_extension {
@@ -914,10 +1047,7 @@ impl MemoryIndex {
 
     /// Count chunks with embeddings (OpenClaw-compatible: model column)
     pub fn embedded_chunk_count(&self, model: &str) -> Result<usize> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.pool.get()?;
 
         let count: i64 = conn.query_row(
             "SELECT COUNT(*) FROM chunks WHERE embedding != '' AND embedding IS NOT NULL AND model = ?1",
@@ -927,6 +1057,50 @@ impl MemoryIndex {
 
         Ok(count as usize)
     }
+
+    /// Load saved backfill progress for a model
+    pub(super) fn load_backfill_state(&self, model: &str) -> Result<Option<BackfillState>> {
+        let conn = self.pool.get()?;
+        let state = conn
+            .query_row(
+                "SELECT model, status, embedded, failed_batches, last_error, started_at, updated_at
+                 FROM embedding_backfill WHERE model = ?1",
+                params![model],
+                |row| {
+                    Ok(BackfillState {
+                        model: row.get(0)?,
+                        status: row.get(1)?,
+                        embedded: row.get::<_, i64>(2)? as usize,
+                        failed_batches: row.get::<_, i64>(3)? as usize,
+                        last_error: row.get(4)?,
+                        started_at: row.get(5)?,
+                        updated_at: row.get(6)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(state)
+    }
+
+    /// Save backfill progress (upsert by model)
+    pub(super) fn save_backfill_state(&self, state: &BackfillState) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO embedding_backfill
+             (model, status, embedded, failed_batches, last_error, started_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                state.model,
+                state.status,
+                state.embedded as i64,
+                state.failed_batches as i64,
+                state.last_error,
+                state.started_at,
+                state.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
 }
 
 fn hash_content(content: &str) -> String {
@@ -966,6 +1140,24 @@ fn build_fts_query(raw: &str) -> Option<String> {
     Some(quoted.join(" AND "))
 }
 
+/// Build an FTS5 query that matches any term (OR), skipping very short tokens
+fn build_fts_query_any(raw: &str) -> Option<String> {
+    let mut tokens: Vec<String> = raw
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|t| t.chars().count() >= 3)
+        .map(|t| t.to_lowercase())
+        .collect();
+    tokens.sort();
+    tokens.dedup();
+
+    if tokens.is_empty() {
+        return None;
+    }
+
+    let quoted: Vec<String> = tokens.iter().map(|t| format!("\"{}\"", t)).collect();
+    Some(quoted.join(" OR "))
+}
+
 struct ChunkInfo {
     line_start: i32,
     line_end: i32,
@@ -1064,4 +1256,90 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_backfill_state_roundtrip() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let index = MemoryIndex::new(temp_dir.path())?;
+        assert_eq!(index.load_backfill_state("model-a")?, None);
+
+        let mut state = BackfillState {
+            model: "model-a".to_string(),
+            status: "running".to_string(),
+            embedded: 64,
+            failed_batches: 1,
+            last_error: Some("rate limited".to_string()),
+            started_at: 1_700_000_000,
+            updated_at: 1_700_000_060,
+        };
+        index.save_backfill_state(&state)?;
+        state.embedded = 96;
+        state.status = "complete".to_string();
+        index.save_backfill_state(&state)?;
+
+        assert_eq!(index.load_backfill_state("model-a")?, Some(state));
+        assert_eq!(index.load_backfill_state("model-b")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_check() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
+        fs::write(workspace.join("notes.md"), "# Notes\n\nSame content.")?;
+        fs::write(workspace.join("notes copy.md"), "# Notes\n\nSame content.")?;
+
+        let index = MemoryIndex::new(workspace)?;
+        index.index_file(&workspace.join("notes.md"), false)?;
+        index.index_file(&workspace.join("notes copy.md"), false)?;
+        let check = index.check()?;
+        assert!(check.is_clean(), "{:?}", check);
+        assert_eq!(
+            check.duplicate_files,
+            vec![vec!["notes copy.md".to_string(), "notes.md".to_string()]]
+        );
+
+        // A chunk indexed twice, and one whose full-text row went missing
+        {
+            let conn = index.pool.get()?;
+            conn.execute(
+                "INSERT INTO chunks (id, path, source, start_line, end_line, hash, model, text, embedding, updated_at)
+                 SELECT 'copy', path, source, start_line, end_line, hash, model, text, embedding, updated_at
+                 FROM chunks WHERE path = 'notes.md'",
+                [],
+            )?;
+        }
+        let check = index.check()?;
+        assert_eq!(check.duplicate_chunks, 1);
+        assert_eq!(check.missing_fts, 1);
+        assert_eq!(check.orphaned_chunks, 0);
+        assert!(!check.is_clean());
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_orphans() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
+        let test_file = workspace.join("notes.md");
+        fs::write(&test_file, "# Notes\n\nOrphaned chunk content.")?;
+
+        let index = MemoryIndex::new(workspace)?;
+        index.index_file(&test_file, false)?;
+        assert!(index.chunk_count()? > 0);
+
+        // Simulate an interrupted delete that left chunks behind
+        index
+            .conn
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM files", [])?;
+
+        assert!(index.prune_orphans()? > 0);
+        assert_eq!(index.chunk_count()?, 0);
+        assert!(index.search("orphaned", 10)?.is_empty());
+
+        index.optimize()?;
+        Ok(())
+    }
 }
@@ -1,23 +1,39 @@
+mod backfill;
+mod context_watcher;
 mod embeddings;
+mod entries;
+mod file_cache;
+mod history;
 mod index;
+mod obsidian;
 mod search;
+mod topic_log;
 mod watcher;
 mod workspace;
 
+pub use backfill::BackfillProgress;
+pub use context_watcher::{
+    CONTEXT_FILES, ContextReloadEvent, emit_context_reload, subscribe_context_reloads,
+    watch_context_files,
+};
 #[cfg(feature = "gguf")]
 pub use embeddings::LlamaCppProvider;
 pub use embeddings::{EmbeddingProvider, FastEmbedProvider, OpenAIEmbeddingProvider, hash_text};
-pub use index::{MemoryIndex, ReindexStats};
+pub use entries::MemoryCategory;
+pub use history::{MemoryHistory, MemoryVersion, write_memory_file};
+pub use index::{IndexCheck, MemoryIndex, ReindexStats};
+pub use obsidian::Vault;
 pub use search::MemoryChunk;
+pub use topic_log::{LogContext, LogOrigin, TopicMeta, parse_topic_log, topic_slug};
 pub use watcher::MemoryWatcher;
 pub use workspace::{init_state_dir, init_workspace};
 
 use anyhow::Result;
 use chrono::Local;
 use std::fs;
-use std::path::PathBuf;
-use std::sync::Arc;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use tokio::runtime::Handle;
 use tracing::{debug, info, warn};
 
@@ -33,6 +49,8 @@ pub struct MemoryManager {
     embedding_provider: Option<Arc<dyn EmbeddingProvider>>,
     /// True if this was a brand new workspace (first run)
     is_brand_new: bool,
+    /// Obsidian layout, when `memory.obsidian.enabled`
+    vault: Option<Vault>,
 }
 
 #[derive(Debug)]
@@ -51,6 +69,17 @@ pub struct FileStats {
     pub lines: usize,
 }
 
+/// Outcome of one `embed_next_batch` call
+#[derive(Debug, Default)]
+struct EmbeddingBatch {
+    /// Chunks without embeddings that were picked up (0 = none left)
+    fetched: usize,
+    embedded: usize,
+    cache_hits: usize,
+    /// Embedding provider error, if the request failed
+    error: Option<String>,
+}
+
 #[derive(Debug)]
 pub struct RecentEntry {
     pub timestamp: String,
@@ -199,6 +228,8 @@ impl MemoryManager {
             }
         };
 
+        let vault = Vault::from_config(&workspace, &memory_config.obsidian);
+
         Ok(Self {
             workspace,
             db_path,
@@ -206,6 +237,7 @@ impl MemoryManager {
             config: memory_config.clone(),
             embedding_provider,
             is_brand_new,
+            vault,
         })
     }
 
@@ -224,64 +256,55 @@ impl MemoryManager {
         &self.workspace
     }
 
+    /// The workspace as an Obsidian vault, if that layout is enabled
+    pub fn vault(&self) -> Option<&Vault> {
+        self.vault.as_ref()
+    }
+
     /// Read the main MEMORY.md file
-    pub fn read_memory_file(&self) -> Result<String> {
-        let path = self.workspace.join("MEMORY.md");
-        if path.exists() {
-            Ok(fs::read_to_string(&path)?)
-        } else {
-            Ok(String::new())
-        }
+    pub fn read_memory_file(&self) -> Result<Arc<str>> {
+        file_cache::read_cached(&self.workspace.join("MEMORY.md"))
     }
 
     /// Read the HEARTBEAT.md file
-    pub fn read_heartbeat_file(&self) -> Result<String> {
-        let path = self.workspace.join("HEARTBEAT.md");
-        if path.exists() {
-            Ok(fs::read_to_string(&path)?)
-        } else {
-            Ok(String::new())
-        }
+    pub fn read_heartbeat_file(&self) -> Result<Arc<str>> {
+        file_cache::read_cached(&self.workspace.join("HEARTBEAT.md"))
     }
 
     /// Read the SOUL.md file (persona/tone guidance)
-    pub fn read_soul_file(&self) -> Result<String> {
-        let path = self.workspace.join("SOUL.md");
-        if path.exists() {
-            Ok(fs::read_to_string(&path)?)
+    pub fn read_soul_file(&self) -> Result<Arc<str>> {
+        file_cache::read_cached(&self.workspace.join("SOUL.md"))
+    }
+
+    /// The persona file for a language: `SOUL.<language>.md` if it exists,
+    /// SOUL.md otherwise
+    pub fn soul_file_path(&self, language: &str) -> PathBuf {
+        let localized = self.workspace.join(format!("SOUL.{}.md", language));
+        if localized.exists() {
+            localized
         } else {
-            Ok(String::new())
+            self.workspace.join("SOUL.md")
         }
     }
 
+    /// Read the persona file for a language (see [`Self::soul_file_path`])
+    pub fn read_soul_file_for(&self, language: &str) -> Result<Arc<str>> {
+        file_cache::read_cached(&self.soul_file_path(language))
+    }
+
     /// Read the USER.md file (OpenClaw-compatible: user info)
-    pub fn read_user_file(&self) -> Result<String> {
-        let path = self.workspace.join("USER.md");
-        if path.exists() {
-            Ok(fs::read_to_string(&path)?)
-        } else {
-            Ok(String::new())
-        }
+    pub fn read_user_file(&self) -> Result<Arc<str>> {
+        file_cache::read_cached(&self.workspace.join("USER.md"))
     }
 
     /// Read the IDENTITY.md file (OpenClaw-compatible: agent identity context)
-    pub fn read_identity_file(&self) -> Result<String> {
-        let path = self.workspace.join("IDENTITY.md");
-        if path.exists() {
-            Ok(fs::read_to_string(&path)?)
-        } else {
-            Ok(String::new())
-        }
+    pub fn read_identity_file(&self) -> Result<Arc<str>> {
+        file_cache::read_cached(&self.workspace.join("IDENTITY.md"))
     }
 
     /// Read the AGENTS.md file (OpenClaw-compatible: list of agents)
-    pub fn read_agents_file(&self) -> Result<String> {
-        let path = self.workspace.join("AGENTS.md");
-        if path.exists() {
-            Ok(fs::read_to_string(&path)?)
-        } else {
-            Ok(String::new())
-        }
+    pub fn read_agents_file(&self) -> Result<Arc<str>> {
+        file_cache::read_cached(&self.workspace.join("AGENTS.md"))
     }
 
     /// Check if this is a brand new workspace (first run)
@@ -290,19 +313,16 @@ impl MemoryManager {
     }
 
     /// Read the TOOLS.md file (OpenClaw-compatible: local tool notes)
-    pub fn read_tools_file(&self) -> Result<String> {
-        let path = self.workspace.join("TOOLS.md");
-        if path.exists() {
-            Ok(fs::read_to_string(&path)?)
-        } else {
-            Ok(String::new())
-        }
+    pub fn read_tools_file(&self) -> Result<Arc<str>> {
+        file_cache::read_cached(&self.workspace.join("TOOLS.md"))
     }
 
-    /// Read recent daily log files
+    /// Read recent daily log files: `memory/YYYY-MM-DD.md`, the Obsidian
+    /// daily note in vault mode, and the day's topic logs in
+    /// `memory/YYYY-MM-DD/`
     pub fn read_recent_daily_logs(&self, days: usize) -> Result<String> {
         let memory_dir = self.workspace.join("memory");
-        if !memory_dir.exists() {
+        if !memory_dir.exists() && self.vault.is_none() {
             return Ok(String::new());
         }
 
@@ -311,16 +331,36 @@ impl MemoryManager {
 
         for i in 0..days {
             let date = today - chrono::Duration::days(i as i64);
-            let filename = format!("{}.md", date.format("%Y-%m-%d"));
-            let path = memory_dir.join(&filename);
+            let mut paths = vec![memory_dir.join(format!("{}.md", date.format("%Y-%m-%d")))];
+            if let Some(ref vault) = self.vault {
+                let daily = vault.daily_note(date);
+                if !paths.contains(&daily) {
+                    paths.push(daily);
+                }
+            }
 
-            if path.exists()
-                && let Ok(file_content) = fs::read_to_string(&path)
-            {
+            for path in paths {
+                if let Ok(file_content) = file_cache::read_cached(&path)
+                    && !file_content.is_empty()
+                {
+                    if !content.is_empty() {
+                        content.push_str("\n---\n\n");
+                    }
+                    let name = path.strip_prefix(&self.workspace).unwrap_or(&path);
+                    let name = name.strip_prefix("memory").unwrap_or(name);
+                    content.push_str(&format!("## {}\n\n", name.display()));
+                    content.push_str(&file_content);
+                }
+            }
+
+            for (name, file_content) in topic_log::read_day(&self.workspace, date) {
+                if file_content.is_empty() {
+                    continue;
+                }
                 if !content.is_empty() {
                     content.push_str("\n---\n\n");
                 }
-                content.push_str(&format!("## {}\n\n", filename));
+                content.push_str(&format!("## {}\n\n", name));
                 content.push_str(&file_content);
             }
         }
@@ -328,6 +368,61 @@ impl MemoryManager {
         Ok(content)
     }
 
+    /// Append a note to today's log for `topic` and index it right away
+    pub fn append_topic_log(
+        &self,
+        topic: &str,
+        content: &str,
+        tags: &[String],
+        context: &LogContext,
+    ) -> Result<PathBuf> {
+        let path = topic_log::append_entry(&self.workspace, topic, content, tags, context)?;
+        if let Err(e) = self.index.index_file(&path, false) {
+            warn!("Failed to index {}: {}", path.display(), e);
+        }
+        self.link_from_daily_note(&path, topic);
+        Ok(path)
+    }
+
+    /// Save a categorized entry: events go to today's `Events` topic log,
+    /// everything else to its section of MEMORY.md. The file is reindexed
+    /// right away.
+    pub fn save_entry(
+        &self,
+        category: MemoryCategory,
+        content: &str,
+        context: &LogContext,
+    ) -> Result<PathBuf> {
+        if category == MemoryCategory::Event {
+            let entry = format!("{}\n\n_({})_", content.trim(), entries::provenance(context));
+            return self.append_topic_log(category.section(), &entry, &[], context);
+        }
+        let path = entries::append_to_memory_file(&self.workspace, category, content, context)?;
+        if let Err(e) = self.index.index_file(&path, false) {
+            warn!("Failed to index {}: {}", path.display(), e);
+        }
+        Ok(path)
+    }
+
+    /// In vault mode, link a note the assistant wrote from today's daily
+    /// note and reindex the daily note. Failures are only logged.
+    pub fn link_from_daily_note(&self, note: &Path, title: &str) {
+        let Some(ref vault) = self.vault else {
+            return;
+        };
+        let today = Local::now().date_naive();
+        match vault.link_from_daily_note(today, note, title) {
+            Ok(true) => {
+                let daily = vault.daily_note(today);
+                if let Err(e) = self.index.index_file(&daily, false) {
+                    warn!("Failed to index {}: {}", daily.display(), e);
+                }
+            }
+            Ok(false) => {}
+            Err(e) => warn!("Failed to link {} from daily note: {}", note.display(), e),
+        }
+    }
+
     /// Search memory using hybrid search (FTS + semantic if available)
     pub fn search(&self, query: &str, limit: usize) -> Result<Vec<MemoryChunk>> {
         // If we have an embedding provider, try hybrid search
@@ -363,6 +458,22 @@ impl MemoryManager {
         self.index.search(query, limit)
     }
 
+    /// Search for passages related to free-form text such as an incoming
+    /// chat message.
+    ///
+    /// Unlike `search`, the keyword fallback matches any term rather than all
+    /// of them, so long conversational queries still surface related chunks.
+    pub fn recall(&self, query: &str, limit: usize) -> Result<Vec<MemoryChunk>> {
+        if self.embedding_provider.is_some() {
+            let results = self.search(query, limit)?;
+            if !results.is_empty() {
+                return Ok(results);
+            }
+        }
+
+        self.index.search_any(query, limit)
+    }
+
     /// Search memory using FTS only (faster, no API calls)
     pub fn search_fts(&self, query: &str, limit: usize) -> Result<Vec<MemoryChunk>> {
         self.index.search(query, limit)
@@ -375,13 +486,18 @@ impl MemoryManager {
 
     /// Reindex all memory files
     pub fn reindex(&self, force: bool) -> Result<ReindexStats> {
+        self.reindex_with(force, 1, |_, _| {})
+    }
+
+    /// Reindex all memory files on `jobs` threads, calling `progress` with
+    /// the number of files done and the total after each file
+    pub fn reindex_with(
+        &self,
+        force: bool,
+        jobs: usize,
+        progress: impl Fn(usize, usize) + Sync,
+    ) -> Result<ReindexStats> {
         let start = std::time::Instant::now();
-        let mut stats = ReindexStats {
-            files_processed: 0,
-            files_updated: 0,
-            chunks_indexed: 0,
-            duration: Duration::default(),
-        };
 
         // First, clean up deleted files from the index
         let files_removed = self.cleanup_deleted_files()?;
@@ -389,6 +505,57 @@ impl MemoryManager {
             info!("Removed {} deleted files from index", files_removed);
         }
 
+        let targets = self.index_targets();
+        let total = targets.len();
+        let next = AtomicUsize::new(0);
+        let done = AtomicUsize::new(0);
+        let updated = AtomicUsize::new(0);
+        let first_error = Mutex::new(None);
+
+        std::thread::scope(|scope| {
+            for _ in 0..jobs.clamp(1, total.max(1)) {
+                scope.spawn(|| {
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = targets.get(i) else { break };
+                        match self.index.index_file(path, force) {
+                            Ok(true) => {
+                                updated.fetch_add(1, Ordering::Relaxed);
+                            }
+                            Ok(false) => {}
+                            Err(e) => {
+                                let e = e.context(format!("Failed to index {}", path.display()));
+                                first_error.lock().unwrap().get_or_insert(e);
+                                // Let the other threads stop too
+                                next.store(total, Ordering::Relaxed);
+                                break;
+                            }
+                        }
+                        progress(done.fetch_add(1, Ordering::Relaxed) + 1, total);
+                    }
+                });
+            }
+        });
+        if let Some(e) = first_error.into_inner().unwrap() {
+            return Err(e);
+        }
+
+        let stats = ReindexStats {
+            files_processed: total,
+            files_updated: updated.into_inner(),
+            chunks_indexed: self.index.chunk_count()?,
+            duration: start.elapsed(),
+        };
+
+        info!("Reindex complete: {:?}", stats);
+        Ok(stats)
+    }
+
+    /// Files to index: all .md files under the workspace, and the
+    /// configured external paths
+    fn index_targets(&self) -> Vec<PathBuf> {
+        let mut targets = Vec::new();
+
         // Index all .md files recursively under workspace
         let pattern = format!("{}/**/*.md", self.workspace.display());
         for entry in glob::glob(&pattern)
@@ -396,11 +563,15 @@ impl MemoryManager {
             .flatten()
             .filter_map(|r| r.ok())
         {
+            if self
+                .vault
+                .as_ref()
+                .is_some_and(|vault| vault.is_hidden(&entry))
+            {
+                continue;
+            }
             if entry.is_file() {
-                stats.files_processed += 1;
-                if self.index.index_file(&entry, force)? {
-                    stats.files_updated += 1;
-                }
+                targets.push(entry);
             }
         }
 
@@ -432,19 +603,29 @@ impl MemoryManager {
                 .filter_map(|r| r.ok())
             {
                 if entry.is_file() {
-                    stats.files_processed += 1;
-                    if self.index.index_file(&entry, force)? {
-                        stats.files_updated += 1;
-                    }
+                    targets.push(entry);
                 }
             }
         }
 
-        stats.chunks_indexed = self.index.chunk_count()?;
-        stats.duration = start.elapsed();
+        targets
+    }
 
-        info!("Reindex complete: {:?}", stats);
-        Ok(stats)
+    /// Check the search index for FTS corruption and orphaned or duplicate
+    /// rows
+    pub fn check_index(&self) -> Result<IndexCheck> {
+        self.index.check()
+    }
+
+    /// Remove index rows left behind by deleted files or interrupted writes
+    pub fn prune_index(&self) -> Result<usize> {
+        self.index.prune_orphans()
+    }
+
+    /// Compact the search index database (FTS optimize, ANALYZE, VACUUM).
+    /// Returns the database size in bytes before and after.
+    pub fn optimize_index(&self) -> Result<(u64, u64)> {
+        self.index.optimize()
     }
 
     /// Remove files from index that no longer exist on disk
@@ -597,6 +778,25 @@ impl MemoryManager {
 
     /// Start file watcher for automatic reindexing
     pub fn start_watcher(&self) -> Result<MemoryWatcher> {
+        // The history's baseline: files changed while nothing watched
+        let files: Vec<PathBuf> = self
+            .index_targets()
+            .into_iter()
+            .filter(|path| path.starts_with(&self.workspace))
+            .collect();
+        match MemoryHistory::for_workspace(&self.workspace) {
+            Ok(history) => {
+                let recorded = history.snapshot(&files);
+                debug!("Recorded {} changed memory files in history", recorded);
+            }
+            Err(e) => warn!("Failed to open memory history: {}", e),
+        }
+
+        // Also broadcast SOUL.md/MEMORY.md/AGENTS.md edits to live agents
+        if let Err(e) = watch_context_files(&self.workspace) {
+            warn!("Failed to watch context files: {}", e);
+        }
+
         MemoryWatcher::new(
             self.workspace.clone(),
             self.db_path.clone(),
@@ -616,86 +816,23 @@ impl MemoryManager {
             }
         };
 
-        let provider_id = provider.id().to_string();
-        let model = provider.model().to_string();
         let mut total_processed = 0;
         let mut total_embedded = 0;
         let mut cache_hits = 0;
 
         loop {
-            // Get chunks without embeddings
-            let chunks = self.index.chunks_without_embeddings(batch_size)?;
-            if chunks.is_empty() {
+            let batch = self.embed_next_batch(provider.as_ref(), batch_size).await?;
+            if batch.fetched == 0 {
                 break;
             }
 
-            total_processed += chunks.len();
-
-            // Separate chunks into cached and uncached
-            let mut to_embed: Vec<(String, String, String)> = Vec::new(); // (id, text, hash)
-            let mut from_cache: Vec<(String, Vec<f32>)> = Vec::new(); // (id, embedding)
-
-            for (chunk_id, text) in &chunks {
-                let text_hash = hash_text(text);
+            total_processed += batch.fetched;
+            total_embedded += batch.embedded;
+            cache_hits += batch.cache_hits;
 
-                // Check cache first
-                if let Ok(Some(cached)) =
-                    self.index
-                        .get_cached_embedding(&provider_id, &model, &text_hash)
-                {
-                    from_cache.push((chunk_id.clone(), cached));
-                    cache_hits += 1;
-                } else {
-                    to_embed.push((chunk_id.clone(), text.clone(), text_hash));
-                }
-            }
-
-            // Store cached embeddings
-            for (chunk_id, embedding) in from_cache {
-                if let Err(e) = self.index.store_embedding(&chunk_id, &embedding, &model) {
-                    warn!(
-                        "Failed to store cached embedding for chunk {}: {}",
-                        chunk_id, e
-                    );
-                } else {
-                    total_embedded += 1;
-                }
-            }
-
-            // Generate new embeddings for uncached chunks
-            if !to_embed.is_empty() {
-                let texts: Vec<String> = to_embed.iter().map(|(_, text, _)| text.clone()).collect();
-
-                match provider.embed_batch(&texts).await {
-                    Ok(embeddings) => {
-                        for ((chunk_id, _text, text_hash), embedding) in
-                            to_embed.iter().zip(embeddings.iter())
-                        {
-                            // Store in chunk
-                            if let Err(e) = self.index.store_embedding(chunk_id, embedding, &model)
-                            {
-                                warn!("Failed to store embedding for chunk {}: {}", chunk_id, e);
-                            } else {
-                                total_embedded += 1;
-                            }
-
-                            // Store in cache for future reuse
-                            if let Err(e) = self.index.cache_embedding(
-                                &provider_id,
-                                &model,
-                                "", // provider_key (API key identifier, can be empty)
-                                text_hash,
-                                embedding,
-                            ) {
-                                debug!("Failed to cache embedding: {}", e);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        warn!("Failed to generate embeddings: {}", e);
-                        break;
-                    }
-                }
+            if let Some(e) = batch.error {
+                warn!("Failed to generate embeddings: {}", e);
+                break;
             }
 
             debug!(
@@ -704,7 +841,7 @@ impl MemoryManager {
             );
 
             // Break if we processed fewer than batch_size (last batch)
-            if chunks.len() < batch_size {
+            if batch.fetched < batch_size {
                 break;
             }
         }
@@ -717,6 +854,90 @@ impl MemoryManager {
         Ok((total_processed, total_embedded))
     }
 
+    /// Embed up to `batch_size` chunks that have no embedding yet.
+    /// Database errors are returned; a failed provider call is reported in
+    /// `EmbeddingBatch::error` so cache hits from the same batch still count.
+    async fn embed_next_batch(
+        &self,
+        provider: &dyn EmbeddingProvider,
+        batch_size: usize,
+    ) -> Result<EmbeddingBatch> {
+        let provider_id = provider.id().to_string();
+        let model = provider.model().to_string();
+
+        // Get chunks without embeddings
+        let chunks = self.index.chunks_without_embeddings(batch_size)?;
+        let mut batch = EmbeddingBatch {
+            fetched: chunks.len(),
+            ..Default::default()
+        };
+
+        // Separate chunks into cached and uncached
+        let mut to_embed: Vec<(String, String, String)> = Vec::new(); // (id, text, hash)
+        let mut from_cache: Vec<(String, Vec<f32>)> = Vec::new(); // (id, embedding)
+
+        for (chunk_id, text) in &chunks {
+            let text_hash = hash_text(text);
+
+            // Check cache first
+            if let Ok(Some(cached)) =
+                self.index
+                    .get_cached_embedding(&provider_id, &model, &text_hash)
+            {
+                from_cache.push((chunk_id.clone(), cached));
+                batch.cache_hits += 1;
+            } else {
+                to_embed.push((chunk_id.clone(), text.clone(), text_hash));
+            }
+        }
+
+        // Store cached embeddings
+        for (chunk_id, embedding) in from_cache {
+            if let Err(e) = self.index.store_embedding(&chunk_id, &embedding, &model) {
+                warn!(
+                    "Failed to store cached embedding for chunk {}: {}",
+                    chunk_id, e
+                );
+            } else {
+                batch.embedded += 1;
+            }
+        }
+
+        // Generate new embeddings for uncached chunks
+        if !to_embed.is_empty() {
+            let texts: Vec<String> = to_embed.iter().map(|(_, text, _)| text.clone()).collect();
+
+            match provider.embed_batch(&texts).await {
+                Ok(embeddings) => {
+                    for ((chunk_id, _text, text_hash), embedding) in
+                        to_embed.iter().zip(embeddings.iter())
+                    {
+                        // Store in chunk
+                        if let Err(e) = self.index.store_embedding(chunk_id, embedding, &model) {
+                            warn!("Failed to store embedding for chunk {}: {}", chunk_id, e);
+                        } else {
+                            batch.embedded += 1;
+                        }
+
+                        // Store in cache for future reuse
+                        if let Err(e) = self.index.cache_embedding(
+                            &provider_id,
+                            &model,
+                            "", // provider_key (API key identifier, can be empty)
+                            text_hash,
+                            embedding,
+                        ) {
+                            debug!("Failed to cache embedding: {}", e);
+                        }
+                    }
+                }
+                Err(e) => batch.error = Some(e.to_string()),
+            }
+        }
+
+        Ok(batch)
+    }
+
     /// Get count of chunks with embeddings
     pub fn embedded_chunk_count(&self) -> Result<usize> {
         let model = self
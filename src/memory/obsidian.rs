@@ -0,0 +1,211 @@
+//! Obsidian vault layout
+//!
+//! With `[memory.obsidian] enabled = true`, `memory.workspace` can point at
+//! an existing Obsidian vault and stay browsable in Obsidian. Daily notes
+//! live where Obsidian's Daily notes plugin puts them,
+//! `<daily_folder>/<date>.md` with `daily_format` (a chrono format, the
+//! equivalent of the plugin's date format), and start with YAML front
+//! matter. Every note the assistant writes (topic logs, saved sessions,
+//! Discord session summaries) is linked from the day's daily note under
+//! `## Notes` with a `[[wikilink]]`, so the daily note lists what was
+//! recorded that day and each note has the day among its backlinks.
+//! Obsidian's own folders (`.obsidian`, `.trash`) are not indexed.
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use super::history::write_memory_file;
+use crate::config::ObsidianConfig;
+
+/// Section of the daily note that links the day's notes
+const NOTES_HEADING: &str = "## Notes";
+
+#[derive(Debug, Clone)]
+pub struct Vault {
+    root: PathBuf,
+    daily_folder: String,
+    daily_format: String,
+}
+
+impl Vault {
+    /// The vault at `workspace`, if the Obsidian layout is enabled
+    pub fn from_config(workspace: &Path, config: &ObsidianConfig) -> Option<Self> {
+        config.enabled.then(|| Self {
+            root: workspace.to_path_buf(),
+            daily_folder: config.daily_folder.trim_matches('/').to_string(),
+            daily_format: config.daily_format.clone(),
+        })
+    }
+
+    /// Path of the daily note for `date`
+    pub fn daily_note(&self, date: NaiveDate) -> PathBuf {
+        let name = format!("{}.md", date.format(&self.daily_format));
+        if self.daily_folder.is_empty() {
+            self.root.join(name)
+        } else {
+            self.root.join(&self.daily_folder).join(name)
+        }
+    }
+
+    /// A note's wikilink target: its vault-relative path without `.md`
+    pub fn link_target(&self, note: &Path) -> String {
+        let relative = note.strip_prefix(&self.root).unwrap_or(note);
+        let target = relative.with_extension("");
+        target
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Link `note` from the daily note for `date`, creating the daily note
+    /// if needed. Returns false if it was already linked.
+    pub fn link_from_daily_note(&self, date: NaiveDate, note: &Path, title: &str) -> Result<bool> {
+        let daily = self.daily_note(date);
+        let target = self.link_target(note);
+        let existing = fs::read_to_string(&daily).unwrap_or_default();
+        if existing.contains(&format!("[[{}|", target))
+            || existing.contains(&format!("[[{}]]", target))
+        {
+            return Ok(false);
+        }
+
+        let existing = if existing.trim().is_empty() {
+            format!(
+                "---\ndate: {}\ntags:\n  - daily\n---\n",
+                date.format("%Y-%m-%d")
+            )
+        } else {
+            existing
+        };
+        let title = title.trim().replace(['[', ']', '|'], "");
+        let content = add_to_notes_section(&existing, &wikilink(&target, &title));
+        write_memory_file(&self.root, &daily, &content, "daily_note")?;
+        Ok(true)
+    }
+
+    /// Whether a path is inside one of Obsidian's hidden folders
+    pub fn is_hidden(&self, path: &Path) -> bool {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .components()
+            .any(
+                |c| matches!(c, Component::Normal(name) if name.to_string_lossy().starts_with('.')),
+            )
+    }
+
+    /// Conventions for the system prompt
+    pub fn prompt_section(&self, date: NaiveDate) -> String {
+        let daily = self.link_target(&self.daily_note(date));
+        format!(
+            "## Obsidian Vault\n\
+             The workspace is an Obsidian vault. Today's daily note is {}.md. \
+             Link related notes with [[wikilinks]] (vault-relative path without .md, \
+             e.g. [[{}]]) and keep the YAML front matter at the top of notes intact.",
+            daily, daily
+        )
+    }
+}
+
+/// `[[target]]`, or `[[target|title]]` when the title differs
+pub fn wikilink(target: &str, title: &str) -> String {
+    let name = target.rsplit('/').next().unwrap_or(target);
+    if title.is_empty() || title == name {
+        format!("[[{}]]", target)
+    } else {
+        format!("[[{}|{}]]", target, title)
+    }
+}
+
+/// Add a list item to the end of the `## Notes` section, adding the
+/// section at the end of the note if it has none
+fn add_to_notes_section(content: &str, link: &str) -> String {
+    let item = format!("- {}\n", link);
+    let Some(start) = content
+        .match_indices(NOTES_HEADING)
+        .find(|(i, _)| {
+            let after = &content[i + NOTES_HEADING.len()..];
+            (*i == 0 || content[..*i].ends_with('\n'))
+                && (after.is_empty() || after.starts_with('\n'))
+        })
+        .map(|(i, _)| i + NOTES_HEADING.len())
+    else {
+        return format!("{}\n\n{}\n\n{}", content.trim_end(), NOTES_HEADING, item);
+    };
+    let end = content[start..]
+        .find("\n## ")
+        .map_or(content.len(), |i| start + i + 1);
+    let section = content[start..end].trim_end();
+    let separator = if section.is_empty() { "\n\n" } else { "\n" };
+    let rest = &content[end..];
+    format!(
+        "{}{}{}{}{}",
+        &content[..start],
+        section,
+        separator,
+        item,
+        if rest.is_empty() {
+            String::new()
+        } else {
+            format!("\n{}", rest)
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_daily_note_links() {
+        let dir = TempDir::new().unwrap();
+        let config = ObsidianConfig {
+            enabled: true,
+            daily_folder: "Daily/".to_string(),
+            daily_format: "%Y-%m-%d".to_string(),
+        };
+        let vault = Vault::from_config(dir.path(), &config).unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let daily = vault.daily_note(date);
+        assert_eq!(daily, dir.path().join("Daily/2026-10-16.md"));
+
+        let topic = dir.path().join("memory/2026-10-16/home-network.md");
+        assert_eq!(vault.link_target(&topic), "memory/2026-10-16/home-network");
+        assert!(
+            vault
+                .link_from_daily_note(date, &topic, "Home network")
+                .unwrap()
+        );
+        assert!(
+            !vault
+                .link_from_daily_note(date, &topic, "Home network")
+                .unwrap()
+        );
+        let text = fs::read_to_string(&daily).unwrap();
+        assert!(text.starts_with("---\ndate: 2026-10-16\n"));
+        assert!(text.ends_with("## Notes\n\n- [[memory/2026-10-16/home-network|Home network]]\n"));
+
+        // Links go to the end of the section, other sections are kept
+        fs::write(
+            &daily,
+            "# Friday\n\n## Notes\n\n- [[Groceries]]\n\n## Journal\n\nQuiet day.\n",
+        )
+        .unwrap();
+        let session = dir.path().join("memory/2026-10-16-router.md");
+        vault
+            .link_from_daily_note(date, &session, "2026-10-16-router")
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&daily).unwrap(),
+            "# Friday\n\n## Notes\n\n- [[Groceries]]\n- [[memory/2026-10-16-router]]\n\n\
+             ## Journal\n\nQuiet day.\n"
+        );
+
+        assert!(vault.is_hidden(&dir.path().join(".obsidian/workspace.md")));
+        assert!(!vault.is_hidden(&topic));
+        assert!(Vault::from_config(dir.path(), &ObsidianConfig::default()).is_none());
+    }
+}
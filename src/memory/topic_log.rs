@@ -0,0 +1,245 @@
+//! Topic-segmented daily logs
+//!
+//! Instead of one chronological `memory/YYYY-MM-DD.md`, the agent files
+//! notes under a topic it names with `memory_log`. Each topic gets its own
+//! file per day, `memory/YYYY-MM-DD/<topic>.md`, starting with YAML front
+//! matter (topic, channel, participants, tags) that is merged as entries
+//! are appended. Search results then point at a single topic, and the
+//! nightly reflection can consolidate a day topic by topic.
+
+use anyhow::Result;
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::history::write_memory_file;
+use crate::security::redact;
+
+/// Topic files of a day read into the context, at most
+const MAX_TOPICS_PER_DAY: usize = 20;
+
+/// Front matter of a topic log
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TopicMeta {
+    pub topic: String,
+    pub date: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub participants: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// Where the current conversation happens, set by the channel before each
+/// turn and shared with `memory_log`
+#[derive(Clone, Default)]
+pub struct LogOrigin(Arc<Mutex<LogContext>>);
+
+#[derive(Debug, Clone, Default)]
+pub struct LogContext {
+    /// e.g. `discord:<channel_id>`
+    pub channel: Option<String>,
+    pub participants: Vec<String>,
+    /// Message being answered, recorded with `save_memory` entries
+    pub message_id: Option<String>,
+}
+
+impl LogOrigin {
+    pub fn set(&self, context: LogContext) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = context;
+    }
+
+    pub fn get(&self) -> LogContext {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// File name for a topic: lowercase words joined by dashes
+pub fn topic_slug(topic: &str) -> String {
+    let slug = topic
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug: String = slug.chars().take(50).collect();
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "general".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Directory holding a day's topic logs
+pub fn day_dir(workspace: &Path, date: NaiveDate) -> PathBuf {
+    workspace
+        .join("memory")
+        .join(date.format("%Y-%m-%d").to_string())
+}
+
+/// Split a topic log into its front matter and body
+pub fn parse_topic_log(text: &str) -> Option<(TopicMeta, &str)> {
+    let rest = text.strip_prefix("---\n")?;
+    let end = rest.find("\n---\n")?;
+    let meta = serde_yaml::from_str(&rest[..end]).ok()?;
+    Some((meta, &rest[end + 5..]))
+}
+
+fn render_topic_log(meta: &TopicMeta, body: &str) -> Result<String> {
+    Ok(format!(
+        "---\n{}---\n\n{}",
+        serde_yaml::to_string(meta)?,
+        body
+    ))
+}
+
+fn merge_unique(into: &mut Vec<String>, values: &[String]) {
+    for value in values {
+        let value = value.trim();
+        if !value.is_empty() && !into.iter().any(|v| v.eq_ignore_ascii_case(value)) {
+            into.push(value.to_string());
+        }
+    }
+}
+
+/// Append an entry to today's log for `topic`, creating it or merging the
+/// participants and tags into its front matter
+pub fn append_entry(
+    workspace: &Path,
+    topic: &str,
+    content: &str,
+    tags: &[String],
+    context: &LogContext,
+) -> Result<PathBuf> {
+    let now = Local::now();
+    let dir = day_dir(workspace, now.date_naive());
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.md", topic_slug(topic)));
+
+    let content = redact("memory", &path.display().to_string(), content);
+
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    let (mut meta, body) = match parse_topic_log(&existing) {
+        Some((meta, body)) => (meta, body.to_string()),
+        None => (
+            TopicMeta {
+                topic: topic.trim().to_string(),
+                date: now.format("%Y-%m-%d").to_string(),
+                ..Default::default()
+            },
+            // A file edited by hand without front matter keeps its text
+            existing,
+        ),
+    };
+    if meta.channel.is_none() {
+        meta.channel = context.channel.clone();
+    }
+    merge_unique(&mut meta.participants, &context.participants);
+    merge_unique(&mut meta.tags, tags);
+
+    let body = format!(
+        "{}\n\n## {}\n\n{}\n",
+        body.trim_end(),
+        now.format("%H:%M"),
+        content.trim()
+    );
+    write_memory_file(
+        workspace,
+        &path,
+        &render_topic_log(&meta, body.trim_start())?,
+        "memory_log",
+    )?;
+    Ok(path)
+}
+
+/// A day's topic logs as (workspace-relative name, content), sorted by name
+pub fn read_day(workspace: &Path, date: NaiveDate) -> Vec<(String, String)> {
+    let dir = day_dir(workspace, date);
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "md"))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .take(MAX_TOPICS_PER_DAY)
+        .filter_map(|path| {
+            let content = super::file_cache::read_cached(&path).ok()?;
+            let name = format!(
+                "memory/{}/{}",
+                date.format("%Y-%m-%d"),
+                path.file_name()?.to_string_lossy()
+            );
+            Some((name, content.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_topic_slug() {
+        assert_eq!(
+            topic_slug("Home network: new router"),
+            "home-network-new-router"
+        );
+        assert_eq!(topic_slug("  ???  "), "general");
+    }
+
+    #[test]
+    fn test_entries_merge_front_matter() {
+        let dir = TempDir::new().unwrap();
+        let context = LogContext {
+            channel: Some("discord:42".to_string()),
+            participants: vec!["alice".to_string()],
+            ..Default::default()
+        };
+        append_entry(
+            dir.path(),
+            "Home network",
+            "Router replaced.",
+            &["router".to_string()],
+            &context,
+        )
+        .unwrap();
+
+        let context = LogContext {
+            channel: Some("discord:7".to_string()),
+            participants: vec!["bob".to_string(), "Alice".to_string()],
+            ..Default::default()
+        };
+        let path = append_entry(
+            dir.path(),
+            "home network",
+            "Wi-Fi password changed.",
+            &["wifi".to_string(), "Router".to_string()],
+            &context,
+        )
+        .unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        let (meta, body) = parse_topic_log(&text).unwrap();
+        assert_eq!(meta.topic, "Home network");
+        assert_eq!(meta.channel.as_deref(), Some("discord:42"));
+        assert_eq!(meta.participants, vec!["alice", "bob"]);
+        assert_eq!(meta.tags, vec!["router", "wifi"]);
+        assert!(body.contains("Router replaced.\n\n## "));
+        assert!(body.ends_with("Wi-Fi password changed.\n"));
+
+        let day = read_day(dir.path(), Local::now().date_naive());
+        assert_eq!(day.len(), 1);
+        assert!(day[0].0.ends_with("/home-network.md"));
+    }
+}
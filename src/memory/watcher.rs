@@ -1,13 +1,17 @@
 //! File system watcher for automatic memory reindexing
+//!
+//! Changes to workspace files are also recorded in the memory history,
+//! including deletions, so edits made outside the agent can be rolled back.
 
 use anyhow::Result;
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
 use std::path::PathBuf;
 use std::sync::mpsc;
 use std::time::Duration;
 use tracing::{debug, info, warn};
 
-use super::MemoryIndex;
+use super::{MemoryHistory, MemoryIndex};
 use crate::config::MemoryConfig;
 
 pub struct MemoryWatcher {
@@ -28,9 +32,9 @@ impl MemoryWatcher {
         let mut watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
             match res {
                 Ok(event) => {
-                    // Filter for modify/create events on .md files
+                    // Filter for modify/create/remove events on .md files
                     match event.kind {
-                        EventKind::Modify(_) | EventKind::Create(_) => {
+                        EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_) => {
                             for path in event.paths {
                                 if path.extension().map(|e| e == "md").unwrap_or(false)
                                     && let Err(e) = tx.send(path.clone())
@@ -92,6 +96,14 @@ impl MemoryWatcher {
                 }
             };
 
+            let history = match MemoryHistory::for_workspace(&workspace_for_task) {
+                Ok(history) => Some(history),
+                Err(e) => {
+                    warn!("Failed to open memory history for watcher: {}", e);
+                    None
+                }
+            };
+
             // Debounce events
             let debounce_duration = Duration::from_secs(2);
 
@@ -101,11 +113,13 @@ impl MemoryWatcher {
                         debug!("File changed: {}", path.display());
 
                         // Debounce: wait for events to settle
+                        let mut changed = BTreeSet::from([path]);
                         let mut last_event_time = std::time::Instant::now();
                         while last_event_time.elapsed() < debounce_duration {
                             match rx.recv_timeout(debounce_duration - last_event_time.elapsed()) {
                                 Ok(p) => {
                                     debug!("Additional file changed: {}", p.display());
+                                    changed.insert(p);
                                     last_event_time = std::time::Instant::now();
                                 }
                                 Err(mpsc::RecvTimeoutError::Timeout) => break,
@@ -113,11 +127,24 @@ impl MemoryWatcher {
                             }
                         }
 
-                        // Reindex the file
-                        if let Err(e) = index.index_file(&path, false) {
-                            warn!("Failed to reindex file {}: {}", path.display(), e);
-                        } else {
-                            info!("Reindexed: {}", path.display());
+                        for path in changed {
+                            let content = std::fs::read_to_string(&path).ok();
+                            if let Some(ref history) = history
+                                && path.starts_with(&workspace_for_task)
+                                && let Err(e) = history.record(&path, content.as_deref(), "watcher")
+                            {
+                                warn!("Failed to record {} in history: {}", path.display(), e);
+                            }
+                            if content.is_none() {
+                                continue;
+                            }
+
+                            // Reindex the file
+                            if let Err(e) = index.index_file(&path, false) {
+                                warn!("Failed to reindex file {}: {}", path.display(), e);
+                            } else {
+                                info!("Reindexed: {}", path.display());
+                            }
                         }
                     }
                     Err(mpsc::RecvTimeoutError::Timeout) => continue,
@@ -0,0 +1,353 @@
+//! System resource monitor
+//!
+//! The daemon samples CPU, RAM and, when `nvidia-smi` is available, GPU and
+//! VRAM usage every `monitor.interval`. The latest sample is served at
+//! `GET /metrics` (Prometheus text format) and `GET /api/status`; the
+//! desktop status view takes its own samples. While the machine is busy,
+//! i.e. usage is above one of the `monitor.busy_*` thresholds, the job
+//! runner leaves queued jobs waiting and the heartbeat skips its run, so
+//! background work doesn't compete with someone using the machine.
+//!
+//! CPU and RAM are read from `/proc`, so they are only reported on Linux.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::sync::RwLock;
+use tracing::{debug, info};
+
+use crate::config::{Config, MonitorConfig, parse_duration};
+use crate::heartbeat::now_ms;
+
+/// Samples older than this many intervals are ignored (the monitor stopped)
+const STALE_INTERVALS: u64 = 3;
+
+/// One GPU's usage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GpuSample {
+    pub name: String,
+    pub utilization_percent: f32,
+    pub vram_used_mb: u64,
+    pub vram_total_mb: u64,
+}
+
+/// Resource usage at one point in time
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceSample {
+    /// Timestamp in milliseconds
+    pub ts: u64,
+    /// CPU busy time since the previous sample, all cores
+    pub cpu_percent: Option<f32>,
+    pub memory_used_mb: Option<u64>,
+    pub memory_total_mb: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gpus: Vec<GpuSample>,
+}
+
+impl ResourceSample {
+    pub fn memory_percent(&self) -> Option<f32> {
+        match (self.memory_used_mb, self.memory_total_mb) {
+            (Some(used), Some(total)) if total > 0 => Some(used as f32 * 100.0 / total as f32),
+            _ => None,
+        }
+    }
+
+    /// Why the machine counts as busy under `config`, if it does
+    pub fn busy_reason(&self, config: &MonitorConfig) -> Option<String> {
+        let over = |value: f32, limit: u8| limit > 0 && value >= f32::from(limit);
+        if let Some(cpu) = self.cpu_percent
+            && over(cpu, config.busy_cpu_percent)
+        {
+            return Some(format!("CPU at {:.0}%", cpu));
+        }
+        if let Some(memory) = self.memory_percent()
+            && over(memory, config.busy_memory_percent)
+        {
+            return Some(format!("RAM at {:.0}%", memory));
+        }
+        self.gpus
+            .iter()
+            .find(|gpu| over(gpu.utilization_percent, config.busy_gpu_percent))
+            .map(|gpu| format!("GPU {} at {:.0}%", gpu.name, gpu.utilization_percent))
+    }
+}
+
+/// The daemon's latest sample
+static LATEST: RwLock<Option<ResourceSample>> = RwLock::new(None);
+
+/// The latest sample taken by the daemon's monitor
+pub fn latest_sample() -> Option<ResourceSample> {
+    LATEST.read().ok().and_then(|guard| guard.clone())
+}
+
+fn store_sample(sample: ResourceSample) {
+    if let Ok(mut guard) = LATEST.write() {
+        *guard = Some(sample);
+    }
+}
+
+/// Why background work should wait right now, if it should. Without a
+/// recent sample (monitor disabled or stopped) nothing waits.
+pub fn busy_reason(config: &Config) -> Option<String> {
+    if !config.monitor.enabled {
+        return None;
+    }
+    let interval = parse_duration(&config.monitor.interval).ok()?;
+    let sample = latest_sample()?;
+    let max_age = interval.as_millis() as u64 * STALE_INTERVALS;
+    if now_ms().saturating_sub(sample.ts) > max_age {
+        return None;
+    }
+    sample.busy_reason(&config.monitor)
+}
+
+/// Takes samples; CPU usage is measured between consecutive samples
+pub struct Sampler {
+    /// Idle and total CPU time at the previous sample
+    last_cpu: Option<(u64, u64)>,
+}
+
+impl Sampler {
+    pub fn new() -> Self {
+        Self {
+            last_cpu: read_cpu_times(),
+        }
+    }
+
+    /// Sample now. Runs `nvidia-smi`, so call it off the async runtime.
+    pub fn sample(&mut self) -> ResourceSample {
+        let cpu = read_cpu_times();
+        let cpu_percent = match (self.last_cpu, cpu) {
+            (Some(last), Some(now)) => cpu_percent(last, now),
+            _ => None,
+        };
+        self.last_cpu = cpu;
+
+        let memory = std::fs::read_to_string("/proc/meminfo")
+            .ok()
+            .and_then(|text| parse_meminfo(&text));
+        ResourceSample {
+            ts: now_ms(),
+            cpu_percent,
+            memory_used_mb: memory.map(|(used, _)| used),
+            memory_total_mb: memory.map(|(_, total)| total),
+            gpus: read_gpus(),
+        }
+    }
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sample every `monitor.interval` for the daemon's lifetime
+pub async fn run(config: Config) -> Result<()> {
+    let interval = parse_duration(&config.monitor.interval)
+        .map_err(|e| anyhow::anyhow!("Invalid monitor interval: {}", e))?;
+    info!(
+        "Resource monitor sampling every {}",
+        config.monitor.interval
+    );
+
+    let mut sampler = Sampler::new();
+    loop {
+        tokio::time::sleep(interval).await;
+        let (returned, sample) = tokio::task::spawn_blocking(move || {
+            let sample = sampler.sample();
+            (sampler, sample)
+        })
+        .await?;
+        sampler = returned;
+        debug!(
+            "Resources: CPU {:?}%, RAM {:?}%",
+            sample.cpu_percent,
+            sample.memory_percent()
+        );
+        store_sample(sample);
+    }
+}
+
+/// Idle and total CPU time from `/proc/stat`
+fn read_cpu_times() -> Option<(u64, u64)> {
+    parse_proc_stat(&std::fs::read_to_string("/proc/stat").ok()?)
+}
+
+fn parse_proc_stat(text: &str) -> Option<(u64, u64)> {
+    let line = text.lines().find(|l| l.starts_with("cpu "))?;
+    let times: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|v| v.parse().ok())
+        .collect();
+    // user nice system idle iowait irq softirq steal ...
+    let idle = times.get(3)? + times.get(4).unwrap_or(&0);
+    Some((idle, times.iter().sum()))
+}
+
+fn cpu_percent(last: (u64, u64), now: (u64, u64)) -> Option<f32> {
+    let total = now.1.checked_sub(last.1).filter(|&t| t > 0)?;
+    let idle = now.0.saturating_sub(last.0).min(total);
+    Some((total - idle) as f32 * 100.0 / total as f32)
+}
+
+/// Used and total RAM in MB from `/proc/meminfo`
+fn parse_meminfo(text: &str) -> Option<(u64, u64)> {
+    let field = |name: &str| {
+        text.lines()
+            .find(|l| l.starts_with(name))
+            .and_then(|l| l.split_whitespace().nth(1))
+            .and_then(|v| v.parse::<u64>().ok())
+    };
+    let total = field("MemTotal:")?;
+    let available = field("MemAvailable:")?;
+    Some((total.saturating_sub(available) / 1024, total / 1024))
+}
+
+/// NVIDIA GPUs; empty when `nvidia-smi` isn't installed
+fn read_gpus() -> Vec<GpuSample> {
+    let output = Command::new("nvidia-smi")
+        .args([
+            "--query-gpu=name,utilization.gpu,memory.used,memory.total",
+            "--format=csv,noheader,nounits",
+        ])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_nvidia_smi(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn parse_nvidia_smi(text: &str) -> Vec<GpuSample> {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [name, utilization, used, total] = fields[..] else {
+                return None;
+            };
+            Some(GpuSample {
+                name: name.to_string(),
+                utilization_percent: utilization.parse().ok()?,
+                vram_used_mb: used.parse().ok()?,
+                vram_total_mb: total.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// A sample in the Prometheus text exposition format
+pub fn prometheus_text(sample: &ResourceSample) -> String {
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, values: Vec<(String, f64)>| {
+        if values.is_empty() {
+            return;
+        }
+        out.push_str(&format!(
+            "# HELP {} {}\n# TYPE {} gauge\n",
+            name, help, name
+        ));
+        for (labels, value) in values {
+            out.push_str(&format!("{}{} {}\n", name, labels, value));
+        }
+    };
+    let single = |value: Option<f64>| value.map(|v| vec![(String::new(), v)]).unwrap_or_default();
+    let per_gpu = |value: fn(&GpuSample) -> f64| {
+        sample
+            .gpus
+            .iter()
+            .enumerate()
+            .map(|(i, gpu)| {
+                let name = gpu.name.replace('\\', "\\\\").replace('"', "\\\"");
+                (format!("{{gpu=\"{}\",name=\"{}\"}}", i, name), value(gpu))
+            })
+            .collect::<Vec<_>>()
+    };
+    const MB: f64 = 1024.0 * 1024.0;
+
+    gauge(
+        "localgpt_cpu_usage_percent",
+        "CPU busy time across all cores",
+        single(sample.cpu_percent.map(f64::from)),
+    );
+    gauge(
+        "localgpt_memory_used_bytes",
+        "RAM in use",
+        single(sample.memory_used_mb.map(|mb| mb as f64 * MB)),
+    );
+    gauge(
+        "localgpt_memory_total_bytes",
+        "Installed RAM",
+        single(sample.memory_total_mb.map(|mb| mb as f64 * MB)),
+    );
+    gauge(
+        "localgpt_gpu_utilization_percent",
+        "GPU utilization",
+        per_gpu(|gpu| f64::from(gpu.utilization_percent)),
+    );
+    gauge(
+        "localgpt_gpu_memory_used_bytes",
+        "VRAM in use",
+        per_gpu(|gpu| gpu.vram_used_mb as f64 * MB),
+    );
+    gauge(
+        "localgpt_gpu_memory_total_bytes",
+        "Installed VRAM",
+        per_gpu(|gpu| gpu.vram_total_mb as f64 * MB),
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_thresholds() {
+        let before = parse_proc_stat("cpu  100 0 100 700 100 0 0 0 0 0\ncpu0 1 2 3\n").unwrap();
+        let after = parse_proc_stat("cpu  400 0 200 900 100 0 0 0 0 0\n").unwrap();
+        assert_eq!(before, (800, 1000));
+        let busy = cpu_percent(before, after).unwrap();
+        assert!((busy - 66.7).abs() < 0.1, "{}", busy);
+        assert_eq!(cpu_percent(after, after), None);
+
+        let meminfo =
+            "MemTotal:       16384000 kB\nMemFree:  1000 kB\nMemAvailable:    4096000 kB\n";
+        assert_eq!(parse_meminfo(meminfo), Some((12000, 16000)));
+
+        let gpus = parse_nvidia_smi("NVIDIA GeForce RTX 4090, 97, 20000, 24564\nbad line\n");
+        assert_eq!(gpus.len(), 1);
+        assert_eq!(gpus[0].vram_total_mb, 24564);
+
+        let mut sample = ResourceSample {
+            ts: 0,
+            cpu_percent: Some(10.0),
+            memory_used_mb: Some(12000),
+            memory_total_mb: Some(16000),
+            gpus,
+        };
+        let config = MonitorConfig::default();
+        assert_eq!(
+            sample.busy_reason(&config).unwrap(),
+            "GPU NVIDIA GeForce RTX 4090 at 97%"
+        );
+        sample.gpus[0].utilization_percent = 5.0;
+        assert_eq!(sample.busy_reason(&config), None);
+        sample.cpu_percent = Some(95.0);
+        assert_eq!(sample.busy_reason(&config).unwrap(), "CPU at 95%");
+        let never = MonitorConfig {
+            busy_cpu_percent: 0,
+            ..MonitorConfig::default()
+        };
+        assert_eq!(sample.busy_reason(&never), None);
+
+        let text = prometheus_text(&sample);
+        assert!(text.contains("localgpt_cpu_usage_percent 95\n"));
+        assert!(text.contains(
+            "localgpt_gpu_utilization_percent{gpu=\"0\",name=\"NVIDIA GeForce RTX 4090\"} 5\n"
+        ));
+        assert!(prometheus_text(&ResourceSample::default()).is_empty());
+    }
+}
@@ -21,6 +21,9 @@
 //! The first entry uses `000...000` (64 zeros) as `prev_entry_sha256`.
 //! Every subsequent entry hashes the raw bytes of the previous line.
 //! A broken chain indicates the log file was tampered with.
+//!
+//! `source` and `detail` are redacted before they are written (see
+//! [`super::redact`]), so a secret quoted in a detail never reaches the log.
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
@@ -29,6 +32,8 @@ use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
+use super::redact::redact;
+
 const AUDIT_FILENAME: &str = ".security_audit.jsonl";
 
 /// The hash used for the first entry in the chain (no predecessor).
@@ -78,6 +83,10 @@ pub enum AuditAction {
     WriteBlocked,
     /// Previous audit entry corrupted, new chain segment started.
     ChainRecovery,
+    /// A chat user went over their message quota.
+    RateLimited,
+    /// A reply was generated as part of an A/B experiment (detail names the variant).
+    ExperimentVariant,
 }
 
 /// Append a new entry to the audit log.
@@ -146,13 +155,14 @@ pub fn append_audit_entry_with_detail(
         GENESIS_HASH.to_string()
     };
 
+    let location = path.display().to_string();
     let entry = AuditEntry {
         ts: chrono::Utc::now().to_rfc3339(),
         action,
         content_sha256: content_sha256.to_string(),
         prev_entry_sha256: prev_hash,
-        source: source.to_string(),
-        detail: detail.map(|d| d.to_string()),
+        source: redact("audit", &location, source).into_owned(),
+        detail: detail.map(|d| redact("audit", &location, d).into_owned()),
     };
 
     let json = serde_json::to_string(&entry).context("Failed to serialize audit entry")?;
@@ -99,6 +99,7 @@
 //! | Policy weakens hardcoded rules | Hardcoded suffix always last in context |
 //! | Policy floods context window | 4096 char limit |
 //! | Audit log tampered | Hash chain + state dir location |
+//! | Secrets leak into logs, memory or the audit log | Redaction (`redact.rs`) |
 
 // ── Policy Verification ─────────────────────────────────────────────
 
@@ -127,6 +128,12 @@ pub use super::protected_files::{
     is_workspace_file_protected,
 };
 
+// ── Secret Redaction ────────────────────────────────────────────────
+
+pub use super::redact::{
+    REDACTED, Redaction, Redactor, install_redactor, redact, redaction_report_path,
+};
+
 // ── Context Window Suffix ───────────────────────────────────────────
 
 pub use super::suffix::{HARDCODED_SECURITY_SUFFIX, build_ending_security_block};
@@ -7,6 +7,7 @@ mod audit;
 mod localgpt;
 mod policy;
 mod protected_files;
+mod redact;
 mod signing;
 mod suffix;
 
@@ -7,15 +7,16 @@ use anyhow::Result;
 use axum::{
     Router,
     extract::{
-        Path, Query, State,
+        Extension, Path, Query, State,
         ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
     },
-    http::{StatusCode, header},
+    http::{HeaderMap, StatusCode, header},
+    middleware,
     response::{
-        IntoResponse, Json, Response,
+        IntoResponse, Json, Redirect, Response,
         sse::{Event, Sse},
     },
-    routing::{delete, get, post},
+    routing::{delete, get, patch, post, put},
 };
 use futures::{SinkExt, StreamExt};
 use rust_embed::RustEmbed;
@@ -30,12 +31,27 @@ use tokio::sync::Mutex;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::{debug, info};
 
-use crate::agent::{Agent, AgentConfig, StreamEvent, extract_tool_detail};
+use crate::agent::{
+    Agent, AgentConfig, ChannelKind, CircuitState, ExportFormat, ExportOptions, FeedbackStore,
+    PromptStore, ReplyPipeline, ReserveStore, Session, StreamEvent, circuit_statuses,
+    export_file_name, export_session, extract_tool_detail,
+};
 use crate::concurrency::{TurnGate, WorkspaceLock};
-use crate::config::Config;
-use crate::discord::SharedAgentMap;
-use crate::heartbeat::{HeartbeatStatus, get_last_heartbeat_event};
-use crate::memory::MemoryManager;
+use crate::config::{Config, parse_duration};
+use crate::db;
+use crate::discord::{SharedAgentMap, settings, shadow};
+use crate::features::FeatureStore;
+use crate::health::{HealthState, latest_statuses};
+use crate::heartbeat::{
+    HeartbeatEvent, HeartbeatStatus, get_last_heartbeat_event, is_heartbeat_paused,
+    load_heartbeat_history,
+};
+use crate::memory::{MemoryHistory, MemoryManager, MemoryVersion};
+use crate::monitor::{ResourceSample, latest_sample, prometheus_text};
+use crate::tasks::{Task, TaskStatus, TaskStore, TaskUpdate, check_title};
+
+use super::proxy::{self, ClientIp, PeerAddr, TrustedProxies};
+use super::tls::{self, TlsListener};
 
 /// Embedded UI assets
 #[derive(RustEmbed)]
@@ -51,6 +67,9 @@ const MAX_SESSIONS: usize = 100;
 /// Agent ID for HTTP sessions
 const HTTP_AGENT_ID: &str = "http";
 
+/// Agent whose heartbeat the dashboard reports
+const HEARTBEAT_AGENT_ID: &str = "main";
+
 pub struct Server {
     config: Config,
     turn_gate: TurnGate,
@@ -75,6 +94,12 @@ struct AppState {
     workspace_lock: WorkspaceLock,
     /// Shared Discord agent map (channel_id → Agent), if Discord is enabled
     discord_agents: Option<SharedAgentMap>,
+    /// Shared task store
+    tasks: TaskStore,
+    /// Prompts behind recent replies, for `/api/inspect`
+    prompts: PromptStore,
+    /// Runtime feature toggles
+    features: FeatureStore,
 }
 
 impl Server {
@@ -116,6 +141,9 @@ impl Server {
             turn_gate: self.turn_gate.clone(),
             workspace_lock,
             discord_agents: self.discord_agents.clone(),
+            tasks: TaskStore::open_default()?,
+            prompts: PromptStore::open_default()?,
+            features: FeatureStore::open_default()?,
         });
 
         // Load persisted sessions on startup
@@ -154,6 +182,7 @@ impl Server {
             .route("/ui/{*path}", get(serve_ui_file))
             // API routes
             .route("/health", get(health_check))
+            .route("/metrics", get(metrics))
             .route("/api/sessions", post(create_session))
             .route("/api/sessions", get(list_sessions))
             .route("/api/sessions/{session_id}", delete(delete_session))
@@ -167,26 +196,94 @@ impl Server {
             .route("/api/sessions/{session_id}/model", post(set_session_model))
             .route("/api/chat", post(chat))
             .route("/api/chat/stream", post(chat_stream))
+            .route("/api/inspect/{message_id}", get(inspect_prompt))
+            .route("/api/feedback", get(feedback_summary))
+            .route("/api/reserve", get(reserve_stats))
             .route("/api/ws", get(websocket_handler))
             .route("/api/memory/search", get(memory_search))
             .route("/api/memory/stats", get(memory_stats))
             .route("/api/memory/reindex", post(memory_reindex))
+            .route("/api/memory/embeddings", get(embedding_progress))
+            .route("/api/memory/history", get(memory_history))
+            .route("/api/memory/history/{id}", get(memory_version))
+            .route(
+                "/api/memory/history/{id}/restore",
+                post(restore_memory_version),
+            )
             .route("/api/status", get(status))
             .route("/api/config", get(get_config))
             .route("/api/heartbeat/status", get(heartbeat_status))
+            .route("/api/tasks", get(list_tasks))
+            .route("/api/tasks", post(create_task))
+            .route("/api/tasks/{id}", get(get_task))
+            .route("/api/tasks/{id}", patch(update_task))
+            .route("/api/tasks/{id}/complete", post(complete_task))
             .route("/api/saved-sessions", get(list_saved_sessions))
             .route("/api/saved-sessions/{session_id}", get(get_saved_session))
+            .route(
+                "/api/saved-sessions/{session_id}/export",
+                get(export_saved_session),
+            )
             .route("/api/logs/daemon", get(get_daemon_logs))
-            .layer(cors)
-            .with_state(state);
+            .route("/api/logging", get(get_logging))
+            .route("/api/logging", put(set_logging))
+            .route("/api/features", get(list_features))
+            .route("/api/features/events", get(feature_events))
+            .route("/api/features/{name}", put(set_feature))
+            .route("/api/discord/mirror", get(discord_mirror))
+            .route("/api/discord/shadow", get(get_discord_shadow))
+            .route("/api/discord/shadow", put(set_discord_shadow))
+            .route(
+                "/api/discord/settings/{guild_id}",
+                get(get_discord_settings),
+            )
+            .route("/api/discord/settings/{guild_id}", put(set_discord_setting));
+
+        // Read-only status page for wall displays
+        let app = if self.config.server.dashboard {
+            app.route("/dashboard", get(serve_dashboard))
+                .route("/api/dashboard", get(dashboard))
+        } else {
+            app
+        };
+        let app = app.layer(cors).with_state(state);
+
+        // Mount everything under the base path when behind a sub-path proxy
+        let base_path = self.config.server.normalized_base_path();
+        let app = if base_path.is_empty() {
+            app
+        } else {
+            let redirect_to = base_path.clone();
+            Router::new()
+                .route(
+                    &format!("{}/", base_path),
+                    get(move || async move { Redirect::permanent(&redirect_to) }),
+                )
+                .nest(&base_path, app)
+        };
+
+        let proxies = Arc::new(TrustedProxies::parse(&self.config.server.trusted_proxies)?);
+        let app = app.layer(middleware::from_fn_with_state(
+            proxies,
+            proxy::resolve_client_ip,
+        ));
+        let service = app.into_make_service_with_connect_info::<PeerAddr>();
 
         let addr: SocketAddr =
             format!("{}:{}", self.config.server.bind, self.config.server.port).parse()?;
-
-        info!("Starting HTTP server on http://{}", addr);
-
         let listener = tokio::net::TcpListener::bind(addr).await?;
-        axum::serve(listener, app).await?;
+
+        match self.config.server.tls {
+            Some(ref tls_config) => {
+                let acceptor = tls::load_acceptor(tls_config)?;
+                info!("Starting HTTPS server on {}", self.config.server.url());
+                axum::serve(TlsListener::new(listener, acceptor)?, service).await?;
+            }
+            None => {
+                info!("Starting HTTP server on {}", self.config.server.url());
+                axum::serve(listener, service).await?;
+            }
+        }
 
         Ok(())
     }
@@ -342,13 +439,71 @@ async fn get_or_create_session(
 }
 
 // Health check endpoint
-async fn health_check() -> &'static str {
-    "OK"
+#[derive(Deserialize)]
+struct HealthQuery {
+    /// Include the daemon's latest dependency checks
+    #[serde(default)]
+    detail: bool,
+}
+
+/// Liveness plus provider circuit state. Stays 200 while a provider is down
+/// so proxies don't restart the daemon over it.
+async fn health_check(Query(query): Query<HealthQuery>) -> Json<serde_json::Value> {
+    let providers = circuit_statuses();
+    let mut degraded = providers.iter().any(|p| p.state != CircuitState::Closed);
+    if !query.detail {
+        return Json(json!({
+            "status": if degraded { "degraded" } else { "ok" },
+            "providers": providers,
+        }));
+    }
+
+    let dependencies = latest_statuses();
+    degraded |= dependencies.iter().any(|d| d.state != HealthState::Up);
+    Json(json!({
+        "status": if degraded { "degraded" } else { "ok" },
+        "providers": providers,
+        "dependencies": dependencies,
+    }))
+}
+
+/// Resource usage from the daemon's monitor, for Prometheus
+async fn metrics() -> Response {
+    let body = latest_sample()
+        .map(|sample| prometheus_text(&sample))
+        .unwrap_or_default();
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response()
 }
 
 // Serve UI index.html at root
-async fn serve_ui_index() -> Response {
-    serve_ui_asset("index.html")
+async fn serve_ui_index(State(state): State<Arc<AppState>>) -> Response {
+    serve_ui_page(&state, "index.html")
+}
+
+// Serve the status dashboard page
+async fn serve_dashboard(State(state): State<Arc<AppState>>) -> Response {
+    serve_ui_page(&state, "dashboard.html")
+}
+
+// Helper to serve an HTML page, rewritten for the configured base path
+fn serve_ui_page(state: &AppState, page: &str) -> Response {
+    let base_path = state.config.server.normalized_base_path();
+    if base_path.is_empty() {
+        return serve_ui_asset(page);
+    }
+    match UiAssets::get(page) {
+        Some(content) => {
+            // Asset and API URLs in the page are absolute; prefix them
+            let html = String::from_utf8_lossy(&content.data)
+                .replace("\"/ui/", &format!("\"{}/ui/", base_path))
+                .replace(
+                    "name=\"localgpt-base\" content=\"\"",
+                    &format!("name=\"localgpt-base\" content=\"{}\"", base_path),
+                );
+            ([(header::CONTENT_TYPE, "text/html")], html).into_response()
+        }
+        None => (StatusCode::NOT_FOUND, "Not found").into_response(),
+    }
 }
 
 // Serve UI static files
@@ -378,6 +533,9 @@ struct StatusResponse {
     model: String,
     memory_chunks: usize,
     active_sessions: usize,
+    /// Latest resource sample (None until the monitor has sampled)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resources: Option<ResourceSample>,
 }
 
 async fn status(State(state): State<Arc<AppState>>) -> Json<StatusResponse> {
@@ -396,6 +554,7 @@ async fn status(State(state): State<Arc<AppState>>) -> Json<StatusResponse> {
         model: state.config.agent.default_model.clone(),
         memory_chunks: state.memory.chunk_count().unwrap_or(0),
         active_sessions: count,
+        resources: latest_sample(),
     })
 }
 
@@ -505,6 +664,8 @@ struct SessionStatusResponse {
     idle_seconds: u64,
     api_input_tokens: u64,
     api_output_tokens: u64,
+    /// Tokens kept free for the reply
+    reserve_tokens: usize,
 }
 
 async fn get_session_status(
@@ -525,6 +686,7 @@ async fn get_session_status(
                         idle_seconds: 0,
                         api_input_tokens: status.api_input_tokens,
                         api_output_tokens: status.api_output_tokens,
+                        reserve_tokens: agent.reserve_tokens(),
                     })
                     .into_response();
                 }
@@ -546,6 +708,7 @@ async fn get_session_status(
                 idle_seconds: entry.last_accessed.elapsed().as_secs(),
                 api_input_tokens: status.api_input_tokens,
                 api_output_tokens: status.api_output_tokens,
+                reserve_tokens: entry.agent.reserve_tokens(),
             })
             .into_response()
         }
@@ -766,6 +929,8 @@ struct ChatResponse {
     response: String,
     session_id: String,
     model: String,
+    /// ID of the request message, for `/api/inspect/{message_id}`
+    message_id: String,
 }
 
 async fn chat(State(state): State<Arc<AppState>>, Json(request): Json<ChatRequest>) -> Response {
@@ -825,10 +990,15 @@ async fn chat(State(state): State<Arc<AppState>>, Json(request): Json<ChatReques
     match result {
         Ok(response) => {
             entry.dirty = true;
+            let response = ReplyPipeline::for_channel(&state.config, ChannelKind::Http)
+                .process_joined(&response);
+            let message_id = uuid::Uuid::new_v4().to_string();
+            record_prompt(&state, &session_id, &message_id, &entry.agent, &response);
             Json(ChatResponse {
                 response,
                 session_id,
                 model: entry.agent.model().to_string(),
+                message_id,
             })
             .into_response()
         }
@@ -849,10 +1019,11 @@ async fn chat_stream(
 
     let state_clone = state.clone();
     let message = request.message.clone();
+    let message_id = uuid::Uuid::new_v4().to_string();
 
     let stream = async_stream::stream! {
         // Send session_id first
-        yield Ok::<Event, Infallible>(Event::default().data(json!({"type": "session", "session_id": session_id}).to_string()));
+        yield Ok::<Event, Infallible>(Event::default().data(json!({"type": "session", "session_id": session_id, "message_id": message_id}).to_string()));
 
         // Acquire in-process turn gate
         let _gate_permit = state_clone.turn_gate.acquire().await;
@@ -884,6 +1055,7 @@ async fn chat_stream(
         entry.dirty = true;
 
         // Use streaming with tools
+        let mut response = String::new();
         match entry.agent.chat_stream_with_tools(&message).await {
             Ok(event_stream) => {
                 use futures::StreamExt;
@@ -894,6 +1066,7 @@ async fn chat_stream(
                 while let Some(event) = pinned_stream.next().await {
                     match event {
                         Ok(StreamEvent::Content(content)) => {
+                            response.push_str(&content);
                             let data = json!({"type": "content", "delta": content});
                             yield Ok(Event::default().data(data.to_string()));
                         }
@@ -928,12 +1101,111 @@ async fn chat_stream(
             }
         }
 
+        if !response.is_empty() {
+            record_prompt(&state_clone, &session_id, &message_id, &entry.agent, &response);
+        }
+
         yield Ok(Event::default().data("[DONE]"));
     };
 
     Sse::new(stream).into_response()
 }
 
+/// Keep the prompt behind a reply for `/api/inspect`
+fn record_prompt(
+    state: &AppState,
+    session_id: &str,
+    message_id: &str,
+    agent: &Agent,
+    response: &str,
+) {
+    let Some(messages) = agent.last_prompt() else {
+        return;
+    };
+    if let Err(e) = state.prompts.record(
+        &format!("http:{}", session_id),
+        &[message_id.to_string()],
+        agent.model(),
+        messages,
+        response,
+    ) {
+        debug!("Failed to record prompt: {}", e);
+    }
+}
+
+// Prompt inspection endpoint
+async fn inspect_prompt(
+    State(state): State<Arc<AppState>>,
+    Path(message_id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(e) = require_admin(&state.config, &headers) {
+        return e.into_response();
+    }
+    match state.prompts.get(&message_id) {
+        Ok(Some(record)) => Json(record).into_response(),
+        Ok(None) => AppError(
+            StatusCode::NOT_FOUND,
+            format!("No recorded prompt for message {}", message_id),
+        )
+        .into_response(),
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// Reaction feedback endpoint
+#[derive(Deserialize)]
+struct FeedbackQuery {
+    /// Look-back window (default: a week)
+    days: Option<u32>,
+}
+
+async fn feedback_summary(Query(query): Query<FeedbackQuery>) -> Response {
+    let days = query.days.unwrap_or(7);
+    let since = chrono::Utc::now().timestamp() - i64::from(days) * 86_400;
+    match FeedbackStore::open_default().and_then(|store| store.summary(since)) {
+        Ok(scopes) => {
+            let positive: u64 = scopes.iter().map(|s| s.positive).sum();
+            let negative: u64 = scopes.iter().map(|s| s.negative).sum();
+            Json(json!({
+                "days": days,
+                "positive": positive,
+                "negative": negative,
+                "scopes": scopes,
+            }))
+            .into_response()
+        }
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// Reply length statistics behind the adaptive reserve
+async fn reserve_stats(State(state): State<Arc<AppState>>) -> Response {
+    let agent = &state.config.agent;
+    let adaptive = &agent.adaptive_reserve;
+    match ReserveStore::open_default().and_then(|store| store.all_stats(adaptive.percentile)) {
+        Ok(stats) => {
+            let scopes: Vec<_> = stats
+                .iter()
+                .map(|stats| {
+                    let reserve = adaptive
+                        .reserve_for(stats, agent.max_tokens)
+                        .unwrap_or(agent.reserve_tokens);
+                    json!({ "stats": stats, "reserve_tokens": reserve })
+                })
+                .collect();
+            Json(json!({
+                "adaptive": adaptive.enabled,
+                "configured_reserve_tokens": agent.reserve_tokens,
+                "percentile": adaptive.percentile,
+                "scopes": scopes,
+            }))
+            .into_response()
+        }
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
 // Memory search endpoint
 #[derive(Deserialize)]
 struct SearchQuery {
@@ -1018,6 +1290,14 @@ fn memory_stats_inner(memory: &MemoryManager) -> Result<StatsResponse, anyhow::E
     })
 }
 
+// Embedding backfill progress endpoint
+async fn embedding_progress(State(state): State<Arc<AppState>>) -> Response {
+    match state.memory.embedding_backfill_progress() {
+        Ok(progress) => Json(progress).into_response(),
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
 // Memory reindex endpoint
 #[derive(Deserialize)]
 struct ReindexRequest {
@@ -1066,6 +1346,84 @@ fn memory_reindex_inner(
     })
 }
 
+// Memory history endpoints
+#[derive(Deserialize)]
+struct HistoryQuery {
+    path: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct VersionResponse {
+    version: MemoryVersion,
+    /// None for a deletion
+    content: Option<String>,
+    /// Unified diff against the file's previous version
+    diff: String,
+}
+
+async fn memory_history(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HistoryQuery>,
+) -> Response {
+    let result = MemoryHistory::for_workspace(state.memory.workspace())
+        .and_then(|history| history.versions(query.path.as_deref(), query.limit.unwrap_or(50)));
+    match result {
+        Ok(versions) => Json(json!({"versions": versions})).into_response(),
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn memory_version(State(state): State<Arc<AppState>>, Path(id): Path<i64>) -> Response {
+    let result = MemoryHistory::for_workspace(state.memory.workspace()).and_then(|history| {
+        let Some(version) = history.get(id)? else {
+            return Ok(None);
+        };
+        Ok(Some(VersionResponse {
+            content: history.content(&version)?,
+            diff: history.diff(&version)?,
+            version,
+        }))
+    });
+    match result {
+        Ok(Some(response)) => Json(response).into_response(),
+        Ok(None) => {
+            AppError(StatusCode::NOT_FOUND, format!("Version {} not found", id)).into_response()
+        }
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn restore_memory_version(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(e) = require_admin(&state.config, &headers) {
+        return e.into_response();
+    }
+    let history = match MemoryHistory::for_workspace(state.memory.workspace()) {
+        Ok(history) => history,
+        Err(e) => {
+            return AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+        }
+    };
+    let version = match history.get(id) {
+        Ok(Some(version)) => version,
+        Ok(None) => {
+            return AppError(StatusCode::NOT_FOUND, format!("Version {} not found", id))
+                .into_response();
+        }
+        Err(e) => {
+            return AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+        }
+    };
+    match history.restore(&version) {
+        Ok(path) => Json(json!({"restored": id, "path": path})).into_response(),
+        Err(e) => AppError(StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
 // Config endpoint - show current configuration (safe subset)
 #[derive(Serialize)]
 struct ConfigResponse {
@@ -1086,6 +1444,8 @@ struct AgentConfigInfo {
 struct ServerConfigInfo {
     port: u16,
     bind: String,
+    base_path: String,
+    tls: bool,
 }
 
 #[derive(Serialize)]
@@ -1112,6 +1472,8 @@ async fn get_config(State(state): State<Arc<AppState>>) -> Json<ConfigResponse>
         server: ServerConfigInfo {
             port: state.config.server.port,
             bind: state.config.server.bind.clone(),
+            base_path: state.config.server.normalized_base_path(),
+            tls: state.config.server.tls.is_some(),
         },
         memory: MemoryConfigInfo {
             workspace: state.config.memory.workspace.clone(),
@@ -1152,16 +1514,9 @@ async fn heartbeat_status(State(state): State<Arc<AppState>>) -> Json<HeartbeatS
             .unwrap_or(0);
         let age_seconds = (now_ms.saturating_sub(event.ts)) / 1000;
 
-        let status = match event.status {
-            HeartbeatStatus::Sent => "sent",
-            HeartbeatStatus::Ok => "ok",
-            HeartbeatStatus::Skipped => "skipped",
-            HeartbeatStatus::Failed => "failed",
-        };
-
         HeartbeatEventInfo {
             ts: event.ts,
-            status: status.to_string(),
+            status: heartbeat_status_str(&event.status).to_string(),
             duration_ms: event.duration_ms,
             preview: event.preview,
             reason: event.reason,
@@ -1176,6 +1531,330 @@ async fn heartbeat_status(State(state): State<Arc<AppState>>) -> Json<HeartbeatS
     })
 }
 
+// Dashboard endpoint: read-only summary for the status page
+const DASHBOARD_HEARTBEAT_RUNS: usize = 24;
+const DASHBOARD_ACTIVITY_ITEMS: usize = 20;
+
+#[derive(Serialize)]
+struct DashboardResponse {
+    version: String,
+    model: String,
+    generated_at: u64,
+    active_sessions: usize,
+    memory: DashboardMemory,
+    heartbeat: DashboardHeartbeat,
+    open_tasks: Vec<DashboardTask>,
+    activity: Vec<DashboardActivity>,
+}
+
+#[derive(Serialize)]
+struct DashboardMemory {
+    total_files: usize,
+    total_chunks: usize,
+    embedded_chunks: Option<usize>,
+    index_size_kb: u64,
+}
+
+#[derive(Serialize)]
+struct DashboardHeartbeat {
+    enabled: bool,
+    paused: bool,
+    interval: String,
+    /// "healthy", "waiting", "stale", "failing", "paused" or "disabled"
+    health: &'static str,
+    last_run: Option<DashboardHeartbeatRun>,
+    /// Recent runs, oldest first
+    runs: Vec<DashboardHeartbeatRun>,
+}
+
+/// A heartbeat run without its response text
+#[derive(Clone, Serialize)]
+struct DashboardHeartbeatRun {
+    ts: u64,
+    status: &'static str,
+    duration_ms: u64,
+    reason: Option<String>,
+}
+
+#[derive(Serialize)]
+struct DashboardTask {
+    title: String,
+    due: Option<String>,
+}
+
+#[derive(Serialize)]
+struct DashboardActivity {
+    ts: u64,
+    kind: &'static str,
+    summary: String,
+}
+
+fn heartbeat_status_str(status: &HeartbeatStatus) -> &'static str {
+    match status {
+        HeartbeatStatus::Sent => "sent",
+        HeartbeatStatus::Ok => "ok",
+        HeartbeatStatus::Skipped => "skipped",
+        HeartbeatStatus::Failed => "failed",
+    }
+}
+
+/// Overall heartbeat health from the most recent run. A run is overdue when
+/// nothing was recorded for two intervals.
+fn heartbeat_health(
+    enabled: bool,
+    paused: bool,
+    interval: Duration,
+    last: Option<&HeartbeatEvent>,
+    now_ms: u64,
+) -> &'static str {
+    if !enabled {
+        return "disabled";
+    }
+    if paused {
+        return "paused";
+    }
+    let Some(last) = last else {
+        return "waiting";
+    };
+    if last.status == HeartbeatStatus::Failed {
+        return "failing";
+    }
+    let overdue_ms = interval.as_millis() as u64 * 2;
+    if now_ms.saturating_sub(last.ts) > overdue_ms {
+        "stale"
+    } else {
+        "healthy"
+    }
+}
+
+async fn dashboard(State(state): State<Arc<AppState>>) -> Json<DashboardResponse> {
+    let now_ms = chrono::Utc::now().timestamp_millis() as u64;
+
+    let mut active_sessions = state.sessions.lock().await.len();
+    if let Some(ref discord_agents) = state.discord_agents
+        && let Ok(agents) = discord_agents.try_lock()
+    {
+        active_sessions += agents.len();
+    }
+
+    let stats = memory_stats_inner(&state.memory).ok();
+    let memory = DashboardMemory {
+        total_files: stats.as_ref().map(|s| s.total_files).unwrap_or(0),
+        total_chunks: stats.as_ref().map(|s| s.total_chunks).unwrap_or(0),
+        embedded_chunks: state
+            .memory
+            .embedding_backfill_progress()
+            .ok()
+            .map(|p| p.embedded_chunks),
+        index_size_kb: stats.as_ref().map(|s| s.index_size_kb).unwrap_or(0),
+    };
+
+    // The history file is written by whichever process runs the heartbeat
+    let mut events = load_heartbeat_history(HEARTBEAT_AGENT_ID, DASHBOARD_HEARTBEAT_RUNS);
+    if events.is_empty() {
+        events.extend(get_last_heartbeat_event());
+    }
+    let paused = is_heartbeat_paused(HEARTBEAT_AGENT_ID);
+    let interval =
+        parse_duration(&state.config.heartbeat.interval).unwrap_or(Duration::from_secs(30 * 60));
+    let last_ran = events
+        .iter()
+        .rev()
+        .find(|e| e.status != HeartbeatStatus::Skipped);
+    let health = heartbeat_health(
+        state.config.heartbeat.enabled,
+        paused,
+        interval,
+        last_ran,
+        now_ms,
+    );
+
+    let mut activity: Vec<DashboardActivity> = events
+        .iter()
+        .filter(|e| e.status != HeartbeatStatus::Skipped)
+        .map(|e| DashboardActivity {
+            ts: e.ts,
+            kind: "heartbeat",
+            summary: match (&e.status, &e.reason) {
+                (HeartbeatStatus::Failed, Some(reason)) => {
+                    format!("Heartbeat failed: {}", reason)
+                }
+                (status, _) => format!("Heartbeat {}", heartbeat_status_str(status)),
+            },
+        })
+        .collect();
+
+    let tasks = state.tasks.clone();
+    let tasks = db::blocking(move || tasks.list(None))
+        .await
+        .unwrap_or_default();
+    for task in &tasks {
+        if let Some(completed_at) = task.completed_at {
+            activity.push(DashboardActivity {
+                ts: completed_at as u64 * 1000,
+                kind: "task",
+                summary: format!("Completed: {}", task.title),
+            });
+        }
+        activity.push(DashboardActivity {
+            ts: task.created_at as u64 * 1000,
+            kind: "task",
+            summary: format!("Added: {}", task.title),
+        });
+    }
+    let open_tasks = tasks
+        .into_iter()
+        .filter(|t| t.status == TaskStatus::Open)
+        .map(|t| DashboardTask {
+            title: t.title,
+            due: t.due,
+        })
+        .collect();
+
+    if let Ok(sessions) = crate::agent::list_sessions_for_agent(HTTP_AGENT_ID) {
+        activity.extend(sessions.into_iter().map(|s| DashboardActivity {
+            ts: s.created_at.timestamp_millis() as u64,
+            kind: "session",
+            summary: format!("Conversation ({} messages)", s.message_count),
+        }));
+    }
+
+    activity.sort_by(|a, b| b.ts.cmp(&a.ts));
+    activity.truncate(DASHBOARD_ACTIVITY_ITEMS);
+
+    let runs: Vec<DashboardHeartbeatRun> = events
+        .into_iter()
+        .map(|e| DashboardHeartbeatRun {
+            ts: e.ts,
+            status: heartbeat_status_str(&e.status),
+            duration_ms: e.duration_ms,
+            reason: e.reason,
+        })
+        .collect();
+
+    Json(DashboardResponse {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        model: state.config.agent.default_model.clone(),
+        generated_at: now_ms,
+        active_sessions,
+        memory,
+        heartbeat: DashboardHeartbeat {
+            enabled: state.config.heartbeat.enabled,
+            paused,
+            interval: state.config.heartbeat.interval.clone(),
+            health,
+            last_run: runs.iter().rev().find(|r| r.status != "skipped").cloned(),
+            runs,
+        },
+        open_tasks,
+        activity,
+    })
+}
+
+// Task endpoints
+#[derive(Deserialize)]
+struct TaskListQuery {
+    /// "open" (default), "done", "cancelled" or "all"
+    status: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TaskListResponse {
+    tasks: Vec<Task>,
+}
+
+#[derive(Deserialize)]
+struct CreateTaskRequest {
+    title: String,
+    notes: Option<String>,
+    due: Option<String>,
+}
+
+async fn list_tasks(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TaskListQuery>,
+) -> Response {
+    let status = match query.status.as_deref().unwrap_or("open") {
+        "all" => None,
+        s => match TaskStatus::parse(s) {
+            Some(status) => Some(status),
+            None => {
+                return AppError(StatusCode::BAD_REQUEST, format!("Invalid status: {}", s))
+                    .into_response();
+            }
+        },
+    };
+
+    let tasks = state.tasks.clone();
+    match db::blocking(move || tasks.list(status)).await {
+        Ok(tasks) => Json(TaskListResponse { tasks }).into_response(),
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn create_task(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<CreateTaskRequest>,
+) -> Response {
+    if let Err(e) = check_title(&request.title) {
+        return AppError(StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+    let tasks = state.tasks.clone();
+    let created = db::blocking(move || {
+        tasks.create(
+            &request.title,
+            request.notes.as_deref(),
+            request.due.as_deref(),
+            Some("http"),
+        )
+    })
+    .await;
+    match created {
+        Ok(task) => (StatusCode::CREATED, Json(task)).into_response(),
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn get_task(State(state): State<Arc<AppState>>, Path(id): Path<i64>) -> Response {
+    let tasks = state.tasks.clone();
+    match db::blocking(move || tasks.get(id)).await {
+        Ok(Some(task)) => Json(task).into_response(),
+        Ok(None) => {
+            AppError(StatusCode::NOT_FOUND, format!("Task {} not found", id)).into_response()
+        }
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn update_task(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(update): Json<TaskUpdate>,
+) -> Response {
+    if let Some(Err(e)) = update.title.as_deref().map(check_title) {
+        return AppError(StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+    let tasks = state.tasks.clone();
+    match db::blocking(move || tasks.update(id, update)).await {
+        Ok(Some(task)) => Json(task).into_response(),
+        Ok(None) => {
+            AppError(StatusCode::NOT_FOUND, format!("Task {} not found", id)).into_response()
+        }
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn complete_task(State(state): State<Arc<AppState>>, Path(id): Path<i64>) -> Response {
+    let tasks = state.tasks.clone();
+    match db::blocking(move || tasks.complete(id)).await {
+        Ok(Some(task)) => Json(task).into_response(),
+        Ok(None) => {
+            AppError(StatusCode::NOT_FOUND, format!("Task {} not found", id)).into_response()
+        }
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
 // Saved sessions endpoint - list sessions from file store
 #[derive(Serialize)]
 struct SavedSessionInfo {
@@ -1330,6 +2009,61 @@ async fn get_saved_session(Path(session_id): Path<String>) -> Response {
     .into_response()
 }
 
+// Export saved session as markdown or HTML
+#[derive(Deserialize)]
+struct ExportQuery {
+    /// "md" (default) or "html"
+    format: Option<String>,
+}
+
+async fn export_saved_session(
+    Path(session_id): Path<String>,
+    Query(query): Query<ExportQuery>,
+) -> Response {
+    let format = match query.format.as_deref() {
+        None => ExportFormat::Markdown,
+        Some(f) => match ExportFormat::parse(f) {
+            Some(format) => format,
+            None => {
+                return AppError(
+                    StatusCode::BAD_REQUEST,
+                    format!("Unknown export format: {} (expected md or html)", f),
+                )
+                .into_response();
+            }
+        },
+    };
+
+    // Session IDs are UUIDs; reject anything that could escape the sessions dir
+    if session_id.is_empty()
+        || !session_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return AppError(StatusCode::BAD_REQUEST, "Invalid session ID".to_string()).into_response();
+    }
+
+    let session = match Session::load_for_agent(HTTP_AGENT_ID, &session_id) {
+        Ok(session) => session,
+        Err(e) => return AppError(StatusCode::NOT_FOUND, e.to_string()).into_response(),
+    };
+
+    let body = export_session(&session, format, &ExportOptions::default());
+    let disposition = format!(
+        "attachment; filename=\"{}\"",
+        export_file_name(&session, format)
+    );
+
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response()
+}
+
 // Daemon logs endpoint - read log file
 #[derive(Deserialize)]
 struct LogsQuery {
@@ -1405,11 +2139,257 @@ async fn get_daemon_logs(Query(query): Query<LogsQuery>) -> Response {
     .into_response()
 }
 
+// Runtime log level endpoints
+#[derive(Deserialize)]
+struct SetLoggingRequest {
+    /// Filter directives, e.g. "info,localgpt::discord=debug"
+    filter: String,
+}
+
+async fn get_logging() -> Response {
+    match crate::logging::current_filter() {
+        Some(filter) => Json(json!({"filter": filter})).into_response(),
+        None => AppError(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Logging is not adjustable in this process".to_string(),
+        )
+        .into_response(),
+    }
+}
+
+async fn set_logging(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<SetLoggingRequest>,
+) -> Response {
+    if let Err(e) = require_admin(&state.config, &headers) {
+        return e.into_response();
+    }
+    match crate::logging::set_filter(&request.filter) {
+        Ok(()) => {
+            info!("Log filter changed to {}", request.filter);
+            Json(json!({"filter": request.filter})).into_response()
+        }
+        Err(e) => AppError(StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+// Feature toggle endpoints
+/// Changes listed with the toggles
+const RECENT_FEATURE_CHANGES: usize = 20;
+
+#[derive(Deserialize)]
+struct SetFeatureRequest {
+    enabled: bool,
+}
+
+/// Admin endpoints need `Authorization: Bearer <server.admin_token>`
+fn require_admin(config: &Config, headers: &HeaderMap) -> Result<(), AppError> {
+    let Some(ref token) = config.server.admin_token else {
+        return Err(AppError(
+            StatusCode::FORBIDDEN,
+            "Set server.admin_token to use admin endpoints".to_string(),
+        ));
+    };
+    let given = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if token.is_empty() || given != Some(token.as_str()) {
+        return Err(AppError(
+            StatusCode::UNAUTHORIZED,
+            "Invalid admin token".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+async fn list_features(State(state): State<Arc<AppState>>) -> Response {
+    match state.features.recent_changes(RECENT_FEATURE_CHANGES) {
+        Ok(changes) => Json(json!({
+            "features": state.features.list(),
+            "changes": changes,
+        }))
+        .into_response(),
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn set_feature(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<SetFeatureRequest>,
+) -> Response {
+    if let Err(e) = require_admin(&state.config, &headers) {
+        return e.into_response();
+    }
+    match state.features.set_by_name(&name, request.enabled, "http") {
+        Ok(()) => Json(json!({"name": name, "enabled": request.enabled})).into_response(),
+        Err(e) => AppError(StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// Feature changes made through the daemon, as server-sent events
+async fn feature_events() -> Response {
+    let mut changes = crate::features::subscribe();
+    let stream = async_stream::stream! {
+        loop {
+            match changes.recv().await {
+                Ok(change) => {
+                    let data = serde_json::to_string(&change).unwrap_or_default();
+                    yield Ok::<Event, Infallible>(Event::default().event("feature").data(data));
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    };
+    Sse::new(stream).into_response()
+}
+
+/// Discord conversations as they happen, as server-sent events (empty
+/// unless `[channels.discord] mirror` is on)
+async fn discord_mirror(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    if let Err(e) = require_admin(&state.config, &headers) {
+        return e.into_response();
+    }
+    let mut events = crate::discord::mirror::subscribe();
+    let stream = async_stream::stream! {
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    let data = serde_json::to_string(&event).unwrap_or_default();
+                    yield Ok::<Event, Infallible>(Event::default().event("mirror").data(data));
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    };
+    Sse::new(stream).into_response()
+}
+
+// Discord shadow mode endpoints
+#[derive(Deserialize)]
+struct ShadowQuery {
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct SetShadowRequest {
+    enabled: bool,
+}
+
+#[derive(Serialize)]
+struct ShadowResponse {
+    enabled: bool,
+    entries: Vec<shadow::ShadowEntry>,
+}
+
+async fn get_discord_shadow(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<ShadowQuery>,
+) -> Response {
+    if let Err(e) = require_admin(&state.config, &headers) {
+        return e.into_response();
+    }
+    Json(ShadowResponse {
+        enabled: shadow::is_enabled(),
+        entries: shadow::load_entries(query.limit.unwrap_or(50).min(500)),
+    })
+    .into_response()
+}
+
+async fn set_discord_shadow(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<SetShadowRequest>,
+) -> Response {
+    if let Err(e) = require_admin(&state.config, &headers) {
+        return e.into_response();
+    }
+    match shadow::set_enabled(request.enabled) {
+        Ok(()) => Json(json!({"enabled": request.enabled})).into_response(),
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// Discord guild/channel settings endpoints
+#[derive(Deserialize)]
+struct SettingsQuery {
+    channel_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SetSettingRequest {
+    /// Change the value for this channel only
+    channel_id: Option<String>,
+    name: String,
+    /// None = back to the guild's or configured value
+    value: Option<String>,
+}
+
+async fn get_discord_settings(
+    State(state): State<Arc<AppState>>,
+    Path(guild_id): Path<String>,
+    Query(query): Query<SettingsQuery>,
+) -> Response {
+    let guild = settings::guild_config(&state.config, &guild_id);
+    let values = settings::SettingsStore::open_default()
+        .and_then(|store| store.resolve(guild, query.channel_id.as_deref()));
+    match values {
+        Ok(values) => Json(json!({
+            "guild_id": guild_id,
+            "channel_id": query.channel_id,
+            "settings": values,
+        }))
+        .into_response(),
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn set_discord_setting(
+    State(state): State<Arc<AppState>>,
+    Path(guild_id): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<SetSettingRequest>,
+) -> Response {
+    if let Err(e) = require_admin(&state.config, &headers) {
+        return e.into_response();
+    }
+    let Some(setting) = settings::Setting::parse(&request.name) else {
+        return AppError(
+            StatusCode::BAD_REQUEST,
+            format!("Unknown setting: {}", request.name),
+        )
+        .into_response();
+    };
+    let result = settings::SettingsStore::open_default().and_then(|store| {
+        store.set(
+            &guild_id,
+            request.channel_id.as_deref(),
+            setting,
+            request.value.as_deref(),
+            "http",
+        )?;
+        let guild = settings::guild_config(&state.config, &guild_id);
+        store.resolve(guild, request.channel_id.as_deref())
+    });
+    match result {
+        Ok(values) => Json(json!({"settings": values})).into_response(),
+        Err(e) => AppError(StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
 // WebSocket handler
 async fn websocket_handler(
     ws: WebSocketUpgrade,
     State(state): State<Arc<AppState>>,
+    Extension(ClientIp(client)): Extension<ClientIp>,
 ) -> impl IntoResponse {
+    debug!("WebSocket upgrade from {}", client);
     ws.on_upgrade(|socket| handle_websocket(socket, state))
 }
 
@@ -1576,6 +2556,9 @@ async fn handle_websocket(socket: WebSocket, state: Arc<AppState>) {
                         match entry.agent.chat(&message).await {
                             Ok(response) => {
                                 // Send response as content
+                                let response =
+                                    ReplyPipeline::for_channel(&state.config, ChannelKind::Http)
+                                        .process_joined(&response);
                                 let content = WsOutgoing::Content { delta: response };
                                 if let Ok(json) = serde_json::to_string(&content) {
                                     let _ = sender.send(WsMessage::Text(json.into())).await;
@@ -1630,3 +2613,41 @@ async fn handle_websocket(socket: WebSocket, state: Arc<AppState>) {
 
     debug!("WebSocket connection closed");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(ts: u64, status: HeartbeatStatus) -> HeartbeatEvent {
+        HeartbeatEvent {
+            ts,
+            status,
+            duration_ms: 100,
+            preview: None,
+            reason: None,
+            actions: Vec::new(),
+            response: None,
+        }
+    }
+
+    #[test]
+    fn test_heartbeat_health() {
+        let interval = Duration::from_secs(60);
+        let now = 1_000_000;
+        let recent = event(now - 30_000, HeartbeatStatus::Ok);
+        let old = event(now - 300_000, HeartbeatStatus::Sent);
+        let failed = event(now - 30_000, HeartbeatStatus::Failed);
+
+        let health = |paused, last| heartbeat_health(true, paused, interval, last, now);
+
+        assert_eq!(health(false, Some(&recent)), "healthy");
+        assert_eq!(health(false, Some(&old)), "stale");
+        assert_eq!(health(false, Some(&failed)), "failing");
+        assert_eq!(health(false, None), "waiting");
+        assert_eq!(health(true, Some(&failed)), "paused");
+        assert_eq!(
+            heartbeat_health(false, false, interval, Some(&recent), now),
+            "disabled"
+        );
+    }
+}
@@ -1,5 +1,7 @@
 mod http;
+mod proxy;
 pub mod telegram;
+mod tls;
 mod websocket;
 
 pub use http::Server;
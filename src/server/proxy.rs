@@ -0,0 +1,185 @@
+//! Client address resolution behind reverse proxies
+//!
+//! The peer address of a connection is the proxy's when LocalGPT sits behind
+//! nginx or caddy. X-Forwarded-For is only honored when the peer is listed in
+//! `server.trusted_proxies`; otherwise any client could claim any address.
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::{ConnectInfo, Request, State, connect_info::Connected},
+    middleware::Next,
+    response::Response,
+    serve::IncomingStream,
+};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tracing::debug;
+
+use super::tls::TlsListener;
+
+/// Address of the peer that opened the connection
+#[derive(Debug, Clone, Copy)]
+pub(super) struct PeerAddr(pub SocketAddr);
+
+impl Connected<IncomingStream<'_, TcpListener>> for PeerAddr {
+    fn connect_info(stream: IncomingStream<'_, TcpListener>) -> Self {
+        Self(*stream.remote_addr())
+    }
+}
+
+impl Connected<IncomingStream<'_, TlsListener>> for PeerAddr {
+    fn connect_info(stream: IncomingStream<'_, TlsListener>) -> Self {
+        Self(*stream.remote_addr())
+    }
+}
+
+/// The originating client address, stored as a request extension
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ClientIp(pub IpAddr);
+
+/// Parsed `server.trusted_proxies` (addresses and CIDR ranges)
+#[derive(Debug, Default)]
+pub(super) struct TrustedProxies {
+    ranges: Vec<(IpAddr, u8)>,
+}
+
+impl TrustedProxies {
+    pub fn parse(entries: &[String]) -> Result<Self> {
+        let ranges = entries
+            .iter()
+            .map(|entry| parse_range(entry).with_context(|| format!("Invalid proxy '{}'", entry)))
+            .collect::<Result<_>>()?;
+        Ok(Self { ranges })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.ranges
+            .iter()
+            .any(|&(net, prefix)| in_range(ip, net, prefix))
+    }
+
+    /// The client address for a request from `peer`. Forwarded addresses
+    /// are walked right to left, skipping trusted proxies; the first
+    /// untrusted hop is the client.
+    pub fn client_ip(&self, peer: IpAddr, forwarded_for: Option<&str>) -> IpAddr {
+        let mut client = peer.to_canonical();
+        if !self.contains(client) {
+            return client;
+        }
+        let Some(forwarded_for) = forwarded_for else {
+            return client;
+        };
+
+        for hop in forwarded_for.rsplit(',') {
+            // A malformed entry was written by whoever sent it, so stop at
+            // the last address we could verify
+            let Ok(ip) = hop.trim().parse::<IpAddr>() else {
+                break;
+            };
+            client = ip.to_canonical();
+            if !self.contains(client) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+fn parse_range(entry: &str) -> Result<(IpAddr, u8)> {
+    let (addr, prefix) = match entry.trim().split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix.parse::<u8>()?)),
+        None => (entry.trim(), None),
+    };
+    let ip = addr.parse::<IpAddr>()?.to_canonical();
+    let max = if ip.is_ipv4() { 32 } else { 128 };
+    let prefix = prefix.unwrap_or(max);
+    if prefix > max {
+        anyhow::bail!("prefix length {} is longer than {}", prefix, max);
+    }
+    Ok((ip, prefix))
+}
+
+fn in_range(ip: IpAddr, net: IpAddr, prefix: u8) -> bool {
+    match (ip, net) {
+        (IpAddr::V4(ip), IpAddr::V4(net)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(net) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(net)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(net) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Middleware: resolve the client address and attach it as [`ClientIp`]
+pub(super) async fn resolve_client_ip(
+    State(proxies): State<Arc<TrustedProxies>>,
+    ConnectInfo(peer): ConnectInfo<PeerAddr>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let forwarded_for = request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok());
+    let client = proxies.client_ip(peer.0.ip(), forwarded_for);
+    debug!(
+        "{} {} from {}",
+        request.method(),
+        request.uri().path(),
+        client
+    );
+
+    request.extensions_mut().insert(ClientIp(client));
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_client_ip() {
+        let proxies =
+            TrustedProxies::parse(&["127.0.0.1".to_string(), "10.0.0.0/8".to_string()]).unwrap();
+
+        // Untrusted peers can't spoof their address
+        assert_eq!(
+            proxies.client_ip(ip("203.0.113.5"), Some("1.2.3.4")),
+            ip("203.0.113.5")
+        );
+        // Trusted proxy chain: first untrusted hop from the right
+        assert_eq!(
+            proxies.client_ip(ip("127.0.0.1"), Some("1.2.3.4, 198.51.100.7, 10.1.2.3")),
+            ip("198.51.100.7")
+        );
+        // IPv4-mapped peer address
+        assert_eq!(
+            proxies.client_ip(ip("::ffff:127.0.0.1"), Some("198.51.100.7")),
+            ip("198.51.100.7")
+        );
+        // Malformed entry: keep the last verified hop
+        assert_eq!(
+            proxies.client_ip(ip("127.0.0.1"), Some("garbage, 10.0.0.2")),
+            ip("10.0.0.2")
+        );
+        assert_eq!(proxies.client_ip(ip("127.0.0.1"), None), ip("127.0.0.1"));
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("10.0.0.0/8").unwrap(), (ip("10.0.0.0"), 8));
+        assert_eq!(parse_range("::1").unwrap(), (ip("::1"), 128));
+        assert!(parse_range("10.0.0.0/33").is_err());
+        assert!(parse_range("localhost").is_err());
+        assert!(in_range(ip("0.0.0.1"), ip("0.0.0.0"), 0));
+    }
+}
@@ -14,7 +14,9 @@ use teloxide::types::{MessageId, ParseMode};
 use tokio::sync::Mutex;
 use tracing::{debug, error, info, warn};
 
-use crate::agent::{Agent, AgentConfig, StreamEvent, extract_tool_detail};
+use crate::agent::{
+    Agent, AgentConfig, ChannelKind, ReplyPipeline, StreamEvent, extract_tool_detail,
+};
 use crate::concurrency::TurnGate;
 use crate::config::Config;
 use crate::memory::MemoryManager;
@@ -747,8 +749,15 @@ async fn handle_chat(
 
     drop(sessions);
 
-    // Final edit with complete response
-    send_long_message(bot, chat_id, Some(msg_id), &response).await;
+    // Final edit with the first part of the response, the rest as new messages
+    let mut parts = ReplyPipeline::for_channel(&state.config, ChannelKind::Telegram)
+        .process(&response)
+        .into_iter();
+    let first = parts.next().unwrap_or_else(|| "(no response)".to_string());
+    send_long_message(bot, chat_id, Some(msg_id), &first).await;
+    for part in parts {
+        send_long_message(bot, chat_id, None, &part).await;
+    }
 
     Ok(())
 }
@@ -0,0 +1,361 @@
+//! Goal/task tracking
+//!
+//! A small SQLite-backed todo list shared by every agent (CLI, HTTP, Discord,
+//! heartbeat). Tasks are exposed to the agent as tools and injected into the
+//! session context under "Pending Tasks".
+
+use anyhow::{Result, anyhow};
+use rusqlite::{Connection, OptionalExtension, Row, TransactionBehavior, params};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::agent::get_state_dir;
+use crate::db::SqlitePool;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Open,
+    Done,
+    Cancelled,
+}
+
+impl TaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Open => "open",
+            TaskStatus::Done => "done",
+            TaskStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "open" | "pending" | "todo" => Some(TaskStatus::Open),
+            "done" | "completed" | "complete" => Some(TaskStatus::Done),
+            "cancelled" | "canceled" => Some(TaskStatus::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Task {
+    pub id: i64,
+    pub title: String,
+    pub notes: Option<String>,
+    pub status: TaskStatus,
+    /// Free-form due date/time as given by the user or agent (e.g. "2026-02-01")
+    pub due: Option<String>,
+    /// Where the task was created (e.g. "discord", "cli", "http")
+    pub source: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub completed_at: Option<i64>,
+}
+
+/// Fields to change on an existing task (None = leave unchanged)
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct TaskUpdate {
+    pub title: Option<String>,
+    pub notes: Option<String>,
+    pub due: Option<String>,
+    pub status: Option<TaskStatus>,
+}
+
+#[derive(Clone)]
+pub struct TaskStore {
+    pool: SqlitePool,
+}
+
+impl TaskStore {
+    /// Open the shared task database at `~/.localgpt/tasks.sqlite`
+    pub fn open_default() -> Result<Self> {
+        Self::open(&Self::default_path()?)
+    }
+
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(get_state_dir()?.join("tasks.sqlite"))
+    }
+
+    pub fn open(db_path: &Path) -> Result<Self> {
+        Self::init(SqlitePool::shared(db_path, None)?)
+    }
+
+    /// In-memory store (tests)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(SqlitePool::open_in_memory(None)?)
+    }
+
+    fn init(pool: SqlitePool) -> Result<Self> {
+        pool.get()?.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS tasks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                title TEXT NOT NULL,
+                notes TEXT,
+                status TEXT NOT NULL DEFAULT 'open',
+                due TEXT,
+                source TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                completed_at INTEGER
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_tasks_status ON tasks(status);
+            "#,
+        )?;
+
+        Ok(Self { pool })
+    }
+
+    pub fn create(
+        &self,
+        title: &str,
+        notes: Option<&str>,
+        due: Option<&str>,
+        source: Option<&str>,
+    ) -> Result<Task> {
+        let title = check_title(title)?;
+
+        let now = chrono::Utc::now().timestamp();
+        let id = {
+            let conn = self.pool.get()?;
+            conn.execute(
+                "INSERT INTO tasks (title, notes, status, due, source, created_at, updated_at)
+                 VALUES (?1, ?2, 'open', ?3, ?4, ?5, ?5)",
+                params![title, notes, due, source, now],
+            )?;
+            conn.last_insert_rowid()
+        };
+
+        self.get(id)?
+            .ok_or_else(|| anyhow!("Task {} disappeared after insert", id))
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<Task>> {
+        get_task(&self.pool.get()?, id)
+    }
+
+    /// Change a task; None if there is no task `id`
+    pub fn update(&self, id: i64, update: TaskUpdate) -> Result<Option<Task>> {
+        if let Some(ref title) = update.title {
+            check_title(title)?;
+        }
+        // Read and write in one transaction so concurrent updates don't
+        // overwrite each other
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let Some(mut task) = get_task(&tx, id)? else {
+            return Ok(None);
+        };
+
+        if let Some(title) = update.title {
+            task.title = title.trim().to_string();
+        }
+        if let Some(notes) = update.notes {
+            task.notes = Some(notes);
+        }
+        if let Some(due) = update.due {
+            task.due = Some(due);
+        }
+        if let Some(status) = update.status {
+            task.completed_at = match status {
+                TaskStatus::Done => task.completed_at.or(Some(chrono::Utc::now().timestamp())),
+                _ => None,
+            };
+            task.status = status;
+        }
+        task.updated_at = chrono::Utc::now().timestamp();
+
+        tx.execute(
+            "UPDATE tasks SET title = ?1, notes = ?2, status = ?3, due = ?4,
+                              updated_at = ?5, completed_at = ?6
+             WHERE id = ?7",
+            params![
+                task.title,
+                task.notes,
+                task.status.as_str(),
+                task.due,
+                task.updated_at,
+                task.completed_at,
+                id
+            ],
+        )?;
+        tx.commit()?;
+
+        Ok(Some(task))
+    }
+
+    /// Mark a task as done; None if there is no task `id`
+    pub fn complete(&self, id: i64) -> Result<Option<Task>> {
+        self.update(
+            id,
+            TaskUpdate {
+                status: Some(TaskStatus::Done),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// List tasks, optionally filtered by status (open tasks first, oldest first)
+    pub fn list(&self, status: Option<TaskStatus>) -> Result<Vec<Task>> {
+        let conn = self.pool.get()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, title, notes, status, due, source, created_at, updated_at, completed_at
+             FROM tasks
+             WHERE ?1 IS NULL OR status = ?1
+             ORDER BY CASE status WHEN 'open' THEN 0 ELSE 1 END, id",
+        )?;
+
+        let tasks = stmt
+            .query_map(params![status.map(|s| s.as_str())], row_to_task)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(tasks)
+    }
+
+    pub fn pending(&self) -> Result<Vec<Task>> {
+        self.list(Some(TaskStatus::Open))
+    }
+
+    /// Refresh query planner statistics and reclaim free pages
+    pub fn optimize(&self) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute_batch("ANALYZE; VACUUM;")?;
+        Ok(())
+    }
+}
+
+fn get_task(conn: &Connection, id: i64) -> Result<Option<Task>> {
+    let task = conn
+        .query_row(
+            "SELECT id, title, notes, status, due, source, created_at, updated_at, completed_at
+             FROM tasks WHERE id = ?1",
+            params![id],
+            row_to_task,
+        )
+        .optional()?;
+
+    Ok(task)
+}
+
+fn row_to_task(row: &Row) -> rusqlite::Result<Task> {
+    let status: String = row.get(3)?;
+    Ok(Task {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        notes: row.get(2)?,
+        status: TaskStatus::parse(&status).unwrap_or(TaskStatus::Open),
+        due: row.get(4)?,
+        source: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+        completed_at: row.get(8)?,
+    })
+}
+
+/// The title trimmed, or an error if nothing is left
+pub fn check_title(title: &str) -> Result<&str> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err(anyhow!("Task title must not be empty"));
+    }
+    Ok(title)
+}
+
+/// Render tasks as a markdown checklist for context injection and tool output
+pub fn format_task_list(tasks: &[Task]) -> String {
+    tasks
+        .iter()
+        .map(|t| {
+            let check = if t.status == TaskStatus::Open {
+                " "
+            } else {
+                "x"
+            };
+            let mut line = format!("- [{}] #{} {}", check, t.id, t.title);
+            if let Some(ref due) = t.due {
+                line.push_str(&format!(" (due: {})", due));
+            }
+            if let Some(ref notes) = t.notes
+                && !notes.is_empty()
+            {
+                line.push_str(&format!(" — {}", notes));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_update_complete() {
+        let store = TaskStore::open_in_memory().unwrap();
+
+        let task = store
+            .create("Renew passport", None, Some("2026-03-01"), Some("cli"))
+            .unwrap();
+        assert_eq!(task.status, TaskStatus::Open);
+        assert_eq!(task.due.as_deref(), Some("2026-03-01"));
+
+        let updated = store
+            .update(
+                task.id,
+                TaskUpdate {
+                    notes: Some("book appointment first".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.notes.as_deref(), Some("book appointment first"));
+
+        let done = store.complete(task.id).unwrap().unwrap();
+        assert_eq!(done.status, TaskStatus::Done);
+        assert!(done.completed_at.is_some());
+        assert!(store.pending().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_list_filters_by_status() {
+        let store = TaskStore::open_in_memory().unwrap();
+        let a = store.create("a", None, None, None).unwrap();
+        store.create("b", None, None, None).unwrap();
+        store.complete(a.id).unwrap();
+
+        assert_eq!(store.list(None).unwrap().len(), 2);
+        let pending = store.pending().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].title, "b");
+    }
+
+    #[test]
+    fn test_empty_title_rejected() {
+        let store = TaskStore::open_in_memory().unwrap();
+        assert!(store.create("  ", None, None, None).is_err());
+        assert!(store.update(42, TaskUpdate::default()).unwrap().is_none());
+
+        let task = store.create("a", None, None, None).unwrap();
+        let blank = TaskUpdate {
+            title: Some(" ".to_string()),
+            ..Default::default()
+        };
+        assert!(store.update(task.id, blank).is_err());
+    }
+
+    #[test]
+    fn test_format_task_list() {
+        let store = TaskStore::open_in_memory().unwrap();
+        store
+            .create("Call mom", Some("about the weekend"), None, None)
+            .unwrap();
+        let text = format_task_list(&store.pending().unwrap());
+        assert_eq!(text, "- [ ] #1 Call mom — about the weekend");
+    }
+}
@@ -0,0 +1,163 @@
+//! Resilient WebSocket client
+//!
+//! The connection plumbing shared by WebSocket integrations (currently the
+//! Discord gateway): connecting, a cloneable sender for use from other
+//! tasks, JSON frames in both directions, periodic heartbeats and the
+//! backoff between reconnects. Protocol details such as handshakes, opcodes
+//! and resume state stay with each integration. Pings are answered by the
+//! underlying client.
+
+use anyhow::{Context, Result};
+use futures::stream::{SplitSink, SplitStream};
+use futures::{SinkExt, StreamExt};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::{self, Duration};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+use tracing::{debug, info, warn};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Open a connection, split into its sending and receiving halves
+pub async fn connect(url: &str) -> Result<(WsSender, WsReceiver)> {
+    let (socket, _) = connect_async(url)
+        .await
+        .with_context(|| format!("Failed to connect to {}", redact_query(url)))?;
+    let (sink, stream) = socket.split();
+    Ok((
+        WsSender {
+            sink: Arc::new(Mutex::new(sink)),
+        },
+        WsReceiver {
+            stream,
+            close_code: None,
+        },
+    ))
+}
+
+/// Sending half; clones share the connection
+#[derive(Clone)]
+pub struct WsSender {
+    sink: Arc<Mutex<SplitSink<Socket, Message>>>,
+}
+
+impl WsSender {
+    pub async fn send_json<T: Serialize>(&self, frame: &T) -> Result<()> {
+        let text = serde_json::to_string(frame)?;
+        self.sink.lock().await.send(Message::Text(text)).await?;
+        Ok(())
+    }
+
+    /// Send `frame()` after `first_delay` and then every `interval`, until
+    /// a send fails or the returned task is aborted
+    pub fn spawn_heartbeat<F>(
+        &self,
+        first_delay: Duration,
+        interval: Duration,
+        frame: F,
+    ) -> JoinHandle<()>
+    where
+        F: Fn() -> serde_json::Value + Send + 'static,
+    {
+        let sender = self.clone();
+        tokio::spawn(async move {
+            time::sleep(first_delay).await;
+            let mut ticker = time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = sender.send_json(&frame()).await {
+                    warn!("Failed to send heartbeat: {}", e);
+                    break;
+                }
+                debug!("Sent heartbeat");
+            }
+        })
+    }
+}
+
+/// Receiving half
+pub struct WsReceiver {
+    stream: SplitStream<Socket>,
+    close_code: Option<u16>,
+}
+
+impl WsReceiver {
+    /// The next JSON frame, or `None` once the server closed the connection.
+    /// Frames other than text are skipped.
+    pub async fn recv_json<T: DeserializeOwned>(&mut self) -> Result<Option<T>> {
+        while let Some(message) = self.stream.next().await {
+            match message? {
+                Message::Text(text) => return Ok(Some(serde_json::from_str(&text)?)),
+                Message::Close(frame) => {
+                    info!("WebSocket closed: {:?}", frame);
+                    self.close_code = frame.map(|f| u16::from(f.code));
+                    return Ok(None);
+                }
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    /// The code the server gave when it closed the connection
+    pub fn close_code(&self) -> Option<u16> {
+        self.close_code
+    }
+}
+
+/// Exponential delay between reconnect attempts
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    next: Duration,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            next: initial,
+        }
+    }
+
+    /// How long to wait before the next attempt; doubles each call up to
+    /// the maximum
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (self.next * 2).min(self.max);
+        delay
+    }
+
+    /// Start over from the initial delay (after a healthy connection)
+    pub fn reset(&mut self) {
+        self.next = self.initial;
+    }
+}
+
+/// The URL without its query, which may carry credentials
+fn redact_query(url: &str) -> &str {
+    url.split('?').next().unwrap_or(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(5));
+        let delays: Vec<u64> = (0..5).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 5, 5]);
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+        assert_eq!(redact_query("wss://host/?v=10&token=x"), "wss://host/");
+    }
+}
@@ -1,4 +1,5 @@
-const API = '/api';
+const BASE = document.querySelector('meta[name="localgpt-base"]')?.content || '';
+const API = `${BASE}/api`;
 let sessionId = null;
 let isStreaming = false;
 let statusPollInterval = null;
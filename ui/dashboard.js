@@ -0,0 +1,117 @@
+// Read-only status dashboard (no chat); refreshes itself for wall displays
+const BASE = document.querySelector('meta[name="localgpt-base"]')?.content || '';
+const API = `${BASE}/api`;
+const REFRESH_MS = 30000;
+
+document.addEventListener('DOMContentLoaded', () => {
+    updateClock();
+    setInterval(updateClock, 1000);
+    loadDashboard();
+    setInterval(loadDashboard, REFRESH_MS);
+});
+
+function updateClock() {
+    document.getElementById('clock').textContent =
+        new Date().toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' });
+}
+
+async function loadDashboard() {
+    const updatedEl = document.getElementById('updated');
+    try {
+        const res = await fetch(`${API}/dashboard`);
+        if (!res.ok) throw new Error(`HTTP ${res.status}`);
+        const data = await res.json();
+        render(data);
+        updatedEl.textContent = `Updated ${new Date(data.generated_at).toLocaleTimeString()}`;
+    } catch (err) {
+        console.error('Failed to load dashboard:', err);
+        updatedEl.textContent = 'Offline - retrying';
+    }
+}
+
+function render(data) {
+    renderHeartbeat(data.heartbeat, data.generated_at);
+
+    setText('memory-files', data.memory.total_files);
+    setText('memory-chunks', data.memory.total_chunks);
+    if (data.memory.embedded_chunks != null && data.memory.total_chunks > 0) {
+        const pct = Math.round((data.memory.embedded_chunks / data.memory.total_chunks) * 100);
+        setText('memory-embedded', `${data.memory.embedded_chunks} (${pct}%)`);
+    } else {
+        setText('memory-embedded', '-');
+    }
+    setText('memory-size', formatSize(data.memory.index_size_kb));
+
+    setText('system-model', data.model);
+    setText('system-sessions', data.active_sessions);
+    setText('system-version', data.version);
+
+    renderList('tasks', data.open_tasks, 'No open tasks', (task) => [
+        ['', task.title],
+        ['when', task.due || ''],
+    ]);
+    renderList('activity', data.activity, 'Nothing yet', (item) => [
+        ['when', formatAge(Math.floor((data.generated_at - item.ts) / 1000))],
+        ['kind', item.kind],
+        ['', item.summary],
+    ]);
+}
+
+function renderHeartbeat(heartbeat, now) {
+    document.getElementById('heartbeat-dot').className = `health-dot ${heartbeat.health}`;
+    setText('heartbeat-health', heartbeat.health);
+    setText('heartbeat-interval', heartbeat.interval);
+
+    const last = heartbeat.last_run;
+    if (last) {
+        const age = formatAge(Math.floor((now - last.ts) / 1000));
+        setText('heartbeat-last', last.reason ? `${age} - ${last.reason}` : `${age} (${last.status})`);
+    } else {
+        setText('heartbeat-last', '-');
+    }
+
+    const runs = document.getElementById('heartbeat-runs');
+    runs.replaceChildren(...heartbeat.runs.map((run) => {
+        const cell = document.createElement('span');
+        cell.className = `run ${run.status}`;
+        cell.title = `${new Date(run.ts).toLocaleString()}: ${run.status}`;
+        return cell;
+    }));
+}
+
+function renderList(id, items, emptyText, columns) {
+    const list = document.getElementById(id);
+    if (!items.length) {
+        const li = document.createElement('li');
+        li.className = 'empty';
+        li.textContent = emptyText;
+        list.replaceChildren(li);
+        return;
+    }
+    list.replaceChildren(...items.map((item) => {
+        const li = document.createElement('li');
+        for (const [className, text] of columns(item)) {
+            const span = document.createElement('span');
+            if (className) span.className = className;
+            span.textContent = text;
+            li.appendChild(span);
+        }
+        return li;
+    }));
+}
+
+function setText(id, value) {
+    document.getElementById(id).textContent = value ?? '-';
+}
+
+function formatSize(kb) {
+    if (kb < 1024) return `${kb} KB`;
+    return `${(kb / 1024).toFixed(1)} MB`;
+}
+
+function formatAge(seconds) {
+    if (seconds < 60) return 'just now';
+    if (seconds < 3600) return `${Math.floor(seconds / 60)}m ago`;
+    if (seconds < 86400) return `${Math.floor(seconds / 3600)}h ago`;
+    return `${Math.floor(seconds / 86400)}d ago`;
+}